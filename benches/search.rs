@@ -0,0 +1,190 @@
+//! Benchmarks for the search pipeline: keyword search, query embedding, ANN search, and
+//! end-to-end hybrid search over a generated fixture corpus.
+//!
+//! Run with `cargo bench`. The embedding and hybrid benchmarks need the ONNX model in
+//! `<data dir>/models` (downloaded on first use) and are skipped when it can't be loaded.
+
+use std::hint::black_box;
+use std::path::PathBuf;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use rust_lang_mcp::parsing::{Document, parse_markdown};
+use rust_lang_mcp::search::embeddings::{EmbeddingService, default_model};
+use rust_lang_mcp::{HybridSearch, SearchIndex, SearchMode, SearchOptions, VectorIndex};
+
+/// Documents in the fixture corpus
+const CORPUS_SIZE: usize = 1_000;
+/// Dimension of the synthetic vectors used for the ANN benchmark
+const VECTOR_DIM: usize = 384;
+
+const TOPICS: &[(&str, &str)] = &[
+    (
+        "Ownership",
+        "Each value in Rust has an owner. When the owner goes out of scope, the value is dropped. Moving a `String` transfers ownership; `Clone` makes a deep copy.",
+    ),
+    (
+        "References and Borrowing",
+        "A reference lets you use a value without taking ownership. You can have either one mutable reference or any number of immutable references, enforced by the borrow checker.",
+    ),
+    (
+        "Lifetimes",
+        "Lifetime annotations such as `'a` describe how long references are valid. Lifetime elision rules let the compiler infer them in common function signatures.",
+    ),
+    (
+        "Traits",
+        "A trait defines shared behavior. Types implement traits with `impl Trait for Type`, and trait bounds like `T: Display` constrain generic parameters.",
+    ),
+    (
+        "Error Handling",
+        "Recoverable errors use `Result<T, E>` and the `?` operator propagates them. `panic!` is for unrecoverable errors; `unwrap` and `expect` panic on `Err`.",
+    ),
+    (
+        "Iterators",
+        "The `Iterator` trait provides `next`. Adapters such as `map`, `filter` and `zip` are lazy, and consumers like `collect` and `sum` drive them.",
+    ),
+    (
+        "Smart Pointers",
+        "`Box<T>` allocates on the heap, `Rc<T>` enables shared ownership, and `RefCell<T>` moves borrow checking to runtime through interior mutability.",
+    ),
+    (
+        "Concurrency",
+        "Threads are spawned with `thread::spawn`. Channels pass messages between threads, and `Arc<Mutex<T>>` shares state. `Send` and `Sync` mark thread-safe types.",
+    ),
+    (
+        "Async",
+        "An `async fn` returns a `Future`. Futures do nothing until polled by an executor such as tokio, and `.await` yields until the value is ready.",
+    ),
+    (
+        "Macros",
+        "Declarative macros are written with `macro_rules!` and match token trees with fragment specifiers like `$e:expr`. Procedural macros operate on a `TokenStream`.",
+    ),
+];
+
+const QUERIES: &[&str] = &[
+    "how does the borrow checker handle mutable references",
+    "Arc<Mutex<T>> shared state between threads",
+    "propagate errors with the question mark operator",
+    "lifetime elision",
+];
+
+/// A deterministic corpus: every topic repeated with numbered variations
+fn fixture_documents() -> Vec<Document> {
+    (0..CORPUS_SIZE)
+        .map(|i| {
+            let (title, body) = TOPICS[i % TOPICS.len()];
+            let (_, related) = TOPICS[(i * 7 + 3) % TOPICS.len()];
+            let markdown = format!(
+                "# {} (part {})\n\n{}\n\n## Related\n\n{}\n\n```rust\nfn example_{}() {{}}\n```\n",
+                title, i, body, related, i
+            );
+            parse_markdown(&markdown, &format!("ch{:04}.md", i), "fixture")
+        })
+        .collect()
+}
+
+/// Deterministic pseudo-random unit vector (xorshift), so the ANN graph is reproducible
+fn synthetic_vector(seed: u64) -> Vec<f32> {
+    let mut state = seed.wrapping_mul(0x9E3779B97F4A7C15) | 1;
+    let mut vector: Vec<f32> = (0..VECTOR_DIM)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 2_000) as f32 / 1_000.0 - 1.0
+        })
+        .collect();
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    vector.iter_mut().for_each(|x| *x /= norm);
+    vector
+}
+
+fn keyword_index(documents: &[Document]) -> SearchIndex {
+    let index = SearchIndex::in_memory().expect("in-memory index");
+    index
+        .index_documents(documents)
+        .expect("index fixture corpus");
+    index
+}
+
+fn models_dir() -> PathBuf {
+    std::env::var("RUST_MCP_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("data"))
+        .join("models")
+}
+
+fn bench_keyword_search(c: &mut Criterion) {
+    let index = keyword_index(&fixture_documents());
+    let options = SearchOptions::new(10);
+
+    c.bench_function("keyword_search", |b| {
+        b.iter(|| {
+            for query in QUERIES {
+                black_box(index.search_counted(black_box(query), &options).unwrap());
+            }
+        })
+    });
+}
+
+fn bench_ann_search(c: &mut Criterion) {
+    let mut index = VectorIndex::new();
+    for i in 0..CORPUS_SIZE {
+        index
+            .add(format!("ch{:04}.md", i), synthetic_vector(i as u64))
+            .unwrap();
+    }
+    let query = synthetic_vector(u64::MAX);
+
+    c.bench_function("ann_search", |b| {
+        b.iter(|| black_box(index.search(black_box(&query), 30)))
+    });
+}
+
+fn bench_embedding_and_hybrid(c: &mut Criterion) {
+    let embeddings = match EmbeddingService::load(&models_dir(), default_model()) {
+        Ok(embeddings) => embeddings,
+        Err(e) => {
+            eprintln!("Skipping query_embedding and hybrid_search: {}", e);
+            return;
+        }
+    };
+
+    c.bench_function("query_embedding", |b| {
+        b.iter(|| black_box(embeddings.embed_text(black_box(QUERIES[0])).unwrap()))
+    });
+
+    let documents = fixture_documents();
+    let keyword = keyword_index(&documents);
+    let mut vectors = VectorIndex::with_capacity(documents.len());
+    for doc in &documents {
+        vectors
+            .add(
+                doc.path.clone(),
+                embeddings.embed_text(&doc.content).unwrap(),
+            )
+            .unwrap();
+    }
+    let hybrid = HybridSearch::new(&keyword, &vectors).with_embeddings(Some(&embeddings));
+    let options = SearchOptions::new(10);
+
+    c.bench_function("hybrid_search", |b| {
+        b.iter(|| {
+            for query in QUERIES {
+                black_box(
+                    hybrid
+                        .search_with_mode(black_box(query), SearchMode::Hybrid, &options)
+                        .unwrap(),
+                );
+            }
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_keyword_search,
+    bench_ann_search,
+    bench_embedding_and_hybrid
+);
+criterion_main!(benches);
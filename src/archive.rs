@@ -0,0 +1,292 @@
+//! Portable archives of the data directory.
+//!
+//! Building the index means cloning every documentation source and embedding thousands of
+//! documents. `rust-lang-mcp export` packs the result (indices, vectors, embedding cache, the
+//! cloned sources and downloaded pages, and optionally the embedding models) into one
+//! zstd-compressed tar archive, and `rust-lang-mcp import` unpacks it on another machine, so a
+//! team builds once and distributes the archive. The config file and logs are machine-specific
+//! and stay out of the archive.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CONFIG_FILE;
+use crate::error::{Error, Result};
+use crate::search::vector_index::IndexModel;
+use crate::sources::doc_sources;
+
+/// Layout version of archives written by this build
+const FORMAT: u32 = 1;
+
+/// Manifest file at the root of an archive
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory an archive is unpacked into before its entries are moved into place
+const IMPORT_STAGING: &str = "import.partial";
+
+/// Data directory entries that are never archived
+const EXCLUDED: &[&str] = &[CONFIG_FILE, "logs", IMPORT_STAGING];
+
+/// Directory of the downloaded embedding models
+const MODELS_DIR: &str = "models";
+
+/// What an archive contains
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Archive layout version
+    pub format: u32,
+    /// Version of the server that wrote the archive
+    pub version: String,
+    /// Embedding model the vector index was built with, when there is one
+    pub embedding_model: Option<IndexModel>,
+    /// Whether the embedding models are included; otherwise they are downloaded on first start
+    pub includes_models: bool,
+    /// Commit of each cloned documentation source, by source ID
+    pub sources: BTreeMap<String, String>,
+}
+
+/// Pack `data_dir` into a zstd-compressed tar archive at `archive`
+pub fn export(data_dir: &Path, archive: &Path, include_models: bool) -> Result<Manifest> {
+    let index_dir = data_dir.join("index");
+    if !index_dir.exists() {
+        return Err(Error::Other(format!(
+            "Nothing to export: {:?} has no index yet. Start the server once to build it.",
+            data_dir
+        )));
+    }
+
+    let manifest = Manifest {
+        format: FORMAT,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        embedding_model: IndexModel::load(&index_dir.join("vectors"))?,
+        includes_models: include_models,
+        sources: source_commits(data_dir),
+    };
+
+    let encoder = zstd::Encoder::new(BufWriter::new(File::create(archive)?), 0)?;
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+
+    // The manifest goes first, so it can be checked without reading the whole archive
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| Error::Other(format!("Failed to write archive manifest: {}", e)))?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_FILE, json.as_slice())?;
+
+    // An archive written into the data directory must not archive itself
+    let archive = std::fs::canonicalize(archive)?;
+    for entry in sorted_entries(data_dir)? {
+        let name = entry
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if EXCLUDED.contains(&name.as_str()) || (name == MODELS_DIR && !include_models) {
+            continue;
+        }
+        append(&mut builder, &entry, Path::new(&name), &archive)?;
+    }
+
+    builder.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+/// Unpack an archive written by `export` into `data_dir`, replacing the entries it contains.
+///
+/// A data directory that already has an index is only overwritten with `force`.
+pub fn import(archive: &Path, data_dir: &Path, force: bool) -> Result<Manifest> {
+    if data_dir.join("index").exists() && !force {
+        return Err(Error::Other(format!(
+            "{:?} already has an index; pass --force to replace it",
+            data_dir
+        )));
+    }
+    std::fs::create_dir_all(data_dir)?;
+
+    let staging = data_dir.join(IMPORT_STAGING);
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    let result = unpack(archive, &staging).and_then(|manifest| {
+        // Everything is unpacked, so a broken archive leaves the data directory untouched
+        for entry in sorted_entries(&staging)? {
+            let name = entry.file_name().unwrap_or_default().to_owned();
+            if name == MANIFEST_FILE {
+                continue;
+            }
+            let target = data_dir.join(&name);
+            if target.is_dir() {
+                std::fs::remove_dir_all(&target)?;
+            } else if target.exists() {
+                std::fs::remove_file(&target)?;
+            }
+            std::fs::rename(&entry, &target)?;
+        }
+        Ok(manifest)
+    });
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    result
+}
+
+/// Unpack an archive into `dir` and read its manifest
+fn unpack(archive: &Path, dir: &Path) -> Result<Manifest> {
+    let decoder = zstd::Decoder::new(File::open(archive)?)?;
+    tar::Archive::new(decoder).unpack(dir)?;
+
+    let file = File::open(dir.join(MANIFEST_FILE)).map_err(|_| {
+        Error::Other(format!(
+            "{:?} is not a rust-lang-mcp archive (no manifest)",
+            archive
+        ))
+    })?;
+    let manifest: Manifest = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| Error::Other(format!("Invalid archive manifest: {}", e)))?;
+    if manifest.format != FORMAT {
+        return Err(Error::Other(format!(
+            "Archive format {} is not supported (expected {}); export it again with this version",
+            manifest.format, FORMAT
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Add a file or directory tree to the archive under `name`, leaving out the index's lock
+/// files and the staging directories of an interrupted vector index save
+fn append<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    path: &Path,
+    name: &Path,
+    archive: &Path,
+) -> Result<()> {
+    let file_name = name.file_name().unwrap_or_default().to_string_lossy();
+    if name.starts_with("index")
+        && [".lock", ".new", ".old"]
+            .iter()
+            .any(|suffix| file_name.ends_with(suffix))
+    {
+        return Ok(());
+    }
+    let file_type = path.symlink_metadata()?.file_type();
+    if file_type.is_symlink() {
+        builder.append_path_with_name(path, name)?;
+    } else if file_type.is_dir() {
+        builder.append_dir(name, path)?;
+        for entry in sorted_entries(path)? {
+            let child = name.join(entry.file_name().unwrap_or_default());
+            append(builder, &entry, &child, archive)?;
+        }
+    } else if std::fs::canonicalize(path)? != archive {
+        builder.append_path_with_name(path, name)?;
+    }
+    Ok(())
+}
+
+/// Paths in a directory, sorted so archives of the same data are laid out the same
+fn sorted_entries(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// HEAD commit of each cloned documentation source
+fn source_commits(data_dir: &Path) -> BTreeMap<String, String> {
+    doc_sources()
+        .iter()
+        .filter_map(|source| Some((source.id.to_string(), source.checkout(data_dir)?.commit)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let root = std::env::temp_dir().join(format!("archive-{}", std::process::id()));
+        let data_dir = root.join("data");
+        write(&data_dir.join("index/meta.json"), "{}");
+        write(&data_dir.join("index/.tantivy-writer.lock"), "");
+        write(
+            &data_dir.join("index/vectors/model.json"),
+            r#"{"id": "all-MiniLM-L6-v2", "dimension": 384}"#,
+        );
+        write(&data_dir.join("index/vectors.old/vector_index.json"), "[]");
+        write(&data_dir.join("models/model.onnx"), "onnx");
+        write(&data_dir.join("book/src/SUMMARY.md"), "# Summary");
+        write(&data_dir.join(CONFIG_FILE), "[auth]\ntokens = [\"secret\"]");
+        write(&data_dir.join("logs/server.log"), "log");
+
+        // Written into the data directory, so it has to skip itself
+        let archive = data_dir.join("docs.tar.zst");
+        let manifest = export(&data_dir, &archive, false).unwrap();
+        assert_eq!(manifest.embedding_model.unwrap().id, "all-MiniLM-L6-v2");
+        assert!(!manifest.includes_models);
+
+        let target = root.join("target");
+        let imported = import(&archive, &target, false).unwrap();
+        assert_eq!(imported.format, FORMAT);
+        let exists = |path: &str| target.join(path).exists();
+        assert!(exists("index/meta.json") && exists("index/vectors/model.json"));
+        assert!(exists("book/src/SUMMARY.md"));
+        for left_out in [
+            "index/.tantivy-writer.lock",
+            "index/vectors.old",
+            "models",
+            CONFIG_FILE,
+            "logs",
+            "docs.tar.zst",
+            MANIFEST_FILE,
+            IMPORT_STAGING,
+        ] {
+            assert!(!exists(left_out), "{} was imported", left_out);
+        }
+
+        // An existing index is only replaced on request
+        assert!(import(&archive, &target, false).is_err());
+        write(&target.join("index/stale.json"), "{}");
+        import(&archive, &target, true).unwrap();
+        let stale = target.join("index/stale.json").exists();
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(!stale);
+    }
+
+    #[test]
+    fn test_import_rejects_other_archives() {
+        let root = std::env::temp_dir().join(format!("archive-other-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let archive = root.join("other.tar.zst");
+        let encoder = zstd::Encoder::new(File::create(&archive).unwrap(), 0).unwrap();
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "index/meta.json", "{}".as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let data_dir = root.join("data");
+        let result = import(&archive, &data_dir, false);
+        let untouched = !data_dir.join("index").exists();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(result.is_err());
+        assert!(untouched);
+    }
+}
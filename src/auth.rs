@@ -0,0 +1,215 @@
+//! Bearer-token authentication for the network transports.
+//!
+//! Tokens are accepted when they match a static token from the config file or when the
+//! configured OAuth authorization server reports them active through token introspection.
+//! Introspection results are cached briefly so every MCP message doesn't cost a round trip.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::config::{AuthConfig, OAuthConfig};
+use crate::error::{Error, Result};
+
+/// How long an introspected token is trusted before asking again
+const INTROSPECTION_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Validates `Authorization` headers against the configured credentials
+#[derive(Clone)]
+pub struct Authenticator {
+    bearer_tokens: Arc<Vec<String>>,
+    oauth: Option<Arc<OAuthConfig>>,
+    /// Introspected tokens and when to stop trusting them
+    introspected: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+/// Relevant fields of an RFC 7662 introspection response
+#[derive(Debug, Deserialize)]
+struct Introspection {
+    active: bool,
+    #[serde(default)]
+    scope: Option<String>,
+    /// Expiry as seconds since the epoch
+    #[serde(default)]
+    exp: Option<u64>,
+}
+
+impl Authenticator {
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            bearer_tokens: Arc::new(config.bearer_tokens),
+            oauth: config.oauth.map(Arc::new),
+            introspected: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Whether requests need a token at all
+    pub fn is_enabled(&self) -> bool {
+        !self.bearer_tokens.is_empty() || self.oauth.is_some()
+    }
+
+    /// Check the value of an `Authorization` header
+    pub async fn authorize(&self, header: Option<&str>) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        let Some(token) = header.and_then(bearer_token) else {
+            return false;
+        };
+
+        if self
+            .bearer_tokens
+            .iter()
+            .any(|known| constant_time_eq(known.as_bytes(), token.as_bytes()))
+        {
+            return true;
+        }
+
+        let Some(oauth) = self.oauth.clone() else {
+            return false;
+        };
+        if self.is_cached(token) {
+            return true;
+        }
+
+        let owned = token.to_string();
+        let introspection = tokio::task::spawn_blocking(move || introspect(&oauth, &owned)).await;
+        match introspection {
+            Ok(Ok(Some(valid_until))) => {
+                if let Ok(mut cache) = self.introspected.lock() {
+                    cache.retain(|_, until| *until > Instant::now());
+                    cache.insert(token.to_string(), valid_until);
+                }
+                true
+            }
+            Ok(Ok(None)) => false,
+            Ok(Err(e)) => {
+                tracing::warn!("Token introspection failed: {}", e);
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Token introspection task failed: {}", e);
+                false
+            }
+        }
+    }
+
+    fn is_cached(&self, token: &str) -> bool {
+        self.introspected
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(token).copied())
+            .is_some_and(|until| until > Instant::now())
+    }
+}
+
+/// Extract the token from a `Bearer <token>` header value
+fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Compare secrets without leaking the position of the first difference through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Ask the authorization server about a token; returns how long it may be trusted
+fn introspect(oauth: &OAuthConfig, token: &str) -> Result<Option<Instant>> {
+    let mut response = ureq::post(&oauth.introspection_url)
+        .header("Accept", "application/json")
+        .send_form([
+            ("token", token),
+            ("token_type_hint", "access_token"),
+            ("client_id", oauth.client_id.as_str()),
+            ("client_secret", oauth.client_secret.as_str()),
+        ])
+        .map_err(|e| Error::Other(format!("Introspection request failed: {}", e)))?;
+    let body = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| Error::Other(format!("Failed to read introspection response: {}", e)))?;
+    let introspection: Introspection = serde_json::from_str(&body)
+        .map_err(|e| Error::Other(format!("Invalid introspection response: {}", e)))?;
+
+    Ok(trusted_until(
+        &introspection,
+        oauth.required_scope.as_deref(),
+        SystemTime::now(),
+    ))
+}
+
+/// When an introspected token stops being trusted, or None if it isn't acceptable
+fn trusted_until(
+    introspection: &Introspection,
+    required_scope: Option<&str>,
+    now: SystemTime,
+) -> Option<Instant> {
+    if !introspection.active {
+        return None;
+    }
+    if let Some(required) = required_scope {
+        let scopes = introspection.scope.as_deref().unwrap_or_default();
+        if !scopes.split_whitespace().any(|scope| scope == required) {
+            return None;
+        }
+    }
+
+    let mut ttl = INTROSPECTION_CACHE_TTL;
+    if let Some(exp) = introspection.exp {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        if exp <= now {
+            return None;
+        }
+        ttl = ttl.min(Duration::from_secs(exp - now));
+    }
+    Some(Instant::now() + ttl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_static_tokens() {
+        let auth = Authenticator::new(AuthConfig {
+            bearer_tokens: vec!["s3cret".to_string()],
+            oauth: None,
+        });
+
+        assert!(auth.authorize(Some("Bearer s3cret")).await);
+        assert!(auth.authorize(Some("bearer  s3cret ")).await);
+        assert!(!auth.authorize(Some("Bearer wrong")).await);
+        assert!(!auth.authorize(Some("Basic s3cret")).await);
+        assert!(!auth.authorize(None).await);
+
+        let open = Authenticator::new(AuthConfig::default());
+        assert!(open.authorize(None).await);
+    }
+
+    #[test]
+    fn test_trusted_until() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+        let token = |active, scope: Option<&str>, exp| Introspection {
+            active,
+            scope: scope.map(str::to_string),
+            exp,
+        };
+
+        assert!(trusted_until(&token(true, None, None), None, now).is_some());
+        assert!(trusted_until(&token(false, None, None), None, now).is_none());
+        assert!(trusted_until(&token(true, None, Some(999)), None, now).is_none());
+        assert!(
+            trusted_until(
+                &token(true, Some("docs:read"), None),
+                Some("docs:read"),
+                now
+            )
+            .is_some()
+        );
+        assert!(trusted_until(&token(true, Some("other"), None), Some("docs:read"), now).is_none());
+    }
+}
@@ -0,0 +1,74 @@
+//! Sizing of the memory-hungry components from one `memory_budget_mb` setting.
+//!
+//! A quarter of the budget each goes to the tantivy writer heap, the embedding batch, and the
+//! HNSW graph allocation. The remaining half is left for the ONNX model, the loaded indices,
+//! and tantivy readers. Without a budget the previous fixed sizes are used.
+
+/// Smallest writer heap tantivy accepts
+const MIN_WRITER_HEAP_BYTES: usize = 15_000_000;
+const MAX_WRITER_HEAP_BYTES: usize = 256_000_000;
+
+/// Rough activation memory of one embedded text (256 tokens through MiniLM)
+const BYTES_PER_EMBEDDING: usize = 8 * 1024 * 1024;
+const MAX_EMBEDDING_BATCH: usize = 32;
+
+/// Rough memory of one HNSW entry (the stored vector, its copy in the graph, and neighbours)
+const BYTES_PER_VECTOR: usize = 4 * 1024;
+const MIN_HNSW_CAPACITY: usize = 1_000;
+const MAX_HNSW_CAPACITY: usize = 100_000;
+
+/// Sizes derived from the memory budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Heap of the tantivy index writer
+    pub writer_heap_bytes: usize,
+    /// Vectors the HNSW graph allocates room for up front
+    pub hnsw_capacity: usize,
+    /// Documents embedded per model call while indexing
+    pub embedding_batch_size: usize,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self {
+            writer_heap_bytes: 50_000_000,
+            hnsw_capacity: 10_000,
+            embedding_batch_size: MAX_EMBEDDING_BATCH,
+        }
+    }
+}
+
+impl MemoryBudget {
+    /// Split a budget in megabytes between the components
+    pub fn from_mb(budget_mb: usize) -> Self {
+        let quarter = budget_mb.saturating_mul(1024 * 1024) / 4;
+        Self {
+            writer_heap_bytes: quarter.clamp(MIN_WRITER_HEAP_BYTES, MAX_WRITER_HEAP_BYTES),
+            hnsw_capacity: (quarter / BYTES_PER_VECTOR).clamp(MIN_HNSW_CAPACITY, MAX_HNSW_CAPACITY),
+            embedding_batch_size: (quarter / BYTES_PER_EMBEDDING).clamp(1, MAX_EMBEDDING_BATCH),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_mb() {
+        let small = MemoryBudget::from_mb(64);
+        assert_eq!(small.writer_heap_bytes, 16 * 1024 * 1024);
+        assert_eq!(small.embedding_batch_size, 2);
+        assert_eq!(small.hnsw_capacity, 4096);
+
+        let tiny = MemoryBudget::from_mb(8);
+        assert_eq!(tiny.writer_heap_bytes, MIN_WRITER_HEAP_BYTES);
+        assert_eq!(tiny.embedding_batch_size, 1);
+        assert_eq!(tiny.hnsw_capacity, MIN_HNSW_CAPACITY);
+
+        let large = MemoryBudget::from_mb(8192);
+        assert_eq!(large.writer_heap_bytes, MAX_WRITER_HEAP_BYTES);
+        assert_eq!(large.embedding_batch_size, MAX_EMBEDDING_BATCH);
+        assert_eq!(large.hnsw_capacity, MAX_HNSW_CAPACITY);
+    }
+}
@@ -0,0 +1,301 @@
+//! Cargo Book reference lookup: map a cargo subcommand or manifest key to the page or
+//! section documenting it, matching keys exactly against section headings.
+
+use crate::error::{Error, Result};
+
+/// Source ID of the Cargo Book
+pub const CARGO_BOOK_SOURCE: &str = "cargo-book";
+
+/// What to look for in the Cargo Book for a user-supplied key
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lookup {
+    /// Subcommand whose `cargo-<name>.md` page to return
+    pub command: Option<String>,
+    /// Heading keys to try, most specific first
+    pub keys: Vec<String>,
+}
+
+impl Lookup {
+    /// Parse `cargo tree`, `cargo-tree`, `[patch]`, `resolver = "2"`, `package.edition`
+    /// or a bare word (tried as a heading, then as a subcommand)
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim().trim_matches('`').trim();
+        if input.is_empty() {
+            return Err(Error::Other(
+                "Empty cargo command or manifest key".to_string(),
+            ));
+        }
+
+        let lower = input.to_lowercase();
+        if let Some(rest) = lower
+            .strip_prefix("cargo ")
+            .or_else(|| lower.strip_prefix("cargo-"))
+        {
+            let name = rest.split_whitespace().next().unwrap_or("");
+            if name.is_empty() || name.starts_with('-') {
+                return Err(Error::Other(format!("No cargo subcommand in '{}'", input)));
+            }
+            return Ok(Self {
+                command: Some(name.to_string()),
+                keys: Vec::new(),
+            });
+        }
+
+        // `resolver = "2"` looks up `resolver`; `[patch.crates-io]` looks up `patch.crates-io`
+        let key = lower.split('=').next().unwrap_or("").trim();
+        let bare = !key.contains(['[', '.']) && !lower.contains('=');
+        let key = key.trim_start_matches('[').trim_end_matches(']').trim();
+        if key.is_empty() {
+            return Err(Error::Other(format!("No manifest key in '{}'", input)));
+        }
+
+        let mut keys = vec![key.to_string()];
+        let segments: Vec<&str> = key.split('.').map(str::trim).collect();
+        for segment in [segments[segments.len() - 1], segments[0]] {
+            if !segment.is_empty() && !keys.iter().any(|k| k == segment) {
+                keys.push(segment.to_string());
+            }
+        }
+        let command = (bare && !key.contains(char::is_whitespace)).then(|| key.to_string());
+        Ok(Self { command, keys })
+    }
+}
+
+/// Path of a subcommand's page in the Cargo Book
+pub fn command_page(name: &str) -> String {
+    format!("commands/cargo-{}.md", name)
+}
+
+/// Comparable key of a heading: "The `[patch]` section" -> "patch",
+/// "The `edition` field" -> "edition", "Resolver versions" -> "resolver versions"
+pub fn heading_key(heading: &str) -> String {
+    let text = heading
+        .trim_start_matches('#')
+        .replace('`', "")
+        .trim()
+        .to_lowercase();
+    let text = text.strip_prefix("the ").unwrap_or(&text);
+    let text = [" section", " field", " table", " key"]
+        .iter()
+        .find_map(|suffix| text.strip_suffix(suffix))
+        .unwrap_or(text);
+    text.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim()
+        .to_string()
+}
+
+/// A heading and the markdown below it, up to the next heading of the same or higher level
+#[derive(Debug, PartialEq, Eq)]
+pub struct Section {
+    pub heading: String,
+    pub body: String,
+}
+
+/// Section whose heading key is exactly `key`
+pub fn find_section(markdown: &str, key: &str) -> Option<Section> {
+    find_section_by(markdown, |heading| heading == key)
+}
+
+/// Section whose heading key starts with the word `key` ("resolver" -> "Resolver versions")
+pub fn find_section_starting(markdown: &str, key: &str) -> Option<Section> {
+    let prefix = format!("{} ", key);
+    find_section_by(markdown, |heading| heading.starts_with(&prefix))
+}
+
+fn find_section_by(markdown: &str, matches: impl Fn(&str) -> bool) -> Option<Section> {
+    let lines = headings(markdown);
+    let (index, (start, level, heading)) = lines
+        .iter()
+        .enumerate()
+        .find(|(_, (_, _, heading))| matches(&heading_key(heading)))?;
+    let body_lines: Vec<&str> = markdown.lines().collect();
+    let end = lines[index + 1..]
+        .iter()
+        .find(|(_, next_level, _)| next_level <= level)
+        .map_or(body_lines.len(), |(line, _, _)| *line);
+    Some(Section {
+        heading: heading.to_string(),
+        body: body_lines[start + 1..end].join("\n").trim().to_string(),
+    })
+}
+
+/// Headings of a rendered document as (line number, level, text), skipping code blocks
+fn headings(markdown: &str) -> Vec<(usize, usize, &str)> {
+    let mut in_code = false;
+    let mut headings = Vec::new();
+    for (number, line) in markdown.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if level > 0 && line[level..].starts_with(' ') {
+            headings.push((number, level, line[level..].trim()));
+        }
+    }
+    headings
+}
+
+/// A command-line option and its description
+#[derive(Debug, PartialEq, Eq)]
+pub struct Flag {
+    pub flag: String,
+    pub description: String,
+}
+
+/// Options listed as term lines (`- `--flag` value`, one per alias) followed by an indented
+/// description, the way command pages' definition lists are rendered
+pub fn flags(markdown: &str) -> Vec<Flag> {
+    let mut flags = Vec::new();
+    let mut terms: Vec<String> = Vec::new();
+    let mut description: Vec<&str> = Vec::new();
+    for line in markdown.lines() {
+        let indented = line
+            .strip_prefix("  ")
+            .filter(|text| !text.trim_start().starts_with('-'));
+        match (line.strip_prefix("- `"), indented) {
+            (Some(term), _) => {
+                if !description.is_empty() {
+                    push_flag(&mut flags, &mut terms, &mut description);
+                }
+                terms.push(format!("`{}", term).replace('`', "").trim().to_string());
+            }
+            (None, Some(text)) if !terms.is_empty() => description.push(text.trim()),
+            _ => push_flag(&mut flags, &mut terms, &mut description),
+        }
+    }
+    push_flag(&mut flags, &mut terms, &mut description);
+    flags
+}
+
+fn push_flag(flags: &mut Vec<Flag>, terms: &mut Vec<String>, description: &mut Vec<&str>) {
+    if !terms.is_empty() && !description.is_empty() {
+        flags.push(Flag {
+            flag: terms.join(", "),
+            description: description.join(" "),
+        });
+    }
+    terms.clear();
+    description.clear();
+}
+
+/// An example: the code of a fenced block and the text line introducing it
+#[derive(Debug, PartialEq, Eq)]
+pub struct Example {
+    pub description: Option<String>,
+    pub code: String,
+}
+
+/// Fenced code blocks in `markdown`, each with the nearest text line above it
+pub fn examples(markdown: &str) -> Vec<Example> {
+    let mut examples = Vec::new();
+    let mut description: Option<String> = None;
+    let mut code: Option<Vec<&str>> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        match code.as_mut() {
+            Some(block) if trimmed.starts_with("```") => {
+                examples.push(Example {
+                    description: description.take(),
+                    code: block.join("\n"),
+                });
+                code = None;
+            }
+            Some(block) => block.push(line),
+            None if trimmed.starts_with("```") => code = Some(Vec::new()),
+            None if !trimmed.is_empty() && !trimmed.starts_with('#') => {
+                let text = trimmed.trim_start_matches("- ");
+                description = Some(text.trim_end_matches(':').to_string());
+            }
+            None => {}
+        }
+    }
+    examples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_parse() {
+        let lookup = Lookup::parse("cargo tree -i").unwrap();
+        assert_eq!(lookup.command.as_deref(), Some("tree"));
+        assert!(lookup.keys.is_empty());
+        assert_eq!(
+            Lookup::parse("cargo-tree").unwrap().command.as_deref(),
+            Some("tree")
+        );
+
+        let lookup = Lookup::parse("[patch]").unwrap();
+        assert_eq!(lookup.command, None);
+        assert_eq!(lookup.keys, vec!["patch"]);
+
+        let lookup = Lookup::parse(r#"resolver = "2""#).unwrap();
+        assert_eq!(lookup.command, None);
+        assert_eq!(lookup.keys, vec!["resolver"]);
+
+        let lookup = Lookup::parse("[profile.release]").unwrap();
+        assert_eq!(lookup.keys, vec!["profile.release", "release", "profile"]);
+
+        let lookup = Lookup::parse("edition").unwrap();
+        assert_eq!(lookup.command.as_deref(), Some("edition"));
+        assert_eq!(lookup.keys, vec!["edition"]);
+
+        assert!(Lookup::parse("cargo --version").is_err());
+        assert!(Lookup::parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_heading_key() {
+        assert_eq!(heading_key("The `[patch]` section"), "patch");
+        assert_eq!(heading_key("## The `edition` field"), "edition");
+        assert_eq!(heading_key("Resolver versions"), "resolver versions");
+    }
+
+    #[test]
+    fn test_find_section() {
+        let md = "Intro.\n\n## The `[patch]` section\n\nOverride deps.\n\n```toml\n# not a heading\n[patch.crates-io]\n```\n\n### Using `[patch]` with multiple versions\n\nMore.\n\n## The `[replace]` section\n\nDeprecated.\n\n## Resolver versions\n\nUse 2.";
+        let section = find_section(md, "patch").unwrap();
+        assert_eq!(section.heading, "The `[patch]` section");
+        assert!(section.body.starts_with("Override deps."));
+        assert!(section.body.contains("More."));
+        assert!(!section.body.contains("Deprecated."));
+
+        assert!(find_section(md, "resolver").is_none());
+        let section = find_section_starting(md, "resolver").unwrap();
+        assert_eq!(section.body, "Use 2.");
+    }
+
+    #[test]
+    fn test_flags_and_examples() {
+        let md = "### Tree Options\n\n- `-i` spec\n- `--invert` spec\n  Show the reverse\n  dependencies.\n- `--depth` depth\n  Maximum display depth.\n- `plain` item\n\n## EXAMPLES\n\n- Display the tree for the package in the current directory:\n\n```rust\ncargo tree\n```\n";
+        assert_eq!(
+            flags(md),
+            vec![
+                Flag {
+                    flag: "-i spec, --invert spec".to_string(),
+                    description: "Show the reverse dependencies.".to_string(),
+                },
+                Flag {
+                    flag: "--depth depth".to_string(),
+                    description: "Maximum display depth.".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            examples(md),
+            vec![Example {
+                description: Some(
+                    "Display the tree for the package in the current directory".to_string()
+                ),
+                code: "cargo tree".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Compact topic cheat sheets assembled from the structure of the best-matching documents.
+
+use serde::Serialize;
+
+use crate::parsing::Document;
+
+const MAX_HEADINGS_PER_DOC: usize = 6;
+const MAX_DEFINITIONS: usize = 3;
+const MAX_KEY_TERMS: usize = 12;
+const MAX_EXAMPLES: usize = 3;
+const MAX_EXAMPLE_LINES: usize = 20;
+
+/// Verbs that usually introduce a definition ("An iterator is ...")
+const DEFINITION_MARKERS: &[&str] = &[" is ", " are ", " allows ", " lets ", " means "];
+
+/// A cheat sheet for one topic
+#[derive(Debug, Serialize)]
+pub struct CheatSheet {
+    pub topic: String,
+    /// Sentences defining the topic
+    pub definitions: Vec<Definition>,
+    /// Inline code items mentioned across the documents, most widespread first
+    pub key_terms: Vec<KeyTerm>,
+    /// Short canonical code examples
+    pub examples: Vec<Example>,
+    /// Where to read more, with each document's section headings
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Definition {
+    pub text: String,
+    pub source: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyTerm {
+    pub term: String,
+    /// Number of documents mentioning the term
+    pub documents: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Example {
+    pub code: String,
+    pub source: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Section {
+    pub title: String,
+    pub headings: Vec<String>,
+    pub source: String,
+    pub path: String,
+}
+
+impl CheatSheet {
+    /// Aggregate a cheat sheet from documents ordered by relevance
+    pub fn build(topic: &str, documents: &[Document]) -> Self {
+        let stems = topic_stems(topic);
+        let key_terms = key_terms(documents);
+
+        CheatSheet {
+            topic: topic.to_string(),
+            definitions: definitions(&stems, documents),
+            examples: examples(&stems, &key_terms, documents),
+            key_terms,
+            sections: documents
+                .iter()
+                .map(|doc| Section {
+                    title: doc.title.clone(),
+                    headings: doc
+                        .headings
+                        .iter()
+                        .take(MAX_HEADINGS_PER_DOC)
+                        .cloned()
+                        .collect(),
+                    source: doc.source.clone(),
+                    path: doc.path.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Check whether anything beyond the section list was found
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty() && self.key_terms.is_empty() && self.examples.is_empty()
+    }
+}
+
+/// Lowercase topic words with a plural "s" removed, so "iterators" also matches "iterator"
+fn topic_stems(topic: &str) -> Vec<String> {
+    topic
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.len() > 2)
+        .map(|w| match w.strip_suffix('s') {
+            Some(stem) if stem.len() > 3 => stem.to_string(),
+            _ => w.to_string(),
+        })
+        .collect()
+}
+
+fn mentions_topic(text: &str, stems: &[String]) -> bool {
+    let lower = text.to_lowercase();
+    stems.iter().any(|stem| lower.contains(stem.as_str()))
+}
+
+fn definitions(stems: &[String], documents: &[Document]) -> Vec<Definition> {
+    let mut definitions: Vec<Definition> = Vec::new();
+
+    for doc in documents {
+        let sentences = doc
+            .content
+            .split(['\n', '.'])
+            .map(str::trim)
+            .filter(|s| (40..=300).contains(&s.len()));
+
+        for sentence in sentences {
+            let lower = sentence.to_lowercase();
+            let is_definition = mentions_topic(sentence, stems)
+                && DEFINITION_MARKERS.iter().any(|m| lower.contains(m));
+
+            if is_definition
+                && !definitions
+                    .iter()
+                    .any(|d| d.text.trim_end_matches('.') == sentence)
+            {
+                definitions.push(Definition {
+                    text: format!("{}.", sentence),
+                    source: doc.source.clone(),
+                    path: doc.path.clone(),
+                });
+                // One definition per document keeps the sheet varied
+                break;
+            }
+        }
+
+        if definitions.len() >= MAX_DEFINITIONS {
+            break;
+        }
+    }
+
+    definitions
+}
+
+fn key_terms(documents: &[Document]) -> Vec<KeyTerm> {
+    let mut terms: Vec<KeyTerm> = Vec::new();
+
+    for doc in documents {
+        for term in &doc.code_terms {
+            if term.len() < 2 || term.contains(char::is_whitespace) {
+                continue;
+            }
+            match terms.iter_mut().find(|t| &t.term == term) {
+                Some(existing) => existing.documents += 1,
+                None => terms.push(KeyTerm {
+                    term: term.clone(),
+                    documents: 1,
+                }),
+            }
+        }
+    }
+
+    // Stable sort keeps first-use order among equally common terms
+    terms.sort_by_key(|t| std::cmp::Reverse(t.documents));
+    terms.truncate(MAX_KEY_TERMS);
+    terms
+}
+
+fn examples(stems: &[String], key_terms: &[KeyTerm], documents: &[Document]) -> Vec<Example> {
+    let relevant = |code: &str| {
+        mentions_topic(code, stems) || key_terms.iter().any(|t| code.contains(t.term.as_str()))
+    };
+
+    documents
+        .iter()
+        .filter_map(|doc| {
+            doc.code_blocks
+                .iter()
+                .find(|code| code.lines().count() <= MAX_EXAMPLE_LINES && relevant(code))
+                .map(|code| Example {
+                    code: code.clone(),
+                    source: doc.source.clone(),
+                    path: doc.path.clone(),
+                })
+        })
+        .take(MAX_EXAMPLES)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(path: &str, content: &str, terms: &[&str], code: &[&str]) -> Document {
+        Document {
+            title: path.to_string(),
+            content: content.to_string(),
+            path: path.to_string(),
+            source: "rust-book".to_string(),
+            headings: vec!["Consuming Adaptors".to_string()],
+            code_blocks: code.iter().map(|c| c.to_string()).collect(),
+            code_terms: terms.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_cheat_sheet() {
+        let docs = vec![
+            document(
+                "ch13-02-iterators.md",
+                "The iterator pattern allows you to perform some task on a sequence of items in turn. Other text here.",
+                &["Iterator", "next", "map"],
+                &["let v2: Vec<_> = v.iter().map(|x| x + 1).collect();"],
+            ),
+            document(
+                "iter.md",
+                "Short.",
+                &["Iterator", "collect"],
+                &["fn main() {}"],
+            ),
+        ];
+
+        let sheet = CheatSheet::build("iterators", &docs);
+
+        assert_eq!(sheet.definitions.len(), 1);
+        assert!(
+            sheet.definitions[0]
+                .text
+                .starts_with("The iterator pattern allows")
+        );
+        assert_eq!(sheet.key_terms[0].term, "Iterator");
+        assert_eq!(sheet.key_terms[0].documents, 2);
+        // The second document's only example mentions neither the topic nor a key term
+        assert_eq!(sheet.examples.len(), 1);
+        assert_eq!(sheet.sections.len(), 2);
+        assert!(!sheet.is_empty());
+    }
+}
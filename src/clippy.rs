@@ -0,0 +1,533 @@
+//! Clippy lint documentation.
+//!
+//! Every Clippy lint is declared with `declare_clippy_lint!` in `clippy_lints/src`, its doc
+//! comment split into `### What it does`, `### Why is this bad?`, and `### Example` sections.
+//! The declarations are parsed into structured lints, with the `clippy.toml` options that
+//! affect each one from `clippy_config/src/conf.rs`. Each lint is indexed as one document for
+//! free-text search, and the catalog is kept in memory for exact lookups by `clippy_lint` and
+//! `explain_lint`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::parse_report::SourceReport;
+use crate::parsing::{Document, parse_markdown};
+
+/// Source ID of the Clippy lints
+pub const CLIPPY_SOURCE: &str = "clippy";
+
+/// Lint declarations, relative to the repository
+const LINTS_DIR: &str = "clippy_lints/src";
+
+/// `clippy.toml` options, relative to the repository
+const CONF_FILE: &str = "clippy_config/src/conf.rs";
+
+/// Documentation page of all lints; a lint's name is its anchor
+const LINTS_PAGE: &str = "https://rust-lang.github.io/rust-clippy/master/index.html";
+
+const DECLARE_MACRO: &str = "declare_clippy_lint! {";
+
+/// Line of a lint's example that introduces the fixed code
+const FIX_MARKER: &str = "Use instead:";
+
+/// A Clippy lint
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Lint {
+    /// Lint name without the `clippy::` prefix, e.g. `needless_range_loop`
+    pub name: String,
+    /// Lint group, e.g. `style` or `pedantic`
+    pub group: String,
+    /// One-line description from the declaration
+    pub description: String,
+    /// Clippy version the lint was added in
+    pub version: Option<String>,
+    pub what_it_does: String,
+    pub why_bad: String,
+    pub known_problems: Option<String>,
+    /// Example section as markdown, usually with a `Use instead:` block
+    pub example: Option<String>,
+    /// `clippy.toml` options that change the lint
+    pub configuration: Vec<ConfigOption>,
+    /// Declaring file, relative to the repository
+    pub file: String,
+}
+
+impl Lint {
+    /// Level the lint has unless configured: its group's level
+    pub fn default_level(&self) -> &'static str {
+        match self.group.as_str() {
+            "correctness" => "deny",
+            "style" | "complexity" | "perf" | "suspicious" => "warn",
+            _ => "allow",
+        }
+    }
+
+    /// The code of the example that the lint fires on: what precedes its `Use instead:` line
+    pub fn flagged_code(&self) -> Option<&str> {
+        let example = self.example.as_deref()?;
+        let flagged = example
+            .split_once(FIX_MARKER)
+            .map_or(example, |(flagged, _)| flagged)
+            .trim();
+        (!flagged.is_empty()).then_some(flagged)
+    }
+
+    /// The fixed code of the example: what follows its `Use instead:` line
+    pub fn suggested_fix(&self) -> Option<&str> {
+        let example = self.example.as_deref()?;
+        let (_, fix) = example.split_once(FIX_MARKER)?;
+        let fix = fix.trim();
+        (!fix.is_empty()).then_some(fix)
+    }
+
+    /// The lint's entry in the Clippy lint list
+    pub fn docs_url(&self) -> String {
+        format!("{}#{}", LINTS_PAGE, self.name)
+    }
+
+    /// The lint as a markdown page
+    fn markdown(&self) -> String {
+        let mut markdown = format!("# clippy::{}\n\n{}\n\n", self.name, self.description);
+        markdown.push_str(&format!(
+            "Group: {}. Default level: {}.\n\n",
+            self.group,
+            self.default_level()
+        ));
+        let mut section = |heading: &str, body: &str| {
+            if !body.is_empty() {
+                markdown.push_str(&format!("## {}\n\n{}\n\n", heading, body));
+            }
+        };
+        section("What it does", &self.what_it_does);
+        section("Why is this bad?", &self.why_bad);
+        section(
+            "Known problems",
+            self.known_problems.as_deref().unwrap_or_default(),
+        );
+        section("Example", self.example.as_deref().unwrap_or_default());
+        let configuration: Vec<String> = self
+            .configuration
+            .iter()
+            .map(|option| format!("- `{}`: {}", option.name, option.docs))
+            .collect();
+        section("Configuration", &configuration.join("\n"));
+        markdown
+    }
+}
+
+/// A `clippy.toml` option
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOption {
+    /// Key in `clippy.toml`, e.g. `avoid-breaking-exported-api`
+    pub name: String,
+    pub docs: String,
+    /// Default value as written in `conf.rs`
+    pub default: Option<String>,
+}
+
+/// The lints of a rust-clippy checkout
+#[derive(Debug, Default)]
+pub struct ClippyLints {
+    lints: HashMap<String, Lint>,
+}
+
+impl ClippyLints {
+    /// Load the lints from the cloned repository; empty when it is not cloned
+    pub fn load(repo_dir: &Path) -> Self {
+        let lints_dir = repo_dir.join(LINTS_DIR);
+        let mut files = Vec::new();
+        collect_rust_files(&lints_dir, &mut files);
+
+        let mut lints = Vec::new();
+        for path in files {
+            let Ok(code) = std::fs::read_to_string(&path) else {
+                tracing::debug!("Skipping unreadable lint file {:?}", path);
+                continue;
+            };
+            let file = path
+                .strip_prefix(repo_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            lints.extend(parse_lints(&code, &file));
+        }
+
+        let conf = std::fs::read_to_string(repo_dir.join(CONF_FILE)).unwrap_or_default();
+        let catalog = Self::new(lints, &parse_config(&conf));
+        if !catalog.is_empty() {
+            tracing::info!("Loaded {} Clippy lints", catalog.lints.len());
+        }
+        catalog
+    }
+
+    /// Build a catalog, attaching the options that name each lint
+    fn new(lints: Vec<Lint>, options: &[(Vec<String>, ConfigOption)]) -> Self {
+        let lints = lints
+            .into_iter()
+            .map(|mut lint| {
+                lint.configuration = options
+                    .iter()
+                    .filter(|(lints, _)| lints.contains(&lint.name))
+                    .map(|(_, option)| option.clone())
+                    .collect();
+                (lint.name.clone(), lint)
+            })
+            .collect();
+        Self { lints }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lints.is_empty()
+    }
+
+    /// Look up a lint by exact name: `needless_range_loop`, `clippy::needless_range_loop`,
+    /// or `needless-range-loop`
+    pub fn get(&self, name: &str) -> Option<&Lint> {
+        self.lints.get(&normalize_name(name))
+    }
+
+    /// Names containing `name`, for suggestions when there is no exact match
+    pub fn similar(&self, name: &str, limit: usize) -> Vec<&str> {
+        let wanted = normalize_name(name);
+        let parts: Vec<&str> = wanted.split('_').filter(|part| part.len() > 2).collect();
+        let mut similar: Vec<(usize, &str)> = self
+            .lints
+            .keys()
+            .filter_map(|lint| {
+                if lint.contains(&wanted) {
+                    return Some((0, lint.as_str()));
+                }
+                let shared = parts.iter().filter(|part| lint.contains(*part)).count();
+                (shared > 0).then(|| (parts.len() - shared + 1, lint.as_str()))
+            })
+            .collect();
+        similar.sort();
+        similar
+            .into_iter()
+            .take(limit)
+            .map(|(_, lint)| lint)
+            .collect()
+    }
+
+    /// Build one document per lint, recording the count in `report`
+    pub fn documents(&self, report: &mut SourceReport) -> Vec<Document> {
+        let mut lints: Vec<&Lint> = self.lints.values().collect();
+        lints.sort_by(|a, b| a.name.cmp(&b.name));
+        let documents: Vec<Document> = lints
+            .into_iter()
+            .map(|lint| parse_markdown(&lint.markdown(), &lint.name, CLIPPY_SOURCE))
+            .collect();
+        report.parsed = documents.len();
+        documents
+    }
+}
+
+/// `needless_range_loop` for `clippy::needless-range-loop` or `NEEDLESS_RANGE_LOOP`
+fn normalize_name(name: &str) -> String {
+    name.trim()
+        .trim_start_matches("clippy::")
+        .to_lowercase()
+        .replace('-', "_")
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// Parse the `declare_clippy_lint!` invocations of a file
+fn parse_lints(code: &str, file: &str) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut lines = code.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() != DECLARE_MACRO {
+            continue;
+        }
+
+        let mut docs = Vec::new();
+        let mut version = None;
+        let mut declaration = String::new();
+        for line in lines.by_ref() {
+            let trimmed = line.trim();
+            if trimmed == "}" {
+                break;
+            }
+            if let Some(doc) = trimmed.strip_prefix("///") {
+                docs.push(doc.strip_prefix(' ').unwrap_or(doc));
+            } else if let Some(attr) = trimmed.strip_prefix("#[clippy::version = \"") {
+                version = attr.split('"').next().map(str::to_string);
+            } else if !trimmed.starts_with("#[") {
+                declaration.push_str(trimmed);
+                declaration.push(' ');
+            }
+        }
+
+        if let Some(mut lint) = parse_declaration(&declaration) {
+            apply_doc_sections(&mut lint, &docs);
+            lint.version = version;
+            lint.file = file.to_string();
+            lints.push(lint);
+        }
+    }
+    lints
+}
+
+/// Parse `pub NAME, group, "description"` (optionally followed by `@` options)
+fn parse_declaration(declaration: &str) -> Option<Lint> {
+    let declaration = declaration.trim().strip_prefix("pub ")?;
+    let (name, rest) = declaration.split_once(',')?;
+    let (group, rest) = rest.split_once(',')?;
+    let start = rest.find('"')?;
+    let end = rest.rfind('"').filter(|&end| end > start)?;
+    Some(Lint {
+        name: name.trim().to_lowercase(),
+        group: group.trim().to_string(),
+        description: rest[start + 1..end].replace("\\\"", "\""),
+        ..Default::default()
+    })
+}
+
+/// Split the lint's doc comment into its `###` sections
+fn apply_doc_sections(lint: &mut Lint, docs: &[&str]) {
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in docs {
+        match line.strip_prefix("### ") {
+            Some(heading) => sections.push((heading.trim().to_lowercase(), Vec::new())),
+            None => {
+                if let Some((_, body)) = sections.last_mut() {
+                    body.push(line);
+                }
+            }
+        }
+    }
+
+    for (heading, body) in sections {
+        let body = body.join("\n").trim().to_string();
+        if body.is_empty() {
+            continue;
+        }
+        match heading.trim_end_matches('?') {
+            "what it does" => lint.what_it_does = body,
+            // Restriction lints explain why someone would want them instead
+            "why is this bad" | "why restrict this" => lint.why_bad = body,
+            "known problems" => lint.known_problems = Some(body),
+            "example" | "examples" => lint.example = Some(body),
+            _ => {}
+        }
+    }
+}
+
+/// Parse the options of `define_Conf!` with the lints each affects, named either in a
+/// `#[lints(...)]` attribute or, in older releases, a `/// Lint: A, B.` doc line
+fn parse_config(conf: &str) -> Vec<(Vec<String>, ConfigOption)> {
+    let mut options = Vec::new();
+    let mut docs: Vec<&str> = Vec::new();
+    let mut lints: Vec<String> = Vec::new();
+    let mut lints_attr: Option<String> = None;
+
+    let lint_names = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|name| name.trim().trim_end_matches('.').to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect()
+    };
+
+    for line in conf.lines() {
+        let trimmed = line.trim();
+
+        // A `#[lints(...)]` attribute may span several lines
+        if let Some(attr) = lints_attr.as_mut() {
+            attr.push_str(trimmed);
+            if let Some(list) = attr.strip_suffix(")]") {
+                lints.extend(lint_names(list));
+                lints_attr = None;
+            }
+            continue;
+        }
+        if let Some(attr) = trimmed.strip_prefix("#[lints(") {
+            match attr.strip_suffix(")]") {
+                Some(list) => lints.extend(lint_names(list)),
+                None => lints_attr = Some(attr.to_string()),
+            }
+            continue;
+        }
+
+        if let Some(doc) = trimmed.strip_prefix("///") {
+            let doc = doc.trim();
+            match doc.strip_prefix("Lint: ") {
+                Some(list) => lints.extend(lint_names(list)),
+                None => docs.push(doc),
+            }
+            continue;
+        }
+        if trimmed.starts_with("#[") || trimmed.is_empty() {
+            continue;
+        }
+
+        // `name: Type = default,` (or wrapped in parentheses in older releases)
+        let field = trimmed.trim_start_matches('(');
+        let Some((name, rest)) = field.split_once(':') else {
+            docs.clear();
+            lints.clear();
+            continue;
+        };
+        let is_field = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if is_field && !lints.is_empty() {
+            let default = rest.split_once('=').map(|(_, default)| {
+                default
+                    .trim()
+                    .trim_end_matches(',')
+                    .trim_end_matches(')')
+                    .trim()
+                    .to_string()
+            });
+            options.push((
+                std::mem::take(&mut lints),
+                ConfigOption {
+                    name: name.replace('_', "-"),
+                    docs: docs.join(" ").trim().to_string(),
+                    default: default.filter(|default| !default.is_empty()),
+                },
+            ));
+        }
+        docs.clear();
+        lints.clear();
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINT_FILE: &str = r#"
+use rustc_lint::LateLintPass;
+
+declare_clippy_lint! {
+    /// ### What it does
+    /// Checks for looping over the range of `0..len` of some
+    /// collection just to get the values by index.
+    ///
+    /// ### Why is this bad?
+    /// Just iterating the collection itself makes the intent
+    /// more clear and is probably faster.
+    ///
+    /// ### Example
+    /// ```no_run
+    /// for i in 0..vec.len() {
+    ///     println!("{}", vec[i]);
+    /// }
+    /// ```
+    ///
+    /// Use instead:
+    /// ```no_run
+    /// for i in &vec {
+    ///     println!("{}", i);
+    /// }
+    /// ```
+    #[clippy::version = "pre 1.29.0"]
+    pub NEEDLESS_RANGE_LOOP,
+    style,
+    "for-looping over a range of indices where an iterator over items would do"
+}
+
+declare_clippy_lint! {
+    /// ### What it does
+    /// Checks for `unwrap` calls.
+    ///
+    /// ### Why restrict this?
+    /// It is better to handle the `None` case.
+    #[clippy::version = "1.45.0"]
+    pub UNWRAP_USED,
+    restriction,
+    "using `.unwrap()` on `Option`, which should at least get a better message using `expect()`"
+}
+"#;
+
+    const CONF: &str = r#"
+define_Conf! {
+    /// Whether to allow `unwrap` in tests
+    #[lints(unwrap_used)]
+    allow_unwrap_in_tests: bool = false,
+    /// The maximum size of an enum's variant to avoid box suggestion
+    #[lints(
+        large_enum_variant,
+        needless_range_loop,
+    )]
+    enum_variant_size_threshold: u64 = 200,
+    /// Lint: UNWRAP_USED.
+    ///
+    /// Whether `unwrap` should be allowed in `const` contexts
+    (allow_unwrap_in_consts: bool = true),
+}
+"#;
+
+    #[test]
+    fn test_parse_lints() {
+        let lints = parse_lints(LINT_FILE, "clippy_lints/src/loops/mod.rs");
+        let clippy = ClippyLints::new(lints, &parse_config(CONF));
+
+        let lint = clippy.get("clippy::needless-range-loop").unwrap();
+        assert_eq!(lint.group, "style");
+        assert_eq!(lint.default_level(), "warn");
+        assert_eq!(lint.version.as_deref(), Some("pre 1.29.0"));
+        assert!(lint.what_it_does.starts_with("Checks for looping"));
+        assert!(lint.why_bad.ends_with("probably faster."));
+        assert!(lint.example.as_deref().unwrap().contains("Use instead:"));
+        assert!(
+            lint.suggested_fix()
+                .unwrap()
+                .starts_with("```no_run\nfor i in &vec {")
+        );
+        let flagged = lint.flagged_code().unwrap();
+        assert!(flagged.starts_with("```no_run\nfor i in 0..vec.len() {"));
+        assert!(flagged.ends_with("```"));
+        assert_eq!(lint.configuration[0].name, "enum-variant-size-threshold");
+        assert_eq!(lint.configuration[0].default.as_deref(), Some("200"));
+
+        let unwrap = clippy.get("UNWRAP_USED").unwrap();
+        assert_eq!(unwrap.default_level(), "allow");
+        assert_eq!(unwrap.why_bad, "It is better to handle the `None` case.");
+        assert!(unwrap.description.contains("`expect()`"));
+        assert_eq!(unwrap.suggested_fix(), None);
+        assert_eq!(unwrap.flagged_code(), None);
+        let options: Vec<&str> = unwrap
+            .configuration
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect();
+        assert_eq!(
+            options,
+            vec!["allow-unwrap-in-tests", "allow-unwrap-in-consts"]
+        );
+
+        assert_eq!(clippy.similar("range_loop", 3), vec!["needless_range_loop"]);
+    }
+
+    #[test]
+    fn test_lint_document() {
+        let lints = parse_lints(LINT_FILE, "clippy_lints/src/loops/mod.rs");
+        let clippy = ClippyLints::new(lints, &[]);
+        let documents = clippy.documents(&mut SourceReport::new(CLIPPY_SOURCE));
+
+        assert_eq!(documents[0].title, "clippy::needless_range_loop");
+        assert_eq!(documents[0].path, "needless_range_loop");
+        assert!(
+            documents[0]
+                .headings
+                .contains(&"Why is this bad?".to_string())
+        );
+    }
+}
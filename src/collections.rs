@@ -0,0 +1,378 @@
+//! Choosing a std collection for a described use case.
+//!
+//! Each collection carries the `std::collections` guide's "Use a ... when" list and its row of
+//! the guide's performance table, quoted so answers can cite them. As with
+//! `concurrency_help`, use-case words are scored against keywords per collection; the best
+//! matches are recommended alongside the Book's common collections chapters.
+
+use crate::search::SearchResult;
+
+/// Sources searched for collection guidance
+pub const COLLECTION_SOURCES: &[&str] = &["rust-book", "rust-by-example", "std-src"];
+
+/// The std collections guide
+pub const GUIDE_URL: &str = "https://doc.rust-lang.org/std/collections/index.html";
+
+/// Anchor of the guide's "When Should You Use Which Collection?" section
+pub const WHEN_ANCHOR: &str = "#when-should-you-use-which-collection";
+
+/// Anchor of the guide's performance tables
+pub const PERFORMANCE_ANCHOR: &str = "#performance";
+
+/// Legend of the guide's performance tables
+pub const COST_LEGEND: &str = "* amortized, ~ expected, n = length, m = length of the other collection, i = index. Sets cost the same as their maps.";
+
+/// Chapters that introduce the collections, pinned above other results
+const COLLECTION_CHAPTERS: &[(&str, &str)] = &[
+    ("rust-book", "ch08-00-common-collections.md"),
+    ("rust-book", "ch08-01-vectors.md"),
+    ("rust-book", "ch08-03-hash-maps.md"),
+];
+
+const MAX_RECOMMENDATIONS: usize = 3;
+
+/// A collection from std
+#[derive(Debug)]
+pub struct Collection {
+    /// Type as it is usually written
+    pub name: &'static str,
+    /// Full std path
+    pub path: &'static str,
+    /// The guide's reasons to use it
+    pub use_when: &'static [&'static str],
+    /// The guide's costs, by operation
+    pub costs: &'static [(&'static str, &'static str)],
+    /// Lowercase use-case words that suggest this collection
+    pub keywords: &'static [&'static str],
+}
+
+impl Collection {
+    /// Link to the std documentation page
+    pub fn doc_url(&self) -> String {
+        let mut segments: Vec<&str> = self.path.split("::").collect();
+        let item = segments.pop().unwrap_or(self.path);
+        format!(
+            "https://doc.rust-lang.org/{}/struct.{}.html",
+            segments.join("/"),
+            item
+        )
+    }
+}
+
+const SEQUENCE_OPERATIONS: [&str; 5] =
+    ["get(i)", "insert(i)", "remove(i)", "append", "split_off(i)"];
+
+const HASH_MAP_COSTS: &[(&str, &str)] = &[
+    ("get", "O(1)~"),
+    ("insert", "O(1)~*"),
+    ("remove", "O(1)~"),
+    ("range", "N/A"),
+    ("append", "N/A"),
+];
+
+const BTREE_MAP_COSTS: &[(&str, &str)] = &[
+    ("get", "O(log(n))"),
+    ("insert", "O(log(n))"),
+    ("remove", "O(log(n))"),
+    ("range", "O(log(n))"),
+    ("append", "O(n+m)"),
+];
+
+const SET_USE_WHEN: &[&str] = &[
+    "You just want to remember which keys you've seen.",
+    "There is no meaningful value to associate with your keys.",
+    "You just want a set.",
+];
+
+/// Built-in collections, in order of preference when scores tie
+pub const COLLECTIONS: &[Collection] = &[
+    Collection {
+        name: "Vec",
+        path: "std::vec::Vec",
+        use_when: &[
+            "You want to collect items up to be processed or sent elsewhere later, and don't care about any properties of the actual values being stored.",
+            "You want a sequence of elements in a particular order, and will only be appending to (or near) the end.",
+            "You want a stack.",
+            "You want a resizable array.",
+            "You want a heap-allocated array.",
+        ],
+        costs: &[
+            (SEQUENCE_OPERATIONS[0], "O(1)"),
+            (SEQUENCE_OPERATIONS[1], "O(n-i)*"),
+            (SEQUENCE_OPERATIONS[2], "O(n-i)"),
+            (SEQUENCE_OPERATIONS[3], "O(m)*"),
+            (SEQUENCE_OPERATIONS[4], "O(n-i)"),
+        ],
+        keywords: &[
+            "stack",
+            "lifo",
+            "push",
+            "pop",
+            "append",
+            "array",
+            "list",
+            "sequence",
+            "index",
+            "indexed",
+            "random access",
+            "collect",
+            "buffer",
+            "resizable",
+            "growable",
+        ],
+    },
+    Collection {
+        name: "VecDeque",
+        path: "std::collections::VecDeque",
+        use_when: &[
+            "You want a Vec that supports efficient insertion at both ends of the sequence.",
+            "You want a queue.",
+            "You want a double-ended queue (deque).",
+        ],
+        costs: &[
+            (SEQUENCE_OPERATIONS[0], "O(1)"),
+            (SEQUENCE_OPERATIONS[1], "O(min(i, n-i))*"),
+            (SEQUENCE_OPERATIONS[2], "O(min(i, n-i))"),
+            (SEQUENCE_OPERATIONS[3], "O(m)*"),
+            (SEQUENCE_OPERATIONS[4], "O(min(i, n-i))"),
+        ],
+        keywords: &[
+            "queue",
+            "fifo",
+            "deque",
+            "double-ended",
+            "both ends",
+            "front",
+            "ring buffer",
+            "circular",
+            "sliding window",
+            "breadth-first",
+            "bfs",
+        ],
+    },
+    Collection {
+        name: "LinkedList",
+        path: "std::collections::LinkedList",
+        use_when: &[
+            "You want a Vec or VecDeque of unknown size, and can't tolerate amortization.",
+            "You want to efficiently split and append lists.",
+            "You are absolutely certain you really, truly, want a doubly linked list.",
+        ],
+        costs: &[
+            (SEQUENCE_OPERATIONS[0], "O(min(i, n-i))"),
+            (SEQUENCE_OPERATIONS[1], "O(min(i, n-i))"),
+            (SEQUENCE_OPERATIONS[2], "O(min(i, n-i))"),
+            (SEQUENCE_OPERATIONS[3], "O(1)"),
+            (SEQUENCE_OPERATIONS[4], "O(min(i, n-i))"),
+        ],
+        keywords: &[
+            "linked list",
+            "doubly linked",
+            "splice",
+            "concatenate",
+            "amortization",
+            "worst-case",
+        ],
+    },
+    Collection {
+        name: "HashMap",
+        path: "std::collections::HashMap",
+        use_when: &[
+            "You want to associate arbitrary keys with an arbitrary value.",
+            "You want a cache.",
+            "You want a map, with no extra functionality.",
+        ],
+        costs: HASH_MAP_COSTS,
+        keywords: &[
+            "map",
+            "key",
+            "keys",
+            "lookup",
+            "look up",
+            "dictionary",
+            "associate",
+            "cache",
+            "counting",
+            "frequency",
+            "by id",
+            "by name",
+        ],
+    },
+    Collection {
+        name: "BTreeMap",
+        path: "std::collections::BTreeMap",
+        use_when: &[
+            "You want a map sorted by its keys.",
+            "You want to be able to get a range of entries on-demand.",
+            "You're interested in what the smallest or largest key-value pair is.",
+            "You want to find the largest or smallest key that is smaller or larger than something.",
+        ],
+        costs: BTREE_MAP_COSTS,
+        keywords: &[
+            "ordered keys",
+            "sorted keys",
+            "ordered map",
+            "sorted map",
+            "ordered",
+            "sorted",
+            "range",
+            "ranges",
+            "smallest",
+            "largest",
+            "nearest",
+            "successor",
+            "predecessor",
+            "in order",
+        ],
+    },
+    Collection {
+        name: "HashSet",
+        path: "std::collections::HashSet",
+        use_when: SET_USE_WHEN,
+        costs: HASH_MAP_COSTS,
+        keywords: &[
+            "set",
+            "seen",
+            "visited",
+            "unique",
+            "distinct",
+            "dedup",
+            "deduplicate",
+            "membership",
+            "contains",
+            "duplicates",
+        ],
+    },
+    Collection {
+        name: "BTreeSet",
+        path: "std::collections::BTreeSet",
+        use_when: SET_USE_WHEN,
+        costs: BTREE_MAP_COSTS,
+        keywords: &[
+            "sorted set",
+            "ordered set",
+            "unique",
+            "distinct",
+            "sorted",
+            "ordered",
+            "range",
+            "smallest",
+            "largest",
+        ],
+    },
+    Collection {
+        name: "BinaryHeap",
+        path: "std::collections::BinaryHeap",
+        use_when: &[
+            "You want to store a bunch of elements, but only ever want to process the \"biggest\" or \"most important\" one at any given time.",
+            "You want a priority queue.",
+        ],
+        costs: &[("push", "O(1)~"), ("pop", "O(log(n))"), ("peek", "O(1)")],
+        keywords: &[
+            "priority",
+            "priority queue",
+            "heap",
+            "biggest",
+            "most important",
+            "top-k",
+            "scheduler",
+            "scheduling",
+            "dijkstra",
+            "min-heap",
+            "max-heap",
+        ],
+    },
+];
+
+/// A collection suggested for a use case
+#[derive(Debug)]
+pub struct Recommendation {
+    pub collection: &'static Collection,
+    /// Use-case words that selected it
+    pub matched: Vec<&'static str>,
+}
+
+/// Recommend collections for a use case, best match first
+pub fn recommend(use_case: &str) -> Vec<Recommendation> {
+    let lower = use_case.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mentions = |keyword: &str| {
+        if keyword.contains(' ') || keyword.contains('-') {
+            lower.contains(keyword)
+        } else {
+            words.contains(&keyword)
+        }
+    };
+
+    let mut recommendations: Vec<Recommendation> = COLLECTIONS
+        .iter()
+        .map(|collection| Recommendation {
+            collection,
+            matched: collection
+                .keywords
+                .iter()
+                .copied()
+                .filter(|k| mentions(k))
+                .collect(),
+        })
+        .filter(|r| !r.matched.is_empty())
+        .collect();
+
+    // Stable sort keeps table order among equal scores
+    recommendations.sort_by_key(|r| std::cmp::Reverse(r.matched.len()));
+    recommendations.truncate(MAX_RECOMMENDATIONS);
+    recommendations
+}
+
+/// Move the common collections chapters to the front, keeping relative order
+pub fn apply_boost(results: &mut [SearchResult]) {
+    results.sort_by_key(|result| {
+        !COLLECTION_CHAPTERS
+            .iter()
+            .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(use_case: &str) -> Vec<&'static str> {
+        recommend(use_case)
+            .iter()
+            .map(|r| r.collection.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_recommend() {
+        assert_eq!(names("ordered keys, frequent range queries")[0], "BTreeMap");
+        assert_eq!(names("LIFO with cheap push/pop")[0], "Vec");
+        assert_eq!(names("a FIFO queue of jobs")[0], "VecDeque");
+        assert_eq!(
+            names("remember which URLs were already visited")[0],
+            "HashSet"
+        );
+        assert_eq!(names("priority queue of timers")[0], "BinaryHeap");
+        assert!(names("hello world").is_empty());
+    }
+
+    #[test]
+    fn test_guide_tables() {
+        for collection in COLLECTIONS {
+            assert!(!collection.use_when.is_empty(), "{}", collection.name);
+            assert!(!collection.costs.is_empty(), "{}", collection.name);
+        }
+        assert_eq!(
+            COLLECTIONS[0].doc_url(),
+            "https://doc.rust-lang.org/std/vec/struct.Vec.html"
+        );
+        assert_eq!(
+            COLLECTIONS[4].doc_url(),
+            "https://doc.rust-lang.org/std/collections/struct.BTreeMap.html"
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Choosing a synchronization primitive for a described scenario.
+//!
+//! Each std primitive lists the scenario words that suggest it; the best-scoring primitives are
+//! recommended alongside the Book's concurrency chapters and the Nomicon's Send/Sync and
+//! atomics chapters.
+
+use crate::search::SearchResult;
+
+/// Sources searched for concurrency guidance
+pub const CONCURRENCY_SOURCES: &[&str] = &["rust-book", "rustonomicon", "rust-by-example"];
+
+/// Chapters that explain concurrency in general, pinned above other results
+const CONCURRENCY_CHAPTERS: &[(&str, &str)] = &[
+    ("rust-book", "ch16-01-threads.md"),
+    ("rust-book", "ch16-02-message-passing.md"),
+    ("rust-book", "ch16-03-shared-state.md"),
+    (
+        "rust-book",
+        "ch16-04-extensible-concurrency-sync-and-send.md",
+    ),
+    ("rustonomicon", "send-and-sync.md"),
+    ("rustonomicon", "atomics.md"),
+    ("rustonomicon", "races.md"),
+];
+
+const MAX_RECOMMENDATIONS: usize = 3;
+
+/// A synchronization primitive from std
+#[derive(Debug)]
+pub struct Primitive {
+    /// Type or function as it is usually written
+    pub name: &'static str,
+    /// Full std path
+    pub path: &'static str,
+    /// The situation it fits
+    pub when: &'static str,
+    /// What to watch out for
+    pub caveats: &'static str,
+    /// Lowercase scenario words that suggest this primitive
+    pub keywords: &'static [&'static str],
+}
+
+impl Primitive {
+    /// Link to the std documentation page
+    pub fn doc_url(&self) -> String {
+        let mut segments: Vec<&str> = self.path.split("::").collect();
+        let item = segments.pop().unwrap_or(self.path);
+        let kind = if item.chars().next().is_some_and(char::is_uppercase) {
+            "struct"
+        } else {
+            "fn"
+        };
+        format!(
+            "https://doc.rust-lang.org/{}/{}.{}.html",
+            segments.join("/"),
+            kind,
+            item
+        )
+    }
+}
+
+/// Built-in primitives, in order of preference when scores tie
+pub const PRIMITIVES: &[Primitive] = &[
+    Primitive {
+        name: "mpsc::channel",
+        path: "std::sync::mpsc::channel",
+        when: "Threads hand work or results to each other instead of sharing state",
+        caveats: "Multiple producers, one consumer; use `sync_channel` for backpressure. The receiver sees a disconnect once every sender is dropped.",
+        keywords: &[
+            "channel",
+            "message",
+            "send results",
+            "producer",
+            "consumer",
+            "queue",
+            "pipeline",
+            "worker",
+            "workers",
+            "pass data",
+        ],
+    },
+    Primitive {
+        name: "Arc<Mutex<T>>",
+        path: "std::sync::Mutex",
+        when: "Several threads mutate the same data and each update touches more than one field",
+        caveats: "Keep critical sections short and never hold the guard across `.await` or while locking another mutex in inconsistent order. A panic while locked poisons the mutex.",
+        keywords: &[
+            "shared",
+            "mutate",
+            "mutable",
+            "update",
+            "modify",
+            "lock",
+            "mutex",
+            "exclusive",
+            "counter",
+            "map",
+            "state",
+        ],
+    },
+    Primitive {
+        name: "Arc<RwLock<T>>",
+        path: "std::sync::RwLock",
+        when: "Data is read often and written rarely by several threads",
+        caveats: "Writers can starve depending on the platform; for small `Copy` values atomics are cheaper.",
+        keywords: &[
+            "read-heavy",
+            "mostly read",
+            "many readers",
+            "readers",
+            "rarely written",
+            "config",
+            "cache",
+            "rwlock",
+        ],
+    },
+    Primitive {
+        name: "AtomicUsize / AtomicBool",
+        path: "std::sync::atomic::AtomicUsize",
+        when: "A single integer or flag is shared: counters, stop flags, IDs",
+        caveats: "Only individual values are atomic; invariants spanning several values need a lock. Use `Ordering::SeqCst` unless you can justify weaker orderings.",
+        keywords: &[
+            "counter",
+            "count",
+            "flag",
+            "stop",
+            "cancel",
+            "atomic",
+            "increment",
+            "statistics",
+            "id",
+        ],
+    },
+    Primitive {
+        name: "OnceLock / LazyLock",
+        path: "std::sync::OnceLock",
+        when: "A value is initialized once, lazily, and then only read (globals, caches built on first use)",
+        caveats: "Initialization runs once even when threads race; `LazyLock` needs Rust 1.80.",
+        keywords: &[
+            "once",
+            "global",
+            "lazy",
+            "initialize",
+            "initialization",
+            "singleton",
+            "static",
+        ],
+    },
+    Primitive {
+        name: "Condvar",
+        path: "std::sync::Condvar",
+        when: "A thread waits until some condition on shared state becomes true",
+        caveats: "Always pair with a `Mutex` and re-check the condition in a loop; wakeups can be spurious.",
+        keywords: &["wait", "notify", "until", "condition", "signal", "wake"],
+    },
+    Primitive {
+        name: "Barrier",
+        path: "std::sync::Barrier",
+        when: "A fixed number of threads must all reach a point before any continues",
+        caveats: "The count is fixed at creation; a thread that never arrives blocks the rest forever.",
+        keywords: &[
+            "barrier",
+            "all threads",
+            "rendezvous",
+            "phase",
+            "in lockstep",
+        ],
+    },
+    Primitive {
+        name: "thread::scope",
+        path: "std::thread::scope",
+        when: "Threads borrow local data and all finish before the function returns",
+        caveats: "Needs Rust 1.63. Borrowing avoids `Arc`, but a panic in a scoped thread propagates when the scope ends.",
+        keywords: &[
+            "borrow",
+            "borrowed",
+            "local",
+            "stack",
+            "scoped",
+            "without arc",
+            "parallel loop",
+            "split",
+        ],
+    },
+];
+
+/// A primitive suggested for a scenario
+#[derive(Debug)]
+pub struct Recommendation {
+    pub primitive: &'static Primitive,
+    /// Scenario words that selected it
+    pub matched: Vec<&'static str>,
+}
+
+/// Recommend primitives for a scenario, best match first
+pub fn recommend(scenario: &str) -> Vec<Recommendation> {
+    let lower = scenario.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mentions = |keyword: &str| {
+        if keyword.contains(' ') || keyword.contains('-') {
+            lower.contains(keyword)
+        } else {
+            words.contains(&keyword)
+        }
+    };
+
+    let mut recommendations: Vec<Recommendation> = PRIMITIVES
+        .iter()
+        .map(|primitive| Recommendation {
+            primitive,
+            matched: primitive
+                .keywords
+                .iter()
+                .copied()
+                .filter(|k| mentions(k))
+                .collect(),
+        })
+        .filter(|r| !r.matched.is_empty())
+        .collect();
+
+    // Stable sort keeps table order among equal scores
+    recommendations.sort_by_key(|r| std::cmp::Reverse(r.matched.len()));
+    recommendations.truncate(MAX_RECOMMENDATIONS);
+    recommendations
+}
+
+/// Move the concurrency chapters to the front, keeping relative order
+pub fn apply_boost(results: &mut [SearchResult]) {
+    results.sort_by_key(|result| {
+        !CONCURRENCY_CHAPTERS
+            .iter()
+            .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(scenario: &str) -> Vec<&'static str> {
+        recommend(scenario)
+            .iter()
+            .map(|r| r.primitive.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_recommend() {
+        assert_eq!(
+            names("worker threads send results back to the main thread")[0],
+            "mpsc::channel"
+        );
+        assert_eq!(
+            names("a stop flag checked by every thread")[0],
+            "AtomicUsize / AtomicBool"
+        );
+        assert_eq!(
+            names("config that many readers check and is rarely written")[0],
+            "Arc<RwLock<T>>"
+        );
+        assert!(names("hello world").is_empty());
+    }
+
+    #[test]
+    fn test_doc_url() {
+        assert_eq!(
+            PRIMITIVES[1].doc_url(),
+            "https://doc.rust-lang.org/std/sync/struct.Mutex.html"
+        );
+        assert_eq!(
+            PRIMITIVES[7].doc_url(),
+            "https://doc.rust-lang.org/std/thread/fn.scope.html"
+        );
+    }
+}
@@ -0,0 +1,448 @@
+//! Server configuration file.
+//!
+//! Settings that don't fit an environment variable (transports, credentials) are read from
+//! `RUST_MCP_CONFIG` or `<data_dir>/config.toml`. Every section is optional; without a file the
+//! server runs on stdio as before.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::profiles;
+use crate::search::embeddings::{self, MODELS, ModelSpec};
+use crate::search::result_cache::{DEFAULT_CACHE_SIZE, DEFAULT_CACHE_TTL, ResultCache};
+use crate::sources;
+use crate::workspace::RootPolicy;
+
+/// Config file read from the data directory when `RUST_MCP_CONFIG` is not set
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Placeholder for the repository in `sources.mirror`
+pub const MIRROR_PLACEHOLDER: &str = "{repo}";
+
+/// Environment variable choosing the embedding model over `embeddings.model`
+const MODEL_VAR: &str = "RUST_MCP_EMBEDDING_MODEL";
+
+/// Server configuration
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Memory to size the index writer, HNSW graph, and embedding batches for; unlimited if unset
+    pub memory_budget_mb: Option<usize>,
+    /// Network listener; serves MCP over HTTP instead of (or next to) stdio when present
+    pub http: Option<HttpConfig>,
+    /// Credentials required by network transports
+    pub auth: AuthConfig,
+    /// Log output format
+    pub logging: LoggingConfig,
+    /// Where documentation sources are cloned from
+    pub sources: SourcesConfig,
+    /// Embedding model for semantic search
+    pub embeddings: EmbeddingsConfig,
+    /// Search latency and default profile settings
+    pub search: SearchConfig,
+    /// Corpus profiles by name, added to or replacing the built-in ones
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// Search settings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Longest a hybrid search waits for its semantic leg (query embedding and vector search)
+    /// before returning keyword results alone, marked partial; unbounded if unset
+    pub semantic_budget_ms: Option<u64>,
+    /// Profile used when a search doesn't ask for one, e.g. `beginner`; every source if unset
+    pub profile: Option<String>,
+    /// Translate queries in other languages into English through the client's sampling
+    /// capability, when the embedding model isn't multilingual; on if unset
+    pub translate_queries: Option<bool>,
+    /// Search outcomes cached per session for repeated queries; 256 if unset, 0 disables the
+    /// cache
+    pub cache_size: Option<usize>,
+    /// How long a cached outcome is served, in seconds; 300 if unset
+    pub cache_ttl_secs: Option<u64>,
+}
+
+impl SearchConfig {
+    pub fn semantic_budget(&self) -> Option<Duration> {
+        self.semantic_budget_ms.map(Duration::from_millis)
+    }
+
+    pub fn translate_queries(&self) -> bool {
+        self.translate_queries.unwrap_or(true)
+    }
+
+    /// An empty result cache of the configured size and time to live
+    pub fn result_cache(&self) -> ResultCache {
+        ResultCache::new(
+            self.cache_size.unwrap_or(DEFAULT_CACHE_SIZE),
+            self.cache_ttl_secs
+                .map_or(DEFAULT_CACHE_TTL, Duration::from_secs),
+        )
+    }
+}
+
+/// A corpus profile
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Source IDs searched; every source if unset
+    pub sources: Option<Vec<String>>,
+    /// Ranking multipliers by source ID, e.g. `{ rust-book = 1.5 }`
+    pub boosts: BTreeMap<String, f32>,
+}
+
+/// Embedding settings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingsConfig {
+    /// One of the built-in models, e.g. `bge-small-en-v1.5`; `all-MiniLM-L6-v2` if unset.
+    /// Changing it requires re-embedding the documentation.
+    pub model: Option<String>,
+}
+
+impl EmbeddingsConfig {
+    /// Use `model` instead of the file's model, if it is a built-in one
+    pub fn override_model(&mut self, model: &str) -> Result<()> {
+        check_model(model, MODEL_VAR)?;
+        self.model = Some(model.to_string());
+        Ok(())
+    }
+
+    /// The configured model (validated when the file is parsed)
+    pub fn model_spec(&self) -> &'static ModelSpec {
+        self.model
+            .as_deref()
+            .and_then(embeddings::model_spec)
+            .unwrap_or_else(embeddings::default_model)
+    }
+}
+
+/// Documentation source settings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourcesConfig {
+    /// Clone URL template replacing `https://github.com/{repo}.git`, for environments without
+    /// GitHub access, e.g. `https://git.corp.example/mirror/{repo}.git`; `{repo}` is the GitHub
+    /// `owner/name` of the source
+    pub mirror: Option<String>,
+    /// Standard library sources to index for `show_std_impl`: a `library` directory, or the
+    /// `rust` directory of the `rust-src` component
+    /// (`$(rustc --print sysroot)/lib/rustlib/src/rust`)
+    pub std_src: Option<PathBuf>,
+    /// Directory of the standard library's rustdoc JSON (`core.json`, `alloc.json`,
+    /// `std.json`) for `show_trait_implementors` and `stable_since`, from the nightly
+    /// `rust-docs-json` component
+    /// (`$(rustc +nightly --print sysroot)/share/doc/rust/json`)
+    pub std_json: Option<PathBuf>,
+    /// IDs of built-in sources not to clone or index, e.g. `["tlborm", "rustwasm-book"]`
+    pub disabled: Vec<String>,
+    /// Branch or tag to check out by source ID, e.g. `{ rust-book = "trpl-v2" }`; sources
+    /// without a pin follow their repository's default branch
+    pub pins: BTreeMap<String, String>,
+    /// Additional mdBooks to clone and index, by source ID
+    pub books: BTreeMap<String, BookConfig>,
+}
+
+/// An mdBook repository added to the documentation sources
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BookConfig {
+    /// Human-readable name; the source ID if unset
+    #[serde(default)]
+    pub name: Option<String>,
+    /// GitHub repository (`owner/name`), cloned through `mirror` like the built-in sources
+    pub repo: String,
+    /// Directory of the book's markdown within the repository; `src` if unset
+    #[serde(default)]
+    pub src_path: Option<String>,
+}
+
+/// Streamable HTTP transport settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Address to listen on, e.g. `127.0.0.1:8080`
+    pub bind: SocketAddr,
+    /// Keep serving stdio as well, so the process spawned by an editor can be shared over HTTP
+    #[serde(default)]
+    pub stdio: bool,
+    /// Directories whose subdirectories HTTP clients may share as workspace roots; roots
+    /// elsewhere, and all roots when this is empty, are not indexed
+    #[serde(default)]
+    pub workspace_roots: Vec<PathBuf>,
+}
+
+impl HttpConfig {
+    /// Which roots of HTTP sessions are indexed
+    pub fn root_policy(&self) -> RootPolicy {
+        RootPolicy::Within(self.workspace_roots.clone().into())
+    }
+}
+
+/// Authentication for network transports
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Static bearer tokens accepted in the `Authorization` header
+    pub bearer_tokens: Vec<String>,
+    /// OAuth 2.0 token introspection, for tokens issued by an authorization server
+    pub oauth: Option<OAuthConfig>,
+}
+
+/// Logging settings; the level still comes from `RUST_LOG`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Also log to rotating files under `<data_dir>/logs`
+    pub file: Option<LogFileConfig>,
+}
+
+/// Rotating log file settings
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogFileConfig {
+    /// Level or `RUST_LOG`-style filter for the file, independent of stderr
+    pub level: String,
+    /// How often to start a new file
+    pub rotation: LogRotation,
+    /// Rotated files to keep before deleting the oldest
+    pub max_files: usize,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            rotation: LogRotation::Daily,
+            max_files: 7,
+        }
+    }
+}
+
+/// Log file rotation period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+/// Format of log lines written to stderr
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, with event fields at the top level
+    Json,
+}
+
+/// OAuth 2.0 token introspection (RFC 7662) settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OAuthConfig {
+    /// Introspection endpoint of the authorization server
+    pub introspection_url: String,
+    /// Client credentials this server authenticates to the endpoint with
+    pub client_id: String,
+    pub client_secret: String,
+    /// Scope a token must carry, if any
+    #[serde(default)]
+    pub required_scope: Option<String>,
+}
+
+impl Config {
+    /// Load the configuration file, if there is one.
+    ///
+    /// The file is taken from `RUST_MCP_CONFIG` or `<data_dir>/config.toml`. Unlike the synonyms
+    /// file, a malformed config is an error: silently dropping an `[auth]` section would expose
+    /// the server.
+    ///
+    /// `RUST_MCP_EMBEDDING_MODEL` overrides `embeddings.model`.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = std::env::var("RUST_MCP_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| data_dir.join(CONFIG_FILE));
+        let mut config = if path.exists() {
+            let text = std::fs::read_to_string(&path)?;
+            // Logging is configured from this file, so nothing is logged here
+            Self::parse(&text)
+                .map_err(|e| Error::Other(format!("Invalid config file {:?}: {}", path, e)))?
+        } else {
+            Self::default()
+        };
+        if let Ok(model) = std::env::var(MODEL_VAR) {
+            config.embeddings.override_model(&model)?;
+        }
+        Ok(config)
+    }
+
+    /// Parse configuration from TOML
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text).map_err(|e| Error::Other(e.to_string()))?;
+        if let Some(mirror) = &config.sources.mirror
+            && !mirror.contains(MIRROR_PLACEHOLDER)
+        {
+            return Err(Error::Other(format!(
+                "sources.mirror {:?} must contain {}",
+                mirror, MIRROR_PLACEHOLDER
+            )));
+        }
+        if let Some(model) = &config.embeddings.model {
+            check_model(model, "embeddings.model")?;
+        }
+        sources::validate(&config.sources)?;
+        profiles::validate(
+            &config.profiles,
+            config.search.profile.as_deref(),
+            &config.sources,
+        )?;
+        Ok(config)
+    }
+}
+
+/// Check that the embedding model set by `setting` is a built-in one
+fn check_model(model: &str, setting: &str) -> Result<()> {
+    if embeddings::model_spec(model).is_some() {
+        return Ok(());
+    }
+    let known: Vec<&str> = MODELS.iter().map(|model| model.id).collect();
+    Err(Error::Other(format!(
+        "Unknown {} {:?}; expected one of {}",
+        setting,
+        model,
+        known.join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            r#"
+memory_budget_mb = 512
+
+[http]
+bind = "0.0.0.0:8080"
+workspace_roots = ["/srv/projects"]
+
+[auth]
+bearer_tokens = ["secret"]
+
+[auth.oauth]
+introspection_url = "https://auth.example.com/introspect"
+client_id = "rust-lang-mcp"
+client_secret = "hunter2"
+
+[logging]
+format = "json"
+
+[logging.file]
+level = "debug"
+
+[sources]
+mirror = "https://git.corp.example/mirror/{repo}.git"
+std_src = "/opt/rust/lib/rustlib/src/rust"
+std_json = "/opt/rust/share/doc/rust/json"
+disabled = ["tlborm"]
+pins = { rust-book = "trpl-v2", embedded-book = "v0.3" }
+
+[sources.books.embedded-book]
+name = "The Embedded Rust Book"
+repo = "rust-embedded/book"
+
+[embeddings]
+model = "bge-small-en-v1.5"
+
+[search]
+semantic_budget_ms = 250
+profile = "async"
+cache_size = 0
+
+[profiles.async]
+sources = ["async-book", "rust-book"]
+boosts = { async-book = 1.5, embedded-book = 0.5 }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.memory_budget_mb, Some(512));
+        let http = config.http.unwrap();
+        assert_eq!(http.bind.port(), 8080);
+        assert!(!http.stdio);
+        assert_eq!(http.workspace_roots, vec![PathBuf::from("/srv/projects")]);
+        assert_eq!(config.auth.bearer_tokens, vec!["secret"]);
+        assert!(config.auth.oauth.unwrap().required_scope.is_none());
+        assert_eq!(config.logging.format, LogFormat::Json);
+        let file = config.logging.file.unwrap();
+        assert_eq!(file.level, "debug");
+        assert_eq!(file.rotation, LogRotation::Daily);
+        assert_eq!(
+            config.sources.mirror.as_deref(),
+            Some("https://git.corp.example/mirror/{repo}.git")
+        );
+        assert_eq!(
+            config.sources.std_src,
+            Some(PathBuf::from("/opt/rust/lib/rustlib/src/rust"))
+        );
+        assert_eq!(
+            config.sources.std_json,
+            Some(PathBuf::from("/opt/rust/share/doc/rust/json"))
+        );
+        assert_eq!(config.sources.disabled, vec!["tlborm"]);
+        assert_eq!(config.sources.pins["embedded-book"], "v0.3");
+        let book = &config.sources.books["embedded-book"];
+        assert_eq!(book.repo, "rust-embedded/book");
+        assert!(book.src_path.is_none());
+        assert_eq!(config.embeddings.model_spec().id, "bge-small-en-v1.5");
+        assert_eq!(
+            config.search.semantic_budget(),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(config.search.profile.as_deref(), Some("async"));
+        let cache = config.search.result_cache();
+        let options = crate::search::SearchOptions::new(5);
+        assert!(
+            cache
+                .get("traits", crate::search::SearchMode::Hybrid, &options)
+                .is_none()
+        );
+        assert_eq!(config.profiles["async"].boosts["async-book"], 1.5);
+    }
+
+    #[test]
+    fn test_parse_defaults_and_errors() {
+        let config = Config::parse("").unwrap();
+        assert!(config.http.is_none());
+        assert!(config.auth.bearer_tokens.is_empty() && config.auth.oauth.is_none());
+        assert_eq!(config.embeddings.model_spec().id, embeddings::DEFAULT_MODEL);
+        assert!(config.search.semantic_budget().is_none());
+
+        assert!(Config::parse("[auth]\nbearer_token = \"typo\"").is_err());
+        assert!(Config::parse("[http]\nbind = \"not an address\"").is_err());
+        assert!(
+            Config::parse("[sources]\nmirror = \"https://git.corp.example/book.git\"").is_err()
+        );
+        assert!(Config::parse("[embeddings]\nmodel = \"text-embedding-3-large\"").is_err());
+        let mut embeddings = config.embeddings;
+        assert!(embeddings.override_model("text-embedding-3-large").is_err());
+        embeddings.override_model("gte-small").unwrap();
+        assert_eq!(embeddings.model_spec().id, "gte-small");
+        assert!(Config::parse("[sources]\ndisabled = [\"rust-boook\"]").is_err());
+        assert!(Config::parse("[sources.pins]\nembedded-book = \"v0.3\"").is_err());
+        assert!(Config::parse("[sources.books.rust-book]\nrepo = \"rust-lang/book\"").is_err());
+        assert!(Config::parse("[sources.books.embedded-book]\nrepo = \"book\"").is_err());
+    }
+}
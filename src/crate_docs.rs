@@ -0,0 +1,427 @@
+//! Documentation of crates published on crates.io.
+//!
+//! The indexed books cover the language and the standard library; `lookup_crate_docs` answers
+//! API questions about other crates (tokio, serde, ...) without indexing them ahead of time.
+//! The first lookup of a release resolves the requested version through the crates.io API,
+//! downloads the rustdoc JSON docs.rs built for it into `data/crates`, and parses it like the
+//! std rustdoc JSON (see `std_json`), with links to docs.rs.
+//!
+//! The last few parsed releases stay in memory. A release that was downloaded before is read
+//! from disk, without going online when its exact version is requested. docs.rs only builds
+//! rustdoc JSON for recent releases; older ones get an error pointing at their HTML pages.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::std_json::{self, ApiItem, StdJson};
+
+/// crates.io API endpoint for crate metadata
+const CRATES_API: &str = "https://crates.io/api/v1/crates";
+
+const DOCS_RS: &str = "https://docs.rs";
+
+/// Directory under the data directory holding downloaded rustdoc JSON
+const CRATES_DIR: &str = "crates";
+
+/// crates.io asks API clients to identify themselves
+const USER_AGENT: &str = concat!(
+    "rust-lang-mcp/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/tauanbinato/rust-lang-mcp)"
+);
+
+/// Parsed releases kept in memory; a large crate's items take tens of megabytes
+const MAX_LOADED: usize = 4;
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+    #[serde(default)]
+    versions: Vec<VersionInfo>,
+}
+
+#[derive(Deserialize)]
+struct CrateInfo {
+    /// Canonical name; crates.io matches names case-insensitively and `-` with `_`
+    name: String,
+    max_stable_version: Option<String>,
+    max_version: String,
+}
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    num: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// The documentation of one release of a crate
+pub struct CrateApi {
+    pub name: String,
+    pub version: String,
+    api: StdJson,
+}
+
+impl CrateApi {
+    /// docs.rs page of the release
+    pub fn docs_url(&self) -> String {
+        docs_url(&self.name, &self.version)
+    }
+
+    /// Documented items named `path` (`tokio::sync::Mutex`, `sync::Mutex::lock`, `Mutex`),
+    /// best matches first
+    pub fn items(&self, path: &str) -> Vec<&ApiItem> {
+        self.api.api_items(path)
+    }
+}
+
+/// Downloaded and parsed crate documentation
+pub struct CrateDocs {
+    dir: PathBuf,
+    /// Most recently used first
+    loaded: Mutex<Vec<Arc<CrateApi>>>,
+}
+
+impl CrateDocs {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            dir: data_dir.join(CRATES_DIR),
+            loaded: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The documentation of a release of `name`, downloading it if needed (this blocks).
+    ///
+    /// `version` is an exact version (`1.40.0`), a prefix (`1`, `1.40`) for the newest release
+    /// it matches, or `None` or `latest` for the newest stable release.
+    pub fn load(&self, name: &str, version: Option<&str>) -> Result<Arc<CrateApi>> {
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Other(format!("'{}' is not a crate name", name)));
+        }
+        let requested = version
+            .map(str::trim)
+            .filter(|version| !version.is_empty() && *version != "latest");
+        // Versions end up in file names, so anything else is rejected before it gets that far
+        if let Some(version) = requested.filter(|version| !is_requestable(version)) {
+            return Err(Error::Other(format!("'{}' is not a version", version)));
+        }
+
+        // An exact release read before needs no lookup on crates.io
+        let known = requested.filter(|version| {
+            is_exact(version)
+                && (self.loaded(name, version).is_some() || self.path(name, version).exists())
+        });
+        let (name, version) = match known {
+            Some(version) => (name.to_string(), version.to_string()),
+            None => resolve_version(name, requested)?,
+        };
+        if !is_exact(&version) {
+            return Err(Error::Other(format!(
+                "crates.io returned '{}' as a version of {}",
+                version, name
+            )));
+        }
+        if let Some(api) = self.loaded(&name, &version) {
+            return Ok(api);
+        }
+
+        let path = self.path(&name, &version);
+        if !path.exists() {
+            download_json(&name, &version, &path)?;
+        }
+        let api = Arc::new(read_json(&name, &version, &path)?);
+        tracing::info!("Loaded the documentation of {} {}", name, version);
+        let mut loaded = self.lock();
+        loaded.insert(0, api.clone());
+        loaded.truncate(MAX_LOADED);
+        Ok(api)
+    }
+
+    /// A parsed release, moved to the front of the loaded ones
+    fn loaded(&self, name: &str, version: &str) -> Option<Arc<CrateApi>> {
+        let mut loaded = self.lock();
+        let position = loaded
+            .iter()
+            .position(|api| same_crate(&api.name, name) && api.version == version)?;
+        let api = loaded.remove(position);
+        loaded.insert(0, api.clone());
+        Some(api)
+    }
+
+    /// Downloaded rustdoc JSON of a release, named so that `Serde_JSON` and `serde-json` share it
+    fn path(&self, name: &str, version: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}-{}.json.zst", crate_key(name), version))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Arc<CrateApi>>> {
+        self.loaded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Canonical name of a crate and the release matching `requested`, from crates.io
+fn resolve_version(name: &str, requested: Option<&str>) -> Result<(String, String)> {
+    let url = format!("{}/{}", CRATES_API, name);
+    let response = match ureq::get(&url).header("User-Agent", USER_AGENT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(404)) => {
+            return Err(Error::Other(format!(
+                "No crate named '{}' on crates.io",
+                name
+            )));
+        }
+        Err(e) => return Err(Error::Other(format!("Failed to query {}: {}", url, e))),
+    };
+    let body = response
+        .into_body()
+        .read_to_string()
+        .map_err(|e| Error::Other(format!("Failed to read {}: {}", url, e)))?;
+    let krate: CrateResponse = serde_json::from_str(&body)
+        .map_err(|e| Error::Other(format!("Unexpected response from {}: {}", url, e)))?;
+
+    let version = pick_version(&krate, requested).ok_or_else(|| {
+        Error::Other(format!(
+            "{} has no release matching '{}' (the newest is {})",
+            krate.krate.name,
+            requested.unwrap_or("latest"),
+            krate.krate.max_version
+        ))
+    })?;
+    Ok((krate.krate.name, version))
+}
+
+/// The release matching `requested`: that exact version (even if yanked), else the newest
+/// release that isn't yanked or a pre-release and starts with it; the newest stable release
+/// when none is requested
+fn pick_version(krate: &CrateResponse, requested: Option<&str>) -> Option<String> {
+    let Some(requested) = requested.map(|version| version.trim_start_matches('v')) else {
+        return krate
+            .krate
+            .max_stable_version
+            .clone()
+            .or_else(|| Some(krate.krate.max_version.clone()));
+    };
+    if krate
+        .versions
+        .iter()
+        .any(|version| version.num == requested)
+    {
+        return Some(requested.to_string());
+    }
+    let prefix = format!("{}.", requested);
+    krate
+        .versions
+        .iter()
+        .filter(|version| {
+            !version.yanked && !version.num.contains('-') && version.num.starts_with(&prefix)
+        })
+        .max_by_key(|version| release_key(&version.num))
+        .map(|version| version.num.clone())
+}
+
+/// Download the zstd-compressed rustdoc JSON of a release from docs.rs
+fn download_json(name: &str, version: &str, path: &Path) -> Result<()> {
+    let url = format!("{}/crate/{}/{}/json", DOCS_RS, name, version);
+    tracing::info!("Downloading the rustdoc JSON of {} {}...", name, version);
+    let response = match ureq::get(&url).header("User-Agent", USER_AGENT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(404)) => {
+            return Err(Error::Other(format!(
+                "docs.rs has no rustdoc JSON for {} {}: it is only built for recent releases, and not when the documentation failed to build. Read {} instead.",
+                name,
+                version,
+                docs_url(name, version)
+            )));
+        }
+        Err(e) => return Err(Error::Other(format!("Failed to download {}: {}", url, e))),
+    };
+
+    // Written next to the final file and renamed, so a failed download isn't read later
+    std::fs::create_dir_all(path.parent().unwrap_or(path))?;
+    let partial = path.with_extension("part");
+    let mut reader = response.into_body().into_reader();
+    std::io::copy(&mut reader, &mut File::create(&partial)?)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Parse downloaded rustdoc JSON
+fn read_json(name: &str, version: &str, path: &Path) -> Result<CrateApi> {
+    let bytes = zstd::decode_all(File::open(path)?)
+        .map_err(|e| Error::Other(format!("Failed to decompress {:?}: {}", path, e)))?;
+    let json = String::from_utf8(bytes)
+        .map_err(|e| Error::Other(format!("Invalid rustdoc JSON in {:?}: {}", path, e)))?;
+    let api = std_json::parse_crate(&json, &docs_url(name, version))?;
+    Ok(CrateApi {
+        name: name.to_string(),
+        version: version.to_string(),
+        api,
+    })
+}
+
+fn docs_url(name: &str, version: &str) -> String {
+    format!("{}/{}/{}", DOCS_RS, name, version)
+}
+
+/// A version with all three numbers (`1.40.0`, `0.1.0-beta.2`), whose pre-release and build
+/// identifiers are limited to `[0-9A-Za-z-]` so it is safe in a file name
+fn is_exact(version: &str) -> bool {
+    let (release, suffix) = match version.split_once(['-', '+']) {
+        Some((release, suffix)) => (release, Some(suffix)),
+        None => (version, None),
+    };
+    let numbers: Vec<&str> = release.split('.').collect();
+    numbers.len() == 3
+        && numbers.iter().all(|n| is_number(n))
+        && suffix.is_none_or(|suffix| {
+            suffix.split(['.', '+']).all(|identifier| {
+                !identifier.is_empty()
+                    && identifier
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+        })
+}
+
+/// An exact version, or a prefix of one (`1`, `v1.40`)
+fn is_requestable(version: &str) -> bool {
+    is_exact(version.trim_start_matches('v'))
+        || version.trim_start_matches('v').split('.').all(is_number)
+}
+
+fn is_number(n: &str) -> bool {
+    !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Numbers of a release, for finding the newest
+fn release_key(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or(version)
+        .split('.')
+        .map(|n| n.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Crate names as crates.io compares them
+fn crate_key(name: &str) -> String {
+    name.to_ascii_lowercase().replace('_', "-")
+}
+
+fn same_crate(a: &str, b: &str) -> bool {
+    crate_key(a) == crate_key(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed rustdoc JSON of a crate with a struct and its constructor
+    const JSON: &str = r##"{
+        "format_version": 57,
+        "index": {
+            "1": {"name": "Widget", "docs": "A widget.", "attrs": [], "inner": {"struct": {"kind": "unit"}}},
+            "2": {"inner": {"impl": {"trait": null,
+                "for": {"resolved_path": {"path": "Widget", "id": 1, "args": null}},
+                "items": [3], "is_synthetic": false, "is_negative": false, "blanket_impl": null}}},
+            "3": {"name": "new", "docs": "Make a widget.", "attrs": [], "inner": {"function": {
+                "sig": {"inputs": [["size", {"primitive": "usize"}]], "output": {"generic": "Self"}, "is_c_variadic": false},
+                "generics": {"params": [], "where_predicates": []},
+                "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"}, "has_body": true}}}
+        },
+        "paths": {
+            "1": {"crate_id": 0, "path": ["demo_widgets", "Widget"], "kind": "struct"}
+        }
+    }"##;
+
+    fn krate(versions: &[(&str, bool)]) -> CrateResponse {
+        CrateResponse {
+            krate: CrateInfo {
+                name: "demo".to_string(),
+                max_stable_version: Some("1.10.0".to_string()),
+                max_version: "2.0.0-rc.1".to_string(),
+            },
+            versions: versions
+                .iter()
+                .map(|(num, yanked)| VersionInfo {
+                    num: num.to_string(),
+                    yanked: *yanked,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_pick_version() {
+        let krate = krate(&[
+            ("2.0.0-rc.1", false),
+            ("1.10.0", false),
+            ("1.9.3", false),
+            ("1.9.4", true),
+            ("0.3.1", false),
+        ]);
+        let pick = |requested| pick_version(&krate, requested);
+        assert_eq!(pick(None).as_deref(), Some("1.10.0"));
+        assert_eq!(pick(Some("1")).as_deref(), Some("1.10.0"));
+        assert_eq!(pick(Some("1.9")).as_deref(), Some("1.9.3"));
+        assert_eq!(pick(Some("1.9.4")).as_deref(), Some("1.9.4"));
+        assert_eq!(pick(Some("v0.3.1")).as_deref(), Some("0.3.1"));
+        assert_eq!(pick(Some("2")), None);
+        assert_eq!(pick(Some("2.0.0-rc.1")).as_deref(), Some("2.0.0-rc.1"));
+
+        assert!(is_exact("1.40.0") && is_exact("0.1.0-beta.2"));
+        assert!(!is_exact("1.40") && !is_exact("1.x.0"));
+        assert!(is_exact("1.0.0-alpha-1+build.5"));
+        assert!(!is_exact("1.0.0-/../../../x") && !is_exact("1.0.0-a..b"));
+        assert!(!is_exact("1.0.0+a\\b") && !is_exact("1.0.0-"));
+        assert!(is_requestable("v1.40") && is_requestable("2.0.0-rc.1"));
+        assert!(!is_requestable("1.") && !is_requestable("../1"));
+    }
+
+    #[test]
+    fn test_load_downloaded_release() {
+        let data_dir = std::env::temp_dir().join(format!("crate-docs-{}", std::process::id()));
+        let dir = data_dir.join(CRATES_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        let compressed = zstd::encode_all(JSON.as_bytes(), 0).unwrap();
+        std::fs::write(dir.join("demo-widgets-0.1.0.json.zst"), compressed).unwrap();
+
+        // The exact version was downloaded, so nothing is fetched
+        let docs = CrateDocs::new(&data_dir);
+        let api = docs.load("demo_widgets", Some("0.1.0")).unwrap();
+        assert_eq!(api.docs_url(), "https://docs.rs/demo_widgets/0.1.0");
+        let new = api.items("demo_widgets::Widget::new");
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].signature, "pub fn new(size: usize) -> Self");
+        assert_eq!(
+            new[0].doc_url.as_deref(),
+            Some("https://docs.rs/demo_widgets/0.1.0/demo_widgets/struct.Widget.html#method.new")
+        );
+        assert_eq!(api.items("Widget")[0].docs, "A widget.");
+
+        // Spelled differently, the same release is served from memory
+        let again = docs.load("Demo-Widgets", Some("0.1.0")).unwrap();
+        assert!(Arc::ptr_eq(&api, &again));
+        assert!(docs.load("demo widgets", None).is_err());
+
+        // A version that would step out of the crates directory is rejected before any lookup
+        let error = docs
+            .load("demo_widgets", Some("1.0.0-/../../../x"))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("is not a version"));
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
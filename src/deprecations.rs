@@ -0,0 +1,389 @@
+//! Deprecated and superseded Rust items, and what replaces them.
+//!
+//! A built-in table covers well-known language and ecosystem changes (`try!` to `?`,
+//! `mem::uninitialized` to `MaybeUninit`). When a rustdoc JSON file for the standard library
+//! is available, every `#[deprecated]` std item is added from it.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// A deprecated or superseded item
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    /// Canonical path, e.g. `std::mem::uninitialized` or `try!`
+    pub item: String,
+    /// Other spellings that should find this entry
+    pub aliases: Vec<String>,
+    /// Version the item was deprecated in, if known
+    pub since: Option<String>,
+    /// What to use instead
+    pub replacement: Option<String>,
+    /// Explanation or the deprecation note from the source
+    pub note: String,
+    /// Search query for documentation that explains the replacement
+    pub query: String,
+    /// Where the entry came from: "builtin" or "rustdoc"
+    pub origin: &'static str,
+}
+
+/// (item, aliases, since, replacement, note, query)
+type BuiltinEntry = (
+    &'static str,
+    &'static [&'static str],
+    Option<&'static str>,
+    &'static str,
+    &'static str,
+    &'static str,
+);
+
+const BUILTIN_DEPRECATIONS: &[BuiltinEntry] = &[
+    (
+        "try!",
+        &["try"],
+        Some("1.39.0"),
+        "the `?` operator",
+        "`try!` is deprecated and `try` is a reserved keyword since the 2018 edition; `?` propagates errors the same way.",
+        "question mark operator propagating errors",
+    ),
+    (
+        "std::mem::uninitialized",
+        &["uninitialized"],
+        Some("1.39.0"),
+        "std::mem::MaybeUninit",
+        "`mem::uninitialized` is undefined behavior for almost every type; `MaybeUninit<T>` makes uninitialized memory explicit.",
+        "MaybeUninit uninitialized memory",
+    ),
+    (
+        "std::sync::ONCE_INIT",
+        &["once_init"],
+        Some("1.38.0"),
+        "std::sync::Once::new()",
+        "`Once::new` is a const fn.",
+        "Once initialization",
+    ),
+    (
+        "std::sync::atomic::AtomicUsize::compare_and_swap",
+        &["compare_and_swap"],
+        Some("1.50.0"),
+        "compare_exchange or compare_exchange_weak",
+        "`compare_exchange` takes separate success and failure orderings.",
+        "atomics compare exchange memory ordering",
+    ),
+    (
+        "std::sync::atomic::spin_loop_hint",
+        &["spin_loop_hint"],
+        Some("1.51.0"),
+        "std::hint::spin_loop",
+        "Moved to `std::hint`.",
+        "spin loop hint",
+    ),
+    (
+        "std::error::Error::description",
+        &["description"],
+        Some("1.42.0"),
+        "the Display implementation (`to_string()`)",
+        "Error messages come from `Display`.",
+        "implementing the Error trait Display",
+    ),
+    (
+        "std::error::Error::cause",
+        &["cause"],
+        Some("1.33.0"),
+        "std::error::Error::source",
+        "`source` returns `&(dyn Error + 'static)`, which supports downcasting.",
+        "Error trait source chain",
+    ),
+    (
+        "str::trim_left",
+        &[
+            "trim_left",
+            "trim_right",
+            "trim_left_matches",
+            "trim_right_matches",
+        ],
+        Some("1.33.0"),
+        "trim_start / trim_end (and the *_matches variants)",
+        "Renamed so the names make sense for right-to-left text.",
+        "string trimming",
+    ),
+    (
+        "std::thread::sleep_ms",
+        &["sleep_ms"],
+        Some("1.6.0"),
+        "std::thread::sleep(Duration::from_millis(ms))",
+        "Durations replaced raw millisecond integers.",
+        "thread sleep Duration",
+    ),
+    (
+        "std::u32::MAX",
+        &[
+            "std::u8::max",
+            "std::u16::max",
+            "std::u64::max",
+            "std::i32::max",
+            "std::usize::max",
+            "std::f64::epsilon",
+        ],
+        None,
+        "associated constants such as u32::MAX and f64::EPSILON",
+        "The primitive type modules are deprecated in favor of associated constants.",
+        "integer types associated constants MAX",
+    ),
+    (
+        "std::ascii::AsciiExt",
+        &["asciiext"],
+        Some("1.26.0"),
+        "inherent methods such as to_ascii_uppercase",
+        "The methods are inherent on `u8`, `char`, `str`, and `[u8]`.",
+        "ascii methods",
+    ),
+    (
+        "bare trait objects",
+        &["box<trait>", "&trait"],
+        None,
+        "dyn Trait",
+        "Trait objects without `dyn` warn in 2018 and are an error in the 2021 edition.",
+        "trait objects dyn",
+    ),
+    (
+        "... range patterns",
+        &["...", "ellipsis range"],
+        None,
+        "..= (inclusive range patterns)",
+        "`...` in patterns is an error in the 2021 edition.",
+        "range patterns inclusive",
+    ),
+    (
+        "lazy_static",
+        &["lazy_static!"],
+        None,
+        "std::sync::LazyLock (Rust 1.80+)",
+        "Lazily initialized statics are available in std; the crate is superseded but not deprecated.",
+        "lazy static initialization",
+    ),
+    (
+        "once_cell",
+        &["once_cell::sync::lazy", "once_cell::sync::oncecell"],
+        None,
+        "std::sync::OnceLock and std::sync::LazyLock",
+        "`OnceLock` (1.70) and `LazyLock` (1.80) were stabilized from once_cell.",
+        "OnceLock lazy initialization",
+    ),
+    (
+        "static mut references",
+        &["static mut", "&static_mut"],
+        None,
+        "atomics, Mutex/OnceLock, or &raw const / &raw mut",
+        "References to `static mut` are denied in the 2024 edition.",
+        "static mut references",
+    ),
+];
+
+/// Lookup table of deprecated items
+#[derive(Debug)]
+pub struct Deprecations {
+    entries: Vec<Deprecation>,
+}
+
+impl Deprecations {
+    /// Built-in entries only
+    pub fn builtin() -> Self {
+        let entries = BUILTIN_DEPRECATIONS
+            .iter()
+            .map(
+                |&(item, aliases, since, replacement, note, query)| Deprecation {
+                    item: item.to_string(),
+                    aliases: aliases.iter().map(|a| a.to_string()).collect(),
+                    since: since.map(str::to_string),
+                    replacement: Some(replacement.to_string()),
+                    note: note.to_string(),
+                    query: query.to_string(),
+                    origin: "builtin",
+                },
+            )
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Built-in entries plus std deprecations from rustdoc JSON, when available.
+    ///
+    /// The JSON is read from `RUST_MCP_STD_JSON`, or `std.json` in the data directory
+    /// (generate it with `rustup component add rust-docs-json` on nightly and copy
+    /// `share/doc/rust/json/std.json` from the sysroot). Errors are logged and ignored.
+    pub fn load(data_dir: &Path) -> Self {
+        let mut deprecations = Self::builtin();
+
+        let path = std::env::var("RUST_MCP_STD_JSON")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| data_dir.join("std.json"));
+        if !path.exists() {
+            return deprecations;
+        }
+
+        match std::fs::read_to_string(&path)
+            .map_err(Error::from)
+            .and_then(|json| parse_rustdoc_json(&json))
+        {
+            Ok(entries) => {
+                tracing::info!("Loaded {} std deprecations from {:?}", entries.len(), path);
+                deprecations.entries.extend(entries);
+            }
+            Err(e) => tracing::warn!("Failed to load rustdoc JSON {:?}: {}", path, e),
+        }
+
+        deprecations
+    }
+
+    /// Find entries for an item, most specific match first
+    pub fn lookup(&self, item: &str) -> Vec<&Deprecation> {
+        let wanted = normalize(item);
+        if wanted.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(u8, &Deprecation)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let path = normalize(&entry.item);
+                let rank = if path == wanted {
+                    0
+                } else if entry.aliases.iter().any(|a| normalize(a) == wanted) {
+                    1
+                } else if path.ends_with(&format!("::{}", wanted)) {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, entry))
+            })
+            .collect();
+
+        matches.sort_by_key(|(rank, _)| *rank);
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+/// Lowercase and strip crate prefixes and call syntax: `std::mem::uninitialized()` -> `mem::uninitialized`
+fn normalize(item: &str) -> String {
+    let item = item.trim().trim_matches('`').to_lowercase();
+    let item = item.trim_end_matches("()");
+    let item = item
+        .strip_prefix("std::")
+        .or_else(|| item.strip_prefix("core::"))
+        .or_else(|| item.strip_prefix("alloc::"))
+        .unwrap_or(item);
+    item.to_string()
+}
+
+/// Extract deprecated items from a rustdoc JSON crate file
+fn parse_rustdoc_json(json: &str) -> Result<Vec<Deprecation>> {
+    let krate: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| Error::Other(format!("Invalid rustdoc JSON: {}", e)))?;
+
+    let index = krate
+        .get("index")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| Error::Other("rustdoc JSON has no index".to_string()))?;
+    let paths = krate.get("paths").and_then(|v| v.as_object());
+
+    let mut entries = Vec::new();
+    for (id, item) in index {
+        let Some(deprecation) = item.get("deprecation").filter(|d| !d.is_null()) else {
+            continue;
+        };
+
+        // Items with a public path (functions, types) use it; members fall back to their name
+        let path = paths
+            .and_then(|p| p.get(id))
+            .and_then(|summary| summary.get("path"))
+            .and_then(|p| p.as_array())
+            .map(|segments| {
+                segments
+                    .iter()
+                    .filter_map(|s| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join("::")
+            });
+        let Some(item_path) = path.or_else(|| item.get("name")?.as_str().map(str::to_string))
+        else {
+            continue;
+        };
+
+        let note = deprecation
+            .get("note")
+            .and_then(|n| n.as_str())
+            .unwrap_or("Deprecated")
+            .to_string();
+        let name = item_path
+            .rsplit("::")
+            .next()
+            .unwrap_or(&item_path)
+            .to_string();
+
+        entries.push(Deprecation {
+            aliases: vec![name.clone()],
+            since: deprecation
+                .get("since")
+                .and_then(|s| s.as_str())
+                .map(str::to_string),
+            replacement: None,
+            query: format!("{} {}", name, note),
+            note,
+            item: item_path,
+            origin: "rustdoc",
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_lookup() {
+        let deprecations = Deprecations::builtin();
+
+        let found = deprecations.lookup("try!");
+        assert_eq!(found[0].item, "try!");
+        assert_eq!(found[0].replacement.as_deref(), Some("the `?` operator"));
+
+        assert_eq!(
+            deprecations.lookup("std::mem::uninitialized()")[0].item,
+            "std::mem::uninitialized"
+        );
+        assert_eq!(
+            deprecations.lookup("mem::uninitialized")[0].item,
+            "std::mem::uninitialized"
+        );
+        assert!(deprecations.lookup("Vec::push").is_empty());
+    }
+
+    #[test]
+    fn test_parse_rustdoc_json() -> Result<()> {
+        let json = r#"{
+            "index": {
+                "1": {"name": "uninitialized", "deprecation": {"since": "1.39.0", "note": "use `mem::MaybeUninit` instead"}},
+                "2": {"name": "zeroed", "deprecation": null},
+                "3": {"name": "description", "deprecation": {"since": "1.42.0", "note": "use the Display impl or to_string()"}}
+            },
+            "paths": {
+                "1": {"path": ["std", "mem", "uninitialized"], "kind": "function"},
+                "2": {"path": ["std", "mem", "zeroed"], "kind": "function"}
+            }
+        }"#;
+
+        let mut entries = parse_rustdoc_json(json)?;
+        entries.sort_by(|a, b| a.item.cmp(&b.item));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].item, "description");
+        assert_eq!(entries[1].item, "std::mem::uninitialized");
+        assert_eq!(entries[1].since.as_deref(), Some("1.39.0"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+//! Parsing of raw `cargo build` / `rustc` output into distinct diagnostics.
+
+/// Phrases that identify well-known classes of compiler errors
+const KEY_PHRASES: &[&str] = &[
+    "cannot borrow",
+    "as mutable more than once",
+    "because it is also borrowed",
+    "does not live long enough",
+    "borrowed value",
+    "use of moved value",
+    "value moved here",
+    "value used here after move",
+    "cannot move out of",
+    "cannot assign twice to immutable variable",
+    "lifetime may not live long enough",
+    "missing lifetime specifier",
+    "returns a value referencing data owned by the current function",
+    "mismatched types",
+    "the trait bound",
+    "is not satisfied",
+    "cannot be sent between threads safely",
+    "cannot be shared between threads safely",
+    "doesn't implement",
+    "no method named",
+    "cannot find",
+    "unresolved import",
+    "type annotations needed",
+    "unused variable",
+    "unused import",
+    "dead code",
+    "never used",
+];
+
+/// Messages that summarize a build rather than describe a problem
+const SUMMARY_PREFIXES: &[&str] = &[
+    "aborting due to",
+    "could not compile",
+    "build failed",
+    "for more information about",
+];
+
+/// Maximum number of distinct diagnostics extracted from one output
+pub const MAX_DIAGNOSTICS: usize = 10;
+
+/// A single diagnostic extracted from compiler output
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// "error" or "warning"
+    pub level: String,
+    /// Error code such as `E0499`, if present
+    pub code: Option<String>,
+    /// The headline message
+    pub message: String,
+    /// First `file:line:col` location, if present
+    pub span: Option<String>,
+    /// Known phrases found in the message and its notes
+    pub key_phrases: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Build a documentation search query for this diagnostic.
+    ///
+    /// Identifiers in backticks are dropped since they are specific to the user's code.
+    pub fn search_query(&self) -> String {
+        let mut query = strip_backticked(&self.message);
+        for phrase in &self.key_phrases {
+            if !query.contains(phrase.as_str()) {
+                query.push(' ');
+                query.push_str(phrase);
+            }
+        }
+        query
+    }
+
+    /// Key used to deduplicate repeated diagnostics
+    fn dedup_key(&self) -> (Option<String>, String) {
+        (self.code.clone(), strip_backticked(&self.message))
+    }
+}
+
+/// Extract the distinct errors and warnings from raw compiler output
+pub fn parse_compiler_output(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut current: Option<Diagnostic> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(diagnostic) = parse_headline(line) {
+            if let Some(done) = current.take() {
+                push_distinct(&mut diagnostics, done);
+            }
+            current = Some(diagnostic);
+            continue;
+        }
+
+        let Some(diagnostic) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            if diagnostic.span.is_none() {
+                diagnostic.span = Some(location.trim().to_string());
+            }
+        } else {
+            // Labels and notes ("value moved here", "borrowed value does not live long enough")
+            add_key_phrases(diagnostic, trimmed);
+        }
+    }
+
+    if let Some(done) = current.take() {
+        push_distinct(&mut diagnostics, done);
+    }
+
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+    diagnostics
+}
+
+/// Parse an `error[E0499]: message` or `warning: message` headline
+fn parse_headline(line: &str) -> Option<Diagnostic> {
+    let (level, rest) = if let Some(rest) = line.strip_prefix("error") {
+        ("error", rest)
+    } else if let Some(rest) = line.strip_prefix("warning") {
+        ("warning", rest)
+    } else {
+        return None;
+    };
+
+    let (code, rest) = match rest.strip_prefix('[') {
+        Some(bracketed) => {
+            let (code, rest) = bracketed.split_once(']')?;
+            (Some(code.to_string()), rest)
+        }
+        None => (None, rest),
+    };
+
+    let message = rest.strip_prefix(':')?.trim().to_string();
+    let lower = message.to_lowercase();
+    if message.is_empty() || SUMMARY_PREFIXES.iter().any(|p| lower.starts_with(p)) {
+        return None;
+    }
+    // "warning: `crate` (bin "x") generated 2 warnings"
+    if level == "warning" && lower.contains("generated") && lower.contains("warning") {
+        return None;
+    }
+
+    let mut diagnostic = Diagnostic {
+        level: level.to_string(),
+        code,
+        message,
+        span: None,
+        key_phrases: Vec::new(),
+    };
+    let headline = diagnostic.message.clone();
+    add_key_phrases(&mut diagnostic, &headline);
+
+    Some(diagnostic)
+}
+
+fn add_key_phrases(diagnostic: &mut Diagnostic, text: &str) {
+    let lower = text.to_lowercase();
+    for phrase in KEY_PHRASES {
+        if lower.contains(phrase) && !diagnostic.key_phrases.iter().any(|p| p == phrase) {
+            diagnostic.key_phrases.push(phrase.to_string());
+        }
+    }
+}
+
+fn push_distinct(diagnostics: &mut Vec<Diagnostic>, diagnostic: Diagnostic) {
+    let key = diagnostic.dedup_key();
+    if !diagnostics.iter().any(|d| d.dedup_key() == key) {
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Remove backticked spans (user identifiers and types) and collapse whitespace
+fn strip_backticked(message: &str) -> String {
+    let mut result = String::new();
+    let mut in_ticks = false;
+
+    for c in message.chars() {
+        match c {
+            '`' => in_ticks = !in_ticks,
+            _ if !in_ticks => result.push(c),
+            _ => {}
+        }
+    }
+
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"   Compiling demo v0.1.0 (/tmp/demo)
+error[E0499]: cannot borrow `v` as mutable more than once at a time
+ --> src/main.rs:4:14
+  |
+3 |     let a = &mut v;
+  |             ------ first mutable borrow occurs here
+4 |     let b = &mut v;
+  |             ^^^^^^ second mutable borrow occurs here
+
+error[E0597]: `x` does not live long enough
+  --> src/main.rs:9:13
+   |
+9  |         r = &x;
+   |             ^^ borrowed value does not live long enough
+
+error[E0499]: cannot borrow `w` as mutable more than once at a time
+  --> src/main.rs:12:14
+
+warning: unused variable: `y`
+  --> src/main.rs:15:9
+
+error: aborting due to 3 previous errors; 1 warning emitted
+
+Some errors have detailed explanations: E0499, E0597.
+For more information about an error, try `rustc --explain E0499`.
+error: could not compile `demo` (bin "demo") due to 3 previous errors; 1 warning emitted
+"#;
+
+    #[test]
+    fn test_parse_distinct_diagnostics() {
+        let diagnostics = parse_compiler_output(OUTPUT);
+        assert_eq!(diagnostics.len(), 3);
+
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0499"));
+        assert_eq!(diagnostics[0].span.as_deref(), Some("src/main.rs:4:14"));
+        assert!(
+            diagnostics[0]
+                .key_phrases
+                .contains(&"as mutable more than once".to_string())
+        );
+
+        assert_eq!(diagnostics[1].code.as_deref(), Some("E0597"));
+        assert!(
+            diagnostics[1]
+                .key_phrases
+                .contains(&"borrowed value".to_string())
+        );
+
+        assert_eq!(diagnostics[2].level, "warning");
+        assert_eq!(diagnostics[2].code, None);
+    }
+
+    #[test]
+    fn test_search_query_drops_identifiers() {
+        let diagnostics = parse_compiler_output(OUTPUT);
+        let query = diagnostics[1].search_query();
+        assert!(!query.contains('`'));
+        assert!(query.starts_with("does not live long enough"));
+        assert!(query.contains("borrowed value"));
+    }
+}
@@ -0,0 +1,384 @@
+//! Curated edition migration data, following the structure of the Edition Guide.
+
+use crate::error::{Error, Result};
+
+/// Editions in release order
+pub const EDITIONS: &[u16] = &[2015, 2018, 2021, 2024];
+
+/// How much of a change `cargo fix --edition` handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fix {
+    /// Fully rewritten by `cargo fix --edition`
+    Automatic,
+    /// `cargo fix` handles common cases; some code needs review
+    Partial,
+    /// Requires manual changes
+    Manual,
+}
+
+impl Fix {
+    /// Name used in tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fix::Automatic => "automatic",
+            Fix::Partial => "partial",
+            Fix::Manual => "manual",
+        }
+    }
+}
+
+/// A change introduced by an edition
+#[derive(Debug)]
+pub struct EditionChange {
+    /// Edition that introduced the change
+    pub edition: u16,
+    pub title: &'static str,
+    /// Edition Guide page, relative to its `src` directory
+    pub path: &'static str,
+    /// Lowercase constructs affected by the change
+    pub keywords: &'static [&'static str],
+    pub fix: Fix,
+    /// What to do when migrating
+    pub note: &'static str,
+}
+
+/// Built-in list of edition changes
+pub const EDITION_CHANGES: &[EditionChange] = &[
+    EditionChange {
+        edition: 2018,
+        title: "Path and module system changes",
+        path: "rust-2018/path-changes.md",
+        keywords: &["extern crate", "mod.rs", "use paths", "crate::", "modules"],
+        fix: Fix::Partial,
+        note: "cargo fix prefixes local paths with `crate::`. `extern crate` is no longer needed and can be removed by hand (except for `extern crate alloc`/sysroot crates); `mod.rs` files keep working but `foo.rs` + `foo/` is now allowed.",
+    },
+    EditionChange {
+        edition: 2018,
+        title: "New keywords: dyn, async, await, try",
+        path: "rust-2018/new-keywords.md",
+        keywords: &[
+            "dyn",
+            "async",
+            "await",
+            "try",
+            "trait objects",
+            "bare trait",
+        ],
+        fix: Fix::Automatic,
+        note: "Identifiers named `async`, `await`, `dyn`, or `try` are renamed to raw identifiers (`r#async`).",
+    },
+    EditionChange {
+        edition: 2018,
+        title: "Anonymous trait function parameters deprecated",
+        path: "rust-2018/trait-fn-parameters.md",
+        keywords: &["trait", "anonymous parameters"],
+        fix: Fix::Automatic,
+        note: "`fn f(u8);` in traits becomes `fn f(_: u8);`.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Additions to the prelude",
+        path: "rust-2021/prelude.md",
+        keywords: &[
+            "tryfrom",
+            "tryinto",
+            "fromiterator",
+            "try_from",
+            "try_into",
+            "prelude",
+        ],
+        fix: Fix::Automatic,
+        note: "Calls that become ambiguous with `TryFrom`, `TryInto`, or `FromIterator` are rewritten to fully qualified syntax.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Default Cargo feature resolver",
+        path: "rust-2021/default-cargo-resolver.md",
+        keywords: &["features", "resolver", "cargo.toml", "workspace"],
+        fix: Fix::Manual,
+        note: "Resolver version 2 is implied. Workspaces must set `resolver = \"2\"` in the root manifest; check that features enabled only for build or dev dependencies are still enabled where needed.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "IntoIterator for arrays",
+        path: "rust-2021/IntoIterator-for-arrays.md",
+        keywords: &["into_iter", "arrays", "array"],
+        fix: Fix::Automatic,
+        note: "`array.into_iter()` now yields values; cargo fix rewrites existing calls to `.iter()`.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Disjoint capture in closures",
+        path: "rust-2021/disjoint-capture-in-closures.md",
+        keywords: &["closure", "closures", "capture", "drop order"],
+        fix: Fix::Automatic,
+        note: "Closures capture individual fields; cargo fix inserts `let _ = &x;` where drop order would change.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Panic macro consistency",
+        path: "rust-2021/panic-macro-consistency.md",
+        keywords: &["panic!", "panic", "format string"],
+        fix: Fix::Automatic,
+        note: "`panic!(\"{}\")` with a single argument is now a format string; cargo fix adds `\"{}\"` where needed.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Reserved syntax (prefixed identifiers and literals)",
+        path: "rust-2021/reserved-syntax.md",
+        keywords: &["macro", "macros", "prefix", "literals"],
+        fix: Fix::Automatic,
+        note: "`ident#`, `ident\"...\"`, and `ident'x'` are reserved; cargo fix inserts whitespace in macro invocations.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Warnings promoted to errors",
+        path: "rust-2021/warnings-promoted-to-error.md",
+        keywords: &[
+            "bare trait",
+            "trait objects",
+            "dyn",
+            "...",
+            "ellipsis",
+            "range patterns",
+        ],
+        fix: Fix::Automatic,
+        note: "Bare trait objects (`Box<Trait>`) and `...` range patterns are errors; cargo fix adds `dyn` and `..=`.",
+    },
+    EditionChange {
+        edition: 2021,
+        title: "Or patterns in macro_rules",
+        path: "rust-2021/or-patterns-macro-rules.md",
+        keywords: &["macro_rules", "macro", "macros", ":pat", "pat fragment"],
+        fix: Fix::Automatic,
+        note: "`$x:pat` now matches `a | b`; cargo fix rewrites affected fragments to `$x:pat_param`.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "RPIT lifetime capture rules",
+        path: "rust-2024/rpit-lifetime-capture.md",
+        keywords: &[
+            "impl trait",
+            "-> impl",
+            "return position impl trait",
+            "lifetimes",
+            "use<",
+        ],
+        fix: Fix::Partial,
+        note: "`impl Trait` in return position captures all in-scope lifetimes; cargo fix adds `use<..>` bounds where the old behaviour is needed.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "if let temporary scope",
+        path: "rust-2024/temporary-if-let-scope.md",
+        keywords: &["if let", "mutex", "lock", "refcell", "temporaries"],
+        fix: Fix::Partial,
+        note: "Temporaries in an `if let` scrutinee are dropped before `else`; cargo fix rewrites some cases to `match`, review code relying on lock guards.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Tail expression temporary scope",
+        path: "rust-2024/temporary-tail-expr-scope.md",
+        keywords: &["tail expression", "temporaries", "refcell", "borrow"],
+        fix: Fix::Manual,
+        note: "Temporaries in a block's tail expression are dropped before locals; lints flag affected code.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Unsafe extern blocks",
+        path: "rust-2024/unsafe-extern.md",
+        keywords: &["extern blocks", "ffi", "extern \"c\"", "extern block"],
+        fix: Fix::Automatic,
+        note: "`extern` blocks must be written `unsafe extern`; items may be marked `safe`.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Unsafe attributes",
+        path: "rust-2024/unsafe-attributes.md",
+        keywords: &["no_mangle", "export_name", "link_section", "attributes"],
+        fix: Fix::Automatic,
+        note: "`#[no_mangle]`, `#[export_name]`, and `#[link_section]` become `#[unsafe(...)]`.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "unsafe_op_in_unsafe_fn warning",
+        path: "rust-2024/unsafe-op-in-unsafe-fn.md",
+        keywords: &["unsafe fn", "unsafe"],
+        fix: Fix::Automatic,
+        note: "Unsafe operations in `unsafe fn` need an `unsafe {}` block; cargo fix wraps bodies.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Disallow references to static mut",
+        path: "rust-2024/static-mut-references.md",
+        keywords: &["static mut", "statics", "global"],
+        fix: Fix::Manual,
+        note: "`&STATIC_MUT` is denied; use `&raw const`/`&raw mut`, atomics, or interior mutability.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Newly unsafe functions",
+        path: "rust-2024/newly-unsafe-functions.md",
+        keywords: &["set_var", "remove_var", "env", "before_exec"],
+        fix: Fix::Automatic,
+        note: "`std::env::set_var` and `remove_var` are unsafe; cargo fix wraps calls in `unsafe {}`, audit them for thread safety.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Never type fallback change",
+        path: "rust-2024/never-type-fallback.md",
+        keywords: &["never type", "fallback"],
+        fix: Fix::Partial,
+        note: "`!` now falls back to `!` instead of `()`; add type annotations where lints warn.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Macro fragment specifiers",
+        path: "rust-2024/macro-fragment-specifiers.md",
+        keywords: &["macro_rules", "macro", "macros", ":expr", "expr fragment"],
+        fix: Fix::Automatic,
+        note: "`$e:expr` also matches `const {}` and `_`; cargo fix rewrites to `$e:expr_2021` where behaviour would change.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "gen keyword",
+        path: "rust-2024/gen-keyword.md",
+        keywords: &["gen", "generators"],
+        fix: Fix::Automatic,
+        note: "`gen` is reserved; identifiers are renamed to `r#gen`.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Additions to the prelude",
+        path: "rust-2024/prelude.md",
+        keywords: &["future", "intofuture", "prelude", "async"],
+        fix: Fix::Automatic,
+        note: "`Future` and `IntoFuture` are in the prelude; ambiguous trait method calls are rewritten.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Rust-version aware Cargo resolver",
+        path: "rust-2024/cargo-resolver.md",
+        keywords: &[
+            "rust-version",
+            "msrv",
+            "resolver",
+            "cargo.toml",
+            "dependencies",
+        ],
+        fix: Fix::Manual,
+        note: "Resolver version 3 prefers dependency versions compatible with `package.rust-version`.",
+    },
+    EditionChange {
+        edition: 2024,
+        title: "Rustfmt style edition",
+        path: "rust-2024/rustfmt-style-edition.md",
+        keywords: &["rustfmt", "formatting", "cargo fmt"],
+        fix: Fix::Automatic,
+        note: "Run `cargo fmt` after migrating to apply the 2024 style edition.",
+    },
+];
+
+/// Parse an edition such as "2021" or "rust 2021"
+pub fn parse_edition(value: &str) -> Result<u16> {
+    let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+    digits
+        .parse::<u16>()
+        .ok()
+        .filter(|edition| EDITIONS.contains(edition))
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "Unknown edition '{}'. Supported editions: 2015, 2018, 2021, 2024",
+                value
+            ))
+        })
+}
+
+/// Consecutive `(from, to)` edition steps between two editions
+pub fn transitions(from: u16, to: u16) -> Result<Vec<(u16, u16)>> {
+    if from >= to {
+        return Err(Error::Other(format!(
+            "Target edition {} must be newer than source edition {}",
+            to, from
+        )));
+    }
+
+    Ok(EDITIONS
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .filter(|(a, b)| *a >= from && *b <= to)
+        .collect())
+}
+
+/// Commands to migrate one edition step, in order
+pub fn migration_commands(from: u16, to: u16) -> Vec<String> {
+    let mut commands = vec![
+        format!(
+            "cargo fix --edition --all-targets --all-features  # while Cargo.toml still says edition = \"{}\"",
+            from
+        ),
+        format!(
+            "set edition = \"{}\" in Cargo.toml (every package in a workspace)",
+            to
+        ),
+        "cargo build --all-targets && cargo test".to_string(),
+    ];
+    if to >= 2024 {
+        commands.push("cargo fmt  # apply the new style edition".to_string());
+    }
+    commands
+}
+
+/// Changes introduced by an edition, those affecting the given constructs first
+pub fn changes_for(edition: u16, constructs: &str) -> Vec<(&'static EditionChange, bool)> {
+    let lower = constructs.to_lowercase();
+    let mut changes: Vec<(&EditionChange, bool)> = EDITION_CHANGES
+        .iter()
+        .filter(|change| change.edition == edition)
+        .map(|change| {
+            let affected = change.keywords.iter().any(|k| mentions(&lower, k));
+            (change, affected)
+        })
+        .collect();
+
+    changes.sort_by_key(|(_, affected)| !affected);
+    changes
+}
+
+/// Check for a keyword that is not part of a longer word ("gen" does not match "generic")
+fn mentions(text: &str, keyword: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    text.match_indices(keyword).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + keyword.len()..].chars().next();
+        !is_word(before) && !is_word(after)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        assert_eq!(parse_edition("Rust 2018").unwrap(), 2018);
+        assert!(parse_edition("2019").is_err());
+        assert_eq!(
+            transitions(2015, 2021).unwrap(),
+            vec![(2015, 2018), (2018, 2021)]
+        );
+        assert!(transitions(2021, 2018).is_err());
+    }
+
+    #[test]
+    fn test_changes_for_constructs() {
+        let changes = changes_for(2018, "I use extern crate and mod.rs files");
+        assert_eq!(changes[0].0.path, "rust-2018/path-changes.md");
+        assert!(changes[0].1);
+        assert!(changes.iter().skip(1).all(|(_, affected)| !affected));
+
+        let changes = changes_for(2024, "generic functions");
+        assert!(changes.iter().all(|(_, affected)| !affected));
+    }
+}
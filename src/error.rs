@@ -1,7 +1,6 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
-#[allow(dead_code)]
 pub enum Error {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
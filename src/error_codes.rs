@@ -0,0 +1,221 @@
+//! rustc error code explanations.
+//!
+//! `rustc --explain E0382` prints one markdown file of `compiler/rustc_error_codes` in
+//! rust-lang/rust, the same text as the online error index. Like the rustc flag pages, the
+//! directory is downloaded instead of cloning the compiler repository. The explanations are
+//! indexed for search, and the `explain_error_code` tool returns one by code.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::parse_report::SourceReport;
+use crate::parsing::{Document, parse_markdown};
+use crate::rustc_flags::download_markdown_directory;
+
+/// Source ID of the error code explanations
+pub const ERROR_CODES_SOURCE: &str = "rustc-error-codes";
+
+/// GitHub API listing of the explanations, one `E0000.md` file per code
+const ERROR_CODES_LISTING: &str = "https://api.github.com/repos/rust-lang/rust/contents/compiler/rustc_error_codes/src/error_codes";
+
+const ERROR_INDEX_URL: &str = "https://doc.rust-lang.org/error_codes";
+
+/// An explanation of a code the compiler no longer emits starts with this note
+const NO_LONGER_EMITTED: &str = "no longer emitted";
+
+/// The explanation of an error code
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorCode {
+    /// The code, e.g. `E0382`
+    pub code: String,
+    /// The explanation's markdown, as `rustc --explain` prints it
+    pub markdown: String,
+}
+
+impl ErrorCode {
+    /// The first paragraph that isn't the "no longer emitted" note, e.g. "A variable was used
+    /// after its contents have been moved elsewhere."
+    pub fn summary(&self) -> String {
+        self.markdown
+            .split("\n\n")
+            .map(str::trim)
+            .find(|paragraph| {
+                !paragraph.is_empty()
+                    && !paragraph.starts_with('#')
+                    && !paragraph.starts_with("```")
+                    && !paragraph.contains(NO_LONGER_EMITTED)
+            })
+            .map(|paragraph| paragraph.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default()
+    }
+
+    /// Whether the compiler no longer emits the code; its explanation is kept for old output
+    pub fn is_retired(&self) -> bool {
+        self.markdown
+            .lines()
+            .take_while(|line| !line.starts_with("```"))
+            .any(|line| line.starts_with('#') && line.contains(NO_LONGER_EMITTED))
+    }
+
+    /// The code's page in the online error index
+    pub fn url(&self) -> String {
+        format!("{}/{}.html", ERROR_INDEX_URL, self.code)
+    }
+
+    /// Path of the indexed document
+    pub fn path(&self) -> String {
+        format!("{}.md", self.code)
+    }
+}
+
+/// The explanations of every error code, by code
+#[derive(Debug, Default)]
+pub struct ErrorCodes {
+    codes: BTreeMap<String, ErrorCode>,
+}
+
+impl ErrorCodes {
+    /// Directory of the downloaded explanations
+    pub fn dir(data_dir: &Path) -> PathBuf {
+        data_dir.join(ERROR_CODES_SOURCE)
+    }
+
+    /// Download the explanations if they are missing, returning how many files were written
+    pub fn download(data_dir: &Path) -> Result<usize> {
+        let dir = Self::dir(data_dir);
+        if dir.exists() {
+            return Ok(0);
+        }
+        tracing::info!("Downloading the rustc error code explanations...");
+        download_markdown_directory(ERROR_CODES_LISTING, &dir)
+    }
+
+    /// Load the downloaded explanations; empty when they are missing
+    pub fn load(data_dir: &Path) -> Self {
+        let dir = Self::dir(data_dir);
+        let mut codes = BTreeMap::new();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+                let code = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(normalize_code);
+                let markdown = std::fs::read_to_string(&path).ok();
+                if let (Some(code), Some(markdown)) = (code, markdown) {
+                    let markdown = markdown.trim().to_string();
+                    codes.insert(code.clone(), ErrorCode { code, markdown });
+                }
+            }
+        }
+
+        if codes.is_empty() {
+            tracing::debug!("No error code explanations at {:?}", dir);
+        } else {
+            tracing::info!("Loaded {} error code explanations", codes.len());
+        }
+        Self { codes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// The explanation of a code written as `E0382`, `e382`, `0382`, or `error[E0382]`
+    pub fn get(&self, code: &str) -> Option<&ErrorCode> {
+        self.codes.get(&normalize_code(code)?)
+    }
+
+    /// The lowest and highest known codes
+    pub fn range(&self) -> Option<(&str, &str)> {
+        let first = self.codes.keys().next()?;
+        let last = self.codes.keys().next_back()?;
+        Some((first, last))
+    }
+
+    /// One document per code, titled with the code, recording the count in `report`
+    pub fn documents(&self, report: &mut SourceReport) -> Vec<Document> {
+        let documents: Vec<Document> = self
+            .codes
+            .values()
+            .map(|code| {
+                let markdown = format!("# {}\n\n{}", code.code, code.markdown);
+                parse_markdown(&markdown, &code.path(), ERROR_CODES_SOURCE)
+            })
+            .collect();
+        report.parsed = documents.len();
+        documents
+    }
+}
+
+/// `E` and four digits, from a code as users write it; `None` when it isn't a code
+pub fn normalize_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    let code = code
+        .strip_prefix("error[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(code);
+    let digits = code.strip_prefix(['E', 'e']).unwrap_or(code);
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("E{:0>4}", digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E0382: &str = "A variable was used after its contents have been moved elsewhere.\n\nErroneous code example:\n\n```compile_fail,E0382\nstruct MyStruct { s: u32 }\n\nfn main() {\n    let mut x = MyStruct{ s: 5u32 };\n    let y = x;\n    x.s = 6;\n    println!(\"{}\", x.s);\n}\n```\n\nSince `MyStruct` is a type that is not marked `Copy`, the data gets moved out\nof `x` when we set `y`.\n";
+
+    const E0001: &str = "#### Note: this error code is no longer emitted by the compiler.\n\nThis error suggests that the expression arm corresponding to the noted pattern\nwill never be reached.\n";
+
+    #[test]
+    fn test_normalize_code() {
+        assert_eq!(normalize_code("E0382").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code("e382").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code(" 0382 ").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code("error[E0499]").as_deref(), Some("E0499"));
+        assert_eq!(normalize_code("E0001").as_deref(), Some("E0001"));
+        assert_eq!(normalize_code("E12345"), None);
+        assert_eq!(normalize_code("borrow"), None);
+        assert_eq!(normalize_code("E-1"), None);
+        assert_eq!(normalize_code("E"), None);
+    }
+
+    #[test]
+    fn test_load_and_document() {
+        let data_dir = std::env::temp_dir().join(format!("error-codes-{}", std::process::id()));
+        let dir = ErrorCodes::dir(&data_dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("E0382.md"), E0382).unwrap();
+        std::fs::write(dir.join("E0001.md"), E0001).unwrap();
+        std::fs::write(dir.join("README.md"), "Not a code.").unwrap();
+        let codes = ErrorCodes::load(&data_dir);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(codes.range(), Some(("E0001", "E0382")));
+        let moved = codes.get("e382").unwrap();
+        assert_eq!(
+            moved.summary(),
+            "A variable was used after its contents have been moved elsewhere."
+        );
+        assert!(!moved.is_retired());
+        assert_eq!(
+            moved.url(),
+            "https://doc.rust-lang.org/error_codes/E0382.html"
+        );
+        let retired = codes.get("E0001").unwrap();
+        assert!(retired.is_retired());
+        assert!(retired.summary().starts_with("This error suggests that"));
+        assert!(codes.get("E0499").is_none());
+
+        let mut report = SourceReport::new(ERROR_CODES_SOURCE);
+        let documents = codes.documents(&mut report);
+        assert_eq!(report.parsed, 2);
+        let document = documents.iter().find(|doc| doc.path == "E0382.md").unwrap();
+        assert_eq!(document.title, "E0382");
+        assert_eq!(document.source, ERROR_CODES_SOURCE);
+        assert_eq!(document.code_blocks.len(), 1);
+    }
+}
@@ -0,0 +1,200 @@
+//! FFI vocabulary used by the `ffi_help` tool.
+//!
+//! FFI questions mix Rust and C terms ("who frees a CString", "panic in a callback"); each topic
+//! expands the query with the terms the documentation uses and pins the sections covering it.
+
+use crate::search::SearchResult;
+use crate::sources::doc_sources;
+
+/// Sources always searched for FFI questions
+const BASE_FFI_SOURCES: &[&str] = &["rustonomicon", "rust-reference", "rust-book"];
+
+/// An FFI topic recognized in queries
+#[derive(Debug)]
+pub struct FfiTopic {
+    /// Stable identifier used in tool output
+    pub id: &'static str,
+    /// Lowercase words or fragments that select this topic
+    pub keywords: &'static [&'static str],
+    /// Terms appended to the search query
+    pub expansion: &'static str,
+    /// `(source, path)` of the sections covering the topic
+    pub targets: &'static [(&'static str, &'static str)],
+}
+
+const NOMICON_FFI: (&str, &str) = ("rustonomicon", "ffi.md");
+
+/// Built-in FFI topics
+pub const FFI_TOPICS: &[FfiTopic] = &[
+    FfiTopic {
+        id: "layout",
+        keywords: &["repr(c)", "repr", "layout", "padding", "alignment"],
+        expansion: "repr(C) type layout representation alignment",
+        targets: &[("rust-reference", "type-layout.md"), NOMICON_FFI],
+    },
+    FfiTopic {
+        id: "strings",
+        keywords: &[
+            "cstring",
+            "cstr",
+            "c_char",
+            "char*",
+            "char *",
+            "null-terminated",
+            "string",
+        ],
+        expansion: "CString CStr c_char nul-terminated strings into_raw from_raw",
+        targets: &[NOMICON_FFI],
+    },
+    FfiTopic {
+        id: "ownership",
+        keywords: &[
+            "free",
+            "ownership",
+            "into_raw",
+            "from_raw",
+            "allocate",
+            "deallocate",
+            "who frees",
+            "leak",
+        ],
+        expansion: "ownership across FFI Box::into_raw Box::from_raw free allocator",
+        targets: &[NOMICON_FFI],
+    },
+    FfiTopic {
+        id: "panics",
+        keywords: &[
+            "panic",
+            "unwind",
+            "unwinding",
+            "catch_unwind",
+            "c-unwind",
+            "abort",
+        ],
+        expansion: "unwinding panics across FFI boundary catch_unwind C-unwind ABI",
+        targets: &[NOMICON_FFI, ("rust-reference", "panic.md")],
+    },
+    FfiTopic {
+        id: "callbacks",
+        keywords: &[
+            "callback",
+            "function pointer",
+            "extern \"c\" fn",
+            "user data",
+            "void*",
+            "void *",
+            "c_void",
+        ],
+        expansion: "callbacks from C code to Rust functions extern \"C\" fn c_void",
+        targets: &[NOMICON_FFI],
+    },
+    FfiTopic {
+        id: "nullability",
+        keywords: &["null", "nullable", "option<&", "nonnull", "option<extern"],
+        expansion: "nullable pointer optimization Option NonNull null pointer",
+        targets: &[NOMICON_FFI, ("rust-reference", "type-layout.md")],
+    },
+    FfiTopic {
+        id: "linking",
+        keywords: &[
+            "link",
+            "linking",
+            "bindgen",
+            "cbindgen",
+            "build.rs",
+            "static library",
+            "dylib",
+            "cdylib",
+            "no_mangle",
+        ],
+        expansion: "linking #[link] external blocks no_mangle cdylib staticlib",
+        targets: &[
+            ("rust-reference", "linkage.md"),
+            ("rust-reference", "external-blocks.md"),
+            NOMICON_FFI,
+        ],
+    },
+    FfiTopic {
+        id: "abi",
+        keywords: &[
+            "abi",
+            "extern \"c\"",
+            "calling convention",
+            "extern block",
+            "variadic",
+        ],
+        expansion: "ABI extern blocks calling convention",
+        targets: &[
+            ("rust-reference", "external-blocks.md"),
+            ("rust-reference", "abi.md"),
+        ],
+    },
+];
+
+/// Sources to search: the built-in ones plus any configured source dedicated to FFI
+pub fn ffi_sources() -> Vec<&'static str> {
+    let configured = doc_sources()
+        .iter()
+        .map(|s| s.id)
+        .filter(|id| id.contains("ffi") && !BASE_FFI_SOURCES.contains(id));
+    BASE_FFI_SOURCES.iter().copied().chain(configured).collect()
+}
+
+/// Topics mentioned in a query
+pub fn detect_topics(query: &str) -> Vec<&'static FfiTopic> {
+    let lower = query.to_lowercase();
+    FFI_TOPICS
+        .iter()
+        .filter(|topic| topic.keywords.iter().any(|k| lower.contains(k)))
+        .collect()
+}
+
+/// Append FFI terms and the expansions of the detected topics to a query
+pub fn expand_query(query: &str, topics: &[&FfiTopic]) -> String {
+    let mut expanded = format!("{} FFI foreign function interface", query);
+    for topic in topics {
+        expanded.push(' ');
+        expanded.push_str(topic.expansion);
+    }
+    expanded
+}
+
+/// Move sections covering the detected topics to the front, keeping relative order
+pub fn apply_boost(topics: &[&FfiTopic], results: &mut [SearchResult]) {
+    let is_target = |result: &SearchResult| {
+        topics.iter().any(|topic| {
+            topic
+                .targets
+                .iter()
+                .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+        })
+    };
+    results.sort_by_key(|result| !is_target(result));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_topics() {
+        let ids =
+            |query: &str| -> Vec<&str> { detect_topics(query).iter().map(|t| t.id).collect() };
+
+        assert_eq!(
+            ids("who frees a CString passed to C?"),
+            vec!["strings", "ownership"]
+        );
+        assert_eq!(
+            ids("can a panic cross a callback"),
+            vec!["panics", "callbacks"]
+        );
+        assert!(ids("hello").is_empty());
+    }
+
+    #[test]
+    fn test_ffi_sources() {
+        let sources = ffi_sources();
+        assert_eq!(&sources[..BASE_FFI_SOURCES.len()], BASE_FFI_SOURCES);
+    }
+}
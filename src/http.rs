@@ -0,0 +1,308 @@
+//! Streamable HTTP transport.
+//!
+//! MCP is served at `/mcp`. Every session gets its own view of the server (its own workspace
+//! roots) on top of the shared indices and embedding model. Only roots inside the configured
+//! `workspace_roots` are indexed, so clients can't have the server read arbitrary host paths.
+//! When credentials are configured, requests without a valid bearer token are rejected before
+//! they reach the MCP layer.
+//!
+//! The listener starts before the index is built. `/healthz` and `/readyz` are served without
+//! authentication for container probes; until the keyword index is built, `/readyz` and the
+//! JSON endpoints return 503. MCP sessions are served as soon as the server exists, with
+//! first-run indexing reported to them as log notifications.
+//!
+//! Clients without MCP get plain JSON: `GET /search?q=...` ranks like `search_rust_docs`, and
+//! `GET /doc/{source}/{path}` returns an indexed document. Both need the same credentials as
+//! `/mcp`.
+
+use std::sync::{Arc, OnceLock};
+
+use axum::Router;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::get;
+use rmcp::transport::streamable_http_server::{
+    StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
+};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+
+use crate::auth::Authenticator;
+use crate::config::HttpConfig;
+use crate::error::Error;
+use crate::server::RustDocServer;
+use crate::workspace::RootPolicy;
+
+/// Path the MCP endpoint is mounted at
+pub const MCP_PATH: &str = "/mcp";
+
+/// The server, once it has started
+#[derive(Clone, Default)]
+pub struct ServerSlot(Arc<OnceLock<RustDocServer>>);
+
+impl ServerSlot {
+    pub fn set(&self, server: RustDocServer) {
+        let _ = self.0.set(server);
+    }
+
+    fn get(&self) -> Option<&RustDocServer> {
+        self.0.get()
+    }
+
+    /// The server, once first-run indexing has finished
+    fn indexed(&self) -> Option<&RustDocServer> {
+        self.get().filter(|server| !server.is_indexing())
+    }
+}
+
+/// Bind the configured address, so startup fails early when it is taken
+pub async fn bind(
+    config: &HttpConfig,
+    authenticator: &Authenticator,
+) -> anyhow::Result<TcpListener> {
+    if !authenticator.is_enabled() && !config.bind.ip().is_loopback() {
+        tracing::warn!(
+            "HTTP transport on {} has no [auth] configured; anyone who can reach it can use the server",
+            config.bind
+        );
+    }
+    Ok(TcpListener::bind(config.bind).await?)
+}
+
+/// Serve MCP over HTTP until the process is stopped
+pub async fn serve(
+    listener: TcpListener,
+    server: ServerSlot,
+    authenticator: Authenticator,
+    roots: RootPolicy,
+) -> anyhow::Result<()> {
+    tracing::info!(
+        "Starting rust-lang-mcp server on http://{}{}",
+        listener.local_addr()?,
+        MCP_PATH
+    );
+    axum::serve(listener, router(server, authenticator, roots)).await?;
+    Ok(())
+}
+
+fn router(server: ServerSlot, authenticator: Authenticator, roots: RootPolicy) -> Router {
+    let sessions = server.clone();
+    let mcp = StreamableHttpService::new(
+        move || {
+            sessions
+                .get()
+                .map(|server| server.for_session(&roots))
+                .ok_or_else(|| std::io::Error::other("Server is still starting"))
+        },
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+
+    Router::new()
+        .nest_service(MCP_PATH, mcp)
+        .route("/search", get(search))
+        .route("/doc/{source}/{*path}", get(document))
+        .layer(middleware::from_fn_with_state(authenticator, require_auth))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(server)
+}
+
+/// Liveness: the process is up and serving HTTP
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness: the index is built and has documents
+async fn readyz(State(server): State<ServerSlot>) -> Response {
+    let readiness = match server.get() {
+        Some(server) => server.readiness(),
+        None => serde_json::json!({ "ready": false, "indexing": true }),
+    };
+    let status = if readiness["ready"] == true {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+/// Query string of `GET /search`
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    #[serde(default)]
+    q: String,
+    /// `hybrid` (default), `keyword`, or `semantic`
+    mode: Option<String>,
+    /// Maximum results (default 5, max 20)
+    #[serde(default)]
+    limit: usize,
+    /// Corpus profile; the server default if unset
+    profile: Option<String>,
+}
+
+/// Search the documentation, ranked as by `search_rust_docs`
+async fn search(State(server): State<ServerSlot>, Query(params): Query<SearchParams>) -> Response {
+    if params.q.trim().is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "Missing query; pass ?q=...");
+    }
+    let Some(server) = server.indexed().cloned() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is still indexing documentation",
+        );
+    };
+
+    // Embedding the query is CPU-bound, so the search runs off the async workers
+    let searched = tokio::task::spawn_blocking(move || {
+        server.search_documentation(
+            &params.q,
+            params.mode.as_deref(),
+            params.limit,
+            params.profile.as_deref(),
+        )
+    })
+    .await;
+    match searched {
+        Ok(Ok(response)) => Json(response).into_response(),
+        // Unknown profiles and unusable queries
+        Ok(Err(Error::Other(message))) => json_error(StatusCode::BAD_REQUEST, &message),
+        Ok(Err(e)) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Search failed: {}", e),
+        ),
+        Err(e) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Search task failed: {}", e),
+        ),
+    }
+}
+
+/// An indexed document: its title, headings, and Markdown
+async fn document(
+    State(server): State<ServerSlot>,
+    Path((source, path)): Path<(String, String)>,
+) -> Response {
+    let Some(server) = server.indexed() else {
+        return json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is still indexing documentation",
+        );
+    };
+    match server.document_json(&source, &path) {
+        Ok(Some(document)) => Json(document).into_response(),
+        Ok(None) => json_error(
+            StatusCode::NOT_FOUND,
+            &format!("No indexed document at {}/{}", source, path),
+        ),
+        Err(e) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to read {}/{}: {}", source, path, e),
+        ),
+    }
+}
+
+/// An error response with a JSON body `{"error": message}`
+fn json_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// Reject requests without a valid bearer token
+async fn require_auth(
+    State(authenticator): State<Authenticator>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if authenticator.authorize(header).await {
+        return next.run(request).await;
+    }
+
+    let mut response =
+        (StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static("Bearer realm=\"rust-lang-mcp\""),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, Config};
+
+    /// GET a path and return the status code
+    async fn status(addr: std::net::SocketAddr, path: &str) -> u16 {
+        let url = format!("http://{}{}", addr, path);
+        tokio::task::spawn_blocking(move || {
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .http_status_as_error(false)
+                .build()
+                .into();
+            agent.get(&url).call().unwrap().status().as_u16()
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_probes_while_indexing() {
+        let authenticator = Authenticator::new(AuthConfig {
+            bearer_tokens: vec!["s3cret".to_string()],
+            oauth: None,
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(
+            listener,
+            ServerSlot::default(),
+            authenticator,
+            RootPolicy::default(),
+        ));
+
+        assert_eq!(status(addr, "/healthz").await, 200);
+        assert_eq!(status(addr, "/readyz").await, 503);
+        assert_eq!(status(addr, MCP_PATH).await, 401);
+        assert_eq!(status(addr, "/search?q=ownership").await, 401);
+        assert_eq!(
+            status(addr, "/doc/rust-book/ch04-01-what-is-ownership.md").await,
+            401
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_endpoints_while_indexing() {
+        let authenticator = Authenticator::new(AuthConfig::default());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(
+            listener,
+            ServerSlot::default(),
+            authenticator,
+            RootPolicy::default(),
+        ));
+
+        assert_eq!(status(addr, "/search?q=ownership").await, 503);
+        assert_eq!(
+            status(addr, "/doc/rust-book/ch04-01-what-is-ownership.md").await,
+            503
+        );
+        // The query string is checked before the server is needed
+        assert_eq!(status(addr, "/search").await, 400);
+    }
+
+    #[test]
+    fn test_sessions_index_no_roots_by_default() {
+        let config = Config::parse("[http]\nbind = \"0.0.0.0:8080\"").unwrap();
+        let roots = config.http.unwrap().root_policy();
+
+        let project = format!("file://{}", std::env::current_dir().unwrap().display());
+        assert!(roots.paths([project.as_str()]).is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+//! Heuristic detection of non-idiomatic constructs in short Rust snippets.
+//!
+//! Detection is purely textual: it flags patterns worth a second look, it does not parse Rust.
+
+use crate::search::SearchResult;
+
+/// A construct that usually has a more idiomatic alternative
+#[derive(Debug)]
+pub struct Idiom {
+    /// Stable identifier used in tool output
+    pub id: &'static str,
+    /// What the construct is and what to prefer instead
+    pub description: &'static str,
+    /// Search query for the sections describing the alternative
+    pub query: &'static str,
+    /// `(source, path)` of the sections that cover this idiom directly
+    pub targets: &'static [(&'static str, &'static str)],
+    detect: fn(&str) -> usize,
+}
+
+/// A construct found in a snippet
+#[derive(Debug)]
+pub struct Finding {
+    /// The matched idiom
+    pub idiom: &'static Idiom,
+    /// Number of times the construct appears
+    pub occurrences: usize,
+}
+
+/// Built-in idioms, checked in order
+pub const IDIOMS: &[Idiom] = &[
+    Idiom {
+        id: "unwrap-chain",
+        description: "Repeated unwrap()/expect() calls panic on failure; propagate errors with `?` and return a Result instead.",
+        query: "error handling question mark operator propagate Result instead of unwrap",
+        targets: &[
+            ("rust-patterns", "question-mark.md"),
+            ("api-guidelines", "dependability.md"),
+        ],
+        detect: detect_unwraps,
+    },
+    Idiom {
+        id: "manual-index-loop",
+        description: "Looping over `0..v.len()` and indexing is error-prone; iterate directly or use iterator adapters like enumerate().",
+        query: "iterators instead of index loop for loop iter enumerate",
+        targets: &[("rust-patterns", "functional.md")],
+        detect: detect_index_loops,
+    },
+    Idiom {
+        id: "clone-heavy",
+        description: "Frequent clone() calls often work around the borrow checker; borrow instead or restructure ownership.",
+        query: "clone to satisfy the borrow checker anti-pattern",
+        targets: &[("rust-patterns", "borrow_clone.md")],
+        detect: detect_clones,
+    },
+    Idiom {
+        id: "owned-type-argument",
+        description: "Parameters like `&String`, `&Vec<T>`, or `&Box<T>` are less flexible than `&str`, `&[T]`, or `&T`.",
+        query: "use borrowed types for arguments deref coercion &str &[T]",
+        targets: &[("rust-patterns", "coercion-arguments.md")],
+        detect: detect_owned_arguments,
+    },
+    Idiom {
+        id: "string-concatenation",
+        description: "Building strings with repeated `+` or push_str is harder to read than format!.",
+        query: "concatenating strings with format!",
+        targets: &[("rust-patterns", "concat-format.md")],
+        detect: detect_concatenation,
+    },
+    Idiom {
+        id: "new-without-default",
+        description: "A no-argument `new()` constructor should usually be paired with a Default implementation.",
+        query: "constructors Default trait new",
+        targets: &[
+            ("rust-patterns", "default.md"),
+            ("rust-patterns", "ctor.md"),
+        ],
+        detect: detect_new_without_default,
+    },
+    Idiom {
+        id: "deref-polymorphism",
+        description: "Implementing Deref to emulate inheritance is surprising; use composition or traits.",
+        query: "Deref polymorphism anti-pattern inheritance",
+        targets: &[("rust-patterns", "deref.md")],
+        detect: detect_deref_impl,
+    },
+    Idiom {
+        id: "deny-warnings",
+        description: "`#![deny(warnings)]` breaks builds when the compiler adds new lints; deny specific lints or use RUSTFLAGS in CI.",
+        query: "deny warnings anti-pattern",
+        targets: &[("rust-patterns", "deny-warnings.md")],
+        detect: detect_deny_warnings,
+    },
+    Idiom {
+        id: "string-error",
+        description: "Errors typed as String or Box<dyn Error> are hard for callers to inspect; define meaningful error types.",
+        query: "error types are meaningful and well-behaved implement std::error::Error",
+        targets: &[("api-guidelines", "interoperability.md")],
+        detect: detect_string_errors,
+    },
+    Idiom {
+        id: "getter-prefix",
+        description: "Getters are named after the field, without a `get_` prefix.",
+        query: "getter names follow Rust convention naming",
+        targets: &[("api-guidelines", "naming.md")],
+        detect: detect_get_prefix,
+    },
+];
+
+/// Detect the non-idiomatic constructs present in a snippet
+pub fn check_snippet(code: &str) -> Vec<Finding> {
+    IDIOMS
+        .iter()
+        .filter_map(|idiom| {
+            let occurrences = (idiom.detect)(code);
+            (occurrences > 0).then_some(Finding { idiom, occurrences })
+        })
+        .collect()
+}
+
+impl Idiom {
+    /// Move results covering this idiom directly to the front, keeping relative order
+    pub fn apply_boost(&self, results: &mut [SearchResult]) {
+        results.sort_by_key(|result| {
+            !self
+                .targets
+                .iter()
+                .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+        });
+    }
+}
+
+fn detect_unwraps(code: &str) -> usize {
+    let count = code.matches(".unwrap()").count() + code.matches(".expect(").count();
+    // A single unwrap in example code is common and usually fine
+    if count >= 2 { count } else { 0 }
+}
+
+fn detect_index_loops(code: &str) -> usize {
+    code.lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            line.starts_with("for ") && line.contains(" in 0..") && line.contains(".len()")
+        })
+        .count()
+}
+
+fn detect_clones(code: &str) -> usize {
+    let count = code.matches(".clone()").count();
+    if count >= 3 { count } else { 0 }
+}
+
+fn detect_owned_arguments(code: &str) -> usize {
+    code.lines()
+        .filter(|line| line.contains("fn "))
+        .map(|line| {
+            [": &String", ": &Vec<", ": &Box<", ": &mut String"]
+                .iter()
+                .map(|pattern| line.matches(pattern).count())
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+fn detect_concatenation(code: &str) -> usize {
+    let plus = code
+        .lines()
+        .filter(|line| line.contains("+ &") && (line.contains("String") || line.contains('"')))
+        .count();
+    let pushes = code.matches(".push_str(").count();
+    if pushes >= 3 { plus + pushes } else { plus }
+}
+
+fn detect_new_without_default(code: &str) -> usize {
+    if code.contains("fn new()")
+        && !code.contains("Default for")
+        && !code.contains("derive(Default")
+    {
+        code.matches("fn new()").count()
+    } else {
+        0
+    }
+}
+
+fn detect_deref_impl(code: &str) -> usize {
+    code.matches("impl Deref for").count() + code.matches("impl std::ops::Deref for").count()
+}
+
+fn detect_deny_warnings(code: &str) -> usize {
+    code.matches("#![deny(warnings)]").count()
+}
+
+fn detect_string_errors(code: &str) -> usize {
+    code.matches(", String>").count()
+        + code.matches("Box<dyn Error>").count()
+        + code.matches("Box<dyn std::error::Error>").count()
+}
+
+fn detect_get_prefix(code: &str) -> usize {
+    code.matches("fn get_").count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNIPPET: &str = r#"
+struct Config { name: String }
+
+impl Config {
+    fn new() -> Self { Config { name: String::new() } }
+    fn get_name(&self) -> &String { &self.name }
+}
+
+fn total(values: &Vec<i32>) -> i32 {
+    let mut sum = 0;
+    for i in 0..values.len() {
+        sum += values[i];
+    }
+    sum
+}
+
+fn load(path: &str) -> Result<String, String> {
+    let text = std::fs::read_to_string(path).unwrap();
+    let first = text.lines().next().unwrap();
+    Ok(first.to_string())
+}
+"#;
+
+    #[test]
+    fn test_detects_constructs() {
+        let ids: Vec<&str> = check_snippet(SNIPPET).iter().map(|f| f.idiom.id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "unwrap-chain",
+                "manual-index-loop",
+                "owned-type-argument",
+                "new-without-default",
+                "string-error",
+                "getter-prefix",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_idiomatic_code_has_no_findings() {
+        let code = "fn total(values: &[i32]) -> i32 { values.iter().sum() }";
+        assert!(check_snippet(code).is_empty());
+    }
+}
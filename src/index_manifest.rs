@@ -0,0 +1,185 @@
+//! Content hashes of the indexed documents.
+//!
+//! A full index build saves the manifest next to the index; an incremental update compares the
+//! freshly collected documents with it, so only added, changed, and removed documents are
+//! re-indexed and re-embedded.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::parsing::{Document, document_key};
+use crate::search::embedding_cache::text_hash;
+
+/// File the manifest is saved to, inside the index directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Hash of every indexed document, by source and path
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexManifest {
+    pub documents: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// How a collection of documents differs from the manifest
+#[derive(Debug, Default)]
+pub struct ManifestDiff<'a> {
+    /// Documents not in the manifest
+    pub added: Vec<&'a Document>,
+    /// Documents whose hash changed
+    pub changed: Vec<&'a Document>,
+    /// Source and path of documents in the manifest that are gone
+    pub removed: Vec<(String, String)>,
+    /// Number of documents with the same hash
+    pub unchanged: usize,
+}
+
+impl ManifestDiff<'_> {
+    /// Keys (`source/path`) of the documents to re-index or delete
+    pub fn affected_documents(&self) -> BTreeSet<String> {
+        self.added
+            .iter()
+            .chain(&self.changed)
+            .map(|doc| document_key(&doc.source, &doc.path))
+            .chain(
+                self.removed
+                    .iter()
+                    .map(|(source, path)| document_key(source, path)),
+            )
+            .collect()
+    }
+
+    /// Sources of the documents to re-index or delete
+    pub fn sources(&self) -> BTreeSet<String> {
+        self.added
+            .iter()
+            .chain(&self.changed)
+            .map(|doc| doc.source.clone())
+            .chain(self.removed.iter().map(|(source, _)| source.clone()))
+            .collect()
+    }
+
+    /// Whether the documents are exactly those of the manifest
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl IndexManifest {
+    /// Manifest of `documents`
+    pub fn of(documents: &[Document]) -> Self {
+        let mut manifest = Self::default();
+        for doc in documents {
+            manifest
+                .documents
+                .entry(doc.source.clone())
+                .or_default()
+                .insert(doc.path.clone(), document_hash(doc));
+        }
+        manifest
+    }
+
+    /// Load the manifest saved with an index, if there is one
+    pub fn load(index_dir: &Path) -> Option<Self> {
+        let json = std::fs::read_to_string(index_dir.join(MANIFEST_FILE)).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable index manifest: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Save the manifest next to the index
+    pub fn save(&self, index_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(index_dir)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Other(format!("Failed to serialize index manifest: {}", e)))?;
+        std::fs::write(index_dir.join(MANIFEST_FILE), json)?;
+        Ok(())
+    }
+
+    /// Compare `documents` with the manifest
+    pub fn diff<'a>(&self, documents: &'a [Document]) -> ManifestDiff<'a> {
+        let mut diff = ManifestDiff::default();
+        let mut seen: BTreeSet<(&str, &str)> = BTreeSet::new();
+        for doc in documents {
+            seen.insert((doc.source.as_str(), doc.path.as_str()));
+            match self
+                .documents
+                .get(&doc.source)
+                .and_then(|paths| paths.get(&doc.path))
+            {
+                None => diff.added.push(doc),
+                Some(hash) if *hash != document_hash(doc) => diff.changed.push(doc),
+                Some(_) => diff.unchanged += 1,
+            }
+        }
+        for (source, paths) in &self.documents {
+            for path in paths.keys() {
+                if !seen.contains(&(source.as_str(), path.as_str())) {
+                    diff.removed.push((source.clone(), path.clone()));
+                }
+            }
+        }
+        diff
+    }
+}
+
+/// Hash of everything indexed about a document: its text as well as the fields the indexer
+/// sets, like the summary order and categories
+fn document_hash(doc: &Document) -> String {
+    format!("{:016x}", text_hash(&format!("{:?}", doc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_markdown;
+
+    #[test]
+    fn test_diff_finds_changed_documents() {
+        let traits = parse_markdown("# Traits\n\nShared behavior.", "traits.md", "rust-book");
+        let enums = parse_markdown("# Enums\n\nVariants.", "enums.md", "rust-book");
+        let unsafe_rust = parse_markdown("# Unsafe\n\nRaw pointers.", "unsafe.md", "nomicon");
+        let manifest = IndexManifest::of(&[traits.clone(), enums.clone(), unsafe_rust]);
+
+        let mut reordered = enums.clone();
+        reordered.summary_order = Some(4);
+        let closures = parse_markdown("# Closures\n\nCaptures.", "closures.md", "rust-book");
+        let documents = vec![traits, reordered, closures];
+        let diff = manifest.diff(&documents);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].path, "closures.md");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].path, "enums.md");
+        assert_eq!(
+            diff.removed,
+            vec![("nomicon".to_string(), "unsafe.md".to_string())]
+        );
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(
+            diff.affected_documents().into_iter().collect::<Vec<_>>(),
+            vec![
+                "nomicon/unsafe.md",
+                "rust-book/closures.md",
+                "rust-book/enums.md"
+            ]
+        );
+        assert_eq!(
+            diff.sources().into_iter().collect::<Vec<_>>(),
+            vec!["nomicon", "rust-book"]
+        );
+
+        let updated = IndexManifest::of(&documents);
+        assert!(updated.diff(&documents).is_empty());
+
+        let dir = std::env::temp_dir().join(format!("index-manifest-{}", std::process::id()));
+        updated.save(&dir).unwrap();
+        assert_eq!(IndexManifest::load(&dir), Some(updated));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,76 @@
-use std::path::Path;
-
-use crate::error::Result;
-use crate::parsing::{parse_markdown_file, Document};
-use crate::search::embeddings::{embed_texts, init_embedding_model};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::clippy::ClippyLints;
+use crate::error::{Error, Result};
+use crate::error_codes::{ERROR_CODES_SOURCE, ErrorCodes};
+use crate::index_manifest::IndexManifest;
+use crate::parse_report::{ParseReport, SourceReport};
+use crate::parsing::{
+    Document, chunk_document_key, chunk_key, document_key, include_targets, parse_markdown,
+    parse_summary, relative_path,
+};
+use crate::platform_support::{PLATFORM_SOURCE, PlatformSupport};
+use crate::releases::{RELEASES_SOURCE, Releases};
+use crate::rustc_flags::RustcFlags;
+use crate::rustlings::Rustlings;
+use crate::search::concepts::COMMON_CONCEPTS;
+use crate::search::embedding_cache::EmbeddingCache;
+use crate::search::embeddings::{EmbeddingService, ModelSpec};
+use crate::search::vector_index::{ChunkInfo, IndexModel};
 use crate::search::{SearchIndex, VectorIndex};
-use crate::sources::{DocSource, DOC_SOURCES};
+use crate::sources::{DocSource, SourceKind, clone_missing_sources, doc_sources};
+use crate::std_source::{self, STD_SOURCE};
+use crate::taxonomy::{self, TAXONOMY_SOURCES};
+
+/// Clone the documentation sources and download the pages of rust-lang/rust that are missing.
+///
+/// Failures are logged, not returned: whatever is on disk is indexed. `on_clone` is called with
+/// each source before it is cloned.
+pub fn fetch_sources(data_dir: &Path, mirror: Option<&str>, on_clone: impl FnMut(&DocSource)) {
+    match clone_missing_sources(data_dir, mirror, on_clone) {
+        Ok(cloned) if cloned > 0 => {
+            tracing::info!("Cloned {} documentation sources", cloned);
+        }
+        Ok(_) => {
+            tracing::debug!("All documentation sources already present");
+        }
+        Err(e) => {
+            tracing::warn!("Failed to clone some sources: {}", e);
+        }
+    }
 
-/// Index all available documentation sources (keyword index only)
-/// Note: Use `index_all_sources_hybrid` for full hybrid search support.
-#[allow(dead_code)]
-pub fn index_all_sources(index: &SearchIndex, data_dir: &Path) -> Result<usize> {
-    let all_documents = collect_all_documents(data_dir)?;
+    // The release notes are one file of rust-lang/rust, so they are downloaded instead
+    if mirror.is_some() {
+        tracing::debug!(
+            "Not downloading release notes with a mirror configured; place RELEASES.md at {:?}",
+            Releases::file_path(data_dir)
+        );
+        return;
+    }
+    if let Err(e) = Releases::download(data_dir) {
+        tracing::warn!("Failed to download release notes: {}", e);
+    }
+    // So are the rustc book's flag and platform support pages and the error index
+    if let Err(e) = RustcFlags::download(data_dir) {
+        tracing::warn!("Failed to download rustc flag documentation: {}", e);
+    }
+    if let Err(e) = PlatformSupport::download(data_dir) {
+        tracing::warn!("Failed to download platform support pages: {}", e);
+    }
+    if let Err(e) = ErrorCodes::download(data_dir) {
+        tracing::warn!("Failed to download error code explanations: {}", e);
+    }
+}
+
+/// Index all available documentation sources in the keyword index; `reembed` builds their
+/// vectors afterwards
+pub fn index_all_sources(
+    index: &SearchIndex,
+    data_dir: &Path,
+    std_src: Option<&Path>,
+) -> Result<usize> {
+    let all_documents = collect_all_documents(data_dir, std_src)?;
 
     if all_documents.is_empty() {
         tracing::warn!("No documents found to index");
@@ -19,44 +79,264 @@ pub fn index_all_sources(index: &SearchIndex, data_dir: &Path) -> Result<usize>
 
     let count = all_documents.len();
     tracing::info!("Indexing {} total documents", count);
-    index.index_documents(&all_documents)?;
+    index_documents(index, &all_documents, data_dir)?;
 
     Ok(count)
 }
 
-/// Index all sources with both keyword and vector indices (hybrid search)
-pub fn index_all_sources_hybrid(
+/// Replace the keyword index's documents with `documents`, recording their hashes for
+/// `update_all_sources`
+fn index_documents(index: &SearchIndex, documents: &[Document], data_dir: &Path) -> Result<()> {
+    index.index_documents(documents)?;
+    if let Err(e) = IndexManifest::of(documents).save(&data_dir.join("index")) {
+        tracing::warn!("Failed to save index manifest: {}", e);
+    }
+    Ok(())
+}
+
+/// What an incremental update did
+#[derive(Debug, Default)]
+pub struct IndexUpdate {
+    /// Documents indexed for the first time
+    pub added: usize,
+    /// Documents re-indexed because they changed
+    pub updated: usize,
+    /// Documents deleted from the indices
+    pub removed: usize,
+    /// Documents left as they were
+    pub unchanged: usize,
+    /// Sources with documents added, changed, or removed
+    pub sources: BTreeSet<String>,
+    /// Set when the vector index was updated
+    pub embedding: Option<EmbeddingStats>,
+}
+
+/// Bring the indices up to date with the documentation on disk, re-indexing and re-embedding
+/// only the documents added, changed, or removed since the last build.
+///
+/// Without a manifest of the last build, everything is re-indexed and re-embedded. The vector
+/// index is updated (and returned) when it was built with the model of `embeddings`; otherwise
+/// it is left for `reembed`.
+pub fn update_all_sources(
     keyword_index: &SearchIndex,
-    vector_index: &mut VectorIndex,
     data_dir: &Path,
-) -> Result<usize> {
-    let all_documents = collect_all_documents(data_dir)?;
+    std_src: Option<&Path>,
+    batch_size: usize,
+    embeddings: &EmbeddingService,
+) -> Result<(IndexUpdate, Option<VectorIndex>)> {
+    let model = embeddings.model();
+    let documents = collect_all_documents(data_dir, std_src)?;
+    if documents.is_empty() {
+        return Err(Error::Other(
+            "No documents found to index; keeping the current index".to_string(),
+        ));
+    }
 
-    if all_documents.is_empty() {
-        tracing::warn!("No documents found to index");
-        return Ok(0);
+    let index_dir = data_dir.join("index");
+    let manifest = match IndexManifest::load(&index_dir) {
+        Some(manifest) if !keyword_index.is_empty()? => manifest,
+        _ => {
+            tracing::info!(
+                "No manifest of the indexed documents; re-indexing all {} documents",
+                documents.len()
+            );
+            index_documents(keyword_index, &documents, data_dir)?;
+            let (vector_index, stats) = reembed(keyword_index, data_dir, batch_size, embeddings)?;
+            let update = IndexUpdate {
+                added: documents.len(),
+                sources: documents.iter().map(|doc| doc.source.clone()).collect(),
+                embedding: Some(stats),
+                ..IndexUpdate::default()
+            };
+            return Ok((update, Some(vector_index)));
+        }
+    };
+
+    let diff = manifest.diff(&documents);
+    let mut update = IndexUpdate {
+        added: diff.added.len(),
+        updated: diff.changed.len(),
+        removed: diff.removed.len(),
+        unchanged: diff.unchanged,
+        sources: diff.sources(),
+        embedding: None,
+    };
+    if diff.is_empty() {
+        return Ok((update, None));
     }
+    tracing::info!(
+        "Updating the index: {} documents added, {} changed, {} removed",
+        update.added,
+        update.updated,
+        update.removed
+    );
+    let upserts: Vec<&Document> = diff.added.iter().chain(&diff.changed).copied().collect();
+    keyword_index.update_documents(&upserts, &diff.removed)?;
+
+    // The manifest is saved once both indices are updated, so a failed update is redone
+    let vector_path = vector_index_path(data_dir);
+    let vector_index = match IndexModel::load(&vector_path)? {
+        Some(built_with) if built_with == IndexModel::of(model) => {
+            let affected = diff.affected_documents();
+            let mut vector_index = VectorIndex::load(&vector_path, 0)?;
+            vector_index.retain(|key| !affected.contains(chunk_document_key(key)))?;
+            let chunks: Vec<Document> = keyword_index
+                .all_chunks()?
+                .into_iter()
+                .filter(|chunk| affected.contains(&document_key(&chunk.source, &chunk.path)))
+                .collect();
+
+            let mut cache = load_embedding_cache(data_dir, model);
+            // Entries of the unchanged documents are still needed by the next `reembed`
+            cache.keep_unused();
+            let sections = embed_chunks(
+                &mut vector_index,
+                &chunks,
+                embeddings,
+                &mut cache,
+                batch_size,
+            )?;
+            save_embedding_cache(&cache, data_dir);
+            if sections < chunks.len() {
+                return Err(Error::Other(format!(
+                    "Only {} of {} changed sections could be embedded; keeping the current vector index",
+                    sections,
+                    chunks.len()
+                )));
+            }
+            vector_index.save_replacing(&vector_path)?;
+            update.embedding = Some(EmbeddingStats {
+                sections,
+                cached: cache.hits,
+                computed: cache.misses,
+            });
+            Some(vector_index)
+        }
+        _ => {
+            tracing::info!(
+                "The vector index wasn't built with {}; leaving it for reembed",
+                model.id
+            );
+            None
+        }
+    };
 
-    let count = all_documents.len();
-    tracing::info!("Indexing {} total documents with hybrid search", count);
+    if let Err(e) = IndexManifest::of(&documents).save(&index_dir) {
+        tracing::warn!("Failed to save index manifest: {}", e);
+    }
+    Ok((update, vector_index))
+}
+
+/// Re-embed the sections of the keyword index with `embeddings`, then replace the saved vector
+/// index with the new one. The current index stays in place (and in use) until the new one is
+/// complete.
+pub fn reembed(
+    keyword_index: &SearchIndex,
+    data_dir: &Path,
+    batch_size: usize,
+    embeddings: &EmbeddingService,
+) -> Result<(VectorIndex, EmbeddingStats)> {
+    let model = embeddings.model();
+    let chunks = keyword_index.all_chunks()?;
+    if chunks.is_empty() {
+        return Err(Error::Other(
+            "The documentation hasn't been indexed yet; nothing to re-embed".to_string(),
+        ));
+    }
+    tracing::info!("Re-embedding {} sections with {}", chunks.len(), model.id);
+
+    let mut vector_index = VectorIndex::new();
+    vector_index.set_model(IndexModel::of(model))?;
+    let mut cache = load_embedding_cache(data_dir, model);
+    let sections = embed_chunks(
+        &mut vector_index,
+        &chunks,
+        embeddings,
+        &mut cache,
+        batch_size,
+    )?;
+    save_embedding_cache(&cache, data_dir);
+    if sections < chunks.len() {
+        return Err(Error::Other(format!(
+            "Only {} of {} sections could be embedded; keeping the current vector index",
+            sections,
+            chunks.len()
+        )));
+    }
+
+    // Searches for common concepts then skip query-time inference
+    match embeddings.embed_queries(COMMON_CONCEPTS) {
+        Ok(embeddings) => {
+            for (concept, embedding) in COMMON_CONCEPTS.iter().zip(embeddings) {
+                vector_index.set_query_embedding(concept, embedding)?;
+            }
+        }
+        Err(e) => tracing::warn!("Failed to embed common concepts: {}", e),
+    }
+    vector_index.save_replacing(&vector_index_path(data_dir))?;
+
+    let stats = EmbeddingStats {
+        sections,
+        cached: cache.hits,
+        computed: cache.misses,
+    };
+    Ok((vector_index, stats))
+}
 
-    // Index in keyword search
-    keyword_index.index_documents(&all_documents)?;
+/// Where the vector index is saved
+fn vector_index_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("index").join("vectors")
+}
 
-    // Clear and rebuild vector index
-    vector_index.clear();
+/// Where embeddings are cached between runs
+fn embedding_cache_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("index").join("embedding-cache")
+}
 
-    // Initialize embedding model
-    let models_dir = data_dir.join("models");
-    init_embedding_model(&models_dir)?;
+/// Load the embeddings the model produced before
+fn load_embedding_cache(data_dir: &Path, model: &'static ModelSpec) -> EmbeddingCache {
+    EmbeddingCache::load(&embedding_cache_dir(data_dir), model)
+}
+
+/// Save the embedding cache; the embeddings are recomputed next time if this fails
+fn save_embedding_cache(cache: &EmbeddingCache, data_dir: &Path) {
+    if let Err(e) = cache.save(&embedding_cache_dir(data_dir)) {
+        tracing::warn!("Failed to save embedding cache: {}", e);
+    }
+}
+
+/// What an embedding run did
+#[derive(Debug, Default)]
+pub struct EmbeddingStats {
+    /// Document sections added to the vector index
+    pub sections: usize,
+    /// Texts whose embedding was taken from the cache
+    pub cached: usize,
+    /// Texts embedded with the model
+    pub computed: usize,
+}
+
+/// Add the content and title embeddings of document chunks to `vector_index`, keyed by chunk,
+/// reusing the cached embeddings of texts embedded before. Returns how many chunks were
+/// embedded.
+fn embed_chunks(
+    vector_index: &mut VectorIndex,
+    chunks: &[Document],
+    embeddings: &EmbeddingService,
+    cache: &mut EmbeddingCache,
+    batch_size: usize,
+) -> Result<usize> {
+    let count = chunks.len();
+
+    // Make room for the chunks' content and title vectors
+    vector_index.reserve(count * 2);
 
     // Generate embeddings in batches
-    const BATCH_SIZE: usize = 32;
     let mut indexed = 0;
 
-    for chunk in all_documents.chunks(BATCH_SIZE) {
+    for batch in chunks.chunks(batch_size.max(1)) {
         // Prepare texts for embedding (use content or title if content is too short)
-        let texts: Vec<&str> = chunk
+        let texts: Vec<&str> = batch
             .iter()
             .map(|doc| {
                 if doc.content.len() > 50 {
@@ -68,14 +348,29 @@ pub fn index_all_sources_hybrid(
             })
             .collect();
 
+        // Titles and headings are embedded separately, so a heading matching the query is
+        // not drowned out by the rest of the section
+        let titles: Vec<String> = batch.iter().map(title_text).collect();
+        let titles: Vec<&str> = titles.iter().map(String::as_str).collect();
+
         // Generate embeddings
-        match embed_texts(&texts) {
-            Ok(embeddings) => {
-                for (doc, embedding) in chunk.iter().zip(embeddings.into_iter()) {
-                    vector_index.add(doc.path.clone(), embedding);
+        match cache
+            .embed(embeddings, &texts)
+            .and_then(|contents| Ok((contents, cache.embed(embeddings, &titles)?)))
+        {
+            Ok((contents, title_embeddings)) => {
+                for ((doc, embedding), title_embedding) in
+                    batch.iter().zip(contents).zip(title_embeddings)
+                {
+                    vector_index.add_chunk(
+                        chunk_key(&doc.source, &doc.path, doc.section.as_ref()),
+                        embedding,
+                        title_embedding,
+                        ChunkInfo::of(doc),
+                    )?;
                 }
-                indexed += chunk.len();
-                tracing::debug!("Embedded {}/{} documents", indexed, count);
+                indexed += batch.len();
+                tracing::debug!("Embedded {}/{} sections", indexed, count);
             }
             Err(e) => {
                 tracing::warn!("Failed to generate embeddings for batch: {}", e);
@@ -83,45 +378,162 @@ pub fn index_all_sources_hybrid(
         }
     }
 
-    tracing::info!("Indexed {} documents with {} embeddings", count, indexed);
-
-    // Save vector index
-    let vector_index_path = data_dir.join("index").join("vectors");
-    vector_index.save(&vector_index_path)?;
+    tracing::info!(
+        "Embedded {} of {} sections ({} texts cached, {} computed)",
+        indexed,
+        count,
+        cache.hits,
+        cache.misses
+    );
+    Ok(indexed)
+}
 
-    Ok(count)
+/// Text of a chunk's title embedding: the document title, the section's breadcrumb, then the
+/// headings within the section
+fn title_text(doc: &Document) -> String {
+    let breadcrumb = doc.section.iter().flat_map(|section| &section.breadcrumb);
+    let mut lines: Vec<&str> = std::iter::once(&doc.title)
+        .chain(breadcrumb)
+        .map(String::as_str)
+        .collect();
+    for heading in &doc.headings {
+        if !lines.contains(&heading.as_str()) {
+            lines.push(heading);
+        }
+    }
+    lines.join("\n")
 }
 
-/// Collect all documents from all sources
-fn collect_all_documents(data_dir: &Path) -> Result<Vec<Document>> {
+/// Collect all documents from all sources, plus the std sources when configured, saving the
+/// parse report next to the index
+fn collect_all_documents(data_dir: &Path, std_src: Option<&Path>) -> Result<Vec<Document>> {
     let mut all_documents = Vec::new();
+    let mut report = ParseReport::default();
 
-    for source in DOC_SOURCES {
+    for source in doc_sources() {
         let docs_path = source.docs_path(data_dir);
         if docs_path.exists() {
-            tracing::info!("Collecting documents from {} ({:?})", source.name, docs_path);
-            match collect_documents(&docs_path, source.id) {
-                Ok(docs) => {
+            tracing::info!(
+                "Collecting documents from {} ({:?})",
+                source.name,
+                docs_path
+            );
+            let mut source_report = SourceReport::new(source.id);
+            let collected = match source.kind {
+                SourceKind::Book => collect_documents(&docs_path, source.id, &mut source_report),
+                SourceKind::Rustlings => {
+                    let repo_path = source.repo_path(data_dir);
+                    let rustlings = Rustlings::load(&repo_path);
+                    if rustlings.is_empty() {
+                        Err(Error::Other("no exercises found in info.toml".to_string()))
+                    } else {
+                        Ok(rustlings.documents(&repo_path, &mut source_report))
+                    }
+                }
+                SourceKind::Clippy => {
+                    let lints = ClippyLints::load(&source.repo_path(data_dir));
+                    if lints.is_empty() {
+                        Err(Error::Other(
+                            "no declare_clippy_lint! lints found".to_string(),
+                        ))
+                    } else {
+                        Ok(lints.documents(&mut source_report))
+                    }
+                }
+            };
+            match collected {
+                Ok(mut docs) => {
                     tracing::info!("  Found {} documents", docs.len());
+                    if TAXONOMY_SOURCES.contains(&source.id) {
+                        for doc in &mut docs {
+                            doc.categories = taxonomy::categorize(doc);
+                        }
+                    }
                     all_documents.extend(docs);
                 }
                 Err(e) => {
                     tracing::warn!("  Failed to collect from {}: {}", source.id, e);
+                    source_report.error = Some(e.to_string());
                 }
             }
+            if !source_report.problems.is_empty() {
+                tracing::warn!(
+                    "  {} files in {} had parse problems (see the parse_report tool)",
+                    source_report.problems.len(),
+                    source.id
+                );
+            }
+            report.sources.push(source_report);
         } else {
             tracing::debug!("Source {} not available at {:?}", source.id, docs_path);
         }
     }
 
+    let releases = Releases::load(data_dir);
+    if !releases.is_empty() {
+        tracing::info!("Collecting release notes");
+        let mut source_report = SourceReport::new(RELEASES_SOURCE);
+        let docs = releases.documents(&mut source_report);
+        tracing::info!("  Found {} releases", docs.len());
+        all_documents.extend(docs);
+        report.sources.push(source_report);
+    }
+
+    let error_codes = ErrorCodes::load(data_dir);
+    if !error_codes.is_empty() {
+        tracing::info!("Collecting error code explanations");
+        let mut source_report = SourceReport::new(ERROR_CODES_SOURCE);
+        let docs = error_codes.documents(&mut source_report);
+        tracing::info!("  Found {} error codes", docs.len());
+        all_documents.extend(docs);
+        report.sources.push(source_report);
+    }
+
+    let platform_dir = PlatformSupport::dir(data_dir);
+    if platform_dir.exists() {
+        tracing::info!("Collecting platform support pages");
+        let mut source_report = SourceReport::new(PLATFORM_SOURCE);
+        match collect_documents(&platform_dir, PLATFORM_SOURCE, &mut source_report) {
+            Ok(docs) => {
+                tracing::info!("  Found {} pages", docs.len());
+                all_documents.extend(docs);
+            }
+            Err(e) => {
+                tracing::warn!("  Failed to collect platform support pages: {}", e);
+                source_report.error = Some(e.to_string());
+            }
+        }
+        report.sources.push(source_report);
+    }
+
+    if let Some(std_src) = std_src {
+        let mut source_report = SourceReport::new(STD_SOURCE);
+        match std_source::library_dir(std_src) {
+            Some(library) => {
+                tracing::info!("Collecting std items from {:?}", library);
+                let docs = std_source::collect_documents(&library, &mut source_report);
+                tracing::info!("  Found {} items", docs.len());
+                all_documents.extend(docs);
+            }
+            None => {
+                tracing::warn!("No core/src under std sources path {:?}", std_src);
+                source_report.error = Some(format!("no core/src under {:?}", std_src));
+            }
+        }
+        report.sources.push(source_report);
+    }
+
+    if let Err(e) = report.save(&data_dir.join("index")) {
+        tracing::warn!("Failed to save parse report: {}", e);
+    }
+
     Ok(all_documents)
 }
 
 /// Index a single documentation source
-#[allow(dead_code)]
 pub fn index_source(index: &SearchIndex, data_dir: &Path, source: &DocSource) -> Result<usize> {
     let docs_path = source.docs_path(data_dir);
-    let documents = collect_documents(&docs_path, source.id)?;
+    let documents = collect_documents(&docs_path, source.id, &mut SourceReport::new(source.id))?;
 
     if documents.is_empty() {
         tracing::warn!("No markdown files found in {:?}", docs_path);
@@ -135,8 +547,9 @@ pub fn index_source(index: &SearchIndex, data_dir: &Path, source: &DocSource) ->
     Ok(count)
 }
 
-/// Recursively collect all markdown documents from a directory
-fn collect_documents(dir: &Path, source: &str) -> Result<Vec<Document>> {
+/// Recursively collect all markdown documents from a directory, recording files that could
+/// not be read and includes of missing files in `report`
+fn collect_documents(dir: &Path, source: &str, report: &mut SourceReport) -> Result<Vec<Document>> {
     let mut documents = Vec::new();
 
     if !dir.exists() {
@@ -145,19 +558,49 @@ fn collect_documents(dir: &Path, source: &str) -> Result<Vec<Document>> {
 
     for entry in walkdir(dir)? {
         let path = entry;
-        if path.extension().is_some_and(|ext| ext == "md") {
-            match parse_markdown_file(&path, source) {
-                Ok(doc) => documents.push(doc),
-                Err(e) => {
-                    tracing::warn!("Failed to parse {:?}: {}", path, e);
-                }
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let relative_path = relative_path(&path, dir);
+
+        let markdown = match std::fs::read_to_string(&path) {
+            Ok(markdown) => markdown,
+            Err(e) => {
+                tracing::warn!("Failed to parse {:?}: {}", path, e);
+                report.unreadable(relative_path, &e);
+                continue;
+            }
+        };
+
+        let file_dir = path.parent().unwrap_or(dir);
+        for target in include_targets(&markdown) {
+            if !file_dir.join(target).exists() {
+                tracing::debug!("Broken include {} in {:?}", target, path);
+                report.broken_include(relative_path.clone(), target);
             }
         }
+
+        documents.push(parse_markdown(&markdown, &relative_path, source));
     }
+    report.parsed = documents.len();
+
+    apply_summary_order(dir, &mut documents);
 
     Ok(documents)
 }
 
+/// Record each chapter's position in the source's SUMMARY.md, if it has one
+fn apply_summary_order(dir: &Path, documents: &mut [Document]) {
+    let Ok(summary) = std::fs::read_to_string(dir.join("SUMMARY.md")) else {
+        return;
+    };
+
+    let chapters = parse_summary(&summary);
+    for doc in documents {
+        doc.summary_order = chapters.iter().position(|chapter| *chapter == doc.path);
+    }
+}
+
 /// Simple recursive directory walker
 fn walkdir(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
     let mut files = Vec::new();
@@ -175,3 +618,83 @@ fn walkdir(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
 
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_report::ProblemKind;
+
+    #[test]
+    fn test_collect_documents_reports_problems() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("collect-docs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("listings"))?;
+        std::fs::write(dir.join("listings/main.rs"), "fn main() {}")?;
+        std::fs::write(
+            dir.join("ok.md"),
+            "# Ok\n\n```rust\n{{#include listings/main.rs}}\n```",
+        )?;
+        std::fs::write(
+            dir.join("broken.md"),
+            "# Broken\n\n```rust\n{{#rustdoc_include listings/missing.rs:all}}\n```",
+        )?;
+        std::fs::write(dir.join("latin1.md"), b"# Caf\xe9\n")?;
+
+        let mut report = SourceReport::new("test");
+        let documents = collect_documents(&dir, "test", &mut report)?;
+        std::fs::remove_dir_all(&dir)?;
+
+        // The file with a broken include is still indexed; the non-UTF-8 one is skipped
+        assert_eq!(documents.len(), 2);
+        assert_eq!(report.parsed, 2);
+        let mut problems: Vec<(&str, ProblemKind)> = report
+            .problems
+            .iter()
+            .map(|p| (p.path.as_str(), p.kind))
+            .collect();
+        problems.sort_by_key(|(path, _)| *path);
+        assert_eq!(
+            problems,
+            vec![
+                ("broken.md", ProblemKind::BrokenInclude),
+                ("latin1.md", ProblemKind::Encoding),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_documents_keeps_directories() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("collect-nested-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("rust-2021"))?;
+        std::fs::create_dir_all(dir.join("rust-2024"))?;
+        std::fs::write(
+            dir.join("rust-2021/prelude.md"),
+            "# Additions to the prelude",
+        )?;
+        std::fs::write(dir.join("rust-2024/prelude.md"), "# Prelude changes")?;
+        std::fs::write(
+            dir.join("SUMMARY.md"),
+            "- [2024 prelude](rust-2024/prelude.md)\n- [2021 prelude](./rust-2021/prelude.md)",
+        )?;
+
+        let mut documents = collect_documents(&dir, "edition-guide", &mut SourceReport::new("t"))?;
+        std::fs::remove_dir_all(&dir)?;
+
+        documents.sort_by_key(|doc| doc.summary_order);
+        let paths: Vec<(&str, Option<usize>)> = documents
+            .iter()
+            .map(|doc| (doc.path.as_str(), doc.summary_order))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("SUMMARY.md", None),
+                ("rust-2024/prelude.md", Some(0)),
+                ("rust-2021/prelude.md", Some(1)),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+//! Learning paths: ordered reading lists built from a small concept graph.
+//!
+//! A goal is matched to concepts, each concept's prerequisites are added first, and the
+//! chapters found for every concept are ordered by their position in the books' SUMMARY.md.
+
+use crate::parsing::Document;
+
+/// Sources used for learning paths, in the order they are recommended
+pub const LEARNING_SOURCES: &[&str] = &["rust-book", "async-book", "rust-by-example"];
+
+/// A concept in the learning graph
+#[derive(Debug)]
+pub struct Concept {
+    /// Stable identifier used for prerequisites and tool output
+    pub id: &'static str,
+    /// Lowercase words or phrases in a goal that select this concept
+    pub keywords: &'static [&'static str],
+    /// Search query for chapters covering the concept
+    pub query: &'static str,
+    /// Concepts to read first
+    pub prerequisites: &'static [&'static str],
+}
+
+/// Built-in concept graph
+pub const CONCEPTS: &[Concept] = &[
+    Concept {
+        id: "basics",
+        keywords: &[
+            "basics",
+            "syntax",
+            "variables",
+            "functions",
+            "control flow",
+            "beginner",
+        ],
+        query: "variables mutability data types functions control flow",
+        prerequisites: &[],
+    },
+    Concept {
+        id: "ownership",
+        keywords: &["ownership", "move", "moves", "drop"],
+        query: "what is ownership move",
+        prerequisites: &["basics"],
+    },
+    Concept {
+        id: "borrowing",
+        keywords: &[
+            "borrow",
+            "borrowing",
+            "references",
+            "borrow checker",
+            "slices",
+        ],
+        query: "references and borrowing",
+        prerequisites: &["ownership"],
+    },
+    Concept {
+        id: "structs-enums",
+        keywords: &["struct", "structs", "enum", "enums", "option"],
+        query: "defining structs enums",
+        prerequisites: &["ownership"],
+    },
+    Concept {
+        id: "pattern-matching",
+        keywords: &[
+            "match",
+            "matching",
+            "patterns",
+            "pattern matching",
+            "if let",
+        ],
+        query: "match control flow patterns",
+        prerequisites: &["structs-enums"],
+    },
+    Concept {
+        id: "error-handling",
+        keywords: &["error", "errors", "error handling", "result", "panic"],
+        query: "recoverable errors with Result",
+        prerequisites: &["pattern-matching"],
+    },
+    Concept {
+        id: "generics",
+        keywords: &["generic", "generics", "type parameters"],
+        query: "generic data types",
+        prerequisites: &["structs-enums"],
+    },
+    Concept {
+        id: "traits",
+        keywords: &["trait", "traits", "trait objects", "dyn", "impl trait"],
+        query: "traits defining shared behavior",
+        prerequisites: &["generics"],
+    },
+    Concept {
+        id: "lifetimes",
+        keywords: &["lifetime", "lifetimes", "'a", "does not live long enough"],
+        query: "validating references with lifetimes",
+        prerequisites: &["borrowing", "generics"],
+    },
+    Concept {
+        id: "closures",
+        keywords: &["closure", "closures", "lambda", "fn traits"],
+        query: "closures capture environment",
+        prerequisites: &["ownership", "traits"],
+    },
+    Concept {
+        id: "iterators",
+        keywords: &["iterator", "iterators", "iter", "adapters"],
+        query: "processing a series of items with iterators",
+        prerequisites: &["closures"],
+    },
+    Concept {
+        id: "smart-pointers",
+        keywords: &[
+            "smart pointer",
+            "smart pointers",
+            "box",
+            "rc",
+            "refcell",
+            "arc",
+        ],
+        query: "smart pointers Box Rc RefCell",
+        prerequisites: &["traits", "borrowing"],
+    },
+    Concept {
+        id: "concurrency",
+        keywords: &[
+            "thread",
+            "threads",
+            "concurrency",
+            "mutex",
+            "send",
+            "sync",
+            "channels",
+        ],
+        query: "fearless concurrency threads",
+        prerequisites: &["closures", "smart-pointers"],
+    },
+    Concept {
+        id: "async",
+        keywords: &[
+            "async",
+            "await",
+            "future",
+            "futures",
+            "tokio",
+            "asynchronous",
+        ],
+        query: "async await futures",
+        prerequisites: &["concurrency", "traits", "lifetimes"],
+    },
+    Concept {
+        id: "macros",
+        keywords: &["macro", "macros", "macro_rules"],
+        query: "macros macro_rules",
+        prerequisites: &["traits", "pattern-matching"],
+    },
+    Concept {
+        id: "unsafe",
+        keywords: &["unsafe", "raw pointer", "raw pointers", "ffi"],
+        query: "unsafe rust raw pointers",
+        prerequisites: &["smart-pointers", "lifetimes"],
+    },
+];
+
+/// One step of a learning path
+#[derive(Debug)]
+pub struct Step {
+    pub concept: &'static Concept,
+    /// Whether the concept was asked for directly (otherwise it is a prerequisite)
+    pub is_goal: bool,
+}
+
+/// Find a concept by ID
+fn concept(id: &str) -> Option<&'static Concept> {
+    CONCEPTS.iter().find(|c| c.id == id)
+}
+
+/// Concepts whose keywords appear in the goal
+pub fn goal_concepts(goal: &str) -> Vec<&'static Concept> {
+    let lower = goal.to_lowercase();
+    let phrase = format!(
+        " {} ",
+        lower
+            .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '_')
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+
+    CONCEPTS
+        .iter()
+        .filter(|c| {
+            c.keywords
+                .iter()
+                .any(|k| phrase.contains(&format!(" {} ", k)))
+        })
+        .collect()
+}
+
+/// Order the goal concepts after all of their prerequisites (depth-first, each concept once)
+pub fn plan(goals: &[&'static Concept], include_prerequisites: bool) -> Vec<Step> {
+    fn visit(concept: &'static Concept, is_goal: bool, recurse: bool, steps: &mut Vec<Step>) {
+        if let Some(step) = steps.iter_mut().find(|s| s.concept.id == concept.id) {
+            step.is_goal |= is_goal;
+            return;
+        }
+        if recurse {
+            for prerequisite in concept
+                .prerequisites
+                .iter()
+                .filter_map(|id| self::concept(id))
+            {
+                visit(prerequisite, false, recurse, steps);
+            }
+        }
+        steps.push(Step { concept, is_goal });
+    }
+
+    let mut steps = Vec::new();
+    for goal in goals {
+        visit(goal, true, include_prerequisites, &mut steps);
+    }
+    steps
+}
+
+/// Order chapters for reading: by source preference, then table-of-contents position
+pub fn reading_order(documents: &mut [Document]) {
+    let source_rank = |doc: &Document| {
+        LEARNING_SOURCES
+            .iter()
+            .position(|s| *s == doc.source)
+            .unwrap_or(LEARNING_SOURCES.len())
+    };
+    documents.sort_by_key(|doc| (source_rank(doc), doc.summary_order.unwrap_or(usize::MAX)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_puts_prerequisites_first() {
+        let goals = goal_concepts("learn lifetimes from scratch");
+        assert_eq!(goals.len(), 1);
+
+        let ids: Vec<&str> = plan(&goals, true).iter().map(|s| s.concept.id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "basics",
+                "ownership",
+                "borrowing",
+                "structs-enums",
+                "generics",
+                "lifetimes"
+            ]
+        );
+
+        let direct = plan(&goals, false);
+        assert_eq!(direct.len(), 1);
+        assert!(direct[0].is_goal);
+    }
+
+    #[test]
+    fn test_reading_order() {
+        let doc = |source: &str, path: &str, order: Option<usize>| Document {
+            source: source.to_string(),
+            path: path.to_string(),
+            summary_order: order,
+            ..Default::default()
+        };
+        let mut docs = vec![
+            doc("rust-by-example", "closures.md", Some(3)),
+            doc("rust-book", "ch13-01-closures.md", Some(90)),
+            doc("rust-book", "ch04-01-what-is-ownership.md", Some(20)),
+            doc("rust-book", "appendix.md", None),
+        ];
+
+        reading_order(&mut docs);
+        let paths: Vec<&str> = docs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "ch04-01-what-is-ownership.md",
+                "ch13-01-closures.md",
+                "appendix.md",
+                "closures.md",
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Search engine and MCP server over the Rust documentation.
+//!
+//! The binary serves this crate over MCP; other tools (editors, bots, CI checkers) can embed the
+//! search engine directly. The stable API is the search types re-exported here, plus
+//! [`sources`] for the documentation corpus and [`indexer`] for building the indices:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use rust_lang_mcp::{HybridSearch, SearchIndex, SearchMode, SearchOptions, VectorIndex};
+//!
+//! # fn main() -> rust_lang_mcp::Result<()> {
+//! let data_dir = Path::new("data");
+//! rust_lang_mcp::sources::clone_all_sources(data_dir, None)?;
+//! let keyword_index = SearchIndex::open_or_create(&data_dir.join("index"))?;
+//! rust_lang_mcp::indexer::index_all_sources(&keyword_index, data_dir, None)?;
+//!
+//! // Without vectors, hybrid search falls back to keyword search
+//! let vector_index = VectorIndex::new();
+//! let outcome = HybridSearch::new(&keyword_index, &vector_index).search_with_mode(
+//!     "borrow checker",
+//!     SearchMode::Hybrid,
+//!     &SearchOptions::new(5),
+//! )?;
+//! for result in outcome.results {
+//!     println!("{} ({})", result.title, result.path);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The remaining public modules (`server`, `http`, `config`, ...) are what the binary is built
+//! from; they may change between releases.
+
+pub mod archive;
+pub mod auth;
+pub mod budget;
+mod cargo_reference;
+mod cheatsheet;
+mod clippy;
+mod collections;
+mod concurrency;
+pub mod config;
+mod crate_docs;
+mod deprecations;
+mod diagnostics;
+mod editions;
+pub mod error;
+mod error_codes;
+mod ffi;
+pub mod http;
+mod idioms;
+mod index_manifest;
+pub mod indexer;
+mod learning;
+pub mod logging;
+mod macro_help;
+mod msrv;
+mod parse_report;
+pub mod parsing;
+mod platform_support;
+pub mod profiles;
+mod prompts;
+mod releases;
+pub mod repl;
+mod resources;
+mod response;
+mod rust_reference;
+mod rustc_flags;
+mod rustlings;
+pub mod search;
+pub mod server;
+pub mod sources;
+mod std_json;
+mod std_source;
+mod taxonomy;
+mod unsafe_review;
+mod workspace;
+
+pub use error::{Error, Result};
+pub use search::{
+    HybridSearch, QueryIntent, SearchIndex, SearchMode, SearchOptions, SearchOutcome, SearchResult,
+    VectorIndex,
+};
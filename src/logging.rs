@@ -0,0 +1,266 @@
+//! Log output setup and the per-tool-call log event.
+//!
+//! Logs go to stderr (stdout carries MCP on stdio) and, since MCP clients often discard stderr,
+//! optionally to rotating files under `<data_dir>/logs`. Every tool call emits one `tool_call` event
+//! with the tool name, a hash of its arguments, the latency, and the number of results, so a
+//! shared deployment can be monitored from its logs without recording what users searched for.
+//!
+//! Each call runs inside a `tool_call` span carrying a fresh request ID, so the events of the
+//! search stages (embedding, keyword search, ANN search, fusion) can be traced back to the call.
+//! The ID is also returned to the client with errors, to match a reported failure to the logs.
+
+use std::path::Path;
+use std::time::Duration;
+
+use rmcp::ErrorData as McpError;
+use rmcp::model::{CallToolResult, Content, JsonObject};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+use crate::config::{LogFileConfig, LogFormat, LogRotation, LoggingConfig};
+use crate::error::{Error, Result};
+
+/// Log files are named `rust-lang-mcp.<date>.log`
+const LOG_FILE_PREFIX: &str = "rust-lang-mcp";
+
+/// Install the global tracing subscriber.
+///
+/// The returned guard flushes the log file when dropped; keep it alive for the whole process.
+pub fn init(config: &LoggingConfig, data_dir: &Path) -> Result<Option<WorkerGuard>> {
+    // Each output has its own level filter
+    let mut layers = vec![
+        format_layer(config.format, std::io::stderr)
+            .with_filter(EnvFilter::from_default_env())
+            .boxed(),
+    ];
+
+    let mut guard = None;
+    if let Some(file_config) = &config.file {
+        let filter = EnvFilter::try_new(&file_config.level).map_err(|e| {
+            Error::Other(format!("Invalid log level {:?}: {}", file_config.level, e))
+        })?;
+        let (writer, file_guard) =
+            tracing_appender::non_blocking(file_appender(file_config, &data_dir.join("logs"))?);
+        layers.push(
+            format_layer(config.format, writer)
+                .with_filter(filter)
+                .boxed(),
+        );
+        guard = Some(file_guard);
+    }
+
+    tracing_subscriber::registry().with(layers).init();
+    Ok(guard)
+}
+
+/// A formatting layer writing text or JSON lines
+fn format_layer<W>(format: LogFormat, writer: W) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().flatten_event(true).boxed(),
+    }
+}
+
+/// Rotating appender writing to `dir`, keeping at most `max_files` files
+fn file_appender(config: &LogFileConfig, dir: &Path) -> Result<RollingFileAppender> {
+    let rotation = match config.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(config.max_files.max(1))
+        .build(dir)
+        .map_err(|e| Error::Other(format!("Failed to open log file in {:?}: {}", dir, e)))
+}
+
+/// A new request ID
+pub fn request_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Span for one tool call; everything logged while handling the call is recorded inside it
+pub fn tool_call_span(request_id: &str, tool: &str) -> tracing::Span {
+    tracing::info_span!("tool_call", request_id, tool)
+}
+
+/// Attach the request ID to a failed call, so the client can report it
+pub fn attach_request_id(
+    result: &mut std::result::Result<CallToolResult, McpError>,
+    request_id: &str,
+) {
+    match result {
+        Ok(output) if output.is_error == Some(true) => {
+            output
+                .content
+                .push(Content::text(format!("Request ID: {}", request_id)));
+        }
+        Ok(_) => {}
+        Err(e) => match &mut e.data {
+            Some(serde_json::Value::Object(data)) => {
+                data.insert("request_id".to_string(), request_id.into());
+            }
+            data => *data = Some(serde_json::json!({ "request_id": request_id })),
+        },
+    }
+}
+
+/// Stable hash of tool arguments (FNV-1a), to group identical queries without logging them
+pub fn query_hash(arguments: Option<&JsonObject>) -> String {
+    let text = arguments
+        .map(|args| serde_json::Value::Object(args.clone()).to_string())
+        .unwrap_or_default();
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Number of results in a tool's JSON output, when it has a `results` list (flat or grouped by
+/// source)
+pub fn result_count(result: &CallToolResult) -> Option<usize> {
+    let text = result.content.first()?.as_text()?;
+    let json: serde_json::Value = serde_json::from_str(&text.text).ok()?;
+    match json.get("results")? {
+        serde_json::Value::Array(results) => Some(results.len()),
+        serde_json::Value::Object(groups) => Some(
+            groups
+                .values()
+                .filter_map(|group| group.as_array())
+                .map(Vec::len)
+                .sum(),
+        ),
+        _ => None,
+    }
+}
+
+/// Emit the `tool_call` event for a finished call
+pub fn log_tool_call(
+    tool: &str,
+    query_hash: &str,
+    latency: Duration,
+    result: &std::result::Result<CallToolResult, McpError>,
+) {
+    let latency_ms = latency.as_millis() as u64;
+    match result {
+        Ok(output) => tracing::info!(
+            tool,
+            query_hash,
+            latency_ms,
+            result_count = result_count(output),
+            is_error = output.is_error.unwrap_or(false),
+            "tool_call"
+        ),
+        Err(e) => tracing::warn!(
+            tool,
+            query_hash,
+            latency_ms,
+            error = %e.message,
+            "tool_call"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_hash() {
+        let args = |query: &str| {
+            serde_json::json!({ "query": query })
+                .as_object()
+                .cloned()
+                .unwrap()
+        };
+
+        assert_eq!(
+            query_hash(Some(&args("lifetimes"))),
+            query_hash(Some(&args("lifetimes")))
+        );
+        assert_ne!(
+            query_hash(Some(&args("lifetimes"))),
+            query_hash(Some(&args("traits")))
+        );
+        assert_eq!(query_hash(None).len(), 16);
+    }
+
+    #[test]
+    fn test_attach_request_id() {
+        let mut failed: std::result::Result<CallToolResult, McpError> = Ok(CallToolResult::error(
+            vec![Content::text("Search failed: index locked")],
+        ));
+        attach_request_id(&mut failed, "abc123");
+        let output = failed.unwrap();
+        assert_eq!(
+            output.content[1].as_text().unwrap().text,
+            "Request ID: abc123"
+        );
+
+        let mut succeeded: std::result::Result<CallToolResult, McpError> =
+            Ok(CallToolResult::success(vec![Content::text("ok")]));
+        attach_request_id(&mut succeeded, "abc123");
+        assert_eq!(succeeded.unwrap().content.len(), 1);
+
+        let mut invalid = Err(McpError::invalid_params("missing query", None));
+        attach_request_id(&mut invalid, "abc123");
+        assert_eq!(
+            invalid.unwrap_err().data,
+            Some(serde_json::json!({ "request_id": "abc123" }))
+        );
+    }
+
+    #[test]
+    fn test_file_appender() {
+        let dir = std::env::temp_dir().join(format!("rust-lang-mcp-logs-{}", std::process::id()));
+        let config = LogFileConfig {
+            rotation: LogRotation::Never,
+            ..LogFileConfig::default()
+        };
+
+        let mut appender = file_appender(&config, &dir).unwrap();
+        std::io::Write::write_all(&mut appender, b"hello\n").unwrap();
+        let written = std::fs::read_to_string(dir.join("rust-lang-mcp.log")).unwrap();
+        assert_eq!(written, "hello\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_result_count() {
+        let output = |json: serde_json::Value| {
+            CallToolResult::success(vec![Content::text(json.to_string())])
+        };
+
+        assert_eq!(
+            result_count(&output(serde_json::json!({ "results": [1, 2, 3] }))),
+            Some(3)
+        );
+        assert_eq!(
+            result_count(&output(
+                serde_json::json!({ "results": { "rust-book": [1, 2], "rust-reference": [3] } })
+            )),
+            Some(3)
+        );
+        assert_eq!(
+            result_count(&output(serde_json::json!({ "cheat_sheet": "..." }))),
+            None
+        );
+        assert_eq!(
+            result_count(&CallToolResult::success(vec![Content::text(
+                "No results found"
+            )])),
+            None
+        );
+    }
+}
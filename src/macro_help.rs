@@ -0,0 +1,208 @@
+//! Macro-specific vocabulary used by the `macro_help` tool.
+//!
+//! Macro questions use jargon ("designator", "tt muncher", "$crate") that rarely matches the
+//! prose explaining it, so each topic expands the query and pins the chapters covering it.
+
+use crate::search::SearchResult;
+
+/// Sources with macro material, in order of preference
+pub const MACRO_SOURCES: &[&str] = &["rust-reference", "tlborm", "rust-book", "rust-by-example"];
+
+/// A macro topic recognized in queries
+#[derive(Debug)]
+pub struct MacroTopic {
+    /// Stable identifier used in tool output
+    pub id: &'static str,
+    /// Lowercase words or fragments that select this topic
+    pub keywords: &'static [&'static str],
+    /// Terms appended to the search query
+    pub expansion: &'static str,
+    /// `(source, path)` of the chapters covering the topic
+    pub targets: &'static [(&'static str, &'static str)],
+}
+
+const MACROS_BY_EXAMPLE: (&str, &str) = ("rust-reference", "macros-by-example.md");
+const PROC_MACROS: (&str, &str) = ("rust-reference", "procedural-macros.md");
+// The Book's macro chapter moved from ch19 to ch20 in the 2024 edition of the book
+const BOOK_MACROS: (&str, &str) = ("rust-book", "ch20-05-macros.md");
+const BOOK_MACROS_OLD: (&str, &str) = ("rust-book", "ch19-06-macros.md");
+
+/// Built-in macro topics
+pub const MACRO_TOPICS: &[MacroTopic] = &[
+    MacroTopic {
+        id: "fragment-specifiers",
+        keywords: &[
+            "fragment",
+            "specifier",
+            "designator",
+            "metavariable",
+            ":expr",
+            ":ident",
+            ":tt",
+            ":ty",
+            ":pat",
+            ":path",
+            ":block",
+            ":literal",
+            ":lifetime",
+            ":vis",
+            ":item",
+            ":meta",
+        ],
+        expansion: "fragment specifiers metavariables expr ident tt ty pat",
+        targets: &[MACROS_BY_EXAMPLE, ("tlborm", "fragment-specifiers.md")],
+    },
+    MacroTopic {
+        id: "repetition",
+        keywords: &["repetition", "repeat", "$(", "variadic", "separator"],
+        expansion: "repetitions $( ) * + ? separator",
+        targets: &[MACROS_BY_EXAMPLE, ("tlborm", "macro_rules.md")],
+    },
+    MacroTopic {
+        id: "hygiene",
+        keywords: &[
+            "hygiene",
+            "hygienic",
+            "$crate",
+            "span",
+            "local variable leak",
+        ],
+        expansion: "hygiene syntax context $crate",
+        targets: &[MACROS_BY_EXAMPLE, ("tlborm", "hygiene.md")],
+    },
+    MacroTopic {
+        id: "scoping",
+        keywords: &[
+            "macro_export",
+            "macro_use",
+            "scope",
+            "scoping",
+            "export",
+            "import a macro",
+        ],
+        expansion: "macro scoping textual scope path-based scope macro_export",
+        targets: &[
+            MACROS_BY_EXAMPLE,
+            ("tlborm", "scoping.md"),
+            ("tlborm", "import-export.md"),
+        ],
+    },
+    MacroTopic {
+        id: "declarative",
+        keywords: &[
+            "macro_rules",
+            "declarative",
+            "macros by example",
+            "macro by example",
+        ],
+        expansion: "macro_rules declarative macros by example",
+        targets: &[MACROS_BY_EXAMPLE, BOOK_MACROS, BOOK_MACROS_OLD],
+    },
+    MacroTopic {
+        id: "procedural",
+        keywords: &[
+            "proc-macro",
+            "proc macro",
+            "proc_macro",
+            "procedural",
+            "derive",
+            "attribute macro",
+            "function-like",
+            "tokenstream",
+            "syn",
+            "quote",
+        ],
+        expansion: "procedural macros proc_macro TokenStream derive attribute",
+        targets: &[PROC_MACROS, BOOK_MACROS, BOOK_MACROS_OLD],
+    },
+    MacroTopic {
+        id: "patterns",
+        keywords: &[
+            "tt muncher",
+            "muncher",
+            "internal rules",
+            "accumulation",
+            "callback",
+            "counting",
+        ],
+        expansion: "tt muncher internal rules push-down accumulation macro patterns",
+        targets: &[("tlborm", "tt-muncher.md"), ("tlborm", "internal-rules.md")],
+    },
+    MacroTopic {
+        id: "debugging",
+        keywords: &[
+            "debug",
+            "debugging",
+            "trace_macros",
+            "log_syntax",
+            "cargo expand",
+            "expand",
+        ],
+        expansion: "debugging macros trace_macros expansion",
+        targets: &[("tlborm", "debugging.md")],
+    },
+];
+
+/// Topics mentioned in a query
+pub fn detect_topics(query: &str) -> Vec<&'static MacroTopic> {
+    let lower = query.to_lowercase();
+    MACRO_TOPICS
+        .iter()
+        .filter(|topic| topic.keywords.iter().any(|k| lower.contains(k)))
+        .collect()
+}
+
+/// Append the expansions of the detected topics to a query
+pub fn expand_query(query: &str, topics: &[&MacroTopic]) -> String {
+    let mut expanded = format!("{} macro", query);
+    for topic in topics {
+        expanded.push(' ');
+        expanded.push_str(topic.expansion);
+    }
+    expanded
+}
+
+/// Move chapters covering the detected topics to the front, keeping relative order
+pub fn apply_boost(topics: &[&MacroTopic], results: &mut [SearchResult]) {
+    let is_target = |result: &SearchResult| {
+        topics.iter().any(|topic| {
+            topic
+                .targets
+                .iter()
+                .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+        })
+    };
+    results.sort_by_key(|result| !is_target(result));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(query: &str) -> Vec<&'static str> {
+        detect_topics(query).iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn test_detect_topics() {
+        assert_eq!(
+            ids("which designator matches a type, :ty or :path?"),
+            vec!["fragment-specifiers"]
+        );
+        assert_eq!(
+            ids("proc-macro vs macro_rules"),
+            vec!["declarative", "procedural"]
+        );
+        assert_eq!(
+            ids("why does my local variable leak through $crate"),
+            vec!["hygiene"]
+        );
+        assert!(ids("how do I write one").is_empty());
+    }
+
+    #[test]
+    fn test_expand_query() {
+        let topics = detect_topics("tt muncher");
+        assert!(expand_query("tt muncher", &topics).contains("push-down accumulation"));
+    }
+}
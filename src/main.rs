@@ -1,30 +1,377 @@
-mod error;
-mod indexer;
-mod parsing;
-mod search;
-mod server;
-mod sources;
-
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
-use tracing_subscriber::EnvFilter;
+use clap::{Parser, Subcommand};
+use rust_lang_mcp::{
+    archive, auth, budget, config, http, indexer, logging, profiles, repl, search, server, sources,
+};
+
+/// MCP server for searching the official Rust documentation
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serve MCP clients over stdio and the configured HTTP listener (the default)
+    Serve,
+    /// Clone the documentation sources and build the keyword and vector indices
+    Index {
+        /// Rebuild the indices even when they are already built
+        #[arg(long)]
+        force: bool,
+    },
+    /// Search the built index and print the results
+    Search {
+        /// What to search for, e.g. "how do lifetimes work"
+        query: String,
+        /// How to rank results: keyword and semantic scores combined, or one of them
+        #[arg(long, default_value = "hybrid", value_parser = ["hybrid", "keyword", "semantic"])]
+        mode: String,
+        /// Results to print (1-20)
+        #[arg(long, default_value_t = 5)]
+        limit: usize,
+    },
+    /// Pull the documentation sources and re-index what changed since the last build
+    Update,
+    /// Re-embed the indexed documentation with the configured model
+    Reembed,
+    /// Search and read the documentation interactively
+    Repl,
+    /// Pack the data directory into an archive for other machines
+    Export {
+        /// Archive to write, e.g. `rust-docs.tar.zst`
+        archive: PathBuf,
+        /// Leave the embedding models out; they are downloaded on first start
+        #[arg(long)]
+        without_models: bool,
+    },
+    /// Unpack an exported archive into the data directory
+    Import {
+        /// Archive written by `export`
+        archive: PathBuf,
+        /// Replace an existing index
+        #[arg(long)]
+        force: bool,
+    },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging to stderr (stdout is used for MCP communication)
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_writer(std::io::stderr)
-        .init();
+    let command = Cli::parse().command.unwrap_or(Command::Serve);
 
     // Data directory for docs and index
     let data_dir = std::env::var("RUST_MCP_DATA_DIR")
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("data"));
 
-    let server = server::RustDocServer::new(data_dir).await?;
-    server.run().await?;
+    let config::Config {
+        memory_budget_mb,
+        http,
+        auth,
+        logging,
+        sources,
+        embeddings,
+        search: search_config,
+        profiles,
+    } = config::Config::load(&data_dir)?;
+
+    // Initialize logging to stderr (stdout is used for MCP communication) and the log file
+    let _log_guard = logging::init(&logging, &data_dir)?;
+
+    // Configured books and pins apply to cloning, indexing, and the archive alike
+    sources::configure(&sources)?;
+
+    let budget = memory_budget_mb
+        .map(budget::MemoryBudget::from_mb)
+        .unwrap_or_default();
+
+    let (repl, search) = match command {
+        Command::Serve => (false, None),
+        Command::Repl => (true, None),
+        Command::Search { query, mode, limit } => {
+            let keyword_index = search::SearchIndex::open_or_create(&data_dir.join("index"))?;
+            if keyword_index.is_empty()? {
+                anyhow::bail!(
+                    "No index at {:?}; run `rust-lang-mcp index` first",
+                    data_dir
+                );
+            }
+            (
+                false,
+                Some((query, search::SearchMode::from(mode.as_str()), limit)),
+            )
+        }
+        Command::Index { force } => {
+            return index(&data_dir, &budget, &sources, embeddings.model_spec(), force);
+        }
+        Command::Update => {
+            return update(
+                &data_dir,
+                &budget,
+                sources.std_src.as_deref(),
+                embeddings.model_spec(),
+            );
+        }
+        Command::Reembed => return reembed(&data_dir, &budget, embeddings.model_spec()),
+        Command::Export {
+            archive,
+            without_models,
+        } => return export(&data_dir, &archive, !without_models),
+        Command::Import { archive, force } => {
+            return import(&archive, &data_dir, force, embeddings.model_spec());
+        }
+    };
+
+    // The REPL and one-off searches serve no network clients
+    let local = repl || search.is_some();
+    let http = http.filter(|_| !local);
+    let serve_stdio = !local && http.as_ref().is_none_or(|http| http.stdio);
+
+    // Listen before building the index, so probes can tell a server that is still indexing
+    // from one that is down
+    let slot = http::ServerSlot::default();
+    let network = match &http {
+        Some(http) => {
+            let authenticator = auth::Authenticator::new(auth);
+            let listener = http::bind(http, &authenticator).await?;
+            Some(tokio::spawn(http::serve(
+                listener,
+                slot.clone(),
+                authenticator,
+                http.root_policy(),
+            )))
+        }
+        None => None,
+    };
+
+    let server = server::RustDocServer::new(
+        data_dir,
+        budget,
+        sources,
+        embeddings.model_spec(),
+        &search_config,
+        profiles::Profiles::new(&profiles, search_config.profile.as_deref()),
+    )
+    .await?;
+    slot.set(server.clone());
+
+    // The REPL and one-off searches have no client to tell that first-run indexing is running
+    if local {
+        server.wait_for_index().await;
+    }
+    if repl {
+        tokio::task::spawn_blocking(move || repl::run(&server)).await??;
+        return Ok(());
+    }
+
+    if let Some((query, mode, limit)) = search {
+        // A one-off search waits for the semantic stage instead of falling back to keywords
+        if mode != search::SearchMode::Keyword {
+            server.wait_for_semantic().await;
+        }
+        tokio::task::spawn_blocking(move || repl::search(&server, &query, mode, limit)).await??;
+        return Ok(());
+    }
+
+    // Both transports share the server's indices and embedding model; when the stdio client
+    // disconnects, the HTTP listener keeps running
+    if serve_stdio {
+        server.run().await?;
+    }
+    if let Some(network) = network {
+        network.await??;
+    }
+
+    Ok(())
+}
+
+/// Clone the documentation sources and build both indices, so a deployment (or CI) starts
+/// with them instead of building them on first start
+fn index(
+    data_dir: &Path,
+    budget: &budget::MemoryBudget,
+    sources: &config::SourcesConfig,
+    model: &'static search::embeddings::ModelSpec,
+    force: bool,
+) -> Result<()> {
+    let mut keyword_index = search::SearchIndex::open_or_create(&data_dir.join("index"))?;
+    keyword_index.set_writer_heap(budget.writer_heap_bytes);
+    if !force && !keyword_index.is_empty()? {
+        anyhow::bail!(
+            "{:?} already has an index; run `rust-lang-mcp update` to refresh it, or `rust-lang-mcp index --force` to rebuild it",
+            data_dir
+        );
+    }
+
+    let started = std::time::Instant::now();
+    indexer::fetch_sources(data_dir, sources.mirror.as_deref(), |_| {});
+    let count = indexer::index_all_sources(&keyword_index, data_dir, sources.std_src.as_deref())?;
+    if count == 0 {
+        anyhow::bail!("No documentation sources found; check the network connection or mirror");
+    }
+    tracing::info!("Indexed {} documents in {:?}", count, started.elapsed());
+
+    let embeddings = load_embeddings(data_dir, model)?;
+    let (vector_index, stats) = indexer::reembed(
+        &keyword_index,
+        data_dir,
+        budget.embedding_batch_size,
+        &embeddings,
+    )?;
+    tracing::info!(
+        "Embedded {} sections with {} into {} vectors in {:?} ({} embeddings cached, {} computed)",
+        stats.sections,
+        model.id,
+        vector_index.len(),
+        started.elapsed(),
+        stats.cached,
+        stats.computed
+    );
+    Ok(())
+}
+
+/// Pull the latest commits of the cloned sources, then re-index the documentation that changed
+/// since the last build; a running server picks the changes up on restart
+fn update(
+    data_dir: &Path,
+    budget: &budget::MemoryBudget,
+    std_src: Option<&Path>,
+    model: &'static search::embeddings::ModelSpec,
+) -> Result<()> {
+    let mut keyword_index = search::SearchIndex::open_or_create(&data_dir.join("index"))?;
+    keyword_index.set_writer_heap(budget.writer_heap_bytes);
+    let started = std::time::Instant::now();
+    let pulls = sources::pull_all_sources(data_dir);
+    let pulled = pulls
+        .iter()
+        .filter(|(_, pulled)| matches!(pulled, Ok(true)));
+    let failed = pulls.iter().filter(|(_, pulled)| pulled.is_err());
+    tracing::info!(
+        "Pulled {} sources: {} updated, {} failed",
+        pulls.len(),
+        pulled.count(),
+        failed.count()
+    );
+    let (update, _) = indexer::update_all_sources(
+        &keyword_index,
+        data_dir,
+        std_src,
+        budget.embedding_batch_size,
+        &load_embeddings(data_dir, model)?,
+    )?;
+    tracing::info!(
+        "Updated the index in {:?}: {} documents added, {} changed, {} removed, {} unchanged",
+        started.elapsed(),
+        update.added,
+        update.updated,
+        update.removed,
+        update.unchanged
+    );
+    if let Some(stats) = update.embedding {
+        tracing::info!(
+            "Embedded {} sections ({} embeddings cached, {} computed)",
+            stats.sections,
+            stats.cached,
+            stats.computed
+        );
+    }
+    Ok(())
+}
+
+/// Re-embed the indexed documentation with the configured model, replacing the saved vector
+/// index; a running server picks the new vectors up on restart
+fn reembed(
+    data_dir: &Path,
+    budget: &budget::MemoryBudget,
+    model: &'static search::embeddings::ModelSpec,
+) -> Result<()> {
+    let keyword_index = search::SearchIndex::open_or_create(&data_dir.join("index"))?;
+    let started = std::time::Instant::now();
+    let embeddings = load_embeddings(data_dir, model)?;
+    let (vector_index, stats) = indexer::reembed(
+        &keyword_index,
+        data_dir,
+        budget.embedding_batch_size,
+        &embeddings,
+    )?;
+    tracing::info!(
+        "Re-embedded {} sections with {} into {} vectors in {:?} ({} embeddings cached, {} computed)",
+        stats.sections,
+        model.id,
+        vector_index.len(),
+        started.elapsed(),
+        stats.cached,
+        stats.computed
+    );
+    Ok(())
+}
+
+/// Load the configured embedding model, downloading it if necessary
+fn load_embeddings(
+    data_dir: &Path,
+    model: &'static search::embeddings::ModelSpec,
+) -> Result<search::embeddings::EmbeddingService> {
+    Ok(search::embeddings::EmbeddingService::load(
+        &data_dir.join("models"),
+        model,
+    )?)
+}
+
+/// Pack the data directory into an archive for other machines
+fn export(data_dir: &Path, archive: &Path, include_models: bool) -> Result<()> {
+    let started = std::time::Instant::now();
+    let manifest = archive::export(data_dir, archive, include_models)?;
+    tracing::info!(
+        "Exported {:?} to {:?} in {:?} ({} sources pinned, embedding models {})",
+        data_dir,
+        archive,
+        started.elapsed(),
+        manifest.sources.len(),
+        if include_models {
+            "included"
+        } else {
+            "left out"
+        }
+    );
+    Ok(())
+}
 
+/// Unpack an exported archive into the data directory
+fn import(
+    archive: &Path,
+    data_dir: &Path,
+    force: bool,
+    model: &'static search::embeddings::ModelSpec,
+) -> Result<()> {
+    let manifest = archive::import(archive, data_dir, force)?;
+    tracing::info!(
+        "Imported {:?} (exported by version {}) into {:?}",
+        archive,
+        manifest.version,
+        data_dir
+    );
+    for (source, commit) in &manifest.sources {
+        tracing::debug!("{} at {}", source, commit);
+    }
+    if let Some(built_with) = &manifest.embedding_model
+        && built_with.id != model.id
+    {
+        tracing::warn!(
+            "The archive's vectors were built with {}, but embeddings.model is {}; \
+             run `rust-lang-mcp reembed` or set embeddings.model to {}",
+            built_with.id,
+            model.id,
+            built_with.id
+        );
+    }
+    if !manifest.includes_models {
+        tracing::info!(
+            "The embedding model is not in the archive; it is downloaded on first start"
+        );
+    }
     Ok(())
 }
@@ -0,0 +1,165 @@
+//! When features became stable, for MSRV (minimum supported Rust version) questions.
+//!
+//! There are two records of a stabilization: the `#[stable(since)]` attribute of std items in
+//! the std rustdoc JSON, exact but limited to library APIs, and the release notes, which also
+//! cover language and Cargo features. An API's attribute is preferred; otherwise the oldest
+//! release-note entry that stabilizes the feature decides.
+
+use crate::releases::{Mention, Releases, Version};
+use crate::std_json::{StableItem, StdJson};
+
+/// Where a stabilization version comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Basis {
+    /// An item's `#[stable(since)]` attribute in the std rustdoc JSON
+    Rustdoc,
+    /// A stabilizing entry in the release notes
+    ReleaseNotes,
+    /// Mentions were found, but none stabilizes the feature
+    None,
+}
+
+impl Basis {
+    /// Name used in tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Basis::Rustdoc => "rustdoc",
+            Basis::ReleaseNotes => "release_notes",
+            Basis::None => "none",
+        }
+    }
+}
+
+/// The evidence of when a feature became stable
+#[derive(Debug)]
+pub struct Stability<'a> {
+    /// std items matching the feature, best match first
+    pub items: Vec<&'a StableItem>,
+    /// Release-note entries mentioning the feature: stabilizing entries first, oldest first
+    pub mentions: Vec<Mention<'a>>,
+}
+
+impl<'a> Stability<'a> {
+    /// Look a feature up: a std path (`OnceLock`, `Option::is_some_and`) in both records, prose
+    /// (`let else`, `async closures`) only in the release notes
+    pub fn find(std_json: &'a StdJson, releases: &'a Releases, feature: &str) -> Self {
+        let feature = feature.trim();
+        let items = if feature.contains(char::is_whitespace) {
+            Vec::new()
+        } else {
+            std_json.stable_items(feature)
+        };
+        let mut mentions = releases.mentions(&feature.replace("::", " "));
+        mentions.sort_by_key(|mention| (!mention.is_stabilization(), mention.release.version));
+        Self { items, mentions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.mentions.is_empty()
+    }
+
+    /// The version the feature became stable in, and what says so
+    pub fn since(&self) -> (Option<Version>, Basis) {
+        match (self.items.first(), self.mentions.first()) {
+            (Some(item), _) => (Some(item.since), Basis::Rustdoc),
+            (None, Some(mention)) if mention.is_stabilization() => {
+                (Some(mention.release.version), Basis::ReleaseNotes)
+            }
+            _ => (None, Basis::None),
+        }
+    }
+
+    /// Link to the notes of the release that stabilized the feature
+    pub fn citation(&self, releases: &Releases) -> Option<String> {
+        match self.since() {
+            (Some(_), Basis::ReleaseNotes) => Some(self.mentions[0].release.citation()),
+            (Some(version), _) => releases.get(version).map(|release| release.citation()),
+            (None, _) => None,
+        }
+    }
+}
+
+/// Parse an MSRV as written in a manifest: `1.70`, `1.70.0`, or `rust-version = "1.70"`
+pub fn parse_msrv(msrv: &str) -> Option<Version> {
+    let value = match msrv.split_once('=') {
+        Some((key, value)) if key.trim() == "rust-version" => value,
+        Some(_) => return None,
+        None => msrv,
+    };
+    Version::parse(value.trim().trim_matches('"'))
+}
+
+/// The `rust-version` manifest line for a version; `.0` patch releases are left out
+pub fn rust_version_line(version: Version) -> String {
+    let version = if version.patch == 0 {
+        format!("{}.{}", version.major, version.minor)
+    } else {
+        version.to_string()
+    };
+    format!("rust-version = \"{}\"", version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_msrv() {
+        let version = |minor, patch| Version {
+            major: 1,
+            minor,
+            patch,
+        };
+        assert_eq!(parse_msrv("1.70"), Some(version(70, 0)));
+        assert_eq!(parse_msrv(" 1.74.1 "), Some(version(74, 1)));
+        assert_eq!(parse_msrv("rust-version = \"1.65\""), Some(version(65, 0)));
+        assert_eq!(parse_msrv("edition = \"2021\""), None);
+        assert_eq!(parse_msrv("stable"), None);
+    }
+
+    #[test]
+    fn test_rust_version_line() {
+        let version = Version::parse("1.80.0").unwrap();
+        assert_eq!(rust_version_line(version), "rust-version = \"1.80\"");
+        let version = Version::parse("1.80.1").unwrap();
+        assert_eq!(rust_version_line(version), "rust-version = \"1.80.1\"");
+    }
+
+    #[test]
+    fn test_stability_from_release_notes() {
+        let releases = Releases::parse(
+            "\
+Version 1.65.0 (2022-11-03)
+==========================
+
+Language
+--------
+- [Stabilize `let`-`else` statements](https://github.com/rust-lang/rust/pull/93628/)
+
+Version 1.64.0 (2022-09-22)
+==========================
+
+Compatibility Notes
+-------------------
+- Linting `let`-`else` in macros
+",
+        );
+        let std_json = StdJson::default();
+        let stability = Stability::find(&std_json, &releases, "let-else");
+        assert_eq!(stability.mentions.len(), 2);
+        assert_eq!(
+            stability.since(),
+            (Version::parse("1.65.0"), Basis::ReleaseNotes)
+        );
+        assert!(
+            stability
+                .citation(&releases)
+                .unwrap()
+                .ends_with("#version-1650-2022-11-03")
+        );
+
+        let stability = Stability::find(&std_json, &releases, "linting");
+        assert_eq!(stability.since(), (None, Basis::None));
+        assert!(Stability::find(&std_json, &releases, "GATs").is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Per-source report of documentation files that failed to parse.
+//!
+//! The indexer records unreadable files, encoding errors, and mdBook includes pointing at missing
+//! files, and saves the report next to the index so the `parse_report` tool can show it after a
+//! restart.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// File the report is saved to, inside the index directory
+const REPORT_FILE: &str = "parse_report.json";
+
+/// Parse problems found during the last full index build
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ParseReport {
+    pub sources: Vec<SourceReport>,
+}
+
+/// Parse results for one documentation source
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SourceReport {
+    pub source: String,
+    /// Number of files indexed
+    pub parsed: usize,
+    /// Set when the source could not be read at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub problems: Vec<ParseProblem>,
+}
+
+/// A file that was skipped, or indexed with parts missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseProblem {
+    /// Path relative to the source's docs directory
+    pub path: String,
+    pub kind: ProblemKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// The file could not be read; it was skipped
+    Unreadable,
+    /// The file is not valid UTF-8; it was skipped
+    Encoding,
+    /// An mdBook include points at a missing file; the document was indexed without it
+    BrokenInclude,
+}
+
+impl ParseReport {
+    /// Load the report saved with an index, if there is one
+    pub fn load(index_dir: &Path) -> Option<Self> {
+        let json = std::fs::read_to_string(index_dir.join(REPORT_FILE)).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable parse report: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Save the report next to the index
+    pub fn save(&self, index_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(index_dir)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Other(format!("Failed to serialize parse report: {}", e)))?;
+        std::fs::write(index_dir.join(REPORT_FILE), json)?;
+        Ok(())
+    }
+
+    /// Total number of problems across all sources
+    pub fn problem_count(&self) -> usize {
+        self.sources.iter().map(|s| s.problems.len()).sum()
+    }
+}
+
+impl SourceReport {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Record a file that could not be read, telling encoding errors apart
+    pub fn unreadable(&mut self, path: String, error: &std::io::Error) {
+        let (kind, message) = if error.kind() == std::io::ErrorKind::InvalidData {
+            (ProblemKind::Encoding, "file is not valid UTF-8".to_string())
+        } else {
+            (ProblemKind::Unreadable, error.to_string())
+        };
+        self.problems.push(ParseProblem {
+            path,
+            kind,
+            message,
+        });
+    }
+
+    /// Record an include directive whose target does not exist
+    pub fn broken_include(&mut self, path: String, target: &str) {
+        self.problems.push(ParseProblem {
+            path,
+            kind: ProblemKind::BrokenInclude,
+            message: format!("included file {} not found", target),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_round_trip() {
+        let dir = std::env::temp_dir().join(format!("parse-report-{}", std::process::id()));
+
+        let mut source = SourceReport::new("rust-book");
+        source.parsed = 2;
+        source.unreadable(
+            "ch01.md".to_string(),
+            &std::io::Error::new(std::io::ErrorKind::InvalidData, "bad"),
+        );
+        source.broken_include("ch02.md".to_string(), "../listings/main.rs");
+        let report = ParseReport {
+            sources: vec![source],
+        };
+        report.save(&dir).unwrap();
+
+        let loaded = ParseReport::load(&dir).unwrap();
+        assert_eq!(loaded.problem_count(), 2);
+        assert_eq!(loaded.sources[0].problems[0].kind, ProblemKind::Encoding);
+        assert_eq!(
+            loaded.sources[0].problems[1].kind,
+            ProblemKind::BrokenInclude
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(ParseReport::load(&dir).is_none());
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use super::markdown::{Document, parse_markdown};
+
+/// Where a chunk sits in its document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    /// Anchor of the section heading, as mdBook generates it (e.g. `default-implementations`)
+    pub anchor: String,
+    /// Headings leading to the section: its H2, then the H3 when it is a subsection
+    pub breadcrumb: Vec<String>,
+}
+
+/// Key of a document across sources: `source/path`, as in its `rust-doc://` URI. Source IDs
+/// never contain `/`.
+pub fn document_key(source: &str, path: &str) -> String {
+    format!("{}/{}", source, path)
+}
+
+/// Key of a chunk in the vector index: the document key, followed by `#anchor` for sections.
+/// Document paths never contain `#`.
+pub fn chunk_key(source: &str, path: &str, section: Option<&Section>) -> String {
+    let document = document_key(source, path);
+    match section {
+        Some(section) => format!("{}#{}", document, section.anchor),
+        None => document,
+    }
+}
+
+/// Document key of a chunk key
+pub fn chunk_document_key(key: &str) -> &str {
+    key.split_once('#').map_or(key, |(document, _)| document)
+}
+
+/// Source and path of a chunk key
+pub fn chunk_source_path(key: &str) -> (&str, &str) {
+    chunk_document_key(key)
+        .split_once('/')
+        .unwrap_or(("", chunk_document_key(key)))
+}
+
+/// A heading that starts a chunk
+struct SectionStart {
+    /// Byte range of the heading in the document's markdown
+    start: usize,
+    end: usize,
+    section: Section,
+}
+
+/// Split a document into chunks at its H2 and H3 headings.
+///
+/// Each chunk is a `Document` with the title, path, source, and indexer-set fields of the whole
+/// document; its text, structure, and `section` are those of the section. Text before the first
+/// section heading is a chunk without a section. A heading directly followed by another one
+/// (a chapter's H2 before its first H3) is kept with the next section. Documents without
+/// section headings are a single chunk.
+pub fn chunk_document(doc: &Document) -> Vec<Document> {
+    let starts = section_starts(doc);
+    if starts.is_empty() {
+        return vec![doc.clone()];
+    }
+
+    let mut chunks = Vec::with_capacity(starts.len() + 1);
+    let preamble = doc.markdown[..starts[0].start].trim();
+    if !preamble.is_empty() {
+        chunks.push(section_chunk(doc, preamble.to_string(), None));
+    }
+
+    // Markdown of heading-only sections, waiting for the section they introduce
+    let mut pending = String::new();
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts
+            .get(i + 1)
+            .map_or(doc.markdown.len(), |next| next.start);
+        let text = doc.markdown[start.start..end].trim();
+        if !pending.is_empty() {
+            pending.push_str("\n\n");
+        }
+        pending.push_str(text);
+        let is_last = i + 1 == starts.len();
+        if doc.markdown[start.end..end].trim().is_empty() && !is_last {
+            continue;
+        }
+        let markdown = std::mem::take(&mut pending);
+        chunks.push(section_chunk(doc, markdown, Some(start.section.clone())));
+    }
+
+    // Checklist IDs of the title belong to the document, so to its first chunk
+    let missing: Vec<String> = doc
+        .checklist_ids
+        .iter()
+        .filter(|id| !chunks.iter().any(|chunk| chunk.checklist_ids.contains(id)))
+        .cloned()
+        .collect();
+    if let Some(first) = chunks.first_mut() {
+        first.checklist_ids.splice(0..0, missing);
+    }
+
+    chunks
+}
+
+/// Reassemble a document from its chunks, given in document order
+pub fn join_chunks(chunks: impl IntoIterator<Item = Document>) -> Option<Document> {
+    let mut chunks = chunks.into_iter();
+    let mut doc = chunks.next()?;
+    doc.section = None;
+    for chunk in chunks {
+        append(&mut doc.content, "\n", &chunk.content);
+        append(&mut doc.markdown, "\n\n", &chunk.markdown);
+        doc.headings.extend(chunk.headings);
+        doc.code_blocks.extend(chunk.code_blocks);
+        for term in chunk.code_terms {
+            if !doc.code_terms.contains(&term) {
+                doc.code_terms.push(term);
+            }
+        }
+        for id in chunk.checklist_ids {
+            if !doc.checklist_ids.contains(&id) {
+                doc.checklist_ids.push(id);
+            }
+        }
+    }
+    Some(doc)
+}
+
+/// Append `text` to `target`, separated from what is there
+fn append(target: &mut String, separator: &str, text: &str) {
+    if !target.is_empty() && !text.is_empty() {
+        target.push_str(separator);
+    }
+    target.push_str(text);
+}
+
+/// A chunk of `doc` with the given markdown
+fn section_chunk(doc: &Document, markdown: String, section: Option<Section>) -> Document {
+    let parsed = parse_markdown(&markdown, &doc.path, &doc.source);
+    Document {
+        title: doc.title.clone(),
+        content: parsed.content,
+        markdown,
+        path: doc.path.clone(),
+        source: doc.source.clone(),
+        checklist_ids: parsed.checklist_ids,
+        headings: parsed.headings,
+        code_blocks: parsed.code_blocks,
+        code_terms: parsed.code_terms,
+        summary_order: doc.summary_order,
+        categories: doc.categories.clone(),
+        section,
+    }
+}
+
+/// The H2 and H3 headings of a document's markdown, with their breadcrumbs and anchors
+fn section_starts(doc: &Document) -> Vec<SectionStart> {
+    let parser = Parser::new_ext(
+        &doc.markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES,
+    );
+
+    let mut starts = Vec::new();
+    // Heading being read: its start offset, level, and text
+    let mut heading: Option<(usize, HeadingLevel, String)> = None;
+    let mut parent: Option<String> = None;
+    // Anchors are unique among all headings of the page, the title included
+    let mut anchors: HashMap<String, usize> = HashMap::new();
+    unique_anchor(&mut anchors, &doc.title);
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((range.start, level, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, heading)) = heading.as_mut() {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((start, level, text)) = heading.take() else {
+                    continue;
+                };
+                let text = text.trim().to_string();
+                let anchor = unique_anchor(&mut anchors, &text);
+                let breadcrumb = if level == HeadingLevel::H2 {
+                    parent = Some(text.clone());
+                    vec![text.clone()]
+                } else if level == HeadingLevel::H3 {
+                    parent.iter().cloned().chain([text]).collect()
+                } else {
+                    continue;
+                };
+                starts.push(SectionStart {
+                    start,
+                    end: range.end,
+                    section: Section { anchor, breadcrumb },
+                });
+            }
+            _ => {}
+        }
+    }
+    starts
+}
+
+/// Anchor of a heading as mdBook generates it: lowercase, whitespace as `-`, punctuation other
+/// than `-` and `_` dropped, and `-1`, `-2`, ... appended to repeated anchors
+fn unique_anchor(anchors: &mut HashMap<String, usize>, heading: &str) -> String {
+    let anchor: String = heading
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_ascii_lowercase())
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect();
+    let seen = anchors.entry(anchor.clone()).or_insert(0);
+    *seen += 1;
+    match *seen {
+        1 => anchor,
+        n => format!("{}-{}", anchor, n - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(markdown: &str) -> Document {
+        let mut doc = parse_markdown(markdown, "ch10-02-traits.md", "rust-book");
+        doc.summary_order = Some(3);
+        doc
+    }
+
+    #[test]
+    fn test_chunks_by_section_heading() {
+        let doc = chapter(
+            "# Traits\n\nA trait defines shared behavior.\n\n## Defining a Trait\n\nUse `trait`.\n\n#### Note\n\nStill here.\n\n### Default Implementations\n\nBodies in the trait.\n\n## Traits as Parameters\n\nUse `impl Trait`.",
+        );
+        let chunks = chunk_document(&doc);
+
+        let sections: Vec<Option<(&str, Vec<&str>)>> = chunks
+            .iter()
+            .map(|chunk| {
+                chunk.section.as_ref().map(|section| {
+                    (
+                        section.anchor.as_str(),
+                        section.breadcrumb.iter().map(String::as_str).collect(),
+                    )
+                })
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                None,
+                Some(("defining-a-trait", vec!["Defining a Trait"])),
+                Some((
+                    "default-implementations",
+                    vec!["Defining a Trait", "Default Implementations"]
+                )),
+                Some(("traits-as-parameters", vec!["Traits as Parameters"])),
+            ]
+        );
+
+        // Every chunk keeps the document's identity; text and structure are the section's
+        assert!(chunks.iter().all(|chunk| chunk.title == "Traits"
+            && chunk.path == "ch10-02-traits.md"
+            && chunk.summary_order == Some(3)));
+        assert_eq!(chunks[0].content, "A trait defines shared behavior.");
+        assert!(chunks[1].content.contains("Still here."));
+        assert_eq!(chunks[1].headings, vec!["Defining a Trait", "Note"]);
+        assert_eq!(chunks[3].code_terms, vec!["impl Trait"]);
+        let key = chunk_key(
+            &chunks[2].source,
+            &chunks[2].path,
+            chunks[2].section.as_ref(),
+        );
+        assert_eq!(key, "rust-book/ch10-02-traits.md#default-implementations");
+        assert_eq!(chunk_document_key(&key), "rust-book/ch10-02-traits.md");
+        assert_eq!(
+            chunk_source_path("rust-by-example/flow_control/for.md#ranges"),
+            ("rust-by-example", "flow_control/for.md")
+        );
+    }
+
+    #[test]
+    fn test_heading_only_sections_join_the_next() {
+        let doc = chapter(
+            "# Traits\n\n## Traits\n\n### Defining a Trait\n\nUse `trait`.\n\n```rust\n// ## not a heading\nfn main() {}\n```\n\n## Summary\n\n## Summary",
+        );
+        let chunks = chunk_document(&doc);
+
+        assert_eq!(chunks.len(), 2);
+        let first = chunks[0].section.as_ref().unwrap();
+        assert_eq!(first.anchor, "defining-a-trait");
+        assert_eq!(first.breadcrumb, vec!["Traits", "Defining a Trait"]);
+        assert!(
+            chunks[0]
+                .markdown
+                .starts_with("## Traits\n\n### Defining a Trait")
+        );
+        assert_eq!(chunks[0].code_blocks.len(), 1);
+        // The last heading has no section to join; anchors repeating a heading (or the title)
+        // are numbered
+        assert_eq!(chunks[1].section.as_ref().unwrap().anchor, "summary-1");
+        assert!(chunks[0].markdown.starts_with("## Traits"));
+    }
+
+    #[test]
+    fn test_join_chunks_restores_the_document() {
+        let doc = chapter(
+            "# Traits (C-TRAIT)\n\nIntro with `dyn`.\n\n## Defining a Trait\n\nUse `trait` and `dyn`.\n\n### Default Implementations\n\n```rust\nfn summarize() {}\n```",
+        );
+        let chunks = chunk_document(&doc);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].checklist_ids, vec!["C-TRAIT"]);
+
+        let joined = join_chunks(chunks).unwrap();
+        assert_eq!(joined.markdown, doc.markdown);
+        assert_eq!(joined.headings, doc.headings);
+        assert_eq!(joined.code_blocks, doc.code_blocks);
+        assert_eq!(joined.code_terms, doc.code_terms);
+        assert_eq!(joined.checklist_ids, doc.checklist_ids);
+        assert_eq!(joined.section, None);
+        assert!(join_chunks(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_documents_without_sections_are_one_chunk() {
+        let doc = chapter("# Traits\n\nShort page.\n\n#### Aside\n\nText.");
+        let chunks = chunk_document(&doc);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, doc.content);
+        assert_eq!(chunks[0].section, None);
+    }
+}
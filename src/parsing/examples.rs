@@ -0,0 +1,98 @@
+//! Code examples of a document: its fenced code blocks, with their language and the prose
+//! that introduces them.
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use serde::Serialize;
+
+/// A fenced code block and the paragraph before it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CodeExample {
+    /// Fence language; untagged blocks and blocks tagged with rustdoc attributes are `rust`
+    pub language: String,
+    pub code: String,
+    /// The paragraph directly before the block, as markdown; empty when a heading or another
+    /// code block comes first
+    pub explanation: String,
+}
+
+/// Code examples of a document's (or chunk's) rendered `markdown`, in document order.
+///
+/// The rendering fences every code block with its language and drops rustdoc hidden lines from
+/// Rust blocks, so the code is what a reader of the book sees.
+pub fn code_examples(markdown: &str) -> Vec<CodeExample> {
+    let mut examples = Vec::new();
+    let mut explanation = String::new();
+    // Language and text of the code block being read
+    let mut block: Option<(String, String)> = None;
+
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { .. }) => explanation.clear(),
+            Event::End(TagEnd::Paragraph) => {
+                explanation = markdown[range].trim().to_string();
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.trim().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                block = Some((language, String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((language, code)) = block.take() {
+                    examples.push(CodeExample {
+                        language: if language.is_empty() {
+                            "rust".to_string()
+                        } else {
+                            language
+                        },
+                        code: code.trim_end().to_string(),
+                        explanation: std::mem::take(&mut explanation),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    examples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_markdown;
+
+    #[test]
+    fn test_code_examples_keep_language_and_explanation() {
+        let doc = parse_markdown(
+            "# Iterators\n\nIterators are lazy.\n\nCall `next` to advance one:\n\n```rust,editable\n# use std::iter;\nfn main() {\n    let mut it = [1, 2].iter();\n    it.next();\n}\n```\n\n```text\n1\n```\n\n## Cargo\n\n```toml\n[dependencies]\n```",
+            "trait/iter.md",
+            "rust-by-example",
+        );
+        let examples = code_examples(&doc.markdown);
+
+        assert_eq!(examples.len(), 3);
+        // Rustdoc attributes go, and so do hidden lines
+        assert_eq!(examples[0].language, "rust");
+        assert!(examples[0].code.starts_with("fn main() {"));
+        assert_eq!(examples[0].explanation, "Call `next` to advance one:");
+        // A block right after another, or after a heading, has no explanation of its own
+        assert_eq!(
+            (
+                examples[1].language.as_str(),
+                examples[1].explanation.as_str()
+            ),
+            ("text", "")
+        );
+        assert_eq!(
+            (examples[2].language.as_str(), examples[2].code.as_str()),
+            ("toml", "[dependencies]")
+        );
+        assert_eq!(examples[2].explanation, "");
+    }
+}
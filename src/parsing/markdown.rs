@@ -1,41 +1,88 @@
-use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use std::path::Path;
 
+use super::chunk::Section;
 use crate::error::Result;
 
+/// Longest inline code span kept as a code term
+const MAX_CODE_TERM_LEN: usize = 40;
+
 /// A parsed documentation document
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Document {
     /// Document title (first H1 heading or filename)
     pub title: String,
     /// Plain text content (markdown stripped)
     pub content: String,
-    /// Relative path to the source file
+    /// Content re-synthesized as markdown below the title: inline code in backticks, code blocks
+    /// fenced (Rust blocks cleaned like `code_blocks`), emphasis and links reduced to their text
+    pub markdown: String,
+    /// Path of the source file relative to the source's root, with `/` separators (e.g.
+    /// `flow_control/for.md`)
     pub path: String,
     /// Documentation source (e.g., "rust-book", "rust-reference")
     pub source: String,
+    /// API Guidelines checklist IDs from headings such as "Casing conforms to RFC 430 (C-CASE)"
+    pub checklist_ids: Vec<String>,
+    /// Section headings below the title, in document order
+    pub headings: Vec<String>,
+    /// Rust code blocks, with rustdoc hidden lines and mdBook includes removed
+    pub code_blocks: Vec<String>,
+    /// Distinct inline code spans (e.g. `Iterator`, `collect`), in order of first use
+    pub code_terms: Vec<String>,
+    /// Position of the chapter in the source's `SUMMARY.md` (set by the indexer)
+    pub summary_order: Option<usize>,
+    /// Best-practice category IDs such as `error-handling` (set by the indexer)
+    pub categories: Vec<String>,
+    /// The section a chunk of the document covers (set by chunking); `None` for whole
+    /// documents and the text before their first section
+    pub section: Option<Section>,
 }
 
-/// Parse a markdown file and extract its content
-pub fn parse_markdown_file(path: &Path, source: &str) -> Result<Document> {
+/// Parse a markdown file and extract its content; the document's path is relative to `root`
+pub fn parse_markdown_file(path: &Path, root: &Path, source: &str) -> Result<Document> {
     let markdown = std::fs::read_to_string(path)?;
-    let relative_path = path
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_default();
+    Ok(parse_markdown(
+        &markdown,
+        &relative_path(path, root),
+        source,
+    ))
+}
 
-    Ok(parse_markdown(&markdown, &relative_path, source))
+/// Path of a file below `root`, with `/` separators on every platform
+pub fn relative_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
 }
 
 /// Parse markdown content and extract title and plain text
-fn parse_markdown(markdown: &str, path: &str, source: &str) -> Document {
-    let parser = Parser::new(markdown);
+pub fn parse_markdown(markdown: &str, path: &str, source: &str) -> Document {
+    // Appendices and glossaries are tables and footnoted lists; without these extensions their
+    // rows run together and footnote definitions are dropped as link definitions
+    let parser = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES);
 
     let mut title: Option<String> = None;
     let mut content = String::new();
+    let mut rendered = String::new();
     let mut in_heading = false;
     let mut heading_level = 0;
     let mut current_heading = String::new();
+    let mut heading_markdown = String::new();
+    let mut checklist_ids = Vec::new();
+    let mut headings = Vec::new();
+    let mut code_blocks = Vec::new();
+    let mut code_terms: Vec<String> = Vec::new();
+    let mut code_block: Option<String> = None;
+    // Language and text of the code block being read (any language)
+    let mut fence: Option<(String, String)> = None;
+    let mut list_depth = 0usize;
+    // Inside an HTML `<dd>` whose text continues on the following lines
+    let mut in_definition = false;
+    // Where the rendering of the table cell being read starts, and the columns of the table
+    let mut cell_start: Option<usize> = None;
+    let mut columns = 0usize;
 
     for event in parser {
         match event {
@@ -43,42 +90,322 @@ fn parse_markdown(markdown: &str, path: &str, source: &str) -> Document {
                 in_heading = true;
                 heading_level = level as u8;
                 current_heading.clear();
+                heading_markdown.clear();
             }
             Event::End(TagEnd::Heading(_)) => {
                 in_heading = false;
-                // Use first H1 as title
+                // Use first H1 as title; it is not repeated in the markdown
                 if heading_level == 1 && title.is_none() {
                     title = Some(current_heading.clone());
+                } else if !heading_markdown.trim().is_empty() {
+                    rendered.push_str(&"#".repeat(heading_level as usize));
+                    rendered.push(' ');
+                    rendered.push_str(heading_markdown.trim());
+                    rendered.push_str("\n\n");
+                }
+                if let Some(id) = checklist_id(&current_heading) {
+                    checklist_ids.push(id);
+                }
+                if heading_level > 1 && !current_heading.trim().is_empty() {
+                    headings.push(current_heading.trim().to_string());
                 }
                 // Add heading to content
                 content.push_str(&current_heading);
                 content.push('\n');
             }
-            Event::Text(text) | Event::Code(text) => {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                if is_rust_code_block(&kind) {
+                    code_block = Some(String::new());
+                }
+                fence = Some((code_block_language(&kind), String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some(code) = code_block.take().and_then(|code| clean_code_block(&code)) {
+                    code_blocks.push(code);
+                }
+                if let Some((language, code)) = fence.take() {
+                    let code = if language == "rust" {
+                        clean_code_block(&code)
+                    } else {
+                        Some(code.trim_end().to_string()).filter(|code| !code.trim().is_empty())
+                    };
+                    if let Some(code) = code {
+                        rendered.push_str(&format!("```{}\n{}\n```\n\n", language, code));
+                    }
+                }
+            }
+            Event::Code(code) if in_heading => {
+                current_heading.push_str(&code);
+                heading_markdown.push_str(&inline_code(&code));
+            }
+            Event::Code(code) => {
+                let term = code.trim();
+                if !term.is_empty()
+                    && term.len() <= MAX_CODE_TERM_LEN
+                    && !code_terms.iter().any(|t| t == term)
+                {
+                    code_terms.push(term.to_string());
+                }
+                content.push_str(&code);
+                rendered.push_str(&inline_code(&code));
+            }
+            Event::Text(text) => {
+                if let Some(code) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
                 if in_heading {
                     current_heading.push_str(&text);
+                    heading_markdown.push_str(&text);
                 } else {
                     content.push_str(&text);
+                    match fence.as_mut() {
+                        Some((_, code)) => code.push_str(&text),
+                        None => rendered.push_str(&text),
+                    }
                 }
             }
             Event::SoftBreak | Event::HardBreak => {
                 content.push(' ');
+                rendered.push(' ');
+            }
+            Event::Start(Tag::List(_)) => {
+                // A nested list starts on its own line
+                if list_depth > 0 && !rendered.ends_with('\n') {
+                    rendered.push('\n');
+                }
+                list_depth += 1;
             }
-            Event::End(TagEnd::Paragraph) | Event::End(TagEnd::Item) => {
+            Event::End(TagEnd::List(_)) => {
+                list_depth = list_depth.saturating_sub(1);
+                if list_depth == 0 {
+                    rendered.push('\n');
+                }
+            }
+            Event::Start(Tag::Item) => {
+                rendered.push_str(&"  ".repeat(list_depth.saturating_sub(1)));
+                rendered.push_str("- ");
+            }
+            Event::End(TagEnd::Paragraph) => {
                 content.push('\n');
+                rendered.push_str(if list_depth > 0 { "\n" } else { "\n\n" });
+            }
+            Event::End(TagEnd::Item) => {
+                content.push('\n');
+                if !rendered.ends_with('\n') {
+                    rendered.push('\n');
+                }
+            }
+            // Tables keep their rows and columns: one row per line, cells separated by `|`
+            Event::Start(Tag::Table(alignments)) => columns = alignments.len(),
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => rendered.push('|'),
+            Event::Start(Tag::TableCell) => cell_start = Some(rendered.len()),
+            Event::End(TagEnd::TableCell) => {
+                if let Some(start) = cell_start.take() {
+                    let cell = rendered.split_off(start);
+                    rendered.push(' ');
+                    rendered.push_str(&cell.trim().replace('|', "\\|"));
+                    rendered.push_str(" |");
+                }
+                content.push(' ');
+            }
+            Event::End(TagEnd::TableHead) => {
+                rendered.push('\n');
+                rendered.push_str(&"|---".repeat(columns));
+                rendered.push_str("|\n");
+                content.push('\n');
+            }
+            Event::End(TagEnd::TableRow) => {
+                rendered.push('\n');
+                content.push('\n');
+            }
+            Event::End(TagEnd::Table) => rendered.push('\n'),
+            // Footnotes keep their labels, so references can be followed to the definitions
+            Event::FootnoteReference(label) => rendered.push_str(&format!("[^{}]", label)),
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                rendered.push_str(&format!("[^{}]: ", label));
+            }
+            // Definition lists in HTML (Cargo's command pages list their options this way) keep
+            // their text: each term on its own line, the definition indented below it
+            Event::Html(html) => {
+                let line = html.trim();
+                let indent = if line.starts_with("<dt") {
+                    in_definition = false;
+                    "- "
+                } else if in_definition || line.starts_with("<dd") {
+                    in_definition = !line.ends_with("</dd>");
+                    "  "
+                } else {
+                    ""
+                };
+                let text = html_text(&html);
+                if !text.is_empty() {
+                    content.push_str(&text.replace('`', ""));
+                    content.push('\n');
+                    rendered.push_str(indent);
+                    rendered.push_str(&text);
+                    rendered.push('\n');
+                }
+            }
+            Event::End(TagEnd::HtmlBlock)
+                if !rendered.is_empty() && !rendered.ends_with("\n\n") =>
+            {
+                rendered.push('\n');
             }
             _ => {}
         }
     }
 
     Document {
-        title: title.unwrap_or_else(|| path.to_string()),
+        title: title.unwrap_or_else(|| path.rsplit('/').next().unwrap_or(path).to_string()),
         content: content.trim().to_string(),
+        markdown: rendered.trim().to_string(),
         path: path.to_string(),
         source: source.to_string(),
+        checklist_ids,
+        headings,
+        code_blocks,
+        code_terms,
+        summary_order: None,
+        categories: Vec::new(),
+        section: None,
     }
 }
 
+/// Check whether a code block holds Rust (untagged blocks are Rust in all indexed books)
+fn is_rust_code_block(kind: &CodeBlockKind) -> bool {
+    code_block_language(kind) == "rust"
+}
+
+/// Fence language of a code block; untagged blocks are Rust, and so are blocks tagged only with
+/// rustdoc attributes, like the error index's `compile_fail,E0382`
+fn code_block_language(kind: &CodeBlockKind) -> String {
+    match kind {
+        CodeBlockKind::Indented => "rust".to_string(),
+        CodeBlockKind::Fenced(info) => match info.split(',').next().unwrap_or("").trim() {
+            "" => "rust".to_string(),
+            lang if is_rustdoc_attribute(lang) => "rust".to_string(),
+            lang => lang.to_string(),
+        },
+    }
+}
+
+/// A rustdoc code block attribute such as `ignore`, `edition2021`, or an error code
+fn is_rustdoc_attribute(tag: &str) -> bool {
+    const ATTRIBUTES: &[&str] = &[
+        "ignore",
+        "should_panic",
+        "no_run",
+        "compile_fail",
+        "test_harness",
+    ];
+    ATTRIBUTES.contains(&tag)
+        || tag.starts_with("edition")
+        || tag
+            .strip_prefix('E')
+            .is_some_and(|digits| digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Render an inline code span, with a longer delimiter when the code holds a backtick
+fn inline_code(code: &str) -> String {
+    if code.contains('`') {
+        format!("`` {} ``", code)
+    } else {
+        format!("`{}`", code)
+    }
+}
+
+/// Files referenced by mdBook include directives (`{{#include ../listings/main.rs:anchor}}`),
+/// without anchors or line ranges
+pub fn include_targets(markdown: &str) -> Vec<&str> {
+    let mut targets = Vec::new();
+    let mut rest = markdown;
+    while let Some(start) = rest.find("{{#") {
+        // `\{{#include ...}}` shows the directive literally
+        let escaped = rest[..start].ends_with('\\');
+        let after = &rest[start + 3..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let directive = &after[..end];
+        rest = &after[end + 2..];
+
+        let mut parts = directive.split_whitespace();
+        let (Some(name), Some(file)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if !escaped && matches!(name, "include" | "rustdoc_include" | "playground") {
+            targets.push(file.split(':').next().unwrap_or(file));
+        }
+    }
+    targets
+}
+
+/// Text of a line of HTML: tags and comments dropped, `<code>` as backticks, common entities
+/// decoded
+fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let end = if tag.starts_with("<!--") {
+            tag.find("-->").map(|end| end + 3)
+        } else {
+            tag.find('>').map(|end| end + 1)
+        };
+        let Some(end) = end else {
+            rest = "";
+            break;
+        };
+        let name = tag[1..end - 1]
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        if name == "code" || name == "/code" {
+            text.push('`');
+        }
+        rest = &tag[end..];
+    }
+    text.push_str(rest);
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drop rustdoc hidden lines (`# use std::io;`) and mdBook include directives
+fn clean_code_block(code: &str) -> Option<String> {
+    let lines: Vec<&str> = code
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim_start();
+            trimmed != "#" && !trimmed.starts_with("# ") && !trimmed.starts_with("{{#")
+        })
+        .collect();
+
+    let cleaned = lines.join("\n").trim().to_string();
+    (!cleaned.is_empty()).then_some(cleaned)
+}
+
+/// Extract a trailing checklist ID like `(C-CASE)` from a heading
+fn checklist_id(heading: &str) -> Option<String> {
+    let inner = heading.trim().strip_suffix(')')?;
+    let id = &inner[inner.rfind('(')? + 1..];
+
+    let is_id = id.starts_with("C-")
+        && id.len() > 2
+        && id
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-');
+    is_id.then(|| id.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,7 +431,74 @@ mod tests {
     #[test]
     fn test_parse_markdown_fallback_title() {
         let md = "No heading here, just content.";
-        let doc = parse_markdown(md, "fallback.md", "test");
+        let doc = parse_markdown(md, "guide/fallback.md", "test");
         assert_eq!(doc.title, "fallback.md");
+        assert_eq!(doc.path, "guide/fallback.md");
+    }
+
+    #[test]
+    fn test_parse_markdown_structure() {
+        let md = "# Iterators\n\nThe `Iterator` trait has `next`.\n\n## Adapters\n\nUse `map`.\n\n```rust,editable\n# fn main() {\nlet v: Vec<i32> = (1..4).map(|x| x * 2).collect();\n# }\n```\n\n```console\n$ cargo run\n```\n\n```rust\n{{#rustdoc_include ../listings/ch13/main.rs}}\n```";
+        let doc = parse_markdown(md, "iterators.md", "test");
+        assert_eq!(doc.headings, vec!["Adapters"]);
+        assert_eq!(doc.code_terms, vec!["Iterator", "next", "map"]);
+        assert_eq!(
+            doc.code_blocks,
+            vec!["let v: Vec<i32> = (1..4).map(|x| x * 2).collect();"]
+        );
+        assert!(doc.content.contains("The Iterator trait has next."));
+    }
+
+    #[test]
+    fn test_parse_markdown_renders_markdown() {
+        let md = "# Iterators\n\nThe `Iterator` trait has **`next`**.\n\n## Adapters\n\n- Use `map`\n- Then [`collect`](iter.html)\n\n```rust,editable\n# fn main() {\nlet v: Vec<i32> = (1..4).map(|x| x * 2).collect();\n# }\n```\n\n```console\n$ cargo run\n```";
+        let doc = parse_markdown(md, "iterators.md", "test");
+        assert_eq!(
+            doc.markdown,
+            "The `Iterator` trait has `next`.\n\n## Adapters\n\n- Use `map`\n- Then `collect`\n\n```rust\nlet v: Vec<i32> = (1..4).map(|x| x * 2).collect();\n```\n\n```console\n$ cargo run\n```"
+        );
+    }
+
+    #[test]
+    fn test_parse_markdown_keeps_html_text() {
+        let md = "# cargo-tree\n\n## OPTIONS\n\n<dl>\n<!-- generated -->\n<dt class=\"option-term\" id=\"option-cargo-tree--i\"><a class=\"option-anchor\" href=\"#option-cargo-tree--i\"></a><code>-i</code> <em>spec</em></dt>\n<dt class=\"option-term\"><code>--invert</code> <em>spec</em></dt>\n<dd class=\"option-desc\"><p>Show the reverse dependencies\n&amp; exit.</p>\n</dd>\n</dl>\n\nAfter.";
+        let doc = parse_markdown(md, "cargo-tree.md", "test");
+        assert!(doc.markdown.contains(
+            "- `-i` spec\n- `--invert` spec\n  Show the reverse dependencies\n  & exit.\n"
+        ));
+        assert!(doc.markdown.ends_with("\n\nAfter."));
+        assert!(doc.content.contains("-i spec"));
+        assert!(!doc.content.contains("generated"));
+    }
+
+    #[test]
+    fn test_parse_markdown_keeps_tables_and_footnotes() {
+        let md = "# Appendix B: Operators\n\n| Operator | Example | Explanation |\n|----------|---------|-------------|\n| `!` | `!expr` | Bitwise or logical complement |\n| `\\|` | `pat \\| pat` | Pattern alternatives[^alt] |\n\n[^alt]: Also in closures.\n";
+        let doc = parse_markdown(md, "appendix-02-operators.md", "rust-book");
+        assert_eq!(
+            doc.markdown,
+            "| Operator | Example | Explanation |\n|---|---|---|\n| `!` | `!expr` | Bitwise or logical complement |\n| `\\|` | `pat \\| pat` | Pattern alternatives[^alt] |\n\n[^alt]: Also in closures."
+        );
+        assert!(
+            doc.content
+                .contains("! !expr Bitwise or logical complement \n")
+        );
+        assert!(doc.content.ends_with("Also in closures."));
+    }
+
+    #[test]
+    fn test_include_targets() {
+        let md = "```rust\n{{#rustdoc_include ../listings/ch02/src/main.rs:print}}\n```\n\n{{#playground example.rs editable}}\n\nWrite \\{{#include file.rs}} to include a file. {{#title Intro}}";
+        assert_eq!(
+            include_targets(md),
+            vec!["../listings/ch02/src/main.rs", "example.rs"]
+        );
+    }
+
+    #[test]
+    fn test_parse_markdown_checklist_ids() {
+        let md = "# Naming\n\n<a id=\"c-case\"></a>\n## Casing conforms to RFC 430 (C-CASE)\n\nText.\n\n## Getter names follow Rust convention (C-GETTER)\n\n## Examples (see above)";
+        let doc = parse_markdown(md, "naming.md", "api-guidelines");
+        assert_eq!(doc.checklist_ids, vec!["C-CASE", "C-GETTER"]);
     }
 }
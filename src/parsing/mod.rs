@@ -1,3 +1,12 @@
+mod chunk;
+mod examples;
 mod markdown;
+mod summary;
 
-pub use markdown::{parse_markdown_file, Document};
+pub use chunk::{
+    Section, chunk_document, chunk_document_key, chunk_key, chunk_source_path, document_key,
+    join_chunks,
+};
+pub use examples::{CodeExample, code_examples};
+pub use markdown::{Document, include_targets, parse_markdown, parse_markdown_file, relative_path};
+pub use summary::parse_summary;
@@ -0,0 +1,44 @@
+use pulldown_cmark::{Event, Parser, Tag};
+
+/// Extract the chapter order from an mdBook `SUMMARY.md`.
+///
+/// Returns the paths of linked chapters in reading order, relative to the book's `src`
+/// directory like `Document::path`.
+pub fn parse_summary(markdown: &str) -> Vec<String> {
+    let mut chapters: Vec<String> = Vec::new();
+
+    for event in Parser::new(markdown) {
+        if let Event::Start(Tag::Link { dest_url, .. }) = event {
+            let target = dest_url.split('#').next().unwrap_or("");
+            if !target.ends_with(".md") {
+                continue;
+            }
+            let path = target.trim_start_matches("./").to_string();
+            if !chapters.contains(&path) {
+                chapters.push(path);
+            }
+        }
+    }
+
+    chapters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_summary_order() {
+        let md = "# Summary\n\n[Foreword](foreword.md)\n\n- [Getting Started](ch01-00-getting-started.md)\n    - [Installation](ch01-01-installation.md)\n- [Closures](fn/closures.md#capturing)\n    - [Capturing](./fn/closures/capture.md)\n- [Draft]()\n- [Installation again](ch01-01-installation.md)";
+        assert_eq!(
+            parse_summary(md),
+            vec![
+                "foreword.md",
+                "ch01-00-getting-started.md",
+                "ch01-01-installation.md",
+                "fn/closures.md",
+                "fn/closures/capture.md",
+            ]
+        );
+    }
+}
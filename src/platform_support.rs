@@ -0,0 +1,407 @@
+//! Rust platform support.
+//!
+//! The rustc book lists every target in the tables of `platform-support.md`, one table per
+//! tier, and documents many targets on their own page under `platform-support/`. Like the rustc
+//! flag pages they are downloaded from rust-lang/rust. The pages are indexed for search, and the
+//! `platform_support` tool reads a target's tier, std support and host tools from the tables.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::rustc_flags::{download_markdown_directory, fetch};
+
+/// Source ID of the platform support pages
+pub const PLATFORM_SOURCE: &str = "platform-support";
+
+/// The tier tables, from the rustc book's sources
+const PLATFORM_SUPPORT_URL: &str =
+    "https://raw.githubusercontent.com/rust-lang/rust/HEAD/src/doc/rustc/src/platform-support.md";
+
+/// GitHub API listing of the per-target pages
+const TARGET_PAGES_LISTING: &str =
+    "https://api.github.com/repos/rust-lang/rust/contents/src/doc/rustc/src/platform-support";
+
+const PLATFORM_SUPPORT_FILE: &str = "platform-support.md";
+
+/// Local directory of the per-target pages
+const TARGETS_DIR: &str = "targets";
+
+const RUSTC_BOOK_URL: &str = "https://doc.rust-lang.org/rustc";
+
+/// How much of the standard library a target has, from the tables' `std` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdSupport {
+    /// The full standard library (✓)
+    Full,
+    /// `no_std` development only: `core`, and usually `alloc` (*)
+    NoStd,
+    /// Unknown or a work in progress (?)
+    Unknown,
+}
+
+impl StdSupport {
+    /// Name used in tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StdSupport::Full => "full",
+            StdSupport::NoStd => "no_std",
+            StdSupport::Unknown => "unknown",
+        }
+    }
+}
+
+/// A row of the platform support tables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    /// Target triple, e.g. `aarch64-unknown-linux-musl`
+    pub triple: String,
+    pub tier: u8,
+    pub std: StdSupport,
+    /// Whether rustc and cargo run on the target; `None` when the table marks it unknown
+    pub host_tools: Option<bool>,
+    /// The row's notes, e.g. "ARM64 Linux with musl 1.2.3"
+    pub notes: String,
+    /// File name of the target's own page, e.g. `aarch64-unknown-linux-musl.md`
+    pub page: Option<String>,
+}
+
+impl Target {
+    /// The target's page on doc.rust-lang.org, or the tier tables
+    pub fn docs_url(&self) -> String {
+        match &self.page {
+            Some(page) => format!(
+                "{}/platform-support/{}.html",
+                RUSTC_BOOK_URL,
+                page.trim_end_matches(".md")
+            ),
+            None => format!("{}/platform-support.html", RUSTC_BOOK_URL),
+        }
+    }
+
+    /// Path of the target's page among the indexed platform support documents
+    pub fn document_path(&self) -> Option<String> {
+        let page = self.page.as_deref()?;
+        Some(format!("{}/{}", TARGETS_DIR, page))
+    }
+
+    /// What the tier promises
+    pub fn guarantee(&self) -> &'static str {
+        match self.tier {
+            1 => "Guaranteed to work: built and fully tested on every change",
+            2 => "Guaranteed to build: built on every change, but tests are not necessarily run",
+            _ => "Supported in the codebase only: no official builds, and it may not build",
+        }
+    }
+
+    /// How to get the standard library for the target
+    pub fn install(&self) -> String {
+        if self.tier <= 2 {
+            format!("rustup target add {}", self.triple)
+        } else {
+            format!(
+                "No prebuilt standard library; build it with `cargo +nightly build -Z build-std --target {}`",
+                self.triple
+            )
+        }
+    }
+}
+
+/// The targets of the platform support tables
+#[derive(Debug, Default)]
+pub struct PlatformSupport {
+    targets: Vec<Target>,
+}
+
+impl PlatformSupport {
+    /// Directory of the downloaded pages; the indexer reads it as the source's docs
+    pub fn dir(data_dir: &Path) -> PathBuf {
+        data_dir.join(PLATFORM_SOURCE)
+    }
+
+    /// Download the tier tables and the per-target pages if they are missing, returning how
+    /// many files were written
+    pub fn download(data_dir: &Path) -> Result<usize> {
+        let dir = Self::dir(data_dir);
+        std::fs::create_dir_all(&dir)?;
+        let mut written = 0;
+
+        let tables = dir.join(PLATFORM_SUPPORT_FILE);
+        if !tables.exists() {
+            tracing::info!("Downloading {}...", PLATFORM_SUPPORT_URL);
+            std::fs::write(&tables, fetch(PLATFORM_SUPPORT_URL)?)?;
+            written += 1;
+        }
+        let targets = dir.join(TARGETS_DIR);
+        if !targets.exists() {
+            tracing::info!("Downloading the rustc book's target pages...");
+            written += download_markdown_directory(TARGET_PAGES_LISTING, &targets)?;
+        }
+        Ok(written)
+    }
+
+    /// Load the downloaded tier tables; empty when they are missing
+    pub fn load(data_dir: &Path) -> Self {
+        let path = Self::dir(data_dir).join(PLATFORM_SUPPORT_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(markdown) => {
+                let support = Self::parse(&markdown);
+                tracing::info!(
+                    "Loaded platform support for {} targets",
+                    support.targets.len()
+                );
+                support
+            }
+            Err(e) => {
+                tracing::debug!("No platform support tables at {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Read the targets from the tables under each `## Tier N ...` heading
+    pub fn parse(markdown: &str) -> Self {
+        let mut targets = Vec::new();
+        let mut tier: Option<(u8, Option<bool>)> = None;
+        let mut columns: Vec<String> = Vec::new();
+
+        for line in markdown.lines() {
+            if let Some(heading) = line.strip_prefix("## ") {
+                tier = parse_tier_heading(heading);
+                columns.clear();
+                continue;
+            }
+            let Some((tier, tier_host)) = tier else {
+                continue;
+            };
+            if !line.contains('|') {
+                continue;
+            }
+            let cells = table_cells(line);
+            if cells
+                .first()
+                .is_some_and(|cell| cell.eq_ignore_ascii_case("target"))
+            {
+                columns = cells.iter().map(|cell| cell.to_lowercase()).collect();
+                continue;
+            }
+            // The delimiter row
+            if line.chars().all(|c| matches!(c, '-' | ':' | '|' | ' ')) {
+                continue;
+            }
+            let cell = |name: &str| {
+                columns
+                    .iter()
+                    .position(|column| column == name)
+                    .and_then(|i| cells.get(i))
+                    .map(String::as_str)
+            };
+            let Some((triple, page)) = cells.first().and_then(|cell| target_cell(cell)) else {
+                continue;
+            };
+
+            let std = match cell("std") {
+                // Tables of targets with host tools have no std column: they have the full std
+                None => StdSupport::Full,
+                Some(mark) if mark.contains('✓') => StdSupport::Full,
+                Some(mark) if mark.contains('*') => StdSupport::NoStd,
+                Some(_) => StdSupport::Unknown,
+            };
+            let host_tools = match (tier_host, cell("host")) {
+                (Some(host), _) => Some(host),
+                (None, Some(mark)) if mark.contains('✓') => Some(true),
+                (None, Some(mark)) if mark.contains('?') => None,
+                (None, _) => Some(false),
+            };
+            targets.push(Target {
+                triple,
+                tier,
+                std,
+                host_tools,
+                notes: cell("notes").map(strip_footnotes).unwrap_or_default(),
+                page,
+            });
+        }
+        Self { targets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// The target with this exact triple (case-insensitive)
+    pub fn get(&self, triple: &str) -> Option<&Target> {
+        let triple = triple.trim().trim_matches('`');
+        self.targets
+            .iter()
+            .find(|target| target.triple.eq_ignore_ascii_case(triple))
+    }
+
+    /// Up to `limit` triples sharing the most components with `triple` (architecture, vendor,
+    /// OS, environment), for suggestions
+    pub fn similar(&self, triple: &str, limit: usize) -> Vec<&str> {
+        let wanted: Vec<String> = triple
+            .trim()
+            .to_lowercase()
+            .split('-')
+            .map(str::to_string)
+            .collect();
+        let mut scored: Vec<(usize, &str)> = self
+            .targets
+            .iter()
+            .map(|target| {
+                let shared = target
+                    .triple
+                    .split('-')
+                    .filter(|part| wanted.iter().any(|w| w == part))
+                    .count();
+                (shared, target.triple.as_str())
+            })
+            .filter(|(shared, _)| *shared > 0)
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, triple)| triple)
+            .collect()
+    }
+}
+
+/// Tier and host tools status of a `## Tier 2 with Host Tools` heading; host tools are read from
+/// the table when the heading doesn't say
+fn parse_tier_heading(heading: &str) -> Option<(u8, Option<bool>)> {
+    let heading = heading.trim().to_lowercase();
+    let rest = heading.strip_prefix("tier ")?;
+    let tier: u8 = rest.split_whitespace().next()?.parse().ok()?;
+    let host = if rest.contains("without host tools") {
+        Some(false)
+    } else if rest.contains("with host tools") {
+        Some(true)
+    } else {
+        None
+    };
+    Some((tier, host))
+}
+
+/// Cells of a markdown table row, with the outer pipes optional
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|')
+        .map(|cell| cell.trim().to_string())
+        .collect()
+}
+
+/// Triple and page of a target cell: `` [`x86_64-unknown-linux-musl`](platform-support/x.md) ``
+fn target_cell(cell: &str) -> Option<(String, Option<String>)> {
+    let start = cell.find('`')? + 1;
+    let end = start + cell[start..].find('`')?;
+    let triple = cell[start..end].trim().to_string();
+    let page = cell
+        .split_once("](")
+        .and_then(|(_, link)| link.split(')').next())
+        .map(|link| link.rsplit('/').next().unwrap_or(link).to_string())
+        .filter(|page| page.ends_with(".md"));
+    Some((triple, page))
+}
+
+/// Drop footnote references (`[^musl]`) from a cell
+fn strip_footnotes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[^") {
+        stripped.push_str(&rest[..start]);
+        match rest[start..].find(']') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => rest = "",
+        }
+    }
+    stripped.push_str(rest);
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLES: &str = "\
+# Platform Support
+
+## Tier 1 with Host Tools
+
+| target | notes |
+|--------|-------|
+[`aarch64-apple-darwin`](platform-support/apple-darwin.md) | ARM64 macOS (11.0+, Big Sur+)
+`x86_64-unknown-linux-gnu` | 64-bit Linux (kernel 3.2+, glibc 2.17+)
+
+## Tier 2 with Host Tools
+
+target | notes
+-------|-------
+`aarch64-unknown-linux-musl` | ARM64 Linux with musl 1.2.3
+
+## Tier 2 without Host Tools
+
+target | std | notes
+-------|:---:|-------
+[`aarch64-apple-ios`](platform-support/apple-ios.md) | ✓ | ARM64 iOS
+`thumbv7em-none-eabihf` | * | Bare Armv7E-M, hardfloat
+
+## Tier 3
+
+target | std | host | notes
+-------|:---:|:----:|-------
+`aarch64-unknown-freebsd` | ✓ | ✓ | ARM64 FreeBSD
+`aarch64-unknown-hermit` | ✓ |  | ARM64 Hermit
+`x86_64-unknown-l4re-uclibc` | ? |  | [^l4re]
+
+[^l4re]: L4Re.
+";
+
+    #[test]
+    fn test_parse_tables() {
+        let support = PlatformSupport::parse(TABLES);
+        assert_eq!(support.targets.len(), 8);
+
+        let darwin = support.get("aarch64-apple-darwin").unwrap();
+        assert_eq!(darwin.tier, 1);
+        assert_eq!(darwin.std, StdSupport::Full);
+        assert_eq!(darwin.host_tools, Some(true));
+        assert_eq!(darwin.page.as_deref(), Some("apple-darwin.md"));
+        assert_eq!(
+            darwin.docs_url(),
+            "https://doc.rust-lang.org/rustc/platform-support/apple-darwin.html"
+        );
+
+        let musl = support.get("`AARCH64-unknown-linux-musl`").unwrap();
+        assert_eq!((musl.tier, musl.host_tools), (2, Some(true)));
+        assert_eq!(musl.notes, "ARM64 Linux with musl 1.2.3");
+        assert_eq!(
+            musl.install(),
+            "rustup target add aarch64-unknown-linux-musl"
+        );
+
+        let thumb = support.get("thumbv7em-none-eabihf").unwrap();
+        assert_eq!(thumb.std, StdSupport::NoStd);
+        assert_eq!(thumb.host_tools, Some(false));
+
+        let freebsd = support.get("aarch64-unknown-freebsd").unwrap();
+        assert_eq!((freebsd.tier, freebsd.host_tools), (3, Some(true)));
+        let hermit = support.get("aarch64-unknown-hermit").unwrap();
+        assert_eq!(hermit.host_tools, Some(false));
+        let l4re = support.get("x86_64-unknown-l4re-uclibc").unwrap();
+        assert_eq!(l4re.std, StdSupport::Unknown);
+        assert_eq!(l4re.notes, "");
+    }
+
+    #[test]
+    fn test_similar() {
+        let support = PlatformSupport::parse(TABLES);
+        assert_eq!(
+            support.similar("aarch64-linux-musl", 2),
+            vec!["aarch64-unknown-linux-musl", "aarch64-apple-darwin"]
+        );
+        assert!(support.similar("mips", 3).is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+//! Corpus profiles: named sets of documentation sources with ranking boosts.
+//!
+//! A beginner is better served by the Book and Rust by Example than by the Reference's grammar,
+//! and someone writing unsafe code the other way around. A profile restricts searches to its
+//! sources and ranks some of them higher. The built-in profiles can be redefined and new ones
+//! added in the config file's `[profiles]` section; `search.profile` picks the deployment's
+//! default, and the search tools take a `profile` parameter per call.
+
+use std::collections::BTreeMap;
+
+use crate::config::{ProfileConfig, SourcesConfig};
+use crate::error::{Error, Result};
+use crate::error_codes::ERROR_CODES_SOURCE;
+use crate::platform_support::PLATFORM_SOURCE;
+use crate::releases::RELEASES_SOURCE;
+use crate::search::SearchOptions;
+use crate::sources::DOC_SOURCES;
+use crate::std_source::STD_SOURCE;
+use crate::workspace::WORKSPACE_SOURCE;
+
+/// Profile searching every source
+pub const FULL_PROFILE: &str = "full";
+
+/// A profile that exists without configuration
+struct BuiltIn {
+    name: &'static str,
+    sources: Option<&'static [&'static str]>,
+    boosts: &'static [(&'static str, f32)],
+}
+
+const BUILT_IN: &[BuiltIn] = &[
+    BuiltIn {
+        name: "beginner",
+        sources: Some(&["rust-book", "rust-by-example", "rustlings"]),
+        boosts: &[("rust-book", 1.2)],
+    },
+    BuiltIn {
+        name: "advanced",
+        sources: Some(&["rust-reference", "rustonomicon", "unsafe-code-guidelines"]),
+        boosts: &[],
+    },
+    BuiltIn {
+        name: FULL_PROFILE,
+        sources: None,
+        boosts: &[],
+    },
+];
+
+/// A named source set
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// Sources searched; every source when `None`
+    pub sources: Option<Vec<String>>,
+    /// Ranking multipliers by source
+    pub boosts: Vec<(String, f32)>,
+}
+
+impl Profile {
+    /// Restrict a search to the profile's sources and add its boosts.
+    ///
+    /// A tool that searches specific sources keeps those the profile also has; when the profile
+    /// has none of them, the tool's sources are kept, so a profile never empties a tool.
+    pub fn apply(&self, options: SearchOptions) -> SearchOptions {
+        let sources = match (&options.sources, &self.sources) {
+            (tool, None) => tool.clone(),
+            (None, Some(profile)) => Some(profile.clone()),
+            (Some(tool), Some(profile)) => {
+                let both: Vec<String> = tool
+                    .iter()
+                    .filter(|source| profile.contains(source))
+                    .cloned()
+                    .collect();
+                Some(if both.is_empty() { tool.clone() } else { both })
+            }
+        };
+        SearchOptions { sources, ..options }.with_source_boosts(&self.boosts)
+    }
+}
+
+/// The built-in and configured profiles, and the deployment default
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    default: Option<String>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new(), None)
+    }
+}
+
+impl Profiles {
+    /// Built-in profiles, replaced or extended by the configured ones (already validated)
+    pub fn new(configured: &BTreeMap<String, ProfileConfig>, default: Option<&str>) -> Self {
+        let mut profiles: Vec<Profile> = BUILT_IN
+            .iter()
+            .filter(|built_in| !configured.contains_key(built_in.name))
+            .map(|built_in| Profile {
+                name: built_in.name.to_string(),
+                sources: built_in
+                    .sources
+                    .map(|sources| sources.iter().map(|s| s.to_string()).collect()),
+                boosts: built_in
+                    .boosts
+                    .iter()
+                    .map(|(source, boost)| (source.to_string(), *boost))
+                    .collect(),
+            })
+            .collect();
+        profiles.extend(configured.iter().map(|(name, config)| {
+            Profile {
+                name: name.clone(),
+                sources: config.sources.clone(),
+                boosts: config
+                    .boosts
+                    .iter()
+                    .map(|(source, boost)| (source.clone(), *boost))
+                    .collect(),
+            }
+        }));
+        Self {
+            profiles,
+            default: default.map(str::to_string),
+        }
+    }
+
+    /// The profile with this name (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        let name = name.trim();
+        self.profiles
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Profile names, for error messages
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// Options of a search using the requested profile, or the deployment default when none is
+    /// requested; an unknown profile is an error naming the known ones
+    pub fn apply(&self, requested: Option<&str>, options: SearchOptions) -> Result<SearchOptions> {
+        let Some(name) = requested.or(self.default.as_deref()) else {
+            return Ok(options);
+        };
+        match self.get(name) {
+            Some(profile) => Ok(profile.apply(options)),
+            None => Err(Error::Other(format!(
+                "Unknown profile '{}'. Available profiles: {}",
+                name,
+                self.names().join(", ")
+            ))),
+        }
+    }
+}
+
+/// Check configured profiles and the default profile name
+pub fn validate(
+    configured: &BTreeMap<String, ProfileConfig>,
+    default: Option<&str>,
+    source_config: &SourcesConfig,
+) -> Result<()> {
+    for (name, profile) in configured {
+        let sources = profile.sources.iter().flatten();
+        for source in sources.chain(profile.boosts.keys()) {
+            if !is_known_source(source) && !source_config.books.contains_key(source) {
+                return Err(Error::Other(format!(
+                    "Unknown source {:?} in profiles.{}",
+                    source, name
+                )));
+            }
+        }
+        if let Some((source, boost)) = profile.boosts.iter().find(|(_, boost)| **boost <= 0.0) {
+            return Err(Error::Other(format!(
+                "profiles.{}.boosts.{} must be positive, got {}",
+                name, source, boost
+            )));
+        }
+    }
+    if let Some(default) = default
+        && Profiles::new(configured, None).get(default).is_none()
+    {
+        return Err(Error::Other(format!(
+            "search.profile {:?} is not a built-in or configured profile",
+            default
+        )));
+    }
+    Ok(())
+}
+
+/// Whether documents can come from a built-in source with this ID
+pub(crate) fn is_known_source(id: &str) -> bool {
+    DOC_SOURCES.iter().any(|source| source.id == id)
+        || [
+            STD_SOURCE,
+            RELEASES_SOURCE,
+            PLATFORM_SOURCE,
+            ERROR_CODES_SOURCE,
+            WORKSPACE_SOURCE,
+        ]
+        .contains(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured(sources: &[&str], boosts: &[(&str, f32)]) -> ProfileConfig {
+        ProfileConfig {
+            sources: Some(sources.iter().map(|s| s.to_string()).collect()),
+            boosts: boosts.iter().map(|(s, b)| (s.to_string(), *b)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_apply_intersects_tool_sources() {
+        let profiles = Profiles::default();
+        let beginner = profiles.get("Beginner").unwrap();
+
+        let all = beginner.apply(SearchOptions::new(5));
+        assert_eq!(all.sources.unwrap().len(), 3);
+        assert_eq!(all.source_boosts, [("rust-book".to_string(), 1.2)]);
+
+        let concepts = beginner
+            .apply(SearchOptions::new(5).with_sources(Some(&["rust-book", "rust-reference"])));
+        assert_eq!(concepts.sources.unwrap(), ["rust-book"]);
+
+        // The profile has none of the tool's sources, so the tool keeps them
+        let practices =
+            beginner.apply(SearchOptions::new(5).with_sources(Some(&["api-guidelines"])));
+        assert_eq!(practices.sources.unwrap(), ["api-guidelines"]);
+
+        let full = profiles
+            .get(FULL_PROFILE)
+            .unwrap()
+            .apply(SearchOptions::new(5));
+        assert!(full.sources.is_none());
+    }
+
+    #[test]
+    fn test_configured_profiles_and_default() {
+        let mut config = BTreeMap::new();
+        config.insert("beginner".to_string(), configured(&["rust-book"], &[]));
+        config.insert(
+            "async".to_string(),
+            configured(&["async-book", "rust-book"], &[("async-book", 2.0)]),
+        );
+        let profiles = Profiles::new(&config, Some("async"));
+
+        assert_eq!(
+            profiles.get("beginner").unwrap().sources.as_deref(),
+            Some(&["rust-book".to_string()][..])
+        );
+        let options = profiles.apply(None, SearchOptions::new(5)).unwrap();
+        assert_eq!(options.source_boost("async-book"), 2.0);
+        let options = profiles.apply(Some("full"), SearchOptions::new(5)).unwrap();
+        assert!(options.sources.is_none());
+        assert!(
+            profiles
+                .apply(Some("expert"), SearchOptions::new(5))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let sources = SourcesConfig::default();
+        let mut config = BTreeMap::new();
+        assert!(validate(&config, Some("advanced"), &sources).is_ok());
+        assert!(validate(&config, Some("expert"), &sources).is_err());
+
+        config.insert("typo".to_string(), configured(&["rust-bok"], &[]));
+        assert!(validate(&config, None, &sources).is_err());
+        config.insert(
+            "typo".to_string(),
+            configured(&["rust-book"], &[("rust-book", 0.0)]),
+        );
+        assert!(validate(&config, None, &sources).is_err());
+
+        // Configured books can be searched like the built-in sources
+        let sources: SourcesConfig =
+            toml::from_str("[books.embedded-book]\nrepo = \"rust-embedded/book\"").unwrap();
+        config.insert("typo".to_string(), configured(&["embedded-book"], &[]));
+        assert!(validate(&config, None, &sources).is_ok());
+    }
+}
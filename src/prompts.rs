@@ -0,0 +1,158 @@
+//! Message text for the built-in MCP prompts.
+//!
+//! The prompt handlers in the server retrieve documentation first; these functions turn the
+//! passages into numbered, citable context with instructions to stay grounded in it.
+
+use crate::resources::document_uri;
+use crate::search::SearchResult;
+
+/// Passages included in a prompt, across all queries
+pub const MAX_PASSAGES: usize = 6;
+
+/// Snippet length for passages (longer than tool snippets, since the model reads them in full)
+pub const PASSAGE_SNIPPET_LEN: usize = 600;
+
+const CITATION_INSTRUCTIONS: &str = "Cite the passages you rely on as [n]. If the passages do not cover something, say so instead of guessing, and keep any claim not backed by a passage clearly marked as your own.";
+
+/// Add results to a passage list, skipping documents already included
+pub fn add_passages(passages: &mut Vec<SearchResult>, results: Vec<SearchResult>) {
+    for result in results {
+        if passages.len() >= MAX_PASSAGES {
+            break;
+        }
+        if !passages
+            .iter()
+            .any(|p| p.source == result.source && p.path == result.path)
+        {
+            passages.push(result);
+        }
+    }
+}
+
+/// Numbered passages with their titles and resource URIs
+fn passages_block(passages: &[SearchResult]) -> String {
+    if passages.is_empty() {
+        return "(No matching documentation was found.)".to_string();
+    }
+
+    passages
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            format!(
+                "[{}] {} ({})\n{}",
+                i + 1,
+                p.title,
+                document_uri(&p.source, &p.path),
+                p.snippet
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// User message for the explain_compiler_error prompt
+pub fn explain_error_message(error: &str, passages: &[SearchResult]) -> String {
+    format!(
+        "Explain the following Rust compiler error: what it means, why the compiler rejects the code, and how to fix it. Base the explanation on the documentation passages below. {}\n\n## Compiler output\n\n```text\n{}\n```\n\n## Documentation\n\n{}",
+        CITATION_INSTRUCTIONS,
+        error.trim(),
+        passages_block(passages)
+    )
+}
+
+/// User message for the doc_grounded_code_review prompt
+pub fn code_review_message(code: &str, findings: &[&str], passages: &[SearchResult]) -> String {
+    let findings = if findings.is_empty() {
+        String::new()
+    } else {
+        format!("\n\nAutomated checks flagged: {}.", findings.join(", "))
+    };
+
+    format!(
+        "Review the following Rust code for correctness, idiomatic style, and API design. Back each recommendation with the documentation passages below. {}{}\n\n## Code\n\n```rust\n{}\n```\n\n## Documentation\n\n{}",
+        CITATION_INSTRUCTIONS,
+        findings,
+        code.trim(),
+        passages_block(passages)
+    )
+}
+
+/// User message for the error_handling_strategy prompt
+pub fn error_handling_message(
+    project: &str,
+    code: Option<&str>,
+    findings: &[&str],
+    passages: &[SearchResult],
+) -> String {
+    let code = match code {
+        Some(code) => format!("\n\n## Current code\n\n```rust\n{}\n```", code.trim()),
+        None => String::new(),
+    };
+    let findings = if findings.is_empty() {
+        String::new()
+    } else {
+        format!("\n\nAutomated checks flagged: {}.", findings.join(", "))
+    };
+
+    format!(
+        "Suggest an error handling strategy for the Rust project described below: which errors should be recoverable and which should panic, what the error types should look like to callers, and how errors are propagated and given context. Base the recommendation on the documentation passages below. {}{}\n\n## Project\n\n{}{}\n\n## Documentation\n\n{}",
+        CITATION_INSTRUCTIONS,
+        findings,
+        project.trim(),
+        code,
+        passages_block(passages)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str) -> SearchResult {
+        SearchResult {
+            title: "References and Borrowing".to_string(),
+            snippet: "At any given time, you can have either one mutable reference...".to_string(),
+            ..SearchResult::for_test("rust-book", path, 1.0)
+        }
+    }
+
+    #[test]
+    fn test_add_passages_dedupes() {
+        let mut passages = Vec::new();
+        add_passages(&mut passages, vec![result("a.md"), result("b.md")]);
+        add_passages(&mut passages, vec![result("a.md"), result("c.md")]);
+
+        let paths: Vec<&str> = passages.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.md", "b.md", "c.md"]);
+    }
+
+    #[test]
+    fn test_explain_error_message() {
+        let message = explain_error_message(
+            "error[E0499]: cannot borrow `v` as mutable more than once",
+            &[result("ch04-02-references-and-borrowing.md")],
+        );
+        assert!(message.contains("[1] References and Borrowing (rust-doc://rust-book/ch04-02-references-and-borrowing.md)"));
+        assert!(message.contains("Cite the passages"));
+    }
+
+    #[test]
+    fn test_error_handling_message() {
+        let passages = [result("ch09-02-recoverable-errors-with-result.md")];
+        let message = error_handling_message(
+            "A library that parses config files",
+            Some("fn load() -> Result<Config, String> { todo!() }"),
+            &["unwrap-in-library"],
+            &passages,
+        );
+        assert!(message.contains("## Project\n\nA library that parses config files"));
+        assert!(message.contains("```rust\nfn load()"));
+        assert!(message.contains("Automated checks flagged: unwrap-in-library."));
+        assert!(message.contains("[1] References and Borrowing"));
+
+        let message = error_handling_message("A CLI tool", None, &[], &passages);
+        assert!(!message.contains("## Current code"));
+        assert!(!message.contains("Automated checks"));
+    }
+}
@@ -0,0 +1,503 @@
+//! Rust release notes.
+//!
+//! rust-lang/rust keeps the notes of every release in `RELEASES.md`. Only that file is needed,
+//! so it is downloaded rather than cloning the compiler repository, and split into one document
+//! per version for search. The `whats_new` tool reads the parsed releases directly to answer
+//! "what changed in 1.78" or "what was stabilized between 1.70 and 1.78".
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::parse_report::SourceReport;
+use crate::parsing::{Document, parse_markdown};
+
+/// Source ID of the release notes
+pub const RELEASES_SOURCE: &str = "rust-releases";
+
+/// Where the release notes are downloaded from
+const RELEASES_URL: &str = "https://raw.githubusercontent.com/rust-lang/rust/HEAD/RELEASES.md";
+
+/// Release notes on GitHub, for citations
+const RELEASES_PAGE: &str = "https://github.com/rust-lang/rust/blob/HEAD/RELEASES.md";
+
+const RELEASES_FILE: &str = "RELEASES.md";
+
+/// Section of entries listed before any section heading, as in most point releases
+const UNSECTIONED: &str = "Changes";
+
+/// A Rust version; `1.78` is `1.78.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Parse `1.78`, `1.78.0`, or `v1.78.0`
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+        let major = parts.next()??;
+        let minor = parts.next()??;
+        let patch = match parts.next() {
+            Some(patch) => patch?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The releases a `whats_new` request covers, both ends inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionRange {
+    pub from: Version,
+    /// `None` for an open range (`1.70..`), up to the latest release
+    pub to: Option<Version>,
+    /// A single minor version (`1.78`) also covers its patch releases
+    include_patches: bool,
+}
+
+impl VersionRange {
+    /// Parse `1.78`, `1.78.1`, `1.70..1.78`, `1.70..=1.78`, or `1.70..`
+    pub fn parse(range: &str) -> Option<Self> {
+        let range = range.trim();
+        if let Some((from, to)) = range.split_once("..") {
+            let to = to.trim_start_matches('=').trim();
+            let from = Version::parse(from)?;
+            let to = if to.is_empty() {
+                None
+            } else {
+                let mut to = Version::parse(to)?;
+                // `..1.78` includes 1.78's patch releases
+                if to.patch == 0 {
+                    to.patch = u32::MAX;
+                }
+                Some(to)
+            };
+            if to.is_some_and(|to| to < from) {
+                return None;
+            }
+            return Some(Self {
+                from,
+                to,
+                include_patches: false,
+            });
+        }
+
+        let version = Version::parse(range)?;
+        Some(Self {
+            from: version,
+            to: Some(version),
+            include_patches: range.trim_start_matches('v').matches('.').count() == 1,
+        })
+    }
+
+    pub fn contains(&self, version: Version) -> bool {
+        if self.include_patches {
+            return (version.major, version.minor) == (self.from.major, self.from.minor);
+        }
+        version >= self.from && self.to.is_none_or(|to| version <= to)
+    }
+}
+
+/// The notes of one release
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: Version,
+    /// Release date as written, e.g. `2024-05-02`
+    pub date: Option<String>,
+    /// The version heading, e.g. `Version 1.78.0 (2024-05-02)`
+    pub title: String,
+    /// Sections in order (`Language`, `Libraries`, `Stabilized APIs`, ...) with their entries
+    pub sections: Vec<(String, Vec<String>)>,
+    /// The release's notes as markdown
+    pub markdown: String,
+}
+
+impl Release {
+    /// Link to the release on GitHub (GitHub's heading anchor)
+    pub fn citation(&self) -> String {
+        let anchor: String = self
+            .title
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-')
+            .map(|c| if c == ' ' { '-' } else { c })
+            .collect();
+        format!("{}#{}", RELEASES_PAGE, anchor)
+    }
+
+    /// Path of the release's document, e.g. `1.78.0`
+    pub fn path(&self) -> String {
+        self.version.to_string()
+    }
+}
+
+/// A release-notes entry
+#[derive(Debug, Clone, Copy)]
+pub struct Mention<'a> {
+    pub release: &'a Release,
+    pub section: &'a str,
+    pub entry: &'a str,
+}
+
+impl Mention<'_> {
+    /// Whether the entry stabilizes something (`Stabilized APIs`, "Stabilize `let`-`else`")
+    pub fn is_stabilization(&self) -> bool {
+        self.section.to_lowercase().contains("stabiliz")
+            || strip_link_targets(self.entry)
+                .to_lowercase()
+                .contains("stabiliz")
+    }
+}
+
+/// The parsed release notes, newest first
+#[derive(Debug, Default)]
+pub struct Releases {
+    releases: Vec<Release>,
+}
+
+impl Releases {
+    /// Path of the downloaded release notes
+    pub fn file_path(data_dir: &Path) -> PathBuf {
+        data_dir.join(RELEASES_SOURCE).join(RELEASES_FILE)
+    }
+
+    /// Download the release notes if they are missing
+    pub fn download(data_dir: &Path) -> Result<bool> {
+        let path = Self::file_path(data_dir);
+        if path.exists() {
+            return Ok(false);
+        }
+        tracing::info!("Downloading release notes from {}...", RELEASES_URL);
+        let response = ureq::get(RELEASES_URL)
+            .call()
+            .map_err(|e| Error::Other(format!("Failed to download {}: {}", RELEASES_URL, e)))?;
+        let markdown = response
+            .into_body()
+            .read_to_string()
+            .map_err(|e| Error::Other(format!("Failed to read {}: {}", RELEASES_URL, e)))?;
+        std::fs::create_dir_all(path.parent().unwrap_or(data_dir))?;
+        std::fs::write(&path, markdown)?;
+        Ok(true)
+    }
+
+    /// Load the downloaded release notes; empty when they are missing
+    pub fn load(data_dir: &Path) -> Self {
+        let path = Self::file_path(data_dir);
+        match std::fs::read_to_string(&path) {
+            Ok(markdown) => {
+                let releases = Self::parse(&markdown);
+                tracing::info!("Loaded notes for {} Rust releases", releases.releases.len());
+                releases
+            }
+            Err(e) => {
+                tracing::debug!("No release notes at {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Split `RELEASES.md` at its `Version x.y.z (date)` headings
+    pub fn parse(markdown: &str) -> Self {
+        let lines: Vec<&str> = markdown.lines().collect();
+        let is_heading = |i: usize| {
+            lines[i].starts_with("Version ")
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| next.len() > 3 && next.chars().all(|c| c == '='))
+        };
+        let starts: Vec<usize> = (0..lines.len()).filter(|&i| is_heading(i)).collect();
+
+        let releases = starts
+            .iter()
+            .enumerate()
+            .filter_map(|(n, &start)| {
+                let end = starts.get(n + 1).copied().unwrap_or(lines.len());
+                parse_release(&lines[start..end])
+            })
+            .collect();
+        Self { releases }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.releases.is_empty()
+    }
+
+    /// The newest release
+    pub fn latest(&self) -> Option<&Release> {
+        self.releases.iter().max_by_key(|release| release.version)
+    }
+
+    /// The release of exactly this version
+    pub fn get(&self, version: Version) -> Option<&Release> {
+        self.releases
+            .iter()
+            .find(|release| release.version == version)
+    }
+
+    /// Releases in a range, oldest first
+    pub fn in_range(&self, range: &VersionRange) -> Vec<&Release> {
+        let mut releases: Vec<&Release> = self
+            .releases
+            .iter()
+            .filter(|release| range.contains(release.version))
+            .collect();
+        releases.sort_by_key(|release| release.version);
+        releases
+    }
+
+    /// Entries mentioning every word of `query` (`let-else`, `GATs`, `OnceLock`), oldest first
+    pub fn mentions(&self, query: &str) -> Vec<Mention<'_>> {
+        let wanted = words(query);
+        if wanted.is_empty() {
+            return Vec::new();
+        }
+        let mut mentions: Vec<Mention> = self
+            .releases
+            .iter()
+            .flat_map(|release| {
+                release.sections.iter().flat_map(move |(section, entries)| {
+                    entries.iter().map(move |entry| Mention {
+                        release,
+                        section,
+                        entry,
+                    })
+                })
+            })
+            .filter(|mention| {
+                let entry_words = words(&strip_link_targets(mention.entry));
+                wanted.iter().all(|word| entry_words.contains(word))
+            })
+            .collect();
+        mentions.sort_by_key(|mention| mention.release.version);
+        mentions
+    }
+
+    /// One document per release, recording the count in `report`
+    pub fn documents(&self, report: &mut SourceReport) -> Vec<Document> {
+        let documents: Vec<Document> = self
+            .releases
+            .iter()
+            .map(|release| {
+                let markdown = format!("# {}\n\n{}", release.title, release.markdown);
+                parse_markdown(&markdown, &release.path(), RELEASES_SOURCE)
+            })
+            .collect();
+        report.parsed = documents.len();
+        documents
+    }
+}
+
+/// Lowercase alphanumeric words
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Drop the URLs of markdown links (`[text](url)` becomes `[text]`), so they don't match words
+fn strip_link_targets(markdown: &str) -> String {
+    let mut text = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+    while let Some(start) = rest.find("](") {
+        text.push_str(&rest[..=start]);
+        rest = &rest[start + 2..];
+        match rest.find(')') {
+            Some(end) => rest = &rest[end + 1..],
+            None => rest = "",
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Parse one release: heading, underline, then setext sections of bullet entries
+fn parse_release(lines: &[&str]) -> Option<Release> {
+    let title = lines.first()?.trim().to_string();
+    let heading = title.strip_prefix("Version ")?;
+    let (version, date) = match heading.split_once(' ') {
+        Some((version, date)) => (
+            version,
+            Some(date.trim_matches(|c| c == '(' || c == ')').to_string()),
+        ),
+        None => (heading, None),
+    };
+    let version = Version::parse(version)?;
+
+    let body = &lines[2.min(lines.len())..];
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    let mut markdown = String::new();
+    let mut i = 0;
+    while i < body.len() {
+        let line = body[i];
+        let underlined = body
+            .get(i + 1)
+            .is_some_and(|next| next.len() > 2 && next.chars().all(|c| c == '-'));
+        if underlined && !line.trim().is_empty() {
+            // Setext heading: rewrite as ATX so the document gets section headings
+            sections.push((line.trim().to_string(), Vec::new()));
+            markdown.push_str(&format!("## {}\n", line.trim()));
+            i += 2;
+            continue;
+        }
+        // Skip the HTML anchors rustc adds before each section
+        if !line.trim_start().starts_with("<a id=") {
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+
+        let trimmed = line.trim_start();
+        let indented = line.len() != trimmed.len();
+        let entry = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "));
+        match entry {
+            // Top-level entries start a new item; point releases often have no sections
+            Some(entry) if !indented => {
+                if sections.is_empty() {
+                    sections.push((UNSECTIONED.to_string(), Vec::new()));
+                }
+                if let Some((_, entries)) = sections.last_mut() {
+                    entries.push(entry.trim().to_string());
+                }
+            }
+            // Nested entries and wrapped lines extend the current item
+            _ if indented && !trimmed.is_empty() => {
+                if let Some(last) = sections
+                    .last_mut()
+                    .and_then(|(_, entries)| entries.last_mut())
+                {
+                    last.push(' ');
+                    last.push_str(entry.unwrap_or(trimmed).trim());
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    Some(Release {
+        version,
+        date,
+        title,
+        sections,
+        markdown: markdown.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELEASES: &str = "\
+Version 1.78.0 (2024-05-02)
+==========================
+
+<a id=\"1.78.0-Language\"></a>
+
+Language
+--------
+- [Stabilize `#[cfg(target_abi = ...)]`](https://github.com/rust-lang/rust/pull/119590/)
+- [Stabilize the `#[diagnostic]` namespace and `#[diagnostic::on_unimplemented]` attribute](https://github.com/rust-lang/rust/pull/119888/)
+  and make it apply to more traits.
+
+Stabilized APIs
+---------------
+
+- [`impl Read for &Stdin`](https://doc.rust-lang.org/stable/std/io/struct.Stdin.html#impl-Read-for-%26Stdin)
+- [`Barrier::new`](https://doc.rust-lang.org/stable/std/sync/struct.Barrier.html#method.new)
+
+Version 1.77.2 (2024-04-09)
+===========================
+
+- [CVE-2024-24576: fix escaping of Windows batch file arguments in `std::process::Command`](https://blog.rust-lang.org/2024/04/09/cve-2024-24576.html)
+
+Version 1.77.0 (2024-03-21)
+==========================
+
+Language
+--------
+- [Reveal opaque types within the defining body for exhaustiveness checking.](https://github.com/rust-lang/rust/pull/116821/)
+";
+
+    #[test]
+    fn test_parse_releases() {
+        let releases = Releases::parse(RELEASES);
+        assert_eq!(releases.latest().unwrap().version.to_string(), "1.78.0");
+
+        let latest = releases.latest().unwrap();
+        assert_eq!(latest.date.as_deref(), Some("2024-05-02"));
+        let names: Vec<&str> = latest
+            .sections
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Language", "Stabilized APIs"]);
+        assert_eq!(latest.sections[0].1.len(), 2);
+        assert!(latest.sections[0].1[1].ends_with("and make it apply to more traits."));
+        assert_eq!(
+            latest.citation(),
+            "https://github.com/rust-lang/rust/blob/HEAD/RELEASES.md#version-1780-2024-05-02"
+        );
+
+        let doc = &releases.documents(&mut SourceReport::new(RELEASES_SOURCE))[0];
+        assert_eq!(doc.path, "1.78.0");
+        assert_eq!(doc.headings, vec!["Language", "Stabilized APIs"]);
+    }
+
+    #[test]
+    fn test_version_ranges() {
+        let releases = Releases::parse(RELEASES);
+        let versions = |range: &str| -> Vec<String> {
+            releases
+                .in_range(&VersionRange::parse(range).unwrap())
+                .iter()
+                .map(|release| release.version.to_string())
+                .collect()
+        };
+
+        assert_eq!(versions("1.77"), vec!["1.77.0", "1.77.2"]);
+        assert_eq!(versions("1.77.0"), vec!["1.77.0"]);
+        assert_eq!(versions("1.70..1.77"), vec!["1.77.0", "1.77.2"]);
+        assert_eq!(versions("1.77.1..=1.78"), vec!["1.77.2", "1.78.0"]);
+        assert_eq!(versions("1.78.."), vec!["1.78.0"]);
+        assert!(VersionRange::parse("1.78..1.70").is_none());
+        assert!(VersionRange::parse("latest").is_none());
+
+        let point = releases.in_range(&VersionRange::parse("1.77.2").unwrap())[0];
+        assert_eq!(point.sections[0].0, "Changes");
+        assert_eq!(point.sections[0].1.len(), 1);
+    }
+
+    #[test]
+    fn test_mentions() {
+        let releases = Releases::parse(RELEASES);
+
+        let mentions = releases.mentions("diagnostic namespace");
+        assert_eq!(mentions.len(), 1);
+        assert_eq!(mentions[0].release.version.to_string(), "1.78.0");
+        assert!(mentions[0].is_stabilization());
+        assert_eq!(
+            releases.mentions("Barrier::new")[0].section,
+            "Stabilized APIs"
+        );
+        assert!(releases.mentions("github").is_empty());
+    }
+}
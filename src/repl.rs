@@ -0,0 +1,423 @@
+//! Interactive terminal mode (`rust-lang-mcp repl`).
+//!
+//! Loads the indices once, like the server, then reads commands from stdin: searches, concept
+//! explanations, examples, and whole documents, without an MCP client. Output is colored when
+//! stdout is a terminal and `NO_COLOR` isn't set; answers taller than the terminal go through
+//! `$PAGER` (`less -R` by default).
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::{Command as Process, Stdio};
+
+use crate::error::Result;
+use crate::parsing::Document;
+use crate::resources;
+use crate::search::{SearchMode, SearchResult};
+use crate::server::RustDocServer;
+
+const PROMPT: &str = "rust> ";
+
+const HELP: &str = "\
+Commands:
+  <query>                     search all sources (same as `search`)
+  search <query>              search all sources
+  explain <concept>           explanations from the Book and the Reference
+  example <topic>             examples from Rust by Example
+  doc <n>                     show result <n> of the last listing
+  doc <source> <path>         show a document
+  mode hybrid|keyword|semantic
+  limit <n>                   results per listing (1-20)
+  help                        this list
+  quit                        leave (or Ctrl-D)";
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+
+/// Terminal height assumed when `LINES` isn't set
+const DEFAULT_HEIGHT: usize = 24;
+
+/// A line of input
+#[derive(Debug, PartialEq)]
+enum Command {
+    Search(String),
+    Explain(String),
+    Example(String),
+    /// A result of the last listing, numbered from 1
+    Result(usize),
+    Document {
+        source: String,
+        path: String,
+    },
+    Mode(SearchMode),
+    Limit(usize),
+    Help,
+    Quit,
+    Empty,
+}
+
+impl Command {
+    /// Parse a line; errors are usage messages
+    fn parse(line: &str) -> std::result::Result<Self, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let argument = |usage: &str| {
+            if rest.is_empty() {
+                Err(format!("Usage: {}", usage))
+            } else {
+                Ok(rest.to_string())
+            }
+        };
+        Ok(match name {
+            "" => Command::Empty,
+            "search" | "s" => Command::Search(argument("search <query>")?),
+            "explain" | "e" => Command::Explain(argument("explain <concept>")?),
+            "example" | "ex" => Command::Example(argument("example <topic>")?),
+            "doc" | "d" => {
+                let usage = || "Usage: doc <n> | doc <source> <path>".to_string();
+                match rest.split_whitespace().collect::<Vec<_>>()[..] {
+                    [source, path] => Command::Document {
+                        source: source.to_string(),
+                        path: path.to_string(),
+                    },
+                    [n] => match n.parse() {
+                        Ok(n) if n > 0 => Command::Result(n),
+                        _ => return Err(usage()),
+                    },
+                    _ => return Err(usage()),
+                }
+            }
+            "mode" => match rest {
+                "hybrid" | "keyword" | "semantic" => Command::Mode(SearchMode::from(rest)),
+                _ => return Err("Usage: mode hybrid|keyword|semantic".to_string()),
+            },
+            "limit" => match rest.parse::<usize>() {
+                Ok(n) if (1..=MAX_LIMIT).contains(&n) => Command::Limit(n),
+                _ => return Err(format!("Usage: limit <1-{}>", MAX_LIMIT)),
+            },
+            "help" | "?" => Command::Help,
+            "quit" | "exit" | "q" => Command::Quit,
+            _ => Command::Search(line.to_string()),
+        })
+    }
+}
+
+/// ANSI styling, or none
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn title(&self, text: &str) -> String {
+        self.paint("1", text)
+    }
+
+    fn heading(&self, text: &str) -> String {
+        self.paint("1;36", text)
+    }
+
+    fn code(&self, text: &str) -> String {
+        self.paint("32", text)
+    }
+
+    fn dim(&self, text: &str) -> String {
+        self.paint("2", text)
+    }
+
+    fn error(&self, text: &str) -> String {
+        self.paint("31", text)
+    }
+}
+
+/// Settings and the last listing
+struct Session {
+    mode: SearchMode,
+    limit: usize,
+    last: Vec<SearchResult>,
+    style: Style,
+}
+
+impl Session {
+    fn new(mode: SearchMode, limit: usize) -> Self {
+        Self {
+            mode,
+            limit,
+            last: Vec::new(),
+            style: Style {
+                color: io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            },
+        }
+    }
+
+    /// Run a command; `None` ends the session
+    fn execute(&mut self, server: &RustDocServer, command: Command) -> Result<Option<String>> {
+        let output = match command {
+            Command::Empty => String::new(),
+            Command::Help => format!("{}\n", HELP),
+            Command::Quit => return Ok(None),
+            Command::Mode(mode) => {
+                self.mode = mode;
+                format!("Search mode: {}\n", mode.as_str())
+            }
+            Command::Limit(limit) => {
+                self.limit = limit;
+                format!("Results per listing: {}\n", limit)
+            }
+            Command::Search(query) => {
+                let outcome = server.search(&query, self.mode, self.limit)?;
+                let mut header = format!(
+                    "{} search, {}",
+                    outcome.mode.as_str(),
+                    outcome.decision.as_str()
+                );
+                if let Some(note) = server.semantic_note(self.mode) {
+                    header = format!("{}. {}", header, note);
+                }
+                self.list(outcome.results, &header)
+            }
+            Command::Explain(concept) => {
+                let results = server.explain(&concept, self.limit)?;
+                self.list(results, "Explanations from the Book and the Reference")
+            }
+            Command::Example(topic) => {
+                let results = server.examples(&topic, self.limit)?;
+                self.list(results, "Examples from Rust by Example")
+            }
+            Command::Result(n) => match self.last.get(n - 1) {
+                Some(result) if !result.source.is_empty() => {
+                    let (source, path) = (result.source.clone(), result.path.clone());
+                    self.show_document(server, &source, &path)?
+                }
+                Some(_) => format!(
+                    "{}\n",
+                    self.style.error("That result has no stored document")
+                ),
+                None => format!(
+                    "{}\n",
+                    self.style.error(&format!(
+                        "No result {}; the last listing has {}",
+                        n,
+                        self.last.len()
+                    ))
+                ),
+            },
+            Command::Document { source, path } => self.show_document(server, &source, &path)?,
+        };
+        Ok(Some(output))
+    }
+
+    /// Number results for `doc <n>` and remember them
+    fn list(&mut self, results: Vec<SearchResult>, header: &str) -> String {
+        let output = render_results(&results, header, self.style);
+        self.last = results;
+        output
+    }
+
+    fn show_document(&self, server: &RustDocServer, source: &str, path: &str) -> Result<String> {
+        Ok(match server.document(source, path)? {
+            Some(doc) => render_document(&doc, self.style),
+            None => format!(
+                "{}\n",
+                self.style
+                    .error(&format!("No document {} in {}", path, source))
+            ),
+        })
+    }
+}
+
+/// A numbered listing with snippets
+fn render_results(results: &[SearchResult], header: &str, style: Style) -> String {
+    if results.is_empty() {
+        return format!("{}\nNo results.\n", style.dim(header));
+    }
+    let mut output = format!("{}\n", style.dim(header));
+    for (i, result) in results.iter().enumerate() {
+        output.push_str(&format!(
+            "\n{} {}  {}\n",
+            style.title(&format!("{}.", i + 1)),
+            style.title(&result.title),
+            style.dim(&format!("{}  {:.2}", result.chunk_key(), result.score)),
+        ));
+        for line in result
+            .snippet
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+        {
+            output.push_str(&format!("   {}\n", line));
+        }
+        output.push_str(&format!(
+            "   {}\n",
+            style.dim(&result.explanation.describe())
+        ));
+    }
+    output
+}
+
+/// A document's markdown with headings and code blocks highlighted
+fn render_document(doc: &Document, style: Style) -> String {
+    let mut output = format!(
+        "{}\n",
+        style.dim(&resources::document_uri(&doc.source, &doc.path))
+    );
+    let mut in_code = false;
+    for line in resources::render_document(doc).lines() {
+        let fence = line.trim_start().starts_with("```");
+        let styled = if fence || in_code {
+            style.code(line)
+        } else if line.starts_with('#') {
+            style.heading(line)
+        } else {
+            line.to_string()
+        };
+        if fence {
+            in_code = !in_code;
+        }
+        output.push_str(&styled);
+        output.push('\n');
+    }
+    output
+}
+
+/// Print output, through the pager when it is taller than the terminal
+fn show(output: &str, page: bool) {
+    let height = std::env::var("LINES")
+        .ok()
+        .and_then(|lines| lines.parse().ok())
+        .unwrap_or(DEFAULT_HEIGHT);
+    if page && output.lines().count() >= height && pager(output).is_ok() {
+        return;
+    }
+    print!("{}", output);
+}
+
+/// Show text in `$PAGER`
+fn pager(text: &str) -> io::Result<()> {
+    let command = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "PAGER is empty"))?;
+    let mut child = Process::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may quit before reading everything
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Run one search and print its results, as the REPL would (`rust-lang-mcp search`)
+pub fn search(server: &RustDocServer, query: &str, mode: SearchMode, limit: usize) -> Result<()> {
+    let mut session = Session::new(mode, limit.clamp(1, MAX_LIMIT));
+    if let Some(output) = session.execute(server, Command::Search(query.to_string()))? {
+        print!("{}", output);
+    }
+    Ok(())
+}
+
+/// Read and run commands until `quit` or end of input
+pub fn run(server: &RustDocServer) -> Result<()> {
+    let terminal = io::stdout().is_terminal();
+    let mut session = Session::new(SearchMode::Hybrid, DEFAULT_LIMIT);
+    if terminal {
+        println!(
+            "rust-lang-mcp {}; type `help` for commands",
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    let mut stdin = io::stdin().lock();
+    let mut line = String::new();
+    loop {
+        if terminal {
+            print!("{}", PROMPT);
+            io::stdout().flush()?;
+        }
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        let outcome = Command::parse(&line)
+            .map_err(|usage| session.style.error(&usage))
+            .and_then(|command| {
+                session
+                    .execute(server, command)
+                    .map_err(|e| session.style.error(&e.to_string()))
+            });
+        match outcome {
+            Ok(Some(output)) => show(&output, terminal),
+            Ok(None) => break,
+            Err(message) => println!("{}", message),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let parse = |line: &str| Command::parse(line);
+        assert_eq!(
+            parse("how do closures capture").unwrap(),
+            Command::Search("how do closures capture".to_string())
+        );
+        assert_eq!(
+            parse("explain  lifetimes \n").unwrap(),
+            Command::Explain("lifetimes".to_string())
+        );
+        assert_eq!(parse("doc 2").unwrap(), Command::Result(2));
+        assert_eq!(
+            parse("doc rust-book ch04-01-what-is-ownership.md").unwrap(),
+            Command::Document {
+                source: "rust-book".to_string(),
+                path: "ch04-01-what-is-ownership.md".to_string(),
+            }
+        );
+        assert_eq!(parse("limit 10").unwrap(), Command::Limit(10));
+        assert_eq!(parse("").unwrap(), Command::Empty);
+        assert_eq!(parse("quit").unwrap(), Command::Quit);
+        assert!(parse("doc 0").is_err());
+        assert!(parse("example").is_err());
+        assert!(parse("mode fuzzy").is_err());
+        assert!(parse("limit 50").is_err());
+    }
+
+    #[test]
+    fn test_render_without_color() {
+        let style = Style { color: false };
+        let results = vec![SearchResult {
+            title: "What Is Ownership?".to_string(),
+            snippet: "Ownership is a set of rules.\n\nIt governs memory.".to_string(),
+            ..SearchResult::for_test("rust-book", "ch04-01-what-is-ownership.md", 0.5)
+        }];
+        let output = render_results(&results, "hybrid search, fused", style);
+        assert!(output.starts_with("hybrid search, fused\n\n1. What Is Ownership?  rust-book/ch04-01-what-is-ownership.md  0.50\n"));
+        assert!(output.contains("   Ownership is a set of rules.\n   It governs memory.\n"));
+        assert!(!output.contains('\x1b'));
+
+        let doc = Document {
+            title: "Ownership".to_string(),
+            markdown: "## Rules\n\n```rust\nlet s = String::new();\n```".to_string(),
+            path: "ch04-01-what-is-ownership.md".to_string(),
+            source: "rust-book".to_string(),
+            ..Default::default()
+        };
+        let styled = render_document(&doc, Style { color: true });
+        assert!(styled.contains("\x1b[1;36m## Rules\x1b[0m"));
+        assert!(styled.contains("\x1b[32mlet s = String::new();\x1b[0m"));
+    }
+}
@@ -0,0 +1,203 @@
+//! `rust-doc://{source}/{path}` URIs for reading indexed documents as MCP resources, and the
+//! subscriptions that tell clients when a re-index changed them.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rmcp::model::ResourceUpdatedNotificationParam;
+use rmcp::service::{Peer, RoleServer};
+
+use crate::parsing::Document;
+use crate::workspace::percent_decode;
+
+/// URI scheme of indexed documents
+pub const DOC_URI_SCHEME: &str = "rust-doc://";
+
+/// Resource template advertised to clients
+pub const DOC_URI_TEMPLATE: &str = "rust-doc://{source}/{path}";
+
+/// URI of an indexed document
+pub fn document_uri(source: &str, path: &str) -> String {
+    format!("{}{}/{}", DOC_URI_SCHEME, source, path)
+}
+
+/// Split a `rust-doc://` URI into `(source, path)`
+pub fn parse_document_uri(uri: &str) -> Option<(String, String)> {
+    let rest = uri.strip_prefix(DOC_URI_SCHEME)?;
+    let (source, path) = rest.split_once('/')?;
+    if source.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((percent_decode(source), percent_decode(path)))
+}
+
+/// Render a stored document as Markdown
+pub fn render_document(doc: &Document) -> String {
+    let body = if doc.markdown.is_empty() {
+        &doc.content
+    } else {
+        &doc.markdown
+    };
+    format!("# {}\n\n{}\n", doc.title, body)
+}
+
+/// Check whether a document URI belongs to a source
+fn uri_in_source(uri: &str, source: &str) -> bool {
+    parse_document_uri(uri).is_some_and(|(uri_source, _)| uri_source == source)
+}
+
+/// Identifies the MCP session a subscription belongs to; sessions share one `Subscriptions`
+pub type SessionId = u64;
+
+/// A subscribed URI, the session that subscribed, and the peer to notify
+struct Subscription<P> {
+    uri: String,
+    session: SessionId,
+    peer: P,
+}
+
+/// Resource URIs clients subscribed to, with the peer to notify.
+///
+/// Entries are keyed by URI and session, so clients subscribed to the same document don't
+/// replace or unsubscribe each other.
+#[derive(Clone)]
+pub struct Subscriptions<P = Peer<RoleServer>> {
+    entries: Arc<Mutex<Vec<Subscription<P>>>>,
+    next_session: Arc<AtomicU64>,
+}
+
+impl<P> Default for Subscriptions<P> {
+    fn default() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Vec::new())),
+            next_session: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<P: Clone> Subscriptions<P> {
+    /// An ID for a new session
+    pub fn new_session(&self) -> SessionId {
+        self.next_session.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Subscribe a session's peer to a URI (subscribing again replaces the session's previous
+    /// peer)
+    pub fn subscribe(&self, session: SessionId, uri: String, peer: P) {
+        let mut entries = self.lock();
+        entries.retain(|entry| !(entry.session == session && entry.uri == uri));
+        entries.push(Subscription { uri, session, peer });
+    }
+
+    /// Remove a session's subscription to a URI; other sessions' stay
+    pub fn unsubscribe(&self, session: SessionId, uri: &str) {
+        self.lock()
+            .retain(|entry| !(entry.session == session && entry.uri == uri));
+    }
+
+    /// Subscriptions to documents of `source`, of `session` only if given
+    fn affected(&self, source: &str, session: Option<SessionId>) -> Vec<(String, SessionId, P)> {
+        self.lock()
+            .iter()
+            .filter(|entry| session.is_none_or(|session| entry.session == session))
+            .filter(|entry| uri_in_source(&entry.uri, source))
+            .map(|entry| (entry.uri.clone(), entry.session, entry.peer.clone()))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Subscription<P>>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Subscriptions {
+    /// Notify every session's subscribers of documents in a source after it was re-indexed.
+    ///
+    /// A failed notification (usually a disconnected client) drops that session's subscription.
+    pub async fn notify_source_updated(&self, source: &str) {
+        self.notify(source, None).await;
+    }
+
+    /// Notify one session's subscribers of documents in a source only it has, such as its
+    /// workspace documentation
+    pub async fn notify_session_source_updated(&self, session: SessionId, source: &str) {
+        self.notify(source, Some(session)).await;
+    }
+
+    async fn notify(&self, source: &str, session: Option<SessionId>) {
+        for (uri, session, peer) in self.affected(source, session) {
+            let notification = ResourceUpdatedNotificationParam { uri: uri.clone() };
+            if let Err(e) = peer.notify_resource_updated(notification).await {
+                tracing::debug!("Dropping subscription to {}: {}", uri, e);
+                self.unsubscribe(session, &uri);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_uri_round_trip() {
+        let uri = document_uri("rust-book", "ch04-01-what-is-ownership.md");
+        assert_eq!(uri, "rust-doc://rust-book/ch04-01-what-is-ownership.md");
+        assert_eq!(
+            parse_document_uri(&uri),
+            Some((
+                "rust-book".to_string(),
+                "ch04-01-what-is-ownership.md".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_document_uri("rust-doc://workspace/docs/my%20guide.md"),
+            Some(("workspace".to_string(), "docs/my guide.md".to_string()))
+        );
+        assert_eq!(parse_document_uri("rust-doc://rust-book/"), None);
+        assert_eq!(parse_document_uri("https://doc.rust-lang.org/book"), None);
+    }
+
+    #[test]
+    fn test_uri_in_source() {
+        assert!(uri_in_source(
+            "rust-doc://workspace/src/lib.rs",
+            "workspace"
+        ));
+        assert!(!uri_in_source(
+            "rust-doc://rust-book/ch01-00.md",
+            "workspace"
+        ));
+        assert!(!uri_in_source(
+            "rust-doc://workspace-extra/a.md",
+            "workspace"
+        ));
+    }
+
+    #[test]
+    fn test_subscriptions_are_per_session() {
+        let subscriptions: Subscriptions<&str> = Subscriptions::default();
+        let (editor, agent) = (subscriptions.new_session(), subscriptions.new_session());
+        assert_ne!(editor, agent);
+        let uri = "rust-doc://rust-book/ch04-01.md";
+
+        // A second session subscribing doesn't evict the first
+        subscriptions.subscribe(editor, uri.to_string(), "editor");
+        subscriptions.subscribe(agent, uri.to_string(), "agent");
+        subscriptions.subscribe(agent, uri.to_string(), "agent again");
+        let peers = |session| -> Vec<&'static str> {
+            subscriptions
+                .affected("rust-book", session)
+                .into_iter()
+                .map(|(_, _, peer)| peer)
+                .collect()
+        };
+        assert_eq!(peers(None), vec!["editor", "agent again"]);
+        assert_eq!(peers(Some(editor)), vec!["editor"]);
+
+        // Unsubscribing only removes the caller's subscription
+        subscriptions.unsubscribe(agent, uri);
+        assert_eq!(peers(None), vec!["editor"]);
+        assert!(subscriptions.affected("workspace", None).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Response size budgets for tool output.
+//!
+//! Clients with small context windows can cap a tool's response with `max_tokens` or
+//! `max_chars`. Results are dropped from the end of the ranked list until the JSON fits, and a
+//! note saying how many were left out is added after it.
+
+use rmcp::model::{CallToolResult, Content};
+use rmcp::schemars::{self, JsonSchema};
+use serde::Deserialize;
+
+/// Characters per token assumed when converting a token budget
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Smallest budget honoured, in characters, so a response is never cut below one result
+const MIN_BUDGET_CHARS: usize = 400;
+
+/// Optional size limit on a tool's response
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+pub struct ResponseBudget {
+    /// Approximate maximum response size in tokens (about 4 characters each); lower-ranked results are left out to fit (default: no limit)
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Maximum response size in characters; lower-ranked results are left out to fit (default: no limit)
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+}
+
+impl ResponseBudget {
+    /// Budget in characters, if any (the tighter of the two limits)
+    fn char_limit(&self) -> Option<usize> {
+        let from_tokens = self
+            .max_tokens
+            .map(|tokens| tokens.saturating_mul(CHARS_PER_TOKEN));
+        let limit = match (from_tokens, self.max_chars) {
+            (Some(a), Some(b)) => a.min(b),
+            (limit, None) | (None, limit) => limit?,
+        };
+        Some(limit.max(MIN_BUDGET_CHARS))
+    }
+
+    /// Shorten a JSON tool result to the budget by dropping its lowest-ranked results.
+    ///
+    /// The ranked list is the top-level array or the `results` field (flat or grouped by
+    /// source). Errors, plain-text results, and results within the budget are returned as is.
+    pub fn fit(&self, mut result: CallToolResult) -> CallToolResult {
+        let Some(limit) = self.char_limit() else {
+            return result;
+        };
+        if result.is_error == Some(true) {
+            return result;
+        }
+        let Some(text) = result.content.first().and_then(|c| c.as_text()) else {
+            return result;
+        };
+        if text.text.chars().count() <= limit {
+            return result;
+        }
+        let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&text.text) else {
+            return result;
+        };
+
+        let mut omitted = 0;
+        let mut output = text.text.clone();
+        while output.chars().count() > limit && drop_last_result(&mut json) {
+            omitted += 1;
+            output = serde_json::to_string_pretty(&json).unwrap_or_default();
+        }
+        if omitted == 0 {
+            return result;
+        }
+
+        let mut note = format!(
+            "Response truncated to fit the {}-character budget: {} lower-ranked result{} left out.",
+            limit,
+            omitted,
+            if omitted == 1 { " was" } else { "s were" }
+        );
+        if output.chars().count() > limit {
+            note.push_str(" The remaining result is still larger than the budget.");
+        }
+        result.content[0] = Content::text(output);
+        result.content.push(Content::text(note));
+        result
+    }
+}
+
+/// Drop the last result of the ranked list, keeping at least one; false when nothing is left
+/// to drop.
+///
+/// Grouped results lose the last entry of their largest group.
+fn drop_last_result(json: &mut serde_json::Value) -> bool {
+    let list = match json {
+        serde_json::Value::Object(object) => match object.get_mut("results") {
+            Some(list) => list,
+            None => return false,
+        },
+        list => list,
+    };
+
+    match list {
+        serde_json::Value::Array(results) if results.len() > 1 => {
+            results.pop();
+            true
+        }
+        serde_json::Value::Object(groups) => {
+            let total: usize = groups
+                .values()
+                .filter_map(|group| group.as_array())
+                .map(Vec::len)
+                .sum();
+            if total <= 1 {
+                return false;
+            }
+            let Some((source, _)) = groups
+                .iter()
+                .filter_map(|(source, group)| Some((source.clone(), group.as_array()?.len())))
+                .max_by_key(|(_, len)| *len)
+            else {
+                return false;
+            };
+            if let Some(serde_json::Value::Array(group)) = groups.get_mut(&source) {
+                group.pop();
+                if group.is_empty() {
+                    groups.remove(&source);
+                }
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(json: serde_json::Value) -> CallToolResult {
+        CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&json).unwrap(),
+        )])
+    }
+
+    fn result(i: usize) -> serde_json::Value {
+        serde_json::json!({ "title": format!("Result {}", i), "snippet": "x".repeat(200) })
+    }
+
+    #[test]
+    fn test_fit_drops_lowest_ranked_results() {
+        let results: Vec<serde_json::Value> = (0..10).map(result).collect();
+        let budget = ResponseBudget {
+            max_tokens: Some(200),
+            max_chars: None,
+        };
+
+        let fitted = budget.fit(output(
+            serde_json::json!({ "mode": "hybrid", "results": results }),
+        ));
+        let text = &fitted.content[0].as_text().unwrap().text;
+        assert!(text.chars().count() <= 800);
+        let json: serde_json::Value = serde_json::from_str(text).unwrap();
+        let kept = json["results"].as_array().unwrap();
+        assert_eq!(kept[0]["title"], "Result 0");
+        assert_eq!(json["mode"], "hybrid");
+
+        let note = &fitted.content[1].as_text().unwrap().text;
+        assert!(note.contains(&format!("{} lower-ranked results", 10 - kept.len())));
+    }
+
+    #[test]
+    fn test_fit_grouped_and_unbudgeted() {
+        let grouped = serde_json::json!({ "results": {
+            "rust-book": [result(0), result(1), result(2)],
+            "rust-reference": [result(3)],
+        }});
+        let budget = ResponseBudget {
+            max_tokens: None,
+            max_chars: Some(700),
+        };
+        let fitted = budget.fit(output(grouped.clone()));
+        let json: serde_json::Value =
+            serde_json::from_str(&fitted.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(json["results"]["rust-book"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            json["results"]["rust-reference"].as_array().unwrap().len(),
+            1
+        );
+
+        let unchanged = ResponseBudget::default().fit(output(grouped));
+        assert_eq!(unchanged.content.len(), 1);
+    }
+}
@@ -0,0 +1,386 @@
+//! Rust Reference lookup by rule identifier, page, or heading anchor, and search over the
+//! Reference's grammar productions.
+//!
+//! The Reference marks each rule with a stable identifier on its own line (`r[expr.match.guard]`)
+//! and writes its grammar in `grammar` code blocks, one production per `Name -> ...` line with
+//! indented continuation lines. Both are read from the indexed pages on request.
+
+use crate::error::{Error, Result};
+use crate::rustc_flags::heading_id;
+
+/// Source ID of the Rust Reference
+pub const REFERENCE_SOURCE: &str = "rust-reference";
+
+/// What to fetch from the Reference for a user-supplied identifier
+#[derive(Debug, PartialEq, Eq)]
+pub enum Target {
+    /// A rule identifier, exact (`expr.match.guard`) or with abbreviated segments
+    /// (`expressions.match`)
+    Rule(String),
+    /// A page file name, and the anchor of a heading on it; a bare anchor searches every page
+    Page {
+        page: Option<String>,
+        anchor: Option<String>,
+    },
+}
+
+impl Target {
+    /// Parse `expr.match`, `r[expr.match]`, `match-expr.md#match-guards`,
+    /// `expressions/match-expr.html`, a doc.rust-lang.org URL, or `#match-guards`
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim().trim_matches('`').trim();
+        let input = input
+            .strip_prefix("r[")
+            .and_then(|rule| rule.strip_suffix(']'))
+            .unwrap_or(input);
+        if input.is_empty() {
+            return Err(Error::Other("Empty Reference identifier".to_string()));
+        }
+
+        let (path, anchor) = match input.split_once('#') {
+            Some((path, anchor)) => (path, Some(anchor.trim()).filter(|a| !a.is_empty())),
+            None => (input, None),
+        };
+        // Rule anchors are the rule identifier prefixed with `r-`
+        if let Some(rule) = anchor.and_then(|anchor| anchor.strip_prefix("r-")) {
+            return Ok(Self::Rule(rule.to_lowercase()));
+        }
+        let is_page = path.contains('/') || path.ends_with(".md") || path.ends_with(".html");
+        if !is_page && anchor.is_none() {
+            return Ok(Self::Rule(path.to_lowercase()));
+        }
+
+        let page = path
+            .rsplit('/')
+            .next()
+            .map(|name| name.trim_end_matches(".html").trim_end_matches(".md"))
+            .filter(|name| !name.is_empty())
+            .map(|name| format!("{}.md", name));
+        Ok(Self::Page {
+            page,
+            anchor: anchor.map(str::to_lowercase),
+        })
+    }
+}
+
+/// How closely a rule identifier matches a requested one: 0 when equal, otherwise the number
+/// of segments one of them abbreviates (`expr` for `expressions`); `None` when they differ
+pub fn rule_match(id: &str, wanted: &str) -> Option<usize> {
+    let ids: Vec<&str> = id.split('.').collect();
+    let wanted: Vec<&str> = wanted.split('.').collect();
+    if ids.len() != wanted.len() {
+        return None;
+    }
+    ids.iter().zip(&wanted).try_fold(0, |rank, (a, b)| {
+        if a == b {
+            Some(rank)
+        } else if !a.is_empty() && !b.is_empty() && (a.starts_with(b) || b.starts_with(a)) {
+            Some(rank + 1)
+        } else {
+            None
+        }
+    })
+}
+
+/// The rule a request names: the best-matching rule identifier, or prefix of one, among `ids`
+pub fn resolve_rule<'a>(wanted: &str, ids: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let depth = wanted.split('.').count();
+    ids.into_iter()
+        .filter_map(|id| {
+            let segments: Vec<&str> = id.split('.').collect();
+            let prefix = segments.get(..depth)?.join(".");
+            Some((rule_match(&prefix, wanted)?, prefix))
+        })
+        .min()
+        .map(|(_, id)| id)
+}
+
+/// Rule identifiers marked in a page, in order
+pub fn rule_ids(markdown: &str) -> Vec<&str> {
+    markdown.lines().filter_map(rule_marker).collect()
+}
+
+/// The identifier of a line starting with a rule marker
+fn rule_marker(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let rest = line.strip_prefix("- ").unwrap_or(line).strip_prefix("r[")?;
+    let id = &rest[..rest.find(']')?];
+    (!id.is_empty() && !id.contains(char::is_whitespace)).then_some(id)
+}
+
+/// A rule and the rules nested below it
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleSection {
+    /// Identifiers of the rules in the section, in order
+    pub rules: Vec<String>,
+    pub text: String,
+}
+
+/// The text of rule `id` and its sub-rules (`id.*`), from the first of their markers up to the
+/// next marker of another rule
+pub fn rule_section(markdown: &str, id: &str) -> Option<RuleSection> {
+    let nested = format!("{}.", id);
+    let in_rule = |rule: &str| rule == id || rule.starts_with(&nested);
+    let mut rules = Vec::new();
+    let mut lines = Vec::new();
+    for line in markdown.lines() {
+        match rule_marker(line) {
+            Some(rule) if in_rule(rule) => rules.push(rule.to_string()),
+            Some(_) if !rules.is_empty() => break,
+            _ => {}
+        }
+        if !rules.is_empty() {
+            lines.push(line);
+        }
+    }
+    (!rules.is_empty()).then(|| RuleSection {
+        rules,
+        text: lines.join("\n").trim().to_string(),
+    })
+}
+
+/// The heading with mdBook anchor `anchor` and the markdown below it, up to the next heading
+/// of the same or higher level
+pub fn anchor_section(markdown: &str, anchor: &str) -> Option<(String, String)> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut in_code = false;
+    let mut found: Option<(usize, usize)> = None;
+    for (number, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if in_code || level == 0 || !line[level..].starts_with(' ') {
+            continue;
+        }
+        match found {
+            Some((start, start_level)) if level <= start_level => {
+                return Some(section_at(&lines, start, number));
+            }
+            None if heading_id(line[level..].trim()) == anchor => found = Some((number, level)),
+            _ => {}
+        }
+    }
+    found.map(|(start, _)| section_at(&lines, start, lines.len()))
+}
+
+/// The heading at `start` and the lines below it up to `end`, without the marker of the next
+/// section's rule
+fn section_at(lines: &[&str], start: usize, mut end: usize) -> (String, String) {
+    let heading = lines[start].trim_start_matches('#').trim().to_string();
+    while end > start + 1 {
+        let line = lines[end - 1].trim();
+        if line.is_empty() || rule_marker(line).is_some_and(|id| line == format!("r[{}]", id)) {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    let body = lines[start + 1..end].join("\n").trim().to_string();
+    (heading, body)
+}
+
+/// A grammar production
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Production {
+    pub name: String,
+    /// The production as written, continuation lines included
+    pub definition: String,
+}
+
+impl Production {
+    /// Whether the production's right-hand side refers to `name`
+    pub fn uses(&self, name: &str) -> bool {
+        let rhs = self.definition.split_once("->").map_or("", |(_, rhs)| rhs);
+        rhs.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| word == name)
+    }
+}
+
+/// Productions of the `grammar` code blocks of a page
+pub fn productions(markdown: &str) -> Vec<Production> {
+    let mut productions: Vec<Production> = Vec::new();
+    let mut in_grammar = false;
+    for line in markdown.lines() {
+        if let Some(fence) = line.trim_start().strip_prefix("```") {
+            in_grammar = !in_grammar && fence.trim().starts_with("grammar");
+            continue;
+        }
+        if !in_grammar || line.trim().is_empty() {
+            continue;
+        }
+        let starts_production = !line.starts_with(char::is_whitespace) && line.contains("->");
+        match productions.last_mut() {
+            Some(production) if !starts_production => {
+                production.definition.push('\n');
+                production.definition.push_str(line);
+            }
+            _ if starts_production => {
+                let name = line.split("->").next().unwrap_or_default();
+                let name = name.trim().trim_start_matches("@root").trim();
+                productions.push(Production {
+                    name: name.to_string(),
+                    definition: line.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    productions
+}
+
+/// How well a production matches a grammar query: 0 for its name, 1 when its name starts
+/// with the query, 2 when it contains it; spaces, `-`, and `_` are ignored and case too
+/// ("match arm" finds `MatchArm`)
+pub fn production_rank(name: &str, query: &str) -> Option<usize> {
+    let normalize = |text: &str| {
+        text.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let (name, query) = (normalize(name), normalize(query));
+    if query.is_empty() {
+        None
+    } else if name == query {
+        Some(0)
+    } else if name.starts_with(&query) {
+        Some(1)
+    } else if name.contains(&query) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH_EXPR: &str = r#"r[expr.match]
+
+r[expr.match.syntax]
+
+```grammar
+MatchExpression ->
+    `match` Scrutinee `{`
+        InnerAttribute*
+        MatchArms?
+    `}`
+
+Scrutinee -> Expression _except [StructExpression]_
+
+MatchArm -> OuterAttribute* Pattern MatchArmGuard?
+```
+
+r[expr.match.intro] A `match` expression branches on a pattern.
+
+r[expr.match.guard]
+
+## Match guards
+
+r[expr.match.guard.intro] Match arms can accept match guards to further refine the criteria.
+
+r[expr.match.guard.type] The guard is an expression of type `bool`.
+
+r[expr.match.attributes]
+
+## Attributes on match arms
+
+Outer attributes are allowed on match arms."#;
+
+    #[test]
+    fn test_parse_target() {
+        let rule = |id: &str| Target::Rule(id.to_string());
+        assert_eq!(Target::parse("expr.match").unwrap(), rule("expr.match"));
+        assert_eq!(
+            Target::parse("`r[expr.match]`").unwrap(),
+            rule("expr.match")
+        );
+        assert_eq!(
+            Target::parse(
+                "https://doc.rust-lang.org/reference/expressions/match-expr.html#r-expr.match.guard"
+            )
+            .unwrap(),
+            rule("expr.match.guard")
+        );
+        assert_eq!(
+            Target::parse("expressions/match-expr.html#Match-guards").unwrap(),
+            Target::Page {
+                page: Some("match-expr.md".to_string()),
+                anchor: Some("match-guards".to_string()),
+            }
+        );
+        assert_eq!(
+            Target::parse("#match-guards").unwrap(),
+            Target::Page {
+                page: None,
+                anchor: Some("match-guards".to_string()),
+            }
+        );
+        assert!(Target::parse(" ").is_err());
+    }
+
+    #[test]
+    fn test_resolve_rule() {
+        let ids = rule_ids(MATCH_EXPR);
+        assert_eq!(ids.len(), 7);
+        assert_eq!(
+            resolve_rule("expr.match", ids.clone()).unwrap(),
+            "expr.match"
+        );
+        assert_eq!(
+            resolve_rule("expressions.match", ids.clone()).unwrap(),
+            "expr.match"
+        );
+        assert_eq!(
+            resolve_rule("expr.match.guards", ids.clone()).unwrap(),
+            "expr.match.guard"
+        );
+        assert!(resolve_rule("items.fn", ids).is_none());
+    }
+
+    #[test]
+    fn test_rule_section() {
+        let guard = rule_section(MATCH_EXPR, "expr.match.guard").unwrap();
+        assert_eq!(
+            guard.rules,
+            [
+                "expr.match.guard",
+                "expr.match.guard.intro",
+                "expr.match.guard.type"
+            ]
+        );
+        assert!(guard.text.starts_with("r[expr.match.guard]"));
+        assert!(guard.text.ends_with("of type `bool`."));
+
+        let all = rule_section(MATCH_EXPR, "expr.match").unwrap();
+        assert_eq!(all.rules.len(), 7);
+        assert!(rule_section(MATCH_EXPR, "expr.if").is_none());
+    }
+
+    #[test]
+    fn test_anchor_section() {
+        let (heading, body) = anchor_section(MATCH_EXPR, "match-guards").unwrap();
+        assert_eq!(heading, "Match guards");
+        assert!(body.ends_with("of type `bool`."));
+        let (_, last) = anchor_section(MATCH_EXPR, "attributes-on-match-arms").unwrap();
+        assert_eq!(last, "Outer attributes are allowed on match arms.");
+        assert!(anchor_section(MATCH_EXPR, "match").is_none());
+    }
+
+    #[test]
+    fn test_productions() {
+        let productions = productions(MATCH_EXPR);
+        let names: Vec<&str> = productions.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["MatchExpression", "Scrutinee", "MatchArm"]);
+        assert_eq!(productions[0].definition.lines().count(), 5);
+        assert!(productions[0].uses("Scrutinee"));
+        assert!(!productions[0].uses("MatchExpression"));
+
+        assert_eq!(production_rank("MatchArm", "match arm"), Some(0));
+        assert_eq!(production_rank("MatchArmGuard", "MatchArm"), Some(1));
+        assert_eq!(production_rank("OuterAttribute", "attribute"), Some(2));
+        assert_eq!(production_rank("Pattern", "match"), None);
+    }
+}
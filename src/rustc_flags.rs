@@ -0,0 +1,553 @@
+//! rustc command-line flags.
+//!
+//! The rustc book documents the command-line arguments on one page and the `-C` codegen options
+//! on another; the `-Z` flags have one page each in the unstable book. These pages live in
+//! rust-lang/rust, so like the release notes they are downloaded instead of cloning the
+//! compiler repository. The `rustc_flag` tool looks flags up by name, exact or by prefix.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Directory of the downloaded pages, under the data directory
+const RUSTC_FLAGS_DIR: &str = "rustc-flags";
+
+/// Raw files of the rustc book's sources
+const RUSTC_BOOK_RAW: &str =
+    "https://raw.githubusercontent.com/rust-lang/rust/HEAD/src/doc/rustc/src";
+
+/// GitHub API listing of the unstable book's compiler flag pages
+const UNSTABLE_FLAGS_LISTING: &str =
+    "https://api.github.com/repos/rust-lang/rust/contents/src/doc/unstable-book/src/compiler-flags";
+
+const RUSTC_BOOK_URL: &str = "https://doc.rust-lang.org/rustc";
+const UNSTABLE_BOOK_URL: &str = "https://doc.rust-lang.org/nightly/unstable-book/compiler-flags";
+
+/// Command-line arguments page (source path in the rustc book, local file name)
+const ARGUMENTS_PAGE: (&str, &str) = ("command-line-arguments.md", "command-line-arguments.md");
+
+/// Codegen options page (source path in the rustc book, local file name)
+const CODEGEN_PAGE: (&str, &str) = ("codegen-options/index.md", "codegen-options.md");
+
+/// Local directory of the `-Z` flag pages
+const UNSTABLE_DIR: &str = "compiler-flags";
+
+/// Which family a flag belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FlagKind {
+    /// A top-level argument such as `--edition` or `-L`
+    Argument,
+    /// A `-C` codegen option
+    Codegen,
+    /// A `-Z` unstable option (nightly only)
+    Unstable,
+}
+
+impl FlagKind {
+    /// Name used in tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagKind::Argument => "argument",
+            FlagKind::Codegen => "codegen",
+            FlagKind::Unstable => "unstable",
+        }
+    }
+}
+
+/// A documented rustc flag
+#[derive(Debug, Clone, PartialEq)]
+pub struct RustcFlag {
+    pub kind: FlagKind,
+    /// Spellings of the flag: `-h`, `--help` for arguments; the option name (`opt-level`) for
+    /// `-C` and `-Z` options
+    pub names: Vec<String>,
+    /// The heading's description, e.g. "configure the compilation environment"
+    pub summary: Option<String>,
+    /// The flag's documentation as markdown
+    pub markdown: String,
+    /// Page on doc.rust-lang.org
+    pub url: String,
+}
+
+impl RustcFlag {
+    /// The flag as typed on the command line: `--cfg`, `-C opt-level`, `-Z sanitizer`
+    pub fn display_name(&self) -> String {
+        match self.kind {
+            FlagKind::Argument => self.names.join("/"),
+            FlagKind::Codegen => format!("-C {}", self.names[0]),
+            FlagKind::Unstable => format!("-Z {}", self.names[0]),
+        }
+    }
+}
+
+/// A flag lookup: `-C opt`, `-Copt-level=3`, `--edition`, `-Z sanitizer`, or a bare name
+#[derive(Debug, PartialEq, Eq)]
+pub struct FlagQuery {
+    /// `None` for a bare name, which matches every family
+    pub kind: Option<FlagKind>,
+    pub name: String,
+}
+
+impl FlagQuery {
+    pub fn parse(query: &str) -> Self {
+        let query = query.trim().trim_matches('`').trim();
+        let query = query.strip_prefix("rustc ").unwrap_or(query).trim();
+        // Values are irrelevant: `-C opt-level=3` looks up `opt-level`
+        let query = query.split('=').next().unwrap_or("").trim();
+
+        let option = |kind, rest: &str| Self {
+            kind: Some(kind),
+            name: option_name(rest),
+        };
+        if let Some(rest) = query
+            .strip_prefix("--codegen")
+            .or_else(|| query.strip_prefix("-C"))
+        {
+            return option(FlagKind::Codegen, rest);
+        }
+        if let Some(rest) = query.strip_prefix("-Z") {
+            return option(FlagKind::Unstable, rest);
+        }
+        if query.starts_with('-') {
+            return Self {
+                kind: Some(FlagKind::Argument),
+                name: query.split_whitespace().next().unwrap_or("").to_string(),
+            };
+        }
+        Self {
+            kind: None,
+            name: option_name(query),
+        }
+    }
+}
+
+/// Normalized `-C`/`-Z` option name: rustc accepts `_` and `-` alike
+fn option_name(name: &str) -> String {
+    name.trim().replace('_', "-")
+}
+
+/// The flags from the downloaded rustc book and unstable book pages
+#[derive(Debug, Default)]
+pub struct RustcFlags {
+    flags: Vec<RustcFlag>,
+}
+
+impl RustcFlags {
+    /// Directory of the downloaded pages
+    pub fn dir(data_dir: &Path) -> PathBuf {
+        data_dir.join(RUSTC_FLAGS_DIR)
+    }
+
+    /// Download the pages that are missing, returning how many files were written
+    pub fn download(data_dir: &Path) -> Result<usize> {
+        let dir = Self::dir(data_dir);
+        std::fs::create_dir_all(&dir)?;
+        let mut written = 0;
+
+        for (source, file) in [ARGUMENTS_PAGE, CODEGEN_PAGE] {
+            let path = dir.join(file);
+            if !path.exists() {
+                let url = format!("{}/{}", RUSTC_BOOK_RAW, source);
+                tracing::info!("Downloading {}...", url);
+                std::fs::write(&path, fetch(&url)?)?;
+                written += 1;
+            }
+        }
+
+        let unstable_dir = dir.join(UNSTABLE_DIR);
+        if !unstable_dir.exists() {
+            tracing::info!("Downloading the unstable book's compiler flags...");
+            written += download_markdown_directory(UNSTABLE_FLAGS_LISTING, &unstable_dir)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Load the downloaded pages; empty when they are missing
+    pub fn load(data_dir: &Path) -> Self {
+        let dir = Self::dir(data_dir);
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok();
+
+        let mut flags = Vec::new();
+        if let Some(markdown) = read(ARGUMENTS_PAGE.1) {
+            flags.extend(parse_arguments(&markdown));
+        }
+        if let Some(markdown) = read(CODEGEN_PAGE.1) {
+            flags.extend(parse_codegen_options(&markdown));
+        }
+        if let Ok(entries) = std::fs::read_dir(dir.join(UNSTABLE_DIR)) {
+            for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+                let name = path.file_stem().and_then(|stem| stem.to_str());
+                let markdown = std::fs::read_to_string(&path).ok();
+                if let (Some(name), Some(markdown)) = (name, markdown) {
+                    flags.push(parse_unstable_flag(name, &markdown));
+                }
+            }
+        }
+
+        if flags.is_empty() {
+            tracing::debug!("No rustc flag pages at {:?}", dir);
+        } else {
+            tracing::info!("Loaded {} rustc flags", flags.len());
+        }
+        Self { flags }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// Flags named exactly as the query, or else those whose name starts with it (`-C opt`
+    /// finds `opt-level`); arguments first, then codegen and unstable options, by name
+    pub fn find(&self, query: &FlagQuery) -> Vec<&RustcFlag> {
+        if query.kind.is_none() && query.name.is_empty() {
+            return Vec::new();
+        }
+        let candidates = || {
+            self.flags
+                .iter()
+                .filter(|flag| query.kind.is_none_or(|kind| flag.kind == kind))
+        };
+        let names = |flag: &RustcFlag| -> Vec<String> {
+            match (query.kind, flag.kind) {
+                // A bare name also matches arguments without their dashes
+                (None, FlagKind::Argument) => flag
+                    .names
+                    .iter()
+                    .map(|name| name.trim_start_matches('-').to_string())
+                    .collect(),
+                _ => flag.names.clone(),
+            }
+        };
+
+        let exact: Vec<&RustcFlag> = candidates()
+            .filter(|flag| names(flag).contains(&query.name))
+            .collect();
+        let mut found = if exact.is_empty() {
+            candidates()
+                .filter(|flag| names(flag).iter().any(|name| name.starts_with(&query.name)))
+                .collect()
+        } else {
+            exact
+        };
+        found.sort_by(|a, b| (a.kind, &a.names).cmp(&(b.kind, &b.names)));
+        found
+    }
+
+    /// Names of every flag of `kind`
+    pub fn names(&self, kind: FlagKind) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .flags
+            .iter()
+            .filter(|flag| flag.kind == kind)
+            .map(RustcFlag::display_name)
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// GET a URL as text
+pub fn fetch(url: &str) -> Result<String> {
+    ureq::get(url)
+        .call()
+        .map_err(|e| Error::Other(format!("Failed to download {}: {}", url, e)))?
+        .into_body()
+        .read_to_string()
+        .map_err(|e| Error::Other(format!("Failed to read {}: {}", url, e)))
+}
+
+/// Download the markdown files of a GitHub API directory listing into `target`, returning how
+/// many were written. Files land in a temporary directory first, so an interrupted download is
+/// retried rather than leaving `target` half-filled.
+pub fn download_markdown_directory(listing_url: &str, target: &Path) -> Result<usize> {
+    let listing: serde_json::Value = serde_json::from_str(&fetch(listing_url)?)
+        .map_err(|e| Error::Other(format!("Invalid listing from {}: {}", listing_url, e)))?;
+    let pages: Vec<(&str, &str)> = listing
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.get("name")?.as_str()?;
+            let url = entry.get("download_url")?.as_str()?;
+            name.ends_with(".md").then_some((name, url))
+        })
+        .collect();
+
+    let mut partial = target.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    std::fs::create_dir_all(&partial)?;
+    for (name, url) in &pages {
+        std::fs::write(partial.join(name), fetch(url)?)?;
+    }
+    std::fs::rename(&partial, target)?;
+    Ok(pages.len())
+}
+
+/// A `##` section: the heading text, the explicit `<a id>` anchor above it, and its body
+struct Section<'a> {
+    heading: &'a str,
+    anchor: Option<&'a str>,
+    body: String,
+}
+
+/// Split a page at its `##` headings (deeper headings stay in the body), skipping code blocks
+fn sections(markdown: &str) -> Vec<Section<'_>> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut anchor = None;
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        if !in_code && let Some(heading) = line.strip_prefix("## ") {
+            sections.push(Section {
+                heading: heading.trim(),
+                anchor: anchor.take(),
+                body: String::new(),
+            });
+            continue;
+        }
+        if !in_code && let Some(id) = anchor_id(line) {
+            anchor = Some(id);
+            continue;
+        }
+        if let Some(section) = sections.last_mut() {
+            section.body.push_str(line);
+            section.body.push('\n');
+        }
+    }
+    for section in &mut sections {
+        section.body = section.body.trim().to_string();
+    }
+    sections
+}
+
+/// The id of an `<a id="..."></a>` line
+fn anchor_id(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix("<a id=\"")?;
+    rest.split('"').next()
+}
+
+/// mdBook's id for a heading: lowercase, spaces as dashes, punctuation other than `-`/`_` dropped
+pub fn heading_id(heading: &str) -> String {
+    heading
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c.to_ascii_lowercase()),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Arguments from `## `-h`/`--help`: get help` headings
+fn parse_arguments(markdown: &str) -> Vec<RustcFlag> {
+    sections(markdown)
+        .into_iter()
+        .filter_map(|section| {
+            let (flags, summary) = match section.heading.split_once(':') {
+                Some((flags, summary)) => (flags, Some(summary.trim().to_string())),
+                None => (section.heading, None),
+            };
+            let names: Vec<String> = flags
+                .split('/')
+                .map(|name| name.trim().trim_matches('`').trim().to_string())
+                .filter(|name| name.starts_with('-'))
+                .collect();
+            if names.is_empty() {
+                return None;
+            }
+            let anchor = section
+                .anchor
+                .map_or_else(|| heading_id(section.heading), str::to_string);
+            Some(RustcFlag {
+                kind: FlagKind::Argument,
+                names,
+                summary,
+                markdown: section.body,
+                url: format!(
+                    "{}/{}#{}",
+                    RUSTC_BOOK_URL, "command-line-arguments.html", anchor
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Codegen options from the `## opt-level` headings of the codegen options page
+fn parse_codegen_options(markdown: &str) -> Vec<RustcFlag> {
+    sections(markdown)
+        .into_iter()
+        .filter(|section| !section.heading.contains(char::is_whitespace))
+        .map(|section| {
+            let name = section.heading.trim_matches('`').to_string();
+            RustcFlag {
+                kind: FlagKind::Codegen,
+                url: format!(
+                    "{}/codegen-options/index.html#{}",
+                    RUSTC_BOOK_URL,
+                    heading_id(&name)
+                ),
+                names: vec![name],
+                summary: None,
+                markdown: section.body,
+            }
+        })
+        .collect()
+}
+
+/// A `-Z` flag from its unstable book page (`sanitizer.md`, titled `# `sanitizer``)
+fn parse_unstable_flag(file_stem: &str, markdown: &str) -> RustcFlag {
+    let body = markdown
+        .trim_start()
+        .strip_prefix("# ")
+        .map_or(markdown, |rest| {
+            rest.split_once('\n').map_or("", |(_, body)| body)
+        });
+    RustcFlag {
+        kind: FlagKind::Unstable,
+        names: vec![option_name(file_stem)],
+        summary: None,
+        markdown: body.trim().to_string(),
+        url: format!("{}/{}.html", UNSTABLE_BOOK_URL, file_stem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARGUMENTS: &str = "\
+# Command-line Arguments
+
+Here's a list of command-line arguments to `rustc` and what they do.
+
+<a id=\"option-help\"></a>
+## `-h`/`--help`: get help
+
+This flag will print out help information for `rustc`.
+
+## `--cfg`: configure the compilation environment
+
+```text
+## not a heading
+```
+
+<a id=\"option-edition\"></a>
+## `--edition`: specify the edition to use
+";
+
+    const CODEGEN: &str = "\
+# Codegen Options
+
+All of these options are passed to `rustc` via the `-C` flag.
+
+## opt-level
+
+This flag controls the optimization level.
+
+### Values
+
+* `0`: no optimizations
+
+## overflow-checks
+
+Enables overflow checks.
+
+## panic
+
+Controls the panic strategy.
+";
+
+    fn flags() -> RustcFlags {
+        let mut flags = parse_arguments(ARGUMENTS);
+        flags.extend(parse_codegen_options(CODEGEN));
+        flags.push(parse_unstable_flag(
+            "sanitizer",
+            "# `sanitizer`\n\nSanitizers are tools that help detect bugs.\n",
+        ));
+        RustcFlags { flags }
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        let flags = flags();
+        let help = &flags.flags[0];
+        assert_eq!(help.names, vec!["-h", "--help"]);
+        assert_eq!(help.summary.as_deref(), Some("get help"));
+        assert_eq!(
+            help.url,
+            "https://doc.rust-lang.org/rustc/command-line-arguments.html#option-help"
+        );
+        let cfg = &flags.flags[1];
+        assert!(cfg.markdown.contains("## not a heading"));
+        assert!(
+            cfg.url
+                .ends_with("#--cfg-configure-the-compilation-environment")
+        );
+
+        let opt_level = &flags.flags[3];
+        assert_eq!(opt_level.display_name(), "-C opt-level");
+        assert!(opt_level.markdown.contains("### Values"));
+        assert!(!opt_level.markdown.contains("overflow"));
+
+        let sanitizer = flags.flags.last().unwrap();
+        assert_eq!(sanitizer.display_name(), "-Z sanitizer");
+        assert_eq!(
+            sanitizer.markdown,
+            "Sanitizers are tools that help detect bugs."
+        );
+    }
+
+    #[test]
+    fn test_query_parse() {
+        let codegen = |name: &str| FlagQuery {
+            kind: Some(FlagKind::Codegen),
+            name: name.to_string(),
+        };
+        assert_eq!(FlagQuery::parse("-C opt"), codegen("opt"));
+        assert_eq!(FlagQuery::parse("-Copt-level=3"), codegen("opt-level"));
+        assert_eq!(
+            FlagQuery::parse("rustc --codegen panic=abort"),
+            codegen("panic")
+        );
+        assert_eq!(
+            FlagQuery::parse("-Z sanitizer_memory_track_origins"),
+            FlagQuery {
+                kind: Some(FlagKind::Unstable),
+                name: "sanitizer-memory-track-origins".to_string(),
+            }
+        );
+        assert_eq!(
+            FlagQuery::parse("--edition=2021"),
+            FlagQuery {
+                kind: Some(FlagKind::Argument),
+                name: "--edition".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let flags = flags();
+        let found = |query: &str| -> Vec<String> {
+            flags
+                .find(&FlagQuery::parse(query))
+                .iter()
+                .map(|flag| flag.display_name())
+                .collect()
+        };
+        assert_eq!(found("-C opt"), vec!["-C opt-level"]);
+        assert_eq!(found("-C o"), vec!["-C opt-level", "-C overflow-checks"]);
+        assert_eq!(found("-C panic=abort"), vec!["-C panic"]);
+        assert_eq!(found("--help"), vec!["-h/--help"]);
+        assert_eq!(found("--ed"), vec!["--edition"]);
+        assert_eq!(found("edition"), vec!["--edition"]);
+        assert_eq!(found("sanitizer"), vec!["-Z sanitizer"]);
+        assert_eq!(found("-C").len(), 3);
+        assert!(found("").is_empty());
+        assert!(found("-Z opt").is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+//! Back matter: the Book's appendices on keywords, operators, and derivable traits, and the
+//! Reference's glossary.
+//!
+//! These pages are lookup tables rather than prose, so a query about one term ("what does the
+//! `move` keyword do", "what is a turbofish") ranks them below chapters that merely use the term
+//! often. When a query asks about a single term and a back-matter page among the results
+//! defines it, the page moves to the front with the definition as its snippet: the table row
+//! under its header, the list item, or the section below the term's heading.
+
+use crate::search::index::SearchResult;
+use crate::search::snippet::{QueryTerms, markdown_snippet};
+
+/// Back-matter pages, by source and path
+pub const BACK_MATTER: &[(&str, &str)] = &[
+    ("rust-book", "appendix-01-keywords.md"),
+    ("rust-book", "appendix-02-operators.md"),
+    ("rust-book", "appendix-03-derivable-traits.md"),
+    ("rust-reference", "glossary.md"),
+];
+
+/// Longest term looked up
+const MAX_TERM_LEN: usize = 40;
+
+/// Words after which a query names the term it asks about
+const TERM_PREFIXES: &[&str] = &[
+    "what is a",
+    "what is an",
+    "what is the",
+    "what is",
+    "what's a",
+    "what's an",
+    "what's",
+    "what does",
+    "what are",
+    "define",
+    "definition of",
+    "meaning of",
+];
+
+/// Words that follow the term in "the `move` keyword" or "the `?` operator"
+const TERM_KINDS: &[&str] = &["keyword", "operator", "symbol", "sigil"];
+
+/// Whether a result is a back-matter page
+pub fn is_back_matter(result: &SearchResult) -> bool {
+    BACK_MATTER
+        .iter()
+        .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+}
+
+/// The single term a query asks about: a code span, the word before "keyword" or "operator",
+/// or the rest of a "what is ..." question of up to three words
+pub fn exact_term(query: &str) -> Option<String> {
+    let term = code_span(query).or_else(|| {
+        let words: Vec<&str> = query.split_whitespace().map(trim_punctuation).collect();
+        let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+        let before_kind = lower
+            .iter()
+            .position(|w| TERM_KINDS.contains(&w.as_str()))
+            .filter(|&i| i > 0 && !matches!(lower[i - 1].as_str(), "the" | "a" | "an"))
+            .map(|i| words[i - 1].to_string());
+        before_kind.or_else(|| {
+            let question = lower.join(" ");
+            let prefix = TERM_PREFIXES.iter().find(|prefix| {
+                question
+                    .strip_prefix(*prefix)
+                    .is_some_and(|rest| rest.starts_with(' '))
+            })?;
+            let rest = &words[prefix.split(' ').count()..];
+            let rest = match rest.last() {
+                Some(last) if last.eq_ignore_ascii_case("mean") => &rest[..rest.len() - 1],
+                _ => rest,
+            };
+            (!rest.is_empty() && rest.len() <= 3).then(|| rest.join(" "))
+        })
+    })?;
+    (!term.is_empty() && term.len() <= MAX_TERM_LEN).then_some(term)
+}
+
+/// The first inline code span of a query
+fn code_span(query: &str) -> Option<String> {
+    let mut parts = query.split('`');
+    parts.next()?;
+    let span = parts.next()?.trim();
+    // An unclosed backtick isn't a code span
+    parts.next()?;
+    Some(span.to_string())
+}
+
+/// A query word without surrounding punctuation; operators such as `?` are kept whole
+fn trim_punctuation(word: &str) -> &str {
+    let trimmed = word.trim_matches(|c: char| matches!(c, '?' | '.' | ',' | '!' | '"' | '\''));
+    if trimmed.is_empty() { word } else { trimmed }
+}
+
+/// Whether a table cell, list item name, or heading names `term`, ignoring code and emphasis
+/// markers and case
+fn names(text: &str, term: &str) -> bool {
+    text.trim()
+        .trim_matches(|c: char| c == '`' || c == '*')
+        .trim()
+        .eq_ignore_ascii_case(term)
+}
+
+/// The entry defining `term` in a back-matter page: matching table rows under the table's
+/// header, a list item starting with the term, or the section below a heading naming it
+pub fn entry(markdown: &str, term: &str) -> Option<String> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    table_rows(&lines, term)
+        .or_else(|| list_item(&lines, term))
+        .or_else(|| heading_section(&lines, term))
+}
+
+/// Rows whose first cell is `term`, with the header of their table
+fn table_rows(lines: &[&str], term: &str) -> Option<String> {
+    let mut header: Option<(&str, &str)> = None;
+    let mut found: Vec<&str> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !line.starts_with('|') {
+            if !found.is_empty() {
+                break;
+            }
+            header = None;
+            continue;
+        }
+        match header {
+            None => {
+                header = lines
+                    .get(i + 1)
+                    .filter(|next| next.starts_with("|-"))
+                    .map(|separator| (*line, *separator));
+            }
+            Some((_, separator)) if *line == separator => {}
+            Some(_) if names(&first_cell(line), term) => found.push(line),
+            Some(_) => {}
+        }
+    }
+    let (header, separator) = header?;
+    (!found.is_empty()).then(|| [&[header, separator][..], &found].concat().join("\n"))
+}
+
+/// Text of the first cell of a table row, with escaped pipes unescaped
+fn first_cell(row: &str) -> String {
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let mut end = row.len();
+    let mut escaped = false;
+    for (i, c) in row.char_indices() {
+        match c {
+            '|' if !escaped => {
+                end = i;
+                break;
+            }
+            _ => escaped = c == '\\',
+        }
+    }
+    row[..end].replace("\\|", "|")
+}
+
+/// A top-level list item whose leading code span (as in "- `move` - make a closure ...") is
+/// `term`, with its continuation lines
+fn list_item(lines: &[&str], term: &str) -> Option<String> {
+    let start = lines.iter().position(|line| {
+        line.strip_prefix("- ")
+            .map(|item| item.trim_start_matches('*'))
+            .and_then(code_span_at_start)
+            .is_some_and(|name| names(name, term))
+    })?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| !line.starts_with("  ") || line.trim().is_empty())
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    Some(lines[start..end].join("\n"))
+}
+
+/// The code span a text starts with
+fn code_span_at_start(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix('`')?;
+    Some(&rest[..rest.find('`')?])
+}
+
+/// The heading naming `term`, or with `term` as one of its code spans ("`Debug` for Programmer
+/// Output"), and the lines below it up to the next heading of the same or a higher level
+fn heading_section(lines: &[&str], term: &str) -> Option<String> {
+    let level = |line: &str| {
+        let hashes = line.chars().take_while(|c| *c == '#').count();
+        (hashes > 0 && line[hashes..].starts_with(' ')).then_some(hashes)
+    };
+    let (start, start_level) = lines.iter().enumerate().find_map(|(i, line)| {
+        let heading_level = level(line)?;
+        let text = line[heading_level..].trim();
+        let in_code = text
+            .split('`')
+            .skip(1)
+            .step_by(2)
+            .any(|span| names(span, term));
+        (names(text, term) || in_code).then_some((i, heading_level))
+    })?;
+    let mut end = lines[start + 1..]
+        .iter()
+        .position(|line| level(line).is_some_and(|l| l <= start_level))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    // The Reference marks the next entry's rule just above its heading
+    while end > start + 1 {
+        let line = lines[end - 1].trim();
+        if line.is_empty() || (line.starts_with("r[") && line.ends_with(']')) {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    Some(lines[start..end].join("\n"))
+}
+
+/// Move back-matter results that define `term` to the front, keeping relative order, with
+/// the definition as their snippet; `markdown` fetches a result's stored markdown
+pub fn apply_boost(
+    term: &str,
+    results: &mut [SearchResult],
+    snippet_len: usize,
+    markdown: impl Fn(&SearchResult) -> Option<String>,
+) {
+    let mut terms = QueryTerms::new(term);
+    let mut defining = Vec::new();
+    for result in results.iter_mut().filter(|result| is_back_matter(result)) {
+        if let Some(entry) = markdown(result).and_then(|markdown| entry(&markdown, term)) {
+            result.snippet = markdown_snippet(&entry, &mut terms, snippet_len);
+            defining.push((result.source.clone(), result.path.clone()));
+        }
+    }
+    if defining.is_empty() {
+        return;
+    }
+    results.sort_by_key(|result| {
+        !defining
+            .iter()
+            .any(|(source, path)| result.source == *source && result.path == *path)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYWORDS: &str = "### Keywords Currently in Use\n\nThe following is a list of keywords.\n\n- `as` - perform primitive casting\n- `move` - make a closure take ownership of all its\n  captures\n- `mut` - denote mutability\n\n### Keywords Reserved for Future Use";
+
+    const OPERATORS: &str = "| Operator | Example | Explanation | Overloadable? |\n|---|---|---|---|\n| `!` | `ident!(...)` | Macro expansion |  |\n| `!` | `!expr` | Bitwise or logical complement | `Not` |\n| `\\|` | `pat \\| pat` | Pattern alternatives |  |\n| `?` | `expr?` | Error propagation |  |";
+
+    const GLOSSARY: &str = "r[glossary.trait]\n\n### Trait\n\nA trait is a language item that is used for describing the functionalities a type must provide.\n\nr[glossary.turbofish]\n\n### Turbofish\n\nPaths with generic parameters in expressions must prefix the opening brackets with a `::`.";
+
+    #[test]
+    fn test_exact_term() {
+        let term = |query: &str| exact_term(query);
+        assert_eq!(
+            term("what does the `move` keyword do").as_deref(),
+            Some("move")
+        );
+        assert_eq!(term("the dyn keyword").as_deref(), Some("dyn"));
+        assert_eq!(term("what is the ? operator").as_deref(), Some("?"));
+        assert_eq!(term("What is a turbofish?").as_deref(), Some("turbofish"));
+        assert_eq!(term("what does ABI mean").as_deref(), Some("ABI"));
+        assert_eq!(
+            term("define blanket implementation").as_deref(),
+            Some("blanket implementation")
+        );
+        assert_eq!(term("how do closures capture variables"), None);
+        assert_eq!(
+            term("what is the difference between a trait object and a generic"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_entry() {
+        assert_eq!(
+            entry(KEYWORDS, "move").unwrap(),
+            "- `move` - make a closure take ownership of all its\n  captures"
+        );
+        assert_eq!(
+            entry(OPERATORS, "!").unwrap(),
+            "| Operator | Example | Explanation | Overloadable? |\n|---|---|---|---|\n| `!` | `ident!(...)` | Macro expansion |  |\n| `!` | `!expr` | Bitwise or logical complement | `Not` |"
+        );
+        assert!(
+            entry(OPERATORS, "|")
+                .unwrap()
+                .ends_with("Pattern alternatives |  |")
+        );
+        assert_eq!(
+            entry(GLOSSARY, "trait").unwrap(),
+            "### Trait\n\nA trait is a language item that is used for describing the functionalities a type must provide."
+        );
+        assert!(entry(GLOSSARY, "ownership").is_none());
+        assert!(entry(KEYWORDS, "async").is_none());
+    }
+
+    #[test]
+    fn test_boost_moves_defining_page_first() {
+        let mut results = vec![
+            SearchResult::for_test("rust-book", "ch13-01-closures.md", 0.5),
+            SearchResult::for_test("rust-reference", "glossary.md", 0.5),
+            SearchResult::for_test("rust-book", "appendix-01-keywords.md", 0.5),
+        ];
+        let markdown = |result: &SearchResult| match result.path.as_str() {
+            "appendix-01-keywords.md" => Some(KEYWORDS.to_string()),
+            "glossary.md" => Some(GLOSSARY.to_string()),
+            _ => None,
+        };
+        apply_boost("move", &mut results, 200, markdown);
+        assert_eq!(results[0].path, "appendix-01-keywords.md");
+        assert!(results[0].snippet.starts_with("- `move` - make a closure"));
+        // The glossary doesn't define the term, so it keeps its place and snippet
+        assert_eq!(results[1].path, "ch13-01-closures.md");
+        assert!(results[2].snippet.is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+//! Frequently asked Rust concepts whose query embeddings are computed at index time.
+//!
+//! Most `explain_concept` and search queries name one of a small set of concepts. Their query
+//! embeddings are stored with the vector index, so those searches skip model inference; any
+//! other query is embedded as usual.
+
+/// Concepts embedded as queries when the vector index is built
+pub const COMMON_CONCEPTS: &[&str] = &[
+    "ownership",
+    "borrowing",
+    "references",
+    "lifetimes",
+    "move semantics",
+    "copy and clone",
+    "traits",
+    "trait objects",
+    "generics",
+    "associated types",
+    "closures",
+    "iterators",
+    "smart pointers",
+    "box",
+    "rc",
+    "refcell",
+    "interior mutability",
+    "error handling",
+    "result",
+    "option",
+    "panic",
+    "pattern matching",
+    "enums",
+    "structs",
+    "modules",
+    "macros",
+    "async",
+    "async await",
+    "futures",
+    "concurrency",
+    "threads",
+    "send and sync",
+    "mutex",
+    "channels",
+    "unsafe",
+    "raw pointers",
+    "slices",
+    "strings",
+    "vectors",
+    "hashmap",
+    "testing",
+    "drop",
+    "deref",
+    "mutability",
+    "shadowing",
+];
+
+/// Key a query is looked up by: lowercase, `-` and `_` read as spaces, whitespace collapsed,
+/// and a trailing question mark or period dropped ("Error-Handling?" -> "error handling")
+pub fn concept_key(query: &str) -> String {
+    query
+        .trim()
+        .trim_end_matches(['?', '.'])
+        .to_lowercase()
+        .replace(['-', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concept_key() {
+        assert_eq!(concept_key("  Error-Handling? "), "error handling");
+        assert_eq!(concept_key("async   await"), "async await");
+        assert_eq!(concept_key("Send_and_Sync."), "send and sync");
+        // Every concept is its own key
+        for concept in COMMON_CONCEPTS {
+            assert_eq!(concept_key(concept), *concept);
+        }
+    }
+}
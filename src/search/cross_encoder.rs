@@ -0,0 +1,225 @@
+//! Cross-encoder reranking of search results.
+//!
+//! RRF fusion orders results by rank alone, which is coarse for nuanced queries. A
+//! cross-encoder reads the query and a passage together and scores their relevance, far more
+//! precisely than comparing embeddings, but too slowly for more than a few dozen passages. So
+//! a search asking for reranking (`SearchOptions::rerank`) fetches `RERANK_CANDIDATES`
+//! results, and the cross-encoder reorders them before they are truncated to the limit.
+//!
+//! The model (ms-marco-MiniLM-L-6-v2, about 90 MB) is downloaded on first use, next to the
+//! embedding models. A loaded model is a `Reranker`, a pool of sessions (see `SessionPool`)
+//! owned by the server, so concurrent reranked searches don't wait on one session.
+
+use std::path::{Path, PathBuf};
+
+use ort::session::Session;
+use ort::session::builder::GraphOptimizationLevel;
+use ort::session::input::SessionInputValue;
+use ort::value::Value;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams, TruncationStrategy};
+
+use crate::error::{Error, Result};
+use crate::search::embeddings::download_file;
+use crate::search::index::SearchResult;
+use crate::search::session_pool::SessionPool;
+
+/// ID of the reranking model, also the name of its directory under the models directory
+pub const RERANK_MODEL: &str = "ms-marco-MiniLM-L-6-v2";
+
+/// Hugging Face repository with `onnx/model.onnx` and `tokenizer.json`
+const RERANK_REPO: &str = "cross-encoder/ms-marco-MiniLM-L-6-v2";
+
+/// Results scored by the cross-encoder; the rest keep their order after them
+pub const RERANK_CANDIDATES: usize = 30;
+
+/// Longest query and passage pair in tokens; passages are truncated to fit
+const MAX_SEQ_LENGTH: usize = 512;
+
+/// Most sessions of the reranking model; each holds a copy of the model's weights
+const MAX_SESSIONS: usize = 2;
+
+/// Cross-encoder scoring query and passage pairs
+pub struct CrossEncoder {
+    session: Session,
+    tokenizer: Tokenizer,
+}
+
+impl CrossEncoder {
+    /// Directory holding the model files
+    pub fn dir(models_dir: &Path) -> PathBuf {
+        models_dir.join(RERANK_MODEL)
+    }
+
+    /// Load the model from disk, downloading it if necessary
+    pub fn load(models_dir: &Path) -> Result<Self> {
+        let dir = Self::dir(models_dir);
+        let model_path = dir.join("model.onnx");
+        let tokenizer_path = dir.join("tokenizer.json");
+
+        if !model_path.exists() || !tokenizer_path.exists() {
+            tracing::info!("Downloading reranking model {}...", RERANK_MODEL);
+            std::fs::create_dir_all(&dir)?;
+            let url = |file: &str| {
+                format!(
+                    "https://huggingface.co/{}/resolve/main/{}",
+                    RERANK_REPO, file
+                )
+            };
+            if !model_path.exists() {
+                download_file(&url("onnx/model.onnx"), &model_path)?;
+            }
+            if !tokenizer_path.exists() {
+                download_file(&url("tokenizer.json"), &tokenizer_path)?;
+            }
+        }
+
+        tracing::info!("Loading reranking model {} from {:?}", RERANK_MODEL, dir);
+        let session = Session::builder()?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .with_intra_threads(4)?
+            .commit_from_file(&model_path)?;
+
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| Error::Other(format!("Failed to load tokenizer: {}", e)))?;
+        // Pairs are padded to the longest in the batch; passages give way to the query
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_SEQ_LENGTH,
+                strategy: TruncationStrategy::OnlySecond,
+                ..Default::default()
+            }))
+            .map_err(|e| Error::Other(format!("Failed to configure tokenizer: {}", e)))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+
+        Ok(Self { session, tokenizer })
+    }
+
+    /// Relevance of each passage to the query, in `[0, 1]`
+    pub fn score(&mut self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        if passages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pairs: Vec<(&str, &str)> = passages.iter().map(|passage| (query, *passage)).collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| Error::Other(format!("Tokenization failed: {}", e)))?;
+
+        let batch_size = encodings.len();
+        let seq_length = encodings.first().map_or(0, |encoding| encoding.len());
+        let tensor = |values: Vec<i64>| {
+            ndarray::Array2::from_shape_vec((batch_size, seq_length), values)
+                .map_err(|e| Error::Other(format!("Failed to create input array: {}", e)))
+        };
+        let flatten = |field: fn(&tokenizers::Encoding) -> &[u32]| {
+            encodings
+                .iter()
+                .flat_map(|encoding| field(encoding).iter().map(|&value| value as i64))
+                .collect::<Vec<i64>>()
+        };
+        let input_ids = Value::from_array(tensor(flatten(tokenizers::Encoding::get_ids))?)?;
+        let attention_mask =
+            Value::from_array(tensor(flatten(tokenizers::Encoding::get_attention_mask))?)?;
+        let token_type_ids =
+            Value::from_array(tensor(flatten(tokenizers::Encoding::get_type_ids))?)?;
+
+        let outputs = self.session.run(vec![
+            ("input_ids", SessionInputValue::from(input_ids)),
+            ("attention_mask", SessionInputValue::from(attention_mask)),
+            ("token_type_ids", SessionInputValue::from(token_type_ids)),
+        ])?;
+
+        // One relevance logit per pair, shape [batch_size, 1]
+        let (_, logits) = outputs[0]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| Error::Other(format!("Failed to extract output: {}", e)))?;
+        Ok(logits
+            .iter()
+            .take(batch_size)
+            .map(|&logit| sigmoid(logit))
+            .collect())
+    }
+}
+
+/// The loaded reranking model
+pub struct Reranker {
+    sessions: SessionPool<CrossEncoder>,
+}
+
+impl Reranker {
+    /// Load the model from `models_dir`, downloading it if necessary
+    pub fn load(models_dir: &Path) -> Result<Self> {
+        let models_dir = models_dir.to_path_buf();
+        let sessions = SessionPool::new(MAX_SESSIONS, move || CrossEncoder::load(&models_dir))?;
+        Ok(Self { sessions })
+    }
+
+    /// Rescore the first `RERANK_CANDIDATES` results against the query and reorder them;
+    /// `passages` holds the text of each result. The results' scores become the
+    /// cross-encoder's.
+    #[tracing::instrument(name = "rerank", level = "debug", skip_all, fields(results = results.len()))]
+    pub fn rerank(
+        &self,
+        query: &str,
+        results: &mut [SearchResult],
+        passages: &[String],
+    ) -> Result<()> {
+        let count = results.len().min(passages.len()).min(RERANK_CANDIDATES);
+        let passages: Vec<&str> = passages[..count].iter().map(String::as_str).collect();
+        let scores = self
+            .sessions
+            .with_session(|model| model.score(query, &passages))?;
+        apply_scores(&mut results[..count], &scores);
+        Ok(())
+    }
+}
+
+/// Give results the cross-encoder's scores and order them by score, keeping the order of
+/// equals
+fn apply_scores(results: &mut [SearchResult], scores: &[f32]) {
+    for (result, &score) in results.iter_mut().zip(scores) {
+        result.score = score;
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+fn sigmoid(logit: f32) -> f32 {
+    1.0 / (1.0 + (-logit).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_scores_reorders() {
+        let mut results = vec![
+            SearchResult::for_test("rust-book", "a.md", 0.9),
+            SearchResult::for_test("rust-book", "b.md", 0.8),
+            SearchResult::for_test("rust-book", "c.md", 0.7),
+        ];
+        apply_scores(&mut results, &[sigmoid(-2.0), sigmoid(4.0), sigmoid(-2.0)]);
+
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.md", "a.md", "c.md"]);
+        assert!(results[0].score > 0.98 && results[0].score < 1.0);
+        assert!((sigmoid(0.0) - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    #[ignore] // Requires model download
+    fn test_cross_encoder_prefers_relevant_passage() {
+        let mut model = CrossEncoder::load(Path::new("data/models")).unwrap();
+        let scores = model
+            .score(
+                "how do lifetimes work",
+                &[
+                    "Lifetimes ensure that references are valid as long as we need them to be.",
+                    "Cargo is the Rust build system and package manager.",
+                ],
+            )
+            .unwrap();
+        assert!(scores[0] > scores[1]);
+    }
+}
@@ -0,0 +1,252 @@
+//! Embeddings cached by content hash.
+//!
+//! Re-indexing or re-embedding mostly sees texts that were embedded before, so the vectors are
+//! kept per model, keyed by a hash of the embedded text. A cached vector is only valid for the
+//! model and dimension it was computed with; a cache file of another model is ignored.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::search::embeddings::{EmbeddingService, ModelSpec};
+
+/// Cache file contents
+#[derive(Serialize, Deserialize)]
+struct StoredCache {
+    model: String,
+    dimension: usize,
+    /// Embeddings keyed by the hex FNV-1a hash of their text
+    entries: HashMap<String, Vec<f32>>,
+}
+
+/// Embeddings of one model, keyed by text hash
+pub struct EmbeddingCache {
+    model: &'static ModelSpec,
+    entries: HashMap<u64, Vec<f32>>,
+    /// Hashes looked up or added since loading; only these are saved, so texts that left the
+    /// corpus drop out of the cache
+    used: HashSet<u64>,
+    /// Texts found in the cache
+    pub hits: usize,
+    /// Texts that had to be embedded
+    pub misses: usize,
+}
+
+impl EmbeddingCache {
+    /// An empty cache for `model`
+    pub fn new(model: &'static ModelSpec) -> Self {
+        Self {
+            model,
+            entries: HashMap::new(),
+            used: HashSet::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Cache file of a model in `dir`
+    pub fn file_path(dir: &Path, model: &ModelSpec) -> PathBuf {
+        dir.join(format!("{}.json", model.id))
+    }
+
+    /// Load the model's cache from `dir`; a missing, unreadable, or mismatched file gives an
+    /// empty cache
+    pub fn load(dir: &Path, model: &'static ModelSpec) -> Self {
+        let mut cache = Self::new(model);
+        let path = Self::file_path(dir, model);
+        let Ok(file) = std::fs::File::open(&path) else {
+            return cache;
+        };
+        let stored: StoredCache = match serde_json::from_reader(std::io::BufReader::new(file)) {
+            Ok(stored) => stored,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable embedding cache {:?}: {}", path, e);
+                return cache;
+            }
+        };
+        if stored.model != model.id || stored.dimension != model.dimension {
+            tracing::warn!(
+                "Ignoring embedding cache {:?} of {} ({} dimensions)",
+                path,
+                stored.model,
+                stored.dimension
+            );
+            return cache;
+        }
+        cache.entries = stored
+            .entries
+            .into_iter()
+            .filter(|(_, embedding)| embedding.len() == model.dimension)
+            .filter_map(|(hash, embedding)| Some((u64::from_str_radix(&hash, 16).ok()?, embedding)))
+            .collect();
+        tracing::debug!("Loaded {} cached embeddings of {}", cache.len(), model.id);
+        cache
+    }
+
+    /// Save the entries used since loading to `dir`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let stored = StoredCache {
+            model: self.model.id.to_string(),
+            dimension: self.model.dimension,
+            entries: self
+                .entries
+                .iter()
+                .filter(|(hash, _)| self.used.contains(hash))
+                .map(|(hash, embedding)| (format!("{:016x}", hash), embedding.clone()))
+                .collect(),
+        };
+        let file = std::fs::File::create(Self::file_path(dir, self.model))?;
+        serde_json::to_writer(std::io::BufWriter::new(file), &stored)
+            .map_err(|e| Error::Other(format!("Failed to save embedding cache: {}", e)))
+    }
+
+    /// Keep every loaded entry when saving, not only those used since loading; for runs that
+    /// embed part of the corpus
+    pub fn keep_unused(&mut self) {
+        self.used.extend(self.entries.keys().copied());
+    }
+
+    /// Number of cached embeddings
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Cached embedding of a text
+    pub fn get(&mut self, text: &str) -> Option<&Vec<f32>> {
+        let hash = text_hash(text);
+        let embedding = self.entries.get(&hash)?;
+        self.used.insert(hash);
+        Some(embedding)
+    }
+
+    pub fn insert(&mut self, text: &str, embedding: Vec<f32>) {
+        let hash = text_hash(text);
+        self.used.insert(hash);
+        self.entries.insert(hash, embedding);
+    }
+
+    /// Embeddings of `texts`, computing (in one batch, with `embeddings`) only those not cached
+    /// yet
+    pub fn embed(
+        &mut self,
+        embeddings: &EmbeddingService,
+        texts: &[&str],
+    ) -> Result<Vec<Vec<f32>>> {
+        self.embed_with(texts, |texts| embeddings.embed_texts(texts))
+    }
+
+    fn embed_with(
+        &mut self,
+        texts: &[&str],
+        compute: impl FnOnce(&[&str]) -> Result<Vec<Vec<f32>>>,
+    ) -> Result<Vec<Vec<f32>>> {
+        let mut missing: Vec<&str> = Vec::new();
+        for text in texts {
+            if self.get(text).is_none() && !missing.contains(text) {
+                missing.push(text);
+            }
+        }
+        self.hits += texts.len() - missing.len();
+        self.misses += missing.len();
+
+        if !missing.is_empty() {
+            for (text, embedding) in missing.iter().zip(compute(&missing)?) {
+                self.insert(text, embedding);
+            }
+        }
+
+        texts
+            .iter()
+            .map(|text| {
+                self.entries
+                    .get(&text_hash(text))
+                    .cloned()
+                    .ok_or_else(|| Error::Other("Embedding missing from batch".to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Stable 64-bit hash of a text (FNV-1a)
+pub fn text_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::embeddings::{MODELS, default_model};
+
+    fn fake_embeddings(texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let mut embedding = vec![0.0; 384];
+                embedding[0] = text.len() as f32;
+                embedding
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_embed_reuses_cached_texts() {
+        let mut cache = EmbeddingCache::new(default_model());
+        let first = cache
+            .embed_with(&["ownership", "borrowing"], fake_embeddings)
+            .unwrap();
+        assert_eq!(first[1][0], 9.0);
+        assert_eq!((cache.hits, cache.misses), (0, 2));
+
+        let second = cache
+            .embed_with(&["borrowing", "lifetimes", "lifetimes"], |texts| {
+                assert_eq!(texts, ["lifetimes"]);
+                fake_embeddings(texts)
+            })
+            .unwrap();
+        assert_eq!(second.len(), 3);
+        assert_eq!(second[0], first[1]);
+        assert_eq!((cache.hits, cache.misses), (2, 3));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("embedding-cache-{}", std::process::id()));
+        let mut cache = EmbeddingCache::new(default_model());
+        cache
+            .embed_with(&["ownership", "traits"], fake_embeddings)
+            .unwrap();
+        cache.save(&dir).unwrap();
+
+        let mut loaded = EmbeddingCache::load(&dir, default_model());
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get("traits").unwrap()[0], 6.0);
+        // Only entries used since loading are saved again
+        loaded.save(&dir).unwrap();
+        assert_eq!(EmbeddingCache::load(&dir, default_model()).len(), 1);
+        // ...unless unused entries are kept
+        let mut partial = EmbeddingCache::load(&dir, default_model());
+        partial.insert("lifetimes", vec![0.0; 384]);
+        partial.keep_unused();
+        partial.save(&dir).unwrap();
+        assert_eq!(EmbeddingCache::load(&dir, default_model()).len(), 2);
+
+        // Another model's vectors are not reused
+        let other = MODELS
+            .iter()
+            .find(|model| model.id != default_model().id)
+            .unwrap();
+        std::fs::rename(
+            EmbeddingCache::file_path(&dir, default_model()),
+            EmbeddingCache::file_path(&dir, other),
+        )
+        .unwrap();
+        let other_cache = EmbeddingCache::load(&dir, other);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(other_cache.len(), 0);
+    }
+}
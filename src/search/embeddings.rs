@@ -1,54 +1,206 @@
 //! Embedding model for semantic search using ONNX Runtime.
 //!
-//! Uses all-MiniLM-L6-v2 model for generating 384-dimensional embeddings.
+//! Uses all-MiniLM-L6-v2 for 384-dimensional embeddings by default; larger models from
+//! `MODELS` can be configured. The vector index records which model built it, since vectors
+//! from different models (or of different dimensions) can't be compared.
+//!
+//! A loaded model is an `EmbeddingService`: a pool of ONNX sessions (see `SessionPool`), so
+//! concurrent searches embed their queries in parallel instead of queueing behind one session.
+//! The server owns its service and passes it to the searches and indexing runs that need it.
 
-use std::path::Path;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use ort::session::Session;
 use ort::session::builder::GraphOptimizationLevel;
 use ort::session::input::SessionInputValue;
-use ort::session::Session;
 use ort::value::Value;
 use tokenizers::Tokenizer;
 
 use crate::error::{Error, Result};
+use crate::search::query_embedder::{PendingEmbedding, QUEUE_LEN, QueryEmbedder};
+use crate::search::session_pool::SessionPool;
+
+/// Model used when none is configured
+pub const DEFAULT_MODEL: &str = "all-MiniLM-L6-v2";
+
+/// How token embeddings are pooled into a text embedding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pooling {
+    /// Mean over the non-padding tokens
+    Mean,
+    /// The first (`[CLS]`) token
+    Cls,
+}
 
-/// Model configuration
-const MODEL_NAME: &str = "all-MiniLM-L6-v2";
-const EMBEDDING_DIM: usize = 384;
-const MAX_SEQ_LENGTH: usize = 256;
+/// A supported embedding model
+#[derive(Debug, PartialEq, Eq)]
+pub struct ModelSpec {
+    pub id: &'static str,
+    /// Length of the embeddings the model produces
+    pub dimension: usize,
+    /// Longest input in tokens; longer texts are truncated
+    pub max_seq_length: usize,
+    pub pooling: Pooling,
+    /// Trained on many languages, so queries in other languages find the English documents
+    pub multilingual: bool,
+    /// Hugging Face repository with `onnx/model.onnx` and `tokenizer.json`
+    repo: &'static str,
+    /// Instruction prepended to search queries
+    query_prefix: &'static str,
+    /// Instruction prepended to indexed documents
+    document_prefix: &'static str,
+}
 
-/// URLs for downloading model files from Hugging Face
-const MODEL_URL: &str = "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/onnx/model.onnx";
-const TOKENIZER_URL: &str = "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/tokenizer.json";
+/// Built-in embedding models
+pub const MODELS: &[ModelSpec] = &[
+    ModelSpec {
+        id: DEFAULT_MODEL,
+        dimension: 384,
+        max_seq_length: 256,
+        pooling: Pooling::Mean,
+        multilingual: false,
+        repo: "sentence-transformers/all-MiniLM-L6-v2",
+        query_prefix: "",
+        document_prefix: "",
+    },
+    ModelSpec {
+        id: "bge-small-en-v1.5",
+        dimension: 384,
+        max_seq_length: 512,
+        pooling: Pooling::Cls,
+        multilingual: false,
+        repo: "BAAI/bge-small-en-v1.5",
+        query_prefix: "Represent this sentence for searching relevant passages: ",
+        document_prefix: "",
+    },
+    ModelSpec {
+        id: "bge-base-en-v1.5",
+        dimension: 768,
+        max_seq_length: 512,
+        pooling: Pooling::Cls,
+        multilingual: false,
+        repo: "BAAI/bge-base-en-v1.5",
+        query_prefix: "Represent this sentence for searching relevant passages: ",
+        document_prefix: "",
+    },
+    ModelSpec {
+        id: "e5-small-v2",
+        dimension: 384,
+        max_seq_length: 512,
+        pooling: Pooling::Mean,
+        multilingual: false,
+        repo: "Xenova/e5-small-v2",
+        query_prefix: "query: ",
+        document_prefix: "passage: ",
+    },
+    ModelSpec {
+        id: "gte-small",
+        dimension: 384,
+        max_seq_length: 512,
+        pooling: Pooling::Mean,
+        multilingual: false,
+        repo: "Xenova/gte-small",
+        query_prefix: "",
+        document_prefix: "",
+    },
+    ModelSpec {
+        id: "multilingual-e5-small",
+        dimension: 384,
+        max_seq_length: 512,
+        pooling: Pooling::Mean,
+        multilingual: true,
+        repo: "Xenova/multilingual-e5-small",
+        query_prefix: "query: ",
+        document_prefix: "passage: ",
+    },
+];
+
+/// The built-in model with this ID (case-insensitive)
+pub fn model_spec(id: &str) -> Option<&'static ModelSpec> {
+    MODELS
+        .iter()
+        .find(|model| model.id.eq_ignore_ascii_case(id.trim()))
+}
 
-/// Global embedding model instance (loaded once)
-static EMBEDDING_MODEL: Mutex<Option<EmbeddingModel>> = Mutex::new(None);
+/// The default embedding model
+pub fn default_model() -> &'static ModelSpec {
+    &MODELS[0]
+}
+
+impl ModelSpec {
+    /// Directory holding the model files. The default model stays directly in `models_dir`,
+    /// where it was downloaded before other models could be configured.
+    pub fn dir(&self, models_dir: &Path) -> PathBuf {
+        if self.id == DEFAULT_MODEL {
+            models_dir.to_path_buf()
+        } else {
+            models_dir.join(self.id)
+        }
+    }
+
+    fn model_url(&self) -> String {
+        format!(
+            "https://huggingface.co/{}/resolve/main/onnx/model.onnx",
+            self.repo
+        )
+    }
+
+    fn tokenizer_url(&self) -> String {
+        format!(
+            "https://huggingface.co/{}/resolve/main/tokenizer.json",
+            self.repo
+        )
+    }
+}
+
+/// Inference threads of a single session
+const MAX_INTRA_THREADS: usize = 4;
+
+/// Most sessions of a loaded model; each holds a copy of the model's weights
+const MAX_SESSIONS: usize = 4;
+
+/// Sessions of the loaded embedding model
+struct EmbeddingPool {
+    spec: &'static ModelSpec,
+    sessions: SessionPool<EmbeddingModel>,
+}
 
 /// Embedding model wrapper
 pub struct EmbeddingModel {
+    spec: &'static ModelSpec,
     session: Session,
     tokenizer: Tokenizer,
 }
 
 impl EmbeddingModel {
     /// Load the embedding model from disk, downloading if necessary
-    pub fn load(models_dir: &Path) -> Result<Self> {
+    pub fn load(models_dir: &Path, spec: &'static ModelSpec) -> Result<Self> {
+        Self::load_with_threads(models_dir, spec, MAX_INTRA_THREADS)
+    }
+
+    /// `load`, running inference on `intra_threads` threads
+    fn load_with_threads(
+        models_dir: &Path,
+        spec: &'static ModelSpec,
+        intra_threads: usize,
+    ) -> Result<Self> {
+        let models_dir = spec.dir(models_dir);
         let model_path = models_dir.join("model.onnx");
         let tokenizer_path = models_dir.join("tokenizer.json");
 
         // Download model files if they don't exist
         if !model_path.exists() || !tokenizer_path.exists() {
-            tracing::info!("Downloading embedding model {}...", MODEL_NAME);
-            Self::download_model_files(models_dir)?;
+            tracing::info!("Downloading embedding model {}...", spec.id);
+            Self::download_model_files(&models_dir, spec)?;
         }
 
-        tracing::info!("Loading embedding model from {:?}", models_dir);
+        tracing::info!("Loading embedding model {} from {:?}", spec.id, models_dir);
 
         // Load ONNX model
         let session = Session::builder()?
             .with_optimization_level(GraphOptimizationLevel::Level3)?
-            .with_intra_threads(4)?
+            .with_intra_threads(intra_threads)?
             .commit_from_file(&model_path)?;
 
         // Load tokenizer
@@ -56,11 +208,15 @@ impl EmbeddingModel {
             .map_err(|e| Error::Other(format!("Failed to load tokenizer: {}", e)))?;
 
         tracing::info!("Embedding model loaded successfully");
-        Ok(Self { session, tokenizer })
+        Ok(Self {
+            spec,
+            session,
+            tokenizer,
+        })
     }
 
     /// Download model files from Hugging Face
-    fn download_model_files(models_dir: &Path) -> Result<()> {
+    fn download_model_files(models_dir: &Path, spec: &ModelSpec) -> Result<()> {
         std::fs::create_dir_all(models_dir)?;
 
         let model_path = models_dir.join("model.onnx");
@@ -69,31 +225,18 @@ impl EmbeddingModel {
         // Download model.onnx
         if !model_path.exists() {
             tracing::info!("Downloading model.onnx...");
-            Self::download_file(MODEL_URL, &model_path)?;
+            download_file(&spec.model_url(), &model_path)?;
         }
 
         // Download tokenizer.json
         if !tokenizer_path.exists() {
             tracing::info!("Downloading tokenizer.json...");
-            Self::download_file(TOKENIZER_URL, &tokenizer_path)?;
+            download_file(&spec.tokenizer_url(), &tokenizer_path)?;
         }
 
         Ok(())
     }
 
-    /// Download a file from URL to disk
-    fn download_file(url: &str, dest: &Path) -> Result<()> {
-        let response = ureq::get(url)
-            .call()
-            .map_err(|e| Error::Other(format!("Failed to download {}: {}", url, e)))?;
-
-        let mut reader = response.into_body().into_reader();
-        let mut file = std::fs::File::create(dest)?;
-        std::io::copy(&mut reader, &mut file)?;
-
-        Ok(())
-    }
-
     /// Generate embedding for a single text
     pub fn embed(&mut self, text: &str) -> Result<Vec<f32>> {
         let embeddings = self.embed_batch(&[text])?;
@@ -113,21 +256,22 @@ impl EmbeddingModel {
             .map_err(|e| Error::Other(format!("Tokenization failed: {}", e)))?;
 
         let batch_size = encodings.len();
+        let max_seq_length = self.spec.max_seq_length;
 
         // Prepare input tensors
-        let mut input_ids: Vec<i64> = Vec::with_capacity(batch_size * MAX_SEQ_LENGTH);
-        let mut attention_mask: Vec<i64> = Vec::with_capacity(batch_size * MAX_SEQ_LENGTH);
-        let mut token_type_ids: Vec<i64> = Vec::with_capacity(batch_size * MAX_SEQ_LENGTH);
+        let mut input_ids: Vec<i64> = Vec::with_capacity(batch_size * max_seq_length);
+        let mut attention_mask: Vec<i64> = Vec::with_capacity(batch_size * max_seq_length);
+        let mut token_type_ids: Vec<i64> = Vec::with_capacity(batch_size * max_seq_length);
 
         for encoding in &encodings {
             let ids = encoding.get_ids();
             let mask = encoding.get_attention_mask();
             let types = encoding.get_type_ids();
 
-            // Truncate or pad to MAX_SEQ_LENGTH
-            let len = ids.len().min(MAX_SEQ_LENGTH);
+            // Truncate or pad to max_seq_length
+            let len = ids.len().min(max_seq_length);
 
-            for i in 0..MAX_SEQ_LENGTH {
+            for i in 0..max_seq_length {
                 if i < len {
                     input_ids.push(ids[i] as i64);
                     attention_mask.push(mask[i] as i64);
@@ -142,13 +286,13 @@ impl EmbeddingModel {
 
         // Create input arrays
         let input_ids_array =
-            ndarray::Array2::from_shape_vec((batch_size, MAX_SEQ_LENGTH), input_ids)
+            ndarray::Array2::from_shape_vec((batch_size, max_seq_length), input_ids)
                 .map_err(|e| Error::Other(format!("Failed to create input array: {}", e)))?;
         let attention_mask_array =
-            ndarray::Array2::from_shape_vec((batch_size, MAX_SEQ_LENGTH), attention_mask)
+            ndarray::Array2::from_shape_vec((batch_size, max_seq_length), attention_mask)
                 .map_err(|e| Error::Other(format!("Failed to create mask array: {}", e)))?;
         let token_type_ids_array =
-            ndarray::Array2::from_shape_vec((batch_size, MAX_SEQ_LENGTH), token_type_ids)
+            ndarray::Array2::from_shape_vec((batch_size, max_seq_length), token_type_ids)
                 .map_err(|e| Error::Other(format!("Failed to create type array: {}", e)))?;
 
         // Create ORT values
@@ -159,8 +303,14 @@ impl EmbeddingModel {
         // Run inference
         let outputs = self.session.run(vec![
             ("input_ids", SessionInputValue::from(input_ids_value)),
-            ("attention_mask", SessionInputValue::from(attention_mask_value)),
-            ("token_type_ids", SessionInputValue::from(token_type_ids_value)),
+            (
+                "attention_mask",
+                SessionInputValue::from(attention_mask_value),
+            ),
+            (
+                "token_type_ids",
+                SessionInputValue::from(token_type_ids_value),
+            ),
         ])?;
 
         // Extract embeddings from output
@@ -177,11 +327,19 @@ impl EmbeddingModel {
         let mut embeddings = Vec::with_capacity(batch_size);
 
         for (batch_idx, encoding) in encodings.iter().enumerate() {
-            let seq_len = encoding.get_ids().len().min(MAX_SEQ_LENGTH).min(seq_len_dim);
-
-            // Mean pooling over non-padding tokens
+            let seq_len = encoding
+                .get_ids()
+                .len()
+                .min(max_seq_length)
+                .min(seq_len_dim);
+
+            // Mean pooling over non-padding tokens, or the [CLS] token's embedding
+            let pooled_len = match self.spec.pooling {
+                Pooling::Mean => seq_len,
+                Pooling::Cls => seq_len.min(1),
+            };
             let mut embedding = vec![0.0f32; hidden_size];
-            for seq_idx in 0..seq_len {
+            for seq_idx in 0..pooled_len {
                 let offset = batch_idx * seq_len_dim * hidden_size + seq_idx * hidden_size;
                 for hidden_idx in 0..hidden_size {
                     embedding[hidden_idx] += output_data[offset + hidden_idx];
@@ -190,7 +348,7 @@ impl EmbeddingModel {
 
             // Divide by sequence length
             for val in &mut embedding {
-                *val /= seq_len as f32;
+                *val /= pooled_len as f32;
             }
 
             // L2 normalize
@@ -208,50 +366,113 @@ impl EmbeddingModel {
     }
 
     /// Get the embedding dimension
-    #[allow(dead_code)]
     pub fn dimension(&self) -> usize {
-        EMBEDDING_DIM
+        self.spec.dimension
     }
 }
 
-/// Initialize the global embedding model (call once at startup)
-pub fn init_embedding_model(models_dir: &Path) -> Result<()> {
-    let mut guard = EMBEDDING_MODEL.lock().map_err(|e| Error::Other(e.to_string()))?;
-    if guard.is_none() {
-        *guard = Some(EmbeddingModel::load(models_dir)?);
-    }
+/// Download a file from URL to disk
+pub(crate) fn download_file(url: &str, dest: &Path) -> Result<()> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| Error::Other(format!("Failed to download {}: {}", url, e)))?;
+
+    let mut reader = response.into_body().into_reader();
+    let mut file = std::fs::File::create(dest)?;
+    std::io::copy(&mut reader, &mut file)?;
+
     Ok(())
 }
 
-/// Get the global embedding model (must call init_embedding_model first)
-pub fn get_embedding_model() -> Result<std::sync::MutexGuard<'static, Option<EmbeddingModel>>> {
-    EMBEDDING_MODEL
-        .lock()
-        .map_err(|e| Error::Other(format!("Failed to lock embedding model: {}", e)))
+/// Sessions of a loaded model and inference threads of each: the cores are shared between up
+/// to `MAX_SESSIONS` sessions of two or more threads
+fn pool_size() -> (usize, usize) {
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let sessions = (cores / 2).clamp(1, MAX_SESSIONS);
+    (sessions, (cores / sessions).clamp(1, MAX_INTRA_THREADS))
 }
 
-/// Generate embedding using the global model
-pub fn embed_text(text: &str) -> Result<Vec<f32>> {
-    let mut guard = get_embedding_model()?;
-    let model = guard
-        .as_mut()
-        .ok_or_else(|| Error::Other("Embedding model not initialized".to_string()))?;
-    model.embed(text)
+impl EmbeddingPool {
+    /// Embed `texts` with one of the sessions, each prefixed with the model's query or document
+    /// prefix
+    fn embed_prefixed(
+        &self,
+        texts: &[&str],
+        prefix: impl Fn(&ModelSpec) -> &'static str,
+    ) -> Result<Vec<Vec<f32>>> {
+        let prefix = prefix(self.spec);
+        self.sessions.with_session(|model| {
+            if prefix.is_empty() {
+                model.embed_batch(texts)
+            } else {
+                let texts: Vec<String> = texts
+                    .iter()
+                    .map(|text| format!("{}{}", prefix, text))
+                    .collect();
+                model.embed_batch(&texts.iter().map(String::as_str).collect::<Vec<_>>())
+            }
+        })
+    }
+
+    fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.embed_prefixed(&[text], |spec| spec.query_prefix)?;
+        Ok(embeddings.into_iter().next().unwrap())
+    }
 }
 
-/// Generate embeddings for multiple texts using the global model
-pub fn embed_texts(texts: &[&str]) -> Result<Vec<Vec<f32>>> {
-    let mut guard = get_embedding_model()?;
-    let model = guard
-        .as_mut()
-        .ok_or_else(|| Error::Other("Embedding model not initialized".to_string()))?;
-    model.embed_batch(texts)
+/// A loaded embedding model: its pooled sessions and the threads embedding search queries with
+/// them. Clones share the model; its sessions and threads are released with the last clone.
+#[derive(Clone)]
+pub struct EmbeddingService {
+    pool: Arc<EmbeddingPool>,
+    queries: Arc<QueryEmbedder>,
 }
 
-/// Get the embedding dimension
-#[allow(dead_code)]
-pub fn embedding_dimension() -> usize {
-    EMBEDDING_DIM
+impl EmbeddingService {
+    /// Load `spec` from `models_dir`, downloading it if necessary
+    pub fn load(models_dir: &Path, spec: &'static ModelSpec) -> Result<Self> {
+        let (workers, intra_threads) = pool_size();
+        let models_dir = models_dir.to_path_buf();
+        let sessions = SessionPool::new(workers, move || {
+            EmbeddingModel::load_with_threads(&models_dir, spec, intra_threads)
+        })?;
+        let pool = Arc::new(EmbeddingPool { spec, sessions });
+        let worker_pool = pool.clone();
+        let queries = QueryEmbedder::new(workers, QUEUE_LEN, move |text| {
+            worker_pool.embed_query(text)
+        });
+        Ok(Self {
+            pool,
+            queries: Arc::new(queries),
+        })
+    }
+
+    /// The loaded model
+    pub fn model(&self) -> &'static ModelSpec {
+        self.pool.spec
+    }
+
+    /// Generate a search query's embedding
+    #[tracing::instrument(name = "embedding", level = "debug", skip_all)]
+    pub fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+        self.pool.embed_query(text)
+    }
+
+    /// Generate embeddings for multiple search queries
+    pub fn embed_queries(&self, queries: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.pool.embed_prefixed(queries, |spec| spec.query_prefix)
+    }
+
+    /// Generate embeddings for multiple documents
+    #[tracing::instrument(name = "embedding", level = "debug", skip_all, fields(texts = texts.len()))]
+    pub fn embed_texts(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.pool.embed_prefixed(texts, |spec| spec.document_prefix)
+    }
+
+    /// Queue a search query for the query embedding threads
+    pub(crate) fn queue_query(&self, text: String) -> PendingEmbedding {
+        self.queries.embed(text)
+    }
 }
 
 #[cfg(test)]
@@ -263,10 +484,10 @@ mod tests {
     #[ignore] // Requires model download
     fn test_embedding_generation() {
         let models_dir = PathBuf::from("data/models");
-        let mut model = EmbeddingModel::load(&models_dir).unwrap();
+        let mut model = EmbeddingModel::load(&models_dir, default_model()).unwrap();
 
         let embedding = model.embed("Hello, world!").unwrap();
-        assert_eq!(embedding.len(), EMBEDDING_DIM);
+        assert_eq!(embedding.len(), model.dimension());
 
         // Check normalization
         let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -277,14 +498,38 @@ mod tests {
     #[ignore] // Requires model download
     fn test_batch_embedding() {
         let models_dir = PathBuf::from("data/models");
-        let mut model = EmbeddingModel::load(&models_dir).unwrap();
+        let mut model = EmbeddingModel::load(&models_dir, default_model()).unwrap();
 
         let texts = vec!["Hello", "World", "Rust programming"];
         let embeddings = model.embed_batch(&texts).unwrap();
 
         assert_eq!(embeddings.len(), 3);
         for emb in &embeddings {
-            assert_eq!(emb.len(), EMBEDDING_DIM);
+            assert_eq!(emb.len(), model.dimension());
         }
     }
+
+    #[test]
+    fn test_model_spec() {
+        assert_eq!(model_spec("BGE-small-en-v1.5").unwrap().dimension, 384);
+        assert_eq!(
+            model_spec("bge-base-en-v1.5").unwrap().pooling,
+            Pooling::Cls
+        );
+        assert_eq!(model_spec("gte-small").unwrap().pooling, Pooling::Mean);
+        assert!(model_spec("text-embedding-3-large").is_none());
+        assert_eq!(default_model().id, DEFAULT_MODEL);
+
+        // The default model keeps its original location
+        let models_dir = Path::new("data/models");
+        assert_eq!(default_model().dir(models_dir), models_dir);
+        assert_eq!(
+            model_spec("e5-small-v2").unwrap().dir(models_dir),
+            models_dir.join("e5-small-v2")
+        );
+        assert_eq!(
+            model_spec("e5-small-v2").unwrap().model_url(),
+            "https://huggingface.co/Xenova/e5-small-v2/resolve/main/onnx/model.onnx"
+        );
+    }
 }
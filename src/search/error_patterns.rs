@@ -0,0 +1,214 @@
+//! Curated mapping from borrow-checker and lifetime errors to the chapters that explain them.
+//!
+//! Compiler messages rarely share vocabulary with the prose that explains them ("E0499" never
+//! appears in the Book), so queries mentioning a known error code or message are expanded with
+//! the topic of the matching chapter and those chapters are moved to the top of the results.
+
+use crate::search::index::SearchResult;
+
+/// A known compiler error and the documents that explain it
+#[derive(Debug)]
+pub struct ErrorPattern {
+    /// Error codes, e.g. `E0499`
+    pub codes: &'static [&'static str],
+    /// Lowercase message fragments identifying the error without its code
+    pub phrases: &'static [&'static str],
+    /// Words describing the underlying concept, added to the search query
+    pub topic: &'static str,
+    /// `(source, path)` of the documents explaining the error
+    pub targets: &'static [(&'static str, &'static str)],
+}
+
+const BORROWING: (&str, &str) = ("rust-book", "ch04-02-references-and-borrowing.md");
+const OWNERSHIP: (&str, &str) = ("rust-book", "ch04-01-what-is-ownership.md");
+const LIFETIMES: (&str, &str) = ("rust-book", "ch10-03-lifetime-syntax.md");
+const MUTABILITY: (&str, &str) = ("rust-book", "ch03-01-variables-and-mutability.md");
+const LIFETIME_ELISION: (&str, &str) = ("rust-reference", "lifetime-elision.md");
+const DESTRUCTORS: (&str, &str) = ("rust-reference", "destructors.md");
+const CLOSURES: (&str, &str) = ("rust-book", "ch13-01-closures.md");
+const THREADS: (&str, &str) = ("rust-book", "ch16-01-threads.md");
+const SEND_SYNC: (&str, &str) = (
+    "rust-book",
+    "ch16-04-extensible-concurrency-sync-and-send.md",
+);
+
+/// Built-in patterns, most specific first
+pub const ERROR_PATTERNS: &[ErrorPattern] = &[
+    ErrorPattern {
+        codes: &["E0499"],
+        phrases: &["as mutable more than once"],
+        topic: "mutable references only one mutable borrow at a time",
+        targets: &[BORROWING],
+    },
+    ErrorPattern {
+        codes: &["E0502"],
+        phrases: &["because it is also borrowed as"],
+        topic: "mutable and immutable references borrowing rules",
+        targets: &[BORROWING],
+    },
+    ErrorPattern {
+        codes: &["E0506"],
+        phrases: &["because it is borrowed"],
+        topic: "references borrowing rules",
+        targets: &[BORROWING],
+    },
+    ErrorPattern {
+        codes: &["E0596"],
+        phrases: &[
+            "as it is not declared as mutable",
+            "cannot borrow as mutable",
+        ],
+        topic: "mutable references mutability",
+        targets: &[BORROWING, MUTABILITY],
+    },
+    ErrorPattern {
+        codes: &["E0597"],
+        phrases: &["does not live long enough", "borrowed value does not live"],
+        topic: "lifetimes scope dangling references",
+        targets: &[LIFETIMES, BORROWING],
+    },
+    ErrorPattern {
+        codes: &["E0716"],
+        phrases: &["temporary value dropped while borrowed"],
+        topic: "temporary scopes drop",
+        targets: &[DESTRUCTORS],
+    },
+    ErrorPattern {
+        codes: &["E0515", "E0106"],
+        phrases: &[
+            "returns a value referencing data owned by the current function",
+            "cannot return reference to local",
+            "missing lifetime specifier",
+        ],
+        topic: "lifetime annotations dangling references",
+        targets: &[LIFETIMES, LIFETIME_ELISION, BORROWING],
+    },
+    ErrorPattern {
+        codes: &[],
+        phrases: &["lifetime may not live long enough"],
+        topic: "lifetime annotations",
+        targets: &[LIFETIMES],
+    },
+    ErrorPattern {
+        codes: &["E0382", "E0505", "E0507"],
+        phrases: &[
+            "use of moved value",
+            "borrow of moved value",
+            "cannot move out of",
+        ],
+        topic: "ownership move semantics",
+        targets: &[OWNERSHIP],
+    },
+    ErrorPattern {
+        codes: &["E0384"],
+        phrases: &["cannot assign twice to immutable variable"],
+        topic: "variables mutability",
+        targets: &[MUTABILITY],
+    },
+    ErrorPattern {
+        codes: &["E0373"],
+        phrases: &["may outlive the current function"],
+        topic: "move closures capture threads",
+        targets: &[THREADS, CLOSURES],
+    },
+    ErrorPattern {
+        codes: &[],
+        phrases: &[
+            "cannot be sent between threads safely",
+            "cannot be shared between threads safely",
+        ],
+        topic: "send sync traits concurrency",
+        targets: &[SEND_SYNC],
+    },
+];
+
+impl ErrorPattern {
+    /// Check whether the text mentions this pattern's error codes or messages
+    fn matches(&self, lower: &str, words: &[&str]) -> bool {
+        self.codes
+            .iter()
+            .any(|code| words.iter().any(|w| w.eq_ignore_ascii_case(code)))
+            || self.phrases.iter().any(|phrase| lower.contains(phrase))
+    }
+
+    fn targets(&self, result: &SearchResult) -> bool {
+        self.targets
+            .iter()
+            .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+    }
+}
+
+/// Patterns mentioned in a query or compiler message
+pub fn matching_patterns(text: &str) -> Vec<&'static ErrorPattern> {
+    let lower = text.to_lowercase();
+    // Drop backticked identifiers so "cannot borrow `v` as mutable" matches "cannot borrow as mutable"
+    let lower = lower.split('`').step_by(2).collect::<Vec<_>>().join(" ");
+    let lower = lower.split_whitespace().collect::<Vec<_>>().join(" ");
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    ERROR_PATTERNS
+        .iter()
+        .filter(|pattern| pattern.matches(&lower, &words))
+        .collect()
+}
+
+/// Append the topics of matched patterns to a query
+pub fn expand_query(query: &str, patterns: &[&ErrorPattern]) -> String {
+    let mut expanded = query.to_string();
+    for pattern in patterns {
+        expanded.push(' ');
+        expanded.push_str(pattern.topic);
+    }
+    expanded
+}
+
+/// Move results targeted by the matched patterns to the front, keeping relative order
+pub fn apply_boost(patterns: &[&ErrorPattern], results: &mut [SearchResult]) {
+    if patterns.is_empty() {
+        return;
+    }
+    results.sort_by_key(|result| !patterns.iter().any(|p| p.targets(result)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_by_code_and_message() {
+        let by_code = matching_patterns("what does E0499 mean");
+        assert_eq!(by_code.len(), 1);
+        assert_eq!(by_code[0].codes, &["E0499"]);
+
+        let by_message = matching_patterns("error: `x` does not live long enough");
+        assert!(by_message.iter().any(|p| p.codes.contains(&"E0597")));
+
+        let by_identifier_message =
+            matching_patterns("cannot borrow `v` as it is not declared as mutable");
+        assert!(
+            by_identifier_message
+                .iter()
+                .any(|p| p.codes.contains(&"E0596"))
+        );
+
+        assert!(matching_patterns("iterators").is_empty());
+    }
+
+    #[test]
+    fn test_boost_moves_targets_first() {
+        let patterns = matching_patterns("E0597");
+        let mut results = vec![
+            SearchResult::for_test("rust-reference", "types.md", 0.5),
+            SearchResult::for_test("rust-book", "ch04-02-references-and-borrowing.md", 0.5),
+            SearchResult::for_test("rust-book", "ch10-03-lifetime-syntax.md", 0.5),
+        ];
+
+        apply_boost(&patterns, &mut results);
+        assert_eq!(results[0].path, "ch04-02-references-and-borrowing.md");
+        assert_eq!(results[1].path, "ch10-03-lifetime-syntax.md");
+        assert_eq!(results[2].path, "types.md");
+    }
+}
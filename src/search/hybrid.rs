@@ -1,19 +1,186 @@
 //! Hybrid search combining keyword and semantic search with RRF score fusion.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
-use crate::error::Result;
-use crate::search::embeddings::embed_text;
-use crate::search::index::{SearchIndex, SearchResult};
+use crate::error::{Error, Result};
+use crate::parsing::chunk_document_key;
+use crate::search::cross_encoder::{RERANK_CANDIDATES, Reranker};
+use crate::search::embeddings::EmbeddingService;
+use crate::search::error_patterns;
+use crate::search::index::{SECTIONS_PER_RESULT, SearchIndex, SearchResult};
+use crate::search::intent::QueryIntent;
+use crate::search::options::SearchOptions;
+use crate::search::paths::PathId;
+use crate::search::pipeline::{CandidateGenerator, FusionContext, Legs, Pipeline, RankingContext};
+use crate::search::query;
+use crate::search::query_embedder::PendingEmbedding;
+use crate::search::result_cache::ResultCache;
+use crate::search::snippet::QueryTerms;
 use crate::search::vector_index::VectorIndex;
 
-/// RRF constant (standard value from the original paper)
-const RRF_K: f32 = 60.0;
+/// BM25 score at which keyword confidence reaches 0.5
+const KEYWORD_CONFIDENCE_MIDPOINT: f32 = 5.0;
+
+/// How hybrid search combined its keyword and semantic legs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FusionDecision {
+    /// Both legs fused with RRF
+    Fused,
+    /// Only keyword results were used
+    KeywordOnly,
+    /// Only semantic results were used
+    SemanticOnly,
+}
+
+impl FusionDecision {
+    /// Name used in logs and tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FusionDecision::Fused => "fused",
+            FusionDecision::KeywordOnly => "keyword_only",
+            FusionDecision::SemanticOnly => "semantic_only",
+        }
+    }
+}
+
+/// Search results together with how they were produced
+#[derive(Debug, Clone)]
+pub struct SearchOutcome {
+    pub results: Vec<SearchResult>,
+    /// The mode that actually ran (may differ from the requested one)
+    pub mode: SearchMode,
+    pub decision: FusionDecision,
+    /// Number of documents matching the keyword query (before truncation); not
+    /// available for semantic-only searches, where every document is a candidate
+    pub total_hits: Option<usize>,
+    /// Keyword leg confidence in `[0, 1)`, set for hybrid searches
+    pub keyword_confidence: Option<f32>,
+    /// Best semantic similarity, set for hybrid searches
+    pub semantic_confidence: Option<f32>,
+    /// The semantic leg of a hybrid search exceeded its latency budget, so only keyword
+    /// results were returned
+    pub partial: bool,
+    /// The cross-encoder reordered the results
+    pub reranked: bool,
+}
+
+impl SearchOutcome {
+    /// Outcome of a search that only ran one leg
+    fn single_leg(
+        results: Vec<SearchResult>,
+        total_hits: Option<usize>,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Self {
+        let decision = match mode {
+            SearchMode::Semantic => FusionDecision::SemanticOnly,
+            _ => FusionDecision::KeywordOnly,
+        };
+
+        let mut outcome = Self {
+            results,
+            mode,
+            decision,
+            total_hits,
+            keyword_confidence: None,
+            semantic_confidence: None,
+            partial: false,
+            reranked: false,
+        };
+        outcome.finalize(options);
+        outcome
+    }
+
+    /// Map raw scores onto `[0, 1]` according to the scale of the leg that produced them,
+    /// order by source boost, keep the best section of each document, apply `min_score` and
+    /// `max_per_source`, then truncate to `limit`.
+    ///
+    /// Results must be in ranking order; when a source hits its cap, the next-best results
+    /// from other sources move up into the freed slots.
+    fn finalize(&mut self, options: &SearchOptions) {
+        for result in &mut self.results {
+            result.score = match self.decision {
+                FusionDecision::Fused => result.score.min(1.0),
+                FusionDecision::KeywordOnly => {
+                    result.score / (result.score + KEYWORD_CONFIDENCE_MIDPOINT)
+                }
+                FusionDecision::SemanticOnly => result.score.clamp(0.0, 1.0),
+            };
+        }
+        if !options.source_boosts.is_empty() {
+            options.sort_by_boosted_score(&mut self.results);
+        }
+
+        // Semantic hits and fused results can hold several sections of one document
+        let mut seen = HashSet::new();
+        self.results
+            .retain(|r| seen.insert((r.source.clone(), r.path.clone())));
+
+        if let Some(min_score) = options.min_score {
+            self.results.retain(|r| r.score >= min_score);
+        }
+
+        if let Some(cap) = options.max_per_source {
+            let mut per_source: HashMap<String, usize> = HashMap::new();
+            self.results.retain(|r| {
+                let count = per_source.entry(r.source.clone()).or_insert(0);
+                *count += 1;
+                *count <= cap
+            });
+        }
+
+        self.results.truncate(options.limit);
+    }
+}
+
+/// Map the best BM25 score onto `[0, 1)` so it can be compared against a threshold
+fn keyword_confidence(results: &[SearchResult]) -> f32 {
+    results
+        .first()
+        .map(|r| r.score / (r.score + KEYWORD_CONFIDENCE_MIDPOINT))
+        .unwrap_or(0.0)
+}
+
+/// Error of a query that needs the embedding model before it is loaded
+fn model_not_loaded() -> Error {
+    Error::Other("Embedding model not loaded".to_string())
+}
+
+/// Another index, such as the workspace's, searched by keyword alongside the documentation
+impl CandidateGenerator for SearchIndex {
+    fn name(&self) -> &str {
+        "keyword_index"
+    }
+
+    fn generate(&self, query: &str, options: &SearchOptions) -> Result<Vec<SearchResult>> {
+        let (results, _) = self.search_counted(query, &options.candidates())?;
+        Ok(SearchOutcome::single_leg(results, None, SearchMode::Keyword, options).results)
+    }
+}
+
+/// Receives the keyword results of a hybrid search before its semantic leg is done
+pub type OnPartial<'a> = &'a dyn Fn(&[SearchResult]);
 
 /// Hybrid search engine combining keyword and semantic search
 pub struct HybridSearch<'a> {
     keyword_index: &'a SearchIndex,
     vector_index: &'a VectorIndex,
+    /// Model embedding queries that have no precomputed embedding; unset until it is loaded
+    embeddings: Option<&'a EmbeddingService>,
+    /// Cross-encoder of searches with `SearchOptions::rerank`; they keep the fused order
+    /// without it
+    reranker: Option<&'a Reranker>,
+    /// Session-local index of the client's workspace documentation, searched by keyword
+    workspace_index: Option<&'a SearchIndex>,
+    /// Longest a hybrid search waits for its semantic leg; unbounded if unset
+    semantic_budget: Option<Duration>,
+    /// Receives the keyword results of a hybrid search while the semantic leg still runs
+    on_partial: Option<OnPartial<'a>>,
+    /// Fusion, filtering, and reranking stages
+    pipeline: &'a Pipeline,
+    /// Outcomes of recent searches, served again for repeated ones
+    cache: Option<&'a ResultCache>,
 }
 
 impl<'a> HybridSearch<'a> {
@@ -22,12 +189,70 @@ impl<'a> HybridSearch<'a> {
         Self {
             keyword_index,
             vector_index,
+            embeddings: None,
+            reranker: None,
+            workspace_index: None,
+            semantic_budget: None,
+            on_partial: None,
+            pipeline: Pipeline::built_in(),
+            cache: None,
         }
     }
 
+    /// Embed queries with `embeddings`; without a model only precomputed query embeddings
+    /// answer the semantic leg
+    pub fn with_embeddings(mut self, embeddings: Option<&'a EmbeddingService>) -> Self {
+        self.embeddings = embeddings;
+        self
+    }
+
+    /// Rerank searches asking for it with `reranker`
+    pub fn with_reranker(mut self, reranker: Option<&'a Reranker>) -> Self {
+        self.reranker = reranker;
+        self
+    }
+
+    /// Serve repeated searches from `cache`, which must be cleared when the indices change
+    pub fn with_cache(mut self, cache: Option<&'a ResultCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// The result cache, unless searches include workspace documentation: a cache is shared
+    /// by the sessions of a server, and only the session's own workspace is searched
+    fn cache(&self) -> Option<&'a ResultCache> {
+        self.cache.filter(|_| self.workspace_index.is_none())
+    }
+
+    /// Rank with the stages of `pipeline` instead of the built-in ones
+    pub fn with_pipeline(mut self, pipeline: &'a Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Return keyword results alone (marked partial) when the semantic leg of a hybrid search
+    /// takes longer than `budget`
+    pub fn with_semantic_budget(mut self, budget: Option<Duration>) -> Self {
+        self.semantic_budget = budget;
+        self
+    }
+
+    /// Pass the keyword results of hybrid searches to `on_partial` (finalized like a keyword
+    /// search) as soon as they are ready, before the semantic leg is waited for
+    pub fn with_partial_results(mut self, on_partial: Option<OnPartial<'a>>) -> Self {
+        self.on_partial = on_partial;
+        self
+    }
+
+    /// Also search the client's workspace documentation, when it has been indexed
+    pub fn with_workspace(mut self, workspace_index: Option<&'a SearchIndex>) -> Self {
+        self.workspace_index = workspace_index;
+        self
+    }
+
     /// Perform hybrid search combining keyword and semantic results
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        self.search_with_sources(query, limit, None)
+        self.search_with_options(query, &SearchOptions::new(limit))
     }
 
     /// Perform hybrid search with optional source filtering
@@ -37,45 +262,332 @@ impl<'a> HybridSearch<'a> {
         limit: usize,
         sources: Option<&[&str]>,
     ) -> Result<Vec<SearchResult>> {
+        self.search_with_options(query, &SearchOptions::new(limit).with_sources(sources))
+    }
+
+    /// Perform hybrid search using the given options
+    pub fn search_with_options(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(self.search_detailed(query, options)?.results)
+    }
+
+    /// Perform hybrid search and report how the two legs were combined.
+    ///
+    /// The pipeline's fuser combines the legs; the built-in one skips fusion when only one leg
+    /// is confident. The query is embedded while the keyword leg runs; if that takes longer
+    /// than the semantic budget, the keyword results are returned as a partial outcome.
+    pub fn search_detailed(&self, query: &str, options: &SearchOptions) -> Result<SearchOutcome> {
+        let started = Instant::now();
+
+        // `-word` exclusions apply to both legs; only the rest of the query is embedded
+        let (text, excluded) = query::split_exclusions(query);
+        let options = &options.clone().with_exclude_terms(&excluded);
+
         // Get more results from each method to ensure good coverage after fusion
-        let expanded_limit = limit * 3;
+        let expanded = SearchOptions {
+            limit: options.limit * 3,
+            ..options.clone()
+        };
 
-        // Run keyword search (with source filtering)
-        let keyword_results =
-            self.keyword_index
-                .search_with_sources(query, expanded_limit, sources)?;
+        // Start the semantic leg's embedding, then run keyword search (with source filtering)
+        let pending = self.start_query_embedding(query::normalize(&text)?);
+        let (mut keyword_results, total_hits) =
+            self.keyword_index.search_counted(query, &expanded)?;
+        self.pipeline.filter(&mut keyword_results, options);
+        let keyword_confidence = keyword_confidence(&keyword_results);
+        if let Some(on_partial) = self.on_partial {
+            let preview = SearchOutcome::single_leg(
+                keyword_results.clone(),
+                Some(total_hits),
+                SearchMode::Keyword,
+                options,
+            );
+            on_partial(&preview.results);
+        }
+
+        let deadline = self.semantic_budget.map(|budget| started + budget);
+        let Some(query_embedding) = pending.wait(deadline) else {
+            tracing::debug!(
+                "Query embedding not ready within the {:?} budget, returning keyword results only",
+                self.semantic_budget.unwrap_or_default()
+            );
+            let mut outcome = SearchOutcome {
+                results: keyword_results,
+                mode: SearchMode::Hybrid,
+                decision: FusionDecision::KeywordOnly,
+                total_hits: Some(total_hits),
+                keyword_confidence: Some(keyword_confidence),
+                semantic_confidence: None,
+                partial: true,
+                reranked: false,
+            };
+            outcome.finalize(options);
+            return Ok(outcome);
+        };
 
         // Run semantic search
-        let query_embedding = embed_text(query)?;
-        let mut semantic_results = self.vector_index.search(&query_embedding, expanded_limit);
+        let query_embedding = query_embedding?;
+        let mut semantic_results = self
+            .vector_index
+            .search_ids(&query_embedding, expanded.limit * SECTIONS_PER_RESULT);
+        self.drop_excluded(&mut semantic_results, options);
 
         // Filter semantic results by source if specified
-        if let Some(sources) = sources {
-            semantic_results.retain(|(path, _)| {
+        if options.sources.is_some() {
+            semantic_results.retain(|&(id, _)| {
+                let key = self.vector_index.path(id);
                 // First check if it's in keyword results
                 if keyword_results
                     .iter()
-                    .any(|r| r.path == *path && sources.contains(&r.source.as_str()))
+                    .any(|r| r.chunk_key() == key && options.allows_source(&r.source))
                 {
                     return true;
                 }
 
-                // Otherwise, look up the source by querying for the exact path
-                // This handles cases where semantic search finds documents that keyword search missed
-                if let Ok(path_results) = self.keyword_index.search(path, 1)
-                    && let Some(result) = path_results.first() {
-                        return result.path == *path && sources.contains(&result.source.as_str());
-                    }
-
-                false
+                // Otherwise, look up the chunk's source; this handles sections that keyword
+                // search missed
+                if let Some(info) = self.vector_index.info(id) {
+                    return options.allows_source(&info.source);
+                }
+                matches!(
+                    self.keyword_index.get_chunk(key),
+                    Ok(Some(chunk)) if options.allows_source(&chunk.source)
+                )
             });
         }
 
-        // Fuse results using RRF
-        let fused = self.rrf_fusion(&keyword_results, &semantic_results);
+        let semantic_confidence = semantic_results
+            .iter()
+            .map(|(_, similarity)| *similarity)
+            .fold(0.0f32, f32::max);
+
+        let legs = Legs {
+            keyword: keyword_results,
+            semantic: semantic_results,
+            keyword_confidence,
+            semantic_confidence,
+        };
+        let context = FusionContext::new(self.keyword_index, self.vector_index, options);
+        let (decision, mut results) = self.pipeline.fuser().fuse(legs, &context);
+        if decision != FusionDecision::KeywordOnly {
+            // Keyword results were filtered already; semantic hits were not
+            self.pipeline.filter(&mut results, options);
+        }
+
+        tracing::debug!(
+            "Hybrid decision {} by {} (keyword confidence {:.2}, semantic confidence {:.2})",
+            decision.as_str(),
+            self.pipeline.fuser().name(),
+            keyword_confidence,
+            semantic_confidence
+        );
+
+        let mut outcome = SearchOutcome {
+            results,
+            mode: SearchMode::Hybrid,
+            decision,
+            total_hits: Some(total_hits),
+            keyword_confidence: Some(keyword_confidence),
+            semantic_confidence: Some(semantic_confidence),
+            partial: false,
+            reranked: false,
+        };
+        outcome.finalize(options);
+
+        Ok(outcome)
+    }
+
+    /// Search using the requested mode, falling back to keyword search when no vectors exist.
+    ///
+    /// Candidates from the workspace documentation (keyword matches only) and the pipeline's
+    /// generators are merged in by normalized score. Snippets are highlighted last, when the
+    /// options ask for it.
+    pub fn search_with_mode(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        if let Some(outcome) = self
+            .cache()
+            .and_then(|cache| cache.get(query, mode, options))
+        {
+            tracing::debug!("Serving {:?} from the result cache", query);
+            return Ok(outcome);
+        }
+        let generation = self.cache().map(ResultCache::generation);
+        let outcome = self.search_uncached(query, mode, options)?;
+        if let (Some(cache), Some(generation)) = (self.cache(), generation) {
+            cache.insert(generation, query, options, &outcome);
+        }
+        Ok(outcome)
+    }
+
+    /// `search_with_mode` without the cache
+    fn search_uncached(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        let mut outcome = self.search_sources(query, mode, options)?;
+
+        let workspace = self
+            .workspace_index
+            .map(|index| index as &dyn CandidateGenerator);
+        for generator in workspace.into_iter().chain(self.pipeline.generators()) {
+            let mut results = generator.generate(query, options)?;
+            self.pipeline.filter(&mut results, options);
+            if !results.is_empty() {
+                tracing::debug!("{} candidates from {}", results.len(), generator.name());
+                outcome.results.extend(results);
+                options.sort_by_boosted_score(&mut outcome.results);
+                outcome.results.truncate(options.limit);
+            }
+        }
+
+        if let Some(highlight) = &options.highlight {
+            let mut terms = QueryTerms::new(query);
+            for result in &mut outcome.results {
+                result.snippet = highlight.apply(&result.snippet, &mut terms);
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Search the documentation sources in the requested mode
+    fn search_sources(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Result<SearchOutcome> {
+        if self.vector_index.is_empty() {
+            if !matches!(mode, SearchMode::Keyword) {
+                tracing::debug!("Vector index empty, falling back to keyword search");
+            }
+            let (results, total) = self
+                .keyword_index
+                .search_counted(query, &options.candidates())?;
+            return Ok(self.single_leg(results, Some(total), SearchMode::Keyword, options));
+        }
+
+        match mode {
+            SearchMode::Hybrid => self.search_detailed(query, options),
+            SearchMode::Keyword => {
+                let (results, total) = self
+                    .keyword_index
+                    .search_counted(query, &options.candidates())?;
+                Ok(self.single_leg(results, Some(total), SearchMode::Keyword, options))
+            }
+            SearchMode::Semantic => {
+                let results = self.semantic_search_with_options(query, &options.candidates())?;
+                Ok(self.single_leg(results, None, SearchMode::Semantic, options))
+            }
+        }
+    }
+
+    /// Outcome of a single-leg search, after the pipeline's filters
+    fn single_leg(
+        &self,
+        mut results: Vec<SearchResult>,
+        total_hits: Option<usize>,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> SearchOutcome {
+        self.pipeline.filter(&mut results, options);
+        SearchOutcome::single_leg(results, total_hits, mode, options)
+    }
+
+    /// Classify the query intent, search all sources with the snippet length suited to that
+    /// intent, then run the pipeline's rerankers, and the cross-encoder when `options.rerank`
+    /// is set
+    pub fn search_routed(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Result<(QueryIntent, SearchOutcome)> {
+        if let Some(routed) = self
+            .cache()
+            .and_then(|cache| cache.get_routed(query, mode, options))
+        {
+            tracing::debug!("Serving {:?} from the result cache", query);
+            return Ok(routed);
+        }
+        let generation = self.cache().map(ResultCache::generation);
+        let (intent, outcome) = self.search_routed_uncached(query, mode, options)?;
+        if let (Some(cache), Some(generation)) = (self.cache(), generation) {
+            cache.insert_routed(generation, query, options, intent, &outcome);
+        }
+        Ok((intent, outcome))
+    }
+
+    /// `search_routed` without the cache
+    fn search_routed_uncached(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Result<(QueryIntent, SearchOutcome)> {
+        let intent = QueryIntent::classify(query);
+        tracing::debug!("Query {:?} classified as {}", query, intent.as_str());
 
-        // Return top results
-        Ok(fused.into_iter().take(limit).collect())
+        // Fetch extra candidates so boosted sources can move up into the top results, and
+        // enough for the cross-encoder to choose from
+        let candidates = if options.rerank {
+            (options.limit * 2).max(RERANK_CANDIDATES)
+        } else {
+            options.limit * 2
+        };
+        let routed = SearchOptions {
+            limit: candidates,
+            snippet_len: intent.snippet_len(),
+            ..options.clone()
+        };
+
+        // Known compiler errors are searched by the concept they stem from
+        let patterns = error_patterns::matching_patterns(query);
+        let expanded = error_patterns::expand_query(query, &patterns);
+        let mut outcome = self.search_uncached(&expanded, mode, &routed)?;
+
+        let context = RankingContext {
+            query,
+            intent,
+            options: &routed,
+            keyword_index: self.keyword_index,
+        };
+        self.pipeline.rerank(&context, &mut outcome.results);
+        if let Some(reranker) = self.reranker.filter(|_| options.rerank) {
+            let passages = self.rerank_passages(&outcome.results);
+            match reranker.rerank(query, &mut outcome.results, &passages) {
+                Ok(()) => outcome.reranked = true,
+                Err(e) => tracing::warn!("Reranking failed, keeping the fused order: {}", e),
+            }
+        }
+        outcome.results.truncate(options.limit);
+
+        Ok((intent, outcome))
+    }
+
+    /// Text the cross-encoder reads for each result: the title and the matched section, or
+    /// the snippet for results that aren't documentation chunks
+    fn rerank_passages(&self, results: &[SearchResult]) -> Vec<String> {
+        results
+            .iter()
+            .take(RERANK_CANDIDATES)
+            .map(|result| {
+                let text = match self.keyword_index.get_chunk(&result.chunk_key()) {
+                    Ok(Some(chunk)) => chunk.content,
+                    _ => result.snippet.clone(),
+                };
+                format!("{}\n{}", result.title, text)
+            })
+            .collect()
     }
 
     /// Perform keyword-only search
@@ -84,111 +596,82 @@ impl<'a> HybridSearch<'a> {
     }
 
     /// Perform keyword-only search with source filtering
-    #[allow(dead_code)]
     pub fn keyword_search_with_sources(
         &self,
         query: &str,
         limit: usize,
         sources: Option<&[&str]>,
     ) -> Result<Vec<SearchResult>> {
-        self.keyword_index.search_with_sources(query, limit, sources)
+        self.keyword_index
+            .search_with_sources(query, limit, sources)
     }
 
     /// Perform semantic-only search
     pub fn semantic_search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let query_embedding = embed_text(query)?;
-        let results = self.vector_index.search(&query_embedding, limit);
-
-        // Convert to SearchResult format
-        // Note: We only have path and score from vector search, so we need to look up
-        // the full document info from the keyword index
-        let mut search_results = Vec::new();
-        for (path, score) in results {
-            // Try to find matching document in keyword search for full info
-            if let Ok(keyword_results) = self.keyword_index.search(&path, 1)
-                && let Some(result) = keyword_results.into_iter().next() {
-                    search_results.push(SearchResult {
-                        score,
-                        ..result
-                    });
-                    continue;
-                }
-            // Fallback: create minimal result
-            search_results.push(SearchResult {
-                title: path.clone(),
-                snippet: String::new(),
-                path,
-                source: String::new(),
-                score,
-            });
-        }
-
-        Ok(search_results)
+        self.semantic_search_with_options(query, &SearchOptions::new(limit))
     }
 
-    /// Reciprocal Rank Fusion to combine results from multiple sources
-    fn rrf_fusion(
+    /// Perform semantic-only search using the given options
+    pub fn semantic_search_with_options(
         &self,
-        keyword_results: &[SearchResult],
-        semantic_results: &[(String, f32)],
-    ) -> Vec<SearchResult> {
-        // Map from document path to (RRF score, SearchResult)
-        let mut scores: HashMap<String, (f32, Option<SearchResult>)> = HashMap::new();
-
-        // Add keyword results with RRF scores
-        for (rank, result) in keyword_results.iter().enumerate() {
-            let rrf_score = 1.0 / (RRF_K + rank as f32 + 1.0);
-            scores
-                .entry(result.path.clone())
-                .and_modify(|(s, _)| *s += rrf_score)
-                .or_insert((rrf_score, Some(result.clone())));
-        }
-
-        // Add semantic results with RRF scores
-        for (rank, (path, _similarity)) in semantic_results.iter().enumerate() {
-            let rrf_score = 1.0 / (RRF_K + rank as f32 + 1.0);
-            scores
-                .entry(path.clone())
-                .and_modify(|(s, _)| *s += rrf_score)
-                .or_insert((rrf_score, None));
-        }
-
-        // Build final results
-        let mut results: Vec<SearchResult> = scores
-            .into_iter()
-            .map(|(path, (rrf_score, maybe_result))| {
-                if let Some(mut result) = maybe_result {
-                    result.score = rrf_score;
-                    result
-                } else {
-                    // We have a semantic-only result, try to get full info
-                    if let Ok(keyword_results) = self.keyword_index.search(&path, 1)
-                        && let Some(mut result) = keyword_results.into_iter().next()
-                    {
-                        result.score = rrf_score;
-                        return result;
-                    }
-                    // Fallback
-                    SearchResult {
-                        title: path.clone(),
-                        snippet: String::new(),
-                        path,
-                        source: String::new(),
-                        score: rrf_score,
-                    }
-                }
-            })
-            .collect();
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let (text, excluded) = query::split_exclusions(query);
+        let options = &options.clone().with_exclude_terms(&excluded);
+        let query_embedding = self.embed_query(query::normalize(&text)?)?;
+        let mut results = self
+            .vector_index
+            .search_ids(&query_embedding, options.limit * SECTIONS_PER_RESULT);
+        self.drop_excluded(&mut results, options);
 
-        // Sort by RRF score descending
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        let context = FusionContext::new(self.keyword_index, self.vector_index, options);
+        Ok(context.resolve_semantic(results))
+    }
 
-        results
+    /// Embedding of a query: precomputed for common concepts, otherwise computed by the model
+    fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
+        match self.vector_index.query_embedding(query) {
+            Some(embedding) => Ok(embedding.to_vec()),
+            None => self
+                .embeddings
+                .ok_or_else(model_not_loaded)?
+                .embed_text(query),
+        }
+    }
+
+    /// Start embedding a query: a precomputed embedding is ready at once, others are queued
+    /// for the query embedding threads
+    fn start_query_embedding(&self, query: &str) -> PendingEmbedding {
+        match self.vector_index.query_embedding(query) {
+            Some(embedding) => PendingEmbedding::ready(embedding.to_vec()),
+            None => match self.embeddings {
+                Some(embeddings) => embeddings.queue_query(query.to_string()),
+                None => PendingEmbedding::failed(model_not_loaded()),
+            },
+        }
+    }
+
+    /// Drop semantic hits on documents that mention an excluded word. Embeddings can't express
+    /// "not", so this is a filter on the keyword index's text.
+    fn drop_excluded(&self, results: &mut Vec<(PathId, f32)>, options: &SearchOptions) {
+        if options.exclude_terms.is_empty() {
+            return;
+        }
+        match self
+            .keyword_index
+            .documents_mentioning(&options.exclude_terms)
+        {
+            Ok(documents) => results.retain(|&(id, _)| {
+                !documents.contains(chunk_document_key(self.vector_index.path(id)))
+            }),
+            Err(e) => tracing::warn!("Could not apply excluded terms to semantic results: {}", e),
+        }
     }
 }
 
 /// Search mode for the search tool
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum SearchMode {
     /// Hybrid search (keyword + semantic with RRF fusion)
     #[default]
@@ -199,9 +682,9 @@ pub enum SearchMode {
     Semantic,
 }
 
-impl SearchMode {
-    /// Parse search mode from string
-    pub fn from_str(s: &str) -> Self {
+impl From<&str> for SearchMode {
+    /// Parse search mode from string; unknown modes are hybrid
+    fn from(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "keyword" | "bm25" => SearchMode::Keyword,
             "semantic" | "embedding" | "vector" => SearchMode::Semantic,
@@ -209,3 +692,292 @@ impl SearchMode {
         }
     }
 }
+
+impl SearchMode {
+    /// Name used in tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchMode::Hybrid => "hybrid",
+            SearchMode::Keyword => "keyword",
+            SearchMode::Semantic => "semantic",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::Document;
+
+    #[test]
+    fn test_normalized_scores_and_min_score() {
+        let results = vec![
+            SearchResult::for_test("rust-book", "a.md", 15.0),
+            SearchResult::for_test("rust-book", "b.md", 1.0),
+        ];
+        let options = SearchOptions::new(5).with_min_score(Some(0.5));
+
+        let outcome = SearchOutcome::single_leg(results, Some(2), SearchMode::Keyword, &options);
+
+        assert_eq!(outcome.results.len(), 1);
+        assert_eq!(outcome.results[0].path, "a.md");
+        assert!((outcome.results[0].score - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_common_concepts_skip_inference() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let mut vector_index = VectorIndex::new();
+        vector_index
+            .add("ch04-01-what-is-ownership.md".to_string(), vec![1.0, 0.0])
+            .unwrap();
+        vector_index
+            .set_query_embedding("ownership", vec![1.0, 0.0])
+            .unwrap();
+        let hybrid = HybridSearch::new(&keyword_index, &vector_index);
+
+        // No model is loaded in tests, so only the precomputed embedding can answer this
+        let results = hybrid.semantic_search("Ownership?", 1).unwrap();
+        assert_eq!(results[0].path, "ch04-01-what-is-ownership.md");
+        assert!(hybrid.semantic_search("lifetimes", 1).is_err());
+    }
+
+    #[test]
+    fn test_excluded_terms_filter_semantic_hits() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let doc = |path: &str, content: &str| Document {
+            title: path.to_string(),
+            content: content.to_string(),
+            path: path.to_string(),
+            source: "rust-book".to_string(),
+            ..Default::default()
+        };
+        keyword_index
+            .index_documents(&[
+                doc("ownership.md", "Each value has an owner."),
+                doc(
+                    "async-ownership.md",
+                    "Async blocks take ownership of captures.",
+                ),
+            ])
+            .unwrap();
+        let mut vector_index = VectorIndex::new();
+        vector_index
+            .add("rust-book/ownership.md".to_string(), vec![1.0, 0.0])
+            .unwrap();
+        vector_index
+            .add("rust-book/async-ownership.md".to_string(), vec![1.0, 0.1])
+            .unwrap();
+        vector_index
+            .set_query_embedding("ownership", vec![1.0, 0.05])
+            .unwrap();
+        let hybrid = HybridSearch::new(&keyword_index, &vector_index);
+
+        assert_eq!(hybrid.semantic_search("ownership", 2).unwrap().len(), 2);
+        // Only "ownership" is embedded (a precomputed concept), and the async page is dropped
+        let results = hybrid.semantic_search("ownership -async", 2).unwrap();
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["ownership.md"]);
+    }
+
+    #[test]
+    fn test_partial_results_report_keyword_leg() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        keyword_index
+            .index_documents(&[Document {
+                title: "Ownership".to_string(),
+                content: "Each value has an owner.".to_string(),
+                path: "ownership.md".to_string(),
+                source: "rust-book".to_string(),
+                ..Default::default()
+            }])
+            .unwrap();
+        let mut vector_index = VectorIndex::new();
+        vector_index
+            .add("rust-book/ownership.md".to_string(), vec![1.0, 0.0])
+            .unwrap();
+        vector_index
+            .set_query_embedding("ownership", vec![1.0, 0.0])
+            .unwrap();
+
+        let partial = std::sync::Mutex::new(Vec::new());
+        let on_partial = |results: &[SearchResult]| {
+            partial.lock().unwrap().push(results.to_vec());
+        };
+        let hybrid = HybridSearch::new(&keyword_index, &vector_index)
+            .with_partial_results(Some(&on_partial));
+        let outcome = hybrid
+            .search_with_mode("ownership", SearchMode::Hybrid, &SearchOptions::new(5))
+            .unwrap();
+        let partial = partial.into_inner().unwrap();
+        assert_eq!(partial.len(), 1);
+        assert_eq!(partial[0][0].path, "ownership.md");
+        assert!(partial[0][0].score < 1.0);
+        assert_eq!(outcome.results[0].path, "ownership.md");
+
+        // Keyword searches have nothing to wait for
+        let calls = std::cell::Cell::new(0);
+        let count = |_: &[SearchResult]| calls.set(calls.get() + 1);
+        HybridSearch::new(&keyword_index, &vector_index)
+            .with_partial_results(Some(&count))
+            .search_with_mode("ownership", SearchMode::Keyword, &SearchOptions::new(5))
+            .unwrap();
+        assert_eq!(calls.get(), 0);
+    }
+
+    #[test]
+    fn test_rerank_without_model_keeps_fused_order() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let doc = |path: &str, content: &str| Document {
+            title: path.to_string(),
+            content: content.to_string(),
+            path: path.to_string(),
+            source: "rust-book".to_string(),
+            ..Default::default()
+        };
+        keyword_index
+            .index_documents(&[
+                doc("lifetimes.md", "Lifetimes of references."),
+                doc("elision.md", "Lifetime elision rules for references."),
+            ])
+            .unwrap();
+        let vector_index = VectorIndex::new();
+        let hybrid = HybridSearch::new(&keyword_index, &vector_index);
+
+        let options = SearchOptions::new(1);
+        let (_, plain) = hybrid
+            .search_routed("references", SearchMode::Keyword, &options)
+            .unwrap();
+        // No cross-encoder is loaded in tests, so reranking falls back to the fused order
+        let (_, outcome) = hybrid
+            .search_routed(
+                "references",
+                SearchMode::Keyword,
+                &options.with_rerank(true),
+            )
+            .unwrap();
+        assert!(!outcome.reranked);
+        assert_eq!(outcome.results.len(), 1);
+        assert_eq!(outcome.results[0].path, plain.results[0].path);
+
+        // The cross-encoder reads the title and the whole matched section
+        let passages = hybrid.rerank_passages(&outcome.results);
+        let expected = keyword_index
+            .get_document("rust-book", &outcome.results[0].path)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            passages,
+            vec![format!("{}\n{}", expected.title, expected.content)]
+        );
+    }
+
+    #[test]
+    fn test_snippets_are_highlighted_on_request() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        keyword_index
+            .index_documents(&[Document {
+                title: "References".to_string(),
+                content: "References borrow a value.".to_string(),
+                path: "references.md".to_string(),
+                source: "rust-book".to_string(),
+                ..Default::default()
+            }])
+            .unwrap();
+        let vector_index = VectorIndex::new();
+        let hybrid = HybridSearch::new(&keyword_index, &vector_index);
+
+        let options = SearchOptions::new(1).with_highlight(Some(crate::search::Highlight::html()));
+        let outcome = hybrid
+            .search_with_mode("borrow a value", SearchMode::Keyword, &options)
+            .unwrap();
+        assert_eq!(
+            outcome.results[0].snippet,
+            "References <b>borrow</b> a <b>value</b>."
+        );
+        let outcome = hybrid
+            .search_with_mode("borrow", SearchMode::Keyword, &SearchOptions::new(1))
+            .unwrap();
+        assert_eq!(outcome.results[0].snippet, "References borrow a value.");
+    }
+
+    #[test]
+    fn test_hybrid_search_falls_back_to_the_confident_leg() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let doc = |path: &str, content: &str| Document {
+            title: path.to_string(),
+            content: content.to_string(),
+            path: path.to_string(),
+            source: "rust-book".to_string(),
+            ..Default::default()
+        };
+        // Unrelated pages make "ownership" a rare term, so its keyword match is confident
+        let mut docs: Vec<Document> = (0..20)
+            .map(|i| doc(&format!("filler-{}.md", i), "Cargo builds crates."))
+            .collect();
+        docs.push(doc("ownership.md", "Ownership: each value has an owner."));
+        docs.push(doc(
+            "references.md",
+            "A reference uses a value without moving it.",
+        ));
+        keyword_index.index_documents(&docs).unwrap();
+        let mut vector_index = VectorIndex::new();
+        vector_index
+            .add("rust-book/ownership.md".to_string(), vec![0.0, 1.0, 0.2])
+            .unwrap();
+        vector_index
+            .add("rust-book/references.md".to_string(), vec![1.0, 0.0, 0.0])
+            .unwrap();
+        vector_index
+            .set_query_embedding("ownership", vec![0.0, 0.0, 1.0])
+            .unwrap();
+        vector_index
+            .set_query_embedding("borrowing", vec![1.0, 0.0, 0.0])
+            .unwrap();
+        let hybrid = HybridSearch::new(&keyword_index, &vector_index);
+        let options = SearchOptions::new(5);
+
+        // A strong keyword match and only weak semantic neighbors: the semantic leg is dropped
+        let outcome = hybrid.search_detailed("ownership", &options).unwrap();
+        assert_eq!(outcome.decision, FusionDecision::KeywordOnly);
+        assert_eq!(outcome.mode, SearchMode::Hybrid);
+        assert!(outcome.keyword_confidence.unwrap() >= 0.2);
+        assert_eq!(outcome.results[0].path, "ownership.md");
+
+        // No keyword match, and a close semantic neighbor: the keyword leg is dropped
+        let outcome = hybrid.search_detailed("borrowing", &options).unwrap();
+        assert_eq!(outcome.decision, FusionDecision::SemanticOnly);
+        assert_eq!(outcome.mode, SearchMode::Hybrid);
+        assert_eq!(outcome.keyword_confidence, Some(0.0));
+        assert_eq!(outcome.results[0].path, "references.md");
+    }
+
+    #[test]
+    fn test_max_per_source_backfills_from_other_sources() {
+        let mut results = vec![
+            SearchResult::for_test("rust-book", "book-1.md", 9.0),
+            SearchResult::for_test("rust-book", "book-2.md", 8.0),
+            SearchResult::for_test("rust-book", "book-3.md", 7.0),
+        ];
+        results.push(SearchResult::for_test("rustonomicon", "nomicon-1.md", 1.0));
+
+        let options = SearchOptions::new(3).with_max_per_source(Some(2));
+        let outcome = SearchOutcome::single_leg(results, Some(4), SearchMode::Keyword, &options);
+
+        let paths: Vec<&str> = outcome.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["book-1.md", "book-2.md", "nomicon-1.md"]);
+    }
+
+    #[test]
+    fn test_max_per_source_zero_is_no_cap() {
+        let results = vec![
+            SearchResult::for_test("rust-book", "book-1.md", 9.0),
+            SearchResult::for_test("rust-book", "book-2.md", 8.0),
+        ];
+
+        let options = SearchOptions::new(3).with_max_per_source(Some(0));
+        let outcome = SearchOutcome::single_leg(results, Some(2), SearchMode::Keyword, &options);
+
+        assert_eq!(outcome.results.len(), 2);
+    }
+}
@@ -1,12 +1,23 @@
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 
-use tantivy::collector::TopDocs;
+use tantivy::collector::{Count, TopDocs};
 use tantivy::query::QueryParser;
-use tantivy::schema::{Schema, Value, STORED, STRING, TEXT};
-use tantivy::{doc, Index, IndexWriter, TantivyDocument};
+use tantivy::schema::{
+    IndexRecordOption, STORED, STRING, Schema, TextFieldIndexing, TextOptions, Value,
+};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::store::{Compressor, ZstdCompressor};
+use tantivy::tokenizer::{LowerCaser, RawTokenizer, TextAnalyzer, TokenStream};
+use tantivy::{Index, IndexSettings, IndexWriter, TantivyDocument, doc};
 
 use crate::error::Result;
-use crate::parsing::Document;
+use crate::parsing::{Document, Section, chunk_document, chunk_key, document_key, join_chunks};
+use crate::search::options::SearchOptions;
+use crate::search::query;
+use crate::search::snippet::{QueryTerms, markdown_snippet, plain_snippet};
+use crate::search::synonyms::Synonyms;
+use crate::search::tokenizer::{TOKENIZER_NAME, identifier_analyzer};
 
 /// Search result returned to users
 #[derive(Debug, Clone)]
@@ -15,13 +26,113 @@ pub struct SearchResult {
     pub snippet: String,
     pub path: String,
     pub source: String,
+    /// API Guidelines checklist IDs covered by the document (e.g. `C-CASE`)
+    pub checklist_ids: Vec<String>,
     pub score: f32,
+    /// Why the document matched the query
+    pub explanation: MatchExplanation,
+    /// The section that matched; `None` when the text before the first section did, or for
+    /// results that aren't documentation chunks
+    pub section: Option<Section>,
 }
 
+impl SearchResult {
+    /// Key of the matched chunk in the vector index
+    pub fn chunk_key(&self) -> String {
+        chunk_key(&self.source, &self.path, self.section.as_ref())
+    }
+
+    /// Ranking order: score descending, with ties broken by source then path, so results with
+    /// equal scores always come back in the same order
+    pub fn rank_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .score
+            .total_cmp(&self.score)
+            .then_with(|| self.source.cmp(&other.source))
+            .then_with(|| self.path.cmp(&other.path))
+    }
+
+    /// A result with only a source, a path (also its title), and a score, for tests
+    #[cfg(test)]
+    pub(crate) fn for_test(source: &str, path: &str, score: f32) -> Self {
+        Self {
+            title: path.to_string(),
+            snippet: String::new(),
+            path: path.to_string(),
+            source: source.to_string(),
+            checklist_ids: Vec::new(),
+            score,
+            explanation: MatchExplanation::default(),
+            section: None,
+        }
+    }
+}
+
+/// Why a search result matched the query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchExplanation {
+    /// Query terms found in each searched field, e.g. `("title", ["borrow"])`
+    pub fields: Vec<(&'static str, Vec<String>)>,
+    /// Cosine similarity to the query, when semantic search found the document
+    pub similarity: Option<f32>,
+}
+
+impl MatchExplanation {
+    /// Explanation for a document found by semantic search alone
+    pub fn semantic(similarity: f32) -> Self {
+        Self {
+            fields: Vec::new(),
+            similarity: Some(similarity),
+        }
+    }
+
+    /// One-line summary, e.g. `matched "borrow" in title; semantic similarity 0.62`
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(field, terms)| {
+                let terms: Vec<String> = terms.iter().map(|t| format!("\"{}\"", t)).collect();
+                format!("{} in {}", terms.join(", "), field)
+            })
+            .collect();
+        match self.similarity {
+            Some(similarity) if parts.is_empty() => {
+                return format!("semantic-only match, similarity {:.2}", similarity);
+            }
+            Some(similarity) => parts.push(format!("semantic similarity {:.2}", similarity)),
+            // Matched through a synonym or stemmed form that isn't a query word
+            None if parts.is_empty() => return "keyword match on related terms".to_string(),
+            None => {}
+        }
+        format!("matched {}", parts.join("; "))
+    }
+}
+
+/// Tokenizer for checklist IDs: the whole ID is one case-insensitive term
+const CHECKLIST_TOKENIZER_NAME: &str = "checklist_id";
+
+const DEFAULT_WRITER_HEAP_BYTES: usize = 50_000_000;
+
+/// Sections fetched per wanted result, so that keeping only the best section of each document
+/// still fills the limit
+pub const SECTIONS_PER_RESULT: usize = 3;
+
+/// Zstd level for stored fields; the index is written once per update, so it pays to compress
+/// harder than the LZ4 default
+const DOCSTORE_ZSTD_LEVEL: i32 = 9;
+
+/// Stored documents are compressed in blocks of this size; whole chapters (content plus
+/// markdown) compress much better in larger blocks than the 16 KB default
+const DOCSTORE_BLOCK_SIZE: usize = 128 * 1024;
+
 /// Tantivy-based search index for documentation
 pub struct SearchIndex {
     index: Index,
     schema: Schema,
+    synonyms: Synonyms,
+    /// Heap of the index writer, in bytes
+    writer_heap_bytes: usize,
 }
 
 impl SearchIndex {
@@ -29,61 +140,252 @@ impl SearchIndex {
     pub fn open_or_create(index_path: &Path) -> Result<Self> {
         let schema = Self::build_schema();
 
-        let index = if index_path.exists() {
-            Index::open_in_dir(index_path)?
+        let mut index = if index_path.join("meta.json").exists() {
+            Some(Index::open_in_dir(index_path)?)
         } else {
-            std::fs::create_dir_all(index_path)?;
-            Index::create_in_dir(index_path, schema.clone())?
+            None
+        };
+
+        // An index written with an older schema or compression is rebuilt; the empty index
+        // triggers re-indexing
+        let settings = Self::index_settings();
+        if index
+            .as_ref()
+            .is_some_and(|existing| existing.schema() != schema || existing.settings() != &settings)
+        {
+            tracing::info!("Index schema or settings changed, rebuilding keyword index");
+            Self::remove_index_files(index_path)?;
+            index = None;
+        }
+
+        let index = match index {
+            Some(index) => index,
+            None => {
+                std::fs::create_dir_all(index_path)?;
+                Index::builder()
+                    .schema(schema.clone())
+                    .settings(settings)
+                    .create_in_dir(index_path)?
+            }
         };
+        Self::register_tokenizers(&index);
 
-        Ok(Self { index, schema })
+        Ok(Self {
+            index,
+            schema,
+            synonyms: Synonyms::builtin(),
+            writer_heap_bytes: DEFAULT_WRITER_HEAP_BYTES,
+        })
     }
 
-    /// Create an in-memory index (for testing)
-    #[cfg(test)]
+    /// Create an in-memory index (tests and session-local workspace documentation)
     pub fn in_memory() -> Result<Self> {
         let schema = Self::build_schema();
-        let index = Index::create_in_ram(schema.clone());
-        Ok(Self { index, schema })
+        let index = Index::builder()
+            .schema(schema.clone())
+            .settings(Self::index_settings())
+            .create_in_ram()?;
+        Self::register_tokenizers(&index);
+        Ok(Self {
+            index,
+            schema,
+            synonyms: Synonyms::builtin(),
+            writer_heap_bytes: DEFAULT_WRITER_HEAP_BYTES,
+        })
+    }
+
+    /// Set the heap of the index writer (at least 15 MB)
+    pub fn set_writer_heap(&mut self, bytes: usize) {
+        self.writer_heap_bytes = bytes;
+    }
+
+    /// Replace the synonym table used for query expansion
+    pub fn set_synonyms(&mut self, synonyms: Synonyms) {
+        self.synonyms = synonyms;
+    }
+
+    fn register_tokenizers(index: &Index) {
+        index
+            .tokenizers()
+            .register(TOKENIZER_NAME, identifier_analyzer());
+        index.tokenizers().register(
+            CHECKLIST_TOKENIZER_NAME,
+            TextAnalyzer::builder(RawTokenizer::default())
+                .filter(LowerCaser)
+                .build(),
+        );
+    }
+
+    /// Delete the Tantivy files in an index directory, keeping nested directories (vectors)
+    fn remove_index_files(index_path: &Path) -> Result<()> {
+        for entry in std::fs::read_dir(index_path)? {
+            let path = entry?.path();
+            if path.is_file() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stored fields are zstd-compressed in large blocks
+    fn index_settings() -> IndexSettings {
+        IndexSettings {
+            docstore_compression: Compressor::Zstd(ZstdCompressor {
+                compression_level: Some(DOCSTORE_ZSTD_LEVEL),
+            }),
+            docstore_blocksize: DOCSTORE_BLOCK_SIZE,
+            ..IndexSettings::default()
+        }
     }
 
     fn build_schema() -> Schema {
+        // Text fields use the identifier-splitting tokenizer so `RefCell` also matches "ref cell"
+        let text_options = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(TOKENIZER_NAME)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            )
+            .set_stored();
+
         let mut schema_builder = Schema::builder();
-        schema_builder.add_text_field("title", TEXT | STORED);
-        schema_builder.add_text_field("content", TEXT | STORED);
-        schema_builder.add_text_field("path", STORED);
+        schema_builder.add_text_field("title", text_options.clone());
+        schema_builder.add_text_field("content", text_options);
+        // Indexed as single terms so documents can be fetched by `(source, path)`, and searches
+        // filtered by source
+        schema_builder.add_text_field("path", STRING | STORED);
         schema_builder.add_text_field("source", STRING | STORED);
+        schema_builder.add_text_field(
+            "checklist",
+            TextOptions::default()
+                .set_indexing_options(
+                    TextFieldIndexing::default()
+                        .set_tokenizer(CHECKLIST_TOKENIZER_NAME)
+                        .set_index_option(IndexRecordOption::Basic),
+                )
+                .set_stored(),
+        );
+        // Structure kept for aggregation tools; stored only, never searched
+        schema_builder.add_text_field("headings", STORED);
+        schema_builder.add_text_field("code", STORED);
+        schema_builder.add_text_field("code_terms", STORED);
+        // Markdown rendering of the content, for snippets
+        schema_builder.add_text_field("markdown", STORED);
+        schema_builder.add_u64_field("summary_order", STORED);
+        // Indexed as whole terms so a category's documents can be listed
+        schema_builder.add_text_field("categories", STRING | STORED);
+        // Each document is indexed as chunks, one per section: the chunk key for fetching a
+        // chunk, the section's anchor and breadcrumb, and the chunk's position in the document
+        schema_builder.add_text_field("chunk", STRING);
+        schema_builder.add_text_field("anchor", STORED);
+        schema_builder.add_text_field("breadcrumb", STORED);
+        schema_builder.add_u64_field("chunk_order", STORED);
         schema_builder.build()
     }
 
-    /// Index a batch of documents
+    /// Index a batch of documents, each split into chunks by section, replacing everything
+    /// indexed before
     pub fn index_documents(&self, documents: &[Document]) -> Result<()> {
-        let mut writer: IndexWriter = self.index.writer(50_000_000)?;
-
-        let title_field = self.schema.get_field("title").unwrap();
-        let content_field = self.schema.get_field("content").unwrap();
-        let path_field = self.schema.get_field("path").unwrap();
-        let source_field = self.schema.get_field("source").unwrap();
+        let mut writer: IndexWriter = self.index.writer(self.writer_heap_bytes)?;
 
         // Clear existing documents
         writer.delete_all_documents()?;
 
         for doc in documents {
-            writer.add_document(doc!(
-                title_field => doc.title.clone(),
-                content_field => doc.content.clone(),
-                path_field => doc.path.clone(),
-                source_field => doc.source.clone(),
-            ))?;
+            for chunk in self.chunk_entries(doc) {
+                writer.add_document(chunk)?;
+            }
+        }
+
+        writer.commit()?;
+        Ok(())
+    }
+
+    /// Re-index `documents` and delete the `removed` documents (by source and path), leaving
+    /// the rest of the index as it is
+    pub fn update_documents(
+        &self,
+        documents: &[&Document],
+        removed: &[(String, String)],
+    ) -> Result<()> {
+        use tantivy::query::{BooleanQuery, Occur};
+
+        let mut writer: IndexWriter = self.index.writer(self.writer_heap_bytes)?;
+
+        let replaced = documents
+            .iter()
+            .map(|doc| (doc.source.as_str(), doc.path.as_str()));
+        let removed = removed
+            .iter()
+            .map(|(source, path)| (source.as_str(), path.as_str()));
+        for (source, path) in replaced.chain(removed) {
+            writer.delete_query(Box::new(BooleanQuery::new(vec![
+                (Occur::Must, self.term_query("source", source)),
+                (Occur::Must, self.term_query("path", path)),
+            ])))?;
+        }
+        // Deletes apply to documents added before them, so the new chunks survive
+        for doc in documents {
+            for chunk in self.chunk_entries(doc) {
+                writer.add_document(chunk)?;
+            }
         }
 
         writer.commit()?;
         Ok(())
     }
 
+    /// The index entries of a document: one per chunk
+    fn chunk_entries(&self, doc: &Document) -> Vec<TantivyDocument> {
+        let field = |name: &str| self.schema.get_field(name).unwrap();
+        chunk_document(doc)
+            .into_iter()
+            .enumerate()
+            .map(|(order, chunk)| {
+                let mut entry = doc!(
+                    field("title") => chunk.title.clone(),
+                    field("content") => chunk.content.clone(),
+                    field("markdown") => chunk.markdown.clone(),
+                    field("path") => chunk.path.clone(),
+                    field("source") => chunk.source.clone(),
+                );
+                for id in &chunk.checklist_ids {
+                    entry.add_text(field("checklist"), id);
+                }
+                for heading in &chunk.headings {
+                    entry.add_text(field("headings"), heading);
+                }
+                for code in &chunk.code_blocks {
+                    entry.add_text(field("code"), code);
+                }
+                for term in &chunk.code_terms {
+                    entry.add_text(field("code_terms"), term);
+                }
+                if let Some(order) = chunk.summary_order {
+                    entry.add_u64(field("summary_order"), order as u64);
+                }
+                for category in &chunk.categories {
+                    entry.add_text(field("categories"), category);
+                }
+                entry.add_text(
+                    field("chunk"),
+                    chunk_key(&chunk.source, &chunk.path, chunk.section.as_ref()),
+                );
+                if let Some(section) = &chunk.section {
+                    entry.add_text(field("anchor"), &section.anchor);
+                    for heading in &section.breadcrumb {
+                        entry.add_text(field("breadcrumb"), heading);
+                    }
+                }
+                entry.add_u64(field("chunk_order"), order as u64);
+                entry
+            })
+            .collect()
+    }
+
     /// Search the index and return top results
     pub fn search(&self, query_str: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        self.search_with_sources(query_str, limit, None)
+        self.search_with_options(query_str, &SearchOptions::new(limit))
     }
 
     /// Search the index with optional source filtering
@@ -93,37 +395,318 @@ impl SearchIndex {
         limit: usize,
         sources: Option<&[&str]>,
     ) -> Result<Vec<SearchResult>> {
+        self.search_with_options(query_str, &SearchOptions::new(limit).with_sources(sources))
+    }
+
+    /// Search the index using the given options
+    pub fn search_with_options(
+        &self,
+        query_str: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(self.search_counted(query_str, options)?.0)
+    }
+
+    /// Search the index and also return the total number of matching sections. Results are
+    /// the best-matching section of each document.
+    #[tracing::instrument(name = "keyword_search", level = "debug", skip_all)]
+    pub fn search_counted(
+        &self,
+        query_str: &str,
+        options: &SearchOptions,
+    ) -> Result<(Vec<SearchResult>, usize)> {
+        let (top_docs, total_hits) = self.top_documents(query_str, options)?;
+        // Excluded words match nothing returned, so they take no part in explanations or snippets
+        let (query_str, _excluded) = query::split_exclusions(query_str);
+        let query_str = query_str.as_str();
+        let mut words = QueryWords::new(&self.synonyms.expand(query_str));
+        // Snippets are cut around the terms the query matched in the content, rare ones first
+        let searcher = self.index.reader()?.searcher();
+        let content_field = self.schema.get_field("content").unwrap();
+        let mut terms = QueryTerms::from(SnippetGenerator::create(
+            &searcher,
+            &*self.parse_user_query(query_str),
+            content_field,
+        )?);
+
+        let results = top_docs
+            .into_iter()
+            .map(|(score, doc)| {
+                let explanation = words.explain(&doc);
+                // Extract a snippet around the query terms, as markdown when the document has it
+                let snippet = if doc.markdown.is_empty() {
+                    plain_snippet(&doc.content, &mut terms, options.snippet_len)
+                } else {
+                    markdown_snippet(&doc.markdown, &mut terms, options.snippet_len)
+                };
+
+                SearchResult {
+                    title: doc.title,
+                    snippet,
+                    path: doc.path,
+                    source: doc.source,
+                    checklist_ids: doc.checklist_ids,
+                    score,
+                    explanation,
+                    section: doc.section,
+                }
+            })
+            .collect();
+
+        Ok((results, total_hits))
+    }
+
+    /// Search the index and return the full stored documents, best match first
+    pub fn search_documents(
+        &self,
+        query_str: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<Document>> {
+        let (top_docs, _total_hits) = self.top_documents(query_str, options)?;
+        let mut documents = Vec::with_capacity(top_docs.len());
+        for (_score, chunk) in top_docs {
+            documents.extend(self.get_document(&chunk.source, &chunk.path)?);
+        }
+        Ok(documents)
+    }
+
+    /// Fetch a stored document by source and path, reassembled from its chunks
+    pub fn get_document(&self, source: &str, path: &str) -> Result<Option<Document>> {
+        use tantivy::query::{BooleanQuery, Occur};
+
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, self.term_query("source", source)),
+            (Occur::Must, self.term_query("path", path)),
+        ]);
+        Ok(self.matching_documents(&query)?.into_iter().next())
+    }
+
+    /// Fetch a stored chunk by its key (see [`SearchResult::chunk_key`])
+    pub fn get_chunk(&self, key: &str) -> Result<Option<Document>> {
+        let reader = self.index.reader()?;
+        let searcher = reader.searcher();
+        let top_docs = searcher.search(&*self.term_query("chunk", key), &TopDocs::with_limit(1))?;
+        match top_docs.first() {
+            Some((_score, doc_address)) => {
+                let doc: TantivyDocument = searcher.doc(*doc_address)?;
+                Ok(Some(self.stored_chunk(&doc).1))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// A chunk as a search result, with a snippet from the start of its section
+    pub fn chunk_result(&self, key: &str, snippet_len: usize) -> Result<Option<SearchResult>> {
+        Ok(self.get_chunk(key)?.map(|chunk| SearchResult {
+            snippet: if chunk.markdown.is_empty() {
+                plain_snippet(&chunk.content, &mut QueryTerms::none(), snippet_len)
+            } else {
+                markdown_snippet(&chunk.markdown, &mut QueryTerms::none(), snippet_len)
+            },
+            title: chunk.title,
+            path: chunk.path,
+            source: chunk.source,
+            checklist_ids: chunk.checklist_ids,
+            score: 0.0,
+            explanation: MatchExplanation::default(),
+            section: chunk.section,
+        }))
+    }
+
+    /// Query matching a whole-term field value
+    fn term_query(&self, field: &str, value: &str) -> Box<dyn tantivy::query::Query> {
+        use tantivy::Term;
+        use tantivy::query::TermQuery;
+
+        let term = Term::from_field_text(self.schema.get_field(field).unwrap(), value);
+        Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+    }
+
+    /// All stored documents tagged with a best-practice category, in no particular order
+    pub fn documents_in_category(&self, category: &str) -> Result<Vec<Document>> {
+        use tantivy::Term;
+        use tantivy::query::TermQuery;
+
+        let field = self.schema.get_field("categories").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(field, category),
+            IndexRecordOption::Basic,
+        );
+        self.matching_documents(&query)
+    }
+
+    /// All stored documents of a source, in no particular order
+    pub fn documents_in_source(&self, source: &str) -> Result<Vec<Document>> {
+        use tantivy::Term;
+        use tantivy::query::TermQuery;
+
+        let field = self.schema.get_field("source").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(field, source),
+            IndexRecordOption::Basic,
+        );
+        self.matching_documents(&query)
+    }
+
+    /// Every stored document, in no particular order
+    pub fn all_documents(&self) -> Result<Vec<Document>> {
+        self.matching_documents(&tantivy::query::AllQuery)
+    }
+
+    /// Every stored chunk, in no particular order (for embedding the indexed corpus)
+    pub fn all_chunks(&self) -> Result<Vec<Document>> {
+        use tantivy::collector::DocSetCollector;
+
+        let reader = self.index.reader()?;
+        let searcher = reader.searcher();
+        searcher
+            .search(&tantivy::query::AllQuery, &DocSetCollector)?
+            .into_iter()
+            .map(|address| {
+                let doc: TantivyDocument = searcher.doc(address)?;
+                Ok(self.stored_chunk(&doc).1)
+            })
+            .collect()
+    }
+
+    /// Source, path, and title of every stored document, sorted by source and path; cheaper
+    /// than `all_documents` for listing them
+    pub fn document_titles(&self) -> Result<Vec<(String, String, String)>> {
+        use tantivy::collector::DocSetCollector;
+
+        let reader = self.index.reader()?;
+        let searcher = reader.searcher();
+        let field = |name: &str| self.schema.get_field(name).unwrap();
+        let (source, path, title) = (field("source"), field("path"), field("title"));
+        let mut titles: BTreeMap<(String, String), String> = BTreeMap::new();
+        for address in searcher.search(&tantivy::query::AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let text = |field| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string()
+            };
+            titles
+                .entry((text(source), text(path)))
+                .or_insert_with(|| text(title));
+        }
+        Ok(titles
+            .into_iter()
+            .map(|((source, path), title)| (source, path, title))
+            .collect())
+    }
+
+    /// Keys (`source/path`) of the documents with a section whose title or content mentions any
+    /// of `terms`
+    pub fn documents_mentioning(&self, terms: &[String]) -> Result<HashSet<String>> {
+        use tantivy::collector::DocSetCollector;
+        use tantivy::query::{BooleanQuery, Occur};
+
+        let query = BooleanQuery::new(
+            self.term_queries(terms)
+                .into_iter()
+                .map(|query| (Occur::Should, query))
+                .collect(),
+        );
         let reader = self.index.reader()?;
         let searcher = reader.searcher();
+        let source_field = self.schema.get_field("source").unwrap();
+        let path_field = self.schema.get_field("path").unwrap();
+        let mut documents = HashSet::new();
+        for address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let text = |field| doc.get_first(field).and_then(|v| v.as_str());
+            if let (Some(source), Some(path)) = (text(source_field), text(path_field)) {
+                documents.insert(document_key(source, path));
+            }
+        }
+        Ok(documents)
+    }
 
+    /// Parser for user queries over the title, content, and checklist fields
+    fn query_parser(&self) -> QueryParser {
         let title_field = self.schema.get_field("title").unwrap();
         let content_field = self.schema.get_field("content").unwrap();
-        let path_field = self.schema.get_field("path").unwrap();
-        let source_field = self.schema.get_field("source").unwrap();
+        let checklist_field = self.schema.get_field("checklist").unwrap();
 
-        let query_parser = QueryParser::for_index(&self.index, vec![title_field, content_field]);
-        let base_query = query_parser.parse_query(query_str)?;
+        // Checklist IDs like "C-CASE" match the checklist field as a single term
+        QueryParser::for_index(
+            &self.index,
+            vec![title_field, content_field, checklist_field],
+        )
+    }
 
-        // If sources specified, combine with source filter
-        let query: Box<dyn tantivy::query::Query> = if let Some(sources) = sources {
-            use tantivy::query::{BooleanQuery, Occur, TermQuery};
-            use tantivy::schema::IndexRecordOption;
-            use tantivy::Term;
+    /// Parse a user query with its synonyms.
+    ///
+    /// Reserved syntax in the user's query is escaped; anything the parser still rejects (say,
+    /// in a custom synonym) is dropped from the query instead of failing the search.
+    fn parse_user_query(&self, query_str: &str) -> Box<dyn tantivy::query::Query> {
+        let escaped_query = query::escape(query_str);
+        let expanded_query = self.synonyms.expand(&escaped_query);
+        let (query, errors) = self.query_parser().parse_query_lenient(&expanded_query);
+        if !errors.is_empty() {
+            tracing::debug!("Ignored parts of query {:?}: {:?}", expanded_query, errors);
+        }
+        query
+    }
 
-            // Create OR query for sources
-            let source_queries: Vec<(Occur, Box<dyn tantivy::query::Query>)> = sources
-                .iter()
-                .map(|s| {
-                    let term = Term::from_field_text(source_field, s);
-                    let term_query: Box<dyn tantivy::query::Query> =
-                        Box::new(TermQuery::new(term, IndexRecordOption::Basic));
-                    (Occur::Should, term_query)
-                })
-                .collect();
+    /// One query per term, matching documents that mention it
+    fn term_queries(&self, terms: &[String]) -> Vec<Box<dyn tantivy::query::Query>> {
+        let query_parser = self.query_parser();
+        terms
+            .iter()
+            .map(|term| query_parser.parse_query_lenient(&query::escape(term)).0)
+            .collect()
+    }
+
+    /// Every document with a chunk matching `query`, reassembled from the matching chunks,
+    /// unranked
+    fn matching_documents(&self, query: &dyn tantivy::query::Query) -> Result<Vec<Document>> {
+        use tantivy::collector::DocSetCollector;
+
+        let reader = self.index.reader()?;
+        let searcher = reader.searcher();
+        let mut documents: BTreeMap<(String, String), Vec<(u64, Document)>> = BTreeMap::new();
+        for address in searcher.search(query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let (order, chunk) = self.stored_chunk(&doc);
+            documents
+                .entry((chunk.source.clone(), chunk.path.clone()))
+                .or_default()
+                .push((order, chunk));
+        }
+        Ok(documents
+            .into_values()
+            .filter_map(|mut chunks| {
+                chunks.sort_by_key(|(order, _)| *order);
+                join_chunks(chunks.into_iter().map(|(_, chunk)| chunk))
+            })
+            .collect())
+    }
+
+    /// Run a BM25 query and load the stored fields of the top documents
+    fn top_documents(
+        &self,
+        query_str: &str,
+        options: &SearchOptions,
+    ) -> Result<(Vec<(f32, Document)>, usize)> {
+        let reader = self.index.reader()?;
+        let searcher = reader.searcher();
 
-            let source_filter = BooleanQuery::new(source_queries);
+        let source_field = self.schema.get_field("source").unwrap();
+        let base_query = self.parse_user_query(query::normalize(query_str)?);
 
-            // Combine: must match query AND must match one of the sources
+        // Sources are filtered while searching, so the top documents are all from them. The
+        // filter scores nothing: a source term's BM25 weight would favor small sources
+        let query: Box<dyn tantivy::query::Query> = if let Some(sources) = &options.sources {
+            use tantivy::Term;
+            use tantivy::query::{BooleanQuery, ConstScoreQuery, Occur, TermSetQuery};
+
+            let terms = sources
+                .iter()
+                .map(|source| Term::from_field_text(source_field, source));
+            let source_filter = ConstScoreQuery::new(Box::new(TermSetQuery::new(terms)), 0.0);
             Box::new(BooleanQuery::new(vec![
                 (Occur::Must, base_query),
                 (Occur::Must, Box::new(source_filter)),
@@ -132,97 +715,196 @@ impl SearchIndex {
             base_query
         };
 
-        let top_docs = searcher.search(&*query, &TopDocs::with_limit(limit))?;
+        // Excluded words rule out any document mentioning them
+        let query: Box<dyn tantivy::query::Query> = if options.exclude_terms.is_empty() {
+            query
+        } else {
+            use tantivy::query::{BooleanQuery, Occur};
+
+            let mut clauses = vec![(Occur::Must, query)];
+            clauses.extend(
+                self.term_queries(&options.exclude_terms)
+                    .into_iter()
+                    .map(|query| (Occur::MustNot, query)),
+            );
+            Box::new(BooleanQuery::new(clauses))
+        };
 
-        let mut results = Vec::new();
+        let (top_docs, total_hits) = searcher.search(
+            &*query,
+            &(
+                TopDocs::with_limit(options.limit * SECTIONS_PER_RESULT),
+                Count,
+            ),
+        )?;
+
+        let mut documents = Vec::with_capacity(top_docs.len());
         for (score, doc_address) in top_docs {
             let doc: TantivyDocument = searcher.doc(doc_address)?;
+            documents.push((score, self.stored_chunk(&doc).1));
+        }
+        // Tantivy orders equal scores by segment and document id, which depend on indexing order
+        documents.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .total_cmp(a_score)
+                .then_with(|| a.source.cmp(&b.source))
+                .then_with(|| a.path.cmp(&b.path))
+        });
 
-            let title = doc
-                .get_first(title_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
+        // The best section stands for its document; excluded words rule out the whole document,
+        // not only the sections mentioning them
+        let excluded = if options.exclude_terms.is_empty() {
+            HashSet::new()
+        } else {
+            self.documents_mentioning(&options.exclude_terms)?
+        };
+        let mut seen = HashSet::new();
+        documents.retain(|(_, doc)| {
+            let key = document_key(&doc.source, &doc.path);
+            !excluded.contains(&key) && seen.insert(key)
+        });
+        documents.truncate(options.limit);
 
-            let content = doc
-                .get_first(content_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
+        Ok((documents, total_hits))
+    }
 
-            let path = doc
-                .get_first(path_field)
+    /// Rebuild a chunk from its stored fields, with its position in the document
+    fn stored_chunk(&self, doc: &TantivyDocument) -> (u64, Document) {
+        let text = |name: &str| {
+            let field = self.schema.get_field(name).unwrap();
+            doc.get_first(field)
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
-                .to_string();
+                .to_string()
+        };
+        let all_text = |name: &str| {
+            let field = self.schema.get_field(name).unwrap();
+            doc.get_all(field)
+                .filter_map(|v| v.as_str())
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+        };
 
-            let source = doc
-                .get_first(source_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            // Extract a snippet around the query terms
-            let snippet = Self::extract_snippet(content, query_str, 200);
-
-            results.push(SearchResult {
-                title,
-                snippet,
-                path,
-                source,
-                score,
-            });
-        }
+        let anchor = text("anchor");
+        let section = (!anchor.is_empty()).then(|| Section {
+            anchor,
+            breadcrumb: all_text("breadcrumb"),
+        });
+        let order = doc
+            .get_first(self.schema.get_field("chunk_order").unwrap())
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
 
-        Ok(results)
+        let chunk = Document {
+            title: text("title"),
+            content: text("content"),
+            markdown: text("markdown"),
+            path: text("path"),
+            source: text("source"),
+            checklist_ids: all_text("checklist"),
+            headings: all_text("headings"),
+            code_blocks: all_text("code"),
+            code_terms: all_text("code_terms"),
+            summary_order: doc
+                .get_first(self.schema.get_field("summary_order").unwrap())
+                .and_then(|v| v.as_u64())
+                .map(|order| order as usize),
+            categories: all_text("categories"),
+            section,
+        };
+        (order, chunk)
     }
 
-    /// Check if the index has any documents
-    pub fn is_empty(&self) -> Result<bool> {
+    /// Number of indexed chunks
+    pub fn num_docs(&self) -> Result<u64> {
         let reader = self.index.reader()?;
-        let searcher = reader.searcher();
-        Ok(searcher.num_docs() == 0)
+        Ok(reader.searcher().num_docs())
     }
 
-    /// Extract a snippet of text around query terms
-    fn extract_snippet(content: &str, query: &str, max_len: usize) -> String {
-        let query_lower = query.to_lowercase();
-        let content_lower = content.to_lowercase();
+    /// Check if the index has any documents
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.num_docs()? == 0)
+    }
+}
 
-        // Find the first occurrence of any query word
-        let query_words: Vec<&str> = query_lower.split_whitespace().collect();
-        let mut best_pos = 0;
+/// Query words with their index tokens, for explaining why documents matched
+struct QueryWords {
+    /// Lowercase word and the tokens it is indexed as
+    words: Vec<(String, HashSet<String>)>,
+    analyzer: TextAnalyzer,
+}
 
-        for word in &query_words {
-            if let Some(pos) = content_lower.find(word) {
-                best_pos = pos;
-                break;
+impl QueryWords {
+    /// Words of a (synonym-expanded) query, without quotes, operators, or duplicates
+    fn new(query: &str) -> Self {
+        let mut analyzer = identifier_analyzer();
+        let mut words: Vec<(String, HashSet<String>)> = Vec::new();
+        for word in query.split_whitespace() {
+            if matches!(word, "AND" | "OR" | "NOT") {
+                continue;
+            }
+            let word = word
+                .trim_matches(|c: char| matches!(c, '"' | '+' | '-' | '(' | ')'))
+                .to_lowercase();
+            if word.is_empty() || words.iter().any(|(w, _)| *w == word) {
+                continue;
+            }
+            let tokens = tokens(&mut analyzer, &word);
+            if !tokens.is_empty() {
+                words.push((word, tokens));
             }
         }
+        Self { words, analyzer }
+    }
 
-        // Extract snippet around the found position
-        let start = best_pos.saturating_sub(max_len / 2);
-        let end = (start + max_len).min(content.len());
+    /// Which query words occur in each searched field of a document.
+    ///
+    /// A word matches a field when all of its tokens occur there, so `RefCell` needs `refcell`,
+    /// `ref`, and `cell`. Checklist IDs match as whole words.
+    fn explain(&mut self, doc: &Document) -> MatchExplanation {
+        let mut fields = Vec::new();
+        for (field, text) in [("title", &doc.title), ("content", &doc.content)] {
+            let field_tokens = tokens(&mut self.analyzer, text);
+            let matched: Vec<String> = self
+                .words
+                .iter()
+                .filter(|(_, word_tokens)| word_tokens.is_subset(&field_tokens))
+                .map(|(word, _)| word.clone())
+                .collect();
+            if !matched.is_empty() {
+                fields.push((field, matched));
+            }
+        }
 
-        let mut snippet: String = content
-            .chars()
-            .skip(start)
-            .take(end - start)
+        let checklist: Vec<String> = self
+            .words
+            .iter()
+            .filter(|(word, _)| {
+                doc.checklist_ids
+                    .iter()
+                    .any(|id| id.eq_ignore_ascii_case(word))
+            })
+            .map(|(word, _)| word.clone())
             .collect();
+        if !checklist.is_empty() {
+            fields.push(("checklist", checklist));
+        }
 
-        // Clean up snippet boundaries
-        if start > 0
-            && let Some(space_pos) = snippet.find(' ') {
-                snippet = snippet[space_pos + 1..].to_string();
-                snippet.insert_str(0, "...");
-            }
-        if end < content.len()
-            && let Some(space_pos) = snippet.rfind(' ') {
-                snippet.truncate(space_pos);
-                snippet.push_str("...");
-            }
+        MatchExplanation {
+            fields,
+            similarity: None,
+        }
+    }
+}
 
-        snippet
+/// Distinct tokens the index analyzer produces for a text
+fn tokens(analyzer: &mut TextAnalyzer, text: &str) -> HashSet<String> {
+    let mut stream = analyzer.token_stream(text);
+    let mut tokens = HashSet::new();
+    while stream.advance() {
+        tokens.insert(stream.token().text.clone());
     }
+    tokens
 }
 
 #[cfg(test)]
@@ -239,12 +921,14 @@ mod tests {
                 content: "Rust uses ownership to manage memory safely.".to_string(),
                 path: "ownership.md".to_string(),
                 source: "rust-book".to_string(),
+                ..Default::default()
             },
             Document {
                 title: "Borrowing".to_string(),
                 content: "Borrowing allows references without taking ownership.".to_string(),
                 path: "borrowing.md".to_string(),
                 source: "rust-book".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -257,10 +941,417 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_search_counted_counts_hits_past_the_limit() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let docs = vec![
+            Document {
+                title: "Ownership".to_string(),
+                content: "Rust uses ownership to manage memory safely.".to_string(),
+                path: "ownership.md".to_string(),
+                source: "rust-book".to_string(),
+                ..Default::default()
+            },
+            Document {
+                title: "Borrowing".to_string(),
+                content: "Borrowing allows references without taking ownership.".to_string(),
+                path: "borrowing.md".to_string(),
+                source: "rust-book".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        index.index_documents(&docs)?;
+
+        let (top, total) = index.search_counted("ownership", &SearchOptions::new(1))?;
+        assert_eq!(top.len(), 1);
+        assert_eq!(total, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_document() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let doc = |source: &str, title: &str| Document {
+            title: title.to_string(),
+            content: format!("{} content", title),
+            path: "ch04-01-what-is-ownership.md".to_string(),
+            source: source.to_string(),
+            ..Default::default()
+        };
+        index.index_documents(&[
+            doc("rust-book", "Ownership"),
+            doc("rust-by-example", "Other"),
+        ])?;
+
+        let found = index.get_document("rust-book", "ch04-01-what-is-ownership.md")?;
+        assert_eq!(found.map(|d| d.title), Some("Ownership".to_string()));
+        assert!(index.get_document("rust-book", "missing.md")?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_documents_are_indexed_by_section() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let markdown = "# Traits\n\nShared behavior.\n\n## Defining a Trait\n\nUse the `trait` keyword.\n\n### Default Implementations\n\nA method body in the trait is the default.\n\n## Trait Bounds\n\nGeneric functions can require a trait.";
+        let doc = crate::parsing::parse_markdown(markdown, "ch10-02-traits.md", "rust-book");
+        index.index_documents(std::slice::from_ref(&doc))?;
+        assert_eq!(index.num_docs()?, 4);
+
+        // The best section stands for the document, and the snippet comes from it
+        let (results, total) = index.search_counted("default trait", &SearchOptions::new(10))?;
+        assert_eq!((results.len(), total), (1, 3));
+        let section = results[0].section.as_ref().unwrap();
+        assert_eq!(section.anchor, "default-implementations");
+        assert_eq!(
+            section.breadcrumb,
+            vec!["Defining a Trait", "Default Implementations"]
+        );
+        assert!(results[0].snippet.contains("default"));
+        assert_eq!(
+            results[0].chunk_key(),
+            "rust-book/ch10-02-traits.md#default-implementations"
+        );
+
+        let chunk = index
+            .get_chunk("rust-book/ch10-02-traits.md#trait-bounds")?
+            .unwrap();
+        assert_eq!(chunk.title, "Traits");
+        assert!(chunk.content.contains("Generic functions"));
+        assert_eq!(index.all_chunks()?.len(), 4);
+
+        // Whole documents are reassembled in order
+        let stored = index
+            .get_document("rust-book", "ch10-02-traits.md")?
+            .unwrap();
+        assert_eq!(stored.markdown, doc.markdown);
+        assert_eq!(stored.section, None);
+        assert_eq!(index.all_documents()?.len(), 1);
+        assert_eq!(
+            index.document_titles()?,
+            vec![(
+                "rust-book".to_string(),
+                "ch10-02-traits.md".to_string(),
+                "Traits".to_string()
+            )]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_documents_replaces_only_their_chunks() -> Result<()> {
+        use crate::parsing::parse_markdown;
+
+        let index = SearchIndex::in_memory()?;
+        let traits = parse_markdown(
+            "# Traits\n\n## Defining a Trait\n\nUse `trait`.\n\n## Trait Bounds\n\nRequire a trait.",
+            "traits.md",
+            "rust-book",
+        );
+        let enums = parse_markdown("# Enums\n\nVariants.", "enums.md", "rust-book");
+        let nomicon_enums = parse_markdown("# Enums\n\nLayout.", "enums.md", "nomicon");
+        index.index_documents(&[traits, enums, nomicon_enums])?;
+        assert_eq!(index.num_docs()?, 4);
+
+        // A changed document loses its old sections; the same path in another source stays
+        let traits = parse_markdown("# Traits\n\nShared behavior.", "traits.md", "rust-book");
+        let closures = parse_markdown("# Closures\n\nCaptures.", "closures.md", "rust-book");
+        index.update_documents(
+            &[&traits, &closures],
+            &[("rust-book".to_string(), "enums.md".to_string())],
+        )?;
+        assert_eq!(index.num_docs()?, 3);
+        assert!(index.get_chunk("traits.md#trait-bounds")?.is_none());
+        let updated = index.get_document("rust-book", "traits.md")?.unwrap();
+        assert!(updated.content.contains("Shared behavior."));
+        assert!(index.get_document("rust-book", "enums.md")?.is_none());
+        assert!(index.get_document("nomicon", "enums.md")?.is_some());
+        assert!(index.get_document("rust-book", "closures.md")?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_identifier_splitting_matches_natural_language() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let docs = vec![Document {
+            title: "Interior Mutability".to_string(),
+            content: "RefCell enforces borrowing rules at runtime; see try_into as well."
+                .to_string(),
+            path: "interior.md".to_string(),
+            source: "rust-book".to_string(),
+            ..Default::default()
+        }];
+
+        index.index_documents(&docs)?;
+
+        assert_eq!(index.search("RefCell", 10)?.len(), 1);
+        assert_eq!(index.search("\"ref cell\"", 10)?.len(), 1);
+        assert_eq!(index.search("into", 10)?.len(), 1);
+        assert_eq!(index.search("try_into", 10)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserved_syntax_is_searched_as_text() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let docs = vec![Document {
+            title: "Swapping Values".to_string(),
+            content: "Use std::mem::swap to exchange two values (see mem::replace).".to_string(),
+            path: "swap.md".to_string(),
+            source: "rust-std".to_string(),
+            ..Default::default()
+        }];
+
+        index.index_documents(&docs)?;
+
+        assert_eq!(index.search("std::mem::swap", 10)?.len(), 1);
+        assert_eq!(index.search("mem::replace)", 10)?.len(), 1);
+        assert_eq!(index.search("\"swap to", 10)?.len(), 1);
+        assert_eq!(index.search("swap^", 10)?.len(), 1);
+        assert!(index.search("   ", 10).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_excluded_terms() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let doc = |title: &str, content: &str, path: &str| Document {
+            title: title.to_string(),
+            content: content.to_string(),
+            path: path.to_string(),
+            source: "rust-book".to_string(),
+            ..Default::default()
+        };
+        index.index_documents(&[
+            doc(
+                "Iterators",
+                "Processing a series of items with iterators.",
+                "iter.md",
+            ),
+            doc(
+                "Streams",
+                "Async iterators are called streams.",
+                "streams.md",
+            ),
+        ])?;
+
+        assert_eq!(index.search("iterators", 10)?.len(), 2);
+        let results = index.search("iterators -async", 10)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "iter.md");
+
+        let options = SearchOptions::new(10).with_exclude_terms(&["Async".to_string()]);
+        let (results, total) = index.search_counted("iterators", &options)?;
+        assert_eq!((results.len(), total), (1, 1));
+
+        let documents =
+            index.documents_mentioning(&["async".to_string(), "missing".to_string()])?;
+        assert_eq!(
+            documents,
+            HashSet::from(["rust-book/streams.md".to_string()])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_checklist_ids_are_searchable_and_returned() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let docs = vec![Document {
+            title: "Naming".to_string(),
+            content: "Casing conforms to RFC 430 (C-CASE)".to_string(),
+            path: "naming.md".to_string(),
+            source: "api-guidelines".to_string(),
+            checklist_ids: vec!["C-CASE".to_string(), "C-CONV".to_string()],
+            ..Default::default()
+        }];
+
+        index.index_documents(&docs)?;
+
+        let results = index.search("c-conv", 10)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].checklist_ids, vec!["C-CASE", "C-CONV"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_documents_in_category() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let doc = |path: &str, categories: &[&str]| Document {
+            title: path.to_string(),
+            path: path.to_string(),
+            source: "rust-patterns".to_string(),
+            categories: categories.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        index.index_documents(&[
+            doc("builder.md", &["builders"]),
+            doc("errors.md", &["error-handling", "ffi"]),
+            doc("strings.md", &["ffi"]),
+        ])?;
+
+        let mut paths: Vec<String> = index
+            .documents_in_category("ffi")?
+            .into_iter()
+            .map(|doc| doc.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["errors.md", "strings.md"]);
+        assert_eq!(
+            index.documents_in_category("builders")?[0].categories,
+            vec!["builders"]
+        );
+        assert!(index.documents_in_category("macros")?.is_empty());
+        assert_eq!(index.all_documents()?.len(), 3);
+        assert_eq!(index.documents_in_source("rust-patterns")?.len(), 3);
+        assert!(index.documents_in_source("rust-book")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_results_explain_matched_terms() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let docs = vec![Document {
+            title: "Interior Mutability".to_string(),
+            content: "A RefCell checks borrowing at runtime (C-CASE).".to_string(),
+            path: "interior.md".to_string(),
+            source: "rust-book".to_string(),
+            checklist_ids: vec!["C-CASE".to_string()],
+            ..Default::default()
+        }];
+
+        index.index_documents(&docs)?;
+
+        let results = index.search("mutability RefCell", 10)?;
+        assert_eq!(
+            results[0].explanation.fields,
+            vec![
+                ("title", vec!["mutability".to_string()]),
+                ("content", vec!["refcell".to_string()]),
+            ]
+        );
+        assert_eq!(
+            results[0].explanation.describe(),
+            "matched \"mutability\" in title; \"refcell\" in content"
+        );
+
+        let results = index.search("c-case", 10)?;
+        assert!(
+            results[0]
+                .explanation
+                .fields
+                .contains(&("checklist", vec!["c-case".to_string()]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_equal_scores_are_ordered_by_source_and_path() -> Result<()> {
+        let doc = |source: &str, path: &str| Document {
+            title: "Borrowing".to_string(),
+            content: "The borrow checker enforces the borrowing rules.".to_string(),
+            path: path.to_string(),
+            source: source.to_string(),
+            ..Document::default()
+        };
+        let index = SearchIndex::in_memory()?;
+        index.index_documents(&[
+            doc("rust-reference", "b.md"),
+            doc("rust-book", "c.md"),
+            doc("rust-book", "a.md"),
+        ])?;
+
+        let results = index.search("borrow checker", 3)?;
+        let order: Vec<(&str, &str)> = results
+            .iter()
+            .map(|r| (r.source.as_str(), r.path.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("rust-book", "a.md"),
+                ("rust-book", "c.md"),
+                ("rust-reference", "b.md")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_filter_keeps_scores() -> Result<()> {
+        let doc = |source: &str, path: &str, title: &str| Document {
+            title: title.to_string(),
+            content: format!("{} and the borrowing rules.", title),
+            path: path.to_string(),
+            source: source.to_string(),
+            ..Document::default()
+        };
+        let index = SearchIndex::in_memory()?;
+        index.index_documents(&[
+            doc("rust-book", "a.md", "Borrowing"),
+            doc("rust-book", "b.md", "References"),
+            doc("rust-book", "c.md", "Slices"),
+            doc("nomicon", "d.md", "Aliasing"),
+        ])?;
+
+        let unfiltered = index.search("borrowing", 4)?;
+        let options = SearchOptions::new(4).with_sources(Some(&["rust-book", "nomicon"]));
+        let filtered = index.search_with_options("borrowing", &options)?;
+        assert_eq!(filtered.len(), 4);
+        // The rarer source's term would raise nomicon's score if the filter were scored
+        for (a, b) in unfiltered.iter().zip(&filtered) {
+            assert_eq!((&a.path, a.score), (&b.path, b.score));
+        }
+
+        // Filtered sections are never counted as hits
+        let options = SearchOptions::new(4).with_sources(Some(&["nomicon"]));
+        let (results, total) = index.search_counted("borrowing", &options)?;
+        assert_eq!(total, 1);
+        assert_eq!(results[0].path, "d.md");
+        Ok(())
+    }
+
     #[test]
     fn test_empty_index() -> Result<()> {
         let index = SearchIndex::in_memory()?;
         assert!(index.is_empty()?);
         Ok(())
     }
+
+    #[test]
+    fn test_reopened_index_is_kept() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("rust-lang-mcp-index-{}", std::process::id()));
+        let docs = vec![Document {
+            title: "Ownership".to_string(),
+            content: "Each value has an owner.".to_string(),
+            markdown: "Each value has an `owner`.".to_string(),
+            path: "ownership.md".to_string(),
+            source: "rust-book".to_string(),
+            ..Document::default()
+        }];
+        SearchIndex::open_or_create(&dir)?.index_documents(&docs)?;
+
+        // Same schema and compression settings: the stored documents survive reopening
+        let index = SearchIndex::open_or_create(&dir)?;
+        let doc = index.get_document("rust-book", "ownership.md")?.unwrap();
+        assert_eq!(doc.markdown, "Each value has an `owner`.");
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,326 @@
+//! Lightweight query intent classification used to route searches.
+//!
+//! Each intent prefers a set of documentation sources and a snippet length. Routing only
+//! biases ranking towards the preferred sources; results from other sources are kept. Queries
+//! naming a topic with its own book (async, Cargo, compiler internals) also prefer that book,
+//! whatever their intent.
+
+use crate::search::hybrid::SearchMode;
+use crate::search::index::SearchResult;
+use crate::search::options::{DEFAULT_SNIPPET_LEN, SearchOptions};
+
+/// Score multiplier applied to results from an intent's preferred sources
+const PREFERRED_SOURCE_BOOST: f32 = 1.5;
+
+/// What the user is most likely asking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryIntent {
+    /// "What is ownership?" - conceptual explanation
+    Concept,
+    /// "Show me an example of iterators" - runnable code
+    Example,
+    /// "Vec::retain signature" - a specific item or syntax
+    ApiLookup,
+    /// "Idiomatic error handling" - recommendations and patterns
+    BestPractice,
+    /// "wasm-bindgen closures" - WebAssembly and browser targets
+    WebAssembly,
+    /// Nothing specific detected
+    General,
+}
+
+/// Sources that are the authority on a topic, with the words naming it
+const TOPIC_SOURCES: &[(&str, &[&str])] = &[
+    (
+        "async-book",
+        &[
+            "async",
+            "await",
+            "future",
+            "futures",
+            "executor",
+            "executors",
+            "waker",
+            "pin",
+            "pinning",
+            "tokio",
+        ],
+    ),
+    (
+        "cargo-book",
+        &[
+            "cargo",
+            "cargo toml",
+            "crates io",
+            "workspace",
+            "workspaces",
+            "feature flags",
+            "cargo features",
+            "release profile",
+            "build profile",
+            "build script",
+        ],
+    ),
+    (
+        "rustc-dev-guide",
+        &[
+            "rustc internals",
+            "compiler internals",
+            "rustc_middle",
+            "mir",
+            "hir",
+            "thir",
+            "query system",
+            "bootstrap",
+            "x py",
+        ],
+    ),
+];
+
+const WASM_MARKERS: &[&str] = &[
+    "wasm",
+    "webassembly",
+    "wasm-bindgen",
+    "wasm_bindgen",
+    "wasm-pack",
+    "web-sys",
+    "js-sys",
+    "wasm32-unknown-unknown",
+    "browser",
+    "javascript",
+];
+const API_MARKERS: &[&str] = &[
+    "signature",
+    "method",
+    "function",
+    "api",
+    "syntax",
+    "keyword",
+];
+const PRACTICE_MARKERS: &[&str] = &[
+    "best practice",
+    "best practices",
+    "idiomatic",
+    "idiom",
+    "idioms",
+    "convention",
+    "conventions",
+    "guideline",
+    "guidelines",
+    "pattern",
+    "patterns",
+    "anti-pattern",
+    "recommended",
+    "should i",
+    "naming",
+];
+const EXAMPLE_MARKERS: &[&str] = &[
+    "example", "examples", "how to", "how do i", "show me", "snippet", "sample", "code for",
+];
+const CONCEPT_MARKERS: &[&str] = &[
+    "what is",
+    "what are",
+    "explain",
+    "why",
+    "meaning",
+    "concept",
+    "difference between",
+    "understand",
+];
+
+impl QueryIntent {
+    /// Classify a query using keyword and syntax heuristics
+    pub fn classify(query: &str) -> Self {
+        let phrase = marker_phrase(query);
+        let has = |markers: &[&str]| has_marker(&phrase, markers);
+
+        // No other source covers WebAssembly, so it wins over the generic intents
+        if has(WASM_MARKERS) {
+            return QueryIntent::WebAssembly;
+        }
+        // Paths and call syntax are a strong signal for an item lookup
+        if query.contains("::") || query.trim_end().ends_with("()") || has(API_MARKERS) {
+            return QueryIntent::ApiLookup;
+        }
+        if has(PRACTICE_MARKERS) {
+            return QueryIntent::BestPractice;
+        }
+        if has(EXAMPLE_MARKERS) {
+            return QueryIntent::Example;
+        }
+        if has(CONCEPT_MARKERS) {
+            return QueryIntent::Concept;
+        }
+
+        QueryIntent::General
+    }
+
+    /// Sources that are the authority on a topic the query names, such as the Async Book for
+    /// `async fn in traits`
+    pub fn topic_sources(query: &str) -> Vec<&'static str> {
+        let phrase = marker_phrase(query);
+        TOPIC_SOURCES
+            .iter()
+            .filter(|(_, markers)| has_marker(&phrase, markers))
+            .map(|(source, _)| *source)
+            .collect()
+    }
+
+    /// Name used in logs and tool output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryIntent::Concept => "concept",
+            QueryIntent::Example => "example",
+            QueryIntent::ApiLookup => "api_lookup",
+            QueryIntent::BestPractice => "best_practice",
+            QueryIntent::WebAssembly => "webassembly",
+            QueryIntent::General => "general",
+        }
+    }
+
+    /// Sources whose results are boosted for this intent
+    pub fn preferred_sources(&self) -> &'static [&'static str] {
+        match self {
+            QueryIntent::Concept => &["rust-book", "rust-reference"],
+            QueryIntent::Example => &["rust-by-example"],
+            QueryIntent::ApiLookup => &["rust-reference", "rust-by-example"],
+            QueryIntent::BestPractice => &["rust-patterns", "api-guidelines"],
+            QueryIntent::WebAssembly => &["rustwasm-book"],
+            QueryIntent::General => &[],
+        }
+    }
+
+    /// Search mode suited to the intent (exact identifiers favour keyword matching)
+    pub fn preferred_mode(&self) -> SearchMode {
+        match self {
+            QueryIntent::ApiLookup => SearchMode::Keyword,
+            _ => SearchMode::Hybrid,
+        }
+    }
+
+    /// Snippet length suited to the intent (explanations need more context than lookups)
+    pub fn snippet_len(&self) -> usize {
+        match self {
+            QueryIntent::Concept => 400,
+            QueryIntent::Example | QueryIntent::BestPractice | QueryIntent::WebAssembly => 300,
+            QueryIntent::ApiLookup | QueryIntent::General => DEFAULT_SNIPPET_LEN,
+        }
+    }
+
+    /// Re-order results so the intent's preferred sources, and those of the topics the query
+    /// names, rank higher, on top of the options' source boosts (reported scores are unchanged)
+    pub fn apply_bias(&self, query: &str, results: &mut [SearchResult], options: &SearchOptions) {
+        let mut preferred = self.preferred_sources().to_vec();
+        preferred.extend(Self::topic_sources(query));
+        if preferred.is_empty() && options.source_boosts.is_empty() {
+            return;
+        }
+
+        let biased = |result: &SearchResult| {
+            let boost = options.source_boost(&result.source);
+            if preferred.contains(&result.source.as_str()) {
+                result.score * PREFERRED_SOURCE_BOOST * boost
+            } else {
+                result.score * boost
+            }
+        };
+
+        results.sort_by(|a, b| {
+            biased(b)
+                .partial_cmp(&biased(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+}
+
+/// The query's words, lowercased and space-separated with a space at each end, so markers are
+/// matched as whole words with `has_marker`
+fn marker_phrase(query: &str) -> String {
+    let lower = query.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .filter(|w| !w.is_empty())
+        .collect();
+    format!(" {} ", words.join(" "))
+}
+
+/// Whether a phrase from `marker_phrase` contains any of the markers as whole words
+fn has_marker(phrase: &str, markers: &[&str]) -> bool {
+    markers
+        .iter()
+        .any(|marker| phrase.contains(&format!(" {} ", marker)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_intents() {
+        assert_eq!(QueryIntent::classify("Vec::retain"), QueryIntent::ApiLookup);
+        assert_eq!(
+            QueryIntent::classify("idiomatic error handling"),
+            QueryIntent::BestPractice
+        );
+        assert_eq!(
+            QueryIntent::classify("show me an example of closures"),
+            QueryIntent::Example
+        );
+        assert_eq!(
+            QueryIntent::classify("What is ownership?"),
+            QueryIntent::Concept
+        );
+        assert_eq!(QueryIntent::classify("lifetimes"), QueryIntent::General);
+        assert_eq!(
+            QueryIntent::classify("wasm_bindgen::JsValue from a closure"),
+            QueryIntent::WebAssembly
+        );
+        assert_eq!(
+            QueryIntent::classify("build for wasm32-unknown-unknown with wasm-pack"),
+            QueryIntent::WebAssembly
+        );
+    }
+
+    #[test]
+    fn test_bias_reorders_preferred_sources() {
+        let result = |source: &str, score: f32| {
+            SearchResult::for_test(source, &format!("{}.md", source), score)
+        };
+        let mut results = vec![
+            result("rust-reference", 1.0),
+            result("rust-by-example", 0.8),
+        ];
+
+        QueryIntent::Example.apply_bias("closures", &mut results, &SearchOptions::new(5));
+        assert_eq!(results[0].source, "rust-by-example");
+
+        // Source boosts from the options stack on the intent's
+        let options =
+            SearchOptions::new(5).with_source_boosts(&[("rust-reference".to_string(), 2.0)]);
+        QueryIntent::Example.apply_bias("closures", &mut results, &options);
+        assert_eq!(results[0].source, "rust-reference");
+
+        // So does the book of a topic the query names
+        let mut results = vec![result("rust-book", 1.0), result("async-book", 0.8)];
+        QueryIntent::General.apply_bias("await", &mut results, &SearchOptions::new(5));
+        assert_eq!(results[0].source, "async-book");
+    }
+
+    #[test]
+    fn test_topic_sources() {
+        assert_eq!(
+            QueryIntent::topic_sources("How do I pin a Future?"),
+            vec!["async-book"]
+        );
+        assert_eq!(
+            QueryIntent::topic_sources("cargo release profile for async code"),
+            vec!["async-book", "cargo-book"]
+        );
+        assert_eq!(
+            QueryIntent::topic_sources("how is MIR borrowck run"),
+            vec!["rustc-dev-guide"]
+        );
+        // Markers are whole words
+        assert!(QueryIntent::topic_sources("asynchronous pinball").is_empty());
+    }
+}
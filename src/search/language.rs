@@ -0,0 +1,339 @@
+//! Query language detection.
+//!
+//! The documentation is in English, so a query in another language finds little by keyword and,
+//! unless the embedding model is multilingual, not much more by meaning. Detection is
+//! heuristic: the script of non-Latin queries, and for Latin-script queries the function words
+//! and accented letters characteristic of each language. Rust identifiers and English words
+//! don't count, so "¿cómo funciona el trait Iterator?" is Spanish and "Vec vs slice" English.
+
+/// A language queries are detected in
+#[derive(Debug, PartialEq, Eq)]
+pub struct Language {
+    /// ISO 639-1 code
+    pub code: &'static str,
+    /// English name, for notes and translation prompts
+    pub name: &'static str,
+    /// Function words and question words, lowercase
+    words: &'static [&'static str],
+    /// Letters that (almost) only this language uses among the detected ones
+    letters: &'static [char],
+}
+
+/// Latin-script languages told apart by their words and letters
+const LATIN: &[Language] = &[
+    Language {
+        code: "es",
+        name: "Spanish",
+        words: &[
+            "qué",
+            "que",
+            "cómo",
+            "como",
+            "cuál",
+            "cuándo",
+            "dónde",
+            "por",
+            "para",
+            "porque",
+            "el",
+            "los",
+            "las",
+            "una",
+            "del",
+            "es",
+            "son",
+            "con",
+            "sin",
+            "entre",
+            "puedo",
+            "hacer",
+            "usar",
+            "funciona",
+            "diferencia",
+            "y",
+            "mi",
+            "se",
+            "está",
+            "cuando",
+        ],
+        letters: &['ñ', '¿', '¡'],
+    },
+    Language {
+        code: "pt",
+        name: "Portuguese",
+        words: &[
+            "que",
+            "como",
+            "qual",
+            "quando",
+            "onde",
+            "por",
+            "para",
+            "porque",
+            "os",
+            "as",
+            "uma",
+            "um",
+            "do",
+            "da",
+            "dos",
+            "das",
+            "é",
+            "são",
+            "com",
+            "sem",
+            "entre",
+            "posso",
+            "fazer",
+            "usar",
+            "funciona",
+            "diferença",
+            "e",
+            "meu",
+            "não",
+            "em",
+            "no",
+            "na",
+        ],
+        letters: &['ã', 'õ'],
+    },
+    Language {
+        code: "fr",
+        name: "French",
+        words: &[
+            "comment",
+            "quoi",
+            "quel",
+            "quelle",
+            "pourquoi",
+            "quand",
+            "où",
+            "le",
+            "les",
+            "une",
+            "des",
+            "du",
+            "est",
+            "sont",
+            "avec",
+            "sans",
+            "entre",
+            "je",
+            "puis",
+            "faire",
+            "utiliser",
+            "fonctionne",
+            "différence",
+            "et",
+            "mon",
+            "ne",
+            "pas",
+            "dans",
+            "pour",
+        ],
+        letters: &['è', 'ù', 'œ', 'û'],
+    },
+    Language {
+        code: "de",
+        name: "German",
+        words: &[
+            "wie",
+            "was",
+            "warum",
+            "wann",
+            "wo",
+            "der",
+            "die",
+            "das",
+            "ein",
+            "eine",
+            "ist",
+            "sind",
+            "mit",
+            "ohne",
+            "zwischen",
+            "ich",
+            "kann",
+            "machen",
+            "verwenden",
+            "funktioniert",
+            "unterschied",
+            "und",
+            "mein",
+            "nicht",
+            "für",
+            "einen",
+        ],
+        letters: &['ä', 'ö', 'ü', 'ß'],
+    },
+    Language {
+        code: "it",
+        name: "Italian",
+        words: &[
+            "come",
+            "cosa",
+            "quale",
+            "perché",
+            "quando",
+            "dove",
+            "il",
+            "lo",
+            "gli",
+            "una",
+            "della",
+            "è",
+            "sono",
+            "con",
+            "senza",
+            "tra",
+            "posso",
+            "fare",
+            "usare",
+            "funziona",
+            "differenza",
+            "e",
+            "mio",
+            "non",
+            "per",
+            "di",
+        ],
+        letters: &['ò', 'ì'],
+    },
+];
+
+/// English words that make a query English even when it shares words with another language
+const ENGLISH_WORDS: &[&str] = &[
+    "the", "how", "what", "why", "when", "where", "which", "is", "are", "does", "do", "to", "of",
+    "in", "for", "with", "without", "between", "can", "use", "using", "and", "my", "not", "an",
+];
+
+static CHINESE: Language = Language {
+    code: "zh",
+    name: "Chinese",
+    words: &[],
+    letters: &[],
+};
+
+static JAPANESE: Language = Language {
+    code: "ja",
+    name: "Japanese",
+    words: &[],
+    letters: &[],
+};
+
+static KOREAN: Language = Language {
+    code: "ko",
+    name: "Korean",
+    words: &[],
+    letters: &[],
+};
+
+static RUSSIAN: Language = Language {
+    code: "ru",
+    name: "Russian",
+    words: &[],
+    letters: &[],
+};
+
+/// The language of a query, or `None` for English and queries too short to tell
+pub fn detect(query: &str) -> Option<&'static Language> {
+    detect_script(query).or_else(|| detect_latin(query))
+}
+
+/// Languages recognized by their script alone
+fn detect_script(query: &str) -> Option<&'static Language> {
+    let (mut han, mut kana, mut hangul, mut cyrillic) = (0, 0, 0, 0);
+    for c in query.chars() {
+        match c {
+            '\u{3040}'..='\u{30ff}' => kana += 1,
+            '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}' => han += 1,
+            '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}' => hangul += 1,
+            '\u{0400}'..='\u{04ff}' => cyrillic += 1,
+            _ => {}
+        }
+    }
+    // Japanese mixes kanji with kana; Chinese has no kana
+    if kana > 0 {
+        Some(&JAPANESE)
+    } else if hangul > 0 {
+        Some(&KOREAN)
+    } else if han > 0 {
+        Some(&CHINESE)
+    } else if cyrillic > 1 {
+        Some(&RUSSIAN)
+    } else {
+        None
+    }
+}
+
+/// Latin-script languages, scored by their words (one point each) and letters (two points);
+/// a language needs two points and more than the English words found
+fn detect_latin(query: &str) -> Option<&'static Language> {
+    let lower = query.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let english = words.iter().filter(|w| ENGLISH_WORDS.contains(w)).count();
+
+    LATIN
+        .iter()
+        .map(|language| {
+            let from_words = words.iter().filter(|w| language.words.contains(w)).count();
+            let from_letters = 2 * lower
+                .chars()
+                .filter(|c| language.letters.contains(c))
+                .count();
+            (from_words + from_letters, language)
+        })
+        .filter(|(score, _)| *score >= 2 && *score > english)
+        // The first language wins ties, so the order of `LATIN` matters
+        .fold(
+            None,
+            |best: Option<(usize, &Language)>, (score, language)| match best {
+                Some((best_score, _)) if best_score >= score => best,
+                _ => Some((score, language)),
+            },
+        )
+        .map(|(_, language)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(query: &str) -> Option<&'static str> {
+        detect(query).map(|language| language.code)
+    }
+
+    #[test]
+    fn test_detect_latin() {
+        assert_eq!(code("¿Cómo funciona el trait Iterator?"), Some("es"));
+        assert_eq!(code("qual a diferença entre String e &str"), Some("pt"));
+        assert_eq!(code("comment fonctionne le borrow checker"), Some("fr"));
+        assert_eq!(code("Wie funktioniert der Borrow Checker?"), Some("de"));
+        assert_eq!(code("come funziona il borrow checker"), Some("it"));
+    }
+
+    #[test]
+    fn test_detect_script() {
+        assert_eq!(code("Rust 的生命周期是什么"), Some("zh"));
+        assert_eq!(code("ライフタイムとは何ですか"), Some("ja"));
+        assert_eq!(code("러스트 소유권"), Some("ko"));
+        assert_eq!(code("что такое заимствование"), Some("ru"));
+    }
+
+    #[test]
+    fn test_english_and_rust_terms() {
+        for query in [
+            "how does the borrow checker work",
+            "Vec vs slice",
+            "impl Trait for Box<dyn Error>",
+            "what is the difference between String and &str",
+            "E0502",
+            "",
+        ] {
+            assert_eq!(code(query), None, "{}", query);
+        }
+    }
+}
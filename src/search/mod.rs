@@ -1,8 +1,30 @@
+pub mod back_matter;
+pub mod concepts;
+pub mod cross_encoder;
+pub mod embedding_cache;
 pub mod embeddings;
+pub mod error_patterns;
 pub mod hybrid;
 mod index;
+pub mod intent;
+pub mod language;
+pub mod options;
+pub mod paths;
+pub mod pipeline;
+pub mod query;
+mod query_embedder;
+pub mod result_cache;
+pub mod session_pool;
+mod snippet;
+pub mod stats;
+pub mod synonyms;
+mod tokenizer;
 pub mod vector_index;
 
-pub use hybrid::{HybridSearch, SearchMode};
-pub use index::SearchIndex;
+pub use hybrid::{HybridSearch, SearchMode, SearchOutcome};
+pub use index::{SearchIndex, SearchResult};
+pub use intent::QueryIntent;
+pub use options::SearchOptions;
+pub use snippet::Highlight;
+pub use synonyms::Synonyms;
 pub use vector_index::VectorIndex;
@@ -0,0 +1,167 @@
+//! Per-query options shared by the keyword, semantic, and hybrid search paths.
+
+use crate::search::index::SearchResult;
+use crate::search::snippet::Highlight;
+
+/// Default snippet length in characters
+pub const DEFAULT_SNIPPET_LEN: usize = 200;
+
+/// Options controlling a single search
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// Maximum number of results to return
+    pub limit: usize,
+    /// Restrict results to these source IDs (all sources when `None`)
+    pub sources: Option<Vec<String>>,
+    /// Approximate snippet length in characters
+    pub snippet_len: usize,
+    /// Drop results whose normalized score (0 to 1) is below this value
+    pub min_score: Option<f32>,
+    /// Maximum number of results from any single source
+    pub max_per_source: Option<usize>,
+    /// Lowercase words no result may mention, e.g. `async` to steer `iterators` away from streams
+    pub exclude_terms: Vec<String>,
+    /// Ranking multipliers by source ID; they change the order of results, not their scores
+    pub source_boosts: Vec<(String, f32)>,
+    /// Reorder the top results of routed searches with the cross-encoder
+    pub rerank: bool,
+    /// Mark the query words in snippets
+    pub highlight: Option<Highlight>,
+}
+
+impl SearchOptions {
+    /// Create options returning up to `limit` results from all sources
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            sources: None,
+            snippet_len: DEFAULT_SNIPPET_LEN,
+            min_score: None,
+            max_per_source: None,
+            exclude_terms: Vec::new(),
+            source_boosts: Vec::new(),
+            rerank: false,
+            highlight: None,
+        }
+    }
+
+    /// Restrict results to the given source IDs
+    pub fn with_sources(mut self, sources: Option<&[&str]>) -> Self {
+        self.sources = sources.map(|s| s.iter().map(|id| id.to_string()).collect());
+        self
+    }
+
+    /// Set the approximate snippet length
+    pub fn with_snippet_len(mut self, snippet_len: usize) -> Self {
+        self.snippet_len = snippet_len;
+        self
+    }
+
+    /// Set the minimum normalized score
+    pub fn with_min_score(mut self, min_score: Option<f32>) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Set the maximum number of results per source; 0, like `None`, means no cap
+    pub fn with_max_per_source(mut self, max_per_source: Option<usize>) -> Self {
+        self.max_per_source = max_per_source.filter(|&cap| cap > 0);
+        self
+    }
+
+    /// Exclude results mentioning any of these words, in addition to those already excluded
+    pub fn with_exclude_terms(mut self, terms: &[String]) -> Self {
+        for term in terms {
+            let term = term.trim().to_lowercase();
+            if !term.is_empty() && !self.exclude_terms.contains(&term) {
+                self.exclude_terms.push(term);
+            }
+        }
+        self
+    }
+
+    /// Rank results from these sources higher, in addition to existing boosts
+    pub fn with_source_boosts(mut self, boosts: &[(String, f32)]) -> Self {
+        self.source_boosts.extend(boosts.iter().cloned());
+        self
+    }
+
+    /// Rerank routed searches with the cross-encoder, which must be loaded
+    pub fn with_rerank(mut self, rerank: bool) -> Self {
+        self.rerank = rerank;
+        self
+    }
+
+    /// Mark the query words in snippets with the given markers
+    pub fn with_highlight(mut self, highlight: Option<Highlight>) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Ranking multiplier of a source (1 when it isn't boosted)
+    pub fn source_boost(&self, source: &str) -> f32 {
+        self.source_boosts
+            .iter()
+            .filter(|(boosted, _)| boosted == source)
+            .map(|(_, boost)| boost)
+            .product()
+    }
+
+    /// Order results by boosted score, breaking ties by source and path like
+    /// `SearchResult::rank_cmp`
+    pub fn sort_by_boosted_score(&self, results: &mut [SearchResult]) {
+        let boosted = |result: &SearchResult| result.score * self.source_boost(&result.source);
+        results.sort_by(|a, b| {
+            boosted(b)
+                .total_cmp(&boosted(a))
+                .then_with(|| a.source.cmp(&b.source))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+
+    /// Number of candidates to fetch so per-source caps can be backfilled
+    pub fn candidate_limit(&self) -> usize {
+        if self.max_per_source.is_some() {
+            self.limit * 3
+        } else {
+            self.limit
+        }
+    }
+
+    /// Options for fetching candidates before per-source caps are applied
+    pub fn candidates(&self) -> SearchOptions {
+        SearchOptions {
+            limit: self.candidate_limit(),
+            ..self.clone()
+        }
+    }
+
+    /// Check whether a source passes the source filter
+    pub fn allows_source(&self, source: &str) -> bool {
+        self.sources
+            .as_ref()
+            .is_none_or(|sources| sources.iter().any(|s| s == source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_by_boosted_score_breaks_ties() {
+        let options = SearchOptions::new(5).with_source_boosts(&[("nomicon".to_string(), 2.0)]);
+        let mut results = vec![
+            SearchResult::for_test("rust-book", "b.md", 0.5),
+            SearchResult::for_test("rust-book", "a.md", 0.5),
+            SearchResult::for_test("async-book", "c.md", 0.5),
+            SearchResult::for_test("nomicon", "d.md", 0.3),
+            SearchResult::for_test("reference", "e.md", f32::NAN),
+        ];
+        options.sort_by_boosted_score(&mut results);
+
+        let order: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        // Positive NaN sorts above every score, as with `rank_cmp`
+        assert_eq!(order, vec!["e.md", "d.md", "c.md", "a.md", "b.md"]);
+    }
+}
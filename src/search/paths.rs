@@ -0,0 +1,75 @@
+//! Interned document paths.
+//!
+//! The search layer refers to documents by a `u32` id into a side table instead of cloning
+//! their path into every vector entry and fusion candidate. Paths are only turned back into
+//! strings for the results that are returned.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Id of an interned path
+pub type PathId = u32;
+
+/// Table of interned paths; each distinct path is stored once
+#[derive(Debug, Default)]
+pub struct PathTable {
+    ids: HashMap<Arc<str>, PathId>,
+    paths: Vec<Arc<str>>,
+}
+
+impl PathTable {
+    /// Id of a path, interning it if it is new
+    pub fn intern(&mut self, path: &str) -> PathId {
+        if let Some(&id) = self.ids.get(path) {
+            return id;
+        }
+        let id = self.paths.len() as PathId;
+        let path: Arc<str> = Arc::from(path);
+        self.paths.push(path.clone());
+        self.ids.insert(path, id);
+        id
+    }
+
+    /// Id of an already interned path
+    pub fn id(&self, path: &str) -> Option<PathId> {
+        self.ids.get(path).copied()
+    }
+
+    /// Path of an id returned by this table
+    pub fn resolve(&self, id: PathId) -> &str {
+        &self.paths[id as usize]
+    }
+
+    /// Number of distinct paths
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.paths.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_and_resolve() {
+        let mut table = PathTable::default();
+        let a = table.intern("ch04-01-what-is-ownership.md");
+        let b = table.intern("ch10-03-lifetime-syntax.md");
+
+        assert_ne!(a, b);
+        assert_eq!(table.intern("ch04-01-what-is-ownership.md"), a);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.id("ch10-03-lifetime-syntax.md"), Some(b));
+        assert_eq!(table.id("missing.md"), None);
+        assert_eq!(table.resolve(b), "ch10-03-lifetime-syntax.md");
+    }
+}
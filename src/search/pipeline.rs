@@ -0,0 +1,609 @@
+//! The ranking pipeline as pluggable stages.
+//!
+//! A search runs the keyword and semantic legs, then four kinds of stages:
+//!
+//! - **Candidate generators** add results from other corpora (the client's workspace, say),
+//!   merged with the documentation results by normalized score
+//! - the **fuser** combines the two legs of a hybrid search into one ranking
+//! - **filters** drop results before they are counted against the limit
+//! - **rerankers** reorder the results of routed searches (intent bias, back matter, compiler
+//!   error chapters)
+//!
+//! `Pipeline::default()` is the built-in ranking. The server builds its pipeline once at
+//! startup; other fusion strategies, rerankers, and filters are added by implementing the
+//! traits and registering them with the `with_*` methods, without touching `hybrid.rs`.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::error::Result;
+use crate::parsing::chunk_source_path;
+use crate::search::back_matter;
+use crate::search::error_patterns;
+use crate::search::hybrid::FusionDecision;
+use crate::search::index::{MatchExplanation, SearchIndex, SearchResult};
+use crate::search::intent::QueryIntent;
+use crate::search::options::SearchOptions;
+use crate::search::paths::PathId;
+use crate::search::vector_index::VectorIndex;
+
+/// RRF constant (standard value from the original paper)
+const RRF_K: f32 = 60.0;
+
+/// Minimum cosine similarity of the best semantic hit for the semantic leg to be trusted
+const SEMANTIC_CONFIDENCE_THRESHOLD: f32 = 0.3;
+
+/// Minimum keyword confidence for the keyword leg to be trusted
+const KEYWORD_CONFIDENCE_THRESHOLD: f32 = 0.2;
+
+/// Finds candidates in a corpus of its own
+pub trait CandidateGenerator: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Candidates for a query, best first, with scores normalized to `[0, 1]` and `options`
+    /// (sources, limit, per-source caps) applied
+    fn generate(&self, query: &str, options: &SearchOptions) -> Result<Vec<SearchResult>>;
+}
+
+/// The two legs of a hybrid search, each best first
+#[derive(Debug, Default)]
+pub struct Legs {
+    /// Keyword results with their BM25 scores
+    pub keyword: Vec<SearchResult>,
+    /// Semantic hits as `(path id, similarity)`, already restricted to the searched sources
+    pub semantic: Vec<(PathId, f32)>,
+    /// Keyword leg confidence in `[0, 1)`
+    pub keyword_confidence: f32,
+    /// Best semantic similarity
+    pub semantic_confidence: f32,
+}
+
+/// Combines the legs of a hybrid search
+pub trait Fuser: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Rank the candidates of both legs, best first. Scores are on the scale of the decision:
+    /// BM25 for `KeywordOnly`, similarity for `SemanticOnly`, and `[0, 1]` for `Fused`.
+    fn fuse(&self, legs: Legs, context: &FusionContext) -> (FusionDecision, Vec<SearchResult>);
+}
+
+/// Drops results a deployment never wants returned
+pub trait Filter: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Whether a result may be returned
+    fn keep(&self, result: &SearchResult, options: &SearchOptions) -> bool;
+}
+
+/// Reorders the results of a routed search
+pub trait Reranker: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Reorder results (best first) and, if it helps, replace their snippets. Scores are
+    /// normalized; the caller truncates to the limit afterwards.
+    fn rerank(&self, context: &RankingContext, results: &mut Vec<SearchResult>);
+}
+
+/// What a fuser can look up besides the legs
+pub struct FusionContext<'a> {
+    pub vector_index: &'a VectorIndex,
+    keyword_index: &'a SearchIndex,
+    pub options: &'a SearchOptions,
+}
+
+impl<'a> FusionContext<'a> {
+    pub fn new(
+        keyword_index: &'a SearchIndex,
+        vector_index: &'a VectorIndex,
+        options: &'a SearchOptions,
+    ) -> Self {
+        Self {
+            vector_index,
+            keyword_index,
+            options,
+        }
+    }
+
+    /// The result for a semantic hit, from the metadata stored with its vectors or, in indices
+    /// built without it, the keyword index's stored chunk; `None` when its source isn't
+    /// searched. Chunks missing from both get a minimal result when all sources are searched.
+    pub fn resolve(
+        &self,
+        id: PathId,
+        score: f32,
+        explanation: MatchExplanation,
+    ) -> Option<SearchResult> {
+        let key = self.vector_index.path(id);
+        let stored = match self.vector_index.info(id) {
+            Some(info) => Some(info.result(key, self.options.snippet_len)),
+            None => self
+                .keyword_index
+                .chunk_result(key, self.options.snippet_len)
+                .ok()
+                .flatten(),
+        };
+        if let Some(result) = stored {
+            return self
+                .options
+                .allows_source(&result.source)
+                .then_some(SearchResult {
+                    score,
+                    explanation,
+                    ..result
+                });
+        }
+        let (source, path) = chunk_source_path(key);
+        self.options.sources.is_none().then(|| SearchResult {
+            title: key.to_string(),
+            snippet: String::new(),
+            path: path.to_string(),
+            source: source.to_string(),
+            checklist_ids: Vec::new(),
+            score,
+            explanation,
+            section: None,
+        })
+    }
+
+    /// Semantic hits as results, scored by similarity and ranked
+    pub fn resolve_semantic(&self, hits: Vec<(PathId, f32)>) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = hits
+            .into_iter()
+            .filter_map(|(id, similarity)| {
+                self.resolve(id, similarity, MatchExplanation::semantic(similarity))
+            })
+            .collect();
+        results.sort_by(SearchResult::rank_cmp);
+        results
+    }
+}
+
+/// A routed search, as rerankers see it
+pub struct RankingContext<'a> {
+    /// The query as the user wrote it
+    pub query: &'a str,
+    pub intent: QueryIntent,
+    pub options: &'a SearchOptions,
+    /// The documentation index, for rerankers that need stored documents
+    pub keyword_index: &'a SearchIndex,
+}
+
+/// The configured stages of a search
+pub struct Pipeline {
+    generators: Vec<Box<dyn CandidateGenerator>>,
+    fuser: Box<dyn Fuser>,
+    filters: Vec<Box<dyn Filter>>,
+    rerankers: Vec<Box<dyn Reranker>>,
+}
+
+impl Default for Pipeline {
+    /// RRF fusion with confidence fallback, no extra generators or filters, and the intent,
+    /// back-matter, and compiler-error rerankers
+    fn default() -> Self {
+        Self {
+            generators: Vec::new(),
+            fuser: Box::new(RrfFuser::default()),
+            filters: Vec::new(),
+            rerankers: vec![
+                Box::new(IntentBias),
+                Box::new(BackMatterBoost),
+                Box::new(ErrorPatternBoost),
+            ],
+        }
+    }
+}
+
+impl Pipeline {
+    /// The default pipeline, shared by searches that weren't given one
+    pub fn built_in() -> &'static Pipeline {
+        static BUILT_IN: LazyLock<Pipeline> = LazyLock::new(Pipeline::default);
+        &BUILT_IN
+    }
+
+    /// Also search another corpus
+    pub fn with_generator(mut self, generator: impl CandidateGenerator + 'static) -> Self {
+        self.generators.push(Box::new(generator));
+        self
+    }
+
+    /// Combine hybrid legs with another strategy
+    pub fn with_fuser(mut self, fuser: impl Fuser + 'static) -> Self {
+        self.fuser = Box::new(fuser);
+        self
+    }
+
+    /// Drop results the filter rejects
+    pub fn with_filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Rerank routed searches after the rerankers already added
+    pub fn with_reranker(mut self, reranker: impl Reranker + 'static) -> Self {
+        self.rerankers.push(Box::new(reranker));
+        self
+    }
+
+    pub fn generators(&self) -> impl Iterator<Item = &dyn CandidateGenerator> {
+        self.generators.iter().map(|generator| generator.as_ref())
+    }
+
+    pub fn fuser(&self) -> &dyn Fuser {
+        self.fuser.as_ref()
+    }
+
+    /// Drop the results any filter rejects, keeping the order of the rest
+    pub fn filter(&self, results: &mut Vec<SearchResult>, options: &SearchOptions) {
+        if !self.filters.is_empty() {
+            results.retain(|result| self.filters.iter().all(|f| f.keep(result, options)));
+        }
+    }
+
+    /// Run the rerankers in order
+    pub fn rerank(&self, context: &RankingContext, results: &mut Vec<SearchResult>) {
+        for reranker in &self.rerankers {
+            reranker.rerank(context, results);
+        }
+    }
+
+    /// Stage names, for logs
+    pub fn describe(&self) -> String {
+        let names = |stages: Vec<&str>| {
+            if stages.is_empty() {
+                "none".to_string()
+            } else {
+                stages.join(", ")
+            }
+        };
+        format!(
+            "generators: {}; fuser: {}; filters: {}; rerankers: {}",
+            names(self.generators().map(|g| g.name()).collect()),
+            self.fuser.name(),
+            names(self.filters.iter().map(|f| f.name()).collect()),
+            names(self.rerankers.iter().map(|r| r.name()).collect()),
+        )
+    }
+}
+
+/// Reciprocal Rank Fusion, skipped when only one leg is confident: the confident leg is then
+/// returned alone, so hybrid never does worse than either leg
+#[derive(Debug, Clone, Copy)]
+pub struct RrfFuser {
+    /// Rank offset; larger values flatten the difference between top ranks
+    pub k: f32,
+}
+
+impl Default for RrfFuser {
+    fn default() -> Self {
+        Self { k: RRF_K }
+    }
+}
+
+impl Fuser for RrfFuser {
+    fn name(&self) -> &str {
+        "rrf"
+    }
+
+    fn fuse(&self, legs: Legs, context: &FusionContext) -> (FusionDecision, Vec<SearchResult>) {
+        let keyword_ok = legs.keyword_confidence >= KEYWORD_CONFIDENCE_THRESHOLD;
+        let semantic_ok = legs.semantic_confidence >= SEMANTIC_CONFIDENCE_THRESHOLD;
+
+        // Candidates are truncated to `limit` when the outcome is finalized, after per-source caps
+        if keyword_ok && !semantic_ok {
+            (FusionDecision::KeywordOnly, legs.keyword)
+        } else if semantic_ok && !keyword_ok {
+            let mut semantic = legs.semantic;
+            semantic.truncate(context.options.candidate_limit());
+            (
+                FusionDecision::SemanticOnly,
+                context.resolve_semantic(semantic),
+            )
+        } else {
+            (
+                FusionDecision::Fused,
+                self.rrf(legs.keyword, &legs.semantic, context),
+            )
+        }
+    }
+}
+
+impl RrfFuser {
+    /// Best possible score: ranked first by both legs
+    fn max_score(&self) -> f32 {
+        2.0 / (self.k + 1.0)
+    }
+
+    /// Fuse both legs by rank, normalized so a document ranked first by both scores 1.
+    ///
+    /// Candidates are keyed by the interned id of their chunk, so a keyword hit and a semantic hit
+    /// fuse when they found the same section; keyword hits without a vector get ids past the end
+    /// of the vector index's path table.
+    #[tracing::instrument(name = "fusion", level = "debug", skip_all)]
+    fn rrf(
+        &self,
+        keyword_results: Vec<SearchResult>,
+        semantic_results: &[(PathId, f32)],
+        context: &FusionContext,
+    ) -> Vec<SearchResult> {
+        let vector_index = context.vector_index;
+        // Map from path id to (RRF score, index into `keyword_results`, semantic similarity)
+        let mut scores: HashMap<PathId, (f32, Option<usize>, Option<f32>)> = HashMap::new();
+        let mut unembedded: HashMap<String, PathId> = HashMap::new();
+        let first_unembedded = vector_index.path_count() as PathId;
+
+        // Add keyword results with RRF scores
+        for (rank, result) in keyword_results.iter().enumerate() {
+            let key = result.chunk_key();
+            let id = vector_index.path_id(&key).unwrap_or_else(|| {
+                let next = first_unembedded + unembedded.len() as PathId;
+                *unembedded.entry(key).or_insert(next)
+            });
+            let rrf_score = 1.0 / (self.k + rank as f32 + 1.0);
+            scores
+                .entry(id)
+                .and_modify(|(s, _, _)| *s += rrf_score)
+                .or_insert((rrf_score, Some(rank), None));
+        }
+
+        // Add semantic results with RRF scores
+        for (rank, &(id, similarity)) in semantic_results.iter().enumerate() {
+            let rrf_score = 1.0 / (self.k + rank as f32 + 1.0);
+            scores
+                .entry(id)
+                .and_modify(|(s, _, sim)| {
+                    *s += rrf_score;
+                    *sim = Some(similarity);
+                })
+                .or_insert((rrf_score, None, Some(similarity)));
+        }
+
+        // Build final results, moving keyword results out instead of cloning them
+        let max_score = self.max_score();
+        let mut keyword_results: Vec<Option<SearchResult>> =
+            keyword_results.into_iter().map(Some).collect();
+        let mut results: Vec<SearchResult> = scores
+            .into_iter()
+            .filter_map(|(id, (rrf_score, keyword_rank, similarity))| {
+                let score = (rrf_score / max_score).min(1.0);
+                if let Some(mut result) = keyword_rank.and_then(|rank| keyword_results[rank].take())
+                {
+                    result.score = score;
+                    result.explanation.similarity = similarity;
+                    return Some(result);
+                }
+                // A semantic-only result
+                let explanation = MatchExplanation::semantic(similarity.unwrap_or_default());
+                context.resolve(id, score, explanation)
+            })
+            .collect();
+
+        // Sort by RRF score descending; ties are common and would otherwise follow HashMap order
+        results.sort_by(SearchResult::rank_cmp);
+
+        results
+    }
+}
+
+/// Ranks the sources suited to the query's intent higher
+pub struct IntentBias;
+
+impl Reranker for IntentBias {
+    fn name(&self) -> &str {
+        "intent"
+    }
+
+    fn rerank(&self, context: &RankingContext, results: &mut Vec<SearchResult>) {
+        context
+            .intent
+            .apply_bias(context.query, results, context.options);
+    }
+}
+
+/// Answers a question about one keyword, operator, or glossary term with the back matter
+/// defining it
+pub struct BackMatterBoost;
+
+impl Reranker for BackMatterBoost {
+    fn name(&self) -> &str {
+        "back_matter"
+    }
+
+    fn rerank(&self, context: &RankingContext, results: &mut Vec<SearchResult>) {
+        let Some(term) = back_matter::exact_term(context.query) else {
+            return;
+        };
+        back_matter::apply_boost(&term, results, context.options.snippet_len, |result| {
+            let document = context
+                .keyword_index
+                .get_document(&result.source, &result.path);
+            Some(document.ok()??.markdown)
+        });
+    }
+}
+
+/// Moves the chapters explaining a compiler error mentioned in the query to the front
+pub struct ErrorPatternBoost;
+
+impl Reranker for ErrorPatternBoost {
+    fn name(&self) -> &str {
+        "error_patterns"
+    }
+
+    fn rerank(&self, context: &RankingContext, results: &mut Vec<SearchResult>) {
+        let patterns = error_patterns::matching_patterns(context.query);
+        error_patterns::apply_boost(&patterns, results);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::vector_index::ChunkInfo;
+
+    #[test]
+    fn test_rrf_fusion_merges_by_path_id() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let mut vector_index = VectorIndex::new();
+        vector_index
+            .add("rust-book/b.md".to_string(), vec![1.0, 0.0])
+            .unwrap();
+        vector_index
+            .add("rust-book/c.md".to_string(), vec![0.0, 1.0])
+            .unwrap();
+        let options = SearchOptions::new(5);
+        let context = FusionContext::new(&keyword_index, &vector_index, &options);
+
+        let b = vector_index.path_id("rust-book/b.md").unwrap();
+        let c = vector_index.path_id("rust-book/c.md").unwrap();
+        let fused = RrfFuser::default().rrf(
+            vec![
+                SearchResult::for_test("rust-book", "b.md", 3.0),
+                SearchResult::for_test("rust-book", "a.md", 2.0),
+            ],
+            &[(b, 0.9), (c, 0.8)],
+            &context,
+        );
+
+        let paths: Vec<&str> = fused.iter().map(|r| r.path.as_str()).collect();
+        // b.md is ranked first by both legs; a.md (no vector) and c.md (no keyword hit) by one each
+        assert_eq!(paths[0], "b.md");
+        assert_eq!(fused.len(), 3);
+        assert!(paths.contains(&"a.md") && paths.contains(&"c.md"));
+
+        // Scores are normalized: first in both legs is 1, second in one leg about a quarter
+        assert!((fused[0].score - 1.0).abs() < 1e-6);
+        assert!(fused[1].score < 0.5);
+
+        // Both legs' evidence is kept for explanations
+        assert_eq!(fused[0].explanation.similarity, Some(0.9));
+        let c_result = fused.iter().find(|r| r.path == "c.md").unwrap();
+        assert_eq!(
+            c_result.explanation.describe(),
+            "semantic-only match, similarity 0.80"
+        );
+    }
+
+    #[test]
+    fn test_resolve_uses_stored_chunk_info() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let mut vector_index = VectorIndex::new();
+        let info = ChunkInfo {
+            title: "Traits".to_string(),
+            source: "rust-book".to_string(),
+            section: None,
+            checklist_ids: vec!["C-TRAIT".to_string()],
+            snippet: "A trait defines shared behavior.".to_string(),
+        };
+        vector_index
+            .add_chunk(
+                "rust-book/traits.md".to_string(),
+                vec![1.0, 0.0],
+                vec![0.0, 1.0],
+                info,
+            )
+            .unwrap();
+        let id = vector_index.path_id("rust-book/traits.md").unwrap();
+
+        // The keyword index is empty; the result comes from the vector index alone
+        let options = SearchOptions::new(5);
+        let context = FusionContext::new(&keyword_index, &vector_index, &options);
+        let result = context
+            .resolve(id, 0.9, MatchExplanation::semantic(0.9))
+            .unwrap();
+        assert_eq!(
+            (result.title.as_str(), result.path.as_str()),
+            ("Traits", "traits.md")
+        );
+        assert_eq!(result.snippet, "A trait defines shared behavior.");
+        assert_eq!(result.checklist_ids, vec!["C-TRAIT"]);
+
+        let options = SearchOptions::new(5).with_sources(Some(&["nomicon"]));
+        let context = FusionContext::new(&keyword_index, &vector_index, &options);
+        assert!(
+            context
+                .resolve(id, 0.9, MatchExplanation::semantic(0.9))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_confidence_fallback() {
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let vector_index = VectorIndex::new();
+        let options = SearchOptions::new(5);
+        let context = FusionContext::new(&keyword_index, &vector_index, &options);
+        let legs = |keyword_confidence, semantic_confidence| Legs {
+            keyword: vec![SearchResult::for_test("rust-book", "a.md", 12.0)],
+            semantic: Vec::new(),
+            keyword_confidence,
+            semantic_confidence,
+        };
+
+        let fuser = RrfFuser::default();
+        let (decision, results) = fuser.fuse(legs(0.7, 0.1), &context);
+        assert_eq!(decision, FusionDecision::KeywordOnly);
+        assert_eq!(results[0].score, 12.0);
+        let (decision, _) = fuser.fuse(legs(0.7, 0.6), &context);
+        assert_eq!(decision, FusionDecision::Fused);
+        let (decision, results) = fuser.fuse(legs(0.1, 0.6), &context);
+        assert_eq!(decision, FusionDecision::SemanticOnly);
+        assert!(results.is_empty());
+    }
+
+    struct NoSource(&'static str);
+
+    impl Filter for NoSource {
+        fn name(&self) -> &str {
+            "no_source"
+        }
+
+        fn keep(&self, result: &SearchResult, _options: &SearchOptions) -> bool {
+            result.source != self.0
+        }
+    }
+
+    struct Reverse;
+
+    impl Reranker for Reverse {
+        fn name(&self) -> &str {
+            "reverse"
+        }
+
+        fn rerank(&self, _context: &RankingContext, results: &mut Vec<SearchResult>) {
+            results.reverse();
+        }
+    }
+
+    #[test]
+    fn test_custom_stages() {
+        let pipeline = Pipeline::default()
+            .with_filter(NoSource("rustonomicon"))
+            .with_reranker(Reverse);
+        assert_eq!(
+            pipeline.describe(),
+            "generators: none; fuser: rrf; filters: no_source; rerankers: intent, back_matter, error_patterns, reverse"
+        );
+
+        let options = SearchOptions::new(5);
+        let mut results = vec![
+            SearchResult::for_test("rust-book", "a.md", 0.9),
+            SearchResult::for_test("rustonomicon", "b.md", 0.8),
+            SearchResult::for_test("rust-book", "c.md", 0.7),
+        ];
+        pipeline.filter(&mut results, &options);
+        let keyword_index = SearchIndex::in_memory().unwrap();
+        let context = RankingContext {
+            query: "ownership",
+            intent: QueryIntent::General,
+            options: &options,
+            keyword_index: &keyword_index,
+        };
+        pipeline.rerank(&context, &mut results);
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["c.md", "a.md"]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Query normalization for the keyword index.
+//!
+//! Users type Rust paths and code (`std::mem::swap`, `Box<dyn Fn()>`, `x^2`) that collide with
+//! tantivy's query syntax. Queries are trimmed and capped, and reserved characters are escaped so
+//! they are searched as text; balanced double quotes still make phrases and `+word`/`-word` still
+//! require or exclude a word. Excluded words are also split off for the semantic leg.
+
+use crate::error::{Error, Result};
+
+/// Longest query searched, in characters; longer queries are cut at a word boundary
+pub const MAX_QUERY_CHARS: usize = 500;
+
+/// Characters escaped wherever they appear
+const RESERVED: &[char] = &['^', '`', ':', '{', '}', '\'', '[', ']', '(', ')', '\\'];
+
+/// Characters escaped at the start of a word, where they are operators
+const LEADING_RESERVED: &[char] = &['+', '-', '!', '*'];
+
+/// Trim a query, rejecting empty ones and cutting long ones to `MAX_QUERY_CHARS`
+pub fn normalize(query: &str) -> Result<&str> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(Error::Other(
+            "Query is empty. Describe what you are looking for, e.g. 'borrow checker' or 'Vec::with_capacity'."
+                .to_string(),
+        ));
+    }
+
+    let Some((cut, _)) = query.char_indices().nth(MAX_QUERY_CHARS) else {
+        return Ok(query);
+    };
+    let end = query[..cut].rfind(char::is_whitespace).unwrap_or(cut);
+    tracing::debug!(
+        "Query longer than {} characters, searching its start only",
+        MAX_QUERY_CHARS
+    );
+    Ok(query[..end].trim_end())
+}
+
+/// Split `-word` exclusions off a query: the rest of the query, and the excluded words lowercased.
+///
+/// Words inside a quoted phrase are left alone. The keyword index understands `-word` itself;
+/// the split is for the semantic leg, which embeds only the rest and filters out hits that
+/// mention an excluded word.
+pub fn split_exclusions(query: &str) -> (String, Vec<String>) {
+    let mut kept = Vec::new();
+    let mut excluded: Vec<String> = Vec::new();
+    let mut in_phrase = false;
+    for word in query.split_whitespace() {
+        let term = word.strip_prefix('-').filter(|term| {
+            !in_phrase && term.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+        });
+        match term {
+            Some(term) => {
+                let term = term.to_lowercase();
+                if !excluded.contains(&term) {
+                    excluded.push(term);
+                }
+            }
+            None => kept.push(word),
+        }
+        if word.matches('"').count() % 2 == 1 {
+            in_phrase = !in_phrase;
+        }
+    }
+    (kept.join(" "), excluded)
+}
+
+/// Escape tantivy query syntax so the query is searched as text.
+///
+/// An unbalanced double quote is dropped rather than opening a phrase that never ends. A
+/// leading `+` or `-` is kept as an operator only when a word follows it (`-unsafe`, not `->`).
+pub fn escape(query: &str) -> String {
+    let unbalanced_quote = if query.matches('"').count() % 2 == 1 {
+        query.rfind('"')
+    } else {
+        None
+    };
+
+    let mut escaped = String::with_capacity(query.len() + 8);
+    let mut word_start = true;
+    let mut chars = query.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, next)| next);
+        let operator = matches!(c, '+' | '-')
+            && next.is_some_and(|next| next.is_alphanumeric() || next == '_' || next == '"');
+
+        if Some(i) == unbalanced_quote {
+            // Dropped
+        } else if RESERVED.contains(&c)
+            || (word_start && LEADING_RESERVED.contains(&c) && !operator)
+        {
+            escaped.push('\\');
+            escaped.push(c);
+        } else {
+            escaped.push(c);
+        }
+        word_start = c.is_whitespace() || (c == '"' && Some(i) != unbalanced_quote);
+    }
+
+    // Boolean keywords are searched as words; a dangling `NOT` or `OR` is a parse error
+    escaped
+        .split(' ')
+        .map(|word| match word {
+            "AND" | "OR" | "NOT" | "IN" => word.to_lowercase(),
+            _ => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_rejects_empty_and_caps_length() {
+        assert!(normalize("   \n").is_err());
+        assert_eq!(normalize("  borrow checker ").unwrap(), "borrow checker");
+
+        let long = "ownership ".repeat(100);
+        let normalized = normalize(&long).unwrap();
+        assert!(normalized.chars().count() <= MAX_QUERY_CHARS);
+        assert!(normalized.ends_with("ownership"));
+    }
+
+    #[test]
+    fn test_escape_reserved_syntax() {
+        assert_eq!(escape("std::mem::swap"), "std\\:\\:mem\\:\\:swap");
+        assert_eq!(escape("Box<dyn Fn()>"), "Box<dyn Fn\\(\\)>");
+        assert_eq!(escape("x^2 [i]"), "x\\^2 \\[i\\]");
+        assert_eq!(
+            escape("\"ref cell\" -unsafe +borrow"),
+            "\"ref cell\" -unsafe +borrow"
+        );
+        assert_eq!(escape("-> operator"), "\\-> operator");
+        assert_eq!(escape("\"unclosed phrase"), "unclosed phrase");
+        assert_eq!(escape("lifetimes AND"), "lifetimes and");
+    }
+
+    #[test]
+    fn test_split_exclusions() {
+        let (rest, excluded) = split_exclusions("iterators -Async -async -> \"-not excluded\"");
+        assert_eq!(rest, "iterators -> \"-not excluded\"");
+        assert_eq!(excluded, ["async"]);
+        assert_eq!(
+            split_exclusions("borrow checker"),
+            ("borrow checker".to_string(), vec![])
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Query embeddings computed alongside the keyword leg of a hybrid search.
+//!
+//! A fixed set of worker threads takes queries from a bounded queue, so the number of threads
+//! doesn't grow with the number of concurrent searches. A search that stops waiting drops its
+//! `PendingEmbedding`: a worker that hasn't started on the query skips it, and one already
+//! embedding it (the model can't be interrupted) drops the result. When the queue is full, the
+//! query isn't embedded at all and the search answers from its keyword leg.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::error::{Error, Result};
+
+/// Queries waiting for a worker; more concurrent searches than this skip their semantic leg
+pub(crate) const QUEUE_LEN: usize = 64;
+
+struct Job {
+    text: String,
+    sender: Sender<Result<Vec<f32>>>,
+    abandoned: Arc<AtomicBool>,
+}
+
+/// Worker threads embedding queued queries
+pub(crate) struct QueryEmbedder {
+    queue: SyncSender<Job>,
+}
+
+impl QueryEmbedder {
+    /// `workers` threads embedding up to `queue_len` queued queries with `embed`; the threads
+    /// stop when the embedder is dropped
+    pub(crate) fn new(
+        workers: usize,
+        queue_len: usize,
+        embed: impl Fn(&str) -> Result<Vec<f32>> + Send + Sync + 'static,
+    ) -> Self {
+        let (queue, jobs) = mpsc::sync_channel::<Job>(queue_len);
+        let jobs = Arc::new(Mutex::new(jobs));
+        let embed = Arc::new(embed);
+        for worker in 0..workers {
+            let jobs = jobs.clone();
+            let embed = embed.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("query-embedding-{}", worker))
+                .spawn(move || {
+                    loop {
+                        // Hold the lock only while taking a job, not while embedding it
+                        let job = jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                        let Ok(job) = job else {
+                            return;
+                        };
+                        if job.abandoned.load(Ordering::Acquire) {
+                            continue;
+                        }
+                        // The receiver is gone if the search gave up waiting
+                        let _ = job.sender.send(embed(&job.text));
+                    }
+                });
+            if let Err(e) = spawned {
+                tracing::warn!("Failed to start a query embedding thread: {}", e);
+            }
+        }
+        Self { queue }
+    }
+
+    /// Queue `text` to be embedded
+    pub(crate) fn embed(&self, text: String) -> PendingEmbedding {
+        let (sender, receiver) = mpsc::channel();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let job = Job {
+            text,
+            sender,
+            abandoned: abandoned.clone(),
+        };
+        match self.queue.try_send(job) {
+            Ok(()) => PendingEmbedding {
+                receiver: Some(receiver),
+                abandoned,
+            },
+            Err(TrySendError::Full(_)) => {
+                tracing::debug!("Query embedding queue is full, skipping the semantic leg");
+                PendingEmbedding::skipped()
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::warn!("No query embedding threads are running");
+                PendingEmbedding::skipped()
+            }
+        }
+    }
+}
+
+/// A query embedding that is being computed, or is ready
+pub(crate) struct PendingEmbedding {
+    /// `None` when the query was not queued
+    receiver: Option<Receiver<Result<Vec<f32>>>>,
+    abandoned: Arc<AtomicBool>,
+}
+
+impl PendingEmbedding {
+    /// An embedding that is already known, e.g. precomputed
+    pub(crate) fn ready(embedding: Vec<f32>) -> Self {
+        Self::finished(Ok(embedding))
+    }
+
+    /// An embedding that can't be computed
+    pub(crate) fn failed(error: Error) -> Self {
+        Self::finished(Err(error))
+    }
+
+    fn finished(result: Result<Vec<f32>>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        // The receiver is alive, so this can't fail
+        let _ = sender.send(result);
+        Self {
+            receiver: Some(receiver),
+            abandoned: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn skipped() -> Self {
+        Self {
+            receiver: None,
+            abandoned: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Wait for the embedding until `deadline`; `None` if it isn't ready by then or the query
+    /// wasn't queued. Blocks the thread, so searches run on the blocking pool rather than on an
+    /// async worker.
+    pub(crate) fn wait(&self, deadline: Option<Instant>) -> Option<Result<Vec<f32>>> {
+        let receiver = self.receiver.as_ref()?;
+        let received = match deadline {
+            Some(deadline) => {
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Err(RecvTimeoutError::Timeout) => return None,
+                    received => received.map_err(|_| ()),
+                }
+            }
+            None => receiver.recv().map_err(|_| ()),
+        };
+        Some(received.unwrap_or_else(|_| {
+            Err(Error::Other(
+                "Query embedding thread stopped without a result".to_string(),
+            ))
+        }))
+    }
+}
+
+impl Drop for PendingEmbedding {
+    fn drop(&mut self) {
+        self.abandoned.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An embedder whose single worker reports each query it starts on and finishes it only
+    /// when released
+    fn gated_embedder(queue_len: usize) -> (QueryEmbedder, Receiver<String>, Sender<()>) {
+        let (started, starts) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let embedder = QueryEmbedder::new(1, queue_len, move |text| {
+            let _ = started.send(text.to_string());
+            released.lock().unwrap().recv().unwrap();
+            Ok(vec![text.len() as f32])
+        });
+        (embedder, starts, release)
+    }
+
+    #[test]
+    fn test_wait_respects_deadline() {
+        let (embedder, starts, release) = gated_embedder(4);
+        let pending = embedder.embed("borrow".to_string());
+        assert_eq!(starts.recv().unwrap(), "borrow");
+        assert!(pending.wait(Some(Instant::now())).is_none());
+        release.send(()).unwrap();
+        assert_eq!(pending.wait(None).unwrap().unwrap(), vec![6.0]);
+
+        let ready = PendingEmbedding::ready(vec![1.0, 0.0]);
+        assert_eq!(
+            ready.wait(Some(Instant::now())).unwrap().unwrap(),
+            vec![1.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_bounded_queue_and_abandoned_queries() {
+        // The worker is busy with the first query and two are queued: the fourth doesn't fit
+        let (embedder, starts, release) = gated_embedder(2);
+        let first = embedder.embed("a".to_string());
+        assert_eq!(starts.recv().unwrap(), "a");
+        let second = embedder.embed("bb".to_string());
+        let third = embedder.embed("ccc".to_string());
+        let fourth = embedder.embed("dddd".to_string());
+        assert!(fourth.wait(None).is_none());
+
+        // The abandoned second query is skipped, so the worker goes on with the third
+        drop(second);
+        release.send(()).unwrap();
+        assert_eq!(first.wait(None).unwrap().unwrap(), vec![1.0]);
+        assert_eq!(starts.recv().unwrap(), "ccc");
+        release.send(()).unwrap();
+        assert_eq!(third.wait(None).unwrap().unwrap(), vec![3.0]);
+    }
+}
@@ -0,0 +1,357 @@
+//! Cache of recent search outcomes.
+//!
+//! Agents often repeat a query within a session, or send it again with different spacing. A
+//! repeated hybrid search costs a query embedding and two index searches; a cached one is a
+//! map lookup. Outcomes are keyed by the query with its whitespace collapsed, the mode, the
+//! kind of search, and every search option, so a search with any other option runs again.
+//!
+//! The cache knows nothing about the indices: whoever swaps or re-indexes them clears it.
+//! Clearing starts a new generation, and a search only caches its outcome if no clear happened
+//! while it ran, so a search over the old indices can't repopulate the cache afterwards.
+//! Outcomes are keyed by the mode that produced them, so a keyword-only outcome from before
+//! the vectors existed isn't served for a hybrid search.
+//! `HybridSearch` doesn't use it for searches that include a client's workspace documentation.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::search::hybrid::{SearchMode, SearchOutcome};
+use crate::search::intent::QueryIntent;
+use crate::search::options::SearchOptions;
+
+/// Outcomes cached when the configuration doesn't say
+pub const DEFAULT_CACHE_SIZE: usize = 256;
+
+/// How long an outcome is served from the cache when the configuration doesn't say
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Which search produced an outcome; a routed search ranks differently than a plain one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SearchKind {
+    Plain,
+    Routed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    kind: SearchKind,
+    query: String,
+    mode: SearchMode,
+    /// Every option, formatted; `SearchOptions` holds floats, so it can't be hashed itself
+    options: String,
+}
+
+impl CacheKey {
+    fn new(kind: SearchKind, query: &str, mode: SearchMode, options: &SearchOptions) -> Self {
+        Self {
+            kind,
+            query: query.split_whitespace().collect::<Vec<_>>().join(" "),
+            mode,
+            options: format!("{:?}", options),
+        }
+    }
+}
+
+struct CacheEntry {
+    intent: Option<QueryIntent>,
+    outcome: SearchOutcome,
+    cached_at: Instant,
+    /// Tick of the last lookup or insertion, for evicting the least recently used entry
+    used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    tick: u64,
+    /// Number of clears so far
+    generation: u64,
+}
+
+/// Least recently used search outcomes, each served for up to a time to live
+pub struct ResultCache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_SIZE, DEFAULT_CACHE_TTL)
+    }
+}
+
+impl ResultCache {
+    /// A cache of up to `capacity` outcomes (none when 0), each kept for `ttl`
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// The cached outcome of a search, if it is still fresh
+    pub fn get(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Option<SearchOutcome> {
+        self.lookup(CacheKey::new(SearchKind::Plain, query, mode, options))
+            .map(|(_, outcome)| outcome)
+    }
+
+    /// Cache the outcome of a search that started in `generation`, keyed by the mode it ran in
+    pub fn insert(
+        &self,
+        generation: u64,
+        query: &str,
+        options: &SearchOptions,
+        outcome: &SearchOutcome,
+    ) {
+        let key = CacheKey::new(SearchKind::Plain, query, outcome.mode, options);
+        self.store(generation, key, None, outcome);
+    }
+
+    /// The cached intent and outcome of a routed search, if it is still fresh
+    pub fn get_routed(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> Option<(QueryIntent, SearchOutcome)> {
+        let (intent, outcome) =
+            self.lookup(CacheKey::new(SearchKind::Routed, query, mode, options))?;
+        Some((intent?, outcome))
+    }
+
+    /// Cache the intent and outcome of a routed search that started in `generation`
+    pub fn insert_routed(
+        &self,
+        generation: u64,
+        query: &str,
+        options: &SearchOptions,
+        intent: QueryIntent,
+        outcome: &SearchOutcome,
+    ) {
+        let key = CacheKey::new(SearchKind::Routed, query, outcome.mode, options);
+        self.store(generation, key, Some(intent), outcome);
+    }
+
+    /// The current generation, read before a search whose outcome will be inserted
+    pub fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+
+    /// Forget every outcome and start a new generation, after the indices changed
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.generation += 1;
+    }
+
+    /// Number of cached outcomes, fresh or not
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lookup(&self, key: CacheKey) -> Option<(Option<QueryIntent>, SearchOutcome)> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(&key)?;
+        if entry.cached_at.elapsed() > self.ttl {
+            state.entries.remove(&key);
+            return None;
+        }
+        entry.used = tick;
+        Some((entry.intent, entry.outcome.clone()))
+    }
+
+    fn store(
+        &self,
+        generation: u64,
+        key: CacheKey,
+        intent: Option<QueryIntent>,
+        outcome: &SearchOutcome,
+    ) {
+        // A search cut short by its semantic budget would be served cut short again
+        if self.capacity == 0 || outcome.partial {
+            return;
+        }
+        let mut state = self.lock();
+        // The indices changed while the search ran
+        if state.generation != generation {
+            return;
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        if state.entries.len() >= self.capacity && !state.entries.contains_key(&key) {
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, entry| entry.cached_at.elapsed() <= ttl);
+            if state.entries.len() >= self.capacity
+                && let Some(oldest) = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used)
+                    .map(|(key, _)| key.clone())
+            {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(
+            key,
+            CacheEntry {
+                intent,
+                outcome: outcome.clone(),
+                cached_at: Instant::now(),
+                used: tick,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::hybrid::FusionDecision;
+
+    fn outcome(paths: &[&str]) -> SearchOutcome {
+        SearchOutcome {
+            results: paths
+                .iter()
+                .map(|path| crate::search::SearchResult::for_test("rust-book", path, 0.5))
+                .collect(),
+            mode: SearchMode::Hybrid,
+            decision: FusionDecision::Fused,
+            total_hits: Some(paths.len()),
+            keyword_confidence: None,
+            semantic_confidence: None,
+            partial: false,
+            reranked: false,
+        }
+    }
+
+    #[test]
+    fn test_cache_keys_and_eviction() {
+        let cache = ResultCache::new(2, DEFAULT_CACHE_TTL);
+        let options = SearchOptions::new(5);
+        cache.insert(0, "borrow checker", &options, &outcome(&["a.md"]));
+
+        // Spacing doesn't matter; the mode, the options, and the kind of search do
+        let cached = cache.get("  borrow   checker ", SearchMode::Hybrid, &options);
+        assert_eq!(cached.unwrap().results[0].path, "a.md");
+        assert!(
+            cache
+                .get("borrow checker", SearchMode::Keyword, &options)
+                .is_none()
+        );
+        let narrowed = options.clone().with_sources(Some(&["nomicon"]));
+        assert!(
+            cache
+                .get("borrow checker", SearchMode::Hybrid, &narrowed)
+                .is_none()
+        );
+        assert!(
+            cache
+                .get_routed("borrow checker", SearchMode::Hybrid, &options)
+                .is_none()
+        );
+
+        // The least recently used outcome makes room
+        cache.insert_routed(
+            0,
+            "lifetimes",
+            &options,
+            QueryIntent::Concept,
+            &outcome(&["b.md"]),
+        );
+        cache.get("borrow checker", SearchMode::Hybrid, &options);
+        cache.insert(0, "traits", &options, &outcome(&["c.md"]));
+        assert_eq!(cache.len(), 2);
+        assert!(
+            cache
+                .get_routed("lifetimes", SearchMode::Hybrid, &options)
+                .is_none()
+        );
+        assert!(
+            cache
+                .get("borrow checker", SearchMode::Hybrid, &options)
+                .is_some()
+        );
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_stale_partial_and_disabled() {
+        let options = SearchOptions::new(5);
+        let expired = ResultCache::new(4, Duration::ZERO);
+        expired.insert(0, "traits", &options, &outcome(&["a.md"]));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(
+            expired
+                .get("traits", SearchMode::Hybrid, &options)
+                .is_none()
+        );
+
+        let cache = ResultCache::default();
+        let mut partial = outcome(&["a.md"]);
+        partial.partial = true;
+        cache.insert(0, "traits", &options, &partial);
+        assert!(cache.is_empty());
+
+        let disabled = ResultCache::new(0, DEFAULT_CACHE_TTL);
+        disabled.insert(0, "traits", &options, &outcome(&["a.md"]));
+        assert!(
+            disabled
+                .get("traits", SearchMode::Hybrid, &options)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_generations_and_fallback_mode() {
+        let cache = ResultCache::default();
+        let options = SearchOptions::new(5);
+
+        // A search that was running when the indices changed doesn't repopulate the cache
+        let generation = cache.generation();
+        cache.clear();
+        cache.insert(generation, "traits", &options, &outcome(&["a.md"]));
+        assert!(cache.is_empty());
+        cache.insert(cache.generation(), "traits", &options, &outcome(&["a.md"]));
+        assert!(cache.get("traits", SearchMode::Hybrid, &options).is_some());
+
+        // A hybrid search that fell back to keyword search is only served as a keyword one
+        let mut fallback = outcome(&["b.md"]);
+        fallback.mode = SearchMode::Keyword;
+        cache.insert(cache.generation(), "lifetimes", &options, &fallback);
+        assert!(
+            cache
+                .get("lifetimes", SearchMode::Hybrid, &options)
+                .is_none()
+        );
+        assert!(
+            cache
+                .get("lifetimes", SearchMode::Keyword, &options)
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! A pool of model sessions for concurrent inference.
+//!
+//! An ONNX session runs one inference at a time, so with a single session every search waits
+//! for the embeddings of the searches before it. A pool hands each caller an idle session,
+//! opening another one (up to a maximum) when all are busy and waiting for one to be returned
+//! after that. Sessions are opened on demand: a server that never sees concurrent searches
+//! keeps one session's memory.
+
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use crate::error::{Error, Result};
+
+/// Opens a session of the pooled model
+type Open<T> = Box<dyn Fn() -> Result<T> + Send + Sync>;
+
+/// Sessions of one model
+pub struct SessionPool<T> {
+    open: Open<T>,
+    state: Mutex<PoolState<T>>,
+    /// Signalled when a session is returned
+    returned: Condvar,
+}
+
+struct PoolState<T> {
+    idle: Vec<T>,
+    /// Sessions open, idle or in use
+    open: usize,
+    /// Most sessions to open; lowered when opening one fails
+    max: usize,
+}
+
+impl<T> SessionPool<T> {
+    /// A pool of up to `max` sessions opened by `open`, starting with one so that a model that
+    /// can't be loaded is an error here rather than on first use
+    pub fn new(max: usize, open: impl Fn() -> Result<T> + Send + Sync + 'static) -> Result<Self> {
+        let first = open()?;
+        Ok(Self {
+            open: Box::new(open),
+            state: Mutex::new(PoolState {
+                idle: vec![first],
+                open: 1,
+                max: max.max(1),
+            }),
+            returned: Condvar::new(),
+        })
+    }
+
+    /// Run `f` on an idle session, opening or waiting for one when all are busy
+    pub fn with_session<R>(&self, f: impl FnOnce(&mut T) -> Result<R>) -> Result<R> {
+        let mut session = self.acquire()?;
+        f(session
+            .session
+            .as_mut()
+            .expect("session is held until dropped"))
+    }
+
+    /// Sessions open, idle or in use
+    pub fn open_sessions(&self) -> usize {
+        self.lock().open
+    }
+
+    fn acquire(&self) -> Result<PooledSession<'_, T>> {
+        let mut state = self.lock();
+        loop {
+            if let Some(session) = state.idle.pop() {
+                return Ok(PooledSession {
+                    pool: self,
+                    session: Some(session),
+                });
+            }
+            if state.open < state.max {
+                state.open += 1;
+                drop(state);
+                // Opening a session takes a while; others keep using the pool meanwhile
+                match (self.open)() {
+                    Ok(session) => {
+                        return Ok(PooledSession {
+                            pool: self,
+                            session: Some(session),
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to open another model session: {}", e);
+                        state = self.lock();
+                        state.open -= 1;
+                        state.max = state.open;
+                        if state.open == 0 {
+                            return Err(e);
+                        }
+                        continue;
+                    }
+                }
+            }
+            state = self
+                .returned
+                .wait(state)
+                .map_err(|e| Error::Other(format!("Failed to wait for a model session: {}", e)))?;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A session taken from the pool, returned to it when dropped (also when inference panics)
+struct PooledSession<'a, T> {
+    pool: &'a SessionPool<T>,
+    session: Option<T>,
+}
+
+impl<T> Drop for PooledSession<'_, T> {
+    fn drop(&mut self) {
+        if let Some(session) = self.session.take() {
+            self.pool.lock().idle.push(session);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_sessions_are_opened_on_demand_up_to_the_maximum() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+        let pool = Arc::new(
+            SessionPool::new(2, move || Ok(counter.fetch_add(1, Ordering::SeqCst))).unwrap(),
+        );
+        assert_eq!(pool.with_session(|session| Ok(*session)).unwrap(), 0);
+        assert_eq!(pool.open_sessions(), 1);
+
+        // While a caller holds the first session, the next one opens a second
+        let (holding, held) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        let holder = {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                pool.with_session(|session| {
+                    holding.send(*session).unwrap();
+                    released.recv().unwrap();
+                    Ok(())
+                })
+            })
+        };
+        assert_eq!(held.recv().unwrap(), 0);
+        assert_eq!(pool.with_session(|session| Ok(*session)).unwrap(), 1);
+        assert_eq!(pool.open_sessions(), 2);
+        release.send(()).unwrap();
+        holder.join().unwrap().unwrap();
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_failed_open_waits_for_an_open_session() {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+        let pool = Arc::new(
+            SessionPool::new(3, move || match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => Ok("first"),
+                _ => Err(Error::Other("out of memory".to_string())),
+            })
+            .unwrap(),
+        );
+
+        // Errors of the caller's closure pass through, and the session goes back to the pool
+        assert!(
+            pool.with_session(|_| Err::<(), _>(Error::Other("bad input".to_string())))
+                .is_err()
+        );
+
+        // The holder keeps the only session until the other caller failed to open one
+        let (holding, held) = mpsc::channel();
+        let holder = {
+            let (pool, opened) = (pool.clone(), opened.clone());
+            std::thread::spawn(move || {
+                pool.with_session(|_| {
+                    holding.send(()).unwrap();
+                    while opened.load(Ordering::SeqCst) < 2 {
+                        std::thread::yield_now();
+                    }
+                    Ok(())
+                })
+            })
+        };
+        held.recv().unwrap();
+        assert_eq!(pool.with_session(|session| Ok(*session)).unwrap(), "first");
+        holder.join().unwrap().unwrap();
+        assert_eq!(pool.open_sessions(), 1);
+
+        // The pool stops trying to open more sessions
+        assert_eq!(pool.with_session(|session| Ok(*session)).unwrap(), "first");
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+        assert!(SessionPool::<()>::new(2, || Err(Error::Other("missing".to_string()))).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+//! Markdown snippets for search results.
+//!
+//! Snippets are cut from a document's re-synthesized markdown rather than its plain text, so
+//! inline code keeps its backticks and code blocks keep their fences. A snippet starts at the
+//! first block (paragraph, heading, list, or code block) matching the query and takes whole
+//! blocks while they fit; a block that is too long on its own is shortened around its best
+//! fragment without splitting an inline code span, and a shortened code block is still closed.
+//! For queries of several terms, the first block matching the most of them is the start.
+//!
+//! Query terms are found by tantivy's `SnippetGenerator` with the keyword index's tokenizer, so
+//! a snippet shows what the index matched: `RefCell` matches "cell", `borrowing` doesn't match
+//! "borrow". `Highlight` marks the terms in a finished snippet, outside code.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use tantivy::schema::Field;
+use tantivy::snippet::{Snippet, SnippetGenerator, collapse_overlapped_ranges};
+use tantivy::tokenizer::TokenStream;
+
+use crate::search::tokenizer::identifier_analyzer;
+
+/// Marker for text left out of a shortened block
+const ELLIPSIS: &str = "...";
+
+/// A top-level markdown block
+struct Block<'a> {
+    text: &'a str,
+    fenced: bool,
+}
+
+/// Terms of a query, found in text by a `SnippetGenerator`
+pub struct QueryTerms {
+    generator: SnippetGenerator,
+}
+
+impl QueryTerms {
+    /// Terms of a user query as the keyword index tokenizes it, without words too common to
+    /// be worth finding; all weigh the same
+    pub fn new(query: &str) -> Self {
+        let mut analyzer = identifier_analyzer();
+        let mut terms = BTreeMap::new();
+        {
+            let mut tokens = analyzer.token_stream(query);
+            while let Some(token) = tokens.next() {
+                if !UNHIGHLIGHTED_WORDS.contains(&token.text.as_str()) {
+                    terms.insert(token.text.clone(), 1.0);
+                }
+            }
+        }
+        // The field is only read when snippets are made from whole documents
+        let generator = SnippetGenerator::new(terms, analyzer, Field::from_field_id(0), usize::MAX);
+        Self { generator }
+    }
+
+    /// No terms, so snippets start at the start of the text
+    pub fn none() -> Self {
+        Self::new("")
+    }
+
+    /// The terms in the whole of `text`; the fragment runs from its start to its last word, or
+    /// is empty when no term is in it
+    fn snippet(&mut self, text: &str) -> Snippet {
+        self.generator.set_max_num_chars(usize::MAX);
+        self.generator.snippet(text)
+    }
+
+    /// Number of distinct terms in `text`
+    fn count_in(&mut self, text: &str) -> usize {
+        let mut found: Vec<String> = self
+            .snippet(text)
+            .highlighted()
+            .iter()
+            .map(|range| text[range.clone()].to_lowercase())
+            .collect();
+        found.sort();
+        found.dedup();
+        found.len()
+    }
+
+    /// Byte range of the first term of the best fragment of `text` about `max_len` long: the
+    /// one whose terms weigh the most
+    fn best_match(&mut self, text: &str, max_len: usize) -> Option<Range<usize>> {
+        self.generator.set_max_num_chars(max_len);
+        let snippet = self.generator.snippet(text);
+        let first = snippet.highlighted().first()?;
+        // The fragment is a copy; an earlier copy of the same text has the same terms
+        let offset = text.find(snippet.fragment())?;
+        Some(offset + first.start..offset + first.end)
+    }
+}
+
+/// Tantivy's terms for a parsed query, weighted by rarity
+impl From<SnippetGenerator> for QueryTerms {
+    fn from(generator: SnippetGenerator) -> Self {
+        Self { generator }
+    }
+}
+
+/// Extract a markdown snippet of about `max_len` characters around the query terms
+pub fn markdown_snippet(markdown: &str, terms: &mut QueryTerms, max_len: usize) -> String {
+    let blocks = split_blocks(markdown);
+    // The first of the blocks matching the most query terms; `max_by_key` would take the last
+    let first = blocks
+        .iter()
+        .enumerate()
+        .fold((0, 0), |(best, best_count), (i, block)| {
+            let count = terms.count_in(block.text);
+            if count > best_count {
+                (i, count)
+            } else {
+                (best, best_count)
+            }
+        })
+        .0;
+
+    let mut snippet = String::new();
+    for block in &blocks[first..] {
+        let separator = if snippet.is_empty() { 0 } else { 2 };
+        let used = snippet.chars().count() + separator;
+        if used + block.text.chars().count() <= max_len {
+            if separator > 0 {
+                snippet.push_str("\n\n");
+            }
+            snippet.push_str(block.text);
+            continue;
+        }
+
+        // The matching block is always shown, shortened if needed; later ones only whole
+        if snippet.is_empty() {
+            snippet = if block.fenced {
+                shorten_code(block.text, max_len)
+            } else {
+                shorten_prose(block.text, terms, max_len)
+            };
+        }
+        break;
+    }
+    snippet
+}
+
+/// Split markdown into blocks separated by blank lines; a fenced code block is one block even
+/// when it contains blank lines
+fn split_blocks(markdown: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    let mut push = |start: usize, end: usize, fenced: bool| {
+        let text = markdown[start..end].trim_end();
+        if !text.is_empty() {
+            blocks.push(Block { text, fenced });
+        }
+    };
+
+    for line in markdown.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+
+        if in_fence {
+            if trimmed == "```"
+                && let Some(block_start) = start.take()
+            {
+                push(block_start, offset, true);
+                in_fence = false;
+            }
+        } else if trimmed.starts_with("```") {
+            if let Some(block_start) = start.take() {
+                push(block_start, line_start, false);
+            }
+            start = Some(line_start);
+            in_fence = true;
+        } else if trimmed.is_empty() {
+            if let Some(block_start) = start.take() {
+                push(block_start, line_start, false);
+            }
+        } else if start.is_none() {
+            start = Some(line_start);
+        }
+    }
+    if let Some(block_start) = start {
+        push(block_start, markdown.len(), in_fence);
+    }
+    blocks
+}
+
+/// Byte ranges of the inline code spans in a block, backticks included
+fn code_spans(text: &str) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    // Start and delimiter length of the open span
+    let mut open: Option<(usize, usize)> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let run_start = i;
+        while i < bytes.len() && bytes[i] == b'`' {
+            i += 1;
+        }
+        match open {
+            Some((start, len)) if len == i - run_start => {
+                spans.push((start, i));
+                open = None;
+            }
+            Some(_) => {}
+            None => open = Some((run_start, i - run_start)),
+        }
+    }
+    spans
+}
+
+/// Extract a snippet of about `max_len` characters around the query terms from plain text
+/// (documents without markdown)
+pub fn plain_snippet(content: &str, terms: &mut QueryTerms, max_len: usize) -> String {
+    cut_around_match(content, terms.best_match(content, max_len), max_len, &[])
+}
+
+/// Markers put around the query terms of a snippet, such as `**` and `**`, or `<b>` and `</b>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub open: String,
+    pub close: String,
+    /// Escape the prose as HTML, as `Snippet::to_html` does
+    html: bool,
+}
+
+/// Query words too common to be worth finding
+const UNHIGHLIGHTED_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "do", "does", "for", "how", "i", "in", "is", "of", "on", "or", "the",
+    "to", "what", "when", "why", "with",
+];
+
+impl Highlight {
+    /// Markdown bold, which renders wherever the snippets' markdown does
+    pub fn markdown() -> Self {
+        Self {
+            open: "**".to_string(),
+            close: "**".to_string(),
+            html: false,
+        }
+    }
+
+    /// HTML bold, with the prose around it escaped
+    pub fn html() -> Self {
+        Self {
+            open: "<b>".to_string(),
+            close: "</b>".to_string(),
+            html: true,
+        }
+    }
+
+    /// Mark the query terms in `snippet`. Inline code and code blocks are left as they are, so
+    /// they still render as code.
+    pub fn apply(&self, snippet: &str, terms: &mut QueryTerms) -> String {
+        let mut highlighted = String::with_capacity(snippet.len());
+        let mut in_fence = false;
+        for line in snippet.split_inclusive('\n') {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+                highlighted.push_str(line);
+            } else if in_fence {
+                highlighted.push_str(line);
+            } else {
+                let mut copied = 0;
+                for (start, end) in code_spans(line) {
+                    self.mark(&line[copied..start], terms, &mut highlighted);
+                    highlighted.push_str(&line[start..end]);
+                    copied = end;
+                }
+                self.mark(&line[copied..], terms, &mut highlighted);
+            }
+        }
+        highlighted
+    }
+
+    /// Append prose with its query terms marked
+    fn mark(&self, prose: &str, terms: &mut QueryTerms, out: &mut String) {
+        let mut snippet = terms.snippet(prose);
+        // Only punctuation and whitespace follow the fragment
+        let rest = &prose[snippet.fragment().len()..];
+        if self.html {
+            snippet.set_snippet_prefix_postfix(&self.open, &self.close);
+            out.push_str(&snippet.to_html());
+            out.push_str(&escape_html(rest));
+            return;
+        }
+
+        // Markdown is left unescaped; adjacent terms (`Ref` and `Cell`) share one pair of markers
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for range in collapse_overlapped_ranges(snippet.highlighted()) {
+            match ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            }
+        }
+        let mut copied = 0;
+        for range in ranges {
+            out.push_str(&prose[copied..range.start]);
+            out.push_str(&self.open);
+            out.push_str(&prose[range.clone()]);
+            out.push_str(&self.close);
+            copied = range.end;
+        }
+        out.push_str(&prose[copied..]);
+    }
+}
+
+/// Escape the characters `Snippet::to_html` escapes
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("&quot;"),
+            '&' => escaped.push_str("&amp;"),
+            '\'' => escaped.push_str("&#x27;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Cut a paragraph to about `max_len` characters around its best fragment, on whitespace
+/// outside inline code
+fn shorten_prose(text: &str, terms: &mut QueryTerms, max_len: usize) -> String {
+    cut_around_match(
+        text,
+        terms.best_match(text, max_len),
+        max_len,
+        &code_spans(text),
+    )
+}
+
+/// Cut text to about `max_len` characters around a query match (the start without one).
+///
+/// All positions are char boundaries. Cuts move onto whitespace outside `spans` (byte ranges
+/// that must stay whole); text without whitespace, such as CJK, is cut between characters.
+fn cut_around_match(
+    text: &str,
+    matched: Option<Range<usize>>,
+    max_len: usize,
+    spans: &[(usize, usize)],
+) -> String {
+    let (match_pos, match_end) = matched.map_or((0, 0), |range| (range.start, range.end));
+
+    // Window of `max_len` characters around the match, as byte offsets
+    let boundaries: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let char_count = boundaries.len() - 1;
+    let match_char = boundaries.partition_point(|&i| i < match_pos);
+    let end_char = (match_char.saturating_sub(max_len / 2) + max_len).min(char_count);
+    let start_char = end_char.saturating_sub(max_len);
+    // The matched word is always kept whole
+    let (mut start, mut end) = (boundaries[start_char], boundaries[end_char].max(match_end));
+
+    // Never split a span: a cut inside one moves to its edge
+    let inside = |pos: usize| spans.iter().find(|&&(s, e)| s < pos && pos < e).copied();
+    let mut start_clean = start == 0 || text[..start].ends_with(char::is_whitespace);
+    let mut end_clean = end == text.len() || text[end..].starts_with(char::is_whitespace);
+    if let Some((span_start, _)) = inside(start) {
+        start = span_start;
+        start_clean = true;
+    }
+    if let Some((_, span_end)) = inside(end) {
+        end = span_end;
+        end_clean = true;
+    }
+
+    // Otherwise move the cuts onto whitespace outside spans, without passing the match
+    let is_cut = |(i, c): &(usize, char)| c.is_whitespace() && inside(*i).is_none();
+    let before_match = match_pos.clamp(start, end);
+    if !start_clean
+        && let Some((i, c)) = text[start..before_match]
+            .char_indices()
+            .map(|(i, c)| (start + i, c))
+            .find(is_cut)
+    {
+        start = i + c.len_utf8();
+    }
+    let after_match = match_end.clamp(start, end);
+    if !end_clean
+        && let Some((i, _)) = text[after_match..end]
+            .char_indices()
+            .map(|(i, c)| (after_match + i, c))
+            .rfind(is_cut)
+    {
+        end = i;
+    }
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str(ELLIPSIS);
+    }
+    snippet.push_str(text[start..end].trim());
+    if end < text.len() {
+        snippet.push_str(ELLIPSIS);
+    }
+    snippet
+}
+
+/// Keep the leading lines of a fenced code block that fit in `max_len`, closing the fence
+fn shorten_code(block: &str, max_len: usize) -> String {
+    let lines: Vec<&str> = block.lines().collect();
+    let closed = lines.len() > 1 && lines[lines.len() - 1].trim() == "```";
+    let body = &lines[1..if closed { lines.len() - 1 } else { lines.len() }];
+
+    let mut snippet = format!("{}\n", lines[0]);
+    // Room kept for the omission marker and the closing fence
+    let reserved = ELLIPSIS.len() + 1 + 3;
+    for (i, line) in body.iter().enumerate() {
+        if i > 0 && snippet.chars().count() + line.chars().count() + 1 + reserved > max_len {
+            snippet.push_str(ELLIPSIS);
+            snippet.push('\n');
+            break;
+        }
+        snippet.push_str(line);
+        snippet.push('\n');
+    }
+    snippet.push_str("```");
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKDOWN: &str = "Values are moved by default.\n\n## Borrowing\n\nA reference created with `&value` borrows without taking ownership, and `&mut value` borrows mutably. Only one mutable borrow may exist at a time, which the borrow checker enforces at compile time.\n\n```rust\nlet s = String::from(\"hello\");\nlet r = &s;\n\nprintln!(\"{}\", r);\n```";
+
+    fn terms(query: &str) -> QueryTerms {
+        QueryTerms::new(query)
+    }
+
+    #[test]
+    fn test_snippet_takes_whole_blocks_from_the_match() {
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("moved"), 60);
+        assert_eq!(snippet, "Values are moved by default.\n\n## Borrowing");
+
+        // A code block containing a blank line stays one block
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("println"), 200);
+        assert_eq!(
+            snippet,
+            "```rust\nlet s = String::from(\"hello\");\nlet r = &s;\n\nprintln!(\"{}\", r);\n```"
+        );
+    }
+
+    #[test]
+    fn test_shortened_prose_keeps_code_spans_whole() {
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("mutably"), 40);
+        assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+        assert!(snippet.contains("mutably"));
+        assert_eq!(snippet.matches('`').count() % 2, 0, "{}", snippet);
+
+        // A window starting inside `&mut value` widens to the whole span
+        let snippet = shorten_prose("ab `&mut value` borrows", &mut terms("borrows"), 16);
+        assert_eq!(snippet, "...`&mut value` borrows");
+    }
+
+    #[test]
+    fn test_plain_snippet_on_non_ascii_text() {
+        let german = "Die Größe eines Wertes ist zur Übersetzungszeit bekannt. Eine Box speichert ihre Daten auf dem Heap, während der Zeiger selbst auf dem Stack liegt.";
+        let snippet = plain_snippet(german, &mut terms("heap"), 40);
+        assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+        assert!(snippet.contains("Heap"));
+        // Cut between words, not inside one
+        assert!(!snippet.contains("...ine ") && !snippet.contains("wäh..."));
+
+        // Uppercase umlauts match their lowercase query
+        assert!(
+            plain_snippet(german, &mut terms("übersetzungszeit"), 30).contains("Übersetzungszeit")
+        );
+
+        // Lowercasing `İ` adds a char; the match position must still be right
+        let snippet = plain_snippet(
+            "İİİİİİİİİİ İİİİİİİİİİ ownership rules",
+            &mut terms("ownership"),
+            16,
+        );
+        assert!(snippet.contains("ownership"), "{}", snippet);
+    }
+
+    #[test]
+    fn test_plain_snippet_without_whitespace() {
+        // `Box` is a term of its own between the brackets
+        let japanese =
+            "所有権はRustの最も独特な機能であり、値は「Box」に格納されるとヒープに置かれます。";
+        let snippet = plain_snippet(japanese, &mut terms("box"), 10);
+        assert!(snippet.contains("Box"));
+        assert_eq!(snippet.trim_matches('.').chars().count(), 10);
+
+        let emoji = "🦀 Ferris 🦀 is the unofficial mascot 🦀 of the Rust community 🦀";
+        let snippet = plain_snippet(emoji, &mut terms("mascot"), 20);
+        assert!(snippet.contains("mascot"));
+    }
+
+    #[test]
+    fn test_snippet_starts_at_the_block_matching_most_words() {
+        // The first block has "moved"; the paragraph has both "mutable" and "borrow"
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("moved mutable borrow?"), 300);
+        assert!(snippet.starts_with("A reference created"), "{}", snippet);
+        // Of blocks matching as many terms, the first
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("moved ownership"), 300);
+        assert!(snippet.starts_with("Values are moved"), "{}", snippet);
+    }
+
+    #[test]
+    fn test_highlight_marks_query_terms_outside_code() {
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("borrow"), 400);
+        let highlighted = Highlight::markdown().apply(&snippet, &mut terms("the Borrow checker"));
+        // Terms match whole words, as in the index: `borrows` isn't `borrow`
+        assert!(
+            highlighted.starts_with("A reference created with `&value` borrows without"),
+            "{}",
+            highlighted
+        );
+        assert!(highlighted.contains("the **borrow** **checker** enforces"));
+        // Code and common words are left alone
+        assert!(highlighted.contains("```rust\nlet s = String::from(\"hello\");\nlet r = &s;"));
+        assert!(!highlighted.contains("**the**"));
+
+        // Identifier parts are terms too
+        let highlighted =
+            Highlight::markdown().apply("A RefCell is a Cell", &mut terms("ref cell"));
+        assert_eq!(highlighted, "A **RefCell** is a **Cell**");
+
+        // HTML escapes the prose around the markers, not the code
+        let html = Highlight::html().apply("Größe & `größe`", &mut terms("GRÖSSE größe"));
+        assert_eq!(html, "<b>Größe</b> &amp; `größe`");
+        let html = Highlight::html().apply("A RefCell<T>", &mut terms("cell"));
+        assert_eq!(html, "A Ref<b>Cell</b>&lt;T&gt;");
+        assert_eq!(
+            Highlight::html().apply("Any text", &mut terms("the")),
+            "Any text"
+        );
+    }
+
+    #[test]
+    fn test_shortened_code_block_is_closed() {
+        let snippet = markdown_snippet(MARKDOWN, &mut terms("String::from"), 50);
+        assert_eq!(
+            snippet,
+            "```rust\nlet s = String::from(\"hello\");\n...\n```"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Corpus statistics, for diagnosing retrieval quality.
+//!
+//! Unexpected search results often come down to the corpus: a source with a handful of huge
+//! documents, one whose pages are mostly code, or documents that never got a vector and so can
+//! only be found by keyword. The statistics are computed from the stored documents on request.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+use tantivy::tokenizer::TokenStream;
+
+use crate::parsing::{Document, chunk_document_key, document_key};
+use crate::search::paths::PathId;
+use crate::search::tokenizer::identifier_analyzer;
+use crate::search::vector_index::VectorIndex;
+
+/// Statistics of a set of documents: one source, or the whole corpus
+#[derive(Debug, Clone, Serialize)]
+pub struct CorpusStats {
+    pub documents: usize,
+    /// Document lengths in words
+    pub length: LengthStats,
+    /// Distinct terms of the titles and content, as indexed
+    pub vocabulary: usize,
+    pub code_blocks: usize,
+    /// Documents with a vector in the vector index
+    pub embedded: usize,
+    /// Documents only keyword search can find
+    pub not_embedded: usize,
+}
+
+/// Distribution of document lengths
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LengthStats {
+    pub mean: f64,
+    pub p50: usize,
+    pub p90: usize,
+    pub p99: usize,
+    pub max: usize,
+}
+
+impl LengthStats {
+    /// Distribution of `lengths` (nearest-rank percentiles)
+    fn of(mut lengths: Vec<usize>) -> Self {
+        if lengths.is_empty() {
+            return Self::default();
+        }
+        lengths.sort_unstable();
+        let percentile = |p: usize| lengths[((lengths.len() * p).div_ceil(100)).max(1) - 1];
+        Self {
+            mean: lengths.iter().sum::<usize>() as f64 / lengths.len() as f64,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: lengths[lengths.len() - 1],
+        }
+    }
+}
+
+/// Statistics of the whole corpus and of each source, keyed by source ID
+pub fn corpus_stats(
+    documents: &[Document],
+    vector_index: &VectorIndex,
+) -> (CorpusStats, BTreeMap<String, CorpusStats>) {
+    let mut by_source: BTreeMap<&str, Vec<&Document>> = BTreeMap::new();
+    for doc in documents {
+        by_source.entry(doc.source.as_str()).or_default().push(doc);
+    }
+    let sources = by_source
+        .into_iter()
+        .map(|(source, docs)| (source.to_string(), stats(&docs, vector_index)))
+        .collect();
+    let all: Vec<&Document> = documents.iter().collect();
+    (stats(&all, vector_index), sources)
+}
+
+fn stats(documents: &[&Document], vector_index: &VectorIndex) -> CorpusStats {
+    let mut analyzer = identifier_analyzer();
+    let mut vocabulary = HashSet::new();
+    for doc in documents {
+        for text in [&doc.title, &doc.content] {
+            let mut stream = analyzer.token_stream(text);
+            while stream.advance() {
+                if !vocabulary.contains(&stream.token().text) {
+                    vocabulary.insert(stream.token().text.clone());
+                }
+            }
+        }
+    }
+
+    // Vectors are keyed by chunk; a document is embedded when any of its sections is
+    let embedded_documents: HashSet<&str> = (0..vector_index.path_count())
+        .map(|id| chunk_document_key(vector_index.path(id as PathId)))
+        .collect();
+    let embedded = documents
+        .iter()
+        .filter(|doc| embedded_documents.contains(document_key(&doc.source, &doc.path).as_str()))
+        .count();
+    CorpusStats {
+        documents: documents.len(),
+        length: LengthStats::of(
+            documents
+                .iter()
+                .map(|doc| doc.content.split_whitespace().count())
+                .collect(),
+        ),
+        vocabulary: vocabulary.len(),
+        code_blocks: documents.iter().map(|doc| doc.code_blocks.len()).sum(),
+        embedded,
+        not_embedded: documents.len() - embedded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(source: &str, path: &str, content: &str, code_blocks: usize) -> Document {
+        Document {
+            title: path.to_string(),
+            content: content.to_string(),
+            path: path.to_string(),
+            source: source.to_string(),
+            code_blocks: vec!["fn main() {}".to_string(); code_blocks],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_length_percentiles() {
+        let length = LengthStats::of((1..=100).collect());
+        assert_eq!(
+            (length.p50, length.p90, length.p99, length.max),
+            (50, 90, 99, 100)
+        );
+        assert!((length.mean - 50.5).abs() < 1e-9);
+        assert_eq!(LengthStats::of(vec![7]).p99, 7);
+        assert_eq!(LengthStats::of(Vec::new()).max, 0);
+    }
+
+    #[test]
+    fn test_corpus_stats() {
+        let documents = vec![
+            document("rust-book", "a.md", "Ownership moves values", 2),
+            document("rust-book", "b.md", "Borrowing", 0),
+            document("rust-reference", "c.md", "Ownership rules", 1),
+        ];
+        let mut vector_index = VectorIndex::new();
+        vector_index
+            .add("rust-book/a.md".to_string(), vec![1.0, 0.0])
+            .unwrap();
+
+        let (all, sources) = corpus_stats(&documents, &vector_index);
+        assert_eq!(all.documents, 3);
+        assert_eq!(all.code_blocks, 3);
+        assert_eq!((all.embedded, all.not_embedded), (1, 2));
+        assert_eq!(all.length.max, 3);
+
+        let book = &sources["rust-book"];
+        assert_eq!(book.documents, 2);
+        // "a", "md", "b", "ownership", "moves", "values", "borrowing"
+        assert_eq!(book.vocabulary, 7);
+        assert_eq!(sources["rust-reference"].embedded, 0);
+    }
+}
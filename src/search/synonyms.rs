@@ -0,0 +1,242 @@
+//! Query-time synonym expansion.
+//!
+//! Maps terms users tend to type (often from other languages or team jargon) to the
+//! vocabulary used in the Rust documentation. A small built-in table is always active;
+//! users can extend it with a synonyms file.
+//!
+//! File format, one entry per line:
+//!
+//! ```text
+//! # comments start with '#'
+//! borrowck = borrow checker, borrowing
+//! our result type = Result, error handling
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Default file name looked up in the data directory
+const SYNONYMS_FILE: &str = "synonyms.txt";
+
+/// Built-in synonyms mapping common non-Rust vocabulary to Rust terms
+const BUILTIN_SYNONYMS: &[(&str, &[&str])] = &[
+    ("borrowck", &["borrow checker", "borrowing"]),
+    ("interface", &["trait"]),
+    ("interfaces", &["traits"]),
+    ("inheritance", &["trait", "trait objects"]),
+    ("lambda", &["closure"]),
+    ("lambdas", &["closures"]),
+    ("null", &["Option", "None"]),
+    ("exception", &["panic", "Result"]),
+    ("exceptions", &["panic", "Result"]),
+    ("destructor", &["Drop"]),
+    ("switch", &["match"]),
+    ("garbage collection", &["ownership", "Drop"]),
+    ("thread safety", &["Send", "Sync"]),
+    ("smart pointer", &["Box", "Rc", "RefCell"]),
+    ("smart pointers", &["Box", "Rc", "RefCell"]),
+    ("interior mutability", &["Cell", "RefCell"]),
+    ("generics", &["type parameters"]),
+];
+
+/// A single synonym entry: the words of the key phrase and its expansions
+#[derive(Debug, Clone)]
+struct Entry {
+    key: Vec<String>,
+    expansions: Vec<String>,
+}
+
+/// Synonym table applied to queries before they reach the keyword index
+#[derive(Debug, Clone, Default)]
+pub struct Synonyms {
+    entries: Vec<Entry>,
+}
+
+impl Synonyms {
+    /// Create a table containing only the built-in synonyms
+    pub fn builtin() -> Self {
+        let entries = BUILTIN_SYNONYMS
+            .iter()
+            .map(|(key, expansions)| Entry {
+                key: split_words(key),
+                expansions: expansions.iter().map(|s| s.to_string()).collect(),
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Load the built-in synonyms plus the user file, if one is configured.
+    ///
+    /// The file is taken from `RUST_MCP_SYNONYMS` or `<data_dir>/synonyms.txt`.
+    /// A missing or malformed file is logged and ignored.
+    pub fn load(data_dir: &Path) -> Self {
+        let mut synonyms = Self::builtin();
+
+        let path = std::env::var("RUST_MCP_SYNONYMS")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| data_dir.join(SYNONYMS_FILE));
+
+        if path.exists() {
+            match Self::from_file(&path) {
+                Ok(custom) => {
+                    tracing::info!("Loaded {} custom synonyms from {:?}", custom.len(), path);
+                    synonyms.extend(custom);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to load synonyms from {:?}: {}", path, e);
+                }
+            }
+        }
+
+        synonyms
+    }
+
+    /// Parse a synonyms file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    /// Parse synonyms from the `key = synonym, synonym` line format
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, expansions) = line.split_once('=').ok_or_else(|| {
+                Error::Other(format!(
+                    "Invalid synonym on line {}: expected 'term = synonym, ...'",
+                    line_no + 1
+                ))
+            })?;
+
+            let key = split_words(key);
+            let expansions: Vec<String> = expansions
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+
+            if key.is_empty() || expansions.is_empty() {
+                return Err(Error::Other(format!(
+                    "Invalid synonym on line {}: term and synonyms must not be empty",
+                    line_no + 1
+                )));
+            }
+
+            entries.push(Entry { key, expansions });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Append the entries of another table (custom entries are applied after built-ins)
+    pub fn extend(&mut self, other: Synonyms) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Number of entries in the table
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the table has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Expand a query by appending the synonyms of any key phrase it contains.
+    ///
+    /// Multi-word synonyms are appended as quoted phrases so they match as a unit.
+    pub fn expand(&self, query: &str) -> String {
+        let words = split_words(query);
+        let mut additions: Vec<String> = Vec::new();
+
+        for entry in &self.entries {
+            if !contains_phrase(&words, &entry.key) {
+                continue;
+            }
+
+            for expansion in &entry.expansions {
+                let expansion_words = split_words(expansion);
+                if contains_phrase(&words, &expansion_words) {
+                    continue;
+                }
+
+                let term = if expansion_words.len() > 1 {
+                    format!("\"{}\"", expansion_words.join(" "))
+                } else {
+                    expansion.clone()
+                };
+
+                if !additions.contains(&term) {
+                    additions.push(term);
+                }
+            }
+        }
+
+        if additions.is_empty() {
+            query.to_string()
+        } else {
+            format!("{} {}", query, additions.join(" "))
+        }
+    }
+}
+
+/// Split text into lowercase alphanumeric words
+fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Check if `phrase` occurs as a contiguous run of words in `words`
+fn contains_phrase(words: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && words.windows(phrase.len()).any(|window| window == phrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_expansion() {
+        let synonyms = Synonyms::builtin();
+        let expanded = synonyms.expand("how do lambdas capture variables");
+        assert!(expanded.starts_with("how do lambdas capture variables"));
+        assert!(expanded.contains("closures"));
+    }
+
+    #[test]
+    fn test_parse_custom_file() -> Result<()> {
+        let text =
+            "# team jargon\nour result type = Result, error handling\n\nborrowck = borrowing\n";
+        let synonyms = Synonyms::parse(text)?;
+        assert_eq!(synonyms.len(), 2);
+
+        let expanded = synonyms.expand("using Our Result Type");
+        assert!(expanded.contains("Result"));
+        assert!(expanded.contains("\"error handling\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_line() {
+        assert!(Synonyms::parse("no separator here").is_err());
+        assert!(Synonyms::parse("term =").is_err());
+    }
+
+    #[test]
+    fn test_no_duplicate_or_partial_matches() {
+        let synonyms = Synonyms::parse("null = Option").unwrap();
+        assert_eq!(synonyms.expand("nullable pointer"), "nullable pointer");
+        assert_eq!(synonyms.expand("null Option"), "null Option");
+    }
+}
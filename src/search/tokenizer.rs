@@ -0,0 +1,211 @@
+//! Identifier-aware tokenizer for the keyword index.
+//!
+//! Rust documentation is full of identifiers like `RefCell`, `try_into`, or `HashMap`.
+//! This tokenizer keeps each identifier as a token and also emits its CamelCase and
+//! snake_case parts, so natural-language queries ("ref cell", "hash map") match
+//! identifier-heavy content while exact identifier queries still work.
+
+use tantivy::tokenizer::{
+    LowerCaser, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+
+/// Name under which the analyzer is registered with the tantivy index
+pub const TOKENIZER_NAME: &str = "rust_identifier";
+
+/// Build the full analyzer: identifier splitting, long-token removal, lowercasing
+pub fn identifier_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(IdentifierTokenizer)
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .build()
+}
+
+/// Tokenizer that splits on non-identifier characters and then on identifier case boundaries.
+///
+/// For `RefCell` it emits `RefCell` at position `p`, then `Ref` at `p` and `Cell` at `p + 1`,
+/// so both the identifier and the phrase "ref cell" match.
+#[derive(Clone, Default)]
+pub struct IdentifierTokenizer;
+
+impl Tokenizer for IdentifierTokenizer {
+    type TokenStream<'a> = IdentifierTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        IdentifierTokenStream {
+            tokens: tokenize(text),
+            index: 0,
+            fallback: Token::default(),
+        }
+    }
+}
+
+/// Token stream over a precomputed list of tokens
+pub struct IdentifierTokenStream {
+    tokens: Vec<Token>,
+    index: usize,
+    fallback: Token,
+}
+
+impl TokenStream for IdentifierTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.index < self.tokens.len() {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn token(&self) -> &Token {
+        self.index
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .unwrap_or(&self.fallback)
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        match self.index.checked_sub(1) {
+            Some(i) if i < self.tokens.len() => &mut self.tokens[i],
+            _ => &mut self.fallback,
+        }
+    }
+}
+
+/// Split text into identifier tokens plus their sub-tokens
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    for (start, word) in words(text) {
+        let parts = split_identifier(word);
+
+        if parts.len() > 1 {
+            tokens.push(make_token(start, word, position));
+        }
+
+        for (offset, part) in parts {
+            tokens.push(make_token(start + offset, part, position));
+            position += 1;
+        }
+    }
+
+    tokens
+}
+
+fn make_token(offset: usize, text: &str, position: usize) -> Token {
+    Token {
+        offset_from: offset,
+        offset_to: offset + text.len(),
+        position,
+        text: text.to_string(),
+        position_length: 1,
+    }
+}
+
+/// Collect runs of alphanumeric/underscore characters with their byte offsets
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut start: Option<usize> = None;
+    let mut result = Vec::new();
+
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            result.push((s, &text[s..i]));
+        }
+    }
+    if let Some(s) = start {
+        result.push((s, &text[s..]));
+    }
+
+    result
+}
+
+/// Split an identifier on underscores and case boundaries, returning byte offsets within it
+fn split_identifier(word: &str) -> Vec<(usize, &str)> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut part_start: Option<usize> = None;
+
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(s) = part_start.take() {
+                parts.push((s, &word[s..offset]));
+            }
+            continue;
+        }
+
+        if let Some(s) = part_start {
+            let prev = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, n)| n);
+            // "refCell" -> ref|Cell, "HTTPServer" -> HTTP|Server
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next.is_some_and(|n| n.is_lowercase())));
+            if boundary {
+                parts.push((s, &word[s..offset]));
+                part_start = Some(offset);
+            }
+        } else {
+            part_start = Some(offset);
+        }
+    }
+
+    if let Some(s) = part_start {
+        parts.push((s, &word[s..]));
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_texts(text: &str) -> Vec<(String, usize)> {
+        let mut analyzer = identifier_analyzer();
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while let Some(token) = stream.next() {
+            tokens.push((token.text.clone(), token.position));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_camel_case_split_keeps_original() {
+        let tokens = token_texts("use RefCell here");
+        assert_eq!(
+            tokens,
+            vec![
+                ("use".to_string(), 0),
+                ("refcell".to_string(), 1),
+                ("ref".to_string(), 1),
+                ("cell".to_string(), 2),
+                ("here".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snake_case_and_acronyms() {
+        let texts: Vec<String> = token_texts("try_into HTTPServer")
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(
+            texts,
+            vec!["try_into", "try", "into", "httpserver", "http", "server"]
+        );
+    }
+
+    #[test]
+    fn test_plain_words_unchanged() {
+        let texts: Vec<String> = token_texts("ownership rules, über")
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(texts, vec!["ownership", "rules", "über"]);
+    }
+}
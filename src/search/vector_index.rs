@@ -1,5 +1,18 @@
 //! Vector index for semantic search using HNSW (Hierarchical Navigable Small World).
-
+//!
+//! A document can have two vectors: one for its body text and one for its title and headings.
+//! Short queries often match a heading closely while the body embedding is diluted by long
+//! prose, so both vectors share one graph and a document's score weighs the two similarities.
+//!
+//! The index records the embedding model that built it, and refuses vectors of another
+//! dimension: similarities between embeddings of different models are meaningless. It also
+//! keeps that model's query embeddings of common concepts (see `concepts.rs`), so searches
+//! for them need no inference.
+//!
+//! Chunks also carry what a search result shows about them (title, source, section, and the
+//! start of their text), so semantic hits become results without a keyword index lookup.
+
+use std::collections::HashMap;
 use std::path::Path;
 
 use hnsw_rs::hnsw::Hnsw;
@@ -7,51 +20,349 @@ use hnsw_rs::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, Result};
-
-/// Stored document with path and embedding for persistence
+use crate::parsing::{Document, Section, chunk_source_path};
+use crate::search::concepts::concept_key;
+use crate::search::embeddings::ModelSpec;
+use crate::search::index::{MatchExplanation, SearchResult};
+use crate::search::paths::{PathId, PathTable};
+use crate::search::snippet::{QueryTerms, markdown_snippet, plain_snippet};
+
+/// Stored document with path and embeddings for persistence
 #[derive(Serialize, Deserialize)]
 struct StoredDocument {
     path: String,
     embedding: Vec<f32>,
+    /// Embedding of the title and headings; absent in indices built before titles were embedded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title_embedding: Option<Vec<f32>>,
+    /// Result metadata; absent in indices built before it was stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    info: Option<ChunkInfo>,
+}
+
+/// Longest snippet stored with a chunk; shorter snippets are cut from it
+const STORED_SNIPPET_LEN: usize = 600;
+
+/// What a search result shows about a chunk, besides its key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkInfo {
+    pub title: String,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<Section>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist_ids: Vec<String>,
+    /// Markdown snippet from the start of the chunk, `STORED_SNIPPET_LEN` characters at most
+    pub snippet: String,
+}
+
+impl ChunkInfo {
+    /// Metadata of a chunk produced by `chunk_document`
+    pub fn of(chunk: &Document) -> Self {
+        Self {
+            title: chunk.title.clone(),
+            source: chunk.source.clone(),
+            section: chunk.section.clone(),
+            checklist_ids: chunk.checklist_ids.clone(),
+            snippet: if chunk.markdown.is_empty() {
+                plain_snippet(&chunk.content, &mut QueryTerms::none(), STORED_SNIPPET_LEN)
+            } else {
+                markdown_snippet(&chunk.markdown, &mut QueryTerms::none(), STORED_SNIPPET_LEN)
+            },
+        }
+    }
+
+    /// The chunk with key `key` as a search result, with a snippet of about `snippet_len`
+    /// characters
+    pub fn result(&self, key: &str, snippet_len: usize) -> SearchResult {
+        SearchResult {
+            title: self.title.clone(),
+            snippet: markdown_snippet(&self.snippet, &mut QueryTerms::none(), snippet_len),
+            path: chunk_source_path(key).1.to_string(),
+            source: self.source.clone(),
+            checklist_ids: self.checklist_ids.clone(),
+            score: 0.0,
+            explanation: MatchExplanation::default(),
+            section: self.section.clone(),
+        }
+    }
+}
+
+/// Embedding model an index was built with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexModel {
+    pub id: String,
+    pub dimension: usize,
+    /// Whether vectors are keyed by document section; indices built before documents were
+    /// chunked are keyed by document path
+    #[serde(default)]
+    pub chunked: bool,
+    /// Whether chunk keys start with the document's source (`source/path#anchor`); earlier
+    /// indices are keyed by file name alone
+    #[serde(default)]
+    pub keyed_by_source: bool,
+}
+
+impl IndexModel {
+    /// Record of vectors of document sections produced by a model
+    pub fn of(spec: &ModelSpec) -> Self {
+        Self {
+            id: spec.id.to_string(),
+            dimension: spec.dimension,
+            chunked: true,
+            keyed_by_source: true,
+        }
+    }
+
+    /// Whether vectors are keyed as the indexer keys them now, so they can be matched to the
+    /// keyword index's chunks
+    pub fn has_current_keys(&self) -> bool {
+        self.chunked && self.keyed_by_source
+    }
+
+    /// The model recorded with the vector index saved at `path`, if any
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let model_path = path.join(MODEL_FILE);
+        if !model_path.exists() {
+            return Ok(None);
+        }
+        let file = std::fs::File::open(&model_path)?;
+        serde_json::from_reader(file)
+            .map(Some)
+            .map_err(|e| Error::Other(format!("Failed to load vector index model: {}", e)))
+    }
+}
+
+/// Weight of the title similarity in a document's score; the content similarity gets the rest
+pub const TITLE_WEIGHT: f32 = 0.3;
+
+/// Which text of a document a vector embeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Content,
+    Title,
 }
 
 /// Vector index for storing and searching document embeddings
 pub struct VectorIndex {
     hnsw: Hnsw<'static, f32, DistCosine>,
-    /// Mapping from HNSW internal ID to document path
-    id_to_path: Vec<String>,
+    /// Mapping from HNSW internal ID to the document's interned path
+    id_to_path: Vec<PathId>,
+    /// Vector IDs of each path's content and title embeddings, indexed by path id
+    by_path: Vec<[Option<usize>; 2]>,
+    /// Side table of document paths
+    paths: PathTable,
+    /// Result metadata of each path, indexed by path id
+    info: Vec<Option<ChunkInfo>>,
     /// Store embeddings for persistence (rebuild index on load)
     embeddings: Vec<Vec<f32>>,
+    /// Number of vectors the graph is sized for; grows as documents are added
+    capacity: usize,
+    /// Model the vectors come from; unknown for indices saved before models were recorded
+    model: Option<IndexModel>,
+    /// Precomputed query embeddings, keyed by `concept_key`
+    queries: HashMap<String, Vec<f32>>,
 }
 
+/// File next to `vector_index.json` recording the embedding model
+const MODEL_FILE: &str = "model.json";
+
+/// File next to `vector_index.json` with the precomputed query embeddings
+const QUERIES_FILE: &str = "query_embeddings.json";
+
+/// Capacity used when no memory budget is configured
+const DEFAULT_CAPACITY: usize = 10_000;
+
 impl VectorIndex {
     /// Create a new empty vector index
     pub fn new() -> Self {
-        let nb_elem = 10000; // Initial capacity
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create a new empty vector index allocating room for `capacity` vectors
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            hnsw: Self::build_graph(capacity),
+            id_to_path: Vec::new(),
+            by_path: Vec::new(),
+            paths: PathTable::default(),
+            info: Vec::new(),
+            embeddings: Vec::new(),
+            capacity,
+            model: None,
+            queries: HashMap::new(),
+        }
+    }
+
+    fn build_graph(capacity: usize) -> Hnsw<'static, f32, DistCosine> {
         let max_nb_connection = 16; // M parameter
         let nb_layer = 16; // Max layers
         let ef_construction = 200; // Build-time search width
 
-        let hnsw = Hnsw::new(max_nb_connection, nb_elem, nb_layer, ef_construction, DistCosine);
+        Hnsw::new(
+            max_nb_connection,
+            capacity,
+            nb_layer,
+            ef_construction,
+            DistCosine,
+        )
+    }
 
-        Self {
-            hnsw,
-            id_to_path: Vec::new(),
-            embeddings: Vec::new(),
+    /// Make room for `additional` more vectors.
+    ///
+    /// A full graph is rebuilt at (at least) twice its capacity from the stored embeddings, so
+    /// the cost is amortized over the insertions that fill it again.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.id_to_path.len() + additional;
+        if needed <= self.capacity {
+            return;
+        }
+
+        let capacity = needed.max(self.capacity * 2);
+        tracing::debug!(
+            "Growing vector index from {} to {} vectors",
+            self.capacity,
+            capacity
+        );
+        self.capacity = capacity;
+        self.hnsw = Self::build_graph(capacity);
+        let data: Vec<(&Vec<f32>, usize)> = self
+            .embeddings
+            .iter()
+            .enumerate()
+            .map(|(id, embedding)| (embedding, id))
+            .collect();
+        self.hnsw.parallel_insert(&data);
+    }
+
+    /// Embedding model of the vectors, when recorded
+    pub fn model(&self) -> Option<&IndexModel> {
+        self.model.as_ref()
+    }
+
+    /// Record the embedding model of an empty index
+    pub fn set_model(&mut self, model: IndexModel) -> Result<()> {
+        if !self.is_empty() {
+            return Err(Error::Other(
+                "Can't change the embedding model of a non-empty vector index".to_string(),
+            ));
+        }
+        self.model = Some(model);
+        Ok(())
+    }
+
+    /// Length of the vectors in the index: the model's dimension, or the first vector's length
+    pub fn dimension(&self) -> Option<usize> {
+        self.model
+            .as_ref()
+            .map(|model| model.dimension)
+            .or_else(|| self.embeddings.first().map(Vec::len))
+    }
+
+    /// Refuse a vector whose length differs from the index's
+    fn check_dimension(&self, embedding: &[f32]) -> Result<()> {
+        match self.dimension() {
+            Some(dimension) if dimension != embedding.len() => Err(Error::Other(format!(
+                "Embedding has {} dimensions, but the vector index holds {}-dimensional vectors{}",
+                embedding.len(),
+                dimension,
+                self.model
+                    .as_ref()
+                    .map(|model| format!(" from {}", model.id))
+                    .unwrap_or_default()
+            ))),
+            _ => Ok(()),
         }
     }
 
+    /// Store the query embedding of a common concept
+    pub fn set_query_embedding(&mut self, query: &str, embedding: Vec<f32>) -> Result<()> {
+        self.check_dimension(&embedding)?;
+        self.queries.insert(concept_key(query), embedding);
+        Ok(())
+    }
+
+    /// Precomputed embedding of a query, if it names a common concept
+    pub fn query_embedding(&self, query: &str) -> Option<&[f32]> {
+        self.queries.get(&concept_key(query)).map(Vec::as_slice)
+    }
+
     /// Add a single document to the index
-    pub fn add(&mut self, path: String, embedding: Vec<f32>) {
+    pub fn add(&mut self, path: String, embedding: Vec<f32>) -> Result<()> {
+        self.add_document(path, embedding, None)
+    }
+
+    /// Add a document with its content embedding and, when available, its title embedding
+    pub fn add_document(
+        &mut self,
+        path: String,
+        embedding: Vec<f32>,
+        title_embedding: Option<Vec<f32>>,
+    ) -> Result<()> {
+        self.add_entry(path, embedding, title_embedding, None)
+    }
+
+    /// Add a chunk with its content and title embeddings and its result metadata
+    pub fn add_chunk(
+        &mut self,
+        key: String,
+        embedding: Vec<f32>,
+        title_embedding: Vec<f32>,
+        info: ChunkInfo,
+    ) -> Result<()> {
+        self.add_entry(key, embedding, Some(title_embedding), Some(info))
+    }
+
+    fn add_entry(
+        &mut self,
+        path: String,
+        embedding: Vec<f32>,
+        title_embedding: Option<Vec<f32>>,
+        info: Option<ChunkInfo>,
+    ) -> Result<()> {
+        self.check_dimension(&embedding)?;
+        if let Some(title_embedding) = &title_embedding {
+            self.check_dimension(title_embedding)?;
+        }
+        self.reserve(1 + usize::from(title_embedding.is_some()));
+        let path_id = self.paths.intern(&path);
+        self.insert(path_id, Field::Content, embedding);
+        if let Some(title_embedding) = title_embedding {
+            self.insert(path_id, Field::Title, title_embedding);
+        }
+        if let Some(info) = info {
+            let slot = path_id as usize;
+            if self.info.len() <= slot {
+                self.info.resize(slot + 1, None);
+            }
+            self.info[slot] = Some(info);
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, path_id: PathId, field: Field, embedding: Vec<f32>) {
         let id = self.id_to_path.len();
         self.hnsw.insert((&embedding, id));
-        self.id_to_path.push(path);
+        self.record(path_id, field, id, embedding);
+    }
+
+    /// Book-keeping for a vector already inserted into the graph as `id`
+    fn record(&mut self, path_id: PathId, field: Field, id: usize, embedding: Vec<f32>) {
+        let slot = path_id as usize;
+        if self.by_path.len() <= slot {
+            self.by_path.resize(slot + 1, [None; 2]);
+        }
+        self.by_path[slot][field as usize] = Some(id);
+        self.id_to_path.push(path_id);
         self.embeddings.push(embedding);
     }
 
     /// Add multiple documents to the index
-    #[allow(dead_code)]
-    pub fn add_batch(&mut self, documents: Vec<(String, Vec<f32>)>) {
+    pub fn add_batch(&mut self, documents: Vec<(String, Vec<f32>)>) -> Result<()> {
+        for (_, embedding) in &documents {
+            self.check_dimension(embedding)?;
+        }
+        self.reserve(documents.len());
         let start_id = self.id_to_path.len();
 
         // Prepare data for parallel insertion
@@ -65,35 +376,104 @@ impl VectorIndex {
         self.hnsw.parallel_insert(&data);
 
         // Store path mappings and embeddings
-        for (path, embedding) in documents {
-            self.id_to_path.push(path);
-            self.embeddings.push(embedding);
+        for (i, (path, embedding)) in documents.into_iter().enumerate() {
+            let path_id = self.paths.intern(&path);
+            self.record(path_id, Field::Content, start_id + i, embedding);
         }
+        Ok(())
     }
 
     /// Search for similar documents
     pub fn search(&self, query_embedding: &[f32], limit: usize) -> Vec<(String, f32)> {
-        let ef_search = limit.max(32); // Search width (higher = more accurate, slower)
+        self.search_ids(query_embedding, limit)
+            .into_iter()
+            .map(|(id, similarity)| (self.path(id).to_string(), similarity))
+            .collect()
+    }
+
+    /// Search for similar documents, returning interned path ids (see `path`).
+    ///
+    /// Documents reached through either of their vectors are scored with `similarity`, so a
+    /// document found by its title is still ranked by its content too.
+    #[tracing::instrument(name = "ann_search", level = "debug", skip_all, fields(limit))]
+    pub fn search_ids(&self, query_embedding: &[f32], limit: usize) -> Vec<(PathId, f32)> {
+        if self
+            .dimension()
+            .is_some_and(|dimension| dimension != query_embedding.len())
+        {
+            tracing::warn!(
+                "Query embedding has {} dimensions, the vector index {:?}; not searching it",
+                query_embedding.len(),
+                self.dimension()
+            );
+            return Vec::new();
+        }
 
-        let neighbors = self.hnsw.search(query_embedding, limit, ef_search);
+        // A document can be reached through two vectors, so fetch twice the neighbors
+        let neighbors = limit * 2;
+        let ef_search = neighbors.max(32); // Search width (higher = more accurate, slower)
+
+        // The graph search stops at the first candidate farther than everything it has found,
+        // so it can miss distant vectors; a graph no larger than the search width is scanned
+        let ids: Vec<usize> = if self.len() <= ef_search {
+            (0..self.len()).collect()
+        } else {
+            self.hnsw
+                .search(query_embedding, neighbors, ef_search)
+                .iter()
+                .map(|neighbor| neighbor.d_id)
+                .collect()
+        };
+        let mut candidates: Vec<PathId> = Vec::new();
+        for id in ids {
+            if let Some(&path_id) = self.id_to_path.get(id)
+                && !candidates.contains(&path_id)
+            {
+                candidates.push(path_id);
+            }
+        }
 
-        neighbors
+        let mut results: Vec<(PathId, f32)> = candidates
             .into_iter()
-            .filter_map(|neighbor| {
-                let idx = neighbor.d_id;
-                if idx < self.id_to_path.len() {
-                    // Convert distance to similarity score (cosine distance -> similarity)
-                    let similarity = 1.0 - neighbor.distance;
-                    Some((self.id_to_path[idx].clone(), similarity))
-                } else {
-                    None
-                }
-            })
-            .collect()
+            .map(|path_id| (path_id, self.similarity(path_id, query_embedding)))
+            .collect();
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(limit);
+        results
+    }
+
+    /// Similarity of a document to the query: its content and title similarities weighed by
+    /// `TITLE_WEIGHT`, or the one available
+    fn similarity(&self, path_id: PathId, query_embedding: &[f32]) -> f32 {
+        let [content, title] = self.by_path[path_id as usize]
+            .map(|id| id.map(|id| cosine_similarity(query_embedding, &self.embeddings[id])));
+        match (content, title) {
+            (Some(content), Some(title)) => TITLE_WEIGHT * title + (1.0 - TITLE_WEIGHT) * content,
+            (similarity, None) | (None, similarity) => similarity.unwrap_or_default(),
+        }
+    }
+
+    /// Path of an id returned by `search_ids`
+    pub fn path(&self, id: PathId) -> &str {
+        self.paths.resolve(id)
     }
 
-    /// Get the number of documents in the index
-    #[allow(dead_code)]
+    /// Result metadata of an id returned by `search_ids`, for chunks embedded with it
+    pub fn info(&self, id: PathId) -> Option<&ChunkInfo> {
+        self.info.get(id as usize)?.as_ref()
+    }
+
+    /// Interned id of a path, if the index has a vector for it
+    pub fn path_id(&self, path: &str) -> Option<PathId> {
+        self.paths.id(path)
+    }
+
+    /// Number of distinct document paths in the index
+    pub fn path_count(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Number of vectors (content and title) in the index
     pub fn len(&self) -> usize {
         self.id_to_path.len()
     }
@@ -109,12 +489,16 @@ impl VectorIndex {
 
         // Save documents (paths + embeddings) as JSON
         let documents: Vec<StoredDocument> = self
-            .id_to_path
+            .by_path
             .iter()
-            .zip(self.embeddings.iter())
-            .map(|(path, embedding)| StoredDocument {
-                path: path.clone(),
-                embedding: embedding.clone(),
+            .enumerate()
+            .filter_map(|(path_id, [content, title])| {
+                Some(StoredDocument {
+                    path: self.path(path_id as PathId).to_string(),
+                    embedding: self.embeddings[(*content)?].clone(),
+                    title_embedding: title.map(|id| self.embeddings[id].clone()),
+                    info: self.info(path_id as PathId).cloned(),
+                })
             })
             .collect();
 
@@ -123,12 +507,54 @@ impl VectorIndex {
         serde_json::to_writer(file, &documents)
             .map_err(|e| Error::Other(format!("Failed to save vector index: {}", e)))?;
 
+        let file = std::fs::File::create(path.join(QUERIES_FILE))?;
+        serde_json::to_writer(file, &self.queries)
+            .map_err(|e| Error::Other(format!("Failed to save query embeddings: {}", e)))?;
+
+        let model_path = path.join(MODEL_FILE);
+        if let Some(model) = &self.model {
+            let file = std::fs::File::create(&model_path)?;
+            serde_json::to_writer(file, model)
+                .map_err(|e| Error::Other(format!("Failed to save vector index model: {}", e)))?;
+        } else if model_path.exists() {
+            std::fs::remove_file(&model_path)?;
+        }
+
         tracing::info!("Saved {} vectors to {:?}", documents.len(), docs_path);
         Ok(())
     }
 
+    /// Save the index to `path`, replacing the index saved there.
+    ///
+    /// The index is written next to `path` first and renamed into place, so a failed save
+    /// leaves the previous index intact.
+    pub fn save_replacing(&self, path: &Path) -> Result<()> {
+        let sibling = |suffix: &str| {
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(suffix);
+            path.with_file_name(name)
+        };
+        let staged = sibling(".new");
+        let previous = sibling(".old");
+        for dir in [&staged, &previous] {
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)?;
+            }
+        }
+
+        self.save(&staged)?;
+        if path.exists() {
+            std::fs::rename(path, &previous)?;
+        }
+        std::fs::rename(&staged, path)?;
+        if previous.exists() {
+            std::fs::remove_dir_all(&previous)?;
+        }
+        Ok(())
+    }
+
     /// Load the index from disk (rebuilds HNSW from stored embeddings)
-    pub fn load(path: &Path) -> Result<Self> {
+    pub fn load(path: &Path, capacity: usize) -> Result<Self> {
         let docs_path = path.join("vector_index.json");
 
         if !docs_path.exists() {
@@ -142,35 +568,104 @@ impl VectorIndex {
 
         tracing::info!("Loading {} vectors from {:?}", documents.len(), docs_path);
 
-        // Create new index and rebuild HNSW
-        let mut index = Self::new();
+        // Create new index sized for the stored documents and rebuild HNSW
+        let vectors = documents
+            .iter()
+            .map(|doc| 1 + usize::from(doc.title_embedding.is_some()))
+            .sum::<usize>();
+        let mut index = Self::with_capacity(capacity.max(vectors));
+        index.model = IndexModel::load(path)?;
         for doc in documents {
-            index.add(doc.path, doc.embedding);
+            index.add_entry(doc.path, doc.embedding, doc.title_embedding, doc.info)?;
+        }
+
+        // Query embeddings are an optimization; without them queries are embedded as usual
+        let queries_path = path.join(QUERIES_FILE);
+        if queries_path.exists() {
+            let file = std::fs::File::open(&queries_path)?;
+            match serde_json::from_reader::<_, HashMap<String, Vec<f32>>>(file) {
+                Ok(queries) => {
+                    for (query, embedding) in queries {
+                        if let Err(e) = index.set_query_embedding(&query, embedding) {
+                            tracing::warn!("Ignoring query embedding of {:?}: {}", query, e);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring unreadable query embeddings: {}", e),
+            }
         }
 
         Ok(index)
     }
 
     /// Load or create the index
-    pub fn open_or_create(path: &Path) -> Result<Self> {
+    pub fn open_or_create(path: &Path, capacity: usize) -> Result<Self> {
         let docs_path = path.join("vector_index.json");
 
         if docs_path.exists() {
-            Self::load(path)
+            Self::load(path, capacity)
         } else {
             std::fs::create_dir_all(path)?;
-            Ok(Self::new())
+            Ok(Self::with_capacity(capacity))
+        }
+    }
+
+    /// Keep only the documents whose path satisfies `keep`, returning how many were removed.
+    ///
+    /// HNSW graphs can't delete vectors, so the graph is rebuilt from the kept embeddings, as
+    /// loading does; the model and query embeddings stay.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) -> Result<usize> {
+        let kept: Vec<StoredDocument> = self
+            .by_path
+            .iter()
+            .enumerate()
+            .filter(|(path_id, _)| keep(self.path(*path_id as PathId)))
+            .filter_map(|(path_id, [content, title])| {
+                Some(StoredDocument {
+                    path: self.path(path_id as PathId).to_string(),
+                    embedding: self.embeddings[(*content)?].clone(),
+                    title_embedding: title.map(|id| self.embeddings[id].clone()),
+                    info: self.info(path_id as PathId).cloned(),
+                })
+            })
+            .collect();
+        let removed = self.by_path.len() - kept.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        let model = self.model.take();
+        let queries = std::mem::take(&mut self.queries);
+        self.clear();
+        self.model = model;
+        self.queries = queries;
+        for doc in kept {
+            self.add_entry(doc.path, doc.embedding, doc.title_embedding, doc.info)?;
         }
+        Ok(removed)
     }
 
-    /// Clear all documents from the index
+    /// Clear all documents, and the recorded model, from the index
     pub fn clear(&mut self) {
-        self.hnsw = Hnsw::new(16, 10000, 16, 200, DistCosine);
+        self.hnsw = Self::build_graph(self.capacity);
         self.id_to_path.clear();
+        self.by_path.clear();
+        self.paths.clear();
+        self.info.clear();
         self.embeddings.clear();
+        self.model = None;
+        self.queries.clear();
     }
 }
 
+/// Cosine similarity, the complement of the graph's `DistCosine` distance
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
 impl Default for VectorIndex {
     fn default() -> Self {
         Self::new()
@@ -186,9 +681,15 @@ mod tests {
         let mut index = VectorIndex::new();
 
         // Add some documents
-        index.add("doc1.md".to_string(), vec![1.0, 0.0, 0.0]);
-        index.add("doc2.md".to_string(), vec![0.0, 1.0, 0.0]);
-        index.add("doc3.md".to_string(), vec![0.0, 0.0, 1.0]);
+        index
+            .add("doc1.md".to_string(), vec![1.0, 0.0, 0.0])
+            .unwrap();
+        index
+            .add("doc2.md".to_string(), vec![0.0, 1.0, 0.0])
+            .unwrap();
+        index
+            .add("doc3.md".to_string(), vec![0.0, 0.0, 1.0])
+            .unwrap();
 
         assert_eq!(index.len(), 3);
 
@@ -198,6 +699,198 @@ mod tests {
         assert_eq!(results[0].0, "doc1.md");
     }
 
+    #[test]
+    fn test_vector_index_grows() {
+        let mut index = VectorIndex::with_capacity(2);
+        for i in 0..5 {
+            let mut embedding = vec![0.0; 5];
+            embedding[i] = 1.0;
+            index.add(format!("doc{}.md", i), embedding).unwrap();
+        }
+
+        assert_eq!(index.len(), 5);
+        assert!(index.capacity >= 5);
+        // Documents inserted before the graph grew are still found
+        assert_eq!(index.search(&[1.0, 0.0, 0.0, 0.0, 0.0], 1)[0].0, "doc0.md");
+        assert_eq!(index.search(&[0.0, 0.0, 0.0, 0.0, 1.0], 1)[0].0, "doc4.md");
+    }
+
+    #[test]
+    fn test_title_similarity_is_weighed_in() {
+        let mut index = VectorIndex::new();
+        // The heading matches the query, the body only partly
+        index
+            .add_document(
+                "heading.md".to_string(),
+                vec![0.6, 0.48, 0.64],
+                Some(vec![0.0, 1.0, 0.0]),
+            )
+            .unwrap();
+        // The body matches a little better, the heading not at all
+        index
+            .add_document(
+                "prose.md".to_string(),
+                vec![0.0, 0.6, 0.8],
+                Some(vec![0.0, 0.0, 1.0]),
+            )
+            .unwrap();
+        index
+            .add("untitled.md".to_string(), vec![1.0, 0.0, 0.0])
+            .unwrap();
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.path_count(), 3);
+
+        let results = index.search(&[0.0, 1.0, 0.0], 3);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0, "heading.md");
+        assert!((results[0].1 - (TITLE_WEIGHT + (1.0 - TITLE_WEIGHT) * 0.48)).abs() < 1e-3);
+        assert_eq!(results[1].0, "prose.md");
+        assert!((results[1].1 - (1.0 - TITLE_WEIGHT) * 0.6).abs() < 1e-3);
+        // Without a title vector the content similarity is used as is
+        assert_eq!(
+            index.search(&[1.0, 0.0, 0.0], 1)[0],
+            ("untitled.md".to_string(), 1.0)
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("vector-index-{}", std::process::id()));
+        let model = IndexModel {
+            id: "test-model".to_string(),
+            dimension: 2,
+            chunked: true,
+            keyed_by_source: true,
+        };
+        let mut index = VectorIndex::new();
+        index.set_model(model.clone()).unwrap();
+        index
+            .add_document("a.md".to_string(), vec![1.0, 0.0], Some(vec![0.0, 1.0]))
+            .unwrap();
+        index.add("b.md".to_string(), vec![0.0, 1.0]).unwrap();
+        index
+            .set_query_embedding("error handling", vec![0.6, 0.8])
+            .unwrap();
+        assert!(index.set_query_embedding("traits", vec![1.0]).is_err());
+        index.save(&dir).unwrap();
+
+        // Replacing the saved index leaves only the new one
+        let mut replacement = VectorIndex::new();
+        replacement.set_model(model.clone()).unwrap();
+        replacement.add("c.md".to_string(), vec![0.0, 1.0]).unwrap();
+        replacement.save_replacing(&dir).unwrap();
+        let replaced = VectorIndex::load(&dir, 10).unwrap();
+        assert_eq!(replaced.len(), 1);
+        assert!(replaced.path_id("c.md").is_some());
+        index.save_replacing(&dir).unwrap();
+
+        let loaded = VectorIndex::load(&dir, 10).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.model(), Some(&model));
+        assert_eq!(
+            loaded.query_embedding("Error-Handling?"),
+            Some([0.6, 0.8].as_slice())
+        );
+        assert!(loaded.query_embedding("traits").is_none());
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.path_count(), 2);
+        let a = loaded.path_id("a.md").unwrap();
+        assert!((loaded.similarity(a, &[0.0, 1.0]) - TITLE_WEIGHT).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_retain_removes_documents() {
+        let mut index = VectorIndex::new();
+        index
+            .add_document("a.md".to_string(), vec![1.0, 0.0], Some(vec![0.0, 1.0]))
+            .unwrap();
+        index.add("a.md#intro".to_string(), vec![0.0, 1.0]).unwrap();
+        index.add("b.md".to_string(), vec![0.6, 0.8]).unwrap();
+        index
+            .set_query_embedding("error handling", vec![0.6, 0.8])
+            .unwrap();
+
+        assert_eq!(index.retain(|path| !path.starts_with("a.md")).unwrap(), 2);
+        assert_eq!((index.len(), index.path_count()), (1, 1));
+        assert_eq!(index.search(&[1.0, 0.0], 2)[0].0, "b.md");
+        assert!(index.query_embedding("error handling").is_some());
+        assert_eq!(index.retain(|_| true).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_chunk_info_is_kept() {
+        let doc = crate::parsing::parse_markdown(
+            "# Traits\n\n## Defining a Trait\n\nA trait defines `shared` behavior.\n\nMore about traits follows here.",
+            "ch10-02-traits.md",
+            "rust-book",
+        );
+        let chunk = crate::parsing::chunk_document(&doc).remove(0);
+        let key = "rust-book/ch10-02-traits.md#defining-a-trait";
+        let mut index = VectorIndex::new();
+        index
+            .add_chunk(
+                key.to_string(),
+                vec![1.0, 0.0],
+                vec![0.0, 1.0],
+                ChunkInfo::of(&chunk),
+            )
+            .unwrap();
+        index
+            .add("rust-book/b.md".to_string(), vec![0.6, 0.8])
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("vector-index-info-{}", std::process::id()));
+        index.save(&dir).unwrap();
+        let mut loaded = VectorIndex::load(&dir, 10).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.retain(|key| key != "rust-book/b.md").unwrap(), 1);
+
+        let id = loaded.path_id(key).unwrap();
+        let result = loaded.info(id).unwrap().result(key, 40);
+        assert_eq!(result.title, "Traits");
+        assert_eq!(
+            (result.source.as_str(), result.path.as_str()),
+            ("rust-book", "ch10-02-traits.md")
+        );
+        assert_eq!(result.section, chunk.section);
+        // The snippet is cut from the stored one, keeping inline code
+        assert!(result.snippet.starts_with("## Defining a Trait"));
+        assert!(result.snippet.chars().count() <= 40);
+        assert!(loaded.info(id).unwrap().snippet.contains("`shared`"));
+    }
+
+    #[test]
+    fn test_refuses_other_dimensions() {
+        let mut index = VectorIndex::new();
+        index
+            .set_model(IndexModel {
+                id: "test-model".to_string(),
+                dimension: 3,
+                chunked: true,
+                keyed_by_source: true,
+            })
+            .unwrap();
+        assert!(index.add("a.md".to_string(), vec![1.0, 0.0]).is_err());
+        index.add("a.md".to_string(), vec![1.0, 0.0, 0.0]).unwrap();
+        assert!(
+            index
+                .add_document("b.md".to_string(), vec![1.0, 0.0, 0.0], Some(vec![1.0]))
+                .is_err()
+        );
+        assert_eq!(index.len(), 1);
+        assert!(index.search(&[1.0, 0.0], 1).is_empty());
+        assert!(
+            index
+                .set_model(IndexModel {
+                    id: "other-model".to_string(),
+                    dimension: 2,
+                    chunked: true,
+                    keyed_by_source: true,
+                })
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_vector_index_empty() {
         let index = VectorIndex::new();
@@ -1,23 +1,83 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
-use rmcp::{
-    ServerHandler,
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
-    model::{CallToolResult, Content, ServerCapabilities, ServerInfo},
-    tool, tool_handler, tool_router,
-    ErrorData as McpError,
-};
 use rmcp::schemars::{self, JsonSchema};
 use rmcp::service::ServiceExt;
 use rmcp::transport::io::stdio;
+use rmcp::{
+    ErrorData as McpError, ServerHandler,
+    handler::server::{
+        router::{prompt::PromptRouter, tool::ToolRouter},
+        tool::ToolCallContext,
+        wrapper::Parameters,
+    },
+    model::{
+        AnnotateAble, CallToolRequestParam, CallToolResult, Content, CreateMessageRequestParam,
+        GetPromptRequestParam, GetPromptResult, ListPromptsResult, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, LoggingLevel, LoggingMessageNotificationParam, Meta,
+        PaginatedRequestParam, ProgressNotificationParam, ProgressToken, PromptMessage,
+        PromptMessageRole, RawResource, RawResourceTemplate, ReadResourceRequestParam,
+        ReadResourceResult, Resource, ResourceContents, Role, SamplingMessage, ServerCapabilities,
+        ServerInfo, SetLevelRequestParam, SubscribeRequestParam, UnsubscribeRequestParam,
+    },
+    prompt, prompt_handler, prompt_router,
+    service::{NotificationContext, Peer, RequestContext, RoleServer},
+    tool, tool_router,
+};
 use serde::Deserialize;
+use tracing::Instrument;
 
-use crate::error::Result as CrateResult;
+use crate::budget::MemoryBudget;
+use crate::cargo_reference::{self, CARGO_BOOK_SOURCE, Lookup};
+use crate::cheatsheet::CheatSheet;
+use crate::clippy::{self, CLIPPY_SOURCE, ClippyLints};
+use crate::collections::{self, COLLECTION_SOURCES};
+use crate::concurrency::{self, CONCURRENCY_SOURCES};
+use crate::config::{SearchConfig, SourcesConfig};
+use crate::crate_docs::CrateDocs;
+use crate::deprecations::Deprecations;
+use crate::diagnostics::parse_compiler_output;
+use crate::editions;
+use crate::error::{Error, Result as CrateResult};
+use crate::error_codes::{ERROR_CODES_SOURCE, ErrorCodes};
+use crate::ffi;
+use crate::idioms::check_snippet;
 use crate::indexer;
-use crate::search::embeddings::init_embedding_model;
-use crate::search::{HybridSearch, SearchIndex, SearchMode, VectorIndex};
-use crate::sources::clone_all_sources;
+use crate::learning::{self, LEARNING_SOURCES};
+use crate::logging;
+use crate::macro_help::{self, MACRO_SOURCES};
+use crate::msrv::{self, Stability};
+use crate::parse_report::ParseReport;
+use crate::parsing::{CodeExample, Document, code_examples, document_key};
+use crate::platform_support::{PLATFORM_SOURCE, PlatformSupport};
+use crate::profiles::Profiles;
+use crate::prompts::{self, PASSAGE_SNIPPET_LEN};
+use crate::releases::{RELEASES_SOURCE, Releases, Version, VersionRange};
+use crate::resources::{self, DOC_URI_TEMPLATE, SessionId, Subscriptions};
+use crate::response::ResponseBudget;
+use crate::rust_reference::{self, REFERENCE_SOURCE, Target};
+use crate::rustc_flags::{FlagKind, FlagQuery, RustcFlags};
+use crate::rustlings::{RUSTLINGS_SOURCE, Rustlings};
+use crate::search::cross_encoder::Reranker;
+use crate::search::embeddings::{DEFAULT_MODEL, EmbeddingService, ModelSpec};
+use crate::search::error_patterns;
+use crate::search::language::{self, Language};
+use crate::search::pipeline::Pipeline;
+use crate::search::result_cache::ResultCache;
+use crate::search::stats::corpus_stats;
+use crate::search::{
+    Highlight, HybridSearch, QueryIntent, SearchIndex, SearchMode, SearchOptions, SearchOutcome,
+    SearchResult, Synonyms, VectorIndex,
+};
+use crate::sources::{doc_sources, get_source, pull_all_sources};
+use crate::std_json::StdJson;
+use crate::std_source::{self, STD_SOURCE};
+use crate::taxonomy::{self, CATEGORIES, Category, TAXONOMY_SOURCES};
+use crate::unsafe_review::{self, UNSAFE_CONSTRUCTS, UNSAFE_SOURCES};
+use crate::workspace::{self, RootPolicy};
 
 /// Parameters for the search_rust_docs tool
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -30,6 +90,47 @@ pub struct SearchDocsParams {
     /// Search mode: "hybrid" (default, combines keyword + semantic), "keyword" (BM25 only), or "semantic" (embedding similarity only)
     #[serde(default)]
     pub mode: Option<String>,
+    /// Minimum relevance score between 0 and 1; weaker results are dropped (default: no minimum)
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Group results by documentation source as `{source: [results...]}` (default: false)
+    #[serde(default)]
+    pub group_by_source: bool,
+    /// Maximum results from any single source, so one book can't take every slot (default, or 0: no cap)
+    #[serde(default)]
+    pub max_per_source: Option<usize>,
+    /// Words no result may mention, to steer away from irrelevant chapters (e.g. ["async"]);
+    /// `-word` in the query does the same
+    #[serde(default)]
+    pub exclude_terms: Vec<String>,
+    /// Corpus profile: "beginner", "advanced", "full", or one from the config file (default: the
+    /// server's default profile)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Rescore the top 30 results with a cross-encoder before returning the best; slower, but
+    /// better ordered for nuanced questions. The first reranked search downloads the model
+    /// (default: false)
+    #[serde(default)]
+    pub rerank: bool,
+    /// Mark the query words in snippets: "markdown" wraps them in `**`, "html" in `<b>`/`</b>`
+    /// (default: no markers)
+    #[serde(default)]
+    pub highlight: Option<String>,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
+}
+
+/// Parameters for the get_document tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetDocumentParams {
+    /// Source of a search result (e.g., "rust-book")
+    pub source: String,
+    /// Path of a search result (e.g., "ch04-01-what-is-ownership.md")
+    pub path: String,
+    /// Return at most this many lines of the document (default: the whole document)
+    #[serde(default)]
+    pub max_lines: Option<usize>,
 }
 
 /// Parameters for the explain_concept tool
@@ -40,16 +141,62 @@ pub struct ExplainConceptParams {
     /// Maximum number of documentation sections to return (default: 3)
     #[serde(default = "default_explain_limit")]
     pub limit: usize,
+    /// Minimum relevance score between 0 and 1; weaker results are dropped (default: no minimum)
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Group results by documentation source as `{source: [results...]}` (default: false)
+    #[serde(default)]
+    pub group_by_source: bool,
+    /// Maximum results from any single source, so one book can't take every slot (default, or 0: no cap)
+    #[serde(default)]
+    pub max_per_source: Option<usize>,
+    /// Words no result may mention, to steer away from irrelevant chapters (e.g. ["async"]);
+    /// `-word` in the query does the same
+    #[serde(default)]
+    pub exclude_terms: Vec<String>,
+    /// Corpus profile: "beginner", "advanced", "full", or one from the config file (default: the
+    /// server's default profile)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
 }
 
 /// Parameters for the get_best_practice tool
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetBestPracticeParams {
-    /// The topic to get best practices for (e.g., "error handling", "API design", "naming")
+    /// The topic to get best practices for (e.g., "error handling", "API design", "naming");
+    /// may be omitted when browsing a category
+    #[serde(default)]
     pub topic: String,
+    /// Category to browse in full, or to restrict the topic search to (e.g., "error-handling",
+    /// "builders", "newtypes", "ffi", "naming")
+    #[serde(default)]
+    pub category: Option<String>,
     /// Maximum number of results to return (default: 5)
     #[serde(default = "default_limit")]
     pub limit: usize,
+    /// Minimum relevance score between 0 and 1; weaker results are dropped (default: no minimum)
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Group results by documentation source as `{source: [results...]}` (default: false)
+    #[serde(default)]
+    pub group_by_source: bool,
+    /// Maximum results from any single source, so one book can't take every slot (default, or 0: no cap)
+    #[serde(default)]
+    pub max_per_source: Option<usize>,
+    /// Words no result may mention, to steer away from irrelevant chapters (e.g. ["async"]);
+    /// `-word` in the query does the same
+    #[serde(default)]
+    pub exclude_terms: Vec<String>,
+    /// Corpus profile: "beginner", "advanced", "full", or one from the config file (default: the
+    /// server's default profile)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
 }
 
 /// Parameters for the show_example tool
@@ -60,331 +207,4630 @@ pub struct ShowExampleParams {
     /// Maximum number of examples to return (default: 3)
     #[serde(default = "default_explain_limit")]
     pub limit: usize,
+    /// Minimum relevance score between 0 and 1; weaker results are dropped (default: no minimum)
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Words no result may mention, to steer away from irrelevant chapters (e.g. ["async"]);
+    /// `-word` in the query does the same
+    #[serde(default)]
+    pub exclude_terms: Vec<String>,
+    /// Corpus profile: "beginner", "advanced", "full", or one from the config file (default: the
+    /// server's default profile)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
 }
 
-fn default_limit() -> usize {
-    5
+/// Parameters for the smart_search tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SmartSearchParams {
+    /// A question or search query about Rust in natural language
+    pub query: String,
+    /// Maximum number of results to return (default: 5, max: 20)
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Minimum relevance score between 0 and 1; weaker results are dropped (default: no minimum)
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Group results by documentation source as `{source: [results...]}` (default: false)
+    #[serde(default)]
+    pub group_by_source: bool,
+    /// Maximum results from any single source, so one book can't take every slot (default, or 0: no cap)
+    #[serde(default)]
+    pub max_per_source: Option<usize>,
+    /// Words no result may mention, to steer away from irrelevant chapters (e.g. ["async"]);
+    /// `-word` in the query does the same
+    #[serde(default)]
+    pub exclude_terms: Vec<String>,
+    /// Corpus profile: "beginner", "advanced", "full", or one from the config file (default: the
+    /// server's default profile)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
 }
 
-fn default_explain_limit() -> usize {
-    3
+/// Parameters for the diagnose_compiler_output tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiagnoseParams {
+    /// Raw output from `cargo build`, `cargo check`, or `rustc`
+    pub output: String,
+    /// Documentation sections to return per distinct diagnostic (default: 3, max: 5)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
 }
 
-/// MCP Server for Rust documentation
-#[derive(Clone)]
-pub struct RustDocServer {
-    keyword_index: Arc<SearchIndex>,
-    vector_index: Arc<VectorIndex>,
-    tool_router: ToolRouter<Self>,
-    #[allow(dead_code)]
-    data_dir: PathBuf,
+/// Parameters for the idiom_check tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct IdiomCheckParams {
+    /// A short Rust code snippet to review
+    pub code: String,
+    /// Documentation sections to return per finding (default: 3, max: 5)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
 }
 
-impl RustDocServer {
-    pub async fn new(data_dir: PathBuf) -> CrateResult<Self> {
-        let index_path = data_dir.join("index");
-        let vector_index_path = index_path.join("vectors");
+/// Parameters for the cheat_sheet tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheatSheetParams {
+    /// The topic to summarize (e.g., "iterators", "smart pointers", "pattern matching")
+    pub topic: String,
+    /// Number of top documents to aggregate (default: 5, max: 10)
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
 
-        let keyword_index = SearchIndex::open_or_create(&index_path)?;
-        let mut vector_index = VectorIndex::open_or_create(&vector_index_path)?;
+/// Parameters for the learning_path tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LearningPathParams {
+    /// What the learner wants to achieve (e.g., "understand async", "learn lifetimes from scratch")
+    pub goal: String,
+    /// Chapters to recommend per step (default: 2, max: 5)
+    #[serde(default = "default_chapters_per_step")]
+    pub chapters_per_step: usize,
+    /// Include prerequisite concepts before the goal (default: true)
+    #[serde(default = "default_true")]
+    pub include_prerequisites: bool,
+}
 
-        // Index documents if the keyword index is empty
-        if keyword_index.is_empty()? {
-            tracing::info!("Index is empty, checking for documentation sources...");
+/// Parameters for the edition_migration tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EditionMigrationParams {
+    /// Current edition of the crate (e.g., "2018")
+    pub from: String,
+    /// Edition to migrate to (default: "2024")
+    #[serde(default = "default_target_edition")]
+    pub to: String,
+    /// Constructs used in the code, to highlight the changes that affect it (e.g., "extern crate and mod.rs files")
+    #[serde(default)]
+    pub constructs: Option<String>,
+}
 
-            // Auto-clone documentation sources if they don't exist
-            match clone_all_sources(&data_dir) {
-                Ok(cloned) if cloned > 0 => {
-                    tracing::info!("Cloned {} documentation sources", cloned);
-                }
-                Ok(_) => {
-                    tracing::debug!("All documentation sources already present");
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to clone some sources: {}", e);
-                }
-            }
+/// Parameters for the check_deprecation tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckDeprecationParams {
+    /// The item to check (e.g., "try!", "std::mem::uninitialized", "trim_left", "lazy_static")
+    pub item: String,
+}
 
-            // Index with both keyword and vector indices for hybrid search
-            let count = indexer::index_all_sources_hybrid(&keyword_index, &mut vector_index, &data_dir)?;
-            if count > 0 {
-                tracing::info!("Hybrid indexing complete: {} documents indexed", count);
-            } else {
-                tracing::warn!("No documentation sources found. Check network connection and try again.");
-            }
-        }
+/// Parameters for the macro_help tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MacroHelpParams {
+    /// The macro question (e.g., "which fragment specifier matches a type", "proc-macro vs macro_rules")
+    pub query: String,
+    /// Maximum number of documentation sections to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
+}
 
-        // Initialize embedding model for semantic/hybrid search
-        if !vector_index.is_empty() {
-            let models_dir = data_dir.join("models");
-            if let Err(e) = init_embedding_model(&models_dir) {
-                tracing::warn!("Failed to initialize embedding model: {}. Semantic search will be disabled.", e);
-            }
-        }
+/// Parameters for the unsafe_review tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UnsafeReviewParams {
+    /// The unsafe construct to review (e.g., "dereferencing a *mut T from FFI", "transmute u32 to char", "unsafe impl Send")
+    pub description: String,
+    /// Documentation sections to return per construct (default: 3, max: 5)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
+}
 
-        Ok(Self {
-            keyword_index: Arc::new(keyword_index),
-            vector_index: Arc::new(vector_index),
-            tool_router: Self::tool_router(),
-            data_dir,
-        })
-    }
+/// Parameters for the ffi_help tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FfiHelpParams {
+    /// The FFI question (e.g., "who frees a CString passed to C", "panic inside a C callback")
+    pub query: String,
+    /// Maximum number of documentation sections to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+    /// Response size limit
+    #[serde(flatten)]
+    pub budget: ResponseBudget,
+}
 
-    pub async fn run(self) -> anyhow::Result<()> {
-        tracing::info!("Starting rust-lang-mcp server on stdio");
-        let service = self.serve(stdio()).await?;
-        service.waiting().await?;
-        Ok(())
-    }
+/// Parameters for the concurrency_help tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConcurrencyHelpParams {
+    /// The scenario to solve (e.g., "worker threads send results back to the main thread", "a global config read by many threads")
+    pub scenario: String,
+    /// Maximum number of documentation sections to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
 }
 
-#[tool_router]
-impl RustDocServer {
-    #[tool(
-        name = "search_rust_docs",
-        description = "Search the indexed Rust documentation (The Rust Book, Rust Reference, etc.) for information about Rust concepts, syntax, and best practices. Uses hybrid search (keyword + semantic) by default for best results."
-    )]
-    async fn search_rust_docs(
-        &self,
-        Parameters(params): Parameters<SearchDocsParams>,
-    ) -> std::result::Result<CallToolResult, McpError> {
-        let limit = if params.limit == 0 {
-            5
-        } else {
-            params.limit.min(20)
-        };
+/// Parameters for the choose_collection tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ChooseCollectionParams {
+    /// The use case to serve (e.g., "ordered keys, frequent range queries", "LIFO with cheap push/pop")
+    pub use_case: String,
+    /// Maximum number of documentation sections to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+}
 
-        // Determine search mode
-        let mode = params
-            .mode
-            .as_ref()
-            .map(|s| SearchMode::from_str(s))
-            .unwrap_or_default();
+/// Parameters for the find_exercise tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindExerciseParams {
+    /// The topic or compiler error to practice (e.g., "lifetimes", "iterators", "borrowed value does not live long enough")
+    pub topic: String,
+    /// Maximum number of exercises to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+}
 
-        // Check if we can do semantic/hybrid search
-        let can_semantic = !self.vector_index.is_empty();
+/// Parameters for the clippy_lint tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClippyLintParams {
+    /// Exact lint name (e.g., "needless_range_loop", "clippy::unwrap_used", "large-enum-variant")
+    pub name: String,
+}
 
-        let results = if can_semantic {
-            let hybrid = HybridSearch::new(&self.keyword_index, &self.vector_index);
-            match mode {
-                SearchMode::Hybrid => hybrid.search(&params.query, limit),
-                SearchMode::Keyword => hybrid.keyword_search(&params.query, limit),
-                SearchMode::Semantic => hybrid.semantic_search(&params.query, limit),
-            }
-        } else {
-            // Fall back to keyword-only search
-            if !matches!(mode, SearchMode::Keyword) {
-                tracing::debug!("Vector index empty, falling back to keyword search");
-            }
-            self.keyword_index.search(&params.query, limit)
-        };
+/// Parameters for the explain_lint tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExplainLintParams {
+    /// Exact lint name (e.g., "needless_collect", "clippy::unwrap_used")
+    pub name: String,
+}
 
-        match results {
-            Ok(results) => {
-                if results.is_empty() {
-                    return Ok(CallToolResult::success(vec![Content::text(
-                        "No results found for your query. Try different keywords.",
-                    )]));
-                }
+/// Parameters for the cargo_reference tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CargoReferenceParams {
+    /// Cargo subcommand or manifest key (e.g., "cargo tree", "[patch]", "resolver = \"2\"", "package.edition")
+    pub key: String,
+}
 
-                let json_results: Vec<serde_json::Value> = results
-                    .into_iter()
-                    .map(|r| {
-                        serde_json::json!({
-                            "title": r.title,
-                            "snippet": r.snippet,
-                            "path": r.path,
-                            "source": r.source,
-                            "score": r.score,
-                        })
-                    })
-                    .collect();
+/// Parameters for the reference_section tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReferenceSectionParams {
+    /// Rule identifier, page, or anchor (e.g., "expr.match.guard", "expressions.match", "match-expr.md#match-guards", a doc.rust-lang.org/reference URL)
+    pub id: String,
+}
 
-                match serde_json::to_string_pretty(&json_results) {
-                    Ok(json) => Ok(CallToolResult::success(vec![Content::text(json)])),
-                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                        "Failed to serialize results: {}",
-                        e
-                    ))])),
-                }
-            }
-            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Search failed: {}",
-                e
-            ))])),
-        }
-    }
+/// Parameters for the reference_grammar tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReferenceGrammarParams {
+    /// Production name or part of one (e.g., "MatchArm", "match arm", "IDENTIFIER")
+    pub query: String,
+    /// Maximum number of productions to return (default: 5, max: 20)
+    #[serde(default = "default_grammar_limit")]
+    pub limit: usize,
+}
 
-    #[tool(
-        name = "explain_concept",
-        description = "Get a detailed explanation of a Rust concept. Searches The Rust Book and Rust Reference for comprehensive explanations of concepts like ownership, lifetimes, traits, borrowing, etc."
-    )]
-    async fn explain_concept(
-        &self,
-        Parameters(params): Parameters<ExplainConceptParams>,
-    ) -> std::result::Result<CallToolResult, McpError> {
-        let limit = if params.limit == 0 { 3 } else { params.limit.min(10) };
+fn default_grammar_limit() -> usize {
+    5
+}
 
-        // Search primarily in rust-book and rust-reference
-        let sources = ["rust-book", "rust-reference"];
-        let hybrid = HybridSearch::new(&self.keyword_index, &self.vector_index);
+/// Parameters for the rustc_flag tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RustcFlagParams {
+    /// Flag or flag prefix (e.g., "--edition", "-C opt", "-Cpanic=abort", "-Z sanitizer", "target-cpu")
+    pub flag: String,
+}
 
-        let results = if !self.vector_index.is_empty() {
-            hybrid.search_with_sources(&params.concept, limit, Some(&sources))
-        } else {
-            self.keyword_index.search_with_sources(&params.concept, limit, Some(&sources))
-        };
+/// Parameters for the explain_error_code tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExplainErrorCodeParams {
+    /// Compiler error code (e.g., "E0382", "e382", "error[E0499]")
+    pub code: String,
+    /// Maximum number of related book sections (default: 3, max: 5)
+    #[serde(default)]
+    pub limit: usize,
+}
 
-        match results {
-            Ok(results) => {
-                if results.is_empty() {
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "No documentation found for concept '{}'. Try a different term or check spelling.",
-                        params.concept
-                    ))]));
-                }
+/// Parameters for the platform_support tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PlatformSupportParams {
+    /// Target triple (e.g., "aarch64-unknown-linux-musl", "wasm32-unknown-unknown")
+    pub target: String,
+}
 
-                let json_results: Vec<serde_json::Value> = results
-                    .into_iter()
-                    .map(|r| {
-                        serde_json::json!({
-                            "title": r.title,
-                            "explanation": r.snippet,
-                            "path": r.path,
-                            "source": r.source,
-                        })
-                    })
-                    .collect();
+/// Parameters for the show_std_impl tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShowStdImplParams {
+    /// The std item whose implementation to show (e.g., "Option::map", "Vec::push", "mem::swap")
+    pub item: String,
+    /// Maximum number of matching items to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+}
 
-                match serde_json::to_string_pretty(&json_results) {
-                    Ok(json) => Ok(CallToolResult::success(vec![Content::text(json)])),
-                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                        "Failed to serialize results: {}", e
-                    ))])),
-                }
-            }
-            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Search failed: {}", e
-            ))])),
-        }
-    }
+/// Parameters for the lookup_std_api tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LookupStdApiParams {
+    /// Path of the std item, fully qualified or not (e.g., "std::vec::Vec::retain", "Vec::retain", "mem::swap", "HashMap")
+    pub path: String,
+    /// Maximum number of matching items to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+}
 
-    #[tool(
-        name = "get_best_practice",
-        description = "Get Rust best practices and idiomatic patterns for a topic. Searches Rust Design Patterns and API Guidelines for recommendations on error handling, API design, naming conventions, and more."
-    )]
-    async fn get_best_practice(
-        &self,
-        Parameters(params): Parameters<GetBestPracticeParams>,
-    ) -> std::result::Result<CallToolResult, McpError> {
-        let limit = if params.limit == 0 { 5 } else { params.limit.min(15) };
+/// Parameters for the lookup_crate_docs tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LookupCrateDocsParams {
+    /// Name of the crate on crates.io (e.g., "tokio", "serde_json")
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// Release to read: an exact version ("1.40.0"), a prefix for its newest release ("1", "1.40"), or "latest" (default: the newest stable release)
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Path of the item in the crate, fully qualified or not (e.g., "tokio::sync::Mutex", "sync::Mutex::lock", "Mutex")
+    pub item_path: String,
+    /// Maximum number of matching items to return (default: 3, max: 10)
+    #[serde(default = "default_explain_limit")]
+    pub limit: usize,
+}
 
-        // Search in rust-patterns, api-guidelines, and rustonomicon
-        let sources = ["rust-patterns", "api-guidelines", "rustonomicon"];
-        let hybrid = HybridSearch::new(&self.keyword_index, &self.vector_index);
+/// Parameters for the show_trait_implementors tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShowTraitImplementorsParams {
+    /// The trait, optionally with generic arguments (e.g., "Send", "From<String>", "std::error::Error")
+    #[serde(rename = "trait")]
+    pub trait_name: String,
+    /// Maximum number of implementors to return (default: 50, max: 200)
+    #[serde(default = "default_implementors_limit")]
+    pub limit: usize,
+}
 
-        let results = if !self.vector_index.is_empty() {
-            hybrid.search_with_sources(&params.topic, limit, Some(&sources))
-        } else {
-            self.keyword_index.search_with_sources(&params.topic, limit, Some(&sources))
-        };
+/// Parameters for the whats_new tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WhatsNewParams {
+    /// A Rust version or inclusive range (e.g., "1.78", "1.77.2", "1.70..1.78", "1.75..", "latest")
+    pub version: String,
+    /// Only return sections whose name contains this (e.g., "language", "stabilized", "cargo", "compatibility")
+    #[serde(default)]
+    pub section: Option<String>,
+}
 
-        match results {
-            Ok(results) => {
-                if results.is_empty() {
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "No best practices found for '{}'. Try searching for related topics like 'error handling', 'API design', or 'naming'.",
-                        params.topic
+/// Parameters for the stable_since tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StableSinceParams {
+    /// A language feature or std API (e.g., "let-else", "GATs", "OnceLock", "Option::is_some_and")
+    pub feature: String,
+}
+
+/// Parameters for the msrv_check tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MsrvCheckParams {
+    /// Language features or std APIs the code uses (e.g., ["let-else", "OnceLock", "Option::is_some_and"])
+    pub features: Vec<String>,
+    /// The project's MSRV to check against (e.g., "1.70" or the manifest's `rust-version = "1.70"`)
+    #[serde(default)]
+    pub msrv: Option<String>,
+}
+
+/// Parameters for the parse_report tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ParseReportParams {
+    /// Only report this documentation source (e.g., "rust-book"); all sources when omitted
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Parameters for the corpus_stats tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CorpusStatsParams {
+    /// Only report this documentation source (e.g., "rust-book"); all sources when omitted
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Arguments for the explain_compiler_error prompt
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExplainCompilerErrorArgs {
+    /// The compiler output or error message to explain
+    pub error: String,
+}
+
+/// Arguments for the doc_grounded_code_review prompt
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CodeReviewArgs {
+    /// The Rust code to review
+    pub code: String,
+    /// What to focus on (e.g., "error handling", "public API naming")
+    #[serde(default)]
+    pub focus: Option<String>,
+}
+
+/// Arguments for the error_handling_strategy prompt
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ErrorHandlingArgs {
+    /// What the project is and how it handles errors today (e.g., "a library parsing config
+    /// files; errors are Strings")
+    pub project: String,
+    /// Code showing the current error handling
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+/// Documentation passages retrieved per query for prompts
+const PASSAGES_PER_QUERY: usize = 2;
+
+/// Queries run per prompt, so a long error log can't fan out into many searches
+const MAX_PROMPT_QUERIES: usize = 3;
+
+/// Longest implementation returned by show_std_impl, in lines
+const MAX_IMPL_LINES: usize = 200;
+
+/// Longest documentation returned per item by lookup_std_api and lookup_crate_docs, in lines
+const MAX_API_DOC_LINES: usize = 150;
+
+/// Most releases returned by whats_new; wider ranges return the newest
+const MAX_RELEASES: usize = 12;
+
+/// Most std items and release-note entries returned by stable_since
+const MAX_STABILITY_EVIDENCE: usize = 5;
+
+/// Most features checked by one msrv_check call
+const MAX_MSRV_FEATURES: usize = 20;
+
+/// Longest Cargo Book section returned by cargo_reference, in lines
+const MAX_CARGO_SECTION_LINES: usize = 150;
+
+/// Searches asking for at least this many results stream their keyword results as a progress
+/// notification, when the client asked for progress
+const STREAM_MIN_LIMIT: usize = 10;
+
+/// Longest Reference section returned by reference_section, in lines
+const MAX_REFERENCE_SECTION_LINES: usize = 150;
+
+/// Most productions listed as using a production found by reference_grammar
+const MAX_GRAMMAR_USERS: usize = 20;
+
+/// Most flags described by rustc_flag; further prefix matches are only named
+const MAX_RUSTC_FLAGS: usize = 5;
+
+/// Documents per page of the resource list
+const RESOURCES_PAGE_SIZE: usize = 500;
+
+/// Logger name of the server's MCP logging notifications
+const LOGGER: &str = "rust-lang-mcp";
+
+/// Added to tool results while the documentation is indexed on first run
+const INDEXING_NOTE: &str = "The documentation is still being indexed for the first time, so results are missing or incomplete. The server sends its progress as log notifications; try again when it is done.";
+
+/// How often `wait_for_index` and `wait_for_semantic` check whether their stage is done
+const SEMANTIC_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Canned searches run at startup to warm the model, indices, and page cache
+const WARM_UP_QUERIES: &[&str] = &[
+    "ownership and borrowing",
+    "how to implement a trait for a generic type",
+];
+
+/// Sources searched by explain_concept
+const CONCEPT_SOURCES: &[&str] = &["rust-book", "rust-reference"];
+
+/// Sources searched by get_best_practice
+const BEST_PRACTICE_SOURCES: &[&str] = &["rust-patterns", "api-guidelines", "rustonomicon"];
+
+/// Query for the Book's and the Reference's error handling chapters, searched by the
+/// error_handling_strategy prompt
+const ERROR_HANDLING_QUERY: &str = "recoverable errors with Result, the ? operator, and panic";
+
+/// Sources searched by idiom_check
+const IDIOM_SOURCES: &[&str] = &["rust-patterns", "api-guidelines"];
+
+/// Sources searched by show_example
+const EXAMPLE_SOURCES: &[&str] = &["rust-by-example"];
+
+/// Sources searched by smart_search for WebAssembly questions
+const WASM_SOURCES: &[&str] = &["rustwasm-book"];
+
+/// `sources` and the books of the topics `query` names (see `QueryIntent::topic_sources`), so
+/// tools with fixed sources still answer async, Cargo, and compiler questions from those books
+fn with_topic_sources(sources: &[&'static str], query: &str) -> Vec<&'static str> {
+    let mut sources = sources.to_vec();
+    for source in QueryIntent::topic_sources(query) {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+fn default_limit() -> usize {
+    5
+}
+
+fn default_explain_limit() -> usize {
+    3
+}
+
+fn default_implementors_limit() -> usize {
+    50
+}
+
+fn default_chapters_per_step() -> usize {
+    2
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_target_edition() -> String {
+    "2024".to_string()
+}
+
+/// Serialize a value as pretty JSON into a tool result
+fn json_response<T: serde::Serialize>(value: &T) -> CallToolResult {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => CallToolResult::success(vec![Content::text(json)]),
+        Err(e) => CallToolResult::error(vec![Content::text(format!(
+            "Failed to serialize results: {}",
+            e
+        ))]),
+    }
+}
+
+/// Cut item documentation after `MAX_API_DOC_LINES` lines, saying how many were left out
+fn truncate_api_docs(docs: &str) -> String {
+    let lines: Vec<&str> = docs.lines().collect();
+    if lines.len() > MAX_API_DOC_LINES {
+        format!(
+            "{}\n\n... {} more lines",
+            lines[..MAX_API_DOC_LINES].join("\n"),
+            lines.len() - MAX_API_DOC_LINES
+        )
+    } else {
+        docs.to_string()
+    }
+}
+
+/// Arrange JSON results as `{source: [results...]}`, keeping ranking order within each source
+fn group_results(results: Vec<serde_json::Value>, group_by_source: bool) -> serde_json::Value {
+    if !group_by_source {
+        return serde_json::Value::Array(results);
+    }
+
+    let mut groups = serde_json::Map::new();
+    for result in results {
+        let source = result
+            .get("source")
+            .and_then(|s| s.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        if let serde_json::Value::Array(items) = groups
+            .entry(source)
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+        {
+            items.push(result);
+        }
+    }
+
+    serde_json::Value::Object(groups)
+}
+
+/// First `max_lines` lines of `text`, noting how many were cut
+fn cap_lines(text: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() <= max_lines {
+        return text.to_string();
+    }
+    format!(
+        "{}\n\n... {} more lines",
+        lines[..max_lines].join("\n"),
+        lines.len() - max_lines
+    )
+}
+
+/// Reports the keyword results of a hybrid search as a progress notification (`message` is the
+/// JSON `{"stage": "keyword", "results": [...]}`), so clients can show them while the
+/// semantic leg runs
+struct PartialResults {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+    limit: usize,
+}
+
+impl PartialResults {
+    /// A reporter for a search, if the client asked for progress and the search is slow
+    /// enough to be worth streaming
+    fn new(meta: &Meta, peer: &Peer<RoleServer>, mode: SearchMode, limit: usize) -> Option<Self> {
+        let token = meta.get_progress_token()?;
+        let streams = matches!(mode, SearchMode::Hybrid) && limit >= STREAM_MIN_LIMIT;
+        streams.then(|| Self {
+            peer: peer.clone(),
+            token,
+            limit,
+        })
+    }
+
+    /// Send the best results found so far
+    async fn report(&self, results: &[SearchResult]) {
+        let results: Vec<serde_json::Value> = results
+            .iter()
+            .take(self.limit)
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "snippet": r.snippet,
+                    "uri": resources::document_uri(&r.source, &r.path),
+                    "path": r.path,
+                    "source": r.source,
+                    "section": r.section,
+                    "score": r.score,
+                })
+            })
+            .collect();
+        let message = serde_json::json!({ "stage": "keyword", "results": results }).to_string();
+        let notification = ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress: 1.0,
+            total: Some(2.0),
+            message: Some(message),
+        };
+        if let Err(e) = self.peer.notify_progress(notification).await {
+            tracing::debug!("Failed to send partial results: {}", e);
+        }
+    }
+}
+
+/// A client told about startup progress, with the lowest level it asked for
+type LogPeer = (Peer<RoleServer>, Arc<RwLock<LoggingLevel>>);
+
+/// Clients connected while the server starts up, sent each `StartupProgress` message as an MCP
+/// logging notification (logger `rust-lang-mcp`). On first run that is minutes of cloning,
+/// indexing, and embedding, which would otherwise look like a server returning nothing.
+#[derive(Clone, Default)]
+struct StartupLog {
+    /// `None` once startup has finished
+    peers: Arc<std::sync::Mutex<Option<Vec<LogPeer>>>>,
+}
+
+impl StartupLog {
+    /// A log taking clients until the returned progress reporter is dropped, with a task
+    /// forwarding its messages in order
+    fn start() -> (Self, StartupProgress) {
+        let log = Self {
+            peers: Arc::new(std::sync::Mutex::new(Some(Vec::new()))),
+        };
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let forwarding = log.clone();
+        tokio::spawn(async move {
+            while let Some((level, message)) = receiver.recv().await {
+                forwarding.send(level, message).await;
+            }
+            *forwarding.peers.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        });
+        (log, StartupProgress(sender))
+    }
+
+    /// Send the rest of the startup progress to a client, if startup is still running
+    fn add(&self, peer: Peer<RoleServer>, level: Arc<RwLock<LoggingLevel>>) {
+        if let Some(peers) = self
+            .peers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            peers.push((peer, level));
+        }
+    }
+
+    async fn send(&self, level: LoggingLevel, message: String) {
+        let peers = self
+            .peers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .unwrap_or_default();
+        for (peer, min_level) in peers {
+            // Levels are declared from least to most severe
+            if (level as u8) < (*min_level.read().unwrap_or_else(|e| e.into_inner()) as u8) {
+                continue;
+            }
+            let notification = LoggingMessageNotificationParam {
+                level,
+                logger: Some(LOGGER.to_string()),
+                data: message.as_str().into(),
+            };
+            if let Err(e) = peer.notify_logging_message(notification).await {
+                tracing::debug!("Failed to send startup progress: {}", e);
+            }
+        }
+    }
+}
+
+/// Reports startup progress to the server log and the clients of a `StartupLog`
+struct StartupProgress(tokio::sync::mpsc::UnboundedSender<(LoggingLevel, String)>);
+
+impl StartupProgress {
+    fn info(&self, message: String) {
+        tracing::info!("{}", message);
+        let _ = self.0.send((LoggingLevel::Info, message));
+    }
+
+    fn warn(&self, message: String) {
+        tracing::warn!("{}", message);
+        let _ = self.0.send((LoggingLevel::Warning, message));
+    }
+}
+
+/// A query as it is searched: the user's words, or their English translation
+struct PreparedQuery {
+    text: String,
+    /// Language of the original query, when it isn't English
+    language: Option<&'static Language>,
+    /// Whether `text` is a translation
+    translated: bool,
+    /// Why a query in another language was searched as it is
+    note: Option<String>,
+}
+
+impl PreparedQuery {
+    /// Add the detected language, the translation, and the note to a search response
+    fn describe(&self, response: &mut serde_json::Value) {
+        if let Some(language) = self.language {
+            response["language"] = language.code.into();
+        }
+        if self.translated {
+            response["translated_query"] = self.text.as_str().into();
+        }
+        if let Some(note) = &self.note {
+            response["language_note"] = note.as_str().into();
+        }
+    }
+}
+
+/// Longest translation requested from the client
+const MAX_TRANSLATION_TOKENS: u32 = 200;
+
+/// Have the client's model translate a query into English
+async fn translate_query(peer: &Peer<RoleServer>, query: &str) -> CrateResult<String> {
+    let request = CreateMessageRequestParam {
+        messages: vec![SamplingMessage {
+            role: Role::User,
+            content: Content::text(query),
+        }],
+        model_preferences: None,
+        system_prompt: Some(
+            "Translate the user's Rust programming question into English. Keep code, identifiers, crate names, and error messages unchanged. Reply with the translation only."
+                .to_string(),
+        ),
+        include_context: None,
+        temperature: Some(0.0),
+        max_tokens: MAX_TRANSLATION_TOKENS,
+        stop_sequences: None,
+        metadata: None,
+    };
+    let result = peer
+        .create_message(request)
+        .await
+        .map_err(|e| Error::Other(e.to_string()))?;
+    let text = result
+        .message
+        .content
+        .as_text()
+        .map(|text| text.text.trim().to_string())
+        .unwrap_or_default();
+    if text.is_empty() {
+        return Err(Error::Other("the client returned no text".to_string()));
+    }
+    Ok(text)
+}
+
+/// Code examples of a Cargo Book section as `{description, code}` objects
+fn cargo_examples(markdown: &str) -> Vec<serde_json::Value> {
+    cargo_reference::examples(markdown)
+        .into_iter()
+        .map(|example| serde_json::json!({ "description": example.description, "code": example.code }))
+        .collect()
+}
+
+/// Describe how a hybrid search combined its legs (null for single-leg searches)
+fn fusion_json(outcome: &SearchOutcome) -> serde_json::Value {
+    if outcome.partial {
+        return serde_json::json!({
+            "decision": outcome.decision.as_str(),
+            "keyword_confidence": outcome.keyword_confidence,
+            "partial": true,
+            "note": "Semantic search exceeded its latency budget; these are keyword results only.",
+        });
+    }
+    match (outcome.keyword_confidence, outcome.semantic_confidence) {
+        (Some(keyword), Some(semantic)) => serde_json::json!({
+            "decision": outcome.decision.as_str(),
+            "keyword_confidence": keyword,
+            "semantic_confidence": semantic,
+        }),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// `search_rust_docs` results with the mode that ran and how its legs were fused
+fn search_json(outcome: SearchOutcome, group_by_source: bool) -> serde_json::Value {
+    let fusion = fusion_json(&outcome);
+    let results: Vec<serde_json::Value> = outcome
+        .results
+        .into_iter()
+        .map(|r| {
+            serde_json::json!({
+                "title": r.title,
+                "snippet": r.snippet,
+                "uri": resources::document_uri(&r.source, &r.path),
+                "path": r.path,
+                "source": r.source,
+                "section": r.section,
+                "score": r.score,
+                "why": r.explanation.describe(),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "mode": outcome.mode.as_str(),
+        "fusion": fusion,
+        "total_hits": outcome.total_hits,
+        "results": group_results(results, group_by_source),
+    })
+}
+
+/// How far the semantic search stage of startup has come
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SemanticStatus {
+    /// The vector index and embedding model are loading, or the documentation is being
+    /// embedded; searches use the keyword index alone
+    Loading,
+    Ready,
+    /// No vectors for the configured model, or the model failed to load
+    Unavailable,
+}
+
+impl SemanticStatus {
+    /// Name used in tool output
+    fn as_str(&self) -> &'static str {
+        match self {
+            SemanticStatus::Loading => "loading",
+            SemanticStatus::Ready => "ready",
+            SemanticStatus::Unavailable => "unavailable",
+        }
+    }
+
+    /// Note for results of a search that wanted semantic search but couldn't have it
+    fn note(&self) -> Option<&'static str> {
+        match self {
+            SemanticStatus::Loading => Some(
+                "Semantic search is still loading; these are keyword results only. Hybrid search starts when it is ready.",
+            ),
+            SemanticStatus::Ready => None,
+            SemanticStatus::Unavailable => {
+                Some("Semantic search is unavailable; these are keyword results only.")
+            }
+        }
+    }
+}
+
+/// MCP Server for Rust documentation
+#[derive(Clone)]
+pub struct RustDocServer {
+    keyword_index: Arc<SearchIndex>,
+    /// Replaced as a whole when the semantic stage finishes or the documentation is re-embedded
+    vector_index: Arc<RwLock<Arc<VectorIndex>>>,
+    semantic_status: Arc<RwLock<SemanticStatus>>,
+    /// Embedding model from the configuration
+    embedding_model: &'static ModelSpec,
+    /// The embedding model once loaded, shared by the sessions of this server
+    embeddings: Arc<OnceLock<EmbeddingService>>,
+    /// Held while the embedding model loads, so it is loaded once
+    loading_model: Arc<Mutex<()>>,
+    /// The reranking model, or why it couldn't be loaded, once a search asked for it
+    reranker: Arc<OnceLock<std::result::Result<Reranker, String>>>,
+    embedding_batch_size: usize,
+    /// Set while a re-embed or documentation update is running
+    rebuilding: Arc<AtomicBool>,
+    /// Set while the documentation is cloned and indexed on first run
+    indexing: Arc<AtomicBool>,
+    /// Set until the startup warm-up searches are done
+    warming_up: Arc<AtomicBool>,
+    /// Longest a hybrid search waits for its semantic leg
+    semantic_budget: Option<Duration>,
+    /// Outcomes of recent searches, cleared whenever an index changes
+    result_cache: Arc<ResultCache>,
+    /// Fusion, filtering, and reranking stages of every search
+    pipeline: Arc<Pipeline>,
+    /// Corpus profiles and the default one
+    profiles: Arc<Profiles>,
+    /// Whether queries in other languages are translated through client sampling
+    translate_queries: bool,
+    deprecations: Arc<Deprecations>,
+    rustlings: Arc<Rustlings>,
+    clippy: Arc<ClippyLints>,
+    releases: Arc<Releases>,
+    rustc_flags: Arc<RustcFlags>,
+    platform_support: Arc<PlatformSupport>,
+    error_codes: Arc<ErrorCodes>,
+    /// `library` directory of the indexed std sources, when configured
+    std_library: Option<PathBuf>,
+    /// Trait impls and stability from the std rustdoc JSON, when configured
+    std_json: Arc<StdJson>,
+    /// rustdoc JSON of crates.io releases, downloaded from docs.rs on first lookup
+    crate_docs: Arc<CrateDocs>,
+    /// Documentation of the client's workspace roots, rebuilt when the roots change
+    workspace_index: Arc<RwLock<Option<Arc<SearchIndex>>>>,
+    /// Which of the client's roots are indexed
+    root_policy: RootPolicy,
+    /// Resource URIs clients asked to be notified about
+    subscriptions: Subscriptions,
+    /// This session's key in `subscriptions`
+    session: SessionId,
+    /// Clients to tell about startup progress
+    startup_log: StartupLog,
+    /// Lowest level of logging notifications the client asked for
+    log_level: Arc<RwLock<LoggingLevel>>,
+    tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
+    data_dir: PathBuf,
+}
+
+impl RustDocServer {
+    pub async fn new(
+        data_dir: PathBuf,
+        budget: MemoryBudget,
+        sources: SourcesConfig,
+        embedding_model: &'static ModelSpec,
+        search: &SearchConfig,
+        profiles: Profiles,
+    ) -> CrateResult<Self> {
+        let index_path = data_dir.join("index");
+
+        let mut keyword_index = SearchIndex::open_or_create(&index_path)?;
+        keyword_index.set_synonyms(Synonyms::load(&data_dir));
+        keyword_index.set_writer_heap(budget.writer_heap_bytes);
+
+        // An empty keyword index is built in the background, so clients don't time out waiting
+        // for the first run's cloning and indexing; the documents are embedded after it
+        let indexing = keyword_index.is_empty()?;
+        let (startup_log, progress) = StartupLog::start();
+        let subscriptions = Subscriptions::default();
+        let session = subscriptions.new_session();
+
+        // Keyword search is served at once; hybrid search starts when the semantic stage is done
+        let server = Self {
+            keyword_index: Arc::new(keyword_index),
+            vector_index: Arc::new(RwLock::new(Arc::new(VectorIndex::new()))),
+            semantic_status: Arc::new(RwLock::new(SemanticStatus::Loading)),
+            embedding_model,
+            embeddings: Arc::new(OnceLock::new()),
+            loading_model: Arc::new(Mutex::new(())),
+            reranker: Arc::new(OnceLock::new()),
+            embedding_batch_size: budget.embedding_batch_size,
+            rebuilding: Arc::new(AtomicBool::new(false)),
+            indexing: Arc::new(AtomicBool::new(indexing)),
+            warming_up: Arc::new(AtomicBool::new(true)),
+            semantic_budget: search.semantic_budget(),
+            result_cache: Arc::new(search.result_cache()),
+            pipeline: Arc::new(Pipeline::default()),
+            profiles: Arc::new(profiles),
+            translate_queries: search.translate_queries(),
+            deprecations: Arc::new(Deprecations::load(&data_dir)),
+            rustlings: Arc::new(
+                get_source(RUSTLINGS_SOURCE)
+                    .map(|source| Rustlings::load(&source.repo_path(&data_dir)))
+                    .unwrap_or_default(),
+            ),
+            clippy: Arc::new(
+                get_source(CLIPPY_SOURCE)
+                    .map(|source| ClippyLints::load(&source.repo_path(&data_dir)))
+                    .unwrap_or_default(),
+            ),
+            releases: Arc::new(Releases::load(&data_dir)),
+            rustc_flags: Arc::new(RustcFlags::load(&data_dir)),
+            platform_support: Arc::new(PlatformSupport::load(&data_dir)),
+            error_codes: Arc::new(ErrorCodes::load(&data_dir)),
+            std_library: sources.std_src.as_deref().and_then(std_source::library_dir),
+            std_json: Arc::new(
+                sources
+                    .std_json
+                    .as_deref()
+                    .map(StdJson::load)
+                    .unwrap_or_default(),
+            ),
+            crate_docs: Arc::new(CrateDocs::new(&data_dir)),
+            workspace_index: Arc::new(RwLock::new(None)),
+            root_policy: RootPolicy::Any,
+            subscriptions,
+            session,
+            startup_log,
+            log_level: Arc::new(RwLock::new(LoggingLevel::Info)),
+            tool_router: Self::tool_router(),
+            prompt_router: Self::prompt_router(),
+            data_dir,
+        };
+        tracing::info!("Ranking pipeline: {}", server.pipeline.describe());
+        server.start_background_stages(
+            budget.hnsw_capacity,
+            sources.mirror,
+            sources.std_src,
+            progress,
+        );
+        Ok(server)
+    }
+
+    /// Finish starting up in the background: clone and index the documentation on first run,
+    /// warm up, then run the semantic stage. Progress goes to the log and to connected clients.
+    ///
+    /// The warm-up searches block, so they run here rather than on the async runtime.
+    fn start_background_stages(
+        &self,
+        hnsw_capacity: usize,
+        mirror: Option<String>,
+        std_src: Option<PathBuf>,
+        progress: StartupProgress,
+    ) {
+        let server = self.clone();
+        tokio::task::spawn_blocking(move || {
+            if server.is_indexing() {
+                server.index_documentation(mirror.as_deref(), std_src.as_deref(), &progress);
+                server.indexing.store(false, Ordering::Release);
+                server.result_cache.clear();
+            }
+            server.warm_up();
+            server.warming_up.store(false, Ordering::Release);
+            server.run_semantic_stage(hnsw_capacity, &progress);
+        });
+    }
+
+    /// Clone the documentation sources and build the keyword index, on first run
+    fn index_documentation(
+        &self,
+        mirror: Option<&str>,
+        std_src: Option<&Path>,
+        progress: &StartupProgress,
+    ) {
+        progress.info("Index is empty, fetching the documentation sources".to_string());
+        indexer::fetch_sources(&self.data_dir, mirror, |source| {
+            progress.info(format!("Cloning {}", source.name))
+        });
+
+        progress.info("Indexing the documentation".to_string());
+        match indexer::index_all_sources(&self.keyword_index, &self.data_dir, std_src) {
+            Ok(0) => progress.warn(
+                "No documentation sources found. Check network connection and try again."
+                    .to_string(),
+            ),
+            Ok(count) => progress.info(format!(
+                "Keyword indexing complete: {} documents indexed; keyword search is ready",
+                count
+            )),
+            Err(e) => progress.warn(format!("Failed to index the documentation: {}", e)),
+        }
+    }
+
+    /// Load the vector index and embedding model, embedding the documentation first when it has
+    /// no vectors yet, then swap them in for hybrid search.
+    ///
+    /// Searches meanwhile run on the keyword index alone and say so in their output.
+    fn run_semantic_stage(&self, hnsw_capacity: usize, progress: &StartupProgress) {
+        let started = Instant::now();
+        let status = match self.load_semantic(hnsw_capacity, progress) {
+            Ok(Some(vector_index)) => {
+                *self.vector_index.write().unwrap_or_else(|e| e.into_inner()) =
+                    Arc::new(vector_index);
+                self.result_cache.clear();
+                progress.info(format!(
+                    "Semantic search ready after {:?}",
+                    started.elapsed()
+                ));
+                SemanticStatus::Ready
+            }
+            Ok(None) => SemanticStatus::Unavailable,
+            Err(e) => {
+                progress.warn(format!("Failed to load semantic search: {}", e));
+                SemanticStatus::Unavailable
+            }
+        };
+        self.set_semantic_status(status);
+        if status == SemanticStatus::Ready {
+            self.warm_up();
+        }
+    }
+
+    /// The vector index with the embedding model loaded, or `None` when semantic search is
+    /// unavailable (no documents, another model's vectors, or a model that fails to load)
+    fn load_semantic(
+        &self,
+        hnsw_capacity: usize,
+        progress: &StartupProgress,
+    ) -> CrateResult<Option<VectorIndex>> {
+        let mut vector_index = VectorIndex::open_or_create(
+            &self.data_dir.join("index").join("vectors"),
+            hnsw_capacity,
+        )?;
+
+        // Freshly indexed documentation has no vectors yet; vectors saved before documents
+        // were split into sections, or before keys carried the source, can't be matched to
+        // sections
+        if !vector_index.is_empty()
+            && !vector_index
+                .model()
+                .is_some_and(|model| model.has_current_keys())
+        {
+            tracing::info!("The vector index predates the current chunk keys, re-embedding");
+            vector_index.clear();
+        }
+        if vector_index.is_empty() {
+            if self.keyword_index.is_empty()? {
+                return Ok(None);
+            }
+            progress.info("Embedding the documentation for semantic search".to_string());
+            let (embedded, stats) = indexer::reembed(
+                &self.keyword_index,
+                &self.data_dir,
+                self.embedding_batch_size,
+                &self.load_embeddings()?,
+            )?;
+            progress.info(format!(
+                "Embedded {} sections ({} embeddings cached, {} computed)",
+                stats.sections, stats.cached, stats.computed
+            ));
+            vector_index = embedded;
+        }
+
+        // Query embeddings of one model can't be compared with another model's vectors; indices
+        // that don't record their model were built with the default one
+        let built_with = vector_index
+            .model()
+            .map_or(DEFAULT_MODEL, |model| model.id.as_str())
+            .to_string();
+        if built_with != self.embedding_model.id {
+            tracing::warn!(
+                "The vector index was built with {}, but embeddings.model is {}; semantic search is disabled. \
+                 To re-embed the documentation with {}, run `rust-lang-mcp reembed` or call the reembed tool, \
+                 or set embeddings.model back to {}.",
+                built_with,
+                self.embedding_model.id,
+                self.embedding_model.id,
+                built_with
+            );
+            return Ok(None);
+        }
+
+        // Load the embedding model for semantic/hybrid search
+        if let Err(e) = self.load_embeddings() {
+            tracing::warn!(
+                "Failed to initialize embedding model: {}. Semantic search will be disabled.",
+                e
+            );
+            return Ok(None);
+        }
+        Ok(Some(vector_index))
+    }
+
+    /// The configured embedding model, loading it (and downloading it if necessary) on first use.
+    /// Blocks, so it runs on the blocking pool.
+    fn load_embeddings(&self) -> CrateResult<EmbeddingService> {
+        let _loading = self.loading_model.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(embeddings) = self.embeddings.get() {
+            return Ok(embeddings.clone());
+        }
+        let embeddings =
+            EmbeddingService::load(&self.data_dir.join("models"), self.embedding_model)?;
+        let _ = self.embeddings.set(embeddings.clone());
+        Ok(embeddings)
+    }
+
+    /// The reranking model, loading it (and downloading it if necessary) on first use. A load
+    /// failure is kept, so later searches don't retry the download. Blocks, so it runs on the
+    /// blocking pool.
+    fn load_reranker(&self) -> std::result::Result<&Reranker, &str> {
+        self.reranker
+            .get_or_init(|| {
+                Reranker::load(&self.data_dir.join("models")).map_err(|e| {
+                    tracing::warn!("Failed to load the reranking model: {}", e);
+                    e.to_string()
+                })
+            })
+            .as_ref()
+            .map_err(String::as_str)
+    }
+
+    /// The embedding model, if it is loaded
+    fn embeddings(&self) -> Option<&EmbeddingService> {
+        self.embeddings.get()
+    }
+
+    /// How far the semantic search stage has come
+    fn semantic_status(&self) -> SemanticStatus {
+        *self
+            .semantic_status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the documentation is being cloned and indexed on first run
+    pub fn is_indexing(&self) -> bool {
+        self.indexing.load(Ordering::Acquire)
+    }
+
+    /// Wait until first-run indexing is done
+    pub async fn wait_for_index(&self) {
+        while self.is_indexing() {
+            tokio::time::sleep(SEMANTIC_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Wait until the semantic stage is done, returning whether semantic search is ready
+    pub async fn wait_for_semantic(&self) -> bool {
+        while self.semantic_status() == SemanticStatus::Loading {
+            tokio::time::sleep(SEMANTIC_POLL_INTERVAL).await;
+        }
+        self.semantic_status() == SemanticStatus::Ready
+    }
+
+    /// Why a search in `mode` returned keyword results only, if semantic search isn't ready
+    pub fn semantic_note(&self, mode: SearchMode) -> Option<&'static str> {
+        match mode {
+            SearchMode::Keyword => None,
+            SearchMode::Hybrid | SearchMode::Semantic => self.semantic_status().note(),
+        }
+    }
+
+    /// Make a query in another language searchable in the English documentation.
+    ///
+    /// A multilingual embedding model matches it as it is; otherwise the client is asked to
+    /// translate it, if it supports sampling. A query that can't be translated is searched as
+    /// it is, with a note suggesting how to do better.
+    async fn prepare_query(&self, query: &str, peer: &Peer<RoleServer>) -> PreparedQuery {
+        let mut prepared = PreparedQuery {
+            text: query.to_string(),
+            language: language::detect(query),
+            translated: false,
+            note: None,
+        };
+        let Some(language) = prepared.language else {
+            return prepared;
+        };
+        let multilingual = self.semantic_status() == SemanticStatus::Ready
+            && self
+                .embeddings()
+                .is_some_and(|embeddings| embeddings.model().multilingual);
+        if multilingual {
+            return prepared;
+        }
+
+        let supports_sampling = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.sampling.is_some());
+        if self.translate_queries && supports_sampling {
+            match translate_query(peer, query).await {
+                Ok(text) => {
+                    prepared.text = text;
+                    prepared.translated = true;
+                    return prepared;
+                }
+                Err(e) => tracing::warn!("Failed to translate {} query: {}", language.name, e),
+            }
+        }
+        prepared.note = Some(format!(
+            "The query looks like {}, but the documentation is in English, so few results may match. Rephrase it in English, or configure the multilingual-e5-small embedding model.",
+            language.name
+        ));
+        prepared
+    }
+
+    fn set_semantic_status(&self, status: SemanticStatus) {
+        *self
+            .semantic_status
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = status;
+    }
+
+    /// Run a few canned searches, once the keyword index is built and again once semantic search
+    /// is ready.
+    ///
+    /// The first query otherwise pays for ONNX session initialization, tantivy reader and
+    /// segment loading, and a cold page cache, and is several times slower than the rest.
+    fn warm_up(&self) {
+        let started = Instant::now();
+        let vector_index = self.vector_index();
+        // No semantic budget: warm-up waits for the cold model
+        let hybrid = HybridSearch::new(&self.keyword_index, &vector_index)
+            .with_embeddings(self.embeddings());
+        let options = SearchOptions::new(5);
+
+        // Hybrid mode embeds each query, warming the model along with both indices
+        for query in WARM_UP_QUERIES {
+            if let Err(e) = hybrid.search_with_mode(query, SearchMode::Hybrid, &options) {
+                tracing::debug!("Warm-up search {:?} failed: {}", query, e);
+            }
+        }
+        tracing::info!("Warm-up finished in {:?}", started.elapsed());
+    }
+
+    /// Index and model state for readiness probes
+    pub fn readiness(&self) -> serde_json::Value {
+        let sections = self.keyword_index.num_docs().unwrap_or(0);
+        let warming_up = self.warming_up.load(Ordering::Acquire);
+        serde_json::json!({
+            "ready": sections > 0 && !self.is_indexing() && !warming_up,
+            "indexing": self.is_indexing(),
+            "warming_up": warming_up,
+            "sections": sections,
+            "vectors": self.vector_index().len(),
+            "semantic_search": self.embeddings().is_some(),
+            "semantic_status": self.semantic_status().as_str(),
+            "embedding_model": self.embeddings().map(|embeddings| embeddings.model().id),
+        })
+    }
+
+    /// Search for the REST endpoint: `search_rust_docs` without workspace documents,
+    /// translation, or progress notifications. An empty result list is not an error.
+    pub fn search_documentation(
+        &self,
+        query: &str,
+        mode: Option<&str>,
+        limit: usize,
+        profile: Option<&str>,
+    ) -> CrateResult<serde_json::Value> {
+        let limit = if limit == 0 { 5 } else { limit.min(20) };
+        let mode = mode.map(SearchMode::from).unwrap_or_default();
+        let options = self.profiles.apply(profile, SearchOptions::new(limit))?;
+
+        let vector_index = self.vector_index();
+        let (_intent, outcome) = self
+            .hybrid_search(&vector_index)
+            .search_routed(query, mode, &options)?;
+        let mut response = search_json(outcome, false);
+        if let Some(note) = self.semantic_note(mode) {
+            response["note"] = note.into();
+        }
+        Ok(response)
+    }
+
+    /// Search for the REPL: routed across all sources, like `search_rust_docs`
+    pub fn search(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        limit: usize,
+    ) -> CrateResult<SearchOutcome> {
+        let vector_index = self.vector_index();
+        let (_intent, outcome) = self.hybrid_search(&vector_index).search_routed(
+            query,
+            mode,
+            &SearchOptions::new(limit),
+        )?;
+        Ok(outcome)
+    }
+
+    /// Explanations of a concept from the Book and the Reference, as `explain_concept` finds them
+    pub fn explain(&self, concept: &str, limit: usize) -> CrateResult<Vec<SearchResult>> {
+        self.search_in(concept, CONCEPT_SOURCES, limit)
+    }
+
+    /// Rust by Example pages for a topic, as `show_example` finds them
+    pub fn examples(&self, topic: &str, limit: usize) -> CrateResult<Vec<SearchResult>> {
+        self.search_in(topic, EXAMPLE_SOURCES, limit)
+    }
+
+    /// Code blocks of a result's section, from its stored chunk
+    fn section_examples(&self, result: &SearchResult) -> Vec<CodeExample> {
+        match self.keyword_index.get_chunk(&result.chunk_key()) {
+            Ok(Some(chunk)) => code_examples(&chunk.markdown),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Failed to load the code of {}: {}", result.chunk_key(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Hybrid search within `sources`
+    fn search_in(
+        &self,
+        query: &str,
+        sources: &[&str],
+        limit: usize,
+    ) -> CrateResult<Vec<SearchResult>> {
+        let vector_index = self.vector_index();
+        let options = SearchOptions::new(limit).with_sources(Some(sources));
+        let outcome = self.hybrid_search(&vector_index).search_with_mode(
+            query,
+            SearchMode::Hybrid,
+            &options,
+        )?;
+        Ok(outcome.results)
+    }
+
+    /// An indexed document, from the workspace index for workspace documents
+    pub fn document(&self, source: &str, path: &str) -> CrateResult<Option<Document>> {
+        if source == workspace::WORKSPACE_SOURCE {
+            return match self.workspace_index() {
+                Some(index) => index.get_document(source, path),
+                None => Ok(None),
+            };
+        }
+        self.keyword_index.get_document(source, path)
+    }
+
+    /// Every indexed document as a resource, the workspace's last
+    fn document_resources(&self) -> CrateResult<Vec<Resource>> {
+        let mut titles = self.keyword_index.document_titles()?;
+        if let Some(index) = self.workspace_index() {
+            titles.extend(index.document_titles()?);
+        }
+        Ok(titles
+            .into_iter()
+            .map(|(source, path, title)| {
+                let description = get_source(&source).map(|source| source.name.to_string());
+                RawResource {
+                    uri: resources::document_uri(&source, &path),
+                    name: document_key(&source, &path),
+                    title: Some(title),
+                    description,
+                    mime_type: Some("text/markdown".to_string()),
+                    size: None,
+                    icons: None,
+                    meta: None,
+                }
+                .no_annotation()
+            })
+            .collect())
+    }
+
+    /// An indexed document as JSON, for the REST endpoint
+    pub fn document_json(
+        &self,
+        source: &str,
+        path: &str,
+    ) -> CrateResult<Option<serde_json::Value>> {
+        let Some(doc) = self.document(source, path)? else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::json!({
+            "title": doc.title,
+            "uri": resources::document_uri(&doc.source, &doc.path),
+            "path": doc.path,
+            "source": doc.source,
+            "headings": doc.headings,
+            "markdown": resources::render_document(&doc),
+        })))
+    }
+
+    /// A handle for a new network session: shares the indices, but not the workspace roots,
+    /// and indexes only the roots `root_policy` allows
+    pub fn for_session(&self, root_policy: &RootPolicy) -> Self {
+        Self {
+            workspace_index: Arc::new(RwLock::new(None)),
+            root_policy: root_policy.clone(),
+            session: self.subscriptions.new_session(),
+            log_level: Arc::new(RwLock::new(LoggingLevel::Info)),
+            ..self.clone()
+        }
+    }
+
+    /// Hybrid search over the documentation with the server's embedding model and the configured
+    /// semantic budget and pipeline
+    fn hybrid_search<'a>(&'a self, vector_index: &'a VectorIndex) -> HybridSearch<'a> {
+        HybridSearch::new(&self.keyword_index, vector_index)
+            .with_embeddings(self.embeddings())
+            .with_semantic_budget(self.semantic_budget)
+            .with_cache(Some(&self.result_cache))
+            .with_pipeline(&self.pipeline)
+    }
+
+    /// Run `search` on the blocking pool with a hybrid search over the current vector index.
+    /// Embedding the query and waiting for it block, so searches stay off the async workers;
+    /// the search starts before the returned future is awaited.
+    fn search_blocking<R, F>(
+        &self,
+        search: F,
+    ) -> impl Future<Output = std::result::Result<R, McpError>>
+    where
+        R: Send + 'static,
+        F: for<'a> FnOnce(HybridSearch<'a>) -> R + Send + 'static,
+    {
+        let server = self.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            let vector_index = server.vector_index();
+            search(server.hybrid_search(&vector_index))
+        });
+        async move {
+            task.await
+                .map_err(|e| McpError::internal_error(format!("Search task failed: {}", e), None))
+        }
+    }
+
+    /// A hybrid search in `mode`, run on the blocking pool
+    async fn run_search(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        options: &SearchOptions,
+    ) -> std::result::Result<CrateResult<SearchOutcome>, McpError> {
+        let (query, options) = (query.to_string(), options.clone());
+        self.search_blocking(move |hybrid| hybrid.search_with_mode(&query, mode, &options))
+            .await
+    }
+
+    /// Passages of `sources` for a prompt about `query`
+    async fn prompt_passages(
+        &self,
+        query: &str,
+        sources: &[&str],
+    ) -> std::result::Result<Vec<SearchResult>, McpError> {
+        let options = SearchOptions::new(PASSAGES_PER_QUERY * 2)
+            .with_sources(Some(sources))
+            .with_snippet_len(PASSAGE_SNIPPET_LEN);
+        self.run_search(query, SearchMode::Hybrid, &options)
+            .await?
+            .map(|outcome| outcome.results)
+            .map_err(|e| McpError::internal_error(format!("Search failed: {}", e), None))
+    }
+
+    /// Current vector index
+    fn vector_index(&self) -> Arc<VectorIndex> {
+        self.vector_index
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Current workspace index, if the client shared roots with documentation
+    fn workspace_index(&self) -> Option<Arc<SearchIndex>> {
+        self.workspace_index
+            .read()
+            .ok()
+            .and_then(|index| index.clone())
+    }
+
+    /// The Clippy lint named `name`, or the response explaining why there is none
+    fn find_clippy_lint(&self, name: &str) -> std::result::Result<&clippy::Lint, CallToolResult> {
+        if self.clippy.is_empty() {
+            return Err(CallToolResult::error(vec![Content::text(
+                "Clippy lints are not available. The rust-clippy repository is cloned with the other documentation sources when the index is built; delete the index directory to rebuild it.",
+            )]));
+        }
+
+        self.clippy.get(name).ok_or_else(|| {
+            let similar = self.clippy.similar(name, 5);
+            let suggestion = if similar.is_empty() {
+                "Use search_rust_docs to find lints by description.".to_string()
+            } else {
+                format!("Did you mean: {}?", similar.join(", "))
+            };
+            CallToolResult::success(vec![Content::text(format!(
+                "No Clippy lint named '{}'. {}",
+                name, suggestion
+            ))])
+        })
+    }
+
+    /// Cargo Book section whose heading matches one of `keys` exactly, trying the keys in
+    /// order; headings that start with a key ("Resolver versions") are the fallback
+    fn find_cargo_section(
+        &self,
+        keys: &[String],
+    ) -> CrateResult<Option<(Document, cargo_reference::Section)>> {
+        let options = SearchOptions::new(20).with_sources(Some(&[CARGO_BOOK_SOURCE]));
+        let mut candidates: Vec<(String, Vec<Document>)> = Vec::with_capacity(keys.len());
+        for key in keys {
+            let query = key.replace(['.', '-', '_'], " ");
+            let documents = self.keyword_index.search_documents(&query, &options)?;
+            candidates.push((key.clone(), documents));
+        }
+
+        let find = [
+            cargo_reference::find_section,
+            cargo_reference::find_section_starting,
+        ];
+        for find in find {
+            for (key, documents) in &candidates {
+                for doc in documents {
+                    if let Some(section) = find(&doc.markdown, key) {
+                        return Ok(Some((doc.clone(), section)));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Pages of the Rust Reference sorted by path, or a tool error when it is not indexed
+    fn reference_pages(&self) -> std::result::Result<Vec<Document>, CallToolResult> {
+        let mut pages = self
+            .keyword_index
+            .documents_in_source(REFERENCE_SOURCE)
+            .map_err(|e| {
+                CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read the Reference: {}",
+                    e
+                ))])
+            })?;
+        if pages.is_empty() {
+            return Err(CallToolResult::error(vec![Content::text(
+                "The Rust Reference is not indexed. It is cloned with the other sources when the index is built; delete the index directory to rebuild it.",
+            )]));
+        }
+        pages.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(pages)
+    }
+
+    /// Every entry of a best-practice category, in reading order per source
+    fn browse_best_practices(
+        &self,
+        category: &Category,
+        budget: &ResponseBudget,
+    ) -> CallToolResult {
+        let mut documents = match self.keyword_index.documents_in_category(category.id) {
+            Ok(documents) => documents,
+            Err(e) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read the index: {}",
+                    e
+                ))]);
+            }
+        };
+        if documents.is_empty() {
+            return CallToolResult::success(vec![Content::text(format!(
+                "No entries in category '{}'. Categories are assigned when the index is built; delete the index directory to rebuild it.",
+                category.id
+            ))]);
+        }
+        documents.sort_by(|a, b| {
+            let source_rank =
+                |doc: &Document| TAXONOMY_SOURCES.iter().position(|s| *s == doc.source);
+            (
+                source_rank(a),
+                a.summary_order.unwrap_or(usize::MAX),
+                &a.path,
+            )
+                .cmp(&(
+                    source_rank(b),
+                    b.summary_order.unwrap_or(usize::MAX),
+                    &b.path,
+                ))
+        });
+
+        let entries: Vec<serde_json::Value> = documents
+            .iter()
+            .map(|doc| {
+                serde_json::json!({
+                    "title": doc.title,
+                    "summary": taxonomy::summary(&doc.markdown),
+                    "source": doc.source,
+                    "path": doc.path,
+                    "checklist_ids": doc.checklist_ids,
+                    "uri": resources::document_uri(&doc.source, &doc.path),
+                })
+            })
+            .collect();
+        budget.fit(json_response(&serde_json::json!({
+            "category": category.id,
+            "name": category.name,
+            "count": entries.len(),
+            "results": entries,
+        })))
+    }
+
+    /// Locations (`file:line`) of indexed std items named `item`, best matches first
+    fn find_std_items(&self, item: &str) -> CrateResult<Vec<String>> {
+        // Search by the name's segments, then keep the documents whose item name matches
+        let wanted = std_source::normalize_item(item);
+        let query = wanted.replace("::", " ");
+        let options = SearchOptions::new(50).with_sources(Some(&[STD_SOURCE]));
+        let mut matches: Vec<(usize, String)> = self
+            .keyword_index
+            .search_documents(&query, &options)?
+            .into_iter()
+            .filter_map(|doc| Some((std_source::match_rank(&doc.title, &wanted)?, doc.path)))
+            .collect();
+        // Stable, so equally good names keep their search order
+        matches.sort_by_key(|(rank, _)| *rank);
+        Ok(matches.into_iter().map(|(_, location)| location).collect())
+    }
+
+    /// Ask the client for its roots and re-index their documentation
+    async fn refresh_workspace(&self, peer: &Peer<RoleServer>) {
+        let supports_roots = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        if !supports_roots {
+            return;
+        }
+
+        let roots = match peer.list_roots().await {
+            Ok(result) => result.roots,
+            Err(e) => {
+                tracing::warn!("Failed to list client roots: {}", e);
+                return;
+            }
+        };
+        let paths = self
+            .root_policy
+            .paths(roots.iter().map(|root| root.uri.as_str()));
+        if paths.len() < roots.len() {
+            tracing::info!(
+                "Not indexing {} client roots: not local, or not allowed for this session",
+                roots.len() - paths.len()
+            );
+        }
+
+        let index = if paths.is_empty() {
+            None
+        } else {
+            match tokio::task::spawn_blocking(move || workspace::index_roots(&paths)).await {
+                Ok(Ok((index, count))) => {
+                    tracing::info!(
+                        "Indexed {} workspace documents from {} roots",
+                        count,
+                        roots.len()
+                    );
+                    Some(Arc::new(index))
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Failed to index workspace documentation: {}", e);
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!("Workspace indexing task failed: {}", e);
+                    None
+                }
+            }
+        };
+
+        if let Ok(mut current) = self.workspace_index.write() {
+            *current = index;
+        }
+        // Every session has its own workspace index, so only this one's subscribers are told
+        self.subscriptions
+            .notify_session_source_updated(self.session, workspace::WORKSPACE_SOURCE)
+            .await;
+    }
+
+    pub async fn run(self) -> anyhow::Result<()> {
+        tracing::info!("Starting rust-lang-mcp server on stdio");
+        let service = self.serve(stdio()).await?;
+        service.waiting().await?;
+        tracing::info!("stdio client disconnected");
+        Ok(())
+    }
+}
+
+#[tool_router]
+impl RustDocServer {
+    #[tool(
+        name = "search_rust_docs",
+        description = "Search the indexed Rust documentation (The Rust Book, Rust Reference, etc.) for information about Rust concepts, syntax, and best practices. Uses hybrid search (keyword + semantic) by default for best results. Results are biased towards the sources that fit the kind of question (concept, example, API lookup, best practice)."
+    )]
+    async fn search_rust_docs(
+        &self,
+        Parameters(params): Parameters<SearchDocsParams>,
+        meta: Meta,
+        peer: Peer<RoleServer>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            5
+        } else {
+            params.limit.min(20)
+        };
+
+        // Determine search mode
+        let mode = params
+            .mode
+            .as_ref()
+            .map(|s| SearchMode::from(s.as_str()))
+            .unwrap_or_default();
+
+        let highlight = match params.highlight.as_deref() {
+            None => None,
+            Some("markdown") => Some(Highlight::markdown()),
+            Some("html") => Some(Highlight::html()),
+            Some(other) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Unknown highlight style '{}'; use \"markdown\" or \"html\"",
+                    other
+                ))]));
+            }
+        };
+
+        let options = SearchOptions::new(limit)
+            .with_min_score(params.min_score)
+            .with_max_per_source(params.max_per_source)
+            .with_exclude_terms(&params.exclude_terms)
+            .with_rerank(params.rerank)
+            .with_highlight(highlight);
+        let options = match self.profiles.apply(params.profile.as_deref(), options) {
+            Ok(options) => options,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+
+        let query = self.prepare_query(&params.query, &peer).await;
+
+        // Classify the query and bias ranking towards the sources suited to it. The search
+        // blocks, so it runs on the blocking pool and sends its keyword results back over a
+        // channel, to be reported from here while the semantic leg runs.
+        let partial = PartialResults::new(&meta, &peer, mode, limit);
+        let (partials, mut partial_results) = tokio::sync::mpsc::unbounded_channel();
+        let streams = partial.is_some();
+        let workspace_index = self.workspace_index();
+        let text = query.text.clone();
+        let server = self.clone();
+        let search = self.search_blocking(move |hybrid| {
+            let report = |results: &[SearchResult]| {
+                let _ = partials.send(results.to_vec());
+            };
+            // The cross-encoder is loaded (and downloaded) by the first search asking for it
+            let reranker = options.rerank.then(|| server.load_reranker());
+            let options = SearchOptions {
+                rerank: matches!(reranker, Some(Ok(_))),
+                ..options
+            };
+            let outcome = hybrid
+                .with_workspace(workspace_index.as_deref())
+                .with_reranker(reranker.and_then(|reranker| reranker.ok()))
+                .with_partial_results(streams.then_some(&report as &dyn Fn(&[SearchResult])))
+                .search_routed(&text, mode, &options)
+                .map(|(_intent, outcome)| outcome);
+            let rerank_error = reranker.and_then(|reranker| reranker.err().map(str::to_string));
+            (outcome, rerank_error)
+        });
+        // The channel closes when the search is done, so no report arrives after the result
+        while let Some(results) = partial_results.recv().await {
+            if let Some(partial) = &partial {
+                partial.report(&results).await;
+            }
+        }
+        let (outcome, rerank_error) = search.await?;
+
+        match outcome {
+            Ok(outcome) => {
+                if outcome.results.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        "No results found for your query. Try different keywords.",
+                    )]));
+                }
+
+                let reranked = outcome.reranked;
+                let mut response = search_json(outcome, params.group_by_source);
+                query.describe(&mut response);
+                if let Some(note) = self.semantic_note(mode) {
+                    response["note"] = note.into();
+                }
+                if params.rerank {
+                    response["reranked"] = reranked.into();
+                    if let Some(e) = rerank_error {
+                        response["rerank_note"] = format!(
+                            "The reranking model could not be loaded ({}); results are in fused order.",
+                            e
+                        )
+                        .into();
+                    }
+                }
+                Ok(params.budget.fit(json_response(&response)))
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        name = "get_document",
+        description = "Fetch the full text of an indexed document by the source and path of a search result (from search_rust_docs or any other search tool), to read a whole chapter instead of its snippet. Returns the title, headings, resource URI, and the document as Markdown."
+    )]
+    async fn get_document(
+        &self,
+        Parameters(params): Parameters<GetDocumentParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        match self.document_json(&params.source, &params.path) {
+            Ok(Some(mut document)) => {
+                if let Some(max_lines) = params.max_lines
+                    && let Some(markdown) = document["markdown"].as_str()
+                {
+                    document["markdown"] = cap_lines(markdown, max_lines.max(1)).into();
+                }
+                Ok(json_response(&document))
+            }
+            Ok(None) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "No document '{}' in source '{}'. Pass the path and source of a search result as they were returned, e.g. path 'ch04-01-what-is-ownership.md' with source 'rust-book'.",
+                params.path, params.source
+            ))])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Lookup failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        name = "explain_concept",
+        description = "Get a detailed explanation of a Rust concept. Searches The Rust Book and Rust Reference for comprehensive explanations of concepts like ownership, lifetimes, traits, borrowing, etc."
+    )]
+    async fn explain_concept(
+        &self,
+        Parameters(params): Parameters<ExplainConceptParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        // Search primarily in rust-book and rust-reference
+        let sources = with_topic_sources(CONCEPT_SOURCES, &params.concept);
+
+        let options = SearchOptions::new(limit)
+            .with_sources(Some(&sources))
+            .with_min_score(params.min_score)
+            .with_max_per_source(params.max_per_source)
+            .with_exclude_terms(&params.exclude_terms);
+        let options = match self.profiles.apply(params.profile.as_deref(), options) {
+            Ok(options) => options,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+        let results = self
+            .run_search(&params.concept, SearchMode::Hybrid, &options)
+            .await?
+            .map(|outcome| (outcome.results, outcome.total_hits));
+
+        match results {
+            Ok((results, total_hits)) => {
+                if results.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No documentation found for concept '{}'. Try a different term or check spelling.",
+                        params.concept
+                    ))]));
+                }
+
+                let json_results: Vec<serde_json::Value> = results
+                    .into_iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "title": r.title,
+                            "explanation": r.snippet,
+                            "path": r.path,
+                            "source": r.source,
+                            "why": r.explanation.describe(),
+                        })
+                    })
+                    .collect();
+
+                Ok(params.budget.fit(json_response(&serde_json::json!({
+                    "total_hits": total_hits,
+                    "results": group_results(json_results, params.group_by_source),
+                }))))
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        name = "get_best_practice",
+        description = "Get Rust best practices and idiomatic patterns for a topic. Searches Rust Design Patterns and API Guidelines for recommendations on error handling, API design, naming conventions, and more. API Guidelines results include their checklist IDs (e.g. C-CASE), which can also be searched for directly. Pass a category (error-handling, builders, newtypes, ffi, naming, conversions, ownership, resources, traits-generics, documentation, macros, future-proofing, anti-patterns) without a topic to list every entry in it."
+    )]
+    async fn get_best_practice(
+        &self,
+        Parameters(params): Parameters<GetBestPracticeParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            5
+        } else {
+            params.limit.min(15)
+        };
+
+        let category_ids = || {
+            CATEGORIES
+                .iter()
+                .map(|category| category.id)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let category = match params.category.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => match taxonomy::get_category(name) {
+                Some(category) => Some(category),
+                None => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Unknown category '{}'. Categories: {}.",
+                        name,
+                        category_ids()
+                    ))]));
+                }
+            },
+            _ => None,
+        };
+        if params.topic.trim().is_empty() {
+            return match category {
+                Some(category) => Ok(self.browse_best_practices(category, &params.budget)),
+                None => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Give a topic to search, or a category to browse: {}.",
+                    category_ids()
+                ))])),
+            };
+        }
+        // Entries of the category, to filter the topic search by
+        let in_category = match category {
+            Some(category) => match self.keyword_index.documents_in_category(category.id) {
+                Ok(documents) => Some(
+                    documents
+                        .into_iter()
+                        .map(|doc| (doc.source, doc.path))
+                        .collect::<HashSet<_>>(),
+                ),
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Search failed: {}",
+                        e
+                    ))]));
+                }
+            },
+            None => None,
+        };
+
+        // Search in rust-patterns, api-guidelines, and rustonomicon; a category search takes
+        // more results since only its entries are kept
+        let sources = BEST_PRACTICE_SOURCES;
+
+        let options = SearchOptions::new(if in_category.is_some() {
+            limit * 4
+        } else {
+            limit
+        })
+        .with_sources(Some(sources))
+        .with_min_score(params.min_score)
+        .with_max_per_source(params.max_per_source)
+        .with_exclude_terms(&params.exclude_terms);
+        let options = match self.profiles.apply(params.profile.as_deref(), options) {
+            Ok(options) => options,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+        // The hit count is of the topic search, so it isn't reported when filtered by category
+        let results = self
+            .run_search(&params.topic, SearchMode::Hybrid, &options)
+            .await?
+            .map(|outcome| match &in_category {
+                Some(entries) => (
+                    outcome
+                        .results
+                        .into_iter()
+                        .filter(|r| entries.contains(&(r.source.clone(), r.path.clone())))
+                        .take(limit)
+                        .collect(),
+                    None,
+                ),
+                None => (outcome.results, outcome.total_hits),
+            });
+
+        match results {
+            Ok((results, total_hits)) => {
+                if results.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No best practices found for '{}'. Try searching for related topics like 'error handling', 'API design', or 'naming'.",
+                        params.topic
+                    ))]));
+                }
+
+                let json_results: Vec<serde_json::Value> = results
+                    .into_iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "title": r.title,
+                            "practice": r.snippet,
+                            "path": r.path,
+                            "source": r.source,
+                            "checklist_ids": r.checklist_ids,
+                            "why": r.explanation.describe(),
+                        })
+                    })
+                    .collect();
+
+                Ok(params.budget.fit(json_response(&serde_json::json!({
+                    "total_hits": total_hits,
+                    "results": group_results(json_results, params.group_by_source),
+                }))))
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        name = "show_example",
+        description = "Get code examples for a Rust topic. Searches Rust by Example for practical, runnable examples demonstrating iterators, pattern matching, closures, error handling, and more. Each result lists the code blocks of the matching section with their language and the sentence introducing them."
+    )]
+    async fn show_example(
+        &self,
+        Parameters(params): Parameters<ShowExampleParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        // Search primarily in rust-by-example
+        let sources = EXAMPLE_SOURCES;
+
+        let options = SearchOptions::new(limit)
+            .with_sources(Some(sources))
+            .with_min_score(params.min_score)
+            .with_exclude_terms(&params.exclude_terms);
+        let options = match self.profiles.apply(params.profile.as_deref(), options) {
+            Ok(options) => options,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+        let results = self
+            .run_search(&params.topic, SearchMode::Hybrid, &options)
+            .await?
+            .map(|outcome| (outcome.results, outcome.total_hits));
+
+        match results {
+            Ok((results, total_hits)) => {
+                if results.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No examples found for '{}'. Try topics like 'iterators', 'match', 'closures', or 'error handling'.",
+                        params.topic
+                    ))]));
+                }
+
+                let json_results: Vec<serde_json::Value> = results
+                    .into_iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "title": r.title,
+                            "example": r.snippet,
+                            "code": self.section_examples(&r),
+                            "uri": resources::document_uri(&r.source, &r.path),
+                            "path": r.path,
+                            "source": r.source,
+                            "section": r.section,
+                            "why": r.explanation.describe(),
+                        })
+                    })
+                    .collect();
+
+                Ok(params.budget.fit(json_response(&serde_json::json!({
+                    "total_hits": total_hits,
+                    "results": json_results,
+                }))))
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        name = "smart_search",
+        description = "Single entry point for Rust documentation questions. Detects whether the query asks for a concept explanation, a code example, an API lookup, a best practice, or WebAssembly guidance, then picks the matching sources, search mode, and result format automatically."
+    )]
+    async fn smart_search(
+        &self,
+        Parameters(params): Parameters<SmartSearchParams>,
+        peer: Peer<RoleServer>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            5
+        } else {
+            params.limit.min(20)
+        };
+        let query = self.prepare_query(&params.query, &peer).await;
+
+        // Same source sets and result fields as the dedicated tools
+        let intent = QueryIntent::classify(&query.text);
+        let (sources, field) = match intent {
+            QueryIntent::Concept => (Some(CONCEPT_SOURCES), "explanation"),
+            QueryIntent::Example => (Some(EXAMPLE_SOURCES), "example"),
+            QueryIntent::BestPractice => (Some(BEST_PRACTICE_SOURCES), "practice"),
+            QueryIntent::WebAssembly => (Some(WASM_SOURCES), "snippet"),
+            QueryIntent::ApiLookup | QueryIntent::General => (None, "snippet"),
+        };
+        let sources = sources.map(|sources| with_topic_sources(sources, &query.text));
+        let mode = intent.preferred_mode();
+
+        let options = SearchOptions::new(limit)
+            .with_sources(sources.as_deref())
+            .with_snippet_len(intent.snippet_len())
+            .with_min_score(params.min_score)
+            .with_max_per_source(params.max_per_source)
+            .with_exclude_terms(&params.exclude_terms);
+        let options = match self.profiles.apply(params.profile.as_deref(), options) {
+            Ok(options) => options,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+        let widened = SearchOptions::new(limit)
+            .with_min_score(params.min_score)
+            .with_max_per_source(params.max_per_source)
+            .with_exclude_terms(&params.exclude_terms);
+        // The profile still applies; it was checked above
+        let widened = self
+            .profiles
+            .apply(params.profile.as_deref(), widened.clone())
+            .unwrap_or(widened);
+
+        let workspace_index = self.workspace_index();
+        let routed = sources.is_some();
+        let text = query.text.clone();
+        let outcome = self
+            .search_blocking(move |hybrid| {
+                let hybrid = hybrid.with_workspace(workspace_index.as_deref());
+                let outcome = hybrid.search_with_mode(&text, mode, &options);
+
+                // Nothing in the routed sources: widen to all sources rather than returning
+                // nothing
+                if routed && matches!(&outcome, Ok(o) if o.results.is_empty()) {
+                    return hybrid
+                        .search_routed(&text, mode, &widened)
+                        .map(|(_intent, outcome)| outcome);
+                }
+                outcome
+            })
+            .await?;
+
+        match outcome {
+            Ok(outcome) => {
+                if outcome.results.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No documentation found for '{}'. Try rephrasing or using more specific Rust terms.",
+                        params.query
+                    ))]));
+                }
+
+                let mode_used = outcome.mode.as_str();
+                let total_hits = outcome.total_hits;
+                let json_results: Vec<serde_json::Value> = outcome
+                    .results
+                    .into_iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "title": r.title,
+                            field: r.snippet,
+                            "path": r.path,
+                            "source": r.source,
+                            "why": r.explanation.describe(),
+                        })
+                    })
+                    .collect();
+
+                let mut response = serde_json::json!({
+                    "intent": intent.as_str(),
+                    "mode": mode_used,
+                    "total_hits": total_hits,
+                    "results": group_results(json_results, params.group_by_source),
+                });
+                query.describe(&mut response);
+                if let Some(note) = self.semantic_note(mode) {
+                    response["note"] = note.into();
+                }
+                Ok(params.budget.fit(json_response(&response)))
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Search failed: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        name = "diagnose_compiler_output",
+        description = "Explain compiler errors. Paste raw `cargo build` output; each distinct error or warning is parsed into its error code, location, and key phrases (e.g. 'cannot borrow as mutable', 'does not live long enough'), and matched to the most relevant Rust Book and Reference sections."
+    )]
+    async fn diagnose_compiler_output(
+        &self,
+        Parameters(params): Parameters<DiagnoseParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(5)
+        };
+
+        let diagnostics = parse_compiler_output(&params.output);
+        if diagnostics.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No errors or warnings found. Paste the full output of `cargo build` including the `error[E....]:` lines.",
+            )]));
+        }
+
+        // Extra candidates let boosted chapters move up into the returned sections
+        let options = SearchOptions::new(limit * 2).with_sources(Some(CONCEPT_SOURCES));
+
+        let mut json_diagnostics = Vec::with_capacity(diagnostics.len());
+        for diagnostic in diagnostics {
+            // Match curated error patterns on the code as well as the message
+            let headline = format!(
+                "{} {}",
+                diagnostic.code.as_deref().unwrap_or_default(),
+                diagnostic.message
+            );
+            let patterns = error_patterns::matching_patterns(&headline);
+            let query = error_patterns::expand_query(&diagnostic.search_query(), &patterns);
+
+            let results = match self
+                .run_search(&query, SearchMode::Hybrid, &options)
+                .await?
+            {
+                Ok(mut outcome) => {
+                    error_patterns::apply_boost(&patterns, &mut outcome.results);
+                    outcome.results.truncate(limit);
+                    outcome.results
+                }
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Search failed: {}",
+                        e
+                    ))]));
+                }
+            };
+
+            let sections: Vec<serde_json::Value> = results
+                .into_iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "title": r.title,
+                        "explanation": r.snippet,
+                        "path": r.path,
+                        "source": r.source,
+                    })
+                })
+                .collect();
+
+            json_diagnostics.push(serde_json::json!({
+                "level": diagnostic.level,
+                "code": diagnostic.code,
+                "message": diagnostic.message,
+                "span": diagnostic.span,
+                "key_phrases": diagnostic.key_phrases,
+                "sections": sections,
+            }));
+        }
+
+        Ok(params.budget.fit(json_response(&json_diagnostics)))
+    }
+
+    #[tool(
+        name = "idiom_check",
+        description = "Review a short Rust snippet for non-idiomatic constructs such as unwrap chains, manual index loops, clone-heavy code, &String/&Vec parameters, and get_ prefixes. Returns each finding with the Rust Design Patterns and API Guidelines sections describing the idiomatic alternative."
+    )]
+    async fn idiom_check(
+        &self,
+        Parameters(params): Parameters<IdiomCheckParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(5)
+        };
+
+        let findings = check_snippet(&params.code);
+        if findings.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No common non-idiomatic constructs detected. Try get_best_practice for guidance on a specific topic.",
+            )]));
+        }
+
+        // Extra candidates let the sections covering each idiom move up
+        let options = SearchOptions::new(limit * 2).with_sources(Some(IDIOM_SOURCES));
+
+        let mut json_findings = Vec::with_capacity(findings.len());
+        for finding in findings {
+            let outcome = self
+                .run_search(finding.idiom.query, SearchMode::Hybrid, &options)
+                .await?;
+            let results = match outcome {
+                Ok(mut outcome) => {
+                    finding.idiom.apply_boost(&mut outcome.results);
+                    outcome.results.truncate(limit);
+                    outcome.results
+                }
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Search failed: {}",
+                        e
+                    ))]));
+                }
+            };
+
+            let sections: Vec<serde_json::Value> = results
+                .into_iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "title": r.title,
+                        "practice": r.snippet,
+                        "path": r.path,
+                        "source": r.source,
+                    })
+                })
+                .collect();
+
+            json_findings.push(serde_json::json!({
+                "construct": finding.idiom.id,
+                "occurrences": finding.occurrences,
+                "suggestion": finding.idiom.description,
+                "sections": sections,
+            }));
+        }
+
+        Ok(params.budget.fit(json_response(&json_findings)))
+    }
+
+    #[tool(
+        name = "cheat_sheet",
+        description = "Build a compact cheat sheet for a Rust topic (e.g. 'iterators'): defining sentences, the key traits, methods, and types mentioned across sources, short canonical code examples, and the section headings of the documents to read next."
+    )]
+    async fn cheat_sheet(
+        &self,
+        Parameters(params): Parameters<CheatSheetParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            5
+        } else {
+            params.limit.min(10)
+        };
+
+        // Aggregation needs the stored structure, which only the keyword index keeps
+        let documents = match self
+            .keyword_index
+            .search_documents(&params.topic, &SearchOptions::new(limit))
+        {
+            Ok(documents) => documents,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+
+        let sheet = CheatSheet::build(&params.topic, &documents);
+        if sheet.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Not enough material found to build a cheat sheet for '{}'. Try a broader topic like 'iterators', 'traits', or 'error handling'.",
+                params.topic
+            ))]));
+        }
+
+        Ok(json_response(&sheet))
+    }
+
+    #[tool(
+        name = "learning_path",
+        description = "Build an ordered reading list for a learning goal (e.g. 'understand async', 'learn lifetimes from scratch'). Prerequisite concepts come first, and chapters from The Rust Book, the Async Book, and Rust by Example are ordered by their position in each book's table of contents."
+    )]
+    async fn learning_path(
+        &self,
+        Parameters(params): Parameters<LearningPathParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let per_step = if params.chapters_per_step == 0 {
+            2
+        } else {
+            params.chapters_per_step.min(5)
+        };
+
+        // (concept, query, is_goal); an unknown goal becomes a single search step
+        let goals = learning::goal_concepts(&params.goal);
+        let steps: Vec<(&str, &str, bool)> = if goals.is_empty() {
+            vec![("goal", params.goal.as_str(), true)]
+        } else {
+            learning::plan(&goals, params.include_prerequisites)
+                .into_iter()
+                .map(|step| (step.concept.id, step.concept.query, step.is_goal))
+                .collect()
+        };
+
+        let options = SearchOptions::new(per_step * 3).with_sources(Some(LEARNING_SOURCES));
+        let mut seen: Vec<(String, String)> = Vec::new();
+        let mut json_steps = Vec::with_capacity(steps.len());
+
+        for (concept, query, is_goal) in steps {
+            let documents = match self.keyword_index.search_documents(query, &options) {
+                Ok(documents) => documents,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Search failed: {}",
+                        e
+                    ))]));
+                }
+            };
+
+            // Prefer chapters listed in a table of contents, skip ones already recommended
+            let (mut listed, unlisted): (Vec<_>, Vec<_>) = documents
+                .into_iter()
+                .filter(|doc| !seen.contains(&(doc.source.clone(), doc.path.clone())))
+                .partition(|doc| doc.summary_order.is_some());
+            listed.extend(unlisted);
+            listed.truncate(per_step);
+            learning::reading_order(&mut listed);
+
+            if listed.is_empty() {
+                continue;
+            }
+
+            let chapters: Vec<serde_json::Value> = listed
+                .into_iter()
+                .map(|doc| {
+                    seen.push((doc.source.clone(), doc.path.clone()));
+                    serde_json::json!({
+                        "title": doc.title,
+                        "source": doc.source,
+                        "path": doc.path,
+                        "position": doc.summary_order,
+                    })
+                })
+                .collect();
+
+            json_steps.push(serde_json::json!({
+                "step": json_steps.len() + 1,
+                "concept": concept,
+                "reason": if is_goal { "goal" } else { "prerequisite" },
+                "chapters": chapters,
+            }));
+        }
+
+        if json_steps.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No chapters found for '{}'. Try a goal like 'understand async' or 'learn lifetimes'.",
+                params.goal
+            ))]));
+        }
+
+        Ok(json_response(&serde_json::json!({
+            "goal": params.goal,
+            "steps": json_steps,
+        })))
+    }
+
+    #[tool(
+        name = "edition_migration",
+        description = "Plan a Rust edition migration (e.g. 2018 to 2021). Returns the cargo fix --edition steps for each edition boundary, the Edition Guide changes introduced along the way (whether cargo fix handles them automatically), with changes affecting the given constructs listed first, and matching Edition Guide sections."
+    )]
+    async fn edition_migration(
+        &self,
+        Parameters(params): Parameters<EditionMigrationParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let transitions = editions::parse_edition(&params.from).and_then(|from| {
+            let to = editions::parse_edition(&params.to)?;
+            editions::transitions(from, to)
+        });
+        let transitions = match transitions {
+            Ok(transitions) => transitions,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+
+        let constructs = params.constructs.as_deref().unwrap_or_default();
+        let json_transitions: Vec<serde_json::Value> = transitions
+            .iter()
+            .map(|&(from, to)| {
+                let changes: Vec<serde_json::Value> = editions::changes_for(to, constructs)
+                    .into_iter()
+                    .map(|(change, affected)| {
+                        serde_json::json!({
+                            "title": change.title,
+                            "fix": change.fix.as_str(),
+                            "note": change.note,
+                            "affects_your_code": affected,
+                            "path": change.path,
+                        })
+                    })
+                    .collect();
+
+                serde_json::json!({
+                    "from": from,
+                    "to": to,
+                    "commands": editions::migration_commands(from, to),
+                    "changes": changes,
+                })
+            })
+            .collect();
+
+        // Cite Edition Guide sections for the user's constructs, or the general migration guide
+        let query = if constructs.is_empty() {
+            "transitioning an existing project to a new edition"
+        } else {
+            constructs
+        };
+        let options = SearchOptions::new(3).with_sources(Some(&["edition-guide"]));
+        let citations: Vec<serde_json::Value> = self
+            .run_search(query, SearchMode::Hybrid, &options)
+            .await?
+            .map(|outcome| outcome.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "snippet": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "transitions": json_transitions,
+            "citations": citations,
+        })))
+    }
+
+    #[tool(
+        name = "check_deprecation",
+        description = "Check whether a Rust item, macro, or crate is deprecated or superseded (e.g. 'try!', 'mem::uninitialized', 'lazy_static') and what replaces it, with citations from the documentation."
+    )]
+    async fn check_deprecation(
+        &self,
+        Parameters(params): Parameters<CheckDeprecationParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let matches = self.deprecations.lookup(&params.item);
+        let Some(best) = matches.first() else {
+            return Ok(json_response(&serde_json::json!({
+                "item": params.item,
+                "deprecated": false,
+                "note": "No deprecation recorded for this item. Only well-known deprecations and, when std rustdoc JSON is installed, std items are tracked.",
+            })));
+        };
+
+        let citations: Vec<serde_json::Value> = self
+            .run_search(&best.query, SearchMode::Hybrid, &SearchOptions::new(3))
+            .await?
+            .map(|outcome| outcome.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "snippet": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        let json_matches: Vec<serde_json::Value> = matches
+            .iter()
+            .map(|d| {
+                serde_json::json!({
+                    "item": d.item,
+                    "since": d.since,
+                    "replacement": d.replacement,
+                    "note": d.note,
+                    "origin": d.origin,
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "item": params.item,
+            "deprecated": true,
+            "matches": json_matches,
+            "citations": citations,
+        })))
+    }
+
+    #[tool(
+        name = "macro_help",
+        description = "Answer questions about Rust macros. Understands macro vocabulary (fragment specifiers like :expr and :tt, repetitions, hygiene, $crate, macro_export, tt munchers, proc-macro vs macro_rules) and searches the Reference's macro chapters, The Little Book of Rust Macros, and The Rust Book's macros chapter."
+    )]
+    async fn macro_help(
+        &self,
+        Parameters(params): Parameters<MacroHelpParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        let topics = macro_help::detect_topics(&params.query);
+        let query = macro_help::expand_query(&params.query, &topics);
+
+        // Extra candidates let the chapters covering each topic move up
+        let options = SearchOptions::new(limit * 2).with_sources(Some(MACRO_SOURCES));
+        let mut results = match self
+            .run_search(&query, SearchMode::Hybrid, &options)
+            .await?
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+        macro_help::apply_boost(&topics, &mut results);
+        results.truncate(limit);
+
+        if results.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No macro documentation found for '{}'. Try terms like 'fragment specifiers', 'hygiene', or 'derive macro'.",
+                params.query
+            ))]));
+        }
+
+        let json_results: Vec<serde_json::Value> = results
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "explanation": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(params.budget.fit(json_response(&serde_json::json!({
+            "topics": topics.iter().map(|t| t.id).collect::<Vec<_>>(),
+            "results": json_results,
+        }))))
+    }
+
+    #[tool(
+        name = "unsafe_review",
+        description = "Review an unsafe construct (raw pointer dereference, transmute, unsafe impl Send/Sync, MaybeUninit, slice::from_raw_parts, static mut, unions, FFI calls). Returns the documented soundness obligations as a checklist, with the relevant Rustonomicon, Unsafe Code Guidelines, and Reference sections."
+    )]
+    async fn unsafe_review(
+        &self,
+        Parameters(params): Parameters<UnsafeReviewParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(5)
+        };
+
+        let constructs = unsafe_review::detect_constructs(&params.description);
+        if constructs.is_empty() {
+            let known: Vec<&str> = UNSAFE_CONSTRUCTS.iter().map(|c| c.id).collect();
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No known unsafe construct recognized in '{}'. Describe one of: {}.",
+                params.description,
+                known.join(", ")
+            ))]));
+        }
+
+        // Extra candidates let the sections covering each construct move up
+        let options = SearchOptions::new(limit * 2).with_sources(Some(UNSAFE_SOURCES));
+
+        let mut reviews = Vec::with_capacity(constructs.len());
+        for construct in constructs {
+            let results = match self
+                .run_search(construct.query, SearchMode::Hybrid, &options)
+                .await?
+            {
+                Ok(mut outcome) => {
+                    construct.apply_boost(&mut outcome.results);
+                    outcome.results.truncate(limit);
+                    outcome.results
+                }
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Search failed: {}",
+                        e
+                    ))]));
+                }
+            };
+
+            let sections: Vec<serde_json::Value> = results
+                .into_iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "title": r.title,
+                        "snippet": r.snippet,
+                        "path": r.path,
+                        "source": r.source,
+                    })
+                })
+                .collect();
+
+            reviews.push(serde_json::json!({
+                "construct": construct.id,
+                "checklist": construct.checklist(),
+                "sections": sections,
+            }));
+        }
+
+        Ok(params.budget.fit(json_response(&reviews)))
+    }
+
+    #[tool(
+        name = "ffi_help",
+        description = "Answer FFI questions (repr(C) layout, CString/CStr ownership, who frees memory, panics across FFI, callbacks and user data, nullable pointers, linking). Expands the query with FFI terminology and searches the Rustonomicon's FFI chapter, the Reference, The Rust Book, and any configured FFI source."
+    )]
+    async fn ffi_help(
+        &self,
+        Parameters(params): Parameters<FfiHelpParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        let topics = ffi::detect_topics(&params.query);
+        let query = ffi::expand_query(&params.query, &topics);
+        let sources = ffi::ffi_sources();
+
+        // Extra candidates let the sections covering each topic move up
+        let options = SearchOptions::new(limit * 2).with_sources(Some(&sources));
+        let mut results = match self
+            .run_search(&query, SearchMode::Hybrid, &options)
+            .await?
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+        ffi::apply_boost(&topics, &mut results);
+        results.truncate(limit);
+
+        if results.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No FFI documentation found for '{}'. Try terms like 'repr(C)', 'CString', or 'callback'.",
+                params.query
+            ))]));
+        }
+
+        let json_results: Vec<serde_json::Value> = results
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "explanation": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(params.budget.fit(json_response(&serde_json::json!({
+            "topics": topics.iter().map(|t| t.id).collect::<Vec<_>>(),
+            "results": json_results,
+        }))))
+    }
+
+    #[tool(
+        name = "concurrency_help",
+        description = "Recommend a synchronization primitive for a described concurrency scenario (channels, Mutex, RwLock, atomics, OnceLock/LazyLock, Condvar, Barrier, scoped threads), with when to use it, caveats, std documentation links, and the relevant Rust Book concurrency and Rustonomicon Send/Sync/atomics sections."
+    )]
+    async fn concurrency_help(
+        &self,
+        Parameters(params): Parameters<ConcurrencyHelpParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        let recommendations = concurrency::recommend(&params.scenario);
+        // Naming the recommended primitives pulls in the sections that use them
+        let query = recommendations
+            .iter()
+            .fold(params.scenario.clone(), |query, r| {
+                format!("{} {}", query, r.primitive.name)
+            });
+
+        let options = SearchOptions::new(limit * 2).with_sources(Some(CONCURRENCY_SOURCES));
+        let mut results = match self
+            .run_search(&query, SearchMode::Hybrid, &options)
+            .await?
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+        concurrency::apply_boost(&mut results);
+        results.truncate(limit);
+
+        let json_recommendations: Vec<serde_json::Value> = recommendations
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "primitive": r.primitive.name,
+                    "path": r.primitive.path,
+                    "when": r.primitive.when,
+                    "caveats": r.primitive.caveats,
+                    "docs": r.primitive.doc_url(),
+                    "matched": r.matched,
+                })
+            })
+            .collect();
+
+        let sections: Vec<serde_json::Value> = results
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "snippet": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "scenario": params.scenario,
+            "recommendations": json_recommendations,
+            "sections": sections,
+        })))
+    }
+
+    #[tool(
+        name = "choose_collection",
+        description = "Recommend std collections (Vec, VecDeque, LinkedList, HashMap, BTreeMap, HashSet, BTreeSet, BinaryHeap) for a described use case such as 'ordered keys, frequent range queries' or 'LIFO with cheap push/pop', citing the std::collections guide's reasons to use each and its Big-O costs, with std documentation links and the Rust Book's common collections sections."
+    )]
+    async fn choose_collection(
+        &self,
+        Parameters(params): Parameters<ChooseCollectionParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        let recommendations = collections::recommend(&params.use_case);
+        // Naming the recommended collections pulls in the sections that use them
+        let query = recommendations
+            .iter()
+            .fold(params.use_case.clone(), |query, r| {
+                format!("{} {}", query, r.collection.name)
+            });
+
+        let options = SearchOptions::new(limit * 2).with_sources(Some(COLLECTION_SOURCES));
+        let mut results = match self
+            .run_search(&query, SearchMode::Hybrid, &options)
+            .await?
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+        collections::apply_boost(&mut results);
+        results.truncate(limit);
+
+        let json_recommendations: Vec<serde_json::Value> = recommendations
+            .iter()
+            .map(|r| {
+                let costs: serde_json::Map<String, serde_json::Value> = r
+                    .collection
+                    .costs
+                    .iter()
+                    .map(|(operation, cost)| (operation.to_string(), (*cost).into()))
+                    .collect();
+                serde_json::json!({
+                    "collection": r.collection.name,
+                    "path": r.collection.path,
+                    "use_when": r.collection.use_when,
+                    "costs": costs,
+                    "docs": r.collection.doc_url(),
+                    "matched": r.matched,
+                })
+            })
+            .collect();
+
+        let sections: Vec<serde_json::Value> = results
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "snippet": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "use_case": params.use_case,
+            "recommendations": json_recommendations,
+            "citations": {
+                "use_when": format!("{}{}", collections::GUIDE_URL, collections::WHEN_ANCHOR),
+                "costs": format!("{}{}", collections::GUIDE_URL, collections::PERFORMANCE_ANCHOR),
+                "legend": collections::COST_LEGEND,
+            },
+            "sections": sections,
+        })))
+    }
+
+    #[tool(
+        name = "find_exercise",
+        description = "Suggest rustlings exercises for a topic (e.g. 'lifetimes', 'iterators') or a compiler error message, with their hints and the command to run them, alongside the Rust Book and Reference chapters covering the topic. Useful for teaching and practice."
+    )]
+    async fn find_exercise(
+        &self,
+        Parameters(params): Parameters<FindExerciseParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        if self.rustlings.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Rustlings exercises are not available. The repository is cloned with the other documentation sources when the index is built; delete the index directory to rebuild it.",
+            )]));
+        }
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        // Compiler errors are searched by the concept they stem from
+        let patterns = error_patterns::matching_patterns(&params.topic);
+        let query = error_patterns::expand_query(&params.topic, &patterns);
+
+        let options = SearchOptions::new(limit).with_sources(Some(&[RUSTLINGS_SOURCE]));
+        let results = match self
+            .run_search(&query, SearchMode::Hybrid, &options)
+            .await?
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+
+        let exercises: Vec<serde_json::Value> = results
+            .iter()
+            .filter_map(|r| {
+                let exercise = self.rustlings.get(&r.path)?;
+                Some(serde_json::json!({
+                    "name": exercise.name,
+                    "topic": exercise.topic,
+                    "path": exercise.path,
+                    "run": exercise.run_command(),
+                    "hint": exercise.hint,
+                    "why": r.explanation.describe(),
+                }))
+            })
+            .collect();
+        if exercises.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No rustlings exercises found for '{}'. Try a broader topic like 'ownership', 'traits', or 'error handling'.",
+                params.topic
+            ))]));
+        }
+
+        let chapter_options = SearchOptions::new(3).with_sources(Some(CONCEPT_SOURCES));
+        let chapters: Vec<serde_json::Value> = self
+            .run_search(&query, SearchMode::Hybrid, &chapter_options)
+            .await?
+            .map(|outcome| outcome.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "snippet": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "topic": params.topic,
+            "exercises": exercises,
+            "chapters": chapters,
+        })))
+    }
+
+    #[tool(
+        name = "clippy_lint",
+        description = "Look up a Clippy lint by its exact name (e.g. 'needless_range_loop', 'clippy::unwrap_used') and get its group, default level, what it does, why it's bad (why it fires), an example with the suggested fix, and the clippy.toml options that configure it. Use search_rust_docs for free-text questions about lints."
+    )]
+    async fn clippy_lint(
+        &self,
+        Parameters(params): Parameters<ClippyLintParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let lint = match self.find_clippy_lint(&params.name) {
+            Ok(lint) => lint,
+            Err(response) => return Ok(response),
+        };
+
+        let configuration: Vec<serde_json::Value> = lint
+            .configuration
+            .iter()
+            .map(|option| {
+                serde_json::json!({
+                    "name": option.name,
+                    "description": option.docs,
+                    "default": option.default,
+                })
+            })
+            .collect();
+        Ok(json_response(&serde_json::json!({
+            "name": format!("clippy::{}", lint.name),
+            "group": lint.group,
+            "default_level": lint.default_level(),
+            "description": lint.description,
+            "what_it_does": lint.what_it_does,
+            "why_is_this_bad": lint.why_bad,
+            "known_problems": lint.known_problems,
+            "example": lint.example,
+            "suggested_fix": lint.suggested_fix(),
+            "configuration": configuration,
+            "added_in": lint.version,
+            "docs_url": lint.docs_url(),
+            "uri": resources::document_uri(CLIPPY_SOURCE, &lint.name),
+        })))
+    }
+
+    #[tool(
+        name = "explain_lint",
+        description = "Explain a Clippy warning by its lint name (e.g. 'needless_collect', 'clippy::unwrap_used'): what the lint is, why it fires, the kind of code it fires on, and the suggested fix. Use clippy_lint for the lint's group, known problems, and clippy.toml options."
+    )]
+    async fn explain_lint(
+        &self,
+        Parameters(params): Parameters<ExplainLintParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let lint = match self.find_clippy_lint(&params.name) {
+            Ok(lint) => lint,
+            Err(response) => return Ok(response),
+        };
+
+        Ok(json_response(&serde_json::json!({
+            "name": format!("clippy::{}", lint.name),
+            "default_level": lint.default_level(),
+            "what_it_is": lint.description,
+            "what_it_does": lint.what_it_does,
+            "why_it_fires": lint.why_bad,
+            "flagged_code": lint.flagged_code(),
+            "suggested_fix": lint.suggested_fix(),
+            "docs_url": lint.docs_url(),
+            "uri": resources::document_uri(CLIPPY_SOURCE, &lint.name),
+        })))
+    }
+
+    #[tool(
+        name = "cargo_reference",
+        description = "Look up a cargo subcommand ('cargo tree') or Cargo.toml key ('[patch]', 'resolver = \"2\"', 'package.edition') and get the Cargo Book reference section for it, with the command's flags and examples. Keys are matched exactly against the book's headings; use search_rust_docs for free-text Cargo questions."
+    )]
+    async fn cargo_reference(
+        &self,
+        Parameters(params): Parameters<CargoReferenceParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let lookup = match Lookup::parse(&params.key) {
+            Ok(lookup) => lookup,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+
+        let section = match self.find_cargo_section(&lookup.keys) {
+            Ok(section) => section,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+        if let Some((doc, section)) = section {
+            return Ok(json_response(&serde_json::json!({
+                "key": params.key,
+                "kind": "manifest",
+                "page": doc.title,
+                "heading": section.heading,
+                "section": cap_lines(&section.body, MAX_CARGO_SECTION_LINES),
+                "examples": cargo_examples(&section.body),
+                "uri": resources::document_uri(CARGO_BOOK_SOURCE, &doc.path),
+            })));
+        }
+
+        let page = match &lookup.command {
+            Some(command) => self
+                .keyword_index
+                .get_document(CARGO_BOOK_SOURCE, &cargo_reference::command_page(command)),
+            None => Ok(None),
+        };
+        let doc = match page {
+            Ok(Some(doc)) => doc,
+            Ok(None) => {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "No Cargo Book section or command page matches '{}'. Manifest keys are matched against section headings (e.g. '[patch]', 'resolver', 'package.edition'); use search_rust_docs with sources ['cargo-book'] for other Cargo questions.",
+                    params.key
+                ))]));
+            }
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read the Cargo Book: {}",
+                    e
+                ))]));
+            }
+        };
+
+        let section = |key: &str| {
+            cargo_reference::find_section(&doc.markdown, key).map(|section| section.body)
+        };
+        let flags: Vec<serde_json::Value> = cargo_reference::flags(&doc.markdown)
+            .into_iter()
+            .map(|flag| serde_json::json!({ "flag": flag.flag, "description": flag.description }))
+            .collect();
+        let description = section("description").unwrap_or_default();
+        Ok(json_response(&serde_json::json!({
+            "key": params.key,
+            "kind": "command",
+            "page": doc.title,
+            "synopsis": section("synopsis"),
+            "section": cap_lines(&description, MAX_CARGO_SECTION_LINES),
+            "flags": flags,
+            "examples": cargo_examples(&section("examples").unwrap_or_default()),
+            "uri": resources::document_uri(CARGO_BOOK_SOURCE, &doc.path),
+        })))
+    }
+
+    #[tool(
+        name = "reference_section",
+        description = "Fetch a section of the Rust Reference by its stable rule identifier ('expr.match.guard', abbreviations like 'expressions.match' work), by page and heading anchor ('match-expr.md#match-guards'), or by doc.rust-lang.org/reference URL. Returns the section text with the identifiers of the rules it contains. For the grammar use reference_grammar; for free-text questions use search_rust_docs."
+    )]
+    async fn reference_section(
+        &self,
+        Parameters(params): Parameters<ReferenceSectionParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let target = match Target::parse(&params.id) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        };
+        let pages = match self.reference_pages() {
+            Ok(pages) => pages,
+            Err(error) => return Ok(error),
+        };
+
+        let found = match &target {
+            Target::Rule(wanted) => rust_reference::resolve_rule(
+                wanted,
+                pages
+                    .iter()
+                    .flat_map(|page| rust_reference::rule_ids(&page.markdown)),
+            )
+            .and_then(|id| {
+                pages.iter().find_map(|page| {
+                    let section = rust_reference::rule_section(&page.markdown, &id)?;
+                    Some(serde_json::json!({
+                        "id": params.id,
+                        "rule": id,
+                        "page": page.title,
+                        "rules": section.rules,
+                        "section": cap_lines(&section.text, MAX_REFERENCE_SECTION_LINES),
+                        "uri": resources::document_uri(REFERENCE_SOURCE, &page.path),
+                    }))
+                })
+            }),
+            Target::Page { page, anchor } => pages
+                .iter()
+                // Pages are named by file; the Reference keeps some in directories
+                .filter(|doc| {
+                    page.as_ref()
+                        .is_none_or(|page| doc.path.rsplit('/').next() == Some(page.as_str()))
+                })
+                .find_map(|doc| {
+                    let (heading, text) = match anchor {
+                        Some(anchor) => rust_reference::anchor_section(&doc.markdown, anchor)?,
+                        None => (doc.title.clone(), doc.markdown.clone()),
+                    };
+                    Some(serde_json::json!({
+                        "id": params.id,
+                        "page": doc.title,
+                        "heading": heading,
+                        "rules": rust_reference::rule_ids(&text),
+                        "section": cap_lines(&text, MAX_REFERENCE_SECTION_LINES),
+                        "uri": resources::document_uri(REFERENCE_SOURCE, &doc.path),
+                    }))
+                }),
+        };
+
+        Ok(match found {
+            Some(section) => json_response(&section),
+            None => CallToolResult::success(vec![Content::text(format!(
+                "No Reference rule, page, or heading matches '{}'. Rule identifiers look like 'expr.match.guard' (shown as r[...] markers in the Reference); pages like 'match-expr.md#match-guards'. Use search_rust_docs with sources ['rust-reference'] for free-text questions.",
+                params.id
+            ))]),
+        })
+    }
+
+    #[tool(
+        name = "reference_grammar",
+        description = "Search the Rust Reference's grammar productions by name ('MatchArm', 'match arm', 'IDENTIFIER'). Returns each production's definition, the page defining it, and the productions that use it."
+    )]
+    async fn reference_grammar(
+        &self,
+        Parameters(params): Parameters<ReferenceGrammarParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            5
+        } else {
+            params.limit.min(20)
+        };
+        let pages = match self.reference_pages() {
+            Ok(pages) => pages,
+            Err(error) => return Ok(error),
+        };
+
+        let grammar: Vec<(&Document, rust_reference::Production)> = pages
+            .iter()
+            .flat_map(|page| {
+                rust_reference::productions(&page.markdown)
+                    .into_iter()
+                    .map(move |production| (page, production))
+            })
+            .collect();
+        let mut matches: Vec<(usize, &(&Document, rust_reference::Production))> = grammar
+            .iter()
+            .filter_map(|entry| {
+                Some((
+                    rust_reference::production_rank(&entry.1.name, &params.query)?,
+                    entry,
+                ))
+            })
+            .collect();
+        // Stable sort keeps page order among equal ranks
+        matches.sort_by_key(|(rank, (_, production))| (*rank, production.name.len()));
+        if matches.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No grammar production of the Reference ({} productions) is named like '{}'. Production names are CamelCase for syntax (e.g. 'MatchExpression') and UPPER_CASE for tokens (e.g. 'IDENTIFIER').",
+                grammar.len(),
+                params.query
+            ))]));
+        }
+
+        let productions: Vec<serde_json::Value> = matches
+            .iter()
+            .take(limit)
+            .map(|(_, (page, production))| {
+                let used_by: Vec<&str> = grammar
+                    .iter()
+                    .filter(|(_, other)| other.uses(&production.name))
+                    .map(|(_, other)| other.name.as_str())
+                    .take(MAX_GRAMMAR_USERS)
+                    .collect();
+                serde_json::json!({
+                    "name": production.name,
+                    "definition": production.definition,
+                    "page": page.title,
+                    "uri": resources::document_uri(REFERENCE_SOURCE, &page.path),
+                    "used_by": used_by,
+                })
+            })
+            .collect();
+        Ok(json_response(&serde_json::json!({
+            "query": params.query,
+            "total": matches.len(),
+            "productions": productions,
+        })))
+    }
+
+    #[tool(
+        name = "rustc_flag",
+        description = "Look up rustc command-line flags in the rustc book: arguments like '--edition' or '-L', '-C' codegen options, and '-Z' unstable options from the unstable book. Prefixes work ('-C opt' finds opt-level), values are ignored ('-C panic=abort'), and a bare name ('target-cpu') searches every family."
+    )]
+    async fn rustc_flag(
+        &self,
+        Parameters(params): Parameters<RustcFlagParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        if self.rustc_flags.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "rustc flag documentation is not available. It is downloaded when the index is built; with a mirror configured, place the rustc book's command-line-arguments.md and codegen-options/index.md (as codegen-options.md) and the unstable book's compiler-flags directory in {:?}.",
+                RustcFlags::dir(&self.data_dir)
+            ))]));
+        }
+
+        let query = FlagQuery::parse(&params.flag);
+        let found = self.rustc_flags.find(&query);
+        if found.is_empty() {
+            let known = match query.kind {
+                Some(kind @ (FlagKind::Codegen | FlagKind::Unstable)) => {
+                    format!(
+                        " Known {} options: {}.",
+                        kind.as_str(),
+                        self.rustc_flags.names(kind).join(", ")
+                    )
+                }
+                _ => String::new(),
+            };
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No rustc flag matches '{}'.{}",
+                params.flag, known
+            ))]));
+        }
+
+        let flags: Vec<serde_json::Value> = found
+            .iter()
+            .take(MAX_RUSTC_FLAGS)
+            .map(|flag| {
+                serde_json::json!({
+                    "flag": flag.display_name(),
+                    "kind": flag.kind.as_str(),
+                    "summary": flag.summary,
+                    "documentation": flag.markdown,
+                    "url": flag.url,
+                })
+            })
+            .collect();
+        let mut response = serde_json::json!({
+            "query": params.flag,
+            "flags": flags,
+        });
+        if found.len() > MAX_RUSTC_FLAGS {
+            response["more"] = serde_json::json!(
+                found[MAX_RUSTC_FLAGS..]
+                    .iter()
+                    .map(|flag| flag.display_name())
+                    .collect::<Vec<_>>()
+            );
+        }
+        Ok(json_response(&response))
+    }
+
+    #[tool(
+        name = "platform_support",
+        description = "Look up a target triple (e.g. 'aarch64-unknown-linux-musl') in the rustc book's platform support tables: its support tier and what the tier guarantees, whether the full standard library or only no_std is available, whether host tools (rustc, cargo) run on it, and how to install its standard library."
+    )]
+    async fn platform_support(
+        &self,
+        Parameters(params): Parameters<PlatformSupportParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        if self.platform_support.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Platform support tables are not available. They are downloaded when the index is built; with a mirror configured, place the rustc book's platform-support.md in {:?}.",
+                PlatformSupport::dir(&self.data_dir)
+            ))]));
+        }
+
+        let Some(target) = self.platform_support.get(&params.target) else {
+            let similar = self.platform_support.similar(&params.target, 5);
+            let suggestion = if similar.is_empty() {
+                "Run `rustc --print target-list` for every target rustc knows.".to_string()
+            } else {
+                format!("Similar targets: {}.", similar.join(", "))
+            };
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No target '{}' in the platform support tables. {}",
+                params.target, suggestion
+            ))]));
+        };
+
+        Ok(json_response(&serde_json::json!({
+            "target": target.triple,
+            "tier": target.tier,
+            "guarantee": target.guarantee(),
+            "std": target.std.as_str(),
+            "host_tools": target.host_tools,
+            "notes": target.notes,
+            "install": target.install(),
+            "docs_url": target.docs_url(),
+            "uri": target
+                .document_path()
+                .map(|path| resources::document_uri(PLATFORM_SOURCE, &path)),
+        })))
+    }
+
+    #[tool(
+        name = "explain_error_code",
+        description = "Explain a rustc error code (e.g. 'E0382', as in 'error[E0382]: borrow of moved value') with the full text of `rustc --explain`: what triggers it, erroneous and fixed examples. Also returns the Rust Book and Reference sections on the concept behind the error. To explain a whole build log, use diagnose_compiler_output."
+    )]
+    async fn explain_error_code(
+        &self,
+        Parameters(params): Parameters<ExplainErrorCodeParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        if self.error_codes.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error code explanations are not available. They are downloaded when the index is built; with a mirror configured, place the E*.md files of rust-lang/rust's compiler/rustc_error_codes/src/error_codes in {:?}.",
+                ErrorCodes::dir(&self.data_dir)
+            ))]));
+        }
+
+        let Some(error_code) = self.error_codes.get(&params.code) else {
+            let known = match self.error_codes.range() {
+                Some((first, last)) => format!(" Known codes range from {} to {}.", first, last),
+                None => String::new(),
+            };
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No explanation for error code '{}'.{} Codes look like E0382.",
+                params.code, known
+            ))]));
+        };
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(5)
+        };
+
+        // The summary names the problem in prose; curated patterns add the chapters' vocabulary
+        let summary = error_code.summary();
+        let patterns =
+            error_patterns::matching_patterns(&format!("{} {}", error_code.code, summary));
+        let query = error_patterns::expand_query(&summary, &patterns);
+        // Extra candidates let boosted chapters move up into the returned sections
+        let options = SearchOptions::new(limit * 2).with_sources(Some(CONCEPT_SOURCES));
+        let mut results = match self
+            .run_search(&query, SearchMode::Hybrid, &options)
+            .await?
+        {
+            Ok(outcome) => outcome.results,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+        error_patterns::apply_boost(&patterns, &mut results);
+        results.truncate(limit);
+        let sections: Vec<serde_json::Value> = results
+            .into_iter()
+            .map(|r| {
+                serde_json::json!({
+                    "title": r.title,
+                    "explanation": r.snippet,
+                    "path": r.path,
+                    "source": r.source,
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "code": error_code.code,
+            "summary": summary,
+            "no_longer_emitted": error_code.is_retired(),
+            "explanation": error_code.markdown,
+            "url": error_code.url(),
+            "uri": resources::document_uri(ERROR_CODES_SOURCE, &error_code.path()),
+            "sections": sections,
+        })))
+    }
+
+    #[tool(
+        name = "show_std_impl",
+        description = "Show the actual source code of a standard library item (e.g. 'Option::map', 'Vec::push', 'mem::swap') with its documentation summary, for 'how is this implemented' questions. Requires the std sources to be configured (sources.std_src)."
+    )]
+    async fn show_std_impl(
+        &self,
+        Parameters(params): Parameters<ShowStdImplParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let Some(library) = &self.std_library else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "The std sources are not indexed. Set `std_src` in the [sources] section of data/config.toml to the rust-src component ($(rustc --print sysroot)/lib/rustlib/src/rust) or a rust-lang/rust `library` directory, then rebuild the index.",
+            )]));
+        };
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        let locations = match self.find_std_items(&params.item) {
+            Ok(locations) => locations,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Search failed: {}",
+                    e
+                ))]));
+            }
+        };
+
+        let implementations: Vec<serde_json::Value> = locations
+            .iter()
+            .filter_map(|location| {
+                let item = std_source::read_item(library, location)?;
+                let (file, _) = std_source::split_location(location)?;
+                let lines: Vec<&str> = item.code.lines().collect();
+                let code = if lines.len() > MAX_IMPL_LINES {
+                    format!(
+                        "{}\n// ... {} more lines",
+                        lines[..MAX_IMPL_LINES].join("\n"),
+                        lines.len() - MAX_IMPL_LINES
+                    )
+                } else {
+                    item.code.clone()
+                };
+                Some(serde_json::json!({
+                    "name": item.name,
+                    "file": format!("library/{}", file),
+                    "line": item.line,
+                    "summary": item.summary(),
+                    "code": code,
+                }))
+            })
+            .take(limit)
+            .collect();
+
+        if implementations.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No std item named '{}' found. Use Type::method (e.g. 'Option::map') or module::function (e.g. 'mem::swap'). If std_src was configured after the index was built, delete the index directory to rebuild it.",
+                params.item
+            ))]));
+        }
+
+        Ok(json_response(&serde_json::json!({
+            "item": params.item,
+            "implementations": implementations,
+        })))
+    }
+
+    #[tool(
+        name = "lookup_std_api",
+        description = "Look up a standard library item by path (e.g. 'std::vec::Vec::retain', 'Vec::retain', 'mem::swap', 'HashMap') and return its signature and documentation, with the Rust version it was stabilized in and a rustdoc link. Use it for exact API questions instead of searching the books. Reads the std rustdoc JSON (sources.std_json), or the std sources (sources.std_src) when only those are configured."
+    )]
+    async fn lookup_std_api(
+        &self,
+        Parameters(params): Parameters<LookupStdApiParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+
+        // The rustdoc JSON has rendered signatures and every item; the sources are the fallback
+        let (from, items): (&str, Vec<serde_json::Value>) = if !self.std_json.is_empty() {
+            let items = self
+                .std_json
+                .api_items(&params.path)
+                .into_iter()
+                .take(limit)
+                .map(|item| {
+                    serde_json::json!({
+                        "path": item.path,
+                        "kind": item.kind,
+                        "signature": item.signature,
+                        "docs": truncate_api_docs(&item.docs),
+                        "stable_since": item.since.map(|since| since.to_string()),
+                        "doc_url": item.doc_url,
+                    })
+                })
+                .collect();
+            ("rustdoc_json", items)
+        } else if let Some(library) = &self.std_library {
+            let locations = match self.find_std_items(&params.path) {
+                Ok(locations) => locations,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Search failed: {}",
+                        e
                     ))]));
                 }
+            };
+            let items = locations
+                .iter()
+                .filter_map(|location| {
+                    let item = std_source::read_item(library, location)?;
+                    let (file, _) = std_source::split_location(location)?;
+                    Some(serde_json::json!({
+                        "path": item.name,
+                        "signature": item.signature(),
+                        "docs": truncate_api_docs(&item.docs),
+                        "file": format!("library/{}", file),
+                        "line": item.line,
+                    }))
+                })
+                .take(limit)
+                .collect();
+            ("std_sources", items)
+        } else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "The std documentation is not loaded. Set `std_json` in the [sources] section of data/config.toml to the nightly rust-docs-json component ($(rustc +nightly --print sysroot)/share/doc/rust/json), or `std_src` to the rust-src component ($(rustc --print sysroot)/lib/rustlib/src/rust) and rebuild the index.",
+            )]));
+        };
 
-                let json_results: Vec<serde_json::Value> = results
-                    .into_iter()
-                    .map(|r| {
-                        serde_json::json!({
-                            "title": r.title,
-                            "practice": r.snippet,
-                            "path": r.path,
-                            "source": r.source,
-                        })
-                    })
-                    .collect();
+        if items.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No std item found for '{}'. Use a path like 'std::vec::Vec::retain', 'Vec::retain', or 'mem::swap'.",
+                params.path
+            ))]));
+        }
+
+        Ok(json_response(&serde_json::json!({
+            "path": params.path,
+            "from": from,
+            "items": items,
+        })))
+    }
 
-                match serde_json::to_string_pretty(&json_results) {
-                    Ok(json) => Ok(CallToolResult::success(vec![Content::text(json)])),
-                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                        "Failed to serialize results: {}", e
-                    ))])),
+    #[tool(
+        name = "lookup_crate_docs",
+        description = "Look up an item of a crate published on crates.io (e.g. crate 'tokio', item 'sync::Mutex::lock'; crate 'serde', item 'Deserialize') and return its signature, documentation, and docs.rs link. Use it for API questions about third-party crates, which the indexed books don't cover. The first lookup of a release downloads its rustdoc JSON from docs.rs, which is only available for recent releases."
+    )]
+    async fn lookup_crate_docs(
+        &self,
+        Parameters(params): Parameters<LookupCrateDocsParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let limit = if params.limit == 0 {
+            3
+        } else {
+            params.limit.min(10)
+        };
+        let crate_docs = self.crate_docs.clone();
+        let (name, version) = (params.crate_name.clone(), params.version.clone());
+        let api =
+            match tokio::task::spawn_blocking(move || crate_docs.load(&name, version.as_deref()))
+                .await
+            {
+                Ok(Ok(api)) => api,
+                Ok(Err(e)) => {
+                    return Ok(CallToolResult::error(vec![Content::text(e.to_string())]));
                 }
-            }
-            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Search failed: {}", e
-            ))])),
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Crate documentation task failed: {}",
+                        e
+                    ))]));
+                }
+            };
+
+        let items: Vec<serde_json::Value> = api
+            .items(&params.item_path)
+            .into_iter()
+            .take(limit)
+            .map(|item| {
+                serde_json::json!({
+                    "path": item.path,
+                    "kind": item.kind,
+                    "signature": item.signature,
+                    "docs": truncate_api_docs(&item.docs),
+                    "doc_url": item.doc_url,
+                })
+            })
+            .collect();
+        if items.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No item found for '{}' in {} {}. Use a path like 'sync::Mutex', 'Mutex::lock', or 'Mutex', or browse {}.",
+                params.item_path,
+                api.name,
+                api.version,
+                api.docs_url()
+            ))]));
         }
+
+        Ok(json_response(&serde_json::json!({
+            "crate": api.name,
+            "version": api.version,
+            "item_path": params.item_path,
+            "docs_url": api.docs_url(),
+            "items": items,
+        })))
     }
 
     #[tool(
-        name = "show_example",
-        description = "Get code examples for a Rust topic. Searches Rust by Example for practical, runnable examples demonstrating iterators, pattern matching, closures, error handling, and more."
+        name = "show_trait_implementors",
+        description = "List the standard library types implementing a trait (e.g. 'Send', 'From<String>', 'Iterator'), with a rustdoc link and the indexed std source for each. Answers 'which std types are Send' or 'what converts from String'. Requires the std rustdoc JSON to be configured (sources.std_json)."
     )]
-    async fn show_example(
+    async fn show_trait_implementors(
         &self,
-        Parameters(params): Parameters<ShowExampleParams>,
+        Parameters(params): Parameters<ShowTraitImplementorsParams>,
     ) -> std::result::Result<CallToolResult, McpError> {
-        let limit = if params.limit == 0 { 3 } else { params.limit.min(10) };
+        if self.std_json.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "The std rustdoc JSON is not loaded. Install it with `rustup component add --toolchain nightly rust-docs-json` and set `std_json` in the [sources] section of data/config.toml to $(rustc +nightly --print sysroot)/share/doc/rust/json.",
+            )]));
+        }
+        let limit = if params.limit == 0 {
+            50
+        } else {
+            params.limit.min(200)
+        };
 
-        // Search primarily in rust-by-example
-        let sources = ["rust-by-example"];
-        let hybrid = HybridSearch::new(&self.keyword_index, &self.vector_index);
+        let (negative, implementors): (Vec<_>, Vec<_>) = self
+            .std_json
+            .implementors(&params.trait_name)
+            .into_iter()
+            .partition(|implementor| implementor.negative);
+        if implementors.is_empty() && negative.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No std implementors of '{}' found. Check the trait name; generic arguments must match exactly (e.g. 'From<&str>', not 'From<str>').",
+                params.trait_name
+            ))]));
+        }
+
+        let total = implementors.len();
+        let listed: Vec<serde_json::Value> = implementors
+            .iter()
+            .take(limit)
+            .map(|implementor| {
+                // The indexed std source of the type, when std_src is configured too
+                let uri = implementor
+                    .type_path
+                    .as_deref()
+                    .filter(|_| self.std_library.is_some())
+                    .and_then(|path| self.find_std_items(path).ok()?.into_iter().next())
+                    .map(|location| resources::document_uri(STD_SOURCE, &location));
+                serde_json::json!({
+                    "impl": implementor.header(),
+                    "type": implementor.for_type,
+                    "path": implementor.type_path,
+                    "auto": implementor.auto,
+                    "docs": implementor.doc_url,
+                    "uri": uri,
+                })
+            })
+            .collect();
+        let not_implemented_by: Vec<&str> = negative
+            .iter()
+            .map(|implementor| implementor.for_type.as_str())
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "trait": params.trait_name,
+            "total": total,
+            "implementors": listed,
+            "not_implemented_by": not_implemented_by,
+        })))
+    }
+
+    #[tool(
+        name = "whats_new",
+        description = "Summarize what changed in a Rust release or range of releases (e.g. '1.78', '1.70..1.78', 'latest') from the official release notes: language changes, stabilized APIs, Cargo and compatibility notes, with a link to each release's notes."
+    )]
+    async fn whats_new(
+        &self,
+        Parameters(params): Parameters<WhatsNewParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        if self.releases.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Release notes are not available. Download https://github.com/rust-lang/rust/blob/HEAD/RELEASES.md to {:?} and restart the server.",
+                Releases::file_path(&self.data_dir)
+            ))]));
+        }
 
-        let results = if !self.vector_index.is_empty() {
-            hybrid.search_with_sources(&params.topic, limit, Some(&sources))
+        let requested = params.version.trim();
+        let range = if requested.eq_ignore_ascii_case("latest") {
+            self.releases
+                .latest()
+                .and_then(|release| VersionRange::parse(&release.version.to_string()))
         } else {
-            self.keyword_index.search_with_sources(&params.topic, limit, Some(&sources))
+            VersionRange::parse(requested)
+        };
+        let Some(range) = range else {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Invalid version '{}'. Use a version (\"1.78\"), an inclusive range (\"1.70..1.78\"), an open range (\"1.75..\"), or \"latest\".",
+                params.version
+            ))]));
         };
 
-        match results {
-            Ok(results) => {
-                if results.is_empty() {
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "No examples found for '{}'. Try topics like 'iterators', 'match', 'closures', or 'error handling'.",
-                        params.topic
-                    ))]));
-                }
+        let mut releases = self.releases.in_range(&range);
+        if releases.is_empty() {
+            let latest = self
+                .releases
+                .latest()
+                .map(|release| release.version.to_string())
+                .unwrap_or_default();
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No Rust release matches '{}'. The newest release in the notes is {}.",
+                params.version, latest
+            ))]));
+        }
+        let total = releases.len();
+        // Keep the newest releases of a wide range
+        releases.drain(..total.saturating_sub(MAX_RELEASES));
 
-                let json_results: Vec<serde_json::Value> = results
-                    .into_iter()
-                    .map(|r| {
-                        serde_json::json!({
-                            "title": r.title,
-                            "example": r.snippet,
-                            "path": r.path,
-                            "source": r.source,
-                        })
+        let section_filter = params.section.as_deref().map(str::to_lowercase);
+        let listed: Vec<serde_json::Value> = releases
+            .iter()
+            .map(|release| {
+                let sections: Vec<serde_json::Value> = release
+                    .sections
+                    .iter()
+                    .filter(|(name, _)| {
+                        section_filter
+                            .as_deref()
+                            .is_none_or(|filter| name.to_lowercase().contains(filter))
                     })
+                    .map(|(name, entries)| serde_json::json!({ "name": name, "entries": entries }))
                     .collect();
+                serde_json::json!({
+                    "version": release.version.to_string(),
+                    "date": release.date,
+                    "citation": release.citation(),
+                    "uri": resources::document_uri(RELEASES_SOURCE, &release.path()),
+                    "sections": sections,
+                })
+            })
+            .collect();
+
+        let mut response = serde_json::json!({
+            "version": params.version,
+            "releases": listed,
+        });
+        if total > MAX_RELEASES {
+            response["note"] = serde_json::json!(format!(
+                "{} releases match; showing the newest {}. Narrow the range for older ones.",
+                total, MAX_RELEASES
+            ));
+        }
+        Ok(json_response(&response))
+    }
+
+    #[tool(
+        name = "stable_since",
+        description = "Find the Rust version a language feature or std API became stable (e.g. 'let-else', 'GATs', 'OnceLock', 'Option::is_some_and'), from the #[stable(since)] attributes in the std rustdoc JSON and the release notes, citing the release each answer comes from."
+    )]
+    async fn stable_since(
+        &self,
+        Parameters(params): Parameters<StableSinceParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let feature = params.feature.trim();
+        if feature.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Name a language feature or std API, e.g. 'let-else' or 'OnceLock'.",
+            )]));
+        }
+        if self.std_json.is_empty() && self.releases.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Neither the release notes nor the std rustdoc JSON are available; see the whats_new and show_trait_implementors tools for how to set them up.",
+            )]));
+        }
+
+        let stability = Stability::find(&self.std_json, &self.releases, feature);
+        if stability.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Nothing named '{}' found in the std rustdoc JSON or the release notes. Try the item's path (e.g. 'sync::OnceLock') or the wording the release notes use (e.g. 'let-else', 'generic associated types').",
+                feature
+            ))]));
+        }
+
+        let (stable_since, basis) = stability.since();
+        let api: Vec<serde_json::Value> = stability
+            .items
+            .iter()
+            .take(MAX_STABILITY_EVIDENCE)
+            .map(|item| {
+                serde_json::json!({
+                    "path": item.path,
+                    "kind": item.kind,
+                    "since": item.since.to_string(),
+                    "feature": item.feature,
+                    "docs": item.doc_url,
+                })
+            })
+            .collect();
+        let release_notes: Vec<serde_json::Value> = stability
+            .mentions
+            .iter()
+            .take(MAX_STABILITY_EVIDENCE)
+            .map(|mention| {
+                serde_json::json!({
+                    "version": mention.release.version.to_string(),
+                    "date": mention.release.date,
+                    "section": mention.section,
+                    "entry": mention.entry,
+                    "citation": mention.release.citation(),
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "feature": feature,
+            "stable_since": stable_since.map(|version| version.to_string()),
+            "basis": basis.as_str(),
+            "api": api,
+            "release_notes": release_notes,
+        })))
+    }
+
+    #[tool(
+        name = "msrv_check",
+        description = "Check which Rust version a set of language features and std APIs needs (e.g. ['let-else', 'OnceLock', 'Option::is_some_and']): the version each became stable in with a link to its release notes, the minimum Rust version they require together as a Cargo.toml rust-version line, and, given the project's MSRV, which features it rules out."
+    )]
+    async fn msrv_check(
+        &self,
+        Parameters(params): Parameters<MsrvCheckParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let features: Vec<&str> = params
+            .features
+            .iter()
+            .map(|feature| feature.trim())
+            .filter(|feature| !feature.is_empty())
+            .collect();
+        if features.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Name at least one language feature or std API, e.g. ['let-else', 'OnceLock'].",
+            )]));
+        }
+        if features.len() > MAX_MSRV_FEATURES {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "At most {} features can be checked at once; got {}.",
+                MAX_MSRV_FEATURES,
+                features.len()
+            ))]));
+        }
+        let msrv = match params.msrv.as_deref().map(msrv::parse_msrv) {
+            Some(None) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Invalid MSRV '{}'. Use a version like '1.70' or the manifest line 'rust-version = \"1.70\"'.",
+                    params.msrv.as_deref().unwrap_or_default()
+                ))]));
+            }
+            Some(Some(msrv)) => Some(msrv),
+            None => None,
+        };
+        if self.std_json.is_empty() && self.releases.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Neither the release notes nor the std rustdoc JSON are available; see the whats_new and show_trait_implementors tools for how to set them up.",
+            )]));
+        }
+
+        let mut required: Option<Version> = None;
+        let mut blocked: Vec<&str> = Vec::new();
+        let mut unknown: Vec<&str> = Vec::new();
+        let checked: Vec<serde_json::Value> = features
+            .iter()
+            .map(|&feature| {
+                let stability = Stability::find(&self.std_json, &self.releases, feature);
+                let (since, basis) = stability.since();
+                match since {
+                    Some(since) => {
+                        required = required.max(Some(since));
+                        if msrv.is_some_and(|msrv| since > msrv) {
+                            blocked.push(feature);
+                        }
+                    }
+                    None => unknown.push(feature),
+                }
+                serde_json::json!({
+                    "feature": feature,
+                    "stable_since": since.map(|version| version.to_string()),
+                    "basis": basis.as_str(),
+                    "api": stability.items.first().map(|item| &item.path),
+                    "citation": stability.citation(&self.releases),
+                    "available": match (since, msrv) {
+                        (Some(since), Some(msrv)) => Some(since <= msrv),
+                        _ => None,
+                    },
+                })
+            })
+            .collect();
+
+        Ok(json_response(&serde_json::json!({
+            "msrv": msrv.map(|version| version.to_string()),
+            "required": required.map(|version| version.to_string()),
+            "rust_version": required.map(msrv::rust_version_line),
+            "features": checked,
+            "blocked": blocked,
+            "unknown": unknown,
+        })))
+    }
+
+    #[tool(
+        name = "reembed",
+        description = "Regenerate the semantic search vectors of all indexed documentation with the configured embedding model and swap them in, e.g. after changing embeddings.model. Unchanged texts reuse cached embeddings; a full re-embed takes minutes. Searches keep using the current vectors until it finishes."
+    )]
+    async fn reembed(&self) -> std::result::Result<CallToolResult, McpError> {
+        if self.semantic_status() == SemanticStatus::Loading {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Semantic search is still loading; try again when it is ready.",
+            )]));
+        }
+        if self.rebuilding.swap(true, Ordering::AcqRel) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "A re-embed or documentation update is already running.",
+            )]));
+        }
+
+        let started = Instant::now();
+        let server = self.clone();
+        let model = self.embedding_model;
+        let result = tokio::task::spawn_blocking(move || {
+            indexer::reembed(
+                &server.keyword_index,
+                &server.data_dir,
+                server.embedding_batch_size,
+                &server.load_embeddings()?,
+            )
+        })
+        .await;
+        self.rebuilding.store(false, Ordering::Release);
+
+        let (vector_index, stats) = match result {
+            Ok(Ok(reembedded)) => reembedded,
+            Ok(Err(e)) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Re-embedding failed: {}",
+                    e
+                ))]));
+            }
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Re-embedding task failed: {}",
+                    e
+                ))]));
+            }
+        };
+        let vectors = vector_index.len();
+        *self.vector_index.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(vector_index);
+        self.result_cache.clear();
+        self.set_semantic_status(SemanticStatus::Ready);
+        tracing::info!(
+            "Re-embedded {} sections with {} in {:?}",
+            stats.sections,
+            model.id,
+            started.elapsed()
+        );
+
+        Ok(json_response(&serde_json::json!({
+            "model": model.id,
+            "dimension": model.dimension,
+            "sections": stats.sections,
+            "vectors": vectors,
+            "cached_embeddings": stats.cached,
+            "computed_embeddings": stats.computed,
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+        })))
+    }
+
+    #[tool(
+        name = "update_docs",
+        description = "Pull the latest commits of the documentation sources and re-index the documents that were added, changed, or removed since the last build, re-embedding only those. Reports which sources moved to a new commit and how many documents changed. Searches keep using the current indices until it finishes."
+    )]
+    async fn update_docs(&self) -> std::result::Result<CallToolResult, McpError> {
+        if self.semantic_status() == SemanticStatus::Loading {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Semantic search is still loading; try again when it is ready.",
+            )]));
+        }
+        if self.rebuilding.swap(true, Ordering::AcqRel) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "A re-embed or documentation update is already running.",
+            )]));
+        }
+
+        let started = Instant::now();
+        let server = self.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let pulls = pull_all_sources(&server.data_dir);
+            let updated = indexer::update_all_sources(
+                &server.keyword_index,
+                &server.data_dir,
+                server.std_library.as_deref(),
+                server.embedding_batch_size,
+                &server.load_embeddings()?,
+            )?;
+            Ok::<_, crate::error::Error>((pulls, updated))
+        })
+        .await;
+        self.rebuilding.store(false, Ordering::Release);
 
-                match serde_json::to_string_pretty(&json_results) {
-                    Ok(json) => Ok(CallToolResult::success(vec![Content::text(json)])),
-                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                        "Failed to serialize results: {}", e
-                    ))])),
+        let (pulls, (update, vector_index)) = match result {
+            Ok(Ok(updated)) => updated,
+            Ok(Err(e)) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Updating the documentation failed: {}",
+                    e
+                ))]));
+            }
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Documentation update task failed: {}",
+                    e
+                ))]));
+            }
+        };
+        if let Some(vector_index) = vector_index {
+            *self.vector_index.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(vector_index);
+            self.set_semantic_status(SemanticStatus::Ready);
+        }
+        self.result_cache.clear();
+        for source in &update.sources {
+            self.subscriptions.notify_source_updated(source).await;
+        }
+        tracing::info!(
+            "Updated the documentation in {:?}: {} documents added, {} changed, {} removed",
+            started.elapsed(),
+            update.added,
+            update.updated,
+            update.removed
+        );
+
+        let mut pulled = Vec::new();
+        let mut up_to_date = Vec::new();
+        let mut failed = serde_json::Map::new();
+        for (source, outcome) in pulls {
+            match outcome {
+                Ok(true) => pulled.push(source.id),
+                Ok(false) => up_to_date.push(source.id),
+                Err(e) => {
+                    failed.insert(source.id.to_string(), e.to_string().into());
                 }
             }
-            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Search failed: {}", e
-            ))])),
         }
+        Ok(json_response(&serde_json::json!({
+            "pulled": pulled,
+            "up_to_date": up_to_date,
+            "failed": failed,
+            "documents": {
+                "added": update.added,
+                "changed": update.updated,
+                "removed": update.removed,
+                "unchanged": update.unchanged,
+            },
+            "updated_sources": update.sources,
+            "sections_embedded": update.embedding.map(|stats| stats.sections),
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+        })))
+    }
+
+    #[tool(
+        name = "parse_report",
+        description = "Report documentation files that failed to parse during the last index build, per source: unreadable files, encoding errors, and mdBook includes of missing files. Use it to find gaps in the indexed corpus."
+    )]
+    async fn parse_report(
+        &self,
+        Parameters(params): Parameters<ParseReportParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let Some(mut report) = ParseReport::load(&self.data_dir.join("index")) else {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No parse report yet. It is written when the documentation index is built; delete the index directory to rebuild it.",
+            )]));
+        };
+
+        if let Some(source) = &params.source {
+            report.sources.retain(|s| s.source == *source);
+            if report.sources.is_empty() {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Source '{}' was not indexed. Indexed sources are listed by parse_report without a source.",
+                    source
+                ))]));
+            }
+        }
+
+        Ok(json_response(&serde_json::json!({
+            "files_parsed": report.sources.iter().map(|s| s.parsed).sum::<usize>(),
+            "problem_count": report.problem_count(),
+            "sources": report.sources,
+        })))
+    }
+
+    #[tool(
+        name = "corpus_stats",
+        description = "Report statistics of the indexed documentation, overall and per source: document counts, document lengths in words (mean and percentiles), vocabulary size, code blocks, and how many documents have semantic search vectors. Use it to diagnose retrieval quality, e.g. a source dominating results or documents only keyword search can find."
+    )]
+    async fn corpus_stats(
+        &self,
+        Parameters(params): Parameters<CorpusStatsParams>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let mut documents = match self.keyword_index.all_documents() {
+            Ok(documents) => documents,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read the index: {}",
+                    e
+                ))]));
+            }
+        };
+        if let Some(source) = &params.source {
+            documents.retain(|doc| doc.source == *source);
+            if documents.is_empty() {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "No documents indexed for source '{}'.",
+                    source
+                ))]));
+            }
+        }
+
+        let vector_index = self.vector_index();
+        let (corpus, sources) = corpus_stats(&documents, &vector_index);
+        Ok(json_response(&serde_json::json!({
+            "corpus": corpus,
+            "vectors": vector_index.len(),
+            "semantic_status": self.semantic_status().as_str(),
+            "sources": sources,
+        })))
+    }
+
+    #[tool(
+        name = "list_sources",
+        description = "List the documentation sources: whether each is cloned, the commit it is at, when it was last cloned or pulled, and how many documents are indexed from it. Use it to check coverage, e.g. why a search returns nothing from a source."
+    )]
+    async fn list_sources(&self) -> std::result::Result<CallToolResult, McpError> {
+        let titles = match self.keyword_index.document_titles() {
+            Ok(titles) => titles,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read the index: {}",
+                    e
+                ))]));
+            }
+        };
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for (source, _, _) in &titles {
+            *counts.entry(source.as_str()).or_default() += 1;
+        }
+
+        let mut sources: Vec<serde_json::Value> = doc_sources()
+            .iter()
+            .map(|source| {
+                let checkout = source.checkout(&self.data_dir);
+                serde_json::json!({
+                    "id": source.id,
+                    "name": source.name,
+                    "repo": source.repo,
+                    "pin": source.pin,
+                    "cloned": checkout.is_some(),
+                    "checkout": checkout,
+                    "documents": counts.remove(source.id).unwrap_or(0),
+                })
+            })
+            .collect();
+        // Sources that aren't cloned repositories, like the standard library and the
+        // downloaded pages of rust-lang/rust
+        sources.extend(
+            counts
+                .into_iter()
+                .map(|(id, documents)| serde_json::json!({ "id": id, "documents": documents })),
+        );
+
+        Ok(json_response(&serde_json::json!({
+            "documents": titles.len(),
+            "indexing": self.is_indexing(),
+            "sources": sources,
+        })))
+    }
+}
+
+#[prompt_router]
+impl RustDocServer {
+    #[prompt(
+        name = "explain_compiler_error",
+        description = "Explain a Rust compiler error. The server parses the diagnostics, retrieves the chapters that explain them, and returns a message with the error, the numbered passages, and instructions to cite them."
+    )]
+    async fn explain_compiler_error(
+        &self,
+        Parameters(args): Parameters<ExplainCompilerErrorArgs>,
+    ) -> std::result::Result<GetPromptResult, McpError> {
+        // Each distinct diagnostic is searched on its own; unparsed text is searched as-is
+        let mut headlines: Vec<(String, String)> = parse_compiler_output(&args.error)
+            .iter()
+            .map(|d| {
+                let headline = format!("{} {}", d.code.as_deref().unwrap_or_default(), d.message);
+                (headline, d.search_query())
+            })
+            .collect();
+        if headlines.is_empty() {
+            headlines.push((args.error.clone(), args.error.clone()));
+        }
+
+        let options = SearchOptions::new(PASSAGES_PER_QUERY * 2)
+            .with_sources(Some(CONCEPT_SOURCES))
+            .with_snippet_len(PASSAGE_SNIPPET_LEN);
+
+        let mut passages = Vec::new();
+        for (headline, query) in headlines.iter().take(MAX_PROMPT_QUERIES) {
+            let patterns = error_patterns::matching_patterns(headline);
+            let query = error_patterns::expand_query(query, &patterns);
+            let mut outcome = self
+                .run_search(&query, SearchMode::Hybrid, &options)
+                .await?
+                .map_err(|e| McpError::internal_error(format!("Search failed: {}", e), None))?;
+            error_patterns::apply_boost(&patterns, &mut outcome.results);
+            outcome.results.truncate(PASSAGES_PER_QUERY);
+            prompts::add_passages(&mut passages, outcome.results);
+        }
+
+        Ok(GetPromptResult {
+            description: Some(
+                "Explain a Rust compiler error with documentation citations".to_string(),
+            ),
+            messages: vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                prompts::explain_error_message(&args.error, &passages),
+            )],
+        })
+    }
+
+    #[prompt(
+        name = "doc_grounded_code_review",
+        description = "Review Rust code against the documentation. The server checks the code for common non-idiomatic constructs, retrieves the Design Patterns and API Guidelines sections that apply (plus the optional focus), and returns a review request with the numbered passages and instructions to cite them."
+    )]
+    async fn doc_grounded_code_review(
+        &self,
+        Parameters(args): Parameters<CodeReviewArgs>,
+    ) -> std::result::Result<GetPromptResult, McpError> {
+        let findings = check_snippet(&args.code);
+
+        let mut passages = Vec::new();
+        if let Some(focus) = &args.focus {
+            let mut results = self.prompt_passages(focus, BEST_PRACTICE_SOURCES).await?;
+            results.truncate(PASSAGES_PER_QUERY);
+            prompts::add_passages(&mut passages, results);
+        }
+        for finding in findings.iter().take(MAX_PROMPT_QUERIES) {
+            let mut results = self
+                .prompt_passages(finding.idiom.query, IDIOM_SOURCES)
+                .await?;
+            finding.idiom.apply_boost(&mut results);
+            results.truncate(PASSAGES_PER_QUERY);
+            prompts::add_passages(&mut passages, results);
+        }
+        if passages.is_empty() {
+            let mut results = self
+                .prompt_passages(
+                    "API design naming error handling guidelines",
+                    BEST_PRACTICE_SOURCES,
+                )
+                .await?;
+            results.truncate(PASSAGES_PER_QUERY);
+            prompts::add_passages(&mut passages, results);
+        }
+
+        let flagged: Vec<&str> = findings.iter().map(|f| f.idiom.id).collect();
+        Ok(GetPromptResult {
+            description: Some("Review Rust code with documentation citations".to_string()),
+            messages: vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                prompts::code_review_message(&args.code, &flagged, &passages),
+            )],
+        })
+    }
+
+    #[prompt(
+        name = "error_handling_strategy",
+        description = "Suggest an error handling strategy for a Rust project. The server retrieves the Book's chapters on Result, `?`, and panics, the Design Patterns and API Guidelines sections on error types that fit the project, and (given code) the sections on its non-idiomatic constructs, and returns a request for a strategy with the numbered passages and instructions to cite them."
+    )]
+    async fn error_handling_strategy(
+        &self,
+        Parameters(args): Parameters<ErrorHandlingArgs>,
+    ) -> std::result::Result<GetPromptResult, McpError> {
+        let findings = args.code.as_deref().map(check_snippet).unwrap_or_default();
+
+        let mut passages = Vec::new();
+        let mut results = self
+            .prompt_passages(ERROR_HANDLING_QUERY, CONCEPT_SOURCES)
+            .await?;
+        results.truncate(PASSAGES_PER_QUERY);
+        prompts::add_passages(&mut passages, results);
+        let mut results = self
+            .prompt_passages(
+                &format!("error types {}", args.project),
+                BEST_PRACTICE_SOURCES,
+            )
+            .await?;
+        results.truncate(PASSAGES_PER_QUERY);
+        prompts::add_passages(&mut passages, results);
+        for finding in findings.iter().take(MAX_PROMPT_QUERIES) {
+            let mut results = self
+                .prompt_passages(finding.idiom.query, IDIOM_SOURCES)
+                .await?;
+            finding.idiom.apply_boost(&mut results);
+            results.truncate(PASSAGES_PER_QUERY);
+            prompts::add_passages(&mut passages, results);
+        }
+
+        let flagged: Vec<&str> = findings.iter().map(|f| f.idiom.id).collect();
+        Ok(GetPromptResult {
+            description: Some(
+                "Suggest an error handling strategy with documentation citations".to_string(),
+            ),
+            messages: vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                prompts::error_handling_message(
+                    &args.project,
+                    args.code.as_deref(),
+                    &flagged,
+                    &passages,
+                ),
+            )],
+        })
     }
 }
 
-#[tool_handler(router = self.tool_router)]
+#[prompt_handler(router = self.prompt_router)]
 impl ServerHandler for RustDocServer {
+    // Tool dispatch is written out (rather than generated by `tool_handler`) to log every call
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let tool = request.name.clone();
+        let request_id = logging::request_id();
+        let query_hash = logging::query_hash(request.arguments.as_ref());
+        let span = logging::tool_call_span(&request_id, &tool);
+        let started = Instant::now();
+
+        let mut result = self
+            .tool_router
+            .call(ToolCallContext::new(self, request, context))
+            .instrument(span.clone())
+            .await;
+        span.in_scope(|| logging::log_tool_call(&tool, &query_hash, started.elapsed(), &result));
+        logging::attach_request_id(&mut result, &request_id);
+        if let Ok(output) = result.as_mut()
+            && self.is_indexing()
+        {
+            output.content.push(Content::text(INDEXING_NOTE));
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult {
+            tools: self.tool_router.list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some("Rust documentation search server providing access to The Rust Book, Rust Reference, Rust by Example, Design Patterns, API Guidelines, and Rustonomicon.".into()),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some("Rust documentation search server providing access to The Rust Book, Rust Reference, Rust by Example, Design Patterns, API Guidelines, Rustonomicon, the Async Book, the Edition Guide, The Little Book of Rust Macros, the Unsafe Code Guidelines, and Rust and WebAssembly.".into()),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_logging()
+                .build(),
             ..Default::default()
         }
     }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListResourcesResult, McpError> {
+        // The cursor is the position of the page's first document
+        let start = match request.and_then(|request| request.cursor) {
+            Some(cursor) => cursor.parse::<usize>().map_err(|_| {
+                McpError::invalid_params(format!("Invalid cursor {:?}", cursor), None)
+            })?,
+            None => 0,
+        };
+        let resources = self.document_resources().map_err(|e| {
+            McpError::internal_error(format!("Failed to list documents: {}", e), None)
+        })?;
+        let end = resources
+            .len()
+            .min(start.saturating_add(RESOURCES_PAGE_SIZE));
+        Ok(ListResourcesResult {
+            meta: None,
+            next_cursor: (end < resources.len()).then(|| end.to_string()),
+            resources: resources.get(start..end).unwrap_or_default().to_vec(),
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListResourceTemplatesResult, McpError> {
+        let template = RawResourceTemplate {
+            uri_template: DOC_URI_TEMPLATE.to_string(),
+            name: "rust-doc".to_string(),
+            title: Some("Indexed Rust documentation".to_string()),
+            description: Some(
+                "An indexed document by source ID and path, as returned in search results (e.g. rust-doc://rust-book/ch04-01-what-is-ownership.md)".to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            template.no_annotation(),
+        ]))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ReadResourceResult, McpError> {
+        let Some((source, path)) = resources::parse_document_uri(&request.uri) else {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Expected a URI like {}, got {}",
+                    DOC_URI_TEMPLATE, request.uri
+                ),
+                None,
+            ));
+        };
+
+        match self.document(&source, &path) {
+            Ok(Some(doc)) => Ok(ReadResourceResult {
+                contents: vec![ResourceContents::TextResourceContents {
+                    uri: request.uri,
+                    mime_type: Some("text/markdown".to_string()),
+                    text: resources::render_document(&doc),
+                    meta: None,
+                }],
+            }),
+            Ok(None) => Err(McpError::resource_not_found(
+                format!("No indexed document at {}", request.uri),
+                None,
+            )),
+            Err(e) => Err(McpError::internal_error(
+                format!("Failed to read {}: {}", request.uri, e),
+                None,
+            )),
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        if resources::parse_document_uri(&request.uri).is_none() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Expected a URI like {}, got {}",
+                    DOC_URI_TEMPLATE, request.uri
+                ),
+                None,
+            ));
+        }
+        self.subscriptions
+            .subscribe(self.session, request.uri, context.peer);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        self.subscriptions.unsubscribe(self.session, &request.uri);
+        Ok(())
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<(), McpError> {
+        *self.log_level.write().unwrap_or_else(|e| e.into_inner()) = request.level;
+        Ok(())
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.startup_log
+            .add(context.peer.clone(), self.log_level.clone());
+        self.refresh_workspace(&context.peer).await
+    }
+
+    async fn on_roots_list_changed(&self, context: NotificationContext<RoleServer>) {
+        self.refresh_workspace(&context.peer).await
+    }
 }
@@ -1,8 +1,13 @@
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
 
 use git2::{FetchOptions, RemoteCallbacks};
+use serde::Serialize;
 
+use crate::config::{MIRROR_PLACEHOLDER, SourcesConfig};
 use crate::error::{Error, Result};
+use crate::profiles::is_known_source;
 
 /// Configuration for a documentation source
 #[derive(Debug, Clone)]
@@ -15,6 +20,31 @@ pub struct DocSource {
     pub repo: &'static str,
     /// Path to markdown source files within the repo
     pub src_path: &'static str,
+    /// How the source's files become documents
+    pub kind: SourceKind,
+    /// Branch or tag checked out instead of the repository's default branch
+    pub pin: Option<&'static str>,
+}
+
+/// The commit checked out in a source's clone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Checkout {
+    pub commit: String,
+    /// Commit date, RFC 3339 in UTC
+    pub committed: String,
+    /// When the clone was last cloned or pulled, RFC 3339 in UTC
+    pub updated: Option<String>,
+}
+
+/// Layout of a documentation source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// An mdBook: each markdown file under `src_path` is a document
+    Book,
+    /// The rustlings exercises: one document per exercise listed in `info.toml`
+    Rustlings,
+    /// Clippy: one document per lint declared with `declare_clippy_lint!`
+    Clippy,
 }
 
 impl DocSource {
@@ -23,70 +53,304 @@ impl DocSource {
         self.repo.split('/').next_back().unwrap_or(self.id)
     }
 
+    /// Get the path of the cloned repository given a data directory
+    pub fn repo_path(&self, data_dir: &Path) -> PathBuf {
+        data_dir.join(self.dir_name())
+    }
+
     /// Get the full path to the source files given a data directory
     pub fn docs_path(&self, data_dir: &Path) -> PathBuf {
-        data_dir.join(self.dir_name()).join(self.src_path)
+        self.repo_path(data_dir).join(self.src_path)
     }
 
-    /// Get the git clone URL
-    pub fn clone_url(&self) -> String {
-        format!("https://github.com/{}.git", self.repo)
+    /// The commit checked out in the source's clone, or `None` when it isn't cloned
+    pub fn checkout(&self, data_dir: &Path) -> Option<Checkout> {
+        let repo = git2::Repository::open(self.repo_path(data_dir)).ok()?;
+        let commit = repo.head().ok()?.peel_to_commit().ok()?;
+        // Cloning writes HEAD, and every pull fetches
+        let updated = ["FETCH_HEAD", "HEAD"]
+            .iter()
+            .filter_map(|name| {
+                std::fs::metadata(repo.path().join(name))
+                    .ok()?
+                    .modified()
+                    .ok()
+            })
+            .max()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| utc_timestamp(since.as_secs() as i64));
+        Some(Checkout {
+            commit: commit.id().to_string(),
+            committed: utc_timestamp(commit.time().seconds()),
+            updated,
+        })
+    }
+
+    /// Get the git clone URL, from GitHub or from a mirror URL template
+    pub fn clone_url(&self, mirror: Option<&str>) -> String {
+        match mirror {
+            Some(template) => template.replace(MIRROR_PLACEHOLDER, self.repo),
+            None => format!("https://github.com/{}.git", self.repo),
+        }
     }
 }
 
-/// All supported documentation sources
+/// Built-in documentation sources, used unless the configuration changes them
 pub const DOC_SOURCES: &[DocSource] = &[
     DocSource {
         id: "rust-book",
         name: "The Rust Programming Language",
         repo: "rust-lang/book",
         src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
     },
     DocSource {
         id: "rust-reference",
         name: "The Rust Reference",
         repo: "rust-lang/reference",
         src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
     },
     DocSource {
         id: "rust-by-example",
         name: "Rust by Example",
         repo: "rust-lang/rust-by-example",
         src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
     },
     DocSource {
         id: "rust-patterns",
         name: "Rust Design Patterns",
         repo: "rust-unofficial/patterns",
         src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
     },
     DocSource {
         id: "api-guidelines",
         name: "Rust API Guidelines",
         repo: "rust-lang/api-guidelines",
         src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
     },
     DocSource {
         id: "rustonomicon",
         name: "The Rustonomicon",
         repo: "rust-lang/nomicon",
         src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "async-book",
+        name: "Asynchronous Programming in Rust",
+        repo: "rust-lang/async-book",
+        src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "edition-guide",
+        name: "The Rust Edition Guide",
+        repo: "rust-lang/edition-guide",
+        src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "tlborm",
+        name: "The Little Book of Rust Macros",
+        repo: "Veykril/tlborm",
+        src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "unsafe-code-guidelines",
+        name: "Unsafe Code Guidelines Reference",
+        repo: "rust-lang/unsafe-code-guidelines",
+        src_path: "reference/src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "rustwasm-book",
+        name: "Rust and WebAssembly",
+        repo: "rustwasm/book",
+        src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "cargo-book",
+        name: "The Cargo Book",
+        repo: "rust-lang/cargo",
+        src_path: "src/doc/src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "rustc-dev-guide",
+        name: "Rust Compiler Development Guide",
+        repo: "rust-lang/rustc-dev-guide",
+        src_path: "src",
+        kind: SourceKind::Book,
+        pin: None,
+    },
+    DocSource {
+        id: "rustlings",
+        name: "Rustlings",
+        repo: "rust-lang/rustlings",
+        src_path: "exercises",
+        kind: SourceKind::Rustlings,
+        pin: None,
+    },
+    DocSource {
+        id: "clippy",
+        name: "Clippy Lints",
+        repo: "rust-lang/rust-clippy",
+        src_path: "clippy_lints/src",
+        kind: SourceKind::Clippy,
+        pin: None,
     },
 ];
 
+/// Sources set from the configuration at startup
+static CONFIGURED: OnceLock<Vec<DocSource>> = OnceLock::new();
+
+/// The documentation sources in use: the configured ones, or the built-in `DOC_SOURCES`
+pub fn doc_sources() -> &'static [DocSource] {
+    CONFIGURED.get().map_or(DOC_SOURCES, Vec::as_slice)
+}
+
+/// Use the sources of `[sources]` in the configuration for the rest of the process: the
+/// built-in sources that aren't disabled, with their pins, followed by the configured books.
+///
+/// Call it once at startup, before anything is cloned or indexed; later calls are ignored.
+pub fn configure(config: &SourcesConfig) -> Result<()> {
+    validate(config)?;
+    let pin = |id: &str| config.pins.get(id).map(|pin| leak(pin));
+    let built_in = DOC_SOURCES
+        .iter()
+        .filter(|source| !config.disabled.iter().any(|id| id == source.id))
+        .map(|source| DocSource {
+            pin: pin(source.id),
+            ..source.clone()
+        });
+    let books = config.books.iter().map(|(id, book)| DocSource {
+        id: leak(id),
+        name: leak(book.name.as_deref().unwrap_or(id)),
+        repo: leak(&book.repo),
+        src_path: leak(book.src_path.as_deref().unwrap_or("src")),
+        kind: SourceKind::Book,
+        pin: pin(id),
+    });
+    let sources: Vec<DocSource> = built_in.chain(books).collect();
+    if CONFIGURED.set(sources).is_err() {
+        tracing::warn!("Documentation sources were already configured; ignoring the new ones");
+    }
+    Ok(())
+}
+
+/// Check `[sources]`: disabled and pinned sources must exist, and books need a new ID and a
+/// GitHub `owner/name` repository
+pub fn validate(config: &SourcesConfig) -> Result<()> {
+    let is_built_in = |id: &str| DOC_SOURCES.iter().any(|source| source.id == id);
+    if let Some(id) = config.disabled.iter().find(|id| !is_built_in(id)) {
+        return Err(Error::Other(format!(
+            "Unknown source {:?} in sources.disabled",
+            id
+        )));
+    }
+    if let Some(id) = config
+        .pins
+        .keys()
+        .find(|id| !is_built_in(id) && !config.books.contains_key(*id))
+    {
+        return Err(Error::Other(format!(
+            "Unknown source {:?} in sources.pins",
+            id
+        )));
+    }
+    for (id, book) in &config.books {
+        if is_known_source(id) || id.is_empty() || id.contains('/') {
+            return Err(Error::Other(format!(
+                "sources.books.{} needs an ID that isn't taken and has no '/'",
+                id
+            )));
+        }
+        let valid_repo = book.repo.split_once('/').is_some_and(|(owner, name)| {
+            !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        });
+        if !valid_repo {
+            return Err(Error::Other(format!(
+                "sources.books.{}.repo {:?} must be a GitHub owner/name",
+                id, book.repo
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A configured string, kept for the rest of the process like the built-in sources' strings
+fn leak(text: &str) -> &'static str {
+    Box::leak(text.to_string().into_boxed_str())
+}
+
+/// `seconds` since the Unix epoch as an RFC 3339 timestamp in UTC
+fn utc_timestamp(seconds: i64) -> String {
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    // Civil date of a day count, in years starting March 1 (Howard Hinnant's civil_from_days)
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
 /// Get a documentation source by ID
-#[allow(dead_code)]
 pub fn get_source(id: &str) -> Option<&'static DocSource> {
-    DOC_SOURCES.iter().find(|s| s.id == id)
+    doc_sources().iter().find(|s| s.id == id)
+}
+
+/// Clone all documentation sources that don't already exist, from GitHub or `mirror`
+pub fn clone_all_sources(data_dir: &Path, mirror: Option<&str>) -> Result<usize> {
+    clone_missing_sources(data_dir, mirror, |_| {})
 }
 
-/// Clone all documentation sources that don't already exist
-pub fn clone_all_sources(data_dir: &Path) -> Result<usize> {
+/// `clone_all_sources`, calling `on_clone` with each source before it is cloned
+pub fn clone_missing_sources(
+    data_dir: &Path,
+    mirror: Option<&str>,
+    mut on_clone: impl FnMut(&DocSource),
+) -> Result<usize> {
     std::fs::create_dir_all(data_dir)?;
 
     let mut cloned = 0;
 
-    for source in DOC_SOURCES {
+    for source in doc_sources() {
         let target_dir = data_dir.join(source.dir_name());
 
         if target_dir.exists() {
@@ -94,9 +358,11 @@ pub fn clone_all_sources(data_dir: &Path) -> Result<usize> {
             continue;
         }
 
-        tracing::info!("Cloning {} from {}...", source.name, source.clone_url());
+        let url = source.clone_url(mirror);
+        tracing::info!("Cloning {} from {}...", source.name, url);
+        on_clone(source);
 
-        match clone_repo(&source.clone_url(), &target_dir) {
+        match clone_repo(&url, &target_dir, source.pin) {
             Ok(()) => {
                 tracing::info!("Successfully cloned {}", source.name);
                 cloned += 1;
@@ -110,8 +376,34 @@ pub fn clone_all_sources(data_dir: &Path) -> Result<usize> {
     Ok(cloned)
 }
 
-/// Clone a single git repository with shallow clone (depth 1)
-fn clone_repo(url: &str, target: &Path) -> Result<()> {
+/// Clone a single git repository with shallow clone (depth 1), checking out `pin` (a branch
+/// or tag) instead of the default branch when given
+fn clone_repo(url: &str, target: &Path, pin: Option<&str>) -> Result<()> {
+    let git_error = |e: git2::Error| Error::Other(format!("Git clone failed: {}", e));
+
+    if let Some(pin) = pin {
+        let cloned = git2::Repository::init(target).and_then(|repo| {
+            repo.remote("origin", url)?;
+            checkout_ref(&repo, pin)
+        });
+        if cloned.is_err() {
+            // Don't leave a clone behind that would be taken for a complete one
+            let _ = std::fs::remove_dir_all(target);
+        }
+        return cloned.map_err(git_error);
+    }
+
+    // Build the clone
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch_options());
+
+    builder.clone(url, target).map_err(git_error)?;
+
+    Ok(())
+}
+
+/// Options of a shallow fetch (depth 1), reporting progress
+fn fetch_options() -> FetchOptions<'static> {
     // Set up callbacks for progress reporting
     let mut callbacks = RemoteCallbacks::new();
     callbacks.transfer_progress(|progress| {
@@ -132,18 +424,105 @@ fn clone_repo(url: &str, target: &Path) -> Result<()> {
         true
     });
 
-    // Set up fetch options with depth 1 (shallow clone)
     let mut fetch_options = FetchOptions::new();
     fetch_options.remote_callbacks(callbacks);
     fetch_options.depth(1);
+    fetch_options
+}
 
-    // Build the clone
-    let mut builder = git2::build::RepoBuilder::new();
-    builder.fetch_options(fetch_options);
+/// Pull the latest commit of every cloned documentation source from the remote it was cloned
+/// from, returning each source with whether it moved to a new commit
+pub fn pull_all_sources(data_dir: &Path) -> Vec<(&'static DocSource, Result<bool>)> {
+    doc_sources()
+        .iter()
+        .filter(|source| source.repo_path(data_dir).join(".git").exists())
+        .map(|source| {
+            tracing::info!("Pulling {}...", source.name);
+            let pulled = pull_repo(&source.repo_path(data_dir), source.pin);
+            match &pulled {
+                Ok(true) => tracing::info!("Updated {}", source.name),
+                Ok(false) => tracing::debug!("{} is up to date", source.name),
+                Err(e) => tracing::warn!("Failed to pull {}: {}", source.name, e),
+            }
+            (source, pulled)
+        })
+        .collect()
+}
 
-    builder
-        .clone(url, target)
-        .map_err(|e| Error::Other(format!("Git clone failed: {}", e)))?;
+/// Fetch `pin`, or the checked-out branch of an unpinned clone, and check it out. Returns
+/// whether the checked-out commit changed.
+fn pull_repo(repo_path: &Path, pin: Option<&str>) -> Result<bool> {
+    let git_error = |e: git2::Error| Error::Other(format!("Git pull failed: {}", e));
 
-    Ok(())
+    let repo = git2::Repository::open(repo_path).map_err(git_error)?;
+    let head = repo.head().map_err(git_error)?;
+    let current = head.target();
+    let name = match pin {
+        Some(pin) => pin.to_string(),
+        None if head.is_branch() => head.shorthand().unwrap_or_default().to_string(),
+        None => {
+            return Err(Error::Other(
+                "Git pull failed: HEAD is not on a branch; pin the source or delete its clone"
+                    .to_string(),
+            ));
+        }
+    };
+    drop(head);
+
+    checkout_ref(&repo, &name).map_err(git_error)?;
+    let moved = repo.head().map_err(git_error)?.target() != current;
+    Ok(moved)
+}
+
+/// Fetch the branch or tag `name` from origin (depth 1) and check it out: a branch as the
+/// local branch of that name, a tag as a detached HEAD.
+///
+/// A shallow clone has no history to find a merge base in, so a branch is moved to the
+/// fetched commit without one; the clones are never edited locally, so this is the
+/// fast-forward a full clone would do.
+fn checkout_ref(repo: &git2::Repository, name: &str) -> std::result::Result<(), git2::Error> {
+    let branch = format!("refs/remotes/origin/{}", name);
+    let tag = format!("refs/tags/{}", name);
+    repo.find_remote("origin")?.fetch(
+        &[
+            format!("+refs/heads/{}:{}", name, branch),
+            format!("+{0}:{0}", tag),
+        ],
+        Some(&mut fetch_options()),
+        None,
+    )?;
+
+    let force = || {
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force();
+        checkout
+    };
+    if let Ok(fetched) = repo.refname_to_id(&branch) {
+        let commit = repo.find_commit(fetched)?;
+        repo.checkout_tree(commit.as_object(), Some(&mut force()))?;
+        let local = format!("refs/heads/{}", name);
+        repo.reference(&local, fetched, true, "checkout: fast-forward")?;
+        repo.set_head(&local)
+    } else if let Ok(fetched) = repo.refname_to_id(&tag) {
+        let commit = repo.find_object(fetched, None)?.peel_to_commit()?;
+        repo.checkout_tree(commit.as_object(), Some(&mut force()))?;
+        repo.set_head_detached(commit.id())
+    } else {
+        Err(git2::Error::from_str(&format!(
+            "no branch or tag {:?} on the remote",
+            name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_timestamp(951_827_696), "2000-02-29T12:34:56Z");
+        assert_eq!(utc_timestamp(1_767_225_599), "2025-12-31T23:59:59Z");
+    }
 }
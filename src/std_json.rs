@@ -0,0 +1,987 @@
+//! The standard library's rustdoc JSON.
+//!
+//! When `sources.std_json` points at the JSON output of rustdoc for `core`, `alloc`, and `std`
+//! (the nightly `rust-docs-json` rustup component), it is read once at startup for two things:
+//!
+//! - Every trait impl, so `show_trait_implementors` can answer "who implements `From<String>`"
+//!   or "which std types are `Send`". Auto trait impls that rustdoc synthesizes are included;
+//!   blanket impls are listed once, under their generic type, rather than for every type they
+//!   cover.
+//! - The `#[stable(since)]` version of every public item and method, for `stable_since` and
+//!   `msrv_check`.
+//! - The signature and documentation of every public item and method, for `lookup_std_api`.
+//!   Signatures are rendered from the JSON types like the impl headers, without lifetimes.
+//!
+//! The parser isn't specific to std: `crate_docs` reads the rustdoc JSON docs.rs builds for
+//! crates.io releases with it, linking items to docs.rs instead.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::releases::Version;
+use crate::std_source;
+
+/// JSON files loaded from the `std_json` directory
+const CRATE_FILES: &[&str] = &["core.json", "alloc.json", "std.json"];
+
+/// Documentation site the `doc_url` links point at
+const DOCS_URL: &str = "https://doc.rust-lang.org";
+
+/// A trait impl
+#[derive(Debug, Clone, PartialEq)]
+pub struct Implementor {
+    /// Trait name, e.g. `From`
+    pub trait_name: String,
+    /// Full path of the trait, e.g. `core::convert::From`
+    pub trait_path: String,
+    /// Generic arguments of the trait without the angle brackets, e.g. `String`; empty if none
+    pub trait_args: String,
+    /// The implementing type as written, e.g. `Box<dyn Error>`
+    pub for_type: String,
+    /// Full path of the implementing type, e.g. `alloc::boxed::Box`; `None` for primitives,
+    /// references, tuples, and generic (blanket) impls
+    pub type_path: Option<String>,
+    /// rustdoc page of the implementing type
+    pub doc_url: Option<String>,
+    /// Auto trait impl synthesized by rustdoc (`Send`, `Sync`, `Unpin`, ...)
+    pub auto: bool,
+    /// `impl !Trait for Type`
+    pub negative: bool,
+}
+
+impl Implementor {
+    /// The impl header, e.g. `impl From<String> for Box<dyn Error>`
+    pub fn header(&self) -> String {
+        let args = if self.trait_args.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", self.trait_args)
+        };
+        let not = if self.negative { "!" } else { "" };
+        format!(
+            "impl {}{}{} for {}",
+            not, self.trait_name, args, self.for_type
+        )
+    }
+}
+
+/// A stable item or method
+#[derive(Debug, Clone, PartialEq)]
+pub struct StableItem {
+    /// Full path, e.g. `std::sync::OnceLock` or `core::option::Option::is_some_and`
+    pub path: String,
+    /// rustdoc item kind (`struct`, `function`, `method`, ...)
+    pub kind: String,
+    /// Version the item was stabilized in
+    pub since: Version,
+    /// Feature gate it was stabilized under, e.g. `once_cell`
+    pub feature: String,
+    /// rustdoc page of the item
+    pub doc_url: Option<String>,
+}
+
+/// A documented item or method
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiItem {
+    /// Full path, e.g. `alloc::vec::Vec::retain`
+    pub path: String,
+    /// rustdoc item kind (`struct`, `function`, `method`, ...)
+    pub kind: String,
+    /// Declaration without the body, e.g. `pub fn retain<F>(&mut self, f: F) where F: FnMut(&T) -> bool`
+    pub signature: String,
+    /// Doc comment (markdown)
+    pub docs: String,
+    /// Version the item was stabilized in; `None` for unstable items
+    pub since: Option<Version>,
+    /// rustdoc page of the item
+    pub doc_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Crate {
+    format_version: u32,
+    index: HashMap<u32, Item>,
+    paths: HashMap<u32, ItemSummary>,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    name: Option<String>,
+    /// Strings in older format versions, `{"other": "..."}` objects in newer ones
+    #[serde(default)]
+    attrs: Vec<Value>,
+    #[serde(default)]
+    docs: Option<String>,
+    inner: ItemInner,
+}
+
+/// Externally tagged item kind; impls and traits, and the declarations of items with a page
+#[derive(Deserialize)]
+struct ItemInner {
+    #[serde(rename = "impl")]
+    impl_: Option<Impl>,
+    #[serde(rename = "trait")]
+    trait_: Option<Trait>,
+    function: Option<Function>,
+    #[serde(rename = "struct")]
+    struct_: Option<Generic>,
+    #[serde(rename = "enum")]
+    enum_: Option<Generic>,
+    union: Option<Generic>,
+    type_alias: Option<Typed>,
+    constant: Option<Typed>,
+    #[serde(rename = "static")]
+    static_: Option<Typed>,
+    #[serde(rename = "macro")]
+    macro_: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Function {
+    /// `decl` in older format versions
+    #[serde(alias = "decl")]
+    sig: Option<Value>,
+    #[serde(default)]
+    generics: Value,
+    #[serde(default)]
+    header: Value,
+}
+
+/// An item declared with generics
+#[derive(Deserialize)]
+struct Generic {
+    #[serde(default)]
+    generics: Value,
+}
+
+/// An item declared with a type
+#[derive(Deserialize)]
+struct Typed {
+    #[serde(rename = "type", default)]
+    type_: Value,
+    #[serde(default)]
+    generics: Value,
+}
+
+#[derive(Deserialize)]
+struct Impl {
+    #[serde(rename = "trait")]
+    trait_: Option<TypePath>,
+    #[serde(rename = "for")]
+    for_: Value,
+    #[serde(default)]
+    items: Vec<u32>,
+    is_synthetic: bool,
+    is_negative: bool,
+    blanket_impl: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Trait {
+    #[serde(default)]
+    items: Vec<u32>,
+    #[serde(default)]
+    generics: Value,
+}
+
+#[derive(Deserialize)]
+struct TypePath {
+    path: String,
+    id: u32,
+    args: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct ItemSummary {
+    path: Vec<String>,
+    kind: String,
+}
+
+/// What the standard library's rustdoc JSON is used for
+#[derive(Debug, Default)]
+pub struct StdJson {
+    implementors: Vec<Implementor>,
+    stable_items: Vec<StableItem>,
+    api_items: Vec<ApiItem>,
+}
+
+impl StdJson {
+    /// Load the rustdoc JSON files in `dir`; empty when none can be read
+    pub fn load(dir: &Path) -> Self {
+        let mut std_json = Self::default();
+        for file in CRATE_FILES {
+            let path = dir.join(file);
+            if !path.exists() {
+                continue;
+            }
+            match std::fs::read_to_string(&path)
+                .map_err(Error::from)
+                .and_then(|json| parse(&json))
+            {
+                Ok(parsed) => {
+                    std_json.implementors.extend(parsed.implementors);
+                    std_json.stable_items.extend(parsed.stable_items);
+                    std_json.api_items.extend(parsed.api_items);
+                }
+                Err(e) => tracing::warn!("Failed to load rustdoc JSON {:?}: {}", path, e),
+            }
+        }
+        if std_json.is_empty() {
+            tracing::warn!("No items found in rustdoc JSON directory {:?}", dir);
+        }
+
+        // std re-exports core and alloc items, so the same impl can appear in several files
+        let mut seen = HashSet::new();
+        std_json
+            .implementors
+            .retain(|i| seen.insert((i.trait_path.clone(), i.header())));
+        std_json
+            .implementors
+            .sort_by(|a, b| a.for_type.cmp(&b.for_type));
+        tracing::info!(
+            "Loaded {} std trait impls, {} stable items, and {} documented items",
+            std_json.implementors.len(),
+            std_json.stable_items.len(),
+            std_json.api_items.len()
+        );
+        std_json
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.implementors.is_empty() && self.stable_items.is_empty() && self.api_items.is_empty()
+    }
+
+    /// Impls of a trait given as `Send`, `From<String>`, or `std::convert::From<&str>`.
+    ///
+    /// The trait name is matched case-insensitively, a module path must match the end of the
+    /// trait's path, and generic arguments, when given, must match exactly (ignoring whitespace
+    /// and lifetimes).
+    pub fn implementors(&self, query: &str) -> Vec<&Implementor> {
+        let query = strip_lifetimes(query);
+        let (name, args) = match query.split_once('<') {
+            Some((name, args)) => (
+                name.trim(),
+                Some(compact(args.trim_end().trim_end_matches('>'))),
+            ),
+            None => (query.trim(), None),
+        };
+        let mut segments: Vec<&str> = name.split("::").map(str::trim).collect();
+        if matches!(segments.first(), Some(&("std" | "core" | "alloc"))) {
+            segments.remove(0);
+        }
+        let Some((trait_name, module)) = segments.split_last() else {
+            return Vec::new();
+        };
+        let module = module.join("::");
+
+        self.implementors
+            .iter()
+            .filter(|i| i.trait_name.eq_ignore_ascii_case(trait_name))
+            .filter(|i| module.is_empty() || i.trait_path.contains(&format!("::{}::", module)))
+            .filter(|i| {
+                args.as_ref()
+                    .is_none_or(|args| compact(&i.trait_args) == *args)
+            })
+            .collect()
+    }
+
+    /// Stable items named `query` (`OnceLock`, `Option::is_some_and`, `std::mem::take`), best
+    /// matches first: the same path, then more specific requests, then items the name ends
+    pub fn stable_items(&self, query: &str) -> Vec<&StableItem> {
+        let mut matches: Vec<(usize, &StableItem)> = self
+            .stable_items
+            .iter()
+            .filter_map(|item| {
+                // Compare without the crate, as std_source names items
+                let name = item
+                    .path
+                    .split_once("::")
+                    .map_or(&*item.path, |(_, name)| name);
+                Some((std_source::match_rank(name, query)?, item))
+            })
+            .collect();
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then(a.since.cmp(&b.since))
+                .then(a.path.cmp(&b.path))
+        });
+        matches.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// Documented items named `query` (`Vec::retain`, `std::vec::Vec::retain`, `mem::swap`),
+    /// best matches first, as for `stable_items`
+    pub fn api_items(&self, query: &str) -> Vec<&ApiItem> {
+        let mut matches: Vec<(usize, &ApiItem)> = self
+            .api_items
+            .iter()
+            .filter_map(|item| {
+                let name = item
+                    .path
+                    .split_once("::")
+                    .map_or(&*item.path, |(_, name)| name);
+                Some((std_source::match_rank(name, query)?, item))
+            })
+            .collect();
+        matches.sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then(a.path.cmp(&b.path)));
+        matches.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// Trait impls, stable items, and documented items in one crate's rustdoc JSON
+pub fn parse(json: &str) -> Result<StdJson> {
+    parse_crate(json, DOCS_URL)
+}
+
+/// Like [`parse`], for a crate documented at `docs_url` (e.g. `https://docs.rs/serde/1.0.219`);
+/// links to primitive types still point at the std documentation
+pub fn parse_crate(json: &str, docs_url: &str) -> Result<StdJson> {
+    let krate: Crate = serde_json::from_str(json)
+        .map_err(|e| Error::Other(format!("Unsupported rustdoc JSON: {}", e)))?;
+    tracing::debug!("rustdoc JSON format version {}", krate.format_version);
+
+    let full_path = |id: u32| krate.paths.get(&id).map(|summary| summary.path.join("::"));
+    let doc_url = |id: u32| {
+        let summary = krate.paths.get(&id)?;
+        item_doc_url(docs_url, &summary.path, &summary.kind)
+    };
+
+    let mut implementors = Vec::new();
+    let mut stable_items = Vec::new();
+    let mut api_items = Vec::new();
+    for (&id, item) in &krate.index {
+        // Items with a page of their own
+        if let Some(summary) = krate.paths.get(&id) {
+            let stable = stable_since(&item.attrs);
+            if let Some(signature) = signature(item) {
+                api_items.push(ApiItem {
+                    path: summary.path.join("::"),
+                    kind: summary.kind.clone(),
+                    signature,
+                    docs: item.docs.clone().unwrap_or_default(),
+                    since: stable.as_ref().map(|(since, _)| *since),
+                    doc_url: doc_url(id),
+                });
+            }
+            if let Some((since, feature)) = stable {
+                stable_items.push(StableItem {
+                    path: summary.path.join("::"),
+                    kind: summary.kind.clone(),
+                    since,
+                    feature,
+                    doc_url: doc_url(id),
+                });
+            }
+        }
+
+        // Methods, documented on their type's or trait's page
+        let (parent, children) = match (&item.inner.impl_, &item.inner.trait_) {
+            (Some(imp), _) if imp.trait_.is_none() => {
+                (imp.for_["resolved_path"]["id"].as_u64(), &imp.items)
+            }
+            (_, Some(trait_)) => (Some(id as u64), &trait_.items),
+            _ => continue,
+        };
+        let Some(parent) = parent.map(|parent| parent as u32) else {
+            continue;
+        };
+        let Some(parent_path) = full_path(parent) else {
+            continue;
+        };
+        for child in children.iter().filter_map(|child| krate.index.get(child)) {
+            let Some(name) = &child.name else {
+                continue;
+            };
+            let stable = stable_since(&child.attrs);
+            let method_url = doc_url(parent).map(|url| format!("{}#method.{}", url, name));
+            if let Some(signature) = signature(child) {
+                api_items.push(ApiItem {
+                    path: format!("{}::{}", parent_path, name),
+                    kind: "method".to_string(),
+                    signature,
+                    docs: child.docs.clone().unwrap_or_default(),
+                    since: stable.as_ref().map(|(since, _)| *since),
+                    doc_url: method_url.clone(),
+                });
+            }
+            let Some((since, feature)) = stable else {
+                continue;
+            };
+            stable_items.push(StableItem {
+                path: format!("{}::{}", parent_path, name),
+                kind: "method".to_string(),
+                since,
+                feature,
+                doc_url: method_url,
+            });
+        }
+    }
+
+    for imp in krate
+        .index
+        .values()
+        .filter_map(|item| item.inner.impl_.as_ref())
+    {
+        // Blanket impls are repeated on every type they cover; keep the generic one
+        if imp.blanket_impl.is_some() {
+            continue;
+        }
+        let Some(trait_) = &imp.trait_ else {
+            continue;
+        };
+        let type_id = imp.for_["resolved_path"]["id"].as_u64();
+        let summary = type_id.and_then(|id| krate.paths.get(&(id as u32)));
+        implementors.push(Implementor {
+            trait_name: last_segment(&trait_.path).to_string(),
+            trait_path: full_path(trait_.id).unwrap_or_else(|| trait_.path.clone()),
+            trait_args: trait_.args.as_ref().map(render_args).unwrap_or_default(),
+            for_type: render_type(&imp.for_),
+            type_path: summary.map(|summary| summary.path.join("::")),
+            doc_url: match summary {
+                Some(summary) => item_doc_url(docs_url, &summary.path, &summary.kind),
+                None => primitive_doc_url(&imp.for_),
+            },
+            auto: imp.is_synthetic,
+            negative: imp.is_negative,
+        });
+    }
+
+    Ok(StdJson {
+        implementors,
+        stable_items,
+        api_items,
+    })
+}
+
+/// Declaration of an item without its body, e.g. `pub fn swap<T>(x: &mut T, y: &mut T)`;
+/// `None` for kinds without one (modules, impls, associated types)
+fn signature(item: &Item) -> Option<String> {
+    let name = item.name.as_deref()?;
+    let inner = &item.inner;
+    if let Some(function) = &inner.function {
+        let header = &function.header;
+        let qualifiers: String = [
+            ("is_const", "const "),
+            ("is_async", "async "),
+            ("is_unsafe", "unsafe "),
+        ]
+        .iter()
+        .filter(|(flag, _)| header[*flag].as_bool() == Some(true))
+        .map(|(_, qualifier)| *qualifier)
+        .collect();
+        let sig = function.sig.as_ref().unwrap_or(&Value::Null);
+        let inputs: Vec<String> = sig["inputs"]
+            .as_array()
+            .map(|inputs| inputs.iter().map(render_input).collect())
+            .unwrap_or_default();
+        let output = match &sig["output"] {
+            Value::Null => String::new(),
+            ty => format!(" -> {}", render_type(ty)),
+        };
+        let (params, bounds) = render_generics(&function.generics);
+        return Some(format!(
+            "pub {}fn {}{}({}){}{}",
+            qualifiers,
+            name,
+            params,
+            inputs.join(", "),
+            output,
+            bounds
+        ));
+    }
+
+    let declared = |keyword: &str, generics: &Value| {
+        let (params, bounds) = render_generics(generics);
+        format!("pub {} {}{}{}", keyword, name, params, bounds)
+    };
+    let signature = if let Some(generic) = &inner.struct_ {
+        declared("struct", &generic.generics)
+    } else if let Some(generic) = &inner.enum_ {
+        declared("enum", &generic.generics)
+    } else if let Some(generic) = &inner.union {
+        declared("union", &generic.generics)
+    } else if let Some(trait_) = &inner.trait_ {
+        declared("trait", &trait_.generics)
+    } else if let Some(alias) = &inner.type_alias {
+        format!(
+            "{} = {}",
+            declared("type", &alias.generics),
+            render_type(&alias.type_)
+        )
+    } else if let Some(constant) = &inner.constant {
+        format!("pub const {}: {}", name, render_type(&constant.type_))
+    } else if let Some(static_) = &inner.static_ {
+        format!("pub static {}: {}", name, render_type(&static_.type_))
+    } else if let Some(macro_) = &inner.macro_ {
+        // The whole `macro_rules!` definition; its first line names the macro
+        macro_.lines().next().unwrap_or(macro_).to_string()
+    } else {
+        return None;
+    };
+    Some(signature)
+}
+
+/// A function parameter: `self`, `&mut self`, or `name: Type`
+fn render_input(input: &Value) -> String {
+    let name = input[0].as_str().unwrap_or("_");
+    let ty = &input[1];
+    if name == "self" {
+        let is_self = |ty: &Value| ty["generic"].as_str() == Some("Self");
+        if is_self(ty) {
+            return "self".to_string();
+        }
+        if is_self(&ty["borrowed_ref"]["type"]) {
+            let mutable = ty["borrowed_ref"]["is_mutable"].as_bool() == Some(true);
+            return if mutable { "&mut self" } else { "&self" }.to_string();
+        }
+    }
+    format!("{}: {}", name, render_type(ty))
+}
+
+/// Generic parameters (`<T: Clone, const N: usize>`) and the where clause
+/// (` where F: FnMut(&T) -> bool`) of a declaration; lifetimes and the parameters of
+/// `impl Trait` arguments are left out
+fn render_generics(generics: &Value) -> (String, String) {
+    let params: Vec<String> = generics["params"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|param| {
+            let name = param["name"].as_str()?;
+            let kind = &param["kind"];
+            if let Some(ty) = kind.get("type") {
+                if ty["is_synthetic"].as_bool() == Some(true) {
+                    return None;
+                }
+                Some(match render_bounds(&ty["bounds"]) {
+                    bounds if bounds.is_empty() => name.to_string(),
+                    bounds => format!("{}: {}", name, bounds),
+                })
+            } else {
+                let ty = kind.get("const")?;
+                Some(format!("const {}: {}", name, render_type(&ty["type"])))
+            }
+        })
+        .collect();
+    let predicates: Vec<String> = generics["where_predicates"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|predicate| {
+            let predicate = predicate.get("bound_predicate")?;
+            let bounds = render_bounds(&predicate["bounds"]);
+            (!bounds.is_empty()).then(|| format!("{}: {}", render_type(&predicate["type"]), bounds))
+        })
+        .collect();
+
+    let params = if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    };
+    let predicates = if predicates.is_empty() {
+        String::new()
+    } else {
+        format!(" where {}", predicates.join(", "))
+    };
+    (params, predicates)
+}
+
+/// Trait bounds joined with ` + ` (`?Sized` included, lifetimes left out)
+fn render_bounds(bounds: &Value) -> String {
+    bounds
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|bound| {
+            let bound = bound.get("trait_bound")?;
+            let maybe = if bound["modifier"].as_str() == Some("maybe") {
+                "?"
+            } else {
+                ""
+            };
+            let trait_ = render_type(&serde_json::json!({ "resolved_path": bound["trait"] }));
+            Some(format!("{}{}", maybe, trait_))
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Version and feature of a `#[stable]` attribute, in either of the forms rustdoc has used:
+/// `#[stable(feature = "once_cell", since = "1.70.0")]`, or the parsed
+/// `#[attr = Stability {stability: Stability {level: Stable {since: Version(RustcVersion {
+/// major: 1, minor: 70, patch: 0 })}, feature: "once_cell"}}]`
+fn stable_since(attrs: &[Value]) -> Option<(Version, String)> {
+    attrs.iter().find_map(|attr| {
+        let text = attr.as_str().or_else(|| attr["other"].as_str())?;
+        let quoted = |key: &str| {
+            let rest = &text[text.find(key)? + key.len()..];
+            rest.split('"').next().map(str::to_string)
+        };
+        if text.starts_with("#[stable(") {
+            let since = Version::parse(&quoted("since = \"")?)?;
+            return Some((since, quoted("feature = \"")?));
+        }
+        if text.starts_with("#[attr = Stability ") && text.contains("level: Stable ") {
+            let number = |key: &str| -> Option<u32> {
+                let rest = &text[text.find(key)? + key.len()..];
+                rest.split(|c: char| !c.is_ascii_digit())
+                    .next()?
+                    .parse()
+                    .ok()
+            };
+            let since = Version {
+                major: number("major: ")?,
+                minor: number("minor: ")?,
+                patch: number("patch: ")?,
+            };
+            return Some((since, quoted("feature: \"")?));
+        }
+        None
+    })
+}
+
+/// Render a rustdoc JSON type as Rust syntax, without lifetimes
+fn render_type(ty: &Value) -> String {
+    let Some((kind, value)) = ty.as_object().and_then(|object| object.iter().next()) else {
+        return "_".to_string();
+    };
+    match kind.as_str() {
+        "resolved_path" => {
+            let name = last_segment(value["path"].as_str().unwrap_or("_"));
+            // `Fn(&T) -> bool`
+            if let Some(parenthesized) = value["args"].get("parenthesized") {
+                let inputs: Vec<String> = parenthesized["inputs"]
+                    .as_array()
+                    .map(|inputs| inputs.iter().map(render_type).collect())
+                    .unwrap_or_default();
+                let output = match &parenthesized["output"] {
+                    Value::Null => String::new(),
+                    ty => format!(" -> {}", render_type(ty)),
+                };
+                return format!("{}({}){}", name, inputs.join(", "), output);
+            }
+            match value
+                .get("args")
+                .filter(|args| !args.is_null())
+                .map(render_args)
+            {
+                Some(args) if !args.is_empty() => format!("{}<{}>", name, args),
+                _ => name.to_string(),
+            }
+        }
+        "generic" | "primitive" => value.as_str().unwrap_or("_").to_string(),
+        "borrowed_ref" => {
+            let mutable = if value["is_mutable"].as_bool() == Some(true) {
+                "mut "
+            } else {
+                ""
+            };
+            format!("&{}{}", mutable, render_type(&value["type"]))
+        }
+        "raw_pointer" => {
+            let mutability = if value["is_mutable"].as_bool() == Some(true) {
+                "mut"
+            } else {
+                "const"
+            };
+            format!("*{} {}", mutability, render_type(&value["type"]))
+        }
+        "slice" => format!("[{}]", render_type(value)),
+        "array" => format!(
+            "[{}; {}]",
+            render_type(&value["type"]),
+            value["len"].as_str().unwrap_or("_")
+        ),
+        "tuple" => {
+            let types: Vec<String> = value
+                .as_array()
+                .map(|types| types.iter().map(render_type).collect())
+                .unwrap_or_default();
+            if types.len() == 1 {
+                format!("({},)", types[0])
+            } else {
+                format!("({})", types.join(", "))
+            }
+        }
+        "dyn_trait" => {
+            let traits: Vec<String> = value["traits"]
+                .as_array()
+                .map(|traits| {
+                    traits
+                        .iter()
+                        .map(|bound| {
+                            render_type(&serde_json::json!({ "resolved_path": bound["trait"] }))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            format!("dyn {}", traits.join(" + "))
+        }
+        "qualified_path" => format!(
+            "{}::{}",
+            render_type(&value["self_type"]),
+            value["name"].as_str().unwrap_or("_")
+        ),
+        "function_pointer" => "fn".to_string(),
+        "impl_trait" => format!("impl {}", render_bounds(value)),
+        _ => "_".to_string(),
+    }
+}
+
+/// Render `angle_bracketed` generic arguments (types and consts; lifetimes are left out)
+fn render_args(args: &Value) -> String {
+    let Some(args) = args["angle_bracketed"]["args"].as_array() else {
+        return String::new();
+    };
+    args.iter()
+        .filter_map(|arg| {
+            if let Some(ty) = arg.get("type") {
+                Some(render_type(ty))
+            } else {
+                arg["const"]["expr"].as_str().map(str::to_string)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// rustdoc page of a named item under `docs_url`, e.g.
+/// `https://doc.rust-lang.org/alloc/string/struct.String.html`
+fn item_doc_url(docs_url: &str, path: &[String], kind: &str) -> Option<String> {
+    let (name, modules) = path.split_last()?;
+    let kind = match kind {
+        "module" => {
+            return Some(format!("{}/{}/index.html", docs_url, path.join("/")));
+        }
+        "struct" | "enum" | "union" | "trait" | "primitive" | "macro" | "constant" | "static" => {
+            kind
+        }
+        "function" => "fn",
+        "type_alias" => "type",
+        "trait_alias" => "traitalias",
+        _ => return None,
+    };
+    Some(format!(
+        "{}/{}/{}.{}.html",
+        docs_url,
+        modules.join("/"),
+        kind,
+        name
+    ))
+}
+
+/// rustdoc page of a primitive type, e.g. `https://doc.rust-lang.org/std/primitive.str.html`
+fn primitive_doc_url(ty: &Value) -> Option<String> {
+    let (kind, value) = ty.as_object()?.iter().next()?;
+    let primitive = match kind.as_str() {
+        "primitive" => value.as_str()?,
+        "slice" => "slice",
+        "array" => "array",
+        "tuple" => "tuple",
+        "borrowed_ref" => "reference",
+        "raw_pointer" => "pointer",
+        "function_pointer" => "fn",
+        _ => return None,
+    };
+    Some(format!("{}/std/primitive.{}.html", DOCS_URL, primitive))
+}
+
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Remove whitespace, for comparing types
+fn compact(ty: &str) -> String {
+    ty.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Remove lifetimes (`&'a str` becomes `&str`, `Cow<'a, str>` becomes `Cow<str>`)
+fn strip_lifetimes(ty: &str) -> String {
+    let mut stripped = String::with_capacity(ty.len());
+    let mut chars = ty.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            stripped.push(c);
+            continue;
+        }
+        while chars
+            .next_if(|c| c.is_alphanumeric() || *c == '_')
+            .is_some()
+        {}
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+    }
+    stripped.replace("<>", "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed rustdoc JSON (format version 57) for a crate with a few impls
+    const JSON: &str = r##"{
+        "format_version": 57,
+        "index": {
+            "10": {"inner": {"impl": {
+                "trait": {"path": "From", "id": 23, "args": {"angle_bracketed": {"args": [{"type": {"resolved_path": {"path": "String", "id": 44, "args": null}}}], "constraints": []}}},
+                "for": {"resolved_path": {"path": "Name", "id": 54, "args": null}},
+                "is_synthetic": false, "is_negative": false, "blanket_impl": null}}},
+            "11": {"inner": {"impl": {
+                "trait": {"path": "From", "id": 23, "args": {"angle_bracketed": {"args": [{"type": {"borrowed_ref": {"lifetime": "'a", "is_mutable": false, "type": {"primitive": "str"}}}}], "constraints": []}}},
+                "for": {"resolved_path": {"path": "Name", "id": 54, "args": null}},
+                "is_synthetic": false, "is_negative": false, "blanket_impl": null}}},
+            "12": {"inner": {"impl": {
+                "trait": {"path": "From", "id": 23, "args": {"angle_bracketed": {"args": [{"type": {"generic": "T"}}], "constraints": []}}},
+                "for": {"resolved_path": {"path": "Name", "id": 54, "args": null}},
+                "is_synthetic": false, "is_negative": false, "blanket_impl": {"generic": "T"}}}},
+            "13": {"inner": {"impl": {
+                "trait": {"path": "Send", "id": 3, "args": null},
+                "for": {"resolved_path": {"path": "Name", "id": 54, "args": null}},
+                "is_synthetic": true, "is_negative": false, "blanket_impl": null}}},
+            "14": {"inner": {"impl": {
+                "trait": {"path": "Send", "id": 3, "args": null},
+                "for": {"resolved_path": {"path": "Rc", "id": 75, "args": {"angle_bracketed": {"args": [{"type": {"generic": "T"}}], "constraints": []}}}},
+                "is_synthetic": true, "is_negative": true, "blanket_impl": null}}},
+            "15": {"inner": {"impl": {
+                "trait": {"path": "Send", "id": 3, "args": null},
+                "for": {"slice": {"primitive": "u8"}},
+                "is_synthetic": true, "is_negative": false, "blanket_impl": null}}},
+            "16": {"inner": {"impl": {
+                "trait": null,
+                "for": {"resolved_path": {"path": "Name", "id": 54, "args": null}},
+                "is_synthetic": false, "is_negative": false, "blanket_impl": null}}},
+            "17": {"inner": {"impl": {
+                "trait": null,
+                "for": {"resolved_path": {"path": "Name", "id": 54, "args": null}},
+                "items": [18],
+                "is_synthetic": false, "is_negative": false, "blanket_impl": null}}},
+            "18": {"name": "as_str", "attrs": ["#[stable(feature = \"name_as_str\", since = \"1.75.0\")]"], "docs": "The name as a string slice.", "inner": {"function": {
+                "sig": {"inputs": [["self", {"borrowed_ref": {"lifetime": null, "is_mutable": false, "type": {"generic": "Self"}}}]], "output": {"borrowed_ref": {"lifetime": null, "is_mutable": false, "type": {"primitive": "str"}}}, "is_c_variadic": false},
+                "generics": {"params": [], "where_predicates": []},
+                "header": {"is_const": true, "is_unsafe": false, "is_async": false, "abi": "Rust"}, "has_body": true}}},
+            "19": {"name": "retain_names", "attrs": [], "docs": "Keeps the names `keep` returns true for.\n\nUnstable.", "inner": {"function": {
+                "sig": {"inputs": [["names", {"borrowed_ref": {"lifetime": null, "is_mutable": true, "type": {"resolved_path": {"path": "Vec", "id": 76, "args": {"angle_bracketed": {"args": [{"type": {"resolved_path": {"path": "Name", "id": 54, "args": null}}}], "constraints": []}}}}}}], ["keep", {"generic": "F"}]], "output": null, "is_c_variadic": false},
+                "generics": {"params": [
+                    {"name": "'a", "kind": {"lifetime": {"outlives": []}}},
+                    {"name": "F", "kind": {"type": {"bounds": [], "default": null, "is_synthetic": false}}}
+                ], "where_predicates": [{"bound_predicate": {"type": {"generic": "F"}, "bounds": [{"trait_bound": {"trait": {"path": "FnMut", "id": 77, "args": {"parenthesized": {"inputs": [{"borrowed_ref": {"lifetime": null, "is_mutable": false, "type": {"resolved_path": {"path": "Name", "id": 54, "args": null}}}}], "output": {"primitive": "bool"}}}}, "generic_params": [], "modifier": "none"}}], "generic_params": []}}]},
+                "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"}, "has_body": true}}},
+            "54": {"name": "Name", "docs": "A name.", "attrs": [{"other": "#[attr = Stability {stability: Stability {level: Stable {since: Version(RustcVersion { major: 1, minor: 70, patch: 0 })},\nfeature: \"name\"}}]"}], "inner": {"struct": {"kind": "unit"}}}
+        },
+        "paths": {
+            "3": {"crate_id": 2, "path": ["core", "marker", "Send"], "kind": "trait"},
+            "23": {"crate_id": 2, "path": ["core", "convert", "From"], "kind": "trait"},
+            "44": {"crate_id": 3, "path": ["alloc", "string", "String"], "kind": "struct"},
+            "19": {"crate_id": 0, "path": ["alloc", "name", "retain_names"], "kind": "function"},
+            "54": {"crate_id": 0, "path": ["alloc", "name", "Name"], "kind": "struct"},
+            "75": {"crate_id": 3, "path": ["alloc", "rc", "Rc"], "kind": "struct"}
+        }
+    }"##;
+
+    #[test]
+    fn test_parse_impls() {
+        let mut implementors = parse(JSON).unwrap().implementors;
+        implementors.sort_by_key(|i| i.header());
+        let headers: Vec<String> = implementors.iter().map(Implementor::header).collect();
+        assert_eq!(
+            headers,
+            vec![
+                "impl !Send for Rc<T>",
+                "impl From<&str> for Name",
+                "impl From<String> for Name",
+                "impl Send for Name",
+                "impl Send for [u8]",
+            ]
+        );
+
+        let from_string = &implementors[2];
+        assert_eq!(from_string.trait_path, "core::convert::From");
+        assert_eq!(from_string.type_path.as_deref(), Some("alloc::name::Name"));
+        assert_eq!(
+            from_string.doc_url.as_deref(),
+            Some("https://doc.rust-lang.org/alloc/name/struct.Name.html")
+        );
+        assert_eq!(
+            implementors[4].doc_url.as_deref(),
+            Some("https://doc.rust-lang.org/std/primitive.slice.html")
+        );
+    }
+
+    #[test]
+    fn test_implementors_query() {
+        let impls = parse(JSON).unwrap();
+        let types = |query: &str| {
+            let mut types: Vec<String> = impls
+                .implementors(query)
+                .iter()
+                .map(|i| i.header())
+                .collect();
+            types.sort();
+            types
+        };
+
+        assert_eq!(types("From<String>"), vec!["impl From<String> for Name"]);
+        assert_eq!(
+            types("std::convert::From<&'a str>"),
+            vec!["impl From<&str> for Name"]
+        );
+        assert_eq!(types("from").len(), 2);
+        assert_eq!(types("send").len(), 3);
+        assert!(types("fmt::From").is_empty());
+        assert_eq!(strip_lifetimes("Cow<'a, str>"), "Cow<str>");
+        assert_eq!(strip_lifetimes("Foo<'a>"), "Foo");
+    }
+
+    #[test]
+    fn test_stable_items() {
+        let std_json = parse(JSON).unwrap();
+
+        let name = std_json.stable_items("Name");
+        assert_eq!(name[0].path, "alloc::name::Name");
+        assert_eq!(name[0].since.to_string(), "1.70.0");
+        assert_eq!(name[0].feature, "name");
+
+        let method = std_json.stable_items("std::name::Name::as_str");
+        assert_eq!(method.len(), 1);
+        assert_eq!(method[0].since.to_string(), "1.75.0");
+        assert_eq!(
+            method[0].doc_url.as_deref(),
+            Some("https://doc.rust-lang.org/alloc/name/struct.Name.html#method.as_str")
+        );
+        assert!(std_json.stable_items("Rc").is_empty());
+    }
+
+    #[test]
+    fn test_api_items() {
+        let std_json = parse(JSON).unwrap();
+        let signature = |query: &str| {
+            let items = std_json.api_items(query);
+            assert_eq!(items.len(), 1, "{}", query);
+            items[0].signature.clone()
+        };
+
+        assert_eq!(signature("Name"), "pub struct Name");
+        assert_eq!(
+            signature("std::name::Name::as_str"),
+            "pub const fn as_str(&self) -> &str"
+        );
+        assert_eq!(
+            signature("name::retain_names"),
+            "pub fn retain_names<F>(names: &mut Vec<Name>, keep: F) where F: FnMut(&Name) -> bool"
+        );
+
+        let as_str = std_json.api_items("Name::as_str")[0];
+        assert_eq!(as_str.path, "alloc::name::Name::as_str");
+        assert_eq!(as_str.docs, "The name as a string slice.");
+        assert_eq!(
+            as_str.since.map(|since| since.to_string()).as_deref(),
+            Some("1.75.0")
+        );
+        assert!(std_json.api_items("retain_names")[0].since.is_none());
+        assert!(std_json.api_items("Rc").is_empty());
+    }
+}
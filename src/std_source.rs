@@ -0,0 +1,668 @@
+//! Rust standard library sources.
+//!
+//! When `sources.std_src` points at a `library` directory (for example from the `rust-src`
+//! rustup component), the public functions, structs, and enums of `core`, `alloc`, and `std` are
+//! indexed one document per item: the doc comments as text and the item's source as code. The
+//! `show_std_impl` tool reads the implementation back from the file.
+//!
+//! Items are found with a lightweight scan rather than a Rust parser: braces and semicolons
+//! outside comments, strings, and char literals delimit bodies, and `impl`/`trait` blocks give
+//! methods their type.
+
+use std::path::{Path, PathBuf};
+
+use crate::parse_report::SourceReport;
+use crate::parsing::{Document, parse_markdown};
+
+/// Source ID of the standard library sources
+pub const STD_SOURCE: &str = "std-src";
+
+/// Crates indexed, in the order their items are searched
+const CRATES: &[&str] = &["core", "alloc", "std"];
+
+/// Directories holding tests rather than library code
+const SKIPPED_DIRS: &[&str] = &["tests", "benches"];
+
+/// Qualifiers that may precede `fn`
+const FN_QUALIFIERS: &[&str] = &[
+    "const ",
+    "async ",
+    "unsafe ",
+    "default ",
+    "safe ",
+    "extern \"C\" ",
+    "extern \"Rust\" ",
+];
+
+/// A public item of the standard library
+#[derive(Debug, Clone, PartialEq)]
+pub struct StdItem {
+    /// Name qualified by its type or module, e.g. `Option::map` or `mem::swap`
+    pub name: String,
+    /// 1-based line of the item's first line, after its attributes
+    pub line: usize,
+    /// Doc comment text (markdown)
+    pub docs: String,
+    /// Source of the item, dedented
+    pub code: String,
+}
+
+impl StdItem {
+    /// First paragraph of the docs
+    pub fn summary(&self) -> &str {
+        self.docs.split("\n\n").next().unwrap_or_default().trim()
+    }
+
+    /// Declaration before the body: the code up to its opening brace, or up to the final
+    /// semicolon of an item without a body (`pub struct Wrapper(u8);`)
+    pub fn signature(&self) -> &str {
+        let end = self
+            .code
+            .find('{')
+            .or_else(|| self.code.rfind(';'))
+            .unwrap_or(self.code.len());
+        self.code[..end].trim()
+    }
+}
+
+/// How well an item name matches a requested one: 0 for the same name, 1 when the request is
+/// more qualified (`std::option::Option::map` for `Option::map`), 2 when less (`map`)
+pub fn match_rank(name: &str, wanted: &str) -> Option<usize> {
+    let wanted = normalize_item(wanted);
+    let is_suffix = |long: &str, short: &str| long.ends_with(&format!("::{}", short));
+    if name == wanted {
+        Some(0)
+    } else if is_suffix(&wanted, name) {
+        Some(1)
+    } else if is_suffix(name, &wanted) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Strip the crate, generics, and call parentheses from a requested item name
+pub fn normalize_item(item: &str) -> String {
+    let mut name = String::new();
+    let mut depth = 0;
+    for c in item.trim().trim_end_matches("()").chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            c if depth == 0 && !c.is_whitespace() => name.push(c),
+            _ => {}
+        }
+    }
+    ["std::", "core::", "alloc::"]
+        .iter()
+        .find_map(|krate| name.strip_prefix(krate))
+        .map(str::to_string)
+        .unwrap_or(name)
+}
+
+/// Find the `library` directory at or below a configured path
+pub fn library_dir(path: &Path) -> Option<PathBuf> {
+    [path.to_path_buf(), path.join("library")]
+        .into_iter()
+        .find(|dir| dir.join("core").join("src").is_dir())
+}
+
+/// Split a document path like `core/src/option.rs:1071` into the file and line
+pub fn split_location(path: &str) -> Option<(&str, usize)> {
+    let (file, line) = path.rsplit_once(':')?;
+    Some((file, line.parse().ok()?))
+}
+
+/// Build one document per public item of `core`, `alloc`, and `std`
+pub fn collect_documents(library: &Path, report: &mut SourceReport) -> Vec<Document> {
+    let mut documents = Vec::new();
+
+    for krate in CRATES {
+        let crate_dir = library.join(krate);
+        for path in rust_files(&crate_dir.join("src")) {
+            let relative = path
+                .strip_prefix(library)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let code = match std::fs::read_to_string(&path) {
+                Ok(code) => code,
+                Err(e) => {
+                    report.unreadable(relative, &e);
+                    continue;
+                }
+            };
+
+            for item in extract_items(&code, &module_path(&relative)) {
+                let markdown = format!(
+                    "# {}\n\n{}\n\n```rust\n{}\n```\n",
+                    item.name, item.docs, item.code
+                );
+                let location = format!("{}:{}", relative, item.line);
+                documents.push(parse_markdown(&markdown, &location, STD_SOURCE));
+            }
+        }
+    }
+
+    report.parsed = documents.len();
+    documents
+}
+
+/// Read the item at a document location back from the sources
+pub fn read_item(library: &Path, location: &str) -> Option<StdItem> {
+    let (file, line) = split_location(location)?;
+    let code = std::fs::read_to_string(library.join(file)).ok()?;
+    extract_items(&code, &module_path(file))
+        .into_iter()
+        .find(|item| item.line == line)
+}
+
+/// Module of a source file, e.g. `mem` for `core/src/mem/mod.rs`; empty for a crate root
+fn module_path(relative: &str) -> String {
+    let inner = relative
+        .split_once("/src/")
+        .map_or(relative, |(_, inner)| inner)
+        .trim_end_matches(".rs");
+    let segments: Vec<&str> = inner
+        .split('/')
+        .filter(|segment| !matches!(*segment, "mod" | "lib"))
+        .collect();
+    segments.join("::")
+}
+
+/// Rust files under a directory, skipping tests and benchmarks
+fn rust_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if !path
+                .file_name()
+                .is_some_and(|name| SKIPPED_DIRS.iter().any(|skipped| name == *skipped))
+            {
+                files.extend(rust_files(&path));
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Braces and semicolons of a file outside comments, strings, and char literals
+struct Structure {
+    /// Byte offset and character of each mark, in order
+    marks: Vec<(usize, u8)>,
+    /// Byte ranges of comments and string literals
+    masked: Vec<(usize, usize)>,
+}
+
+impl Structure {
+    fn scan(code: &str) -> Self {
+        let bytes = code.as_bytes();
+        let mut marks = Vec::new();
+        let mut masked = Vec::new();
+        let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let start = i;
+            match bytes[i] {
+                b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                    i = code[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                    masked.push((start, i));
+                    continue;
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    let mut depth = 0;
+                    while i < bytes.len() {
+                        if bytes[i..].starts_with(b"/*") {
+                            depth += 1;
+                            i += 2;
+                        } else if bytes[i..].starts_with(b"*/") {
+                            depth -= 1;
+                            i += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        } else {
+                            i += 1;
+                        }
+                    }
+                    masked.push((start, i));
+                    continue;
+                }
+                b'r' if i == 0 || !is_ident(bytes[i - 1]) || bytes[i - 1] == b'b' => {
+                    // Raw string `r#"..."#`; `r#ident` and plain identifiers fall through
+                    let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                    if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                        let closing = format!("\"{}", "#".repeat(hashes));
+                        let body = i + 2 + hashes;
+                        i = code[body..]
+                            .find(&closing)
+                            .map_or(bytes.len(), |end| body + end + closing.len());
+                        masked.push((start, i));
+                        continue;
+                    }
+                }
+                b'"' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    masked.push((start, (i + 1).min(bytes.len())));
+                }
+                b'\'' => {
+                    // A char literal, or else a lifetime
+                    if bytes.get(i + 1) == Some(&b'\\') {
+                        let mut j = i + 3;
+                        while j < bytes.len() && bytes[j] != b'\'' {
+                            j += 1;
+                        }
+                        i = j;
+                    } else if let Some(c) = code[i + 1..].chars().next()
+                        && bytes.get(i + 1 + c.len_utf8()) == Some(&b'\'')
+                    {
+                        i += 1 + c.len_utf8();
+                    }
+                }
+                b'{' | b'}' | b';' => marks.push((i, bytes[i])),
+                _ => {}
+            }
+            i += 1;
+        }
+
+        Self { marks, masked }
+    }
+
+    fn is_masked(&self, offset: usize) -> bool {
+        let i = self.masked.partition_point(|&(start, _)| start <= offset);
+        i > 0 && offset < self.masked[i - 1].1
+    }
+
+    /// End of the item starting at `offset`: its first `;`, or the brace closing its first `{`
+    fn item_end(&self, offset: usize) -> Option<(usize, bool)> {
+        let first = self.marks.partition_point(|&(pos, _)| pos < offset);
+        let &(pos, mark) = self.marks.get(first)?;
+        match mark {
+            b';' => Some((pos, false)),
+            b'{' => {
+                let mut depth = 0;
+                for &(pos, mark) in &self.marks[first..] {
+                    match mark {
+                        b'{' => depth += 1,
+                        b'}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Some((pos, true));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Kind of block items can be nested in
+struct Block {
+    start: usize,
+    end: usize,
+    /// Type or trait name the block's functions belong to
+    owner: String,
+    /// Trait definitions and trait impls expose functions without `pub`
+    trait_items: bool,
+}
+
+/// Extract the public items of a source file; `module` qualifies free functions
+pub fn extract_items(code: &str, module: &str) -> Vec<StdItem> {
+    let structure = Structure::scan(code);
+    let lines: Vec<(usize, &str)> = code
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim_end_matches(['\n', '\r'])))
+        })
+        .collect();
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut items = Vec::new();
+    // Functions nested in a body already taken are not items of their own
+    let mut taken_until = 0;
+
+    for (index, &(line_start, line)) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let start = line_start + (line.len() - trimmed.len());
+        if trimmed.is_empty() || structure.is_masked(start) {
+            continue;
+        }
+
+        if let Some((owner, trait_items)) = block_owner(trimmed)
+            && let Some((end, true)) = structure.item_end(start)
+        {
+            blocks.push(Block {
+                start,
+                end,
+                owner,
+                trait_items,
+            });
+            continue;
+        }
+
+        if start < taken_until {
+            continue;
+        }
+        let Some((kind, name, public)) = item_header(trimmed) else {
+            continue;
+        };
+        let block = blocks
+            .iter()
+            .rev()
+            .find(|block| block.start < start && start < block.end);
+        let included = match (kind, block) {
+            ("fn", Some(block)) => public || block.trait_items,
+            _ => public,
+        };
+        let Some((end, _)) = structure.item_end(start) else {
+            continue;
+        };
+        if kind == "fn" {
+            taken_until = end;
+        }
+        if !included {
+            continue;
+        }
+
+        let owner = match block {
+            Some(block) => block.owner.as_str(),
+            None => module,
+        };
+        let name = if owner.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}::{}", owner, name)
+        };
+        items.push(StdItem {
+            name,
+            line: index + 1,
+            docs: doc_comment(&lines[..index]),
+            code: dedent(&code[start..=end]),
+        });
+    }
+
+    items
+}
+
+/// The item declared by a line: kind (`fn`, `struct`, `enum`), name, and whether it is `pub`
+fn item_header(line: &str) -> Option<(&'static str, &str, bool)> {
+    let (public, mut rest) = match line.strip_prefix("pub ") {
+        Some(rest) => (true, rest),
+        // `pub(crate)` and friends are not public API
+        None if line.starts_with("pub(") => return None,
+        None => (false, line),
+    };
+    while let Some(stripped) = FN_QUALIFIERS
+        .iter()
+        .find_map(|qualifier| rest.strip_prefix(qualifier))
+    {
+        rest = stripped;
+    }
+
+    let (kind, rest) = ["fn", "struct", "enum"]
+        .iter()
+        .find_map(|kind| Some((*kind, rest.strip_prefix(kind)?.strip_prefix(' ')?)))?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let name = &rest[..end];
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((kind, name, public))
+}
+
+/// Owner of an `impl` or `trait` block opened on this line, and whether it holds trait items
+fn block_owner(line: &str) -> Option<(String, bool)> {
+    let line = line.strip_prefix("unsafe ").unwrap_or(line);
+    if let Some(header) = line.strip_prefix("impl") {
+        if !header.starts_with(['<', ' ']) {
+            return None;
+        }
+        let header = skip_generics(header.trim_start());
+        let header = header.split(" where").next().unwrap_or(header);
+        let (type_part, trait_impl) = match header.rsplit_once(" for ") {
+            Some((_, type_part)) => (type_part, true),
+            None => (header, false),
+        };
+        return Some((type_name(type_part)?, trait_impl));
+    }
+
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+    let line = line.strip_prefix("unsafe ").unwrap_or(line);
+    let rest = line.strip_prefix("trait ")?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    (end > 0).then(|| (rest[..end].to_string(), true))
+}
+
+/// Skip a leading `<...>` generics list
+fn skip_generics(text: &str) -> &str {
+    if !text.starts_with('<') {
+        return text;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if !text[..i].ends_with('-') => {
+                depth -= 1;
+                if depth == 0 {
+                    return text[i + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Short name of an implemented type: `Option` for `Option<T>`, `slice` for `[T]`
+fn type_name(text: &str) -> Option<String> {
+    let text = text
+        .trim()
+        .trim_start_matches('&')
+        .trim_start_matches("mut ");
+    if text.starts_with('[') {
+        return Some("slice".to_string());
+    }
+    if text.starts_with('*') {
+        return Some("pointer".to_string());
+    }
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(text.len());
+    let path = &text[..end];
+    let name = path.rsplit("::").next().unwrap_or(path);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// The `///` comment above an item, skipping its attributes
+fn doc_comment(lines_above: &[(usize, &str)]) -> String {
+    let mut docs: Vec<&str> = Vec::new();
+    for &(_, line) in lines_above.iter().rev() {
+        let trimmed = line.trim();
+        if let Some(text) = trimmed.strip_prefix("///") {
+            docs.push(text.strip_prefix(' ').unwrap_or(text));
+        } else if trimmed.starts_with("#[") || trimmed.ends_with(")]") || trimmed.ends_with(',') {
+            if !docs.is_empty() {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    docs.reverse();
+    docs.join("\n")
+}
+
+/// Remove the indentation shared by all non-empty lines
+fn dedent(code: &str) -> String {
+    let indent = code
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    // The first line starts at the item, so its indentation is already gone; the closing brace
+    // sets the indentation of the rest
+    let indent = code
+        .lines()
+        .last()
+        .map(|last| last.len() - last.trim_start().len())
+        .map_or(indent, |closing| closing.min(indent));
+
+    code.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line
+            } else {
+                line.get(indent..).unwrap_or(line.trim_start())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTION: &str = r#"//! Optional values.
+
+/// The `Option` type.
+#[stable(feature = "rust1", since = "1.0.0")]
+pub enum Option<T> {
+    /// No value.
+    None,
+    /// Some value of type `T`.
+    Some(T),
+}
+
+impl<T> Option<T> {
+    /// Maps an `Option<T>` to `Option<U>` by applying a function to a contained value.
+    ///
+    /// ```
+    /// let len = Some("hi").map(|s| s.len());
+    /// ```
+    #[inline]
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn map<U, F>(self, f: F) -> Option<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        match self {
+            Some(x) => Some(f(x)),
+            None => None,
+        }
+    }
+
+    fn private_helper(&self) -> char {
+        let brace = '{';
+        let s = "}";
+        brace
+    }
+
+    pub const fn is_some(&self) -> bool {
+        matches!(*self, Some(_))
+    }
+}
+
+impl<T: Clone> Clone for Option<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Some(x) => Some(x.clone()),
+            None => None,
+        }
+    }
+}
+
+pub fn swap<T>(x: &mut T, y: &mut T) {
+    fn nested() {}
+    unsafe { ptr::swap_nonoverlapping(x, y, 1) }
+}
+"#;
+
+    #[test]
+    fn test_extract_items() {
+        let items = extract_items(OPTION, "option");
+        let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "option::Option",
+                "Option::map",
+                "Option::is_some",
+                "Option::clone",
+                "option::swap"
+            ]
+        );
+
+        let map = &items[1];
+        assert_eq!(map.line, 20);
+        assert!(map.docs.starts_with("Maps an `Option<T>` to `Option<U>`"));
+        assert!(
+            map.docs
+                .contains("let len = Some(\"hi\").map(|s| s.len());")
+        );
+        assert!(
+            map.code
+                .starts_with("pub fn map<U, F>(self, f: F) -> Option<U>\nwhere\n")
+        );
+        assert!(map.code.ends_with("        None => None,\n    }\n}"));
+        assert_eq!(
+            map.signature(),
+            "pub fn map<U, F>(self, f: F) -> Option<U>\nwhere\n    F: FnOnce(T) -> U,"
+        );
+
+        // The braces in char and string literals don't end the impl early
+        assert_eq!(
+            items[2].code,
+            "pub const fn is_some(&self) -> bool {\n    matches!(*self, Some(_))\n}"
+        );
+    }
+
+    #[test]
+    fn test_match_rank() {
+        assert_eq!(match_rank("Option::map", "Option::map()"), Some(0));
+        assert_eq!(
+            match_rank("Option::map", "std::option::Option::map"),
+            Some(1)
+        );
+        assert_eq!(match_rank("Vec::push", "Vec<T>::push"), Some(0));
+        assert_eq!(match_rank("Iterator::map", "map"), Some(2));
+        assert_eq!(match_rank("mem::swap", "std::mem::swap"), Some(0));
+        assert_eq!(match_rank("Option::map_or", "map"), None);
+    }
+
+    #[test]
+    fn test_module_path_and_location() {
+        assert_eq!(module_path("core/src/mem/mod.rs"), "mem");
+        assert_eq!(module_path("alloc/src/vec/into_iter.rs"), "vec::into_iter");
+        assert_eq!(module_path("std/src/lib.rs"), "");
+        assert_eq!(
+            split_location("core/src/option.rs:1071"),
+            Some(("core/src/option.rs", 1071))
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Best-practice categories.
+//!
+//! The Rust Design Patterns and API Guidelines entries are tagged with categories at index time,
+//! so `get_best_practice` can list everything about error handling or builders instead of only
+//! what a free-text search ranks highest. An entry belongs to a category when its title, path,
+//! or section headings mention one of the category's keywords, or when it carries one of the
+//! category's API Guidelines checklist IDs; entries can be in several categories.
+
+use crate::parsing::Document;
+
+/// Sources whose documents are categorized
+pub const TAXONOMY_SOURCES: &[&str] = &["rust-patterns", "api-guidelines"];
+
+/// A best-practice category
+#[derive(Debug)]
+pub struct Category {
+    /// Identifier used in tool parameters, e.g. `error-handling`
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Lowercase words or phrases; a word matches at the start of a word ("builder" matches
+    /// "builders")
+    pub keywords: &'static [&'static str],
+    /// API Guidelines checklist IDs that belong to the category
+    pub checklist_ids: &'static [&'static str],
+}
+
+/// Built-in categories
+pub const CATEGORIES: &[Category] = &[
+    Category {
+        id: "error-handling",
+        name: "Error handling",
+        keywords: &["error", "result", "panic", "unwrap", "fallible"],
+        checklist_ids: &["C-GOOD-ERR", "C-FAILURE", "C-QUESTION-MARK"],
+    },
+    Category {
+        id: "builders",
+        name: "Builders and constructors",
+        keywords: &["builder", "constructor", "default"],
+        checklist_ids: &["C-BUILDER", "C-CTOR", "C-COMMON-TRAITS"],
+    },
+    Category {
+        id: "newtypes",
+        name: "Newtypes and type safety",
+        keywords: &[
+            "newtype",
+            "type safety",
+            "type-safety",
+            "phantom",
+            "typestate",
+        ],
+        checklist_ids: &["C-NEWTYPE", "C-NEWTYPE-HIDE", "C-CUSTOM-TYPE", "C-BITFLAG"],
+    },
+    Category {
+        id: "ffi",
+        name: "FFI",
+        keywords: &["ffi", "foreign", "extern", "c string", "cstr"],
+        checklist_ids: &[],
+    },
+    Category {
+        id: "naming",
+        name: "Naming",
+        keywords: &[
+            "naming",
+            "casing",
+            "getter",
+            "conversion method",
+            "iterator method",
+        ],
+        checklist_ids: &[
+            "C-CASE",
+            "C-CONV",
+            "C-GETTER",
+            "C-ITER",
+            "C-ITER-TY",
+            "C-FEATURE",
+            "C-WORD-ORDER",
+        ],
+    },
+    Category {
+        id: "conversions",
+        name: "Conversions and common traits",
+        keywords: &[
+            "conversion",
+            "interoperability",
+            "asref",
+            "tryfrom",
+            "common trait",
+        ],
+        checklist_ids: &[
+            "C-CONV-TRAITS",
+            "C-COLLECT",
+            "C-SERDE",
+            "C-SEND-SYNC",
+            "C-CONV-SPECIFIC",
+        ],
+    },
+    Category {
+        id: "ownership",
+        name: "Ownership and borrowing",
+        keywords: &[
+            "ownership",
+            "borrow",
+            "clone",
+            "lifetime",
+            "mem::take",
+            "mem::replace",
+        ],
+        checklist_ids: &["C-CALLER-CONTROL"],
+    },
+    Category {
+        id: "resources",
+        name: "RAII and resource cleanup",
+        keywords: &["raii", "drop", "guard", "destructor", "finalis", "finaliz"],
+        checklist_ids: &["C-DTOR-FAIL", "C-DTOR-BLOCK"],
+    },
+    Category {
+        id: "traits-generics",
+        name: "Traits and generics",
+        keywords: &["trait", "generic", "dispatch", "sealed", "object safe"],
+        checklist_ids: &["C-GENERIC", "C-OBJECT", "C-SEALED", "C-OVERLOAD"],
+    },
+    Category {
+        id: "documentation",
+        name: "Documentation",
+        keywords: &["documentation", "doc comment", "rustdoc", "crate level doc"],
+        checklist_ids: &[
+            "C-CRATE-DOC",
+            "C-EXAMPLE",
+            "C-QUESTION-MARK",
+            "C-FAILURE",
+            "C-LINK",
+            "C-METADATA",
+            "C-RELNOTES",
+            "C-HIDDEN",
+        ],
+    },
+    Category {
+        id: "macros",
+        name: "Macros",
+        keywords: &["macro"],
+        checklist_ids: &[
+            "C-EVOCATIVE",
+            "C-MACRO-ATTR",
+            "C-ANYWHERE",
+            "C-MACRO-VIS",
+            "C-MACRO-TY",
+        ],
+    },
+    Category {
+        id: "future-proofing",
+        name: "Future proofing and stability",
+        keywords: &[
+            "future proof",
+            "future-proof",
+            "private field",
+            "non_exhaustive",
+            "stability",
+        ],
+        checklist_ids: &[
+            "C-SEALED",
+            "C-STRUCT-PRIVATE",
+            "C-STRUCT-BOUNDS",
+            "C-STABLE",
+            "C-PERMISSIVE",
+        ],
+    },
+    Category {
+        id: "anti-patterns",
+        name: "Anti-patterns",
+        keywords: &["anti-pattern", "anti_pattern", "antipattern"],
+        checklist_ids: &[],
+    },
+];
+
+/// The category with this ID or name (case-insensitive)
+pub fn get_category(id: &str) -> Option<&'static Category> {
+    let id = id.trim();
+    CATEGORIES.iter().find(|category| {
+        category.id.eq_ignore_ascii_case(id) || category.name.eq_ignore_ascii_case(id)
+    })
+}
+
+/// IDs of the categories a document belongs to, in `CATEGORIES` order
+pub fn categorize(doc: &Document) -> Vec<String> {
+    let text = format!("{}\n{}\n{}", doc.title, doc.path, doc.headings.join("\n")).to_lowercase();
+    CATEGORIES
+        .iter()
+        .filter(|category| {
+            category
+                .keywords
+                .iter()
+                .any(|keyword| mentions(&text, keyword))
+                || doc
+                    .checklist_ids
+                    .iter()
+                    .any(|id| category.checklist_ids.contains(&id.as_str()))
+        })
+        .map(|category| category.id.to_string())
+        .collect()
+}
+
+/// Longest entry summary in a category listing, in characters
+const MAX_SUMMARY_CHARS: usize = 300;
+
+/// Opening paragraph of an entry's markdown (headings and code skipped), for listings
+pub fn summary(markdown: &str) -> String {
+    let paragraph = markdown
+        .split("\n\n")
+        .map(str::trim)
+        .find(|block| !block.is_empty() && !block.starts_with('#') && !block.starts_with("```"))
+        .unwrap_or("");
+    let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    if paragraph.chars().count() <= MAX_SUMMARY_CHARS {
+        return paragraph;
+    }
+    let cut: String = paragraph.chars().take(MAX_SUMMARY_CHARS).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!("{}...", cut)
+}
+
+/// Whether `keyword` occurs in `text` at the start of a word
+fn mentions(text: &str, keyword: &str) -> bool {
+    text.match_indices(keyword).any(|(start, _)| {
+        text[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(title: &str, path: &str, headings: &[&str], checklist_ids: &[&str]) -> Document {
+        Document {
+            title: title.to_string(),
+            path: path.to_string(),
+            source: "api-guidelines".to_string(),
+            headings: headings.iter().map(|h| h.to_string()).collect(),
+            checklist_ids: checklist_ids.iter().map(|id| id.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_categorize() {
+        let builder = document("Builders", "builder.md", &["Motivation"], &[]);
+        assert_eq!(categorize(&builder), vec!["builders"]);
+
+        let ffi_errors = document("Error Handling in FFI", "errors.md", &[], &[]);
+        assert_eq!(categorize(&ffi_errors), vec!["error-handling", "ffi"]);
+
+        let naming = document(
+            "Naming",
+            "naming.md",
+            &["Casing conforms to RFC 430 (C-CASE)"],
+            &["C-CASE", "C-CONV"],
+        );
+        assert_eq!(categorize(&naming), vec!["naming"]);
+
+        // Keywords match at word starts only: "terror" is not about errors
+        let other = document("Terror", "terror.md", &[], &[]);
+        assert!(categorize(&other).is_empty());
+    }
+
+    #[test]
+    fn test_summary() {
+        let markdown =
+            "## Description\n\nUse a builder\nto construct values.\n\n```rust\nlet x = 1;\n```";
+        assert_eq!(summary(markdown), "Use a builder to construct values.");
+        let long = "word ".repeat(100);
+        let summarized = summary(&long);
+        assert!(summarized.ends_with("word..."));
+        assert!(summarized.len() <= MAX_SUMMARY_CHARS + 3);
+    }
+
+    #[test]
+    fn test_get_category() {
+        assert_eq!(get_category("FFI").unwrap().id, "ffi");
+        assert_eq!(get_category("error handling").unwrap().id, "error-handling");
+        assert!(get_category("async").is_none());
+    }
+}
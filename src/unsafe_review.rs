@@ -0,0 +1,207 @@
+//! Soundness obligations for common unsafe constructs, used by the `unsafe_review` tool.
+//!
+//! Each construct lists the conditions its caller must uphold, as documented in the standard
+//! library, the Rustonomicon, and the Unsafe Code Guidelines, so a review can tick them off.
+
+use crate::search::SearchResult;
+
+/// Sources searched for unsafe code guidance
+pub const UNSAFE_SOURCES: &[&str] = &["rustonomicon", "unsafe-code-guidelines", "rust-reference"];
+
+/// An unsafe construct with documented soundness obligations
+#[derive(Debug)]
+pub struct UnsafeConstruct {
+    /// Stable identifier used in tool output
+    pub id: &'static str,
+    /// Lowercase words or fragments in a description that select this construct
+    pub keywords: &'static [&'static str],
+    /// Conditions that must hold for the construct to be sound
+    pub obligations: &'static [&'static str],
+    /// Search query for the sections explaining the construct
+    pub query: &'static str,
+    /// `(source, path)` of the sections that cover this construct directly
+    pub targets: &'static [(&'static str, &'static str)],
+}
+
+/// Built-in unsafe constructs, checked in order
+pub const UNSAFE_CONSTRUCTS: &[UnsafeConstruct] = &[
+    UnsafeConstruct {
+        id: "raw-pointer-deref",
+        keywords: &[
+            "raw pointer",
+            "*const",
+            "*mut",
+            "deref",
+            "ptr::read",
+            "ptr::write",
+            "as_ref",
+        ],
+        obligations: &[
+            "The pointer is non-null and aligned for the pointee type",
+            "The pointer is dereferenceable: the whole `size_of::<T>()` range lies within one live allocation",
+            "The pointee is initialized and a valid value of `T`",
+            "A `&T` made from it is not mutated, and a `&mut T` is not aliased, for the chosen lifetime",
+            "No other thread accesses the memory concurrently without synchronization",
+        ],
+        query: "dereferencing raw pointers aliasing validity",
+        targets: &[
+            ("rustonomicon", "what-unsafe-does.md"),
+            ("rustonomicon", "aliasing.md"),
+            ("unsafe-code-guidelines", "pointers.md"),
+        ],
+    },
+    UnsafeConstruct {
+        id: "transmute",
+        keywords: &["transmute", "reinterpret", "type punning"],
+        obligations: &[
+            "Source and target types have the same size",
+            "The bits are a valid value of the target type (no invalid `bool`, `char`, enum discriminant, or null reference)",
+            "Both types have a defined layout (`#[repr(C)]` or primitive); default `repr(Rust)` layout is unspecified",
+            "Lifetimes are not extended beyond what the data actually lives for",
+            "`&T` is never transmuted to `&mut T`",
+        ],
+        query: "transmutes mem::transmute undefined behavior",
+        targets: &[
+            ("rustonomicon", "transmutes.md"),
+            ("unsafe-code-guidelines", "layout.md"),
+        ],
+    },
+    UnsafeConstruct {
+        id: "send-sync-impl",
+        keywords: &["send", "sync", "unsafe impl", "thread safe", "thread-safe"],
+        obligations: &[
+            "`Send`: ownership of the value can move to another thread without leaving shared unsynchronized state behind",
+            "`Sync`: every `&self` method is safe to call from several threads at once",
+            "Raw pointers, `Rc`, or `Cell` fields are only reached through synchronization or are never shared",
+            "Generic parameters are bounded (`T: Send`/`T: Sync`) when the type hands out or stores `T`",
+        ],
+        query: "Send and Sync unsafe impl thread safety",
+        targets: &[("rustonomicon", "send-and-sync.md")],
+    },
+    UnsafeConstruct {
+        id: "uninitialized-memory",
+        keywords: &[
+            "uninit",
+            "maybeuninit",
+            "assume_init",
+            "uninitialized",
+            "set_len",
+        ],
+        obligations: &[
+            "Every byte is initialized before `assume_init` or before the value is read",
+            "Initialized elements are dropped exactly once, including when a panic interrupts initialization",
+            "`Vec::set_len` only covers elements that are initialized and within capacity",
+        ],
+        query: "MaybeUninit unchecked uninitialized memory",
+        targets: &[
+            ("rustonomicon", "unchecked-uninit.md"),
+            ("rustonomicon", "uninitialized.md"),
+        ],
+    },
+    UnsafeConstruct {
+        id: "slice-from-raw-parts",
+        keywords: &["from_raw_parts", "slice from pointer", "get_unchecked"],
+        obligations: &[
+            "The pointer is non-null and aligned, even for empty slices",
+            "`len * size_of::<T>()` bytes are dereferenceable within one allocation and at most `isize::MAX`",
+            "All elements are initialized and valid",
+            "Indices passed to `get_unchecked` are in bounds",
+        ],
+        query: "slices from raw parts pointer length validity",
+        targets: &[
+            ("rustonomicon", "vec/vec-raw.md"),
+            ("unsafe-code-guidelines", "arrays-and-slices.md"),
+        ],
+    },
+    UnsafeConstruct {
+        id: "static-mut",
+        keywords: &["static mut", "global mutable"],
+        obligations: &[
+            "No reference to the static is created; use `&raw const`/`&raw mut` (references are denied in 2024)",
+            "All accesses are synchronized or provably single-threaded",
+            "Prefer atomics, `Mutex`, or `OnceLock`, which need no unsafe at all",
+        ],
+        query: "static mut mutable statics data races",
+        targets: &[("rust-reference", "static-items.md")],
+    },
+    UnsafeConstruct {
+        id: "union-access",
+        keywords: &["union"],
+        obligations: &[
+            "The field read is a valid value for the bits last written",
+            "Fields needing `Drop` are wrapped in `ManuallyDrop` and dropped manually",
+        ],
+        query: "unions reading fields layout",
+        targets: &[
+            ("rust-reference", "unions.md"),
+            ("unsafe-code-guidelines", "unions.md"),
+        ],
+    },
+    UnsafeConstruct {
+        id: "ffi-call",
+        keywords: &["extern", "ffi", "foreign function", "c function"],
+        obligations: &[
+            "The declared signature matches the foreign definition exactly",
+            "Types crossing the boundary are FFI-safe (`#[repr(C)]`, no references to unsized types)",
+            "Pointer arguments and return values follow the foreign function's ownership and lifetime contract",
+            "Panics never unwind across a non-`-unwind` ABI",
+        ],
+        query: "foreign function interface calling foreign functions",
+        targets: &[("rustonomicon", "ffi.md")],
+    },
+];
+
+/// Constructs mentioned in a description
+pub fn detect_constructs(description: &str) -> Vec<&'static UnsafeConstruct> {
+    let lower = description.to_lowercase();
+    UNSAFE_CONSTRUCTS
+        .iter()
+        .filter(|c| c.keywords.iter().any(|k| lower.contains(k)))
+        .collect()
+}
+
+impl UnsafeConstruct {
+    /// Obligations as a Markdown checklist
+    pub fn checklist(&self) -> String {
+        self.obligations
+            .iter()
+            .map(|o| format!("- [ ] {}", o))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Move sections covering this construct to the front, keeping relative order
+    pub fn apply_boost(&self, results: &mut [SearchResult]) {
+        results.sort_by_key(|result| {
+            !self
+                .targets
+                .iter()
+                .any(|(source, path)| result.source == *source && result.path.ends_with(path))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_constructs() {
+        let ids =
+            |text: &str| -> Vec<&str> { detect_constructs(text).iter().map(|c| c.id).collect() };
+
+        assert_eq!(ids("transmute a u32 into a char"), vec!["transmute"]);
+        assert_eq!(
+            ids("unsafe impl Send for a struct holding a *mut T"),
+            vec!["raw-pointer-deref", "send-sync-impl"]
+        );
+        assert!(ids("a safe iterator adapter").is_empty());
+    }
+
+    #[test]
+    fn test_checklist() {
+        let checklist = detect_constructs("union")[0].checklist();
+        assert!(checklist.starts_with("- [ ] "));
+        assert_eq!(checklist.lines().count(), 2);
+    }
+}
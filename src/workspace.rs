@@ -0,0 +1,251 @@
+//! Workspace-local documentation discovered from the client's MCP roots.
+//!
+//! Each `file://` root is scanned for its README, Markdown files under `docs/`, and the doc
+//! comments in `src/`. The documents go into an in-memory index that lives only as long as the
+//! session and is searched alongside the official books under the `workspace` source.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::parsing::{Document, parse_markdown, parse_markdown_file, relative_path};
+use crate::search::SearchIndex;
+
+/// Source ID of workspace documents
+pub const WORKSPACE_SOURCE: &str = "workspace";
+
+/// Upper bound on files read per root, so a huge monorepo can't stall the session
+const MAX_FILES_PER_ROOT: usize = 2000;
+
+/// Directories never worth scanning
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", ".git"];
+
+/// Which of a client's roots are indexed
+#[derive(Debug, Clone, Default)]
+pub enum RootPolicy {
+    /// Every root; the stdio client already runs on this machine as the server's user
+    #[default]
+    Any,
+    /// Only roots inside these directories, so a network client can't have arbitrary host
+    /// directories indexed and read them back
+    Within(Arc<[PathBuf]>),
+}
+
+impl RootPolicy {
+    /// Local paths of the `file://` root URIs the policy allows
+    pub fn paths<'a>(&self, uris: impl IntoIterator<Item = &'a str>) -> Vec<PathBuf> {
+        uris.into_iter()
+            .filter_map(root_path)
+            .filter(|path| self.allows(path))
+            .collect()
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        match self {
+            RootPolicy::Any => true,
+            RootPolicy::Within(dirs) => {
+                // Resolve `..` and symlinks before comparing, so a root can't step outside
+                let Ok(path) = path.canonicalize() else {
+                    return false;
+                };
+                dirs.iter()
+                    .filter_map(|dir| dir.canonicalize().ok())
+                    .any(|dir| path.starts_with(dir))
+            }
+        }
+    }
+}
+
+/// Convert a `file://` root URI into a local path
+pub fn root_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // Drop an authority such as `localhost`
+    let path = &path[path.find('/')?..];
+    Some(PathBuf::from(percent_decode(path)))
+}
+
+/// Decode `%XX` escapes in a URI component
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Collect the README, `docs/` Markdown, and `src/` doc comments of a project root
+pub fn collect_documents(root: &Path) -> Vec<Document> {
+    let mut documents = Vec::new();
+
+    let mut markdown_files: Vec<PathBuf> = ["README.md", "Readme.md", "readme.md"]
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .take(1)
+        .collect();
+    markdown_files.extend(
+        walk(&root.join("docs"))
+            .into_iter()
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md")),
+    );
+
+    for path in markdown_files {
+        match parse_markdown_file(&path, root, WORKSPACE_SOURCE) {
+            Ok(doc) => documents.push(doc),
+            Err(e) => tracing::warn!("Failed to parse {:?}: {}", path, e),
+        }
+    }
+
+    for path in walk(&root.join("src"))
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "rs"))
+    {
+        let Ok(code) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let docs = doc_comments(&code);
+        if !docs.trim().is_empty() {
+            documents.push(parse_markdown(
+                &docs,
+                &relative_path(&path, root),
+                WORKSPACE_SOURCE,
+            ));
+        }
+    }
+
+    documents
+}
+
+/// Build an in-memory index over the documentation of all roots
+pub fn index_roots(roots: &[PathBuf]) -> Result<(SearchIndex, usize)> {
+    let index = SearchIndex::in_memory()?;
+    let documents: Vec<Document> = roots
+        .iter()
+        .flat_map(|root| collect_documents(root))
+        .collect();
+    index.index_documents(&documents)?;
+    Ok((index, documents.len()))
+}
+
+/// The text of `//!` and `///` comments, one paragraph per commented item
+fn doc_comments(code: &str) -> String {
+    let mut docs = String::new();
+    let mut in_block = false;
+
+    for line in code.lines() {
+        let trimmed = line.trim_start();
+        let text = trimmed.strip_prefix("//!").or_else(|| {
+            trimmed
+                .strip_prefix("///")
+                .filter(|_| !trimmed.starts_with("////"))
+        });
+
+        match text {
+            Some(text) => {
+                docs.push_str(text.strip_prefix(' ').unwrap_or(text));
+                docs.push('\n');
+                in_block = true;
+            }
+            None if in_block => {
+                docs.push('\n');
+                in_block = false;
+            }
+            None => {}
+        }
+    }
+
+    docs
+}
+
+/// Files under a directory, skipping build output and hidden directories
+fn walk(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if path.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+            } else if files.len() < MAX_FILES_PER_ROOT {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_path() {
+        assert_eq!(
+            root_path("file:///home/me/my%20project"),
+            Some(PathBuf::from("/home/me/my project"))
+        );
+        assert_eq!(
+            root_path("file://localhost/srv/app"),
+            Some(PathBuf::from("/srv/app"))
+        );
+        assert_eq!(root_path("https://example.com/repo"), None);
+    }
+
+    #[test]
+    fn test_root_policy() {
+        let allowed = std::env::temp_dir().join(format!("workspace-roots-{}", std::process::id()));
+        std::fs::create_dir_all(allowed.join("project")).unwrap();
+        let uri = |path: &Path| format!("file://{}", path.display());
+        let inside = uri(&allowed.join("project"));
+        let escaping = uri(&allowed.join("project/../.."));
+
+        let policy = RootPolicy::Within(Arc::from([allowed.clone()]));
+        assert_eq!(
+            policy.paths([inside.as_str(), escaping.as_str(), "file:///etc"]),
+            vec![allowed.join("project")]
+        );
+        assert!(
+            RootPolicy::Within(Arc::from([]))
+                .paths([inside.as_str()])
+                .is_empty()
+        );
+        assert_eq!(RootPolicy::Any.paths(["file:///etc"]).len(), 1);
+
+        std::fs::remove_dir_all(&allowed).unwrap();
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let code = "//! Crate docs.\n\n/// Adds one.\n///\n/// # Panics\nfn add() {}\n//// not a doc\n// plain\n";
+        assert_eq!(
+            doc_comments(code),
+            "Crate docs.\n\nAdds one.\n\n# Panics\n\n"
+        );
+    }
+}